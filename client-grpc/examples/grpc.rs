@@ -32,6 +32,21 @@ async fn query_itinerary_example(
         earliest_departure_time: Some(departure_time.into()),
         latest_arrival_time: Some(arrival_time.into()),
         priority: FlightPriority::Low as i32,
+        waitlist_user_id: None,
+        excluded_vertiport_ids: vec![],
+        loop_waypoints: vec![],
+        aircraft_type: None,
+        emergency: false,
+        required_arrival_precision_seconds: None,
+        auto_widen: false,
+        ranking: None,
+        include_local_times: false,
+        preferred_departure_time: None,
+        origin_type: None,
+        target_type: None,
+        max_total_travel_seconds: None,
+        min_results: None,
+        cargo_hazard_class: None,
     };
 
     match client.query_flight(request).await {
@@ -57,6 +72,7 @@ async fn create_itinerary_example(
         flight_plans: itinerary.flight_plans.clone(),
         expiry: None,
         user_id: Uuid::new_v4().to_string(), // arbitrary
+        return_flight_plans: vec![],
     };
 
     match client.create_itinerary(request).await {
@@ -101,6 +117,7 @@ async fn cancel_itinerary_example(
         priority: FlightPriority::Low.into(),
         itinerary_id: itinerary_id.to_string(),
         user_id: Uuid::new_v4().to_string(), // arbitrary
+        cancellation_kind: CancellationKind::Hard.into(),
     };
 
     match client.cancel_itinerary(request).await {
@@ -178,6 +195,14 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
             session_id: "AETH1234".to_string(),
             ..Default::default()
         }],
+        vehicle: None,
+        alternative_routes: vec![],
+        local_times: vec![],
+        estimated_price: 0,
+        deadhead_reasons: vec![],
+        path_fallback: false,
+        pad_selection: PadSelection::Unspecified as i32,
+        arrival_estimate: None,
     };
 
     let Some(task_id) = create_itinerary_example(&client, &itinerary).await else {
@@ -73,6 +73,56 @@ impl crate::service::Client<RpcServiceClient<Channel>> for SchedulerClient {
         client.cancel_itinerary(request).await
     }
 
+    async fn cancel_vehicle_itineraries(
+        &self,
+        request: CancelVehicleItinerariesRequest,
+    ) -> Result<tonic::Response<CancelVehicleItinerariesResponse>, tonic::Status> {
+        grpc_info!("{} client.", self.get_name());
+        grpc_debug!("request: {:?}", request);
+        let mut client = self.get_client().await?;
+        client.cancel_vehicle_itineraries(request).await
+    }
+
+    async fn suggest_repositioning(
+        &self,
+        request: SuggestRepositioningRequest,
+    ) -> Result<tonic::Response<SuggestRepositioningResponse>, tonic::Status> {
+        grpc_info!("{} client.", self.get_name());
+        grpc_debug!("request: {:?}", request);
+        let mut client = self.get_client().await?;
+        client.suggest_repositioning(request).await
+    }
+
+    async fn check_flight_plan(
+        &self,
+        request: CheckFlightPlanRequest,
+    ) -> Result<tonic::Response<CheckFlightPlanResponse>, tonic::Status> {
+        grpc_info!("{} client.", self.get_name());
+        grpc_debug!("request: {:?}", request);
+        let mut client = self.get_client().await?;
+        client.check_flight_plan(request).await
+    }
+
+    async fn get_itinerary(
+        &self,
+        request: GetItineraryRequest,
+    ) -> Result<tonic::Response<Itinerary>, tonic::Status> {
+        grpc_info!("{} client.", self.get_name());
+        grpc_debug!("request: {:?}", request);
+        let mut client = self.get_client().await?;
+        client.get_itinerary(request).await
+    }
+
+    async fn restore_itinerary(
+        &self,
+        request: RestoreItineraryRequest,
+    ) -> Result<tonic::Response<TaskResponse>, tonic::Status> {
+        grpc_info!("{} client.", self.get_name());
+        grpc_debug!("request: {:?}", request);
+        let mut client = self.get_client().await?;
+        client.restore_itinerary(request).await
+    }
+
     async fn cancel_task(
         &self,
         request: TaskRequest,
@@ -92,6 +142,96 @@ impl crate::service::Client<RpcServiceClient<Channel>> for SchedulerClient {
         let mut client = self.get_client().await?;
         client.get_task_status(request).await
     }
+
+    async fn list_queued_tasks(
+        &self,
+        request: ListQueuedTasksRequest,
+    ) -> Result<tonic::Response<ListQueuedTasksResponse>, tonic::Status> {
+        grpc_info!("{} client.", self.get_name());
+        grpc_debug!("request: {:?}", request);
+        let mut client = self.get_client().await?;
+        client.list_queued_tasks(request).await
+    }
+
+    async fn requeue_task(
+        &self,
+        request: RequeueTaskRequest,
+    ) -> Result<tonic::Response<TaskResponse>, tonic::Status> {
+        grpc_info!("{} client.", self.get_name());
+        grpc_debug!("request: {:?}", request);
+        let mut client = self.get_client().await?;
+        client.requeue_task(request).await
+    }
+
+    async fn get_fleet_status(
+        &self,
+        request: GetFleetStatusRequest,
+    ) -> Result<tonic::Response<GetFleetStatusResponse>, tonic::Status> {
+        grpc_info!("{} client.", self.get_name());
+        grpc_debug!("request: {:?}", request);
+        let mut client = self.get_client().await?;
+        client.get_fleet_status(request).await
+    }
+
+    async fn validate_schedule(
+        &self,
+        request: ValidateScheduleRequest,
+    ) -> Result<tonic::Response<ValidateScheduleResponse>, tonic::Status> {
+        grpc_info!("{} client.", self.get_name());
+        grpc_debug!("request: {:?}", request);
+        let mut client = self.get_client().await?;
+        client.validate_schedule(request).await
+    }
+
+    async fn estimate_deadheads(
+        &self,
+        request: EstimateDeadheadsRequest,
+    ) -> Result<tonic::Response<EstimateDeadheadsResponse>, tonic::Status> {
+        grpc_info!("{} client.", self.get_name());
+        grpc_debug!("request: {:?}", request);
+        let mut client = self.get_client().await?;
+        client.estimate_deadheads(request).await
+    }
+
+    async fn reachable_vertiports(
+        &self,
+        request: ReachableVertiportsRequest,
+    ) -> Result<tonic::Response<ReachableVertiportsResponse>, tonic::Status> {
+        grpc_info!("{} client.", self.get_name());
+        grpc_debug!("request: {:?}", request);
+        let mut client = self.get_client().await?;
+        client.reachable_vertiports(request).await
+    }
+
+    async fn vertipad_occupancy(
+        &self,
+        request: VertipadOccupancyRequest,
+    ) -> Result<tonic::Response<VertipadOccupancyResponse>, tonic::Status> {
+        grpc_info!("{} client.", self.get_name());
+        grpc_debug!("request: {:?}", request);
+        let mut client = self.get_client().await?;
+        client.vertipad_occupancy(request).await
+    }
+
+    async fn cancel_user_drafts(
+        &self,
+        request: CancelUserDraftsRequest,
+    ) -> Result<tonic::Response<CancelUserDraftsResponse>, tonic::Status> {
+        grpc_info!("{} client.", self.get_name());
+        grpc_debug!("request: {:?}", request);
+        let mut client = self.get_client().await?;
+        client.cancel_user_drafts(request).await
+    }
+
+    async fn list_user_itineraries(
+        &self,
+        request: ListUserItinerariesRequest,
+    ) -> Result<tonic::Response<ListUserItinerariesResponse>, tonic::Status> {
+        grpc_info!("{} client.", self.get_name());
+        grpc_debug!("request: {:?}", request);
+        let mut client = self.get_client().await?;
+        client.list_user_itineraries(request).await
+    }
 }
 
 #[cfg(feature = "stub_client")]
@@ -118,9 +258,25 @@ impl crate::service::Client<RpcServiceClient<Channel>> for SchedulerClient {
         let flight_plan_data = prelude::scheduler_storage::flight_plan::mock::get_future_data_obj();
         let itineraries = vec![Itinerary {
             flight_plans: vec![flight_plan_data],
+            vehicle: None,
+            alternative_routes: vec![],
+            local_times: vec![],
+            estimated_price: 0,
+            deadhead_reasons: vec![],
+            path_fallback: false,
+            pad_selection: PadSelection::Unspecified as i32,
+            arrival_estimate: None,
         }];
 
-        Ok(tonic::Response::new(QueryFlightResponse { itineraries }))
+        Ok(tonic::Response::new(QueryFlightResponse {
+            itineraries,
+            reason: None,
+            window_earliest_departure_time: None,
+            window_latest_arrival_time: None,
+            limiting_constraint: None,
+            partial: false,
+            alternative_destinations: vec![],
+        }))
     }
 
     async fn create_itinerary(
@@ -138,6 +294,7 @@ impl crate::service::Client<RpcServiceClient<Channel>> for SchedulerClient {
                 action: TaskAction::CreateItinerary.into(),
                 user_id: request.user_id,
                 result: None,
+                fallback_vertipad_ids: vec![],
             }),
         }))
     }
@@ -157,6 +314,80 @@ impl crate::service::Client<RpcServiceClient<Channel>> for SchedulerClient {
                 action: TaskAction::CancelItinerary.into(),
                 user_id: request.user_id,
                 result: None,
+                fallback_vertipad_ids: vec![],
+            }),
+        }))
+    }
+
+    async fn cancel_vehicle_itineraries(
+        &self,
+        request: CancelVehicleItinerariesRequest,
+    ) -> Result<tonic::Response<CancelVehicleItinerariesResponse>, tonic::Status> {
+        grpc_info!("(MOCK) {} client.", self.get_name());
+        grpc_debug!("(MOCK) request: {:?}", request);
+        Ok(tonic::Response::new(CancelVehicleItinerariesResponse {
+            itinerary_ids: vec![],
+        }))
+    }
+
+    async fn suggest_repositioning(
+        &self,
+        request: SuggestRepositioningRequest,
+    ) -> Result<tonic::Response<SuggestRepositioningResponse>, tonic::Status> {
+        grpc_info!("(MOCK) {} client.", self.get_name());
+        grpc_debug!("(MOCK) request: {:?}", request);
+        Ok(tonic::Response::new(SuggestRepositioningResponse {
+            flight_plans: vec![],
+        }))
+    }
+
+    async fn check_flight_plan(
+        &self,
+        request: CheckFlightPlanRequest,
+    ) -> Result<tonic::Response<CheckFlightPlanResponse>, tonic::Status> {
+        grpc_info!("(MOCK) {} client.", self.get_name());
+        grpc_debug!("(MOCK) request: {:?}", request);
+        Ok(tonic::Response::new(CheckFlightPlanResponse {
+            schedulable: true,
+            conflict: None,
+        }))
+    }
+
+    async fn get_itinerary(
+        &self,
+        request: GetItineraryRequest,
+    ) -> Result<tonic::Response<Itinerary>, tonic::Status> {
+        grpc_info!("(MOCK) {} client.", self.get_name());
+        grpc_debug!("(MOCK) request: {:?}", request);
+        Ok(tonic::Response::new(Itinerary {
+            flight_plans: vec![],
+            vehicle: None,
+            alternative_routes: vec![],
+            local_times: vec![],
+            estimated_price: 0,
+            deadhead_reasons: vec![],
+            path_fallback: false,
+            pad_selection: PadSelection::Unspecified as i32,
+            arrival_estimate: None,
+        }))
+    }
+
+    async fn restore_itinerary(
+        &self,
+        request: RestoreItineraryRequest,
+    ) -> Result<tonic::Response<TaskResponse>, tonic::Status> {
+        grpc_info!("(MOCK) {} client.", self.get_name());
+        grpc_debug!("(MOCK) request: {:?}", request);
+        let mut rng = rand::thread_rng();
+        Ok(tonic::Response::new(TaskResponse {
+            task_id: rng.gen_range(0..1000000),
+            task_metadata: Some(TaskMetadata {
+                status: TaskStatus::Queued.into(),
+                status_rationale: None,
+                action: TaskAction::CreateItinerary.into(),
+                user_id: request.user_id,
+                result: None,
+                fallback_vertipad_ids: vec![],
             }),
         }))
     }
@@ -175,6 +406,7 @@ impl crate::service::Client<RpcServiceClient<Channel>> for SchedulerClient {
                 action: TaskAction::CancelItinerary.into(),
                 user_id: Uuid::new_v4().to_string(), // arbitrary
                 result: None,
+                fallback_vertipad_ids: vec![],
             }),
         }))
     }
@@ -193,9 +425,127 @@ impl crate::service::Client<RpcServiceClient<Channel>> for SchedulerClient {
                 action: TaskAction::CreateItinerary.into(),
                 user_id: Uuid::new_v4().to_string(), // arbitrary
                 result: None,
+                fallback_vertipad_ids: vec![],
+            }),
+        }))
+    }
+
+    async fn list_queued_tasks(
+        &self,
+        request: ListQueuedTasksRequest,
+    ) -> Result<tonic::Response<ListQueuedTasksResponse>, tonic::Status> {
+        grpc_info!("(MOCK) {} client.", self.get_name());
+        grpc_debug!("(MOCK) request: {:?}", request);
+        Ok(tonic::Response::new(ListQueuedTasksResponse {
+            tasks: vec![],
+        }))
+    }
+
+    async fn requeue_task(
+        &self,
+        request: RequeueTaskRequest,
+    ) -> Result<tonic::Response<TaskResponse>, tonic::Status> {
+        grpc_info!("(MOCK) {} client.", self.get_name());
+        grpc_debug!("(MOCK) request: {:?}", request);
+        Ok(tonic::Response::new(TaskResponse {
+            task_id: request.task_id,
+            task_metadata: Some(TaskMetadata {
+                status: TaskStatus::Queued.into(),
+                status_rationale: None,
+                action: TaskAction::CreateItinerary.into(),
+                user_id: Uuid::new_v4().to_string(), // arbitrary
+                result: None,
+                fallback_vertipad_ids: vec![],
             }),
         }))
     }
+
+    async fn get_fleet_status(
+        &self,
+        request: GetFleetStatusRequest,
+    ) -> Result<tonic::Response<GetFleetStatusResponse>, tonic::Status> {
+        grpc_info!("(MOCK) {} client.", self.get_name());
+        grpc_debug!("(MOCK) request: {:?}", request);
+        Ok(tonic::Response::new(GetFleetStatusResponse {
+            vehicles: vec![],
+        }))
+    }
+
+    async fn validate_schedule(
+        &self,
+        request: ValidateScheduleRequest,
+    ) -> Result<tonic::Response<ValidateScheduleResponse>, tonic::Status> {
+        grpc_info!("(MOCK) {} client.", self.get_name());
+        grpc_debug!("(MOCK) request: {:?}", request);
+        Ok(tonic::Response::new(ValidateScheduleResponse {
+            flight_plans: request
+                .flight_plans
+                .iter()
+                .map(|_| FlightPlanValidation {
+                    schedulable: true,
+                    conflict: None,
+                })
+                .collect(),
+        }))
+    }
+
+    async fn estimate_deadheads(
+        &self,
+        request: EstimateDeadheadsRequest,
+    ) -> Result<tonic::Response<EstimateDeadheadsResponse>, tonic::Status> {
+        grpc_info!("(MOCK) {} client.", self.get_name());
+        grpc_debug!("(MOCK) request: {:?}", request);
+        Ok(tonic::Response::new(EstimateDeadheadsResponse {
+            pre_deadhead: None,
+            post_deadhead: None,
+        }))
+    }
+
+    async fn reachable_vertiports(
+        &self,
+        request: ReachableVertiportsRequest,
+    ) -> Result<tonic::Response<ReachableVertiportsResponse>, tonic::Status> {
+        grpc_info!("(MOCK) {} client.", self.get_name());
+        grpc_debug!("(MOCK) request: {:?}", request);
+        Ok(tonic::Response::new(ReachableVertiportsResponse {
+            vertiports: vec![],
+        }))
+    }
+
+    async fn vertipad_occupancy(
+        &self,
+        request: VertipadOccupancyRequest,
+    ) -> Result<tonic::Response<VertipadOccupancyResponse>, tonic::Status> {
+        grpc_info!("(MOCK) {} client.", self.get_name());
+        grpc_debug!("(MOCK) request: {:?}", request);
+        Ok(tonic::Response::new(VertipadOccupancyResponse {
+            vertiport_id: String::new(),
+            occupied: vec![],
+            free: vec![],
+        }))
+    }
+
+    async fn cancel_user_drafts(
+        &self,
+        request: CancelUserDraftsRequest,
+    ) -> Result<tonic::Response<CancelUserDraftsResponse>, tonic::Status> {
+        grpc_info!("(MOCK) {} client.", self.get_name());
+        grpc_debug!("(MOCK) request: {:?}", request);
+        Ok(tonic::Response::new(CancelUserDraftsResponse {
+            hold_ids: vec![],
+        }))
+    }
+
+    async fn list_user_itineraries(
+        &self,
+        request: ListUserItinerariesRequest,
+    ) -> Result<tonic::Response<ListUserItinerariesResponse>, tonic::Status> {
+        grpc_info!("(MOCK) {} client.", self.get_name());
+        grpc_debug!("(MOCK) request: {:?}", request);
+        Ok(tonic::Response::new(ListUserItinerariesResponse {
+            itineraries: vec![],
+        }))
+    }
 }
 
 #[cfg(test)]
@@ -1,6 +1,5 @@
 // This file is @generated by prost-build.
 /// QueryFlightRequest
-#[derive(Eq)]
 #[allow(clippy::derive_partial_eq_without_eq)]
 #[derive(Clone, PartialEq, ::prost::Message)]
 pub struct QueryFlightRequest {
@@ -31,6 +30,80 @@ pub struct QueryFlightRequest {
         tag = "8"
     )]
     pub priority: i32,
+    /// If no flights are found, waitlist this request on behalf of this user ID
+    #[prost(string, optional, tag = "9")]
+    pub waitlist_user_id: ::core::option::Option<::prost::alloc::string::String>,
+    /// Vertiports to route around, including for deadhead flights
+    #[prost(string, repeated, tag = "10")]
+    pub excluded_vertiport_ids: ::prost::alloc::vec::Vec<::prost::alloc::string::String>,
+    /// Waypoints for a loop flight, visited in order before returning
+    ///   to the origin vertiport. Origin and target vertiport must match.
+    #[prost(message, repeated, tag = "11")]
+    pub loop_waypoints: ::prost::alloc::vec::Vec<Waypoint>,
+    /// Only consider aircraft of this type, any type if not provided
+    #[prost(enumeration = "AircraftType", optional, tag = "12")]
+    pub aircraft_type: ::core::option::Option<i32>,
+    /// Emergency medical flight: booked at the top priority and allowed
+    ///   to depart without the usual advance notice
+    #[prost(bool, tag = "13")]
+    pub emergency: bool,
+    /// Land no earlier than this many seconds before latest_arrival_time,
+    ///   anywhere in the requested window if not provided
+    #[prost(uint32, optional, tag = "14")]
+    pub required_arrival_precision_seconds: ::core::option::Option<u32>,
+    /// If no flights are found, retry with progressively wider windows
+    ///   up to the configured horizon
+    #[prost(bool, tag = "15")]
+    pub auto_widen: bool,
+    /// How to order itineraries, earliest departure first if not provided
+    #[prost(enumeration = "Ranking", optional, tag = "16")]
+    pub ranking: ::core::option::Option<i32>,
+    /// Include the scheduled times of each flight plan in the local
+    ///   time of its vertiports
+    #[prost(bool, tag = "17")]
+    pub include_local_times: bool,
+    /// Ideal departure time within the window, itineraries departing
+    ///   closest to it are returned first if provided
+    #[prost(message, optional, tag = "18")]
+    pub preferred_departure_time: ::core::option::Option<::prost_wkt_types::Timestamp>,
+    /// Type of the departure node, a vertiport if not provided.
+    ///   Flights must depart from a vertiport
+    #[prost(enumeration = "EndpointType", optional, tag = "19")]
+    pub origin_type: ::core::option::Option<i32>,
+    /// Type of the arrival node, a vertiport if not provided. Flights to
+    ///   a waypoint are flown as loop flights back to the origin vertiport
+    #[prost(enumeration = "EndpointType", optional, tag = "20")]
+    pub target_type: ::core::option::Option<i32>,
+    /// Longest time the requested flight may take from loading to
+    ///   unloading, waiting excluded. Any duration if not provided
+    #[prost(uint32, optional, tag = "21")]
+    pub max_total_travel_seconds: ::core::option::Option<u32>,
+    /// Fewest itineraries wanted. The search goes on, widening the
+    ///   window if auto_widen is set, until that many are found or it
+    ///   is exhausted. Any number if not provided
+    #[prost(uint32, optional, tag = "22")]
+    pub min_results: ::core::option::Option<u32>,
+    /// Hazard class of the cargo (e.g. "3" for flammable liquids), only
+    ///   for cargo requests. Routes barred to the class aren't offered
+    #[prost(string, optional, tag = "23")]
+    pub cargo_hazard_class: ::core::option::Option<::prost::alloc::string::String>,
+}
+/// A point in space for loop flights
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct Waypoint {
+    /// latitude in degrees
+    #[prost(double, tag = "1")]
+    pub latitude: f64,
+    /// longitude in degrees
+    #[prost(double, tag = "2")]
+    pub longitude: f64,
+    /// altitude in meters
+    #[prost(float, tag = "3")]
+    pub altitude_meters: f32,
+    /// name of the waypoint in svc-gis, required for loop flights
+    #[prost(string, optional, tag = "4")]
+    pub name: ::core::option::Option<::prost::alloc::string::String>,
 }
 /// Create an itinerary by providing possible flight plan data
 #[allow(clippy::derive_partial_eq_without_eq)]
@@ -53,6 +126,12 @@ pub struct CreateItineraryRequest {
     /// User ID
     #[prost(string, tag = "4")]
     pub user_id: ::prost::alloc::string::String,
+    /// Flight plans of a return itinerary to hold for the user, released
+    ///   if the user doesn't book them within the configured hold time
+    #[prost(message, repeated, tag = "5")]
+    pub return_flight_plans: ::prost::alloc::vec::Vec<
+        ::svc_storage_client_grpc::prelude::flight_plan::Data,
+    >,
 }
 /// Cancel an itinerary by ID
 #[allow(clippy::derive_partial_eq_without_eq)]
@@ -70,6 +149,109 @@ pub struct CancelItineraryRequest {
     /// User ID
     #[prost(string, tag = "3")]
     pub user_id: ::prost::alloc::string::String,
+    /// Whether the cancellation can be reversed
+    #[prost(enumeration = "CancellationKind", tag = "4")]
+    pub cancellation_kind: i32,
+}
+/// Restore a soft-cancelled itinerary
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct RestoreItineraryRequest {
+    /// Priority of the restore task
+    #[prost(
+        enumeration = "::svc_storage_client_grpc::prelude::flight_plan::FlightPriority",
+        tag = "1"
+    )]
+    pub priority: i32,
+    /// Itinerary UUID
+    #[prost(string, tag = "2")]
+    pub itinerary_id: ::prost::alloc::string::String,
+    /// User ID, must match the user that cancelled the itinerary
+    #[prost(string, tag = "3")]
+    pub user_id: ::prost::alloc::string::String,
+}
+/// Cancel all future itineraries for a grounded vehicle
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct CancelVehicleItinerariesRequest {
+    /// Vehicle UUID
+    #[prost(string, tag = "1")]
+    pub vehicle_id: ::prost::alloc::string::String,
+}
+/// Itineraries cancelled for a grounded vehicle
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct CancelVehicleItinerariesResponse {
+    /// Cancelled itinerary UUIDs
+    #[prost(string, repeated, tag = "1")]
+    pub itinerary_ids: ::prost::alloc::vec::Vec<::prost::alloc::string::String>,
+}
+/// Suggest deadhead flights to move idle aircraft toward busy hubs
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct SuggestRepositioningRequest {
+    /// Hubs expected to need aircraft (demand hint)
+    #[prost(string, repeated, tag = "1")]
+    pub hub_vertiport_ids: ::prost::alloc::vec::Vec<::prost::alloc::string::String>,
+    /// Earliest departure time of the repositioning flights
+    #[prost(message, optional, tag = "2")]
+    pub earliest_departure_time: ::core::option::Option<::prost_wkt_types::Timestamp>,
+    /// Latest arrival time of the repositioning flights
+    #[prost(message, optional, tag = "3")]
+    pub latest_arrival_time: ::core::option::Option<::prost_wkt_types::Timestamp>,
+}
+/// Suggested repositioning flights, these are not booked
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct SuggestRepositioningResponse {
+    /// One deadhead flight plan per repositioned aircraft
+    #[prost(message, repeated, tag = "1")]
+    pub flight_plans: ::prost::alloc::vec::Vec<
+        ::svc_storage_client_grpc::prelude::flight_plan::Data,
+    >,
+}
+/// Check whether a proposed flight plan can be scheduled
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct CheckFlightPlanRequest {
+    /// Fully specified flight plan, including aircraft, vertipads and times
+    #[prost(message, optional, tag = "1")]
+    pub flight_plan: ::core::option::Option<
+        ::svc_storage_client_grpc::prelude::flight_plan::Data,
+    >,
+}
+/// Whether the proposed flight plan can be scheduled
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct CheckFlightPlanResponse {
+    /// True if the flight plan can be booked as-is
+    #[prost(bool, tag = "1")]
+    pub schedulable: bool,
+    /// Why the flight plan can't be booked, if it can't
+    #[prost(enumeration = "FlightPlanConflict", optional, tag = "2")]
+    pub conflict: ::core::option::Option<i32>,
+}
+/// Get a booked itinerary by ID
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct GetItineraryRequest {
+    /// Itinerary UUID
+    #[prost(string, tag = "1")]
+    pub itinerary_id: ::prost::alloc::string::String,
+}
+/// Human-friendly details of the aircraft flying an itinerary
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct VehicleInfo {
+    /// Vehicle UUID
+    #[prost(string, tag = "1")]
+    pub vehicle_id: ::prost::alloc::string::String,
+    /// Name of the vehicle model, empty if the model isn't known
+    #[prost(string, tag = "2")]
+    pub model_name: ::prost::alloc::string::String,
+    /// Vehicle registration number
+    #[prost(string, tag = "3")]
+    pub registration_number: ::prost::alloc::string::String,
 }
 /// Itinerary includes id, flight plan and potential deadhead flights
 #[allow(clippy::derive_partial_eq_without_eq)]
@@ -80,6 +262,86 @@ pub struct Itinerary {
     pub flight_plans: ::prost::alloc::vec::Vec<
         ::svc_storage_client_grpc::prelude::flight_plan::Data,
     >,
+    /// Aircraft flying the itinerary
+    #[prost(message, optional, tag = "2")]
+    pub vehicle: ::core::option::Option<VehicleInfo>,
+    /// Other routes for the requested flight, shortest first
+    #[prost(message, repeated, tag = "3")]
+    pub alternative_routes: ::prost::alloc::vec::Vec<AlternativeRoute>,
+    /// Scheduled times in vertiport local time, one entry per flight
+    ///   plan in the same order. Only provided if requested
+    #[prost(message, repeated, tag = "4")]
+    pub local_times: ::prost::alloc::vec::Vec<LocalTimes>,
+    /// Estimated price of the requested flight in the smallest currency
+    ///   unit, 0 if no rates are configured. Only quoted by queryFlight
+    #[prost(uint64, tag = "5")]
+    pub estimated_price: u64,
+    /// Why each flight plan is flown, one entry per flight plan in the
+    ///   same order. Only provided by queryFlight
+    #[prost(enumeration = "DeadheadReason", repeated, tag = "6")]
+    pub deadhead_reasons: ::prost::alloc::vec::Vec<i32>,
+    /// Whether the itinerary uses a straight-line path planned while
+    ///   svc-gis was unavailable, ignoring no-fly zones. Only provided
+    ///   by queryFlight
+    #[prost(bool, tag = "7")]
+    pub path_fallback: bool,
+    /// How the vertipads of the requested flight were selected among
+    ///   the available ones: the ranking if it ordered them, the
+    ///   configured vertipad selection otherwise. Only provided by
+    ///   queryFlight
+    #[prost(enumeration = "PadSelection", tag = "8")]
+    pub pad_selection: i32,
+    /// When the requested flight is likely to land. Only provided by
+    ///   queryFlight
+    #[prost(message, optional, tag = "9")]
+    pub arrival_estimate: ::core::option::Option<ArrivalEstimate>,
+}
+/// Likely landing times of a flight. The scheduled arrival allows for
+///   a generous flight time, most flights land earlier
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct ArrivalEstimate {
+    /// Half of flights land by this time
+    #[prost(message, optional, tag = "1")]
+    pub p50: ::core::option::Option<::prost_wkt_types::Timestamp>,
+    /// Nine in ten flights land by this time
+    #[prost(message, optional, tag = "2")]
+    pub p90: ::core::option::Option<::prost_wkt_types::Timestamp>,
+}
+/// A route other than the one scheduled for a flight
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct AlternativeRoute {
+    /// points along the route
+    #[prost(message, repeated, tag = "1")]
+    pub waypoints: ::prost::alloc::vec::Vec<Waypoint>,
+    /// total length of the route in meters
+    #[prost(double, tag = "2")]
+    pub distance_meters: f64,
+}
+/// Scheduled times of a flight plan in the local time of its vertiports,
+///   as RFC3339 timestamps with the UTC offset of the vertiport timezone
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct LocalTimes {
+    /// IANA timezone of the departure vertiport
+    #[prost(string, tag = "1")]
+    pub origin_timezone: ::prost::alloc::string::String,
+    /// Start of the departure timeslot
+    #[prost(string, tag = "2")]
+    pub origin_timeslot_start: ::prost::alloc::string::String,
+    /// End of the departure timeslot
+    #[prost(string, tag = "3")]
+    pub origin_timeslot_end: ::prost::alloc::string::String,
+    /// IANA timezone of the arrival vertiport
+    #[prost(string, tag = "4")]
+    pub target_timezone: ::prost::alloc::string::String,
+    /// Start of the arrival timeslot
+    #[prost(string, tag = "5")]
+    pub target_timeslot_start: ::prost::alloc::string::String,
+    /// End of the arrival timeslot
+    #[prost(string, tag = "6")]
+    pub target_timeslot_end: ::prost::alloc::string::String,
 }
 /// QueryFlightResponse
 #[allow(clippy::derive_partial_eq_without_eq)]
@@ -88,6 +350,41 @@ pub struct QueryFlightResponse {
     /// array/vector of itineraries items
     #[prost(message, repeated, tag = "1")]
     pub itineraries: ::prost::alloc::vec::Vec<Itinerary>,
+    /// Why no itineraries were returned, if known
+    #[prost(enumeration = "NoFlightsReason", optional, tag = "2")]
+    pub reason: ::core::option::Option<i32>,
+    /// Earliest departure time of the window that was searched,
+    ///   only provided if auto_widen was requested
+    #[prost(message, optional, tag = "3")]
+    pub window_earliest_departure_time: ::core::option::Option<::prost_wkt_types::Timestamp>,
+    /// Latest arrival time of the window that was searched,
+    ///   only provided if auto_widen was requested
+    #[prost(message, optional, tag = "4")]
+    pub window_latest_arrival_time: ::core::option::Option<::prost_wkt_types::Timestamp>,
+    /// Constraint that limited the number of itineraries, only provided
+    ///   if fewer than the maximum number of itineraries were found
+    #[prost(enumeration = "LimitingConstraint", optional, tag = "5")]
+    pub limiting_constraint: ::core::option::Option<i32>,
+    /// The search ran out of time, more itineraries may be available
+    #[prost(bool, tag = "6")]
+    pub partial: bool,
+    /// Vertiports near the requested destination that can be flown to
+    ///   within the window, nearest first. Only provided if the requested
+    ///   destination has no availability
+    #[prost(message, repeated, tag = "7")]
+    pub alternative_destinations: ::prost::alloc::vec::Vec<AlternativeDestination>,
+}
+/// A vertiport near the requested destination
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct AlternativeDestination {
+    /// vertiport UUID
+    #[prost(string, tag = "1")]
+    pub vertiport_id: ::prost::alloc::string::String,
+    /// straight-line distance from the requested destination in meters,
+    ///   as found by svc-gis
+    #[prost(double, tag = "2")]
+    pub distance_meters: f64,
 }
 /// Task-Related Messages
 #[derive(Eq, Copy)]
@@ -130,6 +427,284 @@ pub struct TaskMetadata {
     /// Result from the task, if any
     #[prost(string, optional, tag = "5")]
     pub result: ::core::option::Option<::prost::alloc::string::String>,
+    /// Vertipads landed on instead of requested vertipads that were busy
+    ///   when the itinerary was booked, in flight plan order
+    #[prost(string, repeated, tag = "6")]
+    #[serde(default)]
+    pub fallback_vertipad_ids: ::prost::alloc::vec::Vec<::prost::alloc::string::String>,
+}
+/// List the tasks waiting to be processed (operators only)
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct ListQueuedTasksRequest {}
+/// A task waiting in a priority queue
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct QueuedTask {
+    /// Task ID
+    #[prost(int64, tag = "1")]
+    pub task_id: i64,
+    /// Priority queue the task is waiting in
+    #[prost(
+        enumeration = "::svc_storage_client_grpc::prelude::flight_plan::FlightPriority",
+        tag = "2"
+    )]
+    pub priority: i32,
+    /// Task Details
+    #[prost(message, optional, tag = "3")]
+    pub task_metadata: ::core::option::Option<TaskMetadata>,
+}
+/// Queued tasks, in the order they will be processed
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct ListQueuedTasksResponse {
+    /// Queued tasks
+    #[prost(message, repeated, tag = "1")]
+    pub tasks: ::prost::alloc::vec::Vec<QueuedTask>,
+}
+/// Reprioritize a queued task, or queue a rejected task again (operators only)
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct RequeueTaskRequest {
+    /// Task ID
+    #[prost(int64, tag = "1")]
+    pub task_id: i64,
+    /// Priority queue to move the task to
+    #[prost(
+        enumeration = "::svc_storage_client_grpc::prelude::flight_plan::FlightPriority",
+        tag = "2"
+    )]
+    pub priority: i32,
+}
+/// Get where each aircraft is scheduled to be and its next flight
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct GetFleetStatusRequest {
+    /// Only aircraft at or headed to this vertiport
+    #[prost(string, optional, tag = "1")]
+    pub vertiport_id: ::core::option::Option<::prost::alloc::string::String>,
+    /// Time of the status, defaults to now
+    #[prost(message, optional, tag = "2")]
+    pub time: ::core::option::Option<::prost_wkt_types::Timestamp>,
+}
+/// Scheduled location and next flight of an aircraft
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct VehicleStatus {
+    /// Vehicle UUID
+    #[prost(string, tag = "1")]
+    pub vehicle_id: ::prost::alloc::string::String,
+    /// Vertiport the aircraft is parked at or headed to
+    #[prost(string, tag = "2")]
+    pub vertiport_id: ::prost::alloc::string::String,
+    /// Vertipad the aircraft is parked at or headed to
+    #[prost(string, tag = "3")]
+    pub vertipad_id: ::prost::alloc::string::String,
+    /// First flight departing after the time of the status, if any
+    #[prost(message, optional, tag = "4")]
+    pub next_flight_plan: ::core::option::Option<
+        ::svc_storage_client_grpc::prelude::flight_plan::Data,
+    >,
+}
+/// Status of the fleet
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct GetFleetStatusResponse {
+    /// Status of each aircraft
+    #[prost(message, repeated, tag = "1")]
+    pub vehicles: ::prost::alloc::vec::Vec<VehicleStatus>,
+}
+/// Check a batch of flight plans against current bookings
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct ValidateScheduleRequest {
+    /// Fully specified flight plans, including aircraft, vertipads and times
+    #[prost(message, repeated, tag = "1")]
+    pub flight_plans: ::prost::alloc::vec::Vec<
+        ::svc_storage_client_grpc::prelude::flight_plan::Data,
+    >,
+}
+/// Whether a flight plan of the batch can be scheduled
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct FlightPlanValidation {
+    /// True if the flight plan doesn't conflict with current bookings
+    #[prost(bool, tag = "1")]
+    pub schedulable: bool,
+    /// What the flight plan conflicts with, if it does
+    #[prost(enumeration = "FlightPlanConflict", optional, tag = "2")]
+    pub conflict: ::core::option::Option<i32>,
+}
+/// Validation of a batch of flight plans
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct ValidateScheduleResponse {
+    /// Validation of each flight plan, in the order of the request
+    #[prost(message, repeated, tag = "1")]
+    pub flight_plans: ::prost::alloc::vec::Vec<FlightPlanValidation>,
+}
+/// Estimate the deadhead flights of a prospective booking
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct EstimateDeadheadsRequest {
+    /// Flight query to estimate
+    #[prost(message, optional, tag = "1")]
+    pub query: ::core::option::Option<QueryFlightRequest>,
+    /// Vehicle UUID of the aircraft to fly the query
+    #[prost(string, tag = "2")]
+    pub vehicle_id: ::prost::alloc::string::String,
+}
+/// A deadhead flight that would be flown for a booking
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct DeadheadEstimate {
+    /// Flight plan of the deadhead flight, not booked
+    #[prost(message, optional, tag = "1")]
+    pub flight_plan: ::core::option::Option<
+        ::svc_storage_client_grpc::prelude::flight_plan::Data,
+    >,
+    /// Length of the route in meters
+    #[prost(double, tag = "2")]
+    pub distance_meters: f64,
+    /// Time in the air in seconds
+    #[prost(uint32, tag = "3")]
+    pub duration_seconds: u32,
+}
+/// Deadhead flights the aircraft would fly for the query
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct EstimateDeadheadsResponse {
+    /// Deadhead flight to the departure vertiport, if the aircraft is elsewhere
+    #[prost(message, optional, tag = "1")]
+    pub pre_deadhead: ::core::option::Option<DeadheadEstimate>,
+    /// Deadhead flight away from the arrival vertiport, if any
+    #[prost(message, optional, tag = "2")]
+    pub post_deadhead: ::core::option::Option<DeadheadEstimate>,
+}
+/// List the vertiports reachable from a vertiport in a single flight
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct ReachableVertiportsRequest {
+    /// Vertiport UUID to depart from
+    #[prost(string, tag = "1")]
+    pub origin_vertiport_id: ::prost::alloc::string::String,
+    /// Type of aircraft flying, any configured vehicle model if not provided
+    #[prost(enumeration = "AircraftType", optional, tag = "2")]
+    pub aircraft_type: ::core::option::Option<i32>,
+}
+/// A vertiport reachable in a single flight
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct ReachableVertiport {
+    /// Vertiport UUID
+    #[prost(string, tag = "1")]
+    pub vertiport_id: ::prost::alloc::string::String,
+    /// Straight-line distance in meters, routes around no-fly zones
+    ///   may be longer
+    #[prost(double, tag = "2")]
+    pub distance_meters: f64,
+    /// Estimated flight time in seconds
+    #[prost(uint32, tag = "3")]
+    pub flight_duration_seconds: u32,
+}
+/// Vertiports reachable from the origin vertiport
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct ReachableVertiportsResponse {
+    /// Reachable vertiports, the origin excluded, nearest first
+    #[prost(message, repeated, tag = "1")]
+    pub vertiports: ::prost::alloc::vec::Vec<ReachableVertiport>,
+}
+/// Get the occupied and free time of a vertipad over a window
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct VertipadOccupancyRequest {
+    /// Vertipad UUID
+    #[prost(string, tag = "1")]
+    pub vertipad_id: ::prost::alloc::string::String,
+    /// Start of the window
+    #[prost(message, optional, tag = "2")]
+    pub time_start: ::core::option::Option<::prost_wkt_types::Timestamp>,
+    /// End of the window
+    #[prost(message, optional, tag = "3")]
+    pub time_end: ::core::option::Option<::prost_wkt_types::Timestamp>,
+}
+/// A slot of time on a vertipad
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct VertipadSlot {
+    /// Start of the slot
+    #[prost(message, optional, tag = "1")]
+    pub time_start: ::core::option::Option<::prost_wkt_types::Timestamp>,
+    /// End of the slot
+    #[prost(message, optional, tag = "2")]
+    pub time_end: ::core::option::Option<::prost_wkt_types::Timestamp>,
+}
+/// Occupied and free time of a vertipad, each in time order
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct VertipadOccupancyResponse {
+    /// Vertiport UUID the vertipad belongs to
+    #[prost(string, tag = "1")]
+    pub vertiport_id: ::prost::alloc::string::String,
+    /// Slots taken by flights taking off or landing
+    #[prost(message, repeated, tag = "2")]
+    pub occupied: ::prost::alloc::vec::Vec<VertipadSlot>,
+    /// The rest of the window
+    #[prost(message, repeated, tag = "3")]
+    pub free: ::prost::alloc::vec::Vec<VertipadSlot>,
+}
+/// Release the unconfirmed return holds of a user
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct CancelUserDraftsRequest {
+    /// User UUID
+    #[prost(string, tag = "1")]
+    pub user_id: ::prost::alloc::string::String,
+}
+/// Return holds released for the user
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct CancelUserDraftsResponse {
+    /// IDs of the released holds
+    #[prost(string, repeated, tag = "1")]
+    pub hold_ids: ::prost::alloc::vec::Vec<::prost::alloc::string::String>,
+}
+/// List the itineraries of a user
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct ListUserItinerariesRequest {
+    /// User UUID
+    #[prost(string, tag = "1")]
+    pub user_id: ::prost::alloc::string::String,
+    /// Also list soft-cancelled itineraries that can still be restored
+    #[prost(bool, tag = "2")]
+    pub include_recoverable: bool,
+}
+/// An itinerary of a user
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct UserItinerary {
+    /// Itinerary UUID
+    #[prost(string, tag = "1")]
+    pub itinerary_id: ::prost::alloc::string::String,
+    /// Flight plans of the itinerary in departure order
+    #[prost(message, repeated, tag = "2")]
+    pub flight_plans: ::prost::alloc::vec::Vec<
+        ::svc_storage_client_grpc::prelude::flight_plan::Data,
+    >,
+    /// Whether the itinerary was soft-cancelled and can still be
+    ///   restored with restoreItinerary
+    #[prost(bool, tag = "3")]
+    pub recoverable: bool,
+}
+/// Itineraries of the user
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct ListUserItinerariesResponse {
+    /// Active itineraries, then recoverable ones if requested
+    #[prost(message, repeated, tag = "1")]
+    pub itineraries: ::prost::alloc::vec::Vec<UserItinerary>,
 }
 /// Ready Request
 ///
@@ -147,101 +722,420 @@ pub struct ReadyResponse {
     #[prost(bool, tag = "1")]
     pub ready: bool,
 }
-/// The status of a scheduler task
+/// Types of aircraft a flight can be restricted to
 #[derive(num_derive::FromPrimitive)]
 #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, ::prost::Enumeration)]
 #[repr(i32)]
-pub enum TaskStatus {
-    /// Queued
-    Queued = 0,
-    /// Complete
-    Complete = 1,
-    /// Rejected
-    Rejected = 2,
-    /// Not Found
-    NotFound = 3,
+pub enum AircraftType {
+    /// Cargo aircraft
+    Cargo = 0,
+    /// Passenger aircraft
+    Passenger = 1,
 }
-impl TaskStatus {
+impl AircraftType {
     /// String value of the enum field names used in the ProtoBuf definition.
     ///
     /// The values are not transformed in any way and thus are considered stable
     /// (if the ProtoBuf definition does not change) and safe for programmatic use.
     pub fn as_str_name(&self) -> &'static str {
         match self {
-            TaskStatus::Queued => "QUEUED",
-            TaskStatus::Complete => "COMPLETE",
-            TaskStatus::Rejected => "REJECTED",
-            TaskStatus::NotFound => "NOT_FOUND",
+            AircraftType::Cargo => "CARGO",
+            AircraftType::Passenger => "PASSENGER",
         }
     }
     /// Creates an enum from field names used in the ProtoBuf definition.
     pub fn from_str_name(value: &str) -> ::core::option::Option<Self> {
         match value {
-            "QUEUED" => Some(Self::Queued),
-            "COMPLETE" => Some(Self::Complete),
-            "REJECTED" => Some(Self::Rejected),
-            "NOT_FOUND" => Some(Self::NotFound),
+            "CARGO" => Some(Self::Cargo),
+            "PASSENGER" => Some(Self::Passenger),
             _ => None,
         }
     }
 }
-/// Explanation for a task status
+/// Orders in which itineraries can be returned
+#[derive(num_derive::FromPrimitive)]
 #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, ::prost::Enumeration)]
 #[repr(i32)]
-pub enum TaskStatusRationale {
-    /// Client cancelled
-    ClientCancelled = 0,
-    /// Expired
-    Expired = 1,
-    /// Schedule conflict
-    ScheduleConflict = 2,
-    /// Itinerary ID not found
-    ItineraryIdNotFound = 3,
-    /// Priority change
-    PriorityChange = 4,
-    /// Internal Failure
-    Internal = 5,
-    /// Invalid Action
-    InvalidAction = 6,
+pub enum Ranking {
+    /// Earliest departure first
+    Earliest = 0,
+    /// Cheapest departure time first, per the configured pricing schedule
+    LowestCost = 1,
+    /// Earliest arrival of the requested flight first
+    EarliestArrival = 2,
 }
-impl TaskStatusRationale {
+impl Ranking {
     /// String value of the enum field names used in the ProtoBuf definition.
     ///
     /// The values are not transformed in any way and thus are considered stable
     /// (if the ProtoBuf definition does not change) and safe for programmatic use.
     pub fn as_str_name(&self) -> &'static str {
         match self {
-            TaskStatusRationale::ClientCancelled => "CLIENT_CANCELLED",
-            TaskStatusRationale::Expired => "EXPIRED",
-            TaskStatusRationale::ScheduleConflict => "SCHEDULE_CONFLICT",
-            TaskStatusRationale::ItineraryIdNotFound => "ITINERARY_ID_NOT_FOUND",
-            TaskStatusRationale::PriorityChange => "PRIORITY_CHANGE",
-            TaskStatusRationale::Internal => "INTERNAL",
-            TaskStatusRationale::InvalidAction => "INVALID_ACTION",
+            Ranking::Earliest => "EARLIEST",
+            Ranking::LowestCost => "LOWEST_COST",
+            Ranking::EarliestArrival => "EARLIEST_ARRIVAL",
         }
     }
     /// Creates an enum from field names used in the ProtoBuf definition.
     pub fn from_str_name(value: &str) -> ::core::option::Option<Self> {
         match value {
-            "CLIENT_CANCELLED" => Some(Self::ClientCancelled),
-            "EXPIRED" => Some(Self::Expired),
-            "SCHEDULE_CONFLICT" => Some(Self::ScheduleConflict),
-            "ITINERARY_ID_NOT_FOUND" => Some(Self::ItineraryIdNotFound),
-            "PRIORITY_CHANGE" => Some(Self::PriorityChange),
-            "INTERNAL" => Some(Self::Internal),
-            "INVALID_ACTION" => Some(Self::InvalidAction),
+            "EARLIEST" => Some(Self::Earliest),
+            "LOWEST_COST" => Some(Self::LowestCost),
+            "EARLIEST_ARRIVAL" => Some(Self::EarliestArrival),
             _ => None,
         }
     }
 }
-/// Types of scheduler tasks
+/// Types of nodes a flight can depart from or arrive at
 #[derive(num_derive::FromPrimitive)]
 #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, ::prost::Enumeration)]
 #[repr(i32)]
-pub enum TaskAction {
-    /// Confirm itinerary
-    CreateItinerary = 0,
-    /// Cancel itinerary
+pub enum EndpointType {
+    /// Vertiport, identified by its UUID
+    Vertiport = 0,
+    /// Waypoint known to svc-gis, identified by its name
+    Waypoint = 1,
+}
+impl EndpointType {
+    /// String value of the enum field names used in the ProtoBuf definition.
+    ///
+    /// The values are not transformed in any way and thus are considered stable
+    /// (if the ProtoBuf definition does not change) and safe for programmatic use.
+    pub fn as_str_name(&self) -> &'static str {
+        match self {
+            EndpointType::Vertiport => "VERTIPORT",
+            EndpointType::Waypoint => "WAYPOINT",
+        }
+    }
+    /// Creates an enum from field names used in the ProtoBuf definition.
+    pub fn from_str_name(value: &str) -> ::core::option::Option<Self> {
+        match value {
+            "VERTIPORT" => Some(Self::Vertiport),
+            "WAYPOINT" => Some(Self::Waypoint),
+            _ => None,
+        }
+    }
+}
+/// Kinds of itinerary cancellation
+#[derive(num_derive::FromPrimitive)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, ::prost::Enumeration)]
+#[repr(i32)]
+pub enum CancellationKind {
+    /// Permanent void, can't be reversed
+    Hard = 0,
+    /// Customer cancellation, can be restored within a grace period
+    Soft = 1,
+}
+impl CancellationKind {
+    /// String value of the enum field names used in the ProtoBuf definition.
+    ///
+    /// The values are not transformed in any way and thus are considered stable
+    /// (if the ProtoBuf definition does not change) and safe for programmatic use.
+    pub fn as_str_name(&self) -> &'static str {
+        match self {
+            CancellationKind::Hard => "HARD",
+            CancellationKind::Soft => "SOFT",
+        }
+    }
+    /// Creates an enum from field names used in the ProtoBuf definition.
+    pub fn from_str_name(value: &str) -> ::core::option::Option<Self> {
+        match value {
+            "HARD" => Some(Self::Hard),
+            "SOFT" => Some(Self::Soft),
+            _ => None,
+        }
+    }
+}
+/// Reasons a proposed flight plan can't be scheduled
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, ::prost::Enumeration)]
+#[repr(i32)]
+pub enum FlightPlanConflict {
+    /// Aircraft is busy or not parked at the origin vertiport
+    AircraftUnavailable = 0,
+    /// Origin or target vertipad is occupied
+    VertipadUnavailable = 1,
+    /// Route is longer than the aircraft can fly
+    OutOfRange = 2,
+    /// No route between the vertiports at the requested times
+    NoRoute = 3,
+}
+impl FlightPlanConflict {
+    /// String value of the enum field names used in the ProtoBuf definition.
+    ///
+    /// The values are not transformed in any way and thus are considered stable
+    /// (if the ProtoBuf definition does not change) and safe for programmatic use.
+    pub fn as_str_name(&self) -> &'static str {
+        match self {
+            FlightPlanConflict::AircraftUnavailable => "AIRCRAFT_UNAVAILABLE",
+            FlightPlanConflict::VertipadUnavailable => "VERTIPAD_UNAVAILABLE",
+            FlightPlanConflict::OutOfRange => "OUT_OF_RANGE",
+            FlightPlanConflict::NoRoute => "NO_ROUTE",
+        }
+    }
+    /// Creates an enum from field names used in the ProtoBuf definition.
+    pub fn from_str_name(value: &str) -> ::core::option::Option<Self> {
+        match value {
+            "AIRCRAFT_UNAVAILABLE" => Some(Self::AircraftUnavailable),
+            "VERTIPAD_UNAVAILABLE" => Some(Self::VertipadUnavailable),
+            "OUT_OF_RANGE" => Some(Self::OutOfRange),
+            "NO_ROUTE" => Some(Self::NoRoute),
+            _ => None,
+        }
+    }
+}
+/// Reasons a flight query returned no itineraries
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, ::prost::Enumeration)]
+#[repr(i32)]
+pub enum NoFlightsReason {
+    /// The whole network is grounded during the requested time
+    NetworkBlackout = 0,
+    /// Every route is shorter than the minimum flight distance
+    RouteTooShort = 1,
+    /// No aircraft is scheduled to be on duty during the requested time
+    NoAircraftOnDuty = 2,
+    /// Every available aircraft needs more deadhead flights than allowed
+    TooManyDeadheads = 3,
+    /// The destination has no availability, nearby vertiports are
+    ///   suggested instead
+    DestinationUnavailable = 4,
+    /// The cargo hazard class is barred from the departure or arrival
+    ///   vertiport, or from the corridor between them
+    HazardRestricted = 5,
+}
+impl NoFlightsReason {
+    /// String value of the enum field names used in the ProtoBuf definition.
+    ///
+    /// The values are not transformed in any way and thus are considered stable
+    /// (if the ProtoBuf definition does not change) and safe for programmatic use.
+    pub fn as_str_name(&self) -> &'static str {
+        match self {
+            NoFlightsReason::NetworkBlackout => "NETWORK_BLACKOUT",
+            NoFlightsReason::RouteTooShort => "ROUTE_TOO_SHORT",
+            NoFlightsReason::NoAircraftOnDuty => "NO_AIRCRAFT_ON_DUTY",
+            NoFlightsReason::TooManyDeadheads => "TOO_MANY_DEADHEADS",
+            NoFlightsReason::DestinationUnavailable => "DESTINATION_UNAVAILABLE",
+            NoFlightsReason::HazardRestricted => "HAZARD_RESTRICTED",
+        }
+    }
+    /// Creates an enum from field names used in the ProtoBuf definition.
+    pub fn from_str_name(value: &str) -> ::core::option::Option<Self> {
+        match value {
+            "NETWORK_BLACKOUT" => Some(Self::NetworkBlackout),
+            "ROUTE_TOO_SHORT" => Some(Self::RouteTooShort),
+            "NO_AIRCRAFT_ON_DUTY" => Some(Self::NoAircraftOnDuty),
+            "TOO_MANY_DEADHEADS" => Some(Self::TooManyDeadheads),
+            "DESTINATION_UNAVAILABLE" => Some(Self::DestinationUnavailable),
+            "HAZARD_RESTRICTED" => Some(Self::HazardRestricted),
+            _ => None,
+        }
+    }
+}
+/// Why a flight plan of an itinerary is flown
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, ::prost::Enumeration)]
+#[repr(i32)]
+pub enum DeadheadReason {
+    /// The requested flight, not a deadhead
+    NotDeadhead = 0,
+    /// The aircraft is elsewhere and flies to the departure vertiport
+    PrePositioning = 1,
+    /// The aircraft returns to where it is needed next after the flight
+    Return = 2,
+    /// The aircraft flies to a vertiport to recharge
+    Recharge = 3,
+}
+impl DeadheadReason {
+    /// String value of the enum field names used in the ProtoBuf definition.
+    ///
+    /// The values are not transformed in any way and thus are considered stable
+    /// (if the ProtoBuf definition does not change) and safe for programmatic use.
+    pub fn as_str_name(&self) -> &'static str {
+        match self {
+            DeadheadReason::NotDeadhead => "NOT_DEADHEAD",
+            DeadheadReason::PrePositioning => "PRE_POSITIONING",
+            DeadheadReason::Return => "RETURN",
+            DeadheadReason::Recharge => "RECHARGE",
+        }
+    }
+    /// Creates an enum from field names used in the ProtoBuf definition.
+    pub fn from_str_name(value: &str) -> ::core::option::Option<Self> {
+        match value {
+            "NOT_DEADHEAD" => Some(Self::NotDeadhead),
+            "PRE_POSITIONING" => Some(Self::PrePositioning),
+            "RETURN" => Some(Self::Return),
+            "RECHARGE" => Some(Self::Recharge),
+            _ => None,
+        }
+    }
+}
+/// Constraints that may limit the itineraries of a flight query
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, ::prost::Enumeration)]
+#[repr(i32)]
+pub enum LimitingConstraint {
+    /// Not enough open vertipad timeslots at the vertiports
+    PadCapacity = 0,
+    /// Aircraft were busy or couldn't reach the departure vertiport in time
+    AircraftAvailability = 1,
+    /// No route could be found for the aircraft
+    Routing = 2,
+}
+impl LimitingConstraint {
+    /// String value of the enum field names used in the ProtoBuf definition.
+    ///
+    /// The values are not transformed in any way and thus are considered stable
+    /// (if the ProtoBuf definition does not change) and safe for programmatic use.
+    pub fn as_str_name(&self) -> &'static str {
+        match self {
+            LimitingConstraint::PadCapacity => "PAD_CAPACITY",
+            LimitingConstraint::AircraftAvailability => "AIRCRAFT_AVAILABILITY",
+            LimitingConstraint::Routing => "ROUTING",
+        }
+    }
+    /// Creates an enum from field names used in the ProtoBuf definition.
+    pub fn from_str_name(value: &str) -> ::core::option::Option<Self> {
+        match value {
+            "PAD_CAPACITY" => Some(Self::PadCapacity),
+            "AIRCRAFT_AVAILABILITY" => Some(Self::AircraftAvailability),
+            "ROUTING" => Some(Self::Routing),
+            _ => None,
+        }
+    }
+}
+/// Criteria vertipads are selected by when several are available
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, ::prost::Enumeration)]
+#[repr(i32)]
+pub enum PadSelection {
+    /// Not reported
+    Unspecified = 0,
+    /// The vertipads free soonest
+    EarliestAvailability = 1,
+    /// The vertipads with the shortest route between them
+    ShortestPath = 2,
+    /// The vertipads free closest to the preferred departure time
+    PreferredDeparture = 3,
+    /// The vertipads free in the cheapest pricing window
+    LowestCost = 4,
+}
+impl PadSelection {
+    /// String value of the enum field names used in the ProtoBuf definition.
+    ///
+    /// The values are not transformed in any way and thus are considered stable
+    /// (if the ProtoBuf definition does not change) and safe for programmatic use.
+    pub fn as_str_name(&self) -> &'static str {
+        match self {
+            PadSelection::Unspecified => "PAD_SELECTION_UNSPECIFIED",
+            PadSelection::EarliestAvailability => "EARLIEST_AVAILABILITY",
+            PadSelection::ShortestPath => "SHORTEST_PATH",
+            PadSelection::PreferredDeparture => "PREFERRED_DEPARTURE",
+            PadSelection::LowestCost => "LOWEST_COST",
+        }
+    }
+    /// Creates an enum from field names used in the ProtoBuf definition.
+    pub fn from_str_name(value: &str) -> ::core::option::Option<Self> {
+        match value {
+            "PAD_SELECTION_UNSPECIFIED" => Some(Self::Unspecified),
+            "EARLIEST_AVAILABILITY" => Some(Self::EarliestAvailability),
+            "SHORTEST_PATH" => Some(Self::ShortestPath),
+            "PREFERRED_DEPARTURE" => Some(Self::PreferredDeparture),
+            "LOWEST_COST" => Some(Self::LowestCost),
+            _ => None,
+        }
+    }
+}
+/// The status of a scheduler task
+#[derive(num_derive::FromPrimitive)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, ::prost::Enumeration)]
+#[repr(i32)]
+pub enum TaskStatus {
+    /// Queued
+    Queued = 0,
+    /// Complete
+    Complete = 1,
+    /// Rejected
+    Rejected = 2,
+    /// Not Found
+    NotFound = 3,
+}
+impl TaskStatus {
+    /// String value of the enum field names used in the ProtoBuf definition.
+    ///
+    /// The values are not transformed in any way and thus are considered stable
+    /// (if the ProtoBuf definition does not change) and safe for programmatic use.
+    pub fn as_str_name(&self) -> &'static str {
+        match self {
+            TaskStatus::Queued => "QUEUED",
+            TaskStatus::Complete => "COMPLETE",
+            TaskStatus::Rejected => "REJECTED",
+            TaskStatus::NotFound => "NOT_FOUND",
+        }
+    }
+    /// Creates an enum from field names used in the ProtoBuf definition.
+    pub fn from_str_name(value: &str) -> ::core::option::Option<Self> {
+        match value {
+            "QUEUED" => Some(Self::Queued),
+            "COMPLETE" => Some(Self::Complete),
+            "REJECTED" => Some(Self::Rejected),
+            "NOT_FOUND" => Some(Self::NotFound),
+            _ => None,
+        }
+    }
+}
+/// Explanation for a task status
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, ::prost::Enumeration)]
+#[repr(i32)]
+pub enum TaskStatusRationale {
+    /// Client cancelled
+    ClientCancelled = 0,
+    /// Expired
+    Expired = 1,
+    /// Schedule conflict
+    ScheduleConflict = 2,
+    /// Itinerary ID not found
+    ItineraryIdNotFound = 3,
+    /// Priority change
+    PriorityChange = 4,
+    /// Internal Failure
+    Internal = 5,
+    /// Invalid Action
+    InvalidAction = 6,
+}
+impl TaskStatusRationale {
+    /// String value of the enum field names used in the ProtoBuf definition.
+    ///
+    /// The values are not transformed in any way and thus are considered stable
+    /// (if the ProtoBuf definition does not change) and safe for programmatic use.
+    pub fn as_str_name(&self) -> &'static str {
+        match self {
+            TaskStatusRationale::ClientCancelled => "CLIENT_CANCELLED",
+            TaskStatusRationale::Expired => "EXPIRED",
+            TaskStatusRationale::ScheduleConflict => "SCHEDULE_CONFLICT",
+            TaskStatusRationale::ItineraryIdNotFound => "ITINERARY_ID_NOT_FOUND",
+            TaskStatusRationale::PriorityChange => "PRIORITY_CHANGE",
+            TaskStatusRationale::Internal => "INTERNAL",
+            TaskStatusRationale::InvalidAction => "INVALID_ACTION",
+        }
+    }
+    /// Creates an enum from field names used in the ProtoBuf definition.
+    pub fn from_str_name(value: &str) -> ::core::option::Option<Self> {
+        match value {
+            "CLIENT_CANCELLED" => Some(Self::ClientCancelled),
+            "EXPIRED" => Some(Self::Expired),
+            "SCHEDULE_CONFLICT" => Some(Self::ScheduleConflict),
+            "ITINERARY_ID_NOT_FOUND" => Some(Self::ItineraryIdNotFound),
+            "PRIORITY_CHANGE" => Some(Self::PriorityChange),
+            "INTERNAL" => Some(Self::Internal),
+            "INVALID_ACTION" => Some(Self::InvalidAction),
+            _ => None,
+        }
+    }
+}
+/// Types of scheduler tasks
+#[derive(num_derive::FromPrimitive)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, ::prost::Enumeration)]
+#[repr(i32)]
+pub enum TaskAction {
+    /// Confirm itinerary
+    CreateItinerary = 0,
+    /// Cancel itinerary
     CancelItinerary = 1,
 }
 impl TaskAction {
@@ -420,6 +1314,125 @@ pub mod rpc_service_client {
                 .insert(GrpcMethod::new("grpc.RpcService", "cancelItinerary"));
             self.inner.unary(req, path, codec).await
         }
+        pub async fn cancel_vehicle_itineraries(
+            &mut self,
+            request: impl tonic::IntoRequest<super::CancelVehicleItinerariesRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::CancelVehicleItinerariesResponse>,
+            tonic::Status,
+        > {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::new(
+                        tonic::Code::Unknown,
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static(
+                "/grpc.RpcService/cancelVehicleItineraries",
+            );
+            let mut req = request.into_request();
+            req.extensions_mut()
+                .insert(GrpcMethod::new("grpc.RpcService", "cancelVehicleItineraries"));
+            self.inner.unary(req, path, codec).await
+        }
+        pub async fn suggest_repositioning(
+            &mut self,
+            request: impl tonic::IntoRequest<super::SuggestRepositioningRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::SuggestRepositioningResponse>,
+            tonic::Status,
+        > {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::new(
+                        tonic::Code::Unknown,
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static(
+                "/grpc.RpcService/suggestRepositioning",
+            );
+            let mut req = request.into_request();
+            req.extensions_mut()
+                .insert(GrpcMethod::new("grpc.RpcService", "suggestRepositioning"));
+            self.inner.unary(req, path, codec).await
+        }
+        pub async fn check_flight_plan(
+            &mut self,
+            request: impl tonic::IntoRequest<super::CheckFlightPlanRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::CheckFlightPlanResponse>,
+            tonic::Status,
+        > {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::new(
+                        tonic::Code::Unknown,
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static(
+                "/grpc.RpcService/checkFlightPlan",
+            );
+            let mut req = request.into_request();
+            req.extensions_mut()
+                .insert(GrpcMethod::new("grpc.RpcService", "checkFlightPlan"));
+            self.inner.unary(req, path, codec).await
+        }
+        pub async fn get_itinerary(
+            &mut self,
+            request: impl tonic::IntoRequest<super::GetItineraryRequest>,
+        ) -> std::result::Result<tonic::Response<super::Itinerary>, tonic::Status> {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::new(
+                        tonic::Code::Unknown,
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static(
+                "/grpc.RpcService/getItinerary",
+            );
+            let mut req = request.into_request();
+            req.extensions_mut()
+                .insert(GrpcMethod::new("grpc.RpcService", "getItinerary"));
+            self.inner.unary(req, path, codec).await
+        }
+        pub async fn restore_itinerary(
+            &mut self,
+            request: impl tonic::IntoRequest<super::RestoreItineraryRequest>,
+        ) -> std::result::Result<tonic::Response<super::TaskResponse>, tonic::Status> {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::new(
+                        tonic::Code::Unknown,
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static(
+                "/grpc.RpcService/restoreItinerary",
+            );
+            let mut req = request.into_request();
+            req.extensions_mut()
+                .insert(GrpcMethod::new("grpc.RpcService", "restoreItinerary"));
+            self.inner.unary(req, path, codec).await
+        }
         pub async fn cancel_task(
             &mut self,
             request: impl tonic::IntoRequest<super::TaskRequest>,
@@ -464,6 +1477,231 @@ pub mod rpc_service_client {
                 .insert(GrpcMethod::new("grpc.RpcService", "getTaskStatus"));
             self.inner.unary(req, path, codec).await
         }
+        pub async fn list_queued_tasks(
+            &mut self,
+            request: impl tonic::IntoRequest<super::ListQueuedTasksRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::ListQueuedTasksResponse>,
+            tonic::Status,
+        > {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::new(
+                        tonic::Code::Unknown,
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static(
+                "/grpc.RpcService/listQueuedTasks",
+            );
+            let mut req = request.into_request();
+            req.extensions_mut()
+                .insert(GrpcMethod::new("grpc.RpcService", "listQueuedTasks"));
+            self.inner.unary(req, path, codec).await
+        }
+        pub async fn requeue_task(
+            &mut self,
+            request: impl tonic::IntoRequest<super::RequeueTaskRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::TaskResponse>,
+            tonic::Status,
+        > {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::new(
+                        tonic::Code::Unknown,
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static(
+                "/grpc.RpcService/requeueTask",
+            );
+            let mut req = request.into_request();
+            req.extensions_mut()
+                .insert(GrpcMethod::new("grpc.RpcService", "requeueTask"));
+            self.inner.unary(req, path, codec).await
+        }
+        pub async fn get_fleet_status(
+            &mut self,
+            request: impl tonic::IntoRequest<super::GetFleetStatusRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::GetFleetStatusResponse>,
+            tonic::Status,
+        > {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::new(
+                        tonic::Code::Unknown,
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static(
+                "/grpc.RpcService/getFleetStatus",
+            );
+            let mut req = request.into_request();
+            req.extensions_mut()
+                .insert(GrpcMethod::new("grpc.RpcService", "getFleetStatus"));
+            self.inner.unary(req, path, codec).await
+        }
+        pub async fn validate_schedule(
+            &mut self,
+            request: impl tonic::IntoRequest<super::ValidateScheduleRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::ValidateScheduleResponse>,
+            tonic::Status,
+        > {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::new(
+                        tonic::Code::Unknown,
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static(
+                "/grpc.RpcService/validateSchedule",
+            );
+            let mut req = request.into_request();
+            req.extensions_mut()
+                .insert(GrpcMethod::new("grpc.RpcService", "validateSchedule"));
+            self.inner.unary(req, path, codec).await
+        }
+        pub async fn estimate_deadheads(
+            &mut self,
+            request: impl tonic::IntoRequest<super::EstimateDeadheadsRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::EstimateDeadheadsResponse>,
+            tonic::Status,
+        > {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::new(
+                        tonic::Code::Unknown,
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static(
+                "/grpc.RpcService/estimateDeadheads",
+            );
+            let mut req = request.into_request();
+            req.extensions_mut()
+                .insert(GrpcMethod::new("grpc.RpcService", "estimateDeadheads"));
+            self.inner.unary(req, path, codec).await
+        }
+        pub async fn reachable_vertiports(
+            &mut self,
+            request: impl tonic::IntoRequest<super::ReachableVertiportsRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::ReachableVertiportsResponse>,
+            tonic::Status,
+        > {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::new(
+                        tonic::Code::Unknown,
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static(
+                "/grpc.RpcService/reachableVertiports",
+            );
+            let mut req = request.into_request();
+            req.extensions_mut()
+                .insert(GrpcMethod::new("grpc.RpcService", "reachableVertiports"));
+            self.inner.unary(req, path, codec).await
+        }
+        pub async fn vertipad_occupancy(
+            &mut self,
+            request: impl tonic::IntoRequest<super::VertipadOccupancyRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::VertipadOccupancyResponse>,
+            tonic::Status,
+        > {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::new(
+                        tonic::Code::Unknown,
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static(
+                "/grpc.RpcService/vertipadOccupancy",
+            );
+            let mut req = request.into_request();
+            req.extensions_mut()
+                .insert(GrpcMethod::new("grpc.RpcService", "vertipadOccupancy"));
+            self.inner.unary(req, path, codec).await
+        }
+        pub async fn cancel_user_drafts(
+            &mut self,
+            request: impl tonic::IntoRequest<super::CancelUserDraftsRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::CancelUserDraftsResponse>,
+            tonic::Status,
+        > {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::new(
+                        tonic::Code::Unknown,
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static(
+                "/grpc.RpcService/cancelUserDrafts",
+            );
+            let mut req = request.into_request();
+            req.extensions_mut()
+                .insert(GrpcMethod::new("grpc.RpcService", "cancelUserDrafts"));
+            self.inner.unary(req, path, codec).await
+        }
+        pub async fn list_user_itineraries(
+            &mut self,
+            request: impl tonic::IntoRequest<super::ListUserItinerariesRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::ListUserItinerariesResponse>,
+            tonic::Status,
+        > {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::new(
+                        tonic::Code::Unknown,
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static(
+                "/grpc.RpcService/listUserItineraries",
+            );
+            let mut req = request.into_request();
+            req.extensions_mut()
+                .insert(GrpcMethod::new("grpc.RpcService", "listUserItineraries"));
+            self.inner.unary(req, path, codec).await
+        }
         pub async fn is_ready(
             &mut self,
             request: impl tonic::IntoRequest<super::ReadyRequest>,
@@ -58,6 +58,36 @@ where
         request: super::CancelItineraryRequest,
     ) -> Result<tonic::Response<super::TaskResponse>, tonic::Status>;
 
+    /// wrapper
+    async fn cancel_vehicle_itineraries(
+        &self,
+        request: super::CancelVehicleItinerariesRequest,
+    ) -> Result<tonic::Response<super::CancelVehicleItinerariesResponse>, tonic::Status>;
+
+    /// wrapper
+    async fn suggest_repositioning(
+        &self,
+        request: super::SuggestRepositioningRequest,
+    ) -> Result<tonic::Response<super::SuggestRepositioningResponse>, tonic::Status>;
+
+    /// wrapper
+    async fn check_flight_plan(
+        &self,
+        request: super::CheckFlightPlanRequest,
+    ) -> Result<tonic::Response<super::CheckFlightPlanResponse>, tonic::Status>;
+
+    /// wrapper
+    async fn get_itinerary(
+        &self,
+        request: super::GetItineraryRequest,
+    ) -> Result<tonic::Response<super::Itinerary>, tonic::Status>;
+
+    /// wrapper
+    async fn restore_itinerary(
+        &self,
+        request: super::RestoreItineraryRequest,
+    ) -> Result<tonic::Response<super::TaskResponse>, tonic::Status>;
+
     /// wrapper
     async fn cancel_task(
         &self,
@@ -69,4 +99,58 @@ where
         &self,
         request: super::TaskRequest,
     ) -> Result<tonic::Response<super::TaskResponse>, tonic::Status>;
+
+    /// wrapper
+    async fn list_queued_tasks(
+        &self,
+        request: super::ListQueuedTasksRequest,
+    ) -> Result<tonic::Response<super::ListQueuedTasksResponse>, tonic::Status>;
+
+    /// wrapper
+    async fn requeue_task(
+        &self,
+        request: super::RequeueTaskRequest,
+    ) -> Result<tonic::Response<super::TaskResponse>, tonic::Status>;
+
+    /// wrapper
+    async fn get_fleet_status(
+        &self,
+        request: super::GetFleetStatusRequest,
+    ) -> Result<tonic::Response<super::GetFleetStatusResponse>, tonic::Status>;
+
+    /// wrapper
+    async fn validate_schedule(
+        &self,
+        request: super::ValidateScheduleRequest,
+    ) -> Result<tonic::Response<super::ValidateScheduleResponse>, tonic::Status>;
+
+    /// wrapper
+    async fn estimate_deadheads(
+        &self,
+        request: super::EstimateDeadheadsRequest,
+    ) -> Result<tonic::Response<super::EstimateDeadheadsResponse>, tonic::Status>;
+
+    /// wrapper
+    async fn reachable_vertiports(
+        &self,
+        request: super::ReachableVertiportsRequest,
+    ) -> Result<tonic::Response<super::ReachableVertiportsResponse>, tonic::Status>;
+
+    /// wrapper
+    async fn vertipad_occupancy(
+        &self,
+        request: super::VertipadOccupancyRequest,
+    ) -> Result<tonic::Response<super::VertipadOccupancyResponse>, tonic::Status>;
+
+    /// wrapper
+    async fn cancel_user_drafts(
+        &self,
+        request: super::CancelUserDraftsRequest,
+    ) -> Result<tonic::Response<super::CancelUserDraftsResponse>, tonic::Status>;
+
+    /// wrapper
+    async fn list_user_itineraries(
+        &self,
+        request: super::ListUserItinerariesRequest,
+    ) -> Result<tonic::Response<super::ListUserItinerariesResponse>, tonic::Status>;
 }
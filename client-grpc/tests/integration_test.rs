@@ -20,6 +20,21 @@ async fn test_flights_query() -> Result<(), Box<dyn std::error::Error>> {
         origin_vertiport_id: Uuid::new_v4().to_string(),
         target_vertiport_id: Uuid::new_v4().to_string(),
         priority: FlightPriority::Low.into(),
+        waitlist_user_id: None,
+        excluded_vertiport_ids: vec![],
+        loop_waypoints: vec![],
+        aircraft_type: None,
+        emergency: false,
+        required_arrival_precision_seconds: None,
+        auto_widen: false,
+        ranking: None,
+        include_local_times: false,
+        preferred_departure_time: None,
+        origin_type: None,
+        target_type: None,
+        max_total_travel_seconds: None,
+        min_results: None,
+        cargo_hazard_class: None,
     };
 
     let response = client.query_flight(request).await?;
@@ -36,6 +51,7 @@ async fn test_cancel_itinerary() -> Result<(), Box<dyn std::error::Error>> {
         priority: FlightPriority::Low.into(),
         itinerary_id: Uuid::new_v4().to_string(),
         user_id: Uuid::new_v4().to_string(),
+        cancellation_kind: CancellationKind::Hard.into(),
     };
 
     let response = client.cancel_itinerary(request.clone()).await?.into_inner();
@@ -59,6 +75,7 @@ async fn test_create_itinerary() -> Result<(), Box<dyn std::error::Error>> {
             nanos: 0,
         }),
         user_id: Uuid::new_v4().to_string(),
+        return_flight_plans: vec![],
     };
 
     let response = client.create_itinerary(request.clone()).await?.into_inner();
@@ -19,19 +19,22 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
             ".grpc.FlightPriority",
             "::svc_storage_client_grpc::prelude::flight_plan::FlightPriority",
         )
+        .type_attribute("AircraftType", "#[derive(num_derive::FromPrimitive)]")
+        .type_attribute("CancellationKind", "#[derive(num_derive::FromPrimitive)]")
+        .type_attribute("Ranking", "#[derive(num_derive::FromPrimitive)]")
         .type_attribute("TaskAction", "#[derive(num_derive::FromPrimitive)]")
         .type_attribute("TaskStatus", "#[derive(num_derive::FromPrimitive)]")
         .type_attribute(
             "TaskMetadata",
             "#[derive(serde::Serialize, serde::Deserialize, Eq)]",
         )
+        .field_attribute("TaskMetadata.fallback_vertipad_ids", "#[serde(default)]")
         .type_attribute("ConfirmFlightResponse", "#[derive(Eq)]")
         .type_attribute("CancelFlightResponse", "#[derive(Eq)]")
         .type_attribute("TaskRequest", "#[derive(Eq, Copy)]")
         .type_attribute("TaskResponse", "#[derive(Eq)]")
         .type_attribute("ReadyRequest", "#[derive(Eq, Copy)]")
-        .type_attribute("ReadyResponse", "#[derive(Eq, Copy)]")
-        .type_attribute("QueryFlightRequest", "#[derive(Eq)]");
+        .type_attribute("ReadyResponse", "#[derive(Eq, Copy)]");
 
     let client_config = server_config.clone();
 
@@ -0,0 +1,91 @@
+//! Source of the current time
+//! Time-dependent logic asks a [`Clock`] for the current time instead
+//!  of the system, so that tests can stop the clock at a known time.
+
+use lib_common::time::{DateTime, Utc};
+
+/// Provides the current time
+pub trait Clock: Send + Sync {
+    /// Returns the current time
+    fn now(&self) -> DateTime<Utc>;
+}
+
+/// The system clock
+#[derive(Debug, Copy, Clone, Default)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> DateTime<Utc> {
+        Utc::now()
+    }
+}
+
+#[cfg(test)]
+thread_local! {
+    /// Clock returned by [`get_clock`] on this thread, if set by a test
+    static TEST_CLOCK: std::cell::Cell<Option<&'static dyn Clock>> =
+        const { std::cell::Cell::new(None) };
+}
+
+/// Returns the clock used by the service.
+/// Tests can stop it with [`set_test_clock`].
+pub fn get_clock() -> &'static dyn Clock {
+    #[cfg(test)]
+    if let Some(clock) = TEST_CLOCK.with(|clock| clock.get()) {
+        return clock;
+    }
+
+    &SystemClock
+}
+
+/// Makes [`get_clock`] return `clock` on the current thread, or the
+///  system clock again if `None`. Tests run on their own threads, so
+///  they don't see each other's clocks.
+#[cfg(test)]
+pub fn set_test_clock(clock: Option<&'static dyn Clock>) {
+    TEST_CLOCK.with(|test_clock| test_clock.set(clock));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::MockClock;
+    use lib_common::time::Duration;
+
+    #[test]
+    fn test_system_clock() {
+        let before = Utc::now();
+        let now = get_clock().now();
+        assert!(now >= before);
+        assert!(now <= Utc::now());
+    }
+
+    #[test]
+    fn test_mock_clock() {
+        let start = DateTime::<Utc>::from_timestamp(1_700_000_000, 0).unwrap();
+        let clock = MockClock::new(start);
+        assert_eq!(clock.now(), start);
+
+        // stopped until moved by the test
+        assert_eq!(clock.now(), start);
+        clock.advance(Duration::try_minutes(5).unwrap());
+        assert_eq!(clock.now(), start + Duration::try_minutes(5).unwrap());
+    }
+
+    #[test]
+    fn test_set_test_clock() {
+        let start = DateTime::<Utc>::from_timestamp(1_700_000_000, 0).unwrap();
+        let clock = crate::test_util::set_mock_clock(start);
+        assert_eq!(get_clock().now(), start);
+
+        clock.advance(Duration::try_minutes(5).unwrap());
+        assert_eq!(get_clock().now(), start + Duration::try_minutes(5).unwrap());
+
+        // other threads keep the system clock
+        let now = std::thread::spawn(|| get_clock().now()).join().unwrap();
+        assert!(now > start + Duration::try_days(365).unwrap());
+
+        set_test_clock(None);
+        assert!(get_clock().now() > start + Duration::try_days(365).unwrap());
+    }
+}
@@ -6,6 +6,19 @@ use anyhow::Result;
 use config::{ConfigError, Environment};
 use dotenv::dotenv;
 use serde::Deserialize;
+use tokio::sync::OnceCell;
+
+/// Global configuration, loaded once from the environment
+static CONFIG: OnceCell<Config> = OnceCell::const_new();
+
+/// Returns CONFIG, a Config object generated from environment variables.
+/// Falls back to default values if the environment can not be parsed.
+/// Initializes CONFIG if it hasn't been initialized yet.
+pub async fn get_config() -> &'static Config {
+    CONFIG
+        .get_or_init(|| async move { Config::try_from_env().unwrap_or_default() })
+        .await
+}
 
 /// struct holding configuration options
 #[derive(Debug, Deserialize, Clone)]
@@ -30,6 +43,267 @@ pub struct Config {
 
     /// config to be used for the Redis server
     pub redis: deadpool_redis::Config,
+
+    /// aircraft landing within this many minutes of a connection are
+    ///  treated as already parked at the vertiport
+    pub connection_grace_minutes: u32,
+
+    /// allow scheduling deadhead flights to reposition aircraft
+    ///  before and after a requested flight
+    pub enable_deadhead: bool,
+
+    /// number of departure times to try for a deadhead flight
+    ///  when no path is found
+    pub deadhead_search_attempts: u32,
+
+    /// minutes between deadhead departure attempts
+    pub deadhead_search_step_minutes: u32,
+
+    /// maximum number of waitlisted queries kept before the
+    ///  oldest are evicted (0 for no limit)
+    pub max_waitlist_entries: u32,
+
+    /// maximum seconds a flight may leave after the earliest possible
+    ///  time, used to spread out arrivals (0 to disable)
+    pub arrival_jitter_max_seconds: u32,
+
+    /// maximum number of routes requested from svc-gis for a flight,
+    ///  routes other than the shortest are returned as alternatives
+    pub max_routes: u32,
+
+    /// number of results requested per page when loading lists
+    ///  from svc-storage, all pages are loaded
+    pub storage_page_size: u32,
+
+    /// maximum number of svc-storage calls in flight at once,
+    ///  further calls wait for a slot (0 to disable)
+    pub storage_concurrency_limit: u32,
+
+    /// network-wide blackout periods during which no flights can be
+    ///  scheduled, as comma-separated RFC3339 `start/end` intervals
+    pub blackout_periods: String,
+
+    /// minutes a soft-cancelled itinerary can be restored
+    ///  by the user that cancelled it
+    pub soft_cancel_grace_minutes: u32,
+
+    /// bearer tokens of users, as comma-separated `token:user_id` pairs.
+    ///  Authentication is disabled if no user or operator tokens are set
+    pub user_tokens: String,
+
+    /// bearer tokens of operators, comma-separated. Operators may act
+    ///  on behalf of any user
+    pub operator_tokens: String,
+
+    /// operating-hours overrides that take precedence over vertiport
+    ///  schedules, as comma-separated `vertiport_id|open|start/end` or
+    ///  `vertiport_id|closed|start/end` entries with RFC3339 times
+    pub vertiport_hours_overrides: String,
+
+    /// widest window in minutes that an `auto_widen` flight query may
+    ///  grow to, measured from the earliest departure time
+    pub auto_widen_horizon_minutes: u32,
+
+    /// time-of-use cost multipliers used to rank itineraries by lowest
+    ///  cost, as comma-separated UTC `HH:MM-HH:MM=multiplier` windows.
+    ///  Times outside every window cost 1.0
+    pub pricing_schedule: String,
+
+    /// price quoted per kilometer flown, in the smallest currency unit
+    pub price_per_km: u32,
+
+    /// price quoted per minute of flight time, in the smallest currency unit
+    pub price_per_minute: u32,
+
+    /// landing fee added to each quoted flight, in the smallest currency unit
+    pub landing_fee: u32,
+
+    /// routes shorter than this many meters (e.g. hops between adjacent
+    ///  pads) are not flown and no itineraries are returned for them
+    ///  (0 to disable)
+    pub min_flight_distance_meters: u32,
+
+    /// local timezones of vertiports, as comma-separated
+    ///  `vertiport_id|timezone` entries with IANA timezone names.
+    ///  Only used to present local times, vertiports default to UTC
+    pub vertiport_timezones: String,
+
+    /// maximum number of tasks waiting in the task queues. New tasks are
+    ///  rejected with RESOURCE_EXHAUSTED once reached (0 to disable)
+    pub max_queue_depth: u32,
+
+    /// absolute safety margin added to estimated flight times, in seconds
+    pub flight_time_margin_seconds: u32,
+
+    /// relative safety margin added to estimated flight times, in percent
+    ///  of the estimate (applied before the absolute margin)
+    pub flight_time_margin_percent: u32,
+
+    /// schedule flights on disabled vertipads too, for diagnostics.
+    ///  Soft-deleted vertipads are never scheduled
+    pub include_disabled_vertipads: bool,
+
+    /// minutes a return itinerary is held for a user booking an outbound
+    ///  itinerary, unless the return departs sooner (0 to disable)
+    pub return_hold_minutes: u32,
+
+    /// maximum origin and target timeslot combinations checked for
+    ///  a route between two vertiports (0 for no limit)
+    pub max_timeslot_pair_checks: u32,
+
+    /// stop checking timeslot combinations between two vertiports once
+    ///  this many flights are found (0 for no limit)
+    pub max_timeslot_pairs: u32,
+
+    /// hours before now that existing flight plans are loaded from, by
+    ///  arrival time. Flights still in the air are always loaded (0 for no limit)
+    pub flight_plan_lookback_hours: u32,
+
+    /// seconds after the requested expiry that an itinerary can still be
+    ///  created, its flight plans are checked again when the task runs
+    pub create_expiry_grace_seconds: u32,
+
+    /// adverse weather cells routed around while active, as comma-separated
+    ///  `start/end|lat lon;lat lon;...` RFC3339 intervals and polygons
+    pub weather_cells: String,
+
+    /// attempts to read svc-storage at startup before the service is
+    ///  reported as not serving, after which it is read again at the
+    ///  longest backoff until it recovers
+    pub storage_retry_attempts: u32,
+
+    /// milliseconds to wait after the first failed svc-storage read at
+    ///  startup, doubled after each further failure
+    pub storage_retry_backoff_ms: u32,
+
+    /// shortest departure window in seconds offered for a flight, tighter
+    ///  pairs of origin and target timeslots are discarded (0 for no minimum)
+    pub min_origin_window_seconds: u32,
+
+    /// lowest altitude in meters flown at cruise, clamping the preferred
+    ///  cruise altitude of each aircraft to keep clear of terrain and obstacles
+    pub min_cruise_altitude_meters: u32,
+
+    /// where booking and cancellation audit records are written:
+    ///  "redis" appends to the scheduler:audit stream, "log" to the service log
+    pub audit_sink: String,
+
+    /// most deadhead flights an itinerary may include, queries needing
+    ///  more are rejected (0 for no limit)
+    pub max_deadhead_legs: u32,
+
+    /// skip flight plans loaded from storage that duplicate another one
+    ///  (same aircraft and times), so that they aren't counted twice
+    pub deduplicate_flight_plans: bool,
+
+    /// wall-clock budget of a flight query in milliseconds, the itineraries
+    ///  found when it runs out are returned as partial results (0 for no limit)
+    pub query_budget_ms: u32,
+
+    /// how vehicles with a missing or invalid schedule are handled:
+    ///  "strict" fails the request, "lenient" skips the vehicle with a warning
+    pub invalid_schedule_policy: String,
+
+    /// maximum number of aircraft considered by a flight query, those
+    ///  best positioned for it are kept (0 for no limit)
+    pub max_aircraft_per_query: u32,
+
+    /// reject itineraries whose first flight departs sooner than
+    ///  `departure_lead_seconds` from now, including deadhead flights
+    pub strict_departure_lead: bool,
+
+    /// minimum time in seconds between booking and the first departure
+    ///  of an itinerary, only enforced with `strict_departure_lead`
+    pub departure_lead_seconds: u32,
+
+    /// advance notice vertiports need before a flight departs from or
+    ///  lands at them, as comma-separated `vertiport_id|seconds` entries
+    pub vertiport_lead_times: String,
+
+    /// advance notice in seconds of vertiports without a lead time
+    ///  in `vertiport_lead_times`
+    pub default_vertiport_lead_seconds: u32,
+
+    /// vertiports within this many meters of an unavailable destination
+    ///  are suggested instead (0 to disable)
+    pub alternative_destination_radius_meters: u32,
+
+    /// initialize the router clients on startup instead of on the
+    ///  first query
+    pub warmup_on_startup: bool,
+
+    /// also look up the distances between all vertiports while
+    ///  warming up, caching them for the queries
+    pub warmup_route_distances: bool,
+
+    /// plan straight-line paths between vertiports while svc-gis is
+    ///  unavailable, flagging the itineraries using them
+    pub path_fallback: bool,
+
+    /// length of a straight-line fallback path, as a percentage of
+    ///  the distance between the vertiports
+    pub path_fallback_distance_percent: u32,
+
+    /// recurring vertipad slots reserved for scheduled services, as
+    ///  whitespace-separated `vertipad_id|calendar` entries with the
+    ///  lines of the RRULE calendar separated by `|`
+    pub vertipad_reservations: String,
+
+    /// count the itineraries queued to be created as occupying their
+    ///  vertipads when searching for flights
+    pub draft_occupancy: bool,
+
+    /// what to do when no deadhead flight can take the aircraft back
+    ///  after a flight: "strict" rejects the itinerary, "park" leaves
+    ///  the aircraft at the destination if it isn't needed afterwards
+    ///  and its vertipad stays free
+    pub return_deadhead_policy: String,
+
+    /// hazard classes of cargo barred from vertiports or corridors, as
+    ///  comma-separated `node=class|class` entries where the node is a
+    ///  vertiport ID or two vertiport IDs separated by `~`
+    pub hazard_restrictions: String,
+
+    /// width in meters of a restricted corridor, centered on the straight
+    ///  line between its vertiports
+    pub hazard_corridor_width_meters: u32,
+
+    /// largest random delay in seconds added to the expiry of return
+    ///  holds and soft cancellations, so that those recorded in a burst
+    ///  don't all expire at once (0 to disable)
+    pub expiry_jitter_seconds: u32,
+
+    /// which vertipads of a route are offered first: "shortest_path"
+    ///  prefers pads with the shortest route, "earliest_availability"
+    ///  the pads free soonest
+    pub pad_selection: String,
+
+    /// most return holds a user may have at once; the user's holds
+    ///  expiring soonest are released to make room (0 for no limit)
+    pub max_holds_per_user: u32,
+
+    /// most return holds kept for all users together; the holds
+    ///  expiring soonest are released to make room (0 for no limit)
+    pub max_return_holds: u32,
+
+    /// routes whose lengths differ by less than this many millimeters
+    ///  are ranked as equally short (0 to compare exact lengths)
+    pub distance_tolerance_millimeters: u32,
+
+    /// percentage of the scheduled flight time by which half of flights
+    ///  land, for the arrival estimates of itineraries
+    pub eta_p50_percent: u32,
+
+    /// percentage of the scheduled flight time by which nine in ten
+    ///  flights land, greater than `eta_p50_percent`
+    pub eta_p90_percent: u32,
+
+    /// vehicle models, as comma-separated
+    ///  `model_id|name|type|loading_seconds|unloading_seconds|max_payload_grams`
+    ///  entries with `cargo` or `passenger` types. The model ID `*`
+    ///  describes every model not listed; without it, vehicles of
+    ///  unlisted models are not scheduled
+    pub vehicle_models: String,
 }
 
 impl Default for Config {
@@ -54,6 +328,69 @@ impl Config {
                 pool: None,
                 connection: None,
             },
+            connection_grace_minutes: 2,
+            enable_deadhead: true,
+            deadhead_search_attempts: 6,
+            deadhead_search_step_minutes: 10,
+            max_waitlist_entries: 1000,
+            arrival_jitter_max_seconds: 0,
+            max_routes: 5,
+            storage_page_size: 1000,
+            storage_concurrency_limit: 32,
+            blackout_periods: String::new(),
+            soft_cancel_grace_minutes: 15,
+            user_tokens: String::new(),
+            operator_tokens: String::new(),
+            vertiport_hours_overrides: String::new(),
+            auto_widen_horizon_minutes: 360,
+            pricing_schedule: String::new(),
+            price_per_km: 0,
+            price_per_minute: 0,
+            landing_fee: 0,
+            min_flight_distance_meters: 0,
+            vertiport_timezones: String::new(),
+            max_queue_depth: 10000,
+            flight_time_margin_seconds: 0,
+            flight_time_margin_percent: 0,
+            include_disabled_vertipads: false,
+            return_hold_minutes: 15,
+            max_timeslot_pair_checks: 0,
+            max_timeslot_pairs: 0,
+            flight_plan_lookback_hours: 24,
+            create_expiry_grace_seconds: 5,
+            weather_cells: String::new(),
+            storage_retry_attempts: 5,
+            storage_retry_backoff_ms: 500,
+            min_origin_window_seconds: 0,
+            min_cruise_altitude_meters: 50,
+            audit_sink: String::from("redis"),
+            max_deadhead_legs: 2,
+            deduplicate_flight_plans: true,
+            query_budget_ms: 10000,
+            invalid_schedule_policy: String::from("lenient"),
+            max_aircraft_per_query: 0,
+            strict_departure_lead: false,
+            departure_lead_seconds: 180,
+            vertiport_lead_times: String::new(),
+            default_vertiport_lead_seconds: 0,
+            alternative_destination_radius_meters: 10000,
+            warmup_on_startup: false,
+            warmup_route_distances: false,
+            path_fallback: false,
+            path_fallback_distance_percent: 150,
+            vertipad_reservations: String::new(),
+            draft_occupancy: false,
+            return_deadhead_policy: String::from("strict"),
+            hazard_restrictions: String::new(),
+            hazard_corridor_width_meters: 500,
+            expiry_jitter_seconds: 30,
+            pad_selection: String::from("shortest_path"),
+            max_holds_per_user: 0,
+            max_return_holds: 1000,
+            distance_tolerance_millimeters: 1,
+            eta_p50_percent: 90,
+            eta_p90_percent: 100,
+            vehicle_models: String::from("*|Cargo aircraft|cargo|60|60|0"),
         }
     }
 
@@ -70,6 +407,168 @@ impl Config {
             .set_default("gis_port_grpc", default_config.gis_port_grpc)?
             .set_default("gis_host_grpc", default_config.gis_host_grpc)?
             .set_default("log_config", default_config.log_config)?
+            .set_default(
+                "connection_grace_minutes",
+                default_config.connection_grace_minutes,
+            )?
+            .set_default("enable_deadhead", default_config.enable_deadhead)?
+            .set_default(
+                "deadhead_search_attempts",
+                default_config.deadhead_search_attempts,
+            )?
+            .set_default(
+                "deadhead_search_step_minutes",
+                default_config.deadhead_search_step_minutes,
+            )?
+            .set_default("max_waitlist_entries", default_config.max_waitlist_entries)?
+            .set_default(
+                "arrival_jitter_max_seconds",
+                default_config.arrival_jitter_max_seconds,
+            )?
+            .set_default("max_routes", default_config.max_routes)?
+            .set_default("storage_page_size", default_config.storage_page_size)?
+            .set_default(
+                "storage_concurrency_limit",
+                default_config.storage_concurrency_limit,
+            )?
+            .set_default("blackout_periods", default_config.blackout_periods)?
+            .set_default(
+                "soft_cancel_grace_minutes",
+                default_config.soft_cancel_grace_minutes,
+            )?
+            .set_default("user_tokens", default_config.user_tokens)?
+            .set_default("operator_tokens", default_config.operator_tokens)?
+            .set_default(
+                "vertiport_hours_overrides",
+                default_config.vertiport_hours_overrides,
+            )?
+            .set_default(
+                "auto_widen_horizon_minutes",
+                default_config.auto_widen_horizon_minutes,
+            )?
+            .set_default("pricing_schedule", default_config.pricing_schedule)?
+            .set_default("price_per_km", default_config.price_per_km)?
+            .set_default("price_per_minute", default_config.price_per_minute)?
+            .set_default("landing_fee", default_config.landing_fee)?
+            .set_default(
+                "min_flight_distance_meters",
+                default_config.min_flight_distance_meters,
+            )?
+            .set_default("vertiport_timezones", default_config.vertiport_timezones)?
+            .set_default("max_queue_depth", default_config.max_queue_depth)?
+            .set_default(
+                "flight_time_margin_seconds",
+                default_config.flight_time_margin_seconds,
+            )?
+            .set_default(
+                "flight_time_margin_percent",
+                default_config.flight_time_margin_percent,
+            )?
+            .set_default(
+                "include_disabled_vertipads",
+                default_config.include_disabled_vertipads,
+            )?
+            .set_default("return_hold_minutes", default_config.return_hold_minutes)?
+            .set_default(
+                "max_timeslot_pair_checks",
+                default_config.max_timeslot_pair_checks,
+            )?
+            .set_default("max_timeslot_pairs", default_config.max_timeslot_pairs)?
+            .set_default(
+                "flight_plan_lookback_hours",
+                default_config.flight_plan_lookback_hours,
+            )?
+            .set_default(
+                "create_expiry_grace_seconds",
+                default_config.create_expiry_grace_seconds,
+            )?
+            .set_default("weather_cells", default_config.weather_cells)?
+            .set_default(
+                "storage_retry_attempts",
+                default_config.storage_retry_attempts,
+            )?
+            .set_default(
+                "storage_retry_backoff_ms",
+                default_config.storage_retry_backoff_ms,
+            )?
+            .set_default(
+                "min_origin_window_seconds",
+                default_config.min_origin_window_seconds,
+            )?
+            .set_default(
+                "min_cruise_altitude_meters",
+                default_config.min_cruise_altitude_meters,
+            )?
+            .set_default("audit_sink", default_config.audit_sink)?
+            .set_default("max_deadhead_legs", default_config.max_deadhead_legs)?
+            .set_default(
+                "deduplicate_flight_plans",
+                default_config.deduplicate_flight_plans,
+            )?
+            .set_default("query_budget_ms", default_config.query_budget_ms)?
+            .set_default(
+                "invalid_schedule_policy",
+                default_config.invalid_schedule_policy,
+            )?
+            .set_default(
+                "max_aircraft_per_query",
+                default_config.max_aircraft_per_query,
+            )?
+            .set_default(
+                "strict_departure_lead",
+                default_config.strict_departure_lead,
+            )?
+            .set_default(
+                "departure_lead_seconds",
+                default_config.departure_lead_seconds,
+            )?
+            .set_default("vertiport_lead_times", default_config.vertiport_lead_times)?
+            .set_default(
+                "default_vertiport_lead_seconds",
+                default_config.default_vertiport_lead_seconds,
+            )?
+            .set_default(
+                "alternative_destination_radius_meters",
+                default_config.alternative_destination_radius_meters,
+            )?
+            .set_default("warmup_on_startup", default_config.warmup_on_startup)?
+            .set_default(
+                "warmup_route_distances",
+                default_config.warmup_route_distances,
+            )?
+            .set_default("path_fallback", default_config.path_fallback)?
+            .set_default(
+                "path_fallback_distance_percent",
+                default_config.path_fallback_distance_percent,
+            )?
+            .set_default(
+                "vertipad_reservations",
+                default_config.vertipad_reservations,
+            )?
+            .set_default("draft_occupancy", default_config.draft_occupancy)?
+            .set_default(
+                "return_deadhead_policy",
+                default_config.return_deadhead_policy,
+            )?
+            .set_default("hazard_restrictions", default_config.hazard_restrictions)?
+            .set_default(
+                "hazard_corridor_width_meters",
+                default_config.hazard_corridor_width_meters,
+            )?
+            .set_default(
+                "expiry_jitter_seconds",
+                default_config.expiry_jitter_seconds,
+            )?
+            .set_default("pad_selection", default_config.pad_selection)?
+            .set_default("max_holds_per_user", default_config.max_holds_per_user)?
+            .set_default("max_return_holds", default_config.max_return_holds)?
+            .set_default(
+                "distance_tolerance_millimeters",
+                default_config.distance_tolerance_millimeters,
+            )?
+            .set_default("eta_p50_percent", default_config.eta_p50_percent)?
+            .set_default("eta_p90_percent", default_config.eta_p90_percent)?
+            .set_default("vehicle_models", default_config.vehicle_models)?
             .add_source(Environment::default().separator("__"))
             .build()?
             .try_deserialize()
@@ -96,6 +595,69 @@ mod tests {
         assert!(config.redis.url.is_none());
         assert!(config.redis.pool.is_none());
         assert!(config.redis.connection.is_none());
+        assert_eq!(config.connection_grace_minutes, 2);
+        assert!(config.enable_deadhead);
+        assert_eq!(config.deadhead_search_attempts, 6);
+        assert_eq!(config.deadhead_search_step_minutes, 10);
+        assert_eq!(config.max_waitlist_entries, 1000);
+        assert_eq!(config.arrival_jitter_max_seconds, 0);
+        assert_eq!(config.max_routes, 5);
+        assert_eq!(config.storage_page_size, 1000);
+        assert_eq!(config.storage_concurrency_limit, 32);
+        assert!(config.blackout_periods.is_empty());
+        assert_eq!(config.soft_cancel_grace_minutes, 15);
+        assert!(config.user_tokens.is_empty());
+        assert!(config.operator_tokens.is_empty());
+        assert!(config.vertiport_hours_overrides.is_empty());
+        assert_eq!(config.auto_widen_horizon_minutes, 360);
+        assert!(config.pricing_schedule.is_empty());
+        assert_eq!(config.price_per_km, 0);
+        assert_eq!(config.price_per_minute, 0);
+        assert_eq!(config.landing_fee, 0);
+        assert_eq!(config.min_flight_distance_meters, 0);
+        assert!(config.vertiport_timezones.is_empty());
+        assert_eq!(config.max_queue_depth, 10000);
+        assert_eq!(config.flight_time_margin_seconds, 0);
+        assert_eq!(config.flight_time_margin_percent, 0);
+        assert!(!config.include_disabled_vertipads);
+        assert_eq!(config.return_hold_minutes, 15);
+        assert_eq!(config.max_timeslot_pair_checks, 0);
+        assert_eq!(config.max_timeslot_pairs, 0);
+        assert_eq!(config.flight_plan_lookback_hours, 24);
+        assert_eq!(config.create_expiry_grace_seconds, 5);
+        assert!(config.weather_cells.is_empty());
+        assert_eq!(config.storage_retry_attempts, 5);
+        assert_eq!(config.storage_retry_backoff_ms, 500);
+        assert_eq!(config.min_origin_window_seconds, 0);
+        assert_eq!(config.min_cruise_altitude_meters, 50);
+        assert_eq!(config.audit_sink, "redis");
+        assert_eq!(config.max_deadhead_legs, 2);
+        assert!(config.deduplicate_flight_plans);
+        assert_eq!(config.query_budget_ms, 10000);
+        assert_eq!(config.invalid_schedule_policy, "lenient");
+        assert_eq!(config.max_aircraft_per_query, 0);
+        assert!(!config.strict_departure_lead);
+        assert_eq!(config.departure_lead_seconds, 180);
+        assert!(config.vertiport_lead_times.is_empty());
+        assert_eq!(config.default_vertiport_lead_seconds, 0);
+        assert_eq!(config.alternative_destination_radius_meters, 10000);
+        assert!(!config.warmup_on_startup);
+        assert!(!config.warmup_route_distances);
+        assert!(!config.path_fallback);
+        assert_eq!(config.path_fallback_distance_percent, 150);
+        assert!(config.vertipad_reservations.is_empty());
+        assert!(!config.draft_occupancy);
+        assert_eq!(config.return_deadhead_policy, "strict");
+        assert!(config.hazard_restrictions.is_empty());
+        assert_eq!(config.hazard_corridor_width_meters, 500);
+        assert_eq!(config.expiry_jitter_seconds, 30);
+        assert_eq!(config.pad_selection, "shortest_path");
+        assert_eq!(config.max_holds_per_user, 0);
+        assert_eq!(config.max_return_holds, 1000);
+        assert_eq!(config.distance_tolerance_millimeters, 1);
+        assert_eq!(config.eta_p50_percent, 90);
+        assert_eq!(config.eta_p90_percent, 100);
+        assert_eq!(config.vehicle_models, "*|Cargo aircraft|cargo|60|60|0");
 
         ut_info!("Success.");
     }
@@ -116,6 +678,96 @@ mod tests {
         std::env::set_var("REDIS__POOL__MAX_SIZE", "16");
         std::env::set_var("REDIS__POOL__TIMEOUTS__WAIT__SECS", "2");
         std::env::set_var("REDIS__POOL__TIMEOUTS__WAIT__NANOS", "0");
+        std::env::set_var("CONNECTION_GRACE_MINUTES", "5");
+        std::env::set_var("ENABLE_DEADHEAD", "false");
+        std::env::set_var("DEADHEAD_SEARCH_ATTEMPTS", "12");
+        std::env::set_var("DEADHEAD_SEARCH_STEP_MINUTES", "5");
+        std::env::set_var("MAX_WAITLIST_ENTRIES", "50");
+        std::env::set_var("ARRIVAL_JITTER_MAX_SECONDS", "300");
+        std::env::set_var("MAX_ROUTES", "3");
+        std::env::set_var("STORAGE_PAGE_SIZE", "200");
+        std::env::set_var("STORAGE_CONCURRENCY_LIMIT", "8");
+        std::env::set_var(
+            "BLACKOUT_PERIODS",
+            "2020-01-01T00:00:00Z/2020-01-01T06:00:00Z",
+        );
+        std::env::set_var("SOFT_CANCEL_GRACE_MINUTES", "30");
+        std::env::set_var(
+            "USER_TOKENS",
+            "user-token:00000000-0000-0000-0000-000000000001",
+        );
+        std::env::set_var("OPERATOR_TOKENS", "operator-token");
+        std::env::set_var(
+            "VERTIPORT_HOURS_OVERRIDES",
+            "00000000-0000-0000-0000-000000000002|closed|2020-01-01T12:00:00Z/2020-01-01T14:00:00Z",
+        );
+        std::env::set_var("AUTO_WIDEN_HORIZON_MINUTES", "120");
+        std::env::set_var("PRICING_SCHEDULE", "07:00-09:00=1.5,22:00-06:00=0.5");
+        std::env::set_var("PRICE_PER_KM", "150");
+        std::env::set_var("PRICE_PER_MINUTE", "40");
+        std::env::set_var("LANDING_FEE", "500");
+        std::env::set_var("MIN_FLIGHT_DISTANCE_METERS", "250");
+        std::env::set_var(
+            "VERTIPORT_TIMEZONES",
+            "00000000-0000-0000-0000-000000000002|Europe/Amsterdam",
+        );
+        std::env::set_var("MAX_QUEUE_DEPTH", "500");
+        std::env::set_var("FLIGHT_TIME_MARGIN_SECONDS", "30");
+        std::env::set_var("FLIGHT_TIME_MARGIN_PERCENT", "10");
+        std::env::set_var("INCLUDE_DISABLED_VERTIPADS", "true");
+        std::env::set_var("RETURN_HOLD_MINUTES", "30");
+        std::env::set_var("MAX_TIMESLOT_PAIR_CHECKS", "100");
+        std::env::set_var("MAX_TIMESLOT_PAIRS", "10");
+        std::env::set_var("FLIGHT_PLAN_LOOKBACK_HOURS", "48");
+        std::env::set_var("CREATE_EXPIRY_GRACE_SECONDS", "10");
+        std::env::set_var(
+            "WEATHER_CELLS",
+            "2020-01-01T00:00:00Z/2020-01-01T06:00:00Z|52.0 4.0;52.1 4.0;52.1 4.1",
+        );
+        std::env::set_var("STORAGE_RETRY_ATTEMPTS", "3");
+        std::env::set_var("STORAGE_RETRY_BACKOFF_MS", "100");
+        std::env::set_var("MIN_ORIGIN_WINDOW_SECONDS", "60");
+        std::env::set_var("MIN_CRUISE_ALTITUDE_METERS", "80");
+        std::env::set_var("AUDIT_SINK", "log");
+        std::env::set_var("MAX_DEADHEAD_LEGS", "1");
+        std::env::set_var("DEDUPLICATE_FLIGHT_PLANS", "false");
+        std::env::set_var("QUERY_BUDGET_MS", "5000");
+        std::env::set_var("INVALID_SCHEDULE_POLICY", "strict");
+        std::env::set_var("MAX_AIRCRAFT_PER_QUERY", "10");
+        std::env::set_var("STRICT_DEPARTURE_LEAD", "true");
+        std::env::set_var("DEPARTURE_LEAD_SECONDS", "300");
+        std::env::set_var(
+            "VERTIPORT_LEAD_TIMES",
+            "00000000-0000-0000-0000-000000000002|600",
+        );
+        std::env::set_var("DEFAULT_VERTIPORT_LEAD_SECONDS", "60");
+        std::env::set_var("ALTERNATIVE_DESTINATION_RADIUS_METERS", "5000");
+        std::env::set_var("WARMUP_ON_STARTUP", "true");
+        std::env::set_var("WARMUP_ROUTE_DISTANCES", "true");
+        std::env::set_var("PATH_FALLBACK", "true");
+        std::env::set_var("PATH_FALLBACK_DISTANCE_PERCENT", "200");
+        std::env::set_var(
+            "VERTIPAD_RESERVATIONS",
+            "00000000-0000-0000-0000-000000000003|DTSTART:20240101T080000Z;DURATION:PT15M|RRULE:FREQ=DAILY",
+        );
+        std::env::set_var("DRAFT_OCCUPANCY", "true");
+        std::env::set_var("RETURN_DEADHEAD_POLICY", "park");
+        std::env::set_var(
+            "HAZARD_RESTRICTIONS",
+            "00000000-0000-0000-0000-000000000004=1.1|2.3",
+        );
+        std::env::set_var("HAZARD_CORRIDOR_WIDTH_METERS", "800");
+        std::env::set_var("EXPIRY_JITTER_SECONDS", "60");
+        std::env::set_var("PAD_SELECTION", "earliest_availability");
+        std::env::set_var("MAX_HOLDS_PER_USER", "3");
+        std::env::set_var("MAX_RETURN_HOLDS", "100");
+        std::env::set_var("DISTANCE_TOLERANCE_MILLIMETERS", "10");
+        std::env::set_var("ETA_P50_PERCENT", "95");
+        std::env::set_var("ETA_P90_PERCENT", "115");
+        std::env::set_var(
+            "VEHICLE_MODELS",
+            "00000000-0000-0000-0000-000000000003|Air Taxi 4|passenger|120|90|400000",
+        );
 
         let config = Config::try_from_env();
         assert!(config.is_ok());
@@ -136,6 +788,103 @@ mod tests {
             Some(String::from("redis://test_redis:6379"))
         );
         assert!(config.redis.pool.is_some());
+        assert_eq!(config.connection_grace_minutes, 5);
+        assert!(!config.enable_deadhead);
+        assert_eq!(config.deadhead_search_attempts, 12);
+        assert_eq!(config.deadhead_search_step_minutes, 5);
+        assert_eq!(config.max_waitlist_entries, 50);
+        assert_eq!(config.arrival_jitter_max_seconds, 300);
+        assert_eq!(config.max_routes, 3);
+        assert_eq!(config.storage_page_size, 200);
+        assert_eq!(config.storage_concurrency_limit, 8);
+        assert_eq!(
+            config.blackout_periods,
+            String::from("2020-01-01T00:00:00Z/2020-01-01T06:00:00Z")
+        );
+        assert_eq!(config.soft_cancel_grace_minutes, 30);
+        assert_eq!(
+            config.user_tokens,
+            String::from("user-token:00000000-0000-0000-0000-000000000001")
+        );
+        assert_eq!(config.operator_tokens, String::from("operator-token"));
+        assert_eq!(
+            config.vertiport_hours_overrides,
+            String::from(
+                "00000000-0000-0000-0000-000000000002|closed|2020-01-01T12:00:00Z/2020-01-01T14:00:00Z"
+            )
+        );
+        assert_eq!(config.auto_widen_horizon_minutes, 120);
+        assert_eq!(
+            config.pricing_schedule,
+            String::from("07:00-09:00=1.5,22:00-06:00=0.5")
+        );
+        assert_eq!(config.price_per_km, 150);
+        assert_eq!(config.price_per_minute, 40);
+        assert_eq!(config.landing_fee, 500);
+        assert_eq!(config.min_flight_distance_meters, 250);
+        assert_eq!(
+            config.vertiport_timezones,
+            String::from("00000000-0000-0000-0000-000000000002|Europe/Amsterdam")
+        );
+        assert_eq!(config.max_queue_depth, 500);
+        assert_eq!(config.flight_time_margin_seconds, 30);
+        assert_eq!(config.flight_time_margin_percent, 10);
+        assert!(config.include_disabled_vertipads);
+        assert_eq!(config.return_hold_minutes, 30);
+        assert_eq!(config.max_timeslot_pair_checks, 100);
+        assert_eq!(config.max_timeslot_pairs, 10);
+        assert_eq!(config.flight_plan_lookback_hours, 48);
+        assert_eq!(config.create_expiry_grace_seconds, 10);
+        assert_eq!(
+            config.weather_cells,
+            String::from("2020-01-01T00:00:00Z/2020-01-01T06:00:00Z|52.0 4.0;52.1 4.0;52.1 4.1")
+        );
+        assert_eq!(config.storage_retry_attempts, 3);
+        assert_eq!(config.storage_retry_backoff_ms, 100);
+        assert_eq!(config.min_origin_window_seconds, 60);
+        assert_eq!(config.min_cruise_altitude_meters, 80);
+        assert_eq!(config.audit_sink, "log");
+        assert_eq!(config.max_deadhead_legs, 1);
+        assert!(!config.deduplicate_flight_plans);
+        assert_eq!(config.query_budget_ms, 5000);
+        assert_eq!(config.invalid_schedule_policy, "strict");
+        assert_eq!(config.max_aircraft_per_query, 10);
+        assert!(config.strict_departure_lead);
+        assert_eq!(config.departure_lead_seconds, 300);
+        assert_eq!(
+            config.vertiport_lead_times,
+            String::from("00000000-0000-0000-0000-000000000002|600")
+        );
+        assert_eq!(config.default_vertiport_lead_seconds, 60);
+        assert_eq!(config.alternative_destination_radius_meters, 5000);
+        assert!(config.warmup_on_startup);
+        assert!(config.warmup_route_distances);
+        assert!(config.path_fallback);
+        assert_eq!(config.path_fallback_distance_percent, 200);
+        assert_eq!(
+            config.vertipad_reservations,
+            String::from(
+                "00000000-0000-0000-0000-000000000003|DTSTART:20240101T080000Z;DURATION:PT15M|RRULE:FREQ=DAILY"
+            )
+        );
+        assert!(config.draft_occupancy);
+        assert_eq!(config.return_deadhead_policy, "park");
+        assert_eq!(
+            config.hazard_restrictions,
+            String::from("00000000-0000-0000-0000-000000000004=1.1|2.3")
+        );
+        assert_eq!(config.hazard_corridor_width_meters, 800);
+        assert_eq!(config.expiry_jitter_seconds, 60);
+        assert_eq!(config.pad_selection, "earliest_availability");
+        assert_eq!(config.max_holds_per_user, 3);
+        assert_eq!(config.max_return_holds, 100);
+        assert_eq!(config.distance_tolerance_millimeters, 10);
+        assert_eq!(config.eta_p50_percent, 95);
+        assert_eq!(config.eta_p90_percent, 115);
+        assert_eq!(
+            config.vehicle_models,
+            String::from("00000000-0000-0000-0000-000000000003|Air Taxi 4|passenger|120|90|400000")
+        );
 
         ut_info!("Success.");
     }
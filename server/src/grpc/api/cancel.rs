@@ -1,14 +1,21 @@
 //! This module contains the gRPC cancel_itinerary endpoint implementation.
 
+use crate::clock::get_clock;
+use crate::grpc::client::get_clients;
 use crate::grpc::server::grpc_server::{
-    CancelItineraryRequest, TaskAction, TaskMetadata, TaskResponse, TaskStatus,
+    CancelItineraryRequest, CancelUserDraftsRequest, CancelUserDraftsResponse,
+    CancelVehicleItinerariesRequest, CancelVehicleItinerariesResponse, CancellationKind,
+    TaskAction, TaskMetadata, TaskResponse, TaskStatus,
 };
-use crate::tasks::pool::RedisPool;
+use crate::tasks::audit::{audit, AuditEvent, AuditRecord};
+use crate::tasks::pool::{CacheError, RedisPool};
 use crate::tasks::{Task, TaskBody};
-use lib_common::time::{Duration, Utc};
-use lib_common::uuid::to_uuid;
+use lib_common::time::{DateTime, Duration, Utc};
+use lib_common::uuid::{to_uuid, Uuid};
 use num_traits::FromPrimitive;
+use std::collections::HashSet;
 use std::fmt::{self, Display, Formatter};
+use svc_storage_client_grpc::prelude::*;
 
 /// The expiry of the cancellation task is set to the current time plus one hour.
 /// Cancellations should be handled first, so this should be enough time
@@ -37,6 +44,18 @@ pub enum CancelItineraryError {
 
     /// Error creating a new task
     TaskCreation,
+
+    /// The task queues are full
+    QueueFull,
+
+    /// Invalid vehicle ID provided
+    VehicleId,
+
+    /// Error communicating with svc-storage
+    ClientError,
+
+    /// Invalid cancellation kind provided
+    CancellationKind(i32),
 }
 
 impl Display for CancelItineraryError {
@@ -50,13 +69,38 @@ impl Display for CancelItineraryError {
             Self::InternalError => write!(f, "Internal error."),
             Self::RedisPool => write!(f, "Couldn't get the redis pool."),
             Self::TaskCreation => write!(f, "Could not create new task."),
+            Self::QueueFull => write!(f, "Task queue is full."),
+            Self::VehicleId => write!(f, "Invalid vehicle ID provided."),
+            Self::ClientError => write!(f, "Could not contact svc-storage."),
+            Self::CancellationKind(k) => write!(f, "Invalid cancellation kind provided: {k}."),
         }
     }
 }
 
-/// Cancels an itinerary
+/// Returns the task body for the requested kind of cancellation
+fn get_task_body(kind: CancellationKind, itinerary_id: Uuid) -> TaskBody {
+    match kind {
+        CancellationKind::Hard => TaskBody::CancelItinerary(itinerary_id),
+        CancellationKind::Soft => TaskBody::SoftCancelItinerary(itinerary_id),
+    }
+}
+
+/// Returns the expiry of a new cancellation task
+fn get_cancellation_expiry() -> Result<DateTime<Utc>, CancelItineraryError> {
+    #[cfg(not(tarpaulin_include))]
+    // no_coverage: (R5) this can't fail. See [`tests::test_cancellation_expiry_minutes`] for coverage.
+    let delta = Duration::try_minutes(CANCELLATION_EXPIRY_MINUTES).ok_or_else(|| {
+        grpc_error!("error creating time delta.");
+        CancelItineraryError::TimeDelta
+    })?;
+
+    Ok(get_clock().now() + delta)
+}
+
+/// Cancels an itinerary, audited as cancelled by `actor_id`
 pub async fn cancel_itinerary(
     request: CancelItineraryRequest,
+    actor_id: &str,
 ) -> Result<TaskResponse, CancelItineraryError> {
     let itinerary_id = to_uuid(&request.itinerary_id).ok_or(CancelItineraryError::ItineraryId)?;
 
@@ -65,16 +109,11 @@ pub async fn cancel_itinerary(
     let priority = FromPrimitive::from_i32(request.priority)
         .ok_or(CancelItineraryError::Priority(request.priority))?;
 
-    // TODO(R5): Get the itinerary start time from storage
-    // For now hardcode next hour
-    #[cfg(not(tarpaulin_include))]
-    // no_coverage: (R5) this can't fail. See [`tests::test_cancellation_expiry_minutes`] for coverage.
-    let delta = Duration::try_minutes(CANCELLATION_EXPIRY_MINUTES).ok_or_else(|| {
-        grpc_error!("error creating time delta.");
-        CancelItineraryError::TimeDelta
-    })?;
+    let kind = FromPrimitive::from_i32(request.cancellation_kind).ok_or(
+        CancelItineraryError::CancellationKind(request.cancellation_kind),
+    )?;
 
-    let expiry = Utc::now() + delta;
+    let expiry = get_cancellation_expiry()?;
     let task = Task {
         metadata: TaskMetadata {
             status: TaskStatus::Queued as i32,
@@ -82,8 +121,13 @@ pub async fn cancel_itinerary(
             action: TaskAction::CancelItinerary as i32,
             user_id: user_id.to_string(),
             result: None,
+            fallback_vertipad_ids: vec![],
         },
-        body: TaskBody::CancelItinerary(itinerary_id),
+        body: get_task_body(kind, itinerary_id),
+        eviction_priority: None,
+        return_hold: None,
+        restores: None,
+        actor_id: Some(actor_id.to_string()),
     };
 
     #[cfg(not(tarpaulin_include))]
@@ -95,10 +139,19 @@ pub async fn cancel_itinerary(
         })?;
 
         // Add the task to the scheduler:tasks table
-        let task_id = pool.new_task(&task, priority, expiry).await.map_err(|e| {
-            grpc_error!("Could not create new task: {e}");
-            CancelItineraryError::TaskCreation
-        })?;
+        let task_id = pool
+            .new_task(&task, priority, expiry)
+            .await
+            .map_err(|e| match e {
+                CacheError::QueueFull => {
+                    grpc_warn!("Task queue is full, rejecting cancellation.");
+                    CancelItineraryError::QueueFull
+                }
+                e => {
+                    grpc_error!("Could not create new task: {e}");
+                    CancelItineraryError::TaskCreation
+                }
+            })?;
 
         grpc_info!("Created new task with ID: {}", task_id);
         Ok(TaskResponse {
@@ -108,9 +161,190 @@ pub async fn cancel_itinerary(
     }
 }
 
+/// Cancels all active itineraries containing a flight plan for the given
+///  vehicle that has not yet departed. Used when an aircraft is grounded.
+/// Returns the IDs of the cancelled itineraries.
+/// The cancellations are audited as made by `actor_id`.
+#[cfg(not(tarpaulin_include))]
+// no_coverage: (R5) needs running backend, integration tests
+pub async fn cancel_vehicle_itineraries(
+    request: CancelVehicleItinerariesRequest,
+    actor_id: &str,
+) -> Result<CancelVehicleItinerariesResponse, CancelItineraryError> {
+    let vehicle_id = to_uuid(&request.vehicle_id).ok_or(CancelItineraryError::VehicleId)?;
+    let clients = get_clients().await;
+
+    //
+    // Get all flight plans for this vehicle that haven't departed yet
+    //
+    let filter = AdvancedSearchFilter::search_is_null("deleted_at".to_owned())
+        .and_equals("vehicle_id".to_string(), vehicle_id.to_string())
+        .and_not_in(
+            "flight_status".to_owned(),
+            vec![
+                (flight_plan::FlightStatus::Finished as i32).to_string(),
+                (flight_plan::FlightStatus::Cancelled as i32).to_string(),
+            ],
+        );
+
+    let flight_plans = clients
+        .storage()
+        .await
+        .flight_plan
+        .search(filter)
+        .await
+        .map_err(|e| {
+            grpc_error!("Could not get flight plans for vehicle {vehicle_id}: {e}");
+            CancelItineraryError::ClientError
+        })?
+        .into_inner()
+        .list;
+
+    let flight_plan_ids = get_undeparted_flight_plan_ids(flight_plans, get_clock().now());
+    if flight_plan_ids.is_empty() {
+        grpc_info!("No future flight plans found for vehicle {vehicle_id}.");
+        return Ok(CancelVehicleItinerariesResponse::default());
+    }
+
+    //
+    // Find the active itineraries using those flight plans
+    //
+    let filter = AdvancedSearchFilter::search_equals(
+        "status".to_string(),
+        (itinerary::ItineraryStatus::Active as i32).to_string(),
+    );
+
+    let itineraries = clients
+        .storage()
+        .await
+        .itinerary
+        .search(filter)
+        .await
+        .map_err(|e| {
+            grpc_error!("Could not get active itineraries: {e}");
+            CancelItineraryError::ClientError
+        })?
+        .into_inner()
+        .list;
+
+    let mut itinerary_ids = vec![];
+    let mut cancelled = vec![];
+    for itinerary in itineraries {
+        let Some(itinerary_id) = to_uuid(&itinerary.id) else {
+            grpc_warn!("Itinerary has invalid ID: {}", itinerary.id);
+            continue;
+        };
+
+        let Some(data) = itinerary.data else {
+            grpc_warn!("Itinerary has invalid data: {}", itinerary_id);
+            continue;
+        };
+
+        let linked_ids = match clients
+            .storage()
+            .await
+            .itinerary_flight_plan_link
+            .get_linked_ids(Id {
+                id: itinerary_id.to_string(),
+            })
+            .await
+        {
+            Ok(response) => response.into_inner().ids,
+            Err(e) => {
+                grpc_warn!("Could not get flight plans for itinerary {itinerary_id}: {e}");
+                continue;
+            }
+        };
+
+        if !linked_ids.iter().any(|id| flight_plan_ids.contains(id)) {
+            continue;
+        }
+
+        match crate::tasks::cancel_itinerary_in_storage(clients, &itinerary_id, data).await {
+            Ok(flight_plans) => {
+                let (flight_plan_ids, schedules): (Vec<String>, Vec<_>) =
+                    flight_plans.into_iter().unzip();
+
+                audit(AuditRecord {
+                    event: AuditEvent::Cancelled,
+                    user_id: actor_id.to_string(),
+                    task_id: None,
+                    itinerary_id: Some(itinerary_id.to_string()),
+                    flight_plan_ids,
+                    restored_from: None,
+                    timestamp: get_clock().now(),
+                })
+                .await;
+
+                itinerary_ids.push(itinerary_id.to_string());
+                cancelled.extend(schedules);
+            }
+            Err(e) => grpc_error!("Could not cancel itinerary {itinerary_id}: {e}"),
+        }
+    }
+
+    // Freed capacity may satisfy waitlisted requests for the same routes
+    if let Err(e) = crate::tasks::waitlist::rescan_waitlist(&cancelled).await {
+        grpc_warn!("Could not re-evaluate waitlist: {e}");
+    }
+
+    grpc_info!(
+        "Cancelled {} itineraries for vehicle {vehicle_id}.",
+        itinerary_ids.len()
+    );
+
+    Ok(CancelVehicleItinerariesResponse { itinerary_ids })
+}
+
+/// Releases the unconfirmed return holds of a user who abandoned booking,
+///  instead of keeping them until they expire.
+/// Returns the IDs of the released holds.
+#[cfg(not(tarpaulin_include))]
+// no_coverage: (R5) needs running backend, integration tests
+pub async fn cancel_user_drafts(
+    request: CancelUserDraftsRequest,
+) -> Result<CancelUserDraftsResponse, CancelItineraryError> {
+    let user_id = to_uuid(&request.user_id).ok_or(CancelItineraryError::UserId)?;
+    let (hold_ids, released) = crate::tasks::return_hold::release_user_holds(&user_id.to_string())
+        .await
+        .map_err(|e| {
+            grpc_error!("Could not release the holds of user {user_id}: {e}");
+            CancelItineraryError::RedisPool
+        })?;
+
+    // Released flight plans may satisfy waitlisted requests for the same routes
+    if let Err(e) = crate::tasks::waitlist::rescan_waitlist(&released).await {
+        grpc_warn!("Could not re-evaluate waitlist: {e}");
+    }
+
+    grpc_info!(
+        "Released {} return holds of user {user_id}.",
+        hold_ids.len()
+    );
+
+    Ok(CancelUserDraftsResponse { hold_ids })
+}
+
+/// Returns the IDs of flight plans that depart after the provided time
+fn get_undeparted_flight_plan_ids(
+    flight_plans: Vec<flight_plan::Object>,
+    now: DateTime<Utc>,
+) -> HashSet<String> {
+    flight_plans
+        .into_iter()
+        .filter_map(|fp| {
+            let origin_timeslot_start: DateTime<Utc> =
+                fp.data.as_ref()?.origin_timeslot_start.clone()?.into();
+
+            (origin_timeslot_start > now).then_some(fp.id)
+        })
+        .collect()
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::grpc::auth::OPERATOR_ACTOR_ID;
     use tonic::Status;
 
     #[test]
@@ -118,6 +352,23 @@ mod tests {
         Duration::try_minutes(CANCELLATION_EXPIRY_MINUTES).unwrap();
     }
 
+    #[test]
+    fn test_get_cancellation_expiry() {
+        let start = DateTime::<Utc>::from_timestamp(1_700_000_000, 0).unwrap();
+        let clock = crate::test_util::set_mock_clock(start);
+        let delta = Duration::try_minutes(CANCELLATION_EXPIRY_MINUTES).unwrap();
+        assert_eq!(get_cancellation_expiry().unwrap(), start + delta);
+
+        // the expiry follows the service clock
+        clock.advance(Duration::try_minutes(30).unwrap());
+        assert_eq!(
+            get_cancellation_expiry().unwrap(),
+            start + Duration::try_minutes(30).unwrap() + delta
+        );
+
+        crate::clock::set_test_clock(None);
+    }
+
     #[test]
     fn test_cancel_itinerary_error_display() {
         assert_eq!(
@@ -148,5 +399,206 @@ mod tests {
             format!("{}", CancelItineraryError::TaskCreation),
             "Error cancelling itinerary: Could not create new task."
         );
+        assert_eq!(
+            format!("{}", CancelItineraryError::QueueFull),
+            "Error cancelling itinerary: Task queue is full."
+        );
+        assert_eq!(
+            format!("{}", CancelItineraryError::VehicleId),
+            "Error cancelling itinerary: Invalid vehicle ID provided."
+        );
+        assert_eq!(
+            format!("{}", CancelItineraryError::ClientError),
+            "Error cancelling itinerary: Could not contact svc-storage."
+        );
+        assert_eq!(
+            format!("{}", CancelItineraryError::CancellationKind(2)),
+            "Error cancelling itinerary: Invalid cancellation kind provided: 2."
+        );
+    }
+
+    #[test]
+    fn test_get_task_body() {
+        let itinerary_id = Uuid::new_v4();
+        assert!(matches!(
+            get_task_body(CancellationKind::Hard, itinerary_id),
+            TaskBody::CancelItinerary(id) if id == itinerary_id
+        ));
+        assert!(matches!(
+            get_task_body(CancellationKind::Soft, itinerary_id),
+            TaskBody::SoftCancelItinerary(id) if id == itinerary_id
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_cancel_itinerary_invalid_cancellation_kind() {
+        let request = CancelItineraryRequest {
+            priority: flight_plan::FlightPriority::Low as i32,
+            itinerary_id: Uuid::new_v4().to_string(),
+            user_id: Uuid::new_v4().to_string(),
+            cancellation_kind: 2,
+        };
+
+        let e = cancel_itinerary(request, OPERATOR_ACTOR_ID)
+            .await
+            .unwrap_err();
+        assert_eq!(e, CancelItineraryError::CancellationKind(2));
+    }
+
+    #[test]
+    fn test_get_undeparted_flight_plan_ids() {
+        let now = Utc::now();
+        let make_plan = |offset_minutes: i64| flight_plan::Object {
+            id: Uuid::new_v4().to_string(),
+            data: Some(flight_plan::Data {
+                origin_timeslot_start: Some(
+                    (now + Duration::try_minutes(offset_minutes).unwrap()).into(),
+                ),
+                ..Default::default()
+            }),
+        };
+
+        let departed = make_plan(-10);
+        let future_1 = make_plan(10);
+        let future_2 = make_plan(60);
+        let no_data = flight_plan::Object {
+            id: Uuid::new_v4().to_string(),
+            data: None,
+        };
+
+        let ids = get_undeparted_flight_plan_ids(
+            vec![
+                departed.clone(),
+                future_1.clone(),
+                future_2.clone(),
+                no_data.clone(),
+            ],
+            now,
+        );
+
+        assert_eq!(ids.len(), 2);
+        assert!(ids.contains(&future_1.id));
+        assert!(ids.contains(&future_2.id));
+        assert!(!ids.contains(&departed.id));
+        assert!(!ids.contains(&no_data.id));
+    }
+
+    #[tokio::test]
+    async fn test_cancel_vehicle_itineraries_invalid_vehicle_id() {
+        let request = CancelVehicleItinerariesRequest {
+            vehicle_id: "invalid".to_string(),
+        };
+
+        let e = cancel_vehicle_itineraries(request, OPERATOR_ACTOR_ID)
+            .await
+            .unwrap_err();
+        assert_eq!(e, CancelItineraryError::VehicleId);
+    }
+
+    #[tokio::test]
+    #[cfg(feature = "stub_backends")]
+    async fn test_cancel_vehicle_itineraries() {
+        use crate::router::flight_plan::FlightPlanSchedule;
+
+        lib_common::logger::get_log_handle().await;
+        ut_info!("start");
+
+        let clients = get_clients().await;
+        let now = Utc::now();
+        let vehicle_id = Uuid::new_v4().to_string();
+        let make_plan = |offset_minutes: i64| -> flight_plan::Data {
+            let start = now + Duration::try_minutes(offset_minutes).unwrap();
+            FlightPlanSchedule {
+                origin_vertiport_id: Uuid::new_v4().to_string(),
+                origin_vertipad_id: Uuid::new_v4().to_string(),
+                origin_timeslot_start: start,
+                origin_timeslot_end: start + Duration::try_minutes(1).unwrap(),
+                target_vertiport_id: Uuid::new_v4().to_string(),
+                target_vertipad_id: Uuid::new_v4().to_string(),
+                target_timeslot_start: start + Duration::try_minutes(20).unwrap(),
+                target_timeslot_end: start + Duration::try_minutes(21).unwrap(),
+                vehicle_id: vehicle_id.clone(),
+                path: Some(vec![]),
+            }
+            .into()
+        };
+
+        // Two future itineraries flown by the grounded vehicle
+        let mut expected = vec![];
+        for offset_minutes in [30, 90] {
+            let flight_plan_id = clients
+                .storage()
+                .await
+                .flight_plan
+                .insert(make_plan(offset_minutes))
+                .await
+                .unwrap()
+                .into_inner()
+                .object
+                .unwrap()
+                .id;
+
+            let itinerary_id = clients
+                .storage()
+                .await
+                .itinerary
+                .insert(itinerary::Data {
+                    user_id: Uuid::new_v4().to_string(),
+                    status: itinerary::ItineraryStatus::Active as i32,
+                })
+                .await
+                .unwrap()
+                .into_inner()
+                .object
+                .unwrap()
+                .id;
+
+            clients
+                .storage()
+                .await
+                .itinerary_flight_plan_link
+                .link(itinerary::ItineraryFlightPlans {
+                    id: itinerary_id.clone(),
+                    other_id_list: Some(IdList {
+                        ids: vec![flight_plan_id],
+                    }),
+                })
+                .await
+                .unwrap();
+
+            expected.push(itinerary_id);
+        }
+
+        let response = cancel_vehicle_itineraries(
+            CancelVehicleItinerariesRequest {
+                vehicle_id: vehicle_id.clone(),
+            },
+            OPERATOR_ACTOR_ID,
+        )
+        .await
+        .unwrap();
+
+        let mut itinerary_ids = response.itinerary_ids;
+        itinerary_ids.sort();
+        expected.sort();
+        assert_eq!(itinerary_ids, expected);
+
+        // Both itineraries are now cancelled in storage
+        for itinerary_id in expected {
+            let data = clients
+                .storage()
+                .await
+                .itinerary
+                .get_by_id(Id { id: itinerary_id })
+                .await
+                .unwrap()
+                .into_inner()
+                .data
+                .unwrap();
+
+            assert_eq!(data.status, itinerary::ItineraryStatus::Cancelled as i32);
+        }
+
+        ut_info!("success");
     }
 }
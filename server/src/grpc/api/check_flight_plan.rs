@@ -0,0 +1,330 @@
+//! This module contains the gRPC check_flight_plan endpoint implementation.
+
+use lib_common::time::Duration;
+use svc_gis_client_grpc::prelude::gis::{BestPathRequest, NodeType};
+use tonic::{Response, Status};
+
+use crate::grpc::client::{get_clients, GrpcClients};
+use crate::grpc::server::grpc_server::{
+    CheckFlightPlanRequest, CheckFlightPlanResponse, FlightPlanConflict,
+};
+
+use crate::router::flight_plan::*;
+use crate::router::schedule::*;
+use crate::router::vehicle::*;
+use crate::router::vertiport::{get_available_timeslots, VertiportError};
+use crate::router::{best_path, BestPathError};
+
+/// Returns true if one of the timeslots covers the whole window
+fn covers(slots: &[Timeslot], window: &Timeslot) -> bool {
+    slots.iter().any(|slot| {
+        slot.time_start() <= window.time_start() && slot.time_end() >= window.time_end()
+    })
+}
+
+/// Checks the flight plan against the availability of its aircraft and vertipads.
+/// The aircraft must be parked at the origin vertiport for the whole flight.
+pub(crate) fn get_schedule_conflict(
+    flight_plan: &FlightPlanSchedule,
+    aircraft_gaps: &[Availability],
+    origin_vertipad_slots: &[Timeslot],
+    target_vertipad_slots: &[Timeslot],
+) -> Result<Option<FlightPlanConflict>, Status> {
+    let invalid = |e: TimeslotError| {
+        grpc_warn!("Invalid flight plan times: {e}");
+        Status::invalid_argument("flight_plan: invalid timeslots.")
+    };
+
+    let flight_window = Timeslot::new(
+        flight_plan.origin_timeslot_start,
+        flight_plan.target_timeslot_end,
+    )
+    .map_err(invalid)?;
+
+    let origin_window = Timeslot::new(
+        flight_plan.origin_timeslot_start,
+        flight_plan.origin_timeslot_end,
+    )
+    .map_err(invalid)?;
+
+    let target_window = Timeslot::new(
+        flight_plan.target_timeslot_start,
+        flight_plan.target_timeslot_end,
+    )
+    .map_err(invalid)?;
+
+    let aircraft_available = aircraft_gaps.iter().any(|gap| {
+        gap.vertiport_id == flight_plan.origin_vertiport_id
+            && covers(&[gap.timeslot], &flight_window)
+    });
+
+    if !aircraft_available {
+        return Ok(Some(FlightPlanConflict::AircraftUnavailable));
+    }
+
+    if !covers(origin_vertipad_slots, &origin_window)
+        || !covers(target_vertipad_slots, &target_window)
+    {
+        return Ok(Some(FlightPlanConflict::VertipadUnavailable));
+    }
+
+    Ok(None)
+}
+
+/// Gets the availabilities of the flight plan's aircraft during the flight
+pub(crate) fn get_aircraft_gaps(
+    flight_plan: &FlightPlanSchedule,
+    existing_flight_plans: &[FlightPlanSchedule],
+    aircraft: &[Aircraft],
+    connection_grace: &Duration,
+) -> Result<Vec<Availability>, Status> {
+    let timeslot = Timeslot::new(
+        flight_plan.origin_timeslot_start,
+        flight_plan.target_timeslot_end,
+    )
+    .map_err(|_| Status::invalid_argument("flight_plan: invalid timeslots."))?;
+
+    let gaps = get_aircraft_availabilities(
+        existing_flight_plans,
+        &timeslot.time_start(),
+        aircraft,
+        &timeslot,
+        connection_grace,
+    )
+    .map_err(|e| {
+        grpc_error!("{}", e);
+        Status::internal("Could not get aircraft availabilities.")
+    })?
+    .remove(&flight_plan.vehicle_id)
+    .unwrap_or_default();
+
+    Ok(gaps)
+}
+
+/// Gets the available timeslots of the flight plan's origin and target vertipads
+#[cfg(not(tarpaulin_include))]
+// no_coverage: (R5) needs running backend, integration tests
+pub(crate) async fn get_vertipad_slots(
+    flight_plan: &FlightPlanSchedule,
+    existing_flight_plans: &[FlightPlanSchedule],
+    clients: &GrpcClients,
+) -> Result<(Vec<Timeslot>, Vec<Timeslot>), Status> {
+    let timeslot = Timeslot::new(
+        flight_plan.origin_timeslot_start,
+        flight_plan.target_timeslot_end,
+    )
+    .map_err(|_| Status::invalid_argument("flight_plan: invalid timeslots."))?;
+
+    let vertipad_err = |e: VertiportError| {
+        grpc_error!("{}", e);
+        Status::internal("Could not get vertipad availabilities.")
+    };
+
+    let origin_vertipad_slots = get_available_timeslots(
+        &flight_plan.origin_vertiport_id,
+        Some(&flight_plan.origin_vertipad_id),
+        existing_flight_plans,
+        &timeslot,
+        &(flight_plan.origin_timeslot_end - flight_plan.origin_timeslot_start),
+        clients,
+    )
+    .await
+    .map_err(vertipad_err)?
+    .remove(&flight_plan.origin_vertipad_id)
+    .unwrap_or_default();
+
+    let target_vertipad_slots = get_available_timeslots(
+        &flight_plan.target_vertiport_id,
+        Some(&flight_plan.target_vertipad_id),
+        existing_flight_plans,
+        &timeslot,
+        &(flight_plan.target_timeslot_end - flight_plan.target_timeslot_start),
+        clients,
+    )
+    .await
+    .map_err(vertipad_err)?
+    .remove(&flight_plan.target_vertipad_id)
+    .unwrap_or_default();
+
+    Ok((origin_vertipad_slots, target_vertipad_slots))
+}
+
+/// Builds the response for the first conflict found, if any
+fn get_response(conflict: Option<FlightPlanConflict>) -> CheckFlightPlanResponse {
+    CheckFlightPlanResponse {
+        schedulable: conflict.is_none(),
+        conflict: conflict.map(|c| c as i32),
+    }
+}
+
+/// Checks whether a fully specified flight plan can be booked as-is.
+/// Returns the reason it can't be booked, if any.
+#[cfg(not(tarpaulin_include))]
+// no_coverage: (R5) needs running backend, integration tests
+pub async fn check_flight_plan(
+    request: CheckFlightPlanRequest,
+) -> Result<Response<CheckFlightPlanResponse>, Status> {
+    let data = request
+        .flight_plan
+        .ok_or_else(|| Status::invalid_argument("flight_plan: must be provided."))?;
+
+    let flight_plan = FlightPlanSchedule::try_from(data).map_err(|e| {
+        grpc_warn!("Invalid flight plan: {e}");
+        Status::invalid_argument("flight_plan: must have vertipads, aircraft and times.")
+    })?;
+
+    let clients = get_clients().await;
+    let config = crate::config::get_config().await;
+
+    let existing_flight_plans: Vec<FlightPlanSchedule> =
+        get_sorted_flight_plans(clients).await.map_err(|e| {
+            grpc_error!("{}", e);
+            Status::internal("Could not get existing flight plans.")
+        })?;
+
+    let aircraft = get_aircraft(clients, Some(flight_plan.vehicle_id.clone()))
+        .await
+        .map_err(|e| {
+            grpc_error!("{}", e);
+            Status::internal("Could not get aircraft.")
+        })?;
+
+    let connection_grace = Duration::try_minutes(config.connection_grace_minutes.into())
+        .ok_or_else(|| {
+            grpc_error!("error creating connection grace duration.");
+            Status::internal("Invalid connection grace.")
+        })?;
+
+    let aircraft_gaps = get_aircraft_gaps(
+        &flight_plan,
+        &existing_flight_plans,
+        &aircraft,
+        &connection_grace,
+    )?;
+
+    let (origin_vertipad_slots, target_vertipad_slots) =
+        get_vertipad_slots(&flight_plan, &existing_flight_plans, clients).await?;
+
+    if let Some(conflict) = get_schedule_conflict(
+        &flight_plan,
+        &aircraft_gaps,
+        &origin_vertipad_slots,
+        &target_vertipad_slots,
+    )? {
+        grpc_info!("flight plan is not schedulable: {:?}", conflict);
+        return Ok(Response::new(get_response(Some(conflict))));
+    }
+
+    //
+    // Routability and range
+    //
+    let best_path_request = BestPathRequest {
+        origin_identifier: flight_plan.origin_vertiport_id.clone(),
+        target_identifier: flight_plan.target_vertiport_id.clone(),
+        origin_type: NodeType::Vertiport as i32,
+        target_type: NodeType::Vertiport as i32,
+        time_start: Some(flight_plan.origin_timeslot_end.into()),
+        time_end: Some(flight_plan.target_timeslot_start.into()),
+        limit: 1,
+    };
+
+    let conflict = match best_path(&best_path_request, clients).await {
+        Ok(paths) => match paths.first() {
+            Some((_, distance)) if is_within_range(distance) => None,
+            Some(_) => Some(FlightPlanConflict::OutOfRange),
+            None => Some(FlightPlanConflict::NoRoute),
+        },
+        Err(BestPathError::NoPathFound) => Some(FlightPlanConflict::NoRoute),
+        Err(BestPathError::ClientError) => {
+            return Err(Status::internal("Could not determine path."));
+        }
+    };
+
+    grpc_info!("flight plan conflict: {:?}", conflict);
+    Ok(Response::new(get_response(conflict)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use lib_common::time::{DateTime, Utc};
+    use lib_common::uuid::Uuid;
+
+    fn get_flight_plan(now: DateTime<Utc>) -> FlightPlanSchedule {
+        FlightPlanSchedule {
+            origin_vertiport_id: Uuid::new_v4().to_string(),
+            origin_vertipad_id: Uuid::new_v4().to_string(),
+            origin_timeslot_start: now + Duration::try_minutes(10).unwrap(),
+            origin_timeslot_end: now + Duration::try_minutes(11).unwrap(),
+            target_vertiport_id: Uuid::new_v4().to_string(),
+            target_vertipad_id: Uuid::new_v4().to_string(),
+            target_timeslot_start: now + Duration::try_minutes(30).unwrap(),
+            target_timeslot_end: now + Duration::try_minutes(31).unwrap(),
+            vehicle_id: Uuid::new_v4().to_string(),
+            path: None,
+        }
+    }
+
+    #[test]
+    fn test_get_schedule_conflict() {
+        let now = Utc::now();
+        let flight_plan = get_flight_plan(now);
+        let day = Timeslot::new(now, now + Duration::try_hours(24).unwrap()).unwrap();
+        let parked = vec![Availability {
+            timeslot: day,
+            vertiport_id: flight_plan.origin_vertiport_id.clone(),
+            vertipad_id: flight_plan.origin_vertipad_id.clone(),
+        }];
+
+        // everything is free
+        let conflict = get_schedule_conflict(&flight_plan, &parked, &[day], &[day]).unwrap();
+        assert_eq!(conflict, None);
+
+        // aircraft is parked at another vertiport
+        let mut elsewhere = parked.clone();
+        elsewhere[0].vertiport_id = Uuid::new_v4().to_string();
+        let conflict = get_schedule_conflict(&flight_plan, &elsewhere, &[day], &[day]).unwrap();
+        assert_eq!(conflict, Some(FlightPlanConflict::AircraftUnavailable));
+
+        // aircraft has no availability at all
+        let conflict = get_schedule_conflict(&flight_plan, &[], &[day], &[day]).unwrap();
+        assert_eq!(conflict, Some(FlightPlanConflict::AircraftUnavailable));
+
+        // another flight is landing on the target vertipad
+        let occupied = Timeslot::new(
+            flight_plan.target_timeslot_start,
+            flight_plan.target_timeslot_end,
+        )
+        .unwrap();
+        let target_slots = day - occupied;
+        let conflict = get_schedule_conflict(&flight_plan, &parked, &[day], &target_slots).unwrap();
+        assert_eq!(conflict, Some(FlightPlanConflict::VertipadUnavailable));
+
+        // origin vertipad has no availability
+        let conflict = get_schedule_conflict(&flight_plan, &parked, &[], &[day]).unwrap();
+        assert_eq!(conflict, Some(FlightPlanConflict::VertipadUnavailable));
+    }
+
+    #[test]
+    fn test_get_schedule_conflict_invalid_times() {
+        let now = Utc::now();
+        let mut flight_plan = get_flight_plan(now);
+        flight_plan.target_timeslot_end =
+            flight_plan.target_timeslot_start - Duration::try_minutes(1).unwrap();
+
+        let e = get_schedule_conflict(&flight_plan, &[], &[], &[]).unwrap_err();
+        assert_eq!(e.code(), tonic::Code::InvalidArgument);
+        assert_eq!(e.message(), "flight_plan: invalid timeslots.");
+    }
+
+    #[test]
+    fn test_get_response() {
+        let response = get_response(None);
+        assert!(response.schedulable);
+        assert_eq!(response.conflict, None);
+
+        let response = get_response(Some(FlightPlanConflict::NoRoute));
+        assert!(!response.schedulable);
+        assert_eq!(response.conflict, Some(FlightPlanConflict::NoRoute as i32));
+    }
+}
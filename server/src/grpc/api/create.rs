@@ -1,21 +1,167 @@
 //! This module contains the gRPC confirm_itinerary endpoint implementation.
 
+use crate::clock::get_clock;
 use crate::grpc::server::grpc_server::{
     CreateItineraryRequest, TaskAction, TaskMetadata, TaskResponse, TaskStatus,
 };
 use num_traits::FromPrimitive;
 use tonic::Status;
 
+use crate::router::blackout::{get_blackout_periods, get_overlapping_blackout};
 use crate::router::flight_plan::{FlightPlanError, FlightPlanSchedule};
-use crate::tasks::pool::RedisPool;
+use crate::router::schedule::Timeslot;
+use crate::tasks::audit::{emit_audit_record, get_audit_sink, AuditEvent, AuditRecord, AuditSink};
+use crate::tasks::pool::{CacheError, RedisPool};
+use crate::tasks::return_hold::{get_eviction_priority, ReturnHoldRequest};
 use crate::tasks::{Task, TaskBody};
 
+use lib_common::time::{DateTime, Duration, Utc};
 use lib_common::uuid::Uuid;
 
+/// Rejects the flight plans if any of them overlaps a network-wide blackout
+fn check_blackouts(blackouts: &[Timeslot], schedules: &[FlightPlanSchedule]) -> Result<(), Status> {
+    for schedule in schedules {
+        let timeslot = Timeslot::new(schedule.origin_timeslot_start, schedule.target_timeslot_end)
+            .map_err(|e| {
+                let error_msg = "Invalid flight plan times provided";
+                grpc_error!("{error_msg}: {e}");
+                Status::invalid_argument(format!("{error_msg}."))
+            })?;
+
+        if let Some(blackout) = get_overlapping_blackout(blackouts, &timeslot) {
+            grpc_warn!(
+                "Flight plan overlaps network blackout from {} to {}.",
+                blackout.time_start(),
+                blackout.time_end()
+            );
+
+            return Err(Status::failed_precondition(
+                "Flight plans overlap a network blackout.",
+            ));
+        }
+    }
+
+    Ok(())
+}
+
+/// Returns when the itinerary task expires: at the first departure, or
+///  the requested expiry if that's earlier.
+/// A requested expiry that passed less than `grace` ago is still accepted,
+///  the task then expires at the end of the grace. The flight plans are
+///  checked for conflicts again when the task runs.
+fn get_task_expiry(
+    departure: DateTime<Utc>,
+    request_expiry: Option<DateTime<Utc>>,
+    grace: Duration,
+) -> Result<DateTime<Utc>, Status> {
+    grpc_debug!("Default expiry: {departure}.");
+
+    let Some(request_expiry) = request_expiry else {
+        return Ok(departure);
+    };
+
+    grpc_debug!("Request expiry: {request_expiry}.");
+    let now = get_clock().now();
+    let expiry = departure.min(request_expiry);
+    if expiry > now {
+        return Ok(expiry);
+    }
+
+    if departure > now && request_expiry + grace > now {
+        grpc_info!("Itinerary expired at {request_expiry}, still within the grace period.");
+        return Ok((request_expiry + grace).min(departure));
+    }
+
+    grpc_warn!("Itinerary expired at {expiry}.");
+    Err(Status::failed_precondition(
+        "Itinerary expired, please query again.",
+    ))
+}
+
+/// Returns the minimum time between now and the first departure of
+///  an itinerary, if strict departure lead times are configured
+pub async fn get_departure_lead() -> Result<Option<Duration>, Status> {
+    let config = crate::config::get_config().await;
+    if !config.strict_departure_lead {
+        return Ok(None);
+    }
+
+    Duration::try_seconds(config.departure_lead_seconds.into())
+        .map(Some)
+        .ok_or_else(|| {
+            grpc_error!("error creating departure lead duration.");
+            Status::internal("Invalid departure lead.")
+        })
+}
+
+/// Rejects an itinerary that departs sooner than `lead` from now
+pub fn check_departure_lead(
+    departure: DateTime<Utc>,
+    now: DateTime<Utc>,
+    lead: Duration,
+) -> Result<(), Status> {
+    if departure >= now + lead {
+        return Ok(());
+    }
+
+    grpc_warn!(
+        "Itinerary departs at {departure}, less than {} seconds from now.",
+        lead.num_seconds()
+    );
+
+    Err(Status::failed_precondition(
+        "Itinerary departs too soon, please query again.",
+    ))
+}
+
+/// Maps an error creating a task to a gRPC status.
+/// Clients are asked to back off if the task queues are full.
+pub fn get_task_creation_status(e: CacheError) -> Status {
+    match e {
+        CacheError::QueueFull => {
+            grpc_warn!("Task queue is full, rejecting new task.");
+            Status::resource_exhausted("Task queue is full, try again later.")
+        }
+        e => {
+            grpc_error!("Could not create new task: {e}");
+            Status::internal("Could not create new task.")
+        }
+    }
+}
+
+/// Records the confirmation of an itinerary by `actor_id` in the audit
+///  trail, with the soft-cancelled itinerary it `restores`, if any.
+/// The itinerary and its flight plans only get IDs once the task books them.
+async fn audit_confirmation<S>(sink: &mut S, actor_id: &str, task_id: i64, restores: Option<Uuid>)
+where
+    S: AuditSink + Send + ?Sized,
+{
+    let record = AuditRecord {
+        event: AuditEvent::Confirmed,
+        user_id: actor_id.to_string(),
+        task_id: Some(task_id),
+        itinerary_id: None,
+        flight_plan_ids: vec![],
+        restored_from: restores.map(|id| id.to_string()),
+        timestamp: get_clock().now(),
+    };
+
+    emit_audit_record(sink, record).await;
+}
+
 /// Creates an itinerary from a list of flight plans.
 /// The flight plans provided are expected to be the valid output from the `query_flight` endpoint.
 /// Invalid flight plans will be quickly rejected.
-pub async fn create_itinerary(request: CreateItineraryRequest) -> Result<TaskResponse, Status> {
+/// High priority and emergency bookings of operators evict contending
+///  return holds of a lower priority once booked.
+/// `restores` is the soft-cancelled itinerary that the new itinerary
+///  replaces, if any. The booking is audited as made by `actor_id`.
+pub async fn create_itinerary(
+    request: CreateItineraryRequest,
+    is_operator: bool,
+    actor_id: &str,
+    restores: Option<Uuid>,
+) -> Result<TaskResponse, Status> {
     let priority = FromPrimitive::from_i32(request.priority).ok_or_else(|| {
         let error_msg = "Invalid priority provided";
         grpc_error!("{error_msg}: {}", request.priority);
@@ -39,23 +185,46 @@ pub async fn create_itinerary(request: CreateItineraryRequest) -> Result<TaskRes
             Status::invalid_argument(format!("{error_msg}."))
         })?;
 
+    // No flights can be scheduled during a network-wide blackout
+    let blackouts = get_blackout_periods().await.map_err(|e| {
+        grpc_error!("{e}");
+        Status::internal("Invalid blackout periods.")
+    })?;
+
+    check_blackouts(&blackouts, &schedules)?;
+
+    let return_schedules = request
+        .return_flight_plans
+        .into_iter()
+        .map(FlightPlanSchedule::try_from)
+        .collect::<Result<Vec<FlightPlanSchedule>, FlightPlanError>>()
+        .map_err(|e| {
+            let error_msg = "Invalid return flight plans provided";
+            grpc_error!("{error_msg}: {e}");
+            Status::invalid_argument(format!("{error_msg}."))
+        })?;
+
     // Set to expire if it hasn't been acted on by the start of the first flight plan
-    let expiry = schedules
+    let departure = schedules
         .iter()
         .min()
         .ok_or(Status::invalid_argument("No flight plans provided."))?
         .origin_timeslot_start;
 
-    grpc_debug!("Default expiry: {expiry}.");
+    if let Some(lead) = get_departure_lead().await? {
+        check_departure_lead(departure, get_clock().now(), lead)?;
+    }
 
-    let expiry = match request.expiry {
-        // if an earlier expiry was provided in the request, use that instead
-        Some(request_expiry) => {
-            grpc_debug!("Request expiry: {expiry}.");
-            expiry.min(request_expiry.into())
-        }
-        None => expiry,
-    };
+    let grace_seconds = crate::config::get_config()
+        .await
+        .create_expiry_grace_seconds;
+
+    let grace = Duration::try_seconds(grace_seconds.into()).ok_or_else(|| {
+        grpc_error!("error creating expiry grace duration.");
+        Status::internal("Invalid expiry grace.")
+    })?;
+
+    let expiry = get_task_expiry(departure, request.expiry.map(Into::into), grace)?;
 
     grpc_debug!("Task expiry set to: {expiry}.");
 
@@ -66,8 +235,17 @@ pub async fn create_itinerary(request: CreateItineraryRequest) -> Result<TaskRes
             action: TaskAction::CreateItinerary as i32,
             user_id: user_id.to_string(),
             result: None,
+            fallback_vertipad_ids: vec![],
         },
         body: TaskBody::CreateItinerary(schedules),
+        eviction_priority: get_eviction_priority(priority as i32, is_operator),
+        // Hold the return for the user while they book it
+        return_hold: (!return_schedules.is_empty()).then(|| ReturnHoldRequest {
+            flight_plans: return_schedules,
+            priority: priority as i32,
+        }),
+        restores,
+        actor_id: Some(actor_id.to_string()),
     };
 
     // Add the task to the scheduler:tasks table
@@ -76,15 +254,153 @@ pub async fn create_itinerary(request: CreateItineraryRequest) -> Result<TaskRes
         Status::internal("Internal error.")
     })?;
 
-    let task_id = pool.new_task(&task, priority, expiry).await.map_err(|e| {
-        let error_msg = "Could not create new task.";
-        grpc_error!("{error_msg}: {e}");
-        Status::internal(format!("{error_msg}."))
-    })?;
+    let task_id = pool
+        .new_task(&task, priority, expiry)
+        .await
+        .map_err(get_task_creation_status)?;
 
     grpc_info!("Created new task with ID: {}", task_id);
+
+    match get_audit_sink().await {
+        Ok(mut sink) => audit_confirmation(sink.as_mut(), actor_id, task_id, restores).await,
+        Err(e) => grpc_warn!("Could not audit confirmation of task {task_id}: {e}"),
+    }
+
     Ok(TaskResponse {
         task_id,
         task_metadata: Some(task.metadata),
     })
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::clock::Clock;
+
+    #[test]
+    fn test_get_task_creation_status() {
+        let status = get_task_creation_status(CacheError::QueueFull);
+        assert_eq!(status.code(), tonic::Code::ResourceExhausted);
+
+        let status = get_task_creation_status(CacheError::OperationFailed);
+        assert_eq!(status.code(), tonic::Code::Internal);
+    }
+
+    #[tokio::test]
+    async fn test_audit_confirmation() {
+        let mut sink = crate::test_util::MemoryAuditSink::default();
+        let actor_id = Uuid::new_v4().to_string();
+        let before = Utc::now();
+
+        audit_confirmation(&mut sink, &actor_id, 42, None).await;
+
+        assert_eq!(sink.records.len(), 1);
+        let record = &sink.records[0];
+        assert_eq!(record.event, AuditEvent::Confirmed);
+        assert_eq!(record.user_id, actor_id);
+        assert_eq!(record.task_id, Some(42));
+        assert!(record.itinerary_id.is_none());
+        assert!(record.flight_plan_ids.is_empty());
+        assert!(record.restored_from.is_none());
+        assert!(record.timestamp >= before && record.timestamp <= Utc::now());
+
+        // restores are confirmed with the itinerary they restore
+        let restored_id = Uuid::new_v4();
+        audit_confirmation(&mut sink, &actor_id, 43, Some(restored_id)).await;
+        let record = &sink.records[1];
+        assert_eq!(record.event, AuditEvent::Confirmed);
+        assert_eq!(record.task_id, Some(43));
+        assert_eq!(record.restored_from, Some(restored_id.to_string()));
+    }
+
+    #[test]
+    fn test_get_task_expiry() {
+        let now = DateTime::<Utc>::from_timestamp(1_700_000_000, 0).unwrap();
+        let clock = crate::test_util::set_mock_clock(now);
+        let grace = Duration::try_seconds(5).unwrap();
+        let departure = now + Duration::try_hours(1).unwrap();
+
+        // no requested expiry, or one before departure
+        assert_eq!(get_task_expiry(departure, None, grace).unwrap(), departure);
+
+        let request_expiry = now + Duration::try_minutes(10).unwrap();
+        assert_eq!(
+            get_task_expiry(departure, Some(request_expiry), grace).unwrap(),
+            request_expiry
+        );
+
+        // just expired, still within the grace period
+        clock.advance(Duration::try_minutes(10).unwrap() + Duration::try_milliseconds(50).unwrap());
+        assert_eq!(
+            get_task_expiry(departure, Some(request_expiry), grace).unwrap(),
+            request_expiry + grace
+        );
+
+        // expired after the grace period
+        clock.advance(Duration::try_seconds(6).unwrap());
+        let e = get_task_expiry(departure, Some(request_expiry), grace).unwrap_err();
+        assert_eq!(e.code(), tonic::Code::FailedPrecondition);
+        assert_eq!(e.message(), "Itinerary expired, please query again.");
+
+        // the grace period doesn't extend past departure
+        let departure = clock.now() + Duration::try_seconds(2).unwrap();
+        let request_expiry = clock.now() - Duration::try_seconds(1).unwrap();
+        assert_eq!(
+            get_task_expiry(departure, Some(request_expiry), grace).unwrap(),
+            departure
+        );
+
+        // already departed
+        clock.advance(Duration::try_seconds(2).unwrap());
+        let e = get_task_expiry(departure, Some(request_expiry), grace).unwrap_err();
+        assert_eq!(e.code(), tonic::Code::FailedPrecondition);
+
+        crate::clock::set_test_clock(None);
+    }
+
+    #[test]
+    fn test_check_departure_lead() {
+        let now = Utc::now();
+        let lead = Duration::try_minutes(3).unwrap();
+
+        check_departure_lead(now + lead, now, lead).unwrap();
+        check_departure_lead(now + Duration::try_hours(1).unwrap(), now, lead).unwrap();
+
+        // too soon, or already in the past
+        let e =
+            check_departure_lead(now + Duration::try_minutes(2).unwrap(), now, lead).unwrap_err();
+        assert_eq!(e.code(), tonic::Code::FailedPrecondition);
+
+        let e =
+            check_departure_lead(now - Duration::try_seconds(1).unwrap(), now, lead).unwrap_err();
+        assert_eq!(e.code(), tonic::Code::FailedPrecondition);
+    }
+
+    #[test]
+    fn test_check_blackouts() {
+        let now = Utc::now();
+        let schedule = FlightPlanSchedule {
+            origin_vertiport_id: Uuid::new_v4().to_string(),
+            origin_vertipad_id: Uuid::new_v4().to_string(),
+            origin_timeslot_start: now,
+            origin_timeslot_end: now,
+            target_vertiport_id: Uuid::new_v4().to_string(),
+            target_vertipad_id: Uuid::new_v4().to_string(),
+            target_timeslot_start: now + Duration::try_minutes(30).unwrap(),
+            target_timeslot_end: now + Duration::try_minutes(30).unwrap(),
+            vehicle_id: Uuid::new_v4().to_string(),
+            path: None,
+        };
+
+        let blackout = Timeslot::new(
+            now + Duration::try_minutes(20).unwrap(),
+            now + Duration::try_hours(1).unwrap(),
+        )
+        .unwrap();
+
+        check_blackouts(&[], &[schedule.clone()]).unwrap();
+
+        let e = check_blackouts(&[blackout], &[schedule]).unwrap_err();
+        assert_eq!(e.code(), tonic::Code::FailedPrecondition);
+    }
+}
@@ -0,0 +1,195 @@
+//! This module contains the gRPC estimate_deadheads endpoint implementation.
+
+use svc_storage_client_grpc::prelude::flight_plan;
+use tonic::{Response, Status};
+
+use super::query_flight::query_aircraft_itineraries;
+use crate::grpc::server::grpc_server::{
+    DeadheadEstimate, EstimateDeadheadsRequest, EstimateDeadheadsResponse,
+};
+use crate::router::flight_plan::FlightPlanSchedule;
+use crate::router::vertiport::get_distance_meters;
+use lib_common::uuid::Uuid;
+
+/// Estimates the distance and time in the air of a deadhead flight plan
+fn get_deadhead_estimate(flight_plan: flight_plan::Data) -> Result<DeadheadEstimate, Status> {
+    let schedule = FlightPlanSchedule::try_from(flight_plan.clone()).map_err(|e| {
+        grpc_error!("Invalid deadhead flight plan: {e}");
+        Status::internal("Invalid deadhead flight plan.")
+    })?;
+
+    let duration_seconds = (schedule.target_timeslot_start - schedule.origin_timeslot_end)
+        .num_seconds()
+        .max(0);
+
+    let distance_meters: f64 = schedule
+        .path
+        .unwrap_or_default()
+        .windows(2)
+        .map(|segment| get_distance_meters(&segment[0], &segment[1]).0)
+        .sum();
+
+    Ok(DeadheadEstimate {
+        distance_meters,
+        duration_seconds: u32::try_from(duration_seconds).unwrap_or(u32::MAX),
+        flight_plan: Some(flight_plan),
+    })
+}
+
+/// Splits the deadhead flights of an itinerary from the requested flight
+///  between the origin and target vertiports
+fn get_deadhead_estimates(
+    mut flight_plans: Vec<flight_plan::Data>,
+    origin_vertiport_id: &str,
+    target_vertiport_id: &str,
+) -> Result<EstimateDeadheadsResponse, Status> {
+    let index = flight_plans
+        .iter()
+        .position(|fp| {
+            fp.origin_vertiport_id.as_deref() == Some(origin_vertiport_id)
+                && fp.target_vertiport_id.as_deref() == Some(target_vertiport_id)
+        })
+        .ok_or_else(|| {
+            grpc_error!(
+                "Itinerary has no flight from {origin_vertiport_id} to {target_vertiport_id}."
+            );
+            Status::internal("Invalid itinerary.")
+        })?;
+
+    // flight plans before the requested flight take the aircraft
+    //  to the origin, those after take it away from the target
+    let post_deadhead = flight_plans.split_off(index + 1).into_iter().next();
+    let pre_deadhead = match index {
+        0 => None,
+        _ => flight_plans.into_iter().nth(index - 1),
+    };
+
+    Ok(EstimateDeadheadsResponse {
+        pre_deadhead: pre_deadhead.map(get_deadhead_estimate).transpose()?,
+        post_deadhead: post_deadhead.map(get_deadhead_estimate).transpose()?,
+    })
+}
+
+/// Estimates the deadhead flights an aircraft would fly for a flight
+///  query, as in the itinerary `query_flight` would return for it.
+/// Nothing is booked.
+#[cfg(not(tarpaulin_include))]
+// no_coverage: (R5) needs running backend, integration tests
+pub async fn estimate_deadheads(
+    request: EstimateDeadheadsRequest,
+) -> Result<Response<EstimateDeadheadsResponse>, Status> {
+    let query = request
+        .query
+        .ok_or_else(|| Status::invalid_argument("query: must be provided."))?;
+
+    Uuid::parse_str(&request.vehicle_id).map_err(|e| {
+        grpc_warn!("Invalid vehicle ID {}: {e}", request.vehicle_id);
+        Status::invalid_argument("vehicle_id: must be a UUID.")
+    })?;
+
+    let origin_vertiport_id = query.origin_vertiport_id.clone();
+    let target_vertiport_id = query.target_vertiport_id.clone();
+    let itinerary = query_aircraft_itineraries(query, &request.vehicle_id)
+        .await?
+        .itineraries
+        .into_iter()
+        .next()
+        .ok_or_else(|| {
+            grpc_info!("Aircraft {} can't fly the query.", request.vehicle_id);
+            Status::not_found("Aircraft can't fly the query.")
+        })?;
+
+    get_deadhead_estimates(
+        itinerary.flight_plans,
+        &origin_vertiport_id,
+        &target_vertiport_id,
+    )
+    .map(Response::new)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use lib_common::time::{DateTime, Duration, Utc};
+    use svc_storage_client_grpc::prelude::{GeoLineString, GeoPoint};
+
+    fn get_flight_plan(
+        origin_vertiport_id: &str,
+        target_vertiport_id: &str,
+        departure: DateTime<Utc>,
+        minutes: i64,
+    ) -> flight_plan::Data {
+        let arrival = departure + Duration::try_minutes(minutes).unwrap();
+        flight_plan::Data {
+            origin_vertiport_id: Some(origin_vertiport_id.to_string()),
+            origin_vertipad_id: Uuid::new_v4().to_string(),
+            origin_timeslot_start: Some(departure.into()),
+            origin_timeslot_end: Some(departure.into()),
+            target_vertiport_id: Some(target_vertiport_id.to_string()),
+            target_vertipad_id: Uuid::new_v4().to_string(),
+            target_timeslot_start: Some(arrival.into()),
+            target_timeslot_end: Some(arrival.into()),
+            vehicle_id: Uuid::new_v4().to_string(),
+            path: Some(GeoLineString {
+                points: vec![
+                    GeoPoint {
+                        latitude: 52.0,
+                        longitude: 4.0,
+                        altitude: 100.0,
+                    },
+                    GeoPoint {
+                        latitude: 52.1,
+                        longitude: 4.0,
+                        altitude: 100.0,
+                    },
+                ],
+            }),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_get_deadhead_estimates_match_itinerary() {
+        let now = Utc::now();
+        let hangar = Uuid::new_v4().to_string();
+        let origin = Uuid::new_v4().to_string();
+        let target = Uuid::new_v4().to_string();
+
+        // deadhead to the origin, the requested flight, deadhead home
+        let itinerary = vec![
+            get_flight_plan(&hangar, &origin, now, 10),
+            get_flight_plan(
+                &origin,
+                &target,
+                now + Duration::try_minutes(10).unwrap(),
+                20,
+            ),
+            get_flight_plan(
+                &target,
+                &hangar,
+                now + Duration::try_minutes(30).unwrap(),
+                15,
+            ),
+        ];
+
+        let estimates = get_deadhead_estimates(itinerary.clone(), &origin, &target).unwrap();
+        let pre = estimates.pre_deadhead.unwrap();
+        assert_eq!(pre.flight_plan, Some(itinerary[0].clone()));
+        assert_eq!(pre.duration_seconds, 600);
+        // 0.1 degrees of latitude
+        assert!((pre.distance_meters - 11_119.0).abs() < 1.0);
+
+        let post = estimates.post_deadhead.unwrap();
+        assert_eq!(post.flight_plan, Some(itinerary[2].clone()));
+        assert_eq!(post.duration_seconds, 900);
+
+        // aircraft already at the origin and staying at the target
+        let estimates = get_deadhead_estimates(itinerary[1..2].to_vec(), &origin, &target).unwrap();
+        assert!(estimates.pre_deadhead.is_none());
+        assert!(estimates.post_deadhead.is_none());
+
+        // requested flight missing from the itinerary
+        let e = get_deadhead_estimates(itinerary, &target, &origin).unwrap_err();
+        assert_eq!(e.code(), tonic::Code::Internal);
+    }
+}
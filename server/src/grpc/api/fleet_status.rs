@@ -0,0 +1,160 @@
+//! This module contains the gRPC get_fleet_status endpoint implementation.
+
+use lib_common::time::{DateTime, Utc};
+use lib_common::uuid::Uuid;
+use tonic::{Response, Status};
+
+use crate::clock::get_clock;
+use crate::grpc::client::get_clients;
+use crate::grpc::server::grpc_server::{
+    GetFleetStatusRequest, GetFleetStatusResponse, VehicleStatus,
+};
+
+use crate::router::flight_plan::*;
+use crate::router::vehicle::*;
+
+/// Checks the request and returns the vertiport to filter by, if any,
+///  and the time of the status.
+fn validate_fleet_status_request(
+    request: &GetFleetStatusRequest,
+) -> Result<(Option<String>, DateTime<Utc>), Status> {
+    let vertiport_id = match &request.vertiport_id {
+        Some(id) => Some(
+            Uuid::parse_str(id)
+                .map_err(|_| Status::invalid_argument("vertiport_id: must be a valid UUID."))?
+                .to_string(),
+        ),
+        None => None,
+    };
+
+    let time = match request.time.clone() {
+        Some(time) => time.into(),
+        None => get_clock().now(),
+    };
+
+    Ok((vertiport_id, time))
+}
+
+/// Gets the status of each aircraft, keeping only aircraft at or headed
+///  to the vertiport if one is provided.
+fn get_fleet_status_list(
+    aircraft: &[Aircraft],
+    flight_plans: &[FlightPlanSchedule],
+    vertiport_id: Option<&str>,
+    time: &DateTime<Utc>,
+) -> Vec<VehicleStatus> {
+    aircraft
+        .iter()
+        .map(|a| get_aircraft_status(a, flight_plans, time))
+        .filter(|status| vertiport_id.map_or(true, |id| status.vertiport_id == id))
+        .map(|status| VehicleStatus {
+            vehicle_id: status.vehicle_id,
+            vertiport_id: status.vertiport_id,
+            vertipad_id: status.vertipad_id,
+            next_flight_plan: status.next_flight_plan.map(Into::into),
+        })
+        .collect()
+}
+
+/// Gets where each aircraft is scheduled to be and its next flight
+#[cfg(not(tarpaulin_include))]
+// no_coverage: (R5) needs running backend, integration tests
+pub async fn get_fleet_status(
+    request: GetFleetStatusRequest,
+) -> Result<Response<GetFleetStatusResponse>, Status> {
+    let (vertiport_id, time) = validate_fleet_status_request(&request).map_err(|e| {
+        grpc_warn!("Invalid fleet status request: {}", e.message());
+        e
+    })?;
+
+    let clients = get_clients().await;
+    let flight_plans = get_sorted_flight_plans(clients).await.map_err(|e| {
+        grpc_error!("{}", e);
+        Status::internal("Could not get existing flight plans.")
+    })?;
+
+    let aircraft = get_aircraft(clients, None).await.map_err(|e| {
+        grpc_error!("{}", e);
+        Status::internal("Could not get aircraft.")
+    })?;
+
+    let vehicles = get_fleet_status_list(&aircraft, &flight_plans, vertiport_id.as_deref(), &time);
+    grpc_info!("found status of {} aircraft.", vehicles.len());
+    Ok(Response::new(GetFleetStatusResponse { vehicles }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use lib_common::time::Duration;
+    use svc_storage_client_grpc::prelude::vehicle;
+
+    fn get_aircraft_at(hangar_id: &str) -> Aircraft {
+        let models = crate::test_util::get_test_vehicle_models();
+        Aircraft::try_from((
+            vehicle::Object {
+                id: Uuid::new_v4().to_string(),
+                data: Some(vehicle::Data {
+                    schedule: Some(
+                        "DTSTART:20221020T180000Z;DURATION:PT24H
+                    RRULE:FREQ=DAILY"
+                            .to_owned(),
+                    ),
+                    hangar_id: Some(hangar_id.to_string()),
+                    hangar_bay_id: Some(Uuid::new_v4().to_string()),
+                    ..Default::default()
+                }),
+            },
+            &models,
+        ))
+        .unwrap()
+    }
+
+    #[test]
+    fn test_validate_fleet_status_request() {
+        let vertiport_id = Uuid::new_v4().to_string();
+        let time = Utc::now() + Duration::try_hours(1).unwrap();
+        let request = GetFleetStatusRequest {
+            vertiport_id: Some(vertiport_id.clone()),
+            time: Some(time.into()),
+        };
+
+        let (id, t) = validate_fleet_status_request(&request).unwrap();
+        assert_eq!(id, Some(vertiport_id));
+        assert_eq!(t, time);
+
+        // all vertiports, now
+        let before = Utc::now();
+        let (id, t) = validate_fleet_status_request(&GetFleetStatusRequest::default()).unwrap();
+        assert!(id.is_none());
+        assert!(t >= before);
+
+        let request = GetFleetStatusRequest {
+            vertiport_id: Some("invalid".to_string()),
+            time: None,
+        };
+        let e = validate_fleet_status_request(&request).unwrap_err();
+        assert_eq!(e.code(), tonic::Code::InvalidArgument);
+        assert_eq!(e.message(), "vertiport_id: must be a valid UUID.");
+    }
+
+    #[test]
+    fn test_get_fleet_status_list() {
+        let vertiport_1 = Uuid::new_v4().to_string();
+        let vertiport_2 = Uuid::new_v4().to_string();
+        let aircraft = vec![
+            get_aircraft_at(&vertiport_1),
+            get_aircraft_at(&vertiport_2),
+            get_aircraft_at(&vertiport_2),
+        ];
+
+        let now = Utc::now();
+        let all = get_fleet_status_list(&aircraft, &[], None, &now);
+        assert_eq!(all.len(), 3);
+
+        let filtered = get_fleet_status_list(&aircraft, &[], Some(&vertiport_2), &now);
+        assert_eq!(filtered.len(), 2);
+        assert!(filtered.iter().all(|s| s.vertiport_id == vertiport_2));
+        assert!(filtered.iter().all(|s| s.next_flight_plan.is_none()));
+    }
+}
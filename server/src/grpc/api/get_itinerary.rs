@@ -0,0 +1,333 @@
+//! This module contains the gRPC get_itinerary endpoint implementation.
+
+use lib_common::time::{DateTime, Utc};
+use lib_common::uuid::to_uuid;
+use std::collections::HashMap;
+use svc_storage_client_grpc::prelude::{flight_plan, Id};
+use tonic::{Code, Response, Status};
+
+use crate::grpc::api::query_flight::get_vehicle_info;
+use crate::grpc::client::{get_clients, GrpcClients};
+use crate::grpc::server::grpc_server::{GetItineraryRequest, Itinerary, PadSelection};
+
+/// Maps a storage error for the given resource to a gRPC status
+fn get_storage_status(e: &Status, resource: &str) -> Status {
+    match e.code() {
+        Code::NotFound => Status::not_found(format!("{resource} not found.")),
+        _ => Status::internal(format!("Could not get {resource}.")),
+    }
+}
+
+/// Sorts the flight plans of an itinerary from earliest to latest departure
+pub fn sort_flight_plans(mut flight_plans: Vec<flight_plan::Data>) -> Vec<flight_plan::Data> {
+    flight_plans.sort_by_key(|fp| {
+        fp.origin_timeslot_start
+            .clone()
+            .map(DateTime::<Utc>::from)
+            .unwrap_or(DateTime::<Utc>::MIN_UTC)
+    });
+
+    flight_plans
+}
+
+/// Gets the ID of the user that owns an itinerary
+#[cfg(not(tarpaulin_include))]
+// no_coverage: (R5) needs running backend, integration tests
+pub async fn get_itinerary_owner(itinerary_id: &str) -> Result<String, Status> {
+    let itinerary_id = to_uuid(itinerary_id)
+        .ok_or_else(|| Status::invalid_argument("itinerary_id: must be a valid UUID."))?
+        .to_string();
+
+    get_clients()
+        .await
+        .storage()
+        .await
+        .itinerary
+        .get_by_id(Id {
+            id: itinerary_id.clone(),
+        })
+        .await
+        .map_err(|e| {
+            grpc_warn!("Could not get itinerary {itinerary_id}: {e}");
+            get_storage_status(&e, "Itinerary")
+        })?
+        .into_inner()
+        .data
+        .map(|data| data.user_id)
+        .ok_or_else(|| {
+            grpc_error!("Itinerary {itinerary_id} has no data.");
+            Status::internal("Itinerary has no data.")
+        })
+}
+
+/// Gets a booked itinerary and its flight plans from storage.
+/// Returns them in the same shape as the itineraries from `query_flight`.
+#[cfg(not(tarpaulin_include))]
+// no_coverage: (R5) needs running backend, integration tests
+pub async fn get_itinerary(request: GetItineraryRequest) -> Result<Response<Itinerary>, Status> {
+    let itinerary_id = to_uuid(&request.itinerary_id)
+        .ok_or_else(|| Status::invalid_argument("itinerary_id: must be a valid UUID."))?
+        .to_string();
+
+    let clients = get_clients().await;
+
+    clients
+        .storage()
+        .await
+        .itinerary
+        .get_by_id(Id {
+            id: itinerary_id.clone(),
+        })
+        .await
+        .map_err(|e| {
+            grpc_warn!("Could not get itinerary {itinerary_id}: {e}");
+            get_storage_status(&e, "Itinerary")
+        })?;
+
+    let flight_plans = get_itinerary_flight_plans(clients, &itinerary_id).await?;
+
+    // All flight plans in an itinerary use the same aircraft
+    let vehicle = match flight_plans.first() {
+        Some(fp) => get_vehicle_info(clients, &fp.vehicle_id, &mut HashMap::new()).await,
+        None => None,
+    };
+
+    grpc_info!(
+        "get_itinerary returning {} flight plans for itinerary {itinerary_id}.",
+        flight_plans.len()
+    );
+
+    Ok(Response::new(Itinerary {
+        flight_plans,
+        vehicle,
+        // TODO(R5): alternative routes are only known when querying
+        alternative_routes: vec![],
+        local_times: vec![],
+        estimated_price: 0,
+        deadhead_reasons: vec![],
+        path_fallback: false,
+        pad_selection: PadSelection::Unspecified as i32,
+        arrival_estimate: None,
+    }))
+}
+
+/// Gets the flight plans of an itinerary from storage, in departure order
+#[cfg(not(tarpaulin_include))]
+// no_coverage: (R5) needs running backend, integration tests
+pub async fn get_itinerary_flight_plans(
+    clients: &GrpcClients,
+    itinerary_id: &str,
+) -> Result<Vec<flight_plan::Data>, Status> {
+    let flight_plan_ids = clients
+        .storage()
+        .await
+        .itinerary_flight_plan_link
+        .get_linked_ids(Id {
+            id: itinerary_id.to_string(),
+        })
+        .await
+        .map_err(|e| {
+            grpc_error!("Could not get flight plans for itinerary {itinerary_id}: {e}");
+            get_storage_status(&e, "Itinerary flight plans")
+        })?
+        .into_inner()
+        .ids;
+
+    let mut flight_plans = vec![];
+    for id in flight_plan_ids {
+        let data = clients
+            .storage()
+            .await
+            .flight_plan
+            .get_by_id(Id { id: id.clone() })
+            .await
+            .map_err(|e| {
+                grpc_error!("Could not get flight plan {id} of itinerary {itinerary_id}: {e}");
+                get_storage_status(&e, "Flight plan")
+            })?
+            .into_inner()
+            .data
+            .ok_or_else(|| {
+                grpc_error!("Flight plan {id} of itinerary {itinerary_id} has no data.");
+                Status::internal("Flight plan has no data.")
+            })?;
+
+        flight_plans.push(data);
+    }
+
+    Ok(sort_flight_plans(flight_plans))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use lib_common::time::Duration;
+
+    #[test]
+    fn test_get_storage_status() {
+        let status = get_storage_status(&Status::not_found("gone"), "Itinerary");
+        assert_eq!(status.code(), Code::NotFound);
+        assert_eq!(status.message(), "Itinerary not found.");
+
+        let status = get_storage_status(&Status::unavailable("down"), "Itinerary");
+        assert_eq!(status.code(), Code::Internal);
+        assert_eq!(status.message(), "Could not get Itinerary.");
+    }
+
+    #[test]
+    fn test_sort_flight_plans() {
+        let now = Utc::now();
+        let make_plan = |offset_minutes: i64| flight_plan::Data {
+            origin_timeslot_start: Some(
+                (now + Duration::try_minutes(offset_minutes).unwrap()).into(),
+            ),
+            ..Default::default()
+        };
+
+        let flight_plans = sort_flight_plans(vec![make_plan(30), make_plan(-10), make_plan(10)]);
+        assert_eq!(
+            flight_plans,
+            vec![make_plan(-10), make_plan(10), make_plan(30)]
+        );
+    }
+
+    #[tokio::test]
+    #[cfg(feature = "stub_backends")]
+    async fn test_get_itinerary() {
+        use crate::router::flight_plan::FlightPlanSchedule;
+        use lib_common::uuid::Uuid;
+        use svc_storage_client_grpc::prelude::{itinerary, IdList};
+
+        lib_common::logger::get_log_handle().await;
+        ut_info!("start");
+
+        let clients = get_clients().await;
+        let now = Utc::now();
+        let vehicle_id = Uuid::new_v4().to_string();
+        let make_plan = |offset_minutes: i64| -> flight_plan::Data {
+            let start = now + Duration::try_minutes(offset_minutes).unwrap();
+            FlightPlanSchedule {
+                origin_vertiport_id: Uuid::new_v4().to_string(),
+                origin_vertipad_id: Uuid::new_v4().to_string(),
+                origin_timeslot_start: start,
+                origin_timeslot_end: start + Duration::try_minutes(1).unwrap(),
+                target_vertiport_id: Uuid::new_v4().to_string(),
+                target_vertipad_id: Uuid::new_v4().to_string(),
+                target_timeslot_start: start + Duration::try_minutes(20).unwrap(),
+                target_timeslot_end: start + Duration::try_minutes(21).unwrap(),
+                vehicle_id: vehicle_id.clone(),
+                path: Some(vec![]),
+            }
+            .into()
+        };
+
+        // Create the itinerary, second leg first
+        let mut expected = vec![];
+        let mut flight_plan_ids = vec![];
+        for offset_minutes in [60, 30] {
+            let object = clients
+                .storage()
+                .await
+                .flight_plan
+                .insert(make_plan(offset_minutes))
+                .await
+                .unwrap()
+                .into_inner()
+                .object
+                .unwrap();
+
+            flight_plan_ids.push(object.id);
+            expected.insert(0, object.data.unwrap());
+        }
+
+        let itinerary_id = clients
+            .storage()
+            .await
+            .itinerary
+            .insert(itinerary::Data {
+                user_id: Uuid::new_v4().to_string(),
+                status: itinerary::ItineraryStatus::Active as i32,
+            })
+            .await
+            .unwrap()
+            .into_inner()
+            .object
+            .unwrap()
+            .id;
+
+        clients
+            .storage()
+            .await
+            .itinerary_flight_plan_link
+            .link(itinerary::ItineraryFlightPlans {
+                id: itinerary_id.clone(),
+                other_id_list: Some(IdList {
+                    ids: flight_plan_ids,
+                }),
+            })
+            .await
+            .unwrap();
+
+        // Fetch it back
+        let itinerary = get_itinerary(GetItineraryRequest { itinerary_id })
+            .await
+            .unwrap()
+            .into_inner();
+
+        assert_eq!(itinerary.flight_plans, expected);
+        assert!(itinerary.alternative_routes.is_empty());
+
+        // Unknown and invalid IDs
+        let e = get_itinerary(GetItineraryRequest {
+            itinerary_id: Uuid::new_v4().to_string(),
+        })
+        .await
+        .unwrap_err();
+        assert_eq!(e.code(), Code::NotFound);
+
+        let e = get_itinerary(GetItineraryRequest {
+            itinerary_id: "invalid".to_string(),
+        })
+        .await
+        .unwrap_err();
+        assert_eq!(e.code(), Code::InvalidArgument);
+
+        ut_info!("success");
+    }
+    #[tokio::test]
+    #[cfg(feature = "stub_backends")]
+    async fn test_get_itinerary_owner() {
+        use lib_common::uuid::Uuid;
+        use svc_storage_client_grpc::prelude::itinerary;
+
+        lib_common::logger::get_log_handle().await;
+        ut_info!("start");
+
+        let user_id = Uuid::new_v4().to_string();
+        let itinerary_id = get_clients()
+            .await
+            .storage()
+            .await
+            .itinerary
+            .insert(itinerary::Data {
+                user_id: user_id.clone(),
+                status: itinerary::ItineraryStatus::Active as i32,
+            })
+            .await
+            .unwrap()
+            .into_inner()
+            .object
+            .unwrap()
+            .id;
+
+        let owner_id = get_itinerary_owner(&itinerary_id).await.unwrap();
+        assert_eq!(owner_id, user_id);
+
+        let e = get_itinerary_owner(&Uuid::new_v4().to_string())
+            .await
+            .unwrap_err();
+        assert_eq!(e.code(), Code::NotFound);
+
+        ut_info!("success");
+    }
+}
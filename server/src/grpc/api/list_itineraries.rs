@@ -0,0 +1,182 @@
+//! This module contains the gRPC list_user_itineraries endpoint implementation.
+
+use crate::grpc::api::get_itinerary::{get_itinerary_flight_plans, sort_flight_plans};
+use crate::grpc::client::{get_clients, search_all_pages};
+use crate::grpc::server::grpc_server::{
+    ListUserItinerariesRequest, ListUserItinerariesResponse, UserItinerary,
+};
+use crate::tasks::pool::RedisPool;
+use crate::tasks::soft_cancel::SoftCancellation;
+use lib_common::uuid::to_uuid;
+use svc_storage_client_grpc::prelude::{itinerary, AdvancedSearchFilter};
+use tonic::Status;
+
+/// Returns the user's itineraries: the active ones, followed by the
+///  soft-cancelled ones that can still be restored if `include_recoverable`
+pub fn get_user_itineraries(
+    active: Vec<UserItinerary>,
+    cancellations: Vec<(String, SoftCancellation)>,
+    user_id: &str,
+    include_recoverable: bool,
+) -> Vec<UserItinerary> {
+    if !include_recoverable {
+        return active;
+    }
+
+    let recoverable = cancellations
+        .into_iter()
+        .filter(|(_, cancellation)| cancellation.user_id == user_id)
+        .map(|(itinerary_id, cancellation)| UserItinerary {
+            itinerary_id,
+            flight_plans: sort_flight_plans(
+                cancellation
+                    .flight_plans
+                    .into_iter()
+                    .map(Into::into)
+                    .collect(),
+            ),
+            recoverable: true,
+        });
+
+    active.into_iter().chain(recoverable).collect()
+}
+
+/// Lists the active itineraries of a user and, if requested, the
+///  soft-cancelled ones still within their grace period
+#[cfg(not(tarpaulin_include))]
+// no_coverage: (R5) needs running backend, integration tests
+pub async fn list_user_itineraries(
+    request: ListUserItinerariesRequest,
+) -> Result<ListUserItinerariesResponse, Status> {
+    let user_id = to_uuid(&request.user_id)
+        .ok_or_else(|| Status::invalid_argument("user_id: must be a valid UUID."))?
+        .to_string();
+
+    let clients = get_clients().await;
+    let page_size = crate::config::get_config().await.storage_page_size;
+    let filter = AdvancedSearchFilter::search_is_null("deleted_at".to_owned())
+        .and_equals("user_id".to_string(), user_id.clone())
+        .and_equals(
+            "status".to_string(),
+            (itinerary::ItineraryStatus::Active as i32).to_string(),
+        );
+
+    let itineraries = search_all_pages(filter, page_size, |filter| async move {
+        clients
+            .storage()
+            .await
+            .itinerary
+            .search(filter)
+            .await
+            .map(|response| response.into_inner().list)
+    })
+    .await
+    .map_err(|e| {
+        grpc_error!("Could not get itineraries of user {user_id}: {e}");
+        Status::internal("Could not get itineraries.")
+    })?;
+
+    let mut active = vec![];
+    for itinerary in itineraries {
+        let flight_plans = get_itinerary_flight_plans(clients, &itinerary.id).await?;
+        active.push(UserItinerary {
+            itinerary_id: itinerary.id,
+            flight_plans,
+            recoverable: false,
+        });
+    }
+
+    let cancellations = match request.include_recoverable {
+        false => vec![],
+        true => {
+            let mut pool = crate::tasks::pool::get_pool().await.ok_or_else(|| {
+                grpc_error!("Couldn't get the redis pool.");
+                Status::internal("Internal error.")
+            })?;
+
+            pool.get_soft_cancellations().await.map_err(|e| {
+                grpc_error!("Could not get soft cancellations: {e}");
+                Status::internal("Could not get recoverable itineraries.")
+            })?
+        }
+    };
+
+    let itineraries =
+        get_user_itineraries(active, cancellations, &user_id, request.include_recoverable);
+
+    grpc_info!(
+        "list_user_itineraries returning {} itineraries for user {user_id}.",
+        itineraries.len()
+    );
+
+    Ok(ListUserItinerariesResponse { itineraries })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::router::flight_plan::FlightPlanSchedule;
+    use lib_common::time::{Duration, Utc};
+    use lib_common::uuid::Uuid;
+
+    fn get_flight_plan(offset_minutes: i64) -> FlightPlanSchedule {
+        let start = Utc::now() + Duration::try_minutes(offset_minutes).unwrap();
+        FlightPlanSchedule {
+            origin_vertiport_id: Uuid::new_v4().to_string(),
+            origin_vertipad_id: Uuid::new_v4().to_string(),
+            origin_timeslot_start: start,
+            origin_timeslot_end: start + Duration::try_minutes(1).unwrap(),
+            target_vertiport_id: Uuid::new_v4().to_string(),
+            target_vertipad_id: Uuid::new_v4().to_string(),
+            target_timeslot_start: start + Duration::try_minutes(20).unwrap(),
+            target_timeslot_end: start + Duration::try_minutes(21).unwrap(),
+            vehicle_id: Uuid::new_v4().to_string(),
+            path: Some(vec![]),
+        }
+    }
+
+    #[test]
+    fn test_get_user_itineraries_recoverable() {
+        let user_id = Uuid::new_v4().to_string();
+        let active = vec![UserItinerary {
+            itinerary_id: Uuid::new_v4().to_string(),
+            flight_plans: vec![get_flight_plan(10).into()],
+            recoverable: false,
+        }];
+
+        // soft-cancelled by the user, second leg first, and by another user
+        let cancelled_id = Uuid::new_v4().to_string();
+        let (first_leg, second_leg) = (get_flight_plan(30), get_flight_plan(60));
+        let cancellations = vec![
+            (
+                cancelled_id.clone(),
+                SoftCancellation {
+                    user_id: user_id.clone(),
+                    flight_plans: vec![second_leg.clone(), first_leg.clone()],
+                },
+            ),
+            (
+                Uuid::new_v4().to_string(),
+                SoftCancellation {
+                    user_id: Uuid::new_v4().to_string(),
+                    flight_plans: vec![get_flight_plan(30)],
+                },
+            ),
+        ];
+
+        // absent unless requested
+        let itineraries =
+            get_user_itineraries(active.clone(), cancellations.clone(), &user_id, false);
+        assert_eq!(itineraries, active);
+
+        let itineraries = get_user_itineraries(active.clone(), cancellations, &user_id, true);
+        assert_eq!(itineraries.len(), 2);
+        assert_eq!(itineraries[0], active[0]);
+        assert_eq!(itineraries[1].itinerary_id, cancelled_id);
+        assert!(itineraries[1].recoverable);
+        assert_eq!(
+            itineraries[1].flight_plans,
+            vec![first_leg.into(), second_leg.into()]
+        );
+    }
+}
@@ -1,5 +1,15 @@
 //! gRPC API parent module
 
 pub mod cancel;
+pub mod check_flight_plan;
 pub mod create;
+pub mod estimate_deadheads;
+pub mod fleet_status;
+pub mod get_itinerary;
+pub mod list_itineraries;
 pub mod query_flight;
+pub mod reachable_vertiports;
+pub mod repositioning;
+pub mod restore;
+pub mod validate_schedule;
+pub mod vertipad_occupancy;
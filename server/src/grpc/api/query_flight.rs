@@ -1,18 +1,54 @@
 //! This module contains the gRPC query_flight endpoint implementation.
 
+use lib_common::time::chrono_tz::Tz;
 use lib_common::time::{DateTime, Duration, Utc};
 use lib_common::uuid::Uuid;
+use num_traits::FromPrimitive;
+use std::collections::{HashMap, HashSet};
 use std::fmt::{Display, Formatter, Result as FmtResult};
+use std::future::Future;
+use std::str::FromStr;
+use svc_gis_client_grpc::client::PointZ;
+use svc_storage_client_grpc::prelude::{flight_plan, vehicle, Id};
+use tokio::time::{timeout_at, Instant};
 use tonic::{Response, Status};
 
-use crate::grpc::client::get_clients;
-use crate::grpc::server::grpc_server::{Itinerary, QueryFlightRequest, QueryFlightResponse};
+use super::create::{check_departure_lead, get_departure_lead};
+use crate::clock::get_clock;
+use crate::grpc::client::{get_clients, GrpcClients};
+use crate::grpc::server::grpc_server::{
+    AircraftType as RequestAircraftType, AlternativeDestination, AlternativeRoute,
+    ArrivalEstimate as RequestArrivalEstimate, DeadheadReason as RequestDeadheadReason,
+    EndpointType as RequestEndpointType, Itinerary, LimitingConstraint, LocalTimes,
+    NoFlightsReason, PadSelection as RequestPadSelection, QueryFlightRequest, QueryFlightResponse,
+    Ranking as RequestRanking, VehicleInfo, Waypoint,
+};
 
+use crate::router::blackout::{get_blackout_periods, get_overlapping_blackout};
+use crate::router::eta::{estimate_arrival, get_arrival_variance, ArrivalVariance};
+use crate::router::exclusion::{get_excluded_locations, ExcludingRouter};
+use crate::router::fallback::{get_fallback_distance_percent, FallbackRouter};
 use crate::router::flight_plan::*;
-use crate::router::itinerary::calculate_itineraries;
+use crate::router::hazard::{
+    avoid_restricted_corridors, get_hazard_restrictions, get_restricted_corridors,
+    get_route_restriction, HazardRestriction,
+};
+use crate::router::itinerary::{
+    calculate_itineraries_with_rejections, DeadheadReason, DeadheadSearch, ItineraryError,
+    ItineraryOption, LimitingFactor, ParkingLimits, Rejections, ReturnPolicy,
+};
+use crate::router::lead_time::get_lead_times;
+use crate::router::pricing::{
+    estimate_price, get_cost_multiplier, get_price_rates, get_pricing_schedule,
+    rank_timeslot_pairs, PricingWindow, Ranking,
+};
 use crate::router::schedule::*;
+use crate::router::timezone::{get_timezone, get_vertiport_timezones, to_local_time};
+use crate::router::units::Meters;
 use crate::router::vehicle::*;
+use crate::router::vehicle_model::{get_vehicle_models, VehicleModels};
 use crate::router::vertiport::*;
+use crate::tasks::return_hold::get_eviction_priority;
 
 /// Time to block vertiport for cargo loading and takeoff
 pub const LOADING_AND_TAKEOFF_TIME_SECONDS: i64 = 60;
@@ -22,6 +58,21 @@ pub const LANDING_AND_UNLOADING_TIME_SECONDS: i64 = 60;
 pub const MAX_FLIGHT_QUERY_WINDOW_MINUTES: i64 = 720; // +/- 3 hours (6 total)
 /// Cannot schedule flight leaving within the next N minutes
 pub const ADVANCE_NOTICE_MINUTES: i64 = 3;
+/// Most nearby destinations suggested if the requested one is unavailable
+const MAX_ALTERNATIVE_DESTINATIONS: usize = 3;
+/// Most nearby destinations checked for availability
+const MAX_ALTERNATIVE_CANDIDATES: i32 = 10;
+
+/// The caller of a flight query
+#[derive(Debug, Clone, Default)]
+pub struct QueryCaller {
+    /// The user querying, whose own return holds the query may use
+    pub user_id: Option<String>,
+
+    /// If the caller is an operator, whose high priority and emergency
+    ///  queries may evict return holds
+    pub is_operator: bool,
+}
 
 /// Sanitized version of the gRPC query
 #[derive(Debug)]
@@ -32,6 +83,14 @@ struct FlightQuery {
     latest_arrival_time: DateTime<Utc>,
     required_loading_time: Duration,
     required_unloading_time: Duration,
+    excluded_vertiport_ids: HashSet<String>,
+    loop_waypoints: Vec<String>,
+    aircraft_type: Option<AircraftType>,
+    arrival_window: Option<Timeslot>,
+    ranking: Ranking,
+    max_total_travel: Option<Duration>,
+    min_results: usize,
+    cargo_hazard_class: Option<String>,
 }
 
 /// Error type for FlightQuery
@@ -40,6 +99,11 @@ enum FlightQueryError {
     VertiportId,
     Time,
     TimeRangeTooLarge,
+    ExcludedVertiport,
+    AircraftType,
+    Ranking,
+    EndpointType,
+    HazardClass,
     Internal,
 }
 
@@ -49,38 +113,146 @@ impl Display for FlightQueryError {
             FlightQueryError::VertiportId => write!(f, "Invalid vertiport ID"),
             FlightQueryError::Time => write!(f, "Invalid time"),
             FlightQueryError::TimeRangeTooLarge => write!(f, "Time range too large"),
+            FlightQueryError::ExcludedVertiport => write!(f, "Vertiport is excluded"),
+            FlightQueryError::AircraftType => write!(f, "Invalid aircraft type"),
+            FlightQueryError::Ranking => write!(f, "Invalid ranking"),
+            FlightQueryError::EndpointType => write!(f, "Invalid endpoint type"),
+            FlightQueryError::HazardClass => write!(f, "Invalid cargo hazard class"),
             FlightQueryError::Internal => write!(f, "Internal error"),
         }
     }
 }
 
+/// Gets the declared type of an endpoint, a vertiport if not provided
+fn get_endpoint_type(endpoint_type: Option<i32>) -> Result<RequestEndpointType, FlightQueryError> {
+    match endpoint_type {
+        None => Ok(RequestEndpointType::Vertiport),
+        Some(endpoint_type) => FromPrimitive::from_i32(endpoint_type).ok_or_else(|| {
+            grpc_warn!("Invalid endpoint type: {endpoint_type}");
+            FlightQueryError::EndpointType
+        }),
+    }
+}
+
+/// Checks that a vertiport ID is a UUID. Returns the normalized ID.
+fn get_vertiport_id(identifier: &str) -> Result<String, FlightQueryError> {
+    Uuid::parse_str(identifier)
+        .map(|id| id.to_string())
+        .map_err(|e| {
+            grpc_error!("Invalid vertiport ID {identifier}: {e}");
+            FlightQueryError::VertiportId
+        })
+}
+
+/// Checks that all required fields of a flight query are present and
+///  consistent, reporting the first offending field.
+fn validate_query_request(request: &QueryFlightRequest) -> Result<(), Status> {
+    if request.origin_vertiport_id.is_empty() {
+        return Err(Status::invalid_argument(
+            "origin_vertiport_id: must not be empty.",
+        ));
+    }
+
+    if request.target_vertiport_id.is_empty() {
+        return Err(Status::invalid_argument(
+            "target_vertiport_id: must not be empty.",
+        ));
+    }
+
+    let is_loop = !request.loop_waypoints.is_empty();
+    if !is_loop && request.origin_vertiport_id == request.target_vertiport_id {
+        return Err(Status::invalid_argument(
+            "target_vertiport_id: must differ from origin_vertiport_id.",
+        ));
+    }
+
+    if is_loop && request.origin_vertiport_id != request.target_vertiport_id {
+        return Err(Status::invalid_argument(
+            "loop_waypoints: origin and target vertiport must match for loop flights.",
+        ));
+    }
+
+    for waypoint in request.loop_waypoints.iter() {
+        if !(-90.0..=90.0).contains(&waypoint.latitude)
+            || !(-180.0..=180.0).contains(&waypoint.longitude)
+        {
+            return Err(Status::invalid_argument(
+                "loop_waypoints: latitude must be within [-90, 90] and longitude within [-180, 180] degrees.",
+            ));
+        }
+
+        // svc-gis routes between named nodes
+        if waypoint.name.as_deref().unwrap_or_default().is_empty() {
+            return Err(Status::invalid_argument(
+                "loop_waypoints: must name a waypoint known to svc-gis.",
+            ));
+        }
+    }
+
+    if request.earliest_departure_time.is_none() {
+        return Err(Status::invalid_argument(
+            "earliest_departure_time: must be provided.",
+        ));
+    }
+
+    if request.latest_arrival_time.is_none() {
+        return Err(Status::invalid_argument(
+            "latest_arrival_time: must be provided.",
+        ));
+    }
+
+    Ok(())
+}
+
 impl TryFrom<QueryFlightRequest> for FlightQuery {
     type Error = FlightQueryError;
 
     fn try_from(request: QueryFlightRequest) -> Result<Self, Self::Error> {
         const ERROR_PREFIX: &str = "(try_from)";
 
-        let origin_vertiport_id = Uuid::parse_str(&request.origin_vertiport_id)
-            .map_err(|_| {
-                grpc_error!(
-                    "{} Invalid departure vertiport ID: {}",
-                    ERROR_PREFIX,
-                    request.origin_vertiport_id
-                );
-                FlightQueryError::VertiportId
-            })?
-            .to_string();
+        // aircraft depart from and land on vertipads
+        if get_endpoint_type(request.origin_type)? != RequestEndpointType::Vertiport {
+            grpc_warn!("{} flights must depart from a vertiport.", ERROR_PREFIX);
+            return Err(FlightQueryError::EndpointType);
+        }
 
-        let arrival_vertiport_id = Uuid::parse_str(&request.target_vertiport_id)
-            .map_err(|e| {
-                grpc_error!(
-                    "{} Invalid departure vertiport ID {}: {e}",
-                    ERROR_PREFIX,
-                    request.target_vertiport_id
-                );
-                FlightQueryError::VertiportId
-            })?
-            .to_string();
+        let origin_vertiport_id = get_vertiport_id(&request.origin_vertiport_id)?;
+        let mut loop_waypoints: Vec<String> = request
+            .loop_waypoints
+            .iter()
+            .map(|waypoint| waypoint.name.clone().unwrap_or_default())
+            .collect();
+
+        // Flights to a waypoint are flown as loop flights back to the
+        //  origin vertiport
+        let arrival_vertiport_id = match get_endpoint_type(request.target_type)? {
+            RequestEndpointType::Vertiport => get_vertiport_id(&request.target_vertiport_id)?,
+            RequestEndpointType::Waypoint => {
+                loop_waypoints = vec![request.target_vertiport_id.clone()];
+                origin_vertiport_id.clone()
+            }
+        };
+
+        let excluded_vertiport_ids = request
+            .excluded_vertiport_ids
+            .iter()
+            .map(|id| {
+                Uuid::parse_str(id).map(|id| id.to_string()).map_err(|e| {
+                    grpc_error!("{} Invalid excluded vertiport ID {id}: {e}", ERROR_PREFIX);
+                    FlightQueryError::VertiportId
+                })
+            })
+            .collect::<Result<HashSet<String>, FlightQueryError>>()?;
+
+        if excluded_vertiport_ids.contains(&origin_vertiport_id)
+            || excluded_vertiport_ids.contains(&arrival_vertiport_id)
+        {
+            grpc_warn!(
+                "{} departure or arrival vertiport is excluded.",
+                ERROR_PREFIX
+            );
+            return Err(FlightQueryError::ExcludedVertiport);
+        }
 
         let latest_arrival_time: DateTime<Utc> = request
             .latest_arrival_time
@@ -128,7 +300,13 @@ impl TryFrom<QueryFlightRequest> for FlightQuery {
             FlightQueryError::Internal
         })?;
 
-        if earliest_departure_time < (Utc::now() + delta) {
+        // Emergency flights may depart right away, but not in the past
+        let advance_notice = match request.emergency {
+            true => Duration::zero(),
+            false => delta,
+        };
+
+        if earliest_departure_time < (get_clock().now() + advance_notice) {
             grpc_warn!("{} earliest departure time is in the past, or within the next {ADVANCE_NOTICE_MINUTES} minutes.", ERROR_PREFIX);
             return Err(FlightQueryError::Time);
         }
@@ -149,6 +327,91 @@ impl TryFrom<QueryFlightRequest> for FlightQuery {
                 FlightQueryError::Time
             })?;
 
+        let aircraft_type = match request.aircraft_type {
+            None => None,
+            Some(aircraft_type) => match FromPrimitive::from_i32(aircraft_type) {
+                Some(RequestAircraftType::Cargo) => Some(AircraftType::Cargo),
+                Some(RequestAircraftType::Passenger) => Some(AircraftType::Passenger),
+                None => {
+                    grpc_warn!("{} invalid aircraft type: {aircraft_type}", ERROR_PREFIX);
+                    return Err(FlightQueryError::AircraftType);
+                }
+            },
+        };
+
+        let preferred_departure_time: Option<DateTime<Utc>> =
+            request.preferred_departure_time.map(DateTime::<Utc>::from);
+        if let Some(preferred) = preferred_departure_time {
+            if preferred < earliest_departure_time || preferred > latest_arrival_time {
+                grpc_warn!(
+                    "{} preferred departure time is outside of the window.",
+                    ERROR_PREFIX
+                );
+                return Err(FlightQueryError::Time);
+            }
+        }
+
+        let ranking = match (preferred_departure_time, request.ranking) {
+            // a preferred departure time takes precedence over the ranking
+            (Some(preferred), _) => Ranking::PreferredDeparture(preferred),
+            (None, None) => Ranking::Earliest,
+            (None, Some(ranking)) => match FromPrimitive::from_i32(ranking) {
+                Some(RequestRanking::Earliest) => Ranking::Earliest,
+                Some(RequestRanking::LowestCost) => Ranking::LowestCost,
+                Some(RequestRanking::EarliestArrival) => Ranking::EarliestArrival,
+                None => {
+                    grpc_warn!("{} invalid ranking: {ranking}", ERROR_PREFIX);
+                    return Err(FlightQueryError::Ranking);
+                }
+            },
+        };
+
+        // Tighter precision narrows the part of the window the flight may land in
+        let arrival_window = match request.required_arrival_precision_seconds {
+            None => None,
+            Some(precision) => {
+                let precision = Duration::try_seconds(precision.into()).ok_or_else(|| {
+                    grpc_error!(
+                        "{} error creating arrival precision duration.",
+                        ERROR_PREFIX
+                    );
+                    FlightQueryError::Time
+                })?;
+
+                let window_start = (latest_arrival_time - precision).max(earliest_departure_time);
+                let window = Timeslot::new(window_start, latest_arrival_time).map_err(|e| {
+                    grpc_error!("{} invalid arrival window: {e}", ERROR_PREFIX);
+                    FlightQueryError::Time
+                })?;
+
+                Some(window)
+            }
+        };
+
+        let max_total_travel = match request.max_total_travel_seconds {
+            None => None,
+            Some(seconds) => Some(Duration::try_seconds(seconds.into()).ok_or_else(|| {
+                grpc_error!("{} error creating max travel duration.", ERROR_PREFIX);
+                FlightQueryError::Time
+            })?),
+        };
+
+        let cargo_hazard_class = match request.cargo_hazard_class {
+            None => None,
+            Some(_) if !request.is_cargo => {
+                grpc_warn!(
+                    "{} hazard class provided for a passenger flight.",
+                    ERROR_PREFIX
+                );
+                return Err(FlightQueryError::HazardClass);
+            }
+            Some(class) if class.trim().is_empty() => {
+                grpc_warn!("{} empty cargo hazard class.", ERROR_PREFIX);
+                return Err(FlightQueryError::HazardClass);
+            }
+            Some(class) => Some(class.trim().to_string()),
+        };
+
         Ok(FlightQuery {
             origin_vertiport_id,
             arrival_vertiport_id,
@@ -157,246 +420,2812 @@ impl TryFrom<QueryFlightRequest> for FlightQuery {
             // TODO(R5): Get needed loading/unloading times from request
             required_loading_time,
             required_unloading_time,
+            excluded_vertiport_ids,
+            loop_waypoints,
+            aircraft_type,
+            arrival_window,
+            ranking,
+            max_total_travel,
+            min_results: request.min_results.unwrap_or(0) as usize,
+            cargo_hazard_class,
         })
     }
 }
 
-/// Finds the first possible flight for customer location, flight type and requested time.
-/// TODO(R5): Return a stream of messages for live updates on query progress
-pub async fn query_flight(
-    request: QueryFlightRequest,
-) -> Result<Response<QueryFlightResponse>, Status> {
-    let request = FlightQuery::try_from(request).map_err(|e| {
-        grpc_error!("{}", e);
-        let error_str = "Invalid flight query request";
-        Status::invalid_argument(error_str)
-    })?;
+impl From<(vehicle::Object, &VehicleModels)> for VehicleInfo {
+    fn from((vehicle, models): (vehicle::Object, &VehicleModels)) -> Self {
+        let data = vehicle.data.unwrap_or_default();
+        let model_name = models
+            .get(&data.vehicle_model_id)
+            .map(|model| model.name.clone())
+            .unwrap_or_default();
 
-    let timeslot = Timeslot::new(request.earliest_departure_time, request.latest_arrival_time)
-        .map_err(|e| {
-            grpc_error!("Invalid timeslot: {e}");
-            Status::internal("Invalid timeslot")
-        })?;
+        VehicleInfo {
+            vehicle_id: vehicle.id,
+            model_name,
+            registration_number: data.registration_number,
+        }
+    }
+}
 
-    let clients = get_clients().await;
+/// Converts alternative paths from the router into routes for the response
+fn get_alternative_routes(paths: &[(Vec<PointZ>, Meters)]) -> Vec<AlternativeRoute> {
+    paths
+        .iter()
+        .map(|(points, distance)| AlternativeRoute {
+            waypoints: points
+                .iter()
+                .map(|point| Waypoint {
+                    latitude: point.latitude,
+                    longitude: point.longitude,
+                    altitude_meters: point.altitude_meters,
+                    name: None,
+                })
+                .collect(),
+            distance_meters: distance.0,
+        })
+        .collect()
+}
 
-    // Get all flight plans from this time to latest departure time (including partially fitting flight plans)
-    // - this assumes that all landed flights have updated vehicle.last_vertiport_id (otherwise we would need to look in to the past)
-    let existing_flight_plans: Vec<FlightPlanSchedule> =
-        get_sorted_flight_plans(clients).await.map_err(|e| {
-            grpc_error!("{}", e);
-            let error_str = "Could not get existing flight plans.";
-            Status::internal(error_str)
-        })?;
+/// Converts the scheduled times of flight plans into the local time of
+///  their vertiports, one entry per flight plan
+fn get_local_times(
+    flight_plans: &[flight_plan::Data],
+    timezones: &HashMap<String, Tz>,
+) -> Vec<LocalTimes> {
+    let local_time = |time: &Option<prost_wkt_types::Timestamp>, timezone: Tz| {
+        time.clone()
+            .map(|time| to_local_time(time.into(), timezone))
+            .unwrap_or_default()
+    };
 
-    grpc_debug!("found existing flight plans: {:?}", existing_flight_plans);
+    flight_plans
+        .iter()
+        .map(|fp| {
+            let origin_timezone = get_timezone(
+                timezones,
+                fp.origin_vertiport_id.as_deref().unwrap_or_default(),
+            );
+            let target_timezone = get_timezone(
+                timezones,
+                fp.target_vertiport_id.as_deref().unwrap_or_default(),
+            );
 
-    //
-    // TODO(R5): Determine if there's an open space for cargo on an existing flight plan
-    //
+            LocalTimes {
+                origin_timezone: origin_timezone.name().to_string(),
+                origin_timeslot_start: local_time(&fp.origin_timeslot_start, origin_timezone),
+                origin_timeslot_end: local_time(&fp.origin_timeslot_end, origin_timezone),
+                target_timezone: target_timezone.name().to_string(),
+                target_timeslot_start: local_time(&fp.target_timeslot_start, target_timezone),
+                target_timeslot_end: local_time(&fp.target_timeslot_end, target_timezone),
+            }
+        })
+        .collect()
+}
 
-    //
-    // Get available timeslots for departure vertiport that are large enough to
-    //  fit the required loading and takeoff time.
-    //
-    let timeslot_pairs = get_timeslot_pairs(
+/// Gets the details of a vehicle, looking up each vehicle at most once
+///  per query
+#[cfg(not(tarpaulin_include))]
+// no_coverage: (R5) needs running backend, integration tests
+pub async fn get_vehicle_info(
+    clients: &GrpcClients,
+    vehicle_id: &str,
+    cache: &mut HashMap<String, Option<VehicleInfo>>,
+) -> Option<VehicleInfo> {
+    if let Some(info) = cache.get(vehicle_id) {
+        return info.clone();
+    }
+
+    let models = match get_vehicle_models().await {
+        Ok(models) => models,
+        Err(e) => {
+            grpc_warn!("Could not get vehicle models: {e}");
+            return None;
+        }
+    };
+
+    let info = match clients
+        .storage()
+        .await
+        .vehicle
+        .get_by_id(Id {
+            id: vehicle_id.to_string(),
+        })
+        .await
+    {
+        Ok(response) => Some(VehicleInfo::from((response.into_inner(), &models))),
+        Err(e) => {
+            grpc_warn!("Could not get vehicle {vehicle_id}: {e}");
+            None
+        }
+    };
+
+    cache.insert(vehicle_id.to_string(), info.clone());
+    info
+}
+
+/// Adds the query to the waitlist if the requester opted in
+#[cfg(not(tarpaulin_include))]
+// no_coverage: (R5) need redis backend to test this
+async fn waitlist_query(request: &QueryFlightRequest) {
+    let Some(user_id) = &request.waitlist_user_id else {
+        return;
+    };
+
+    match crate::tasks::waitlist::add_to_waitlist(request, user_id).await {
+        Ok(entry_id) => grpc_info!("Added query to waitlist as entry {entry_id}."),
+        Err(e) => grpc_warn!("Could not add query to waitlist: {e}"),
+    }
+}
+
+/// Returns an empty response with the blackout reason if the requested
+///  window overlaps a network-wide blackout
+fn get_blackout_response(
+    blackouts: &[Timeslot],
+    timeslot: &Timeslot,
+) -> Option<QueryFlightResponse> {
+    let blackout = get_overlapping_blackout(blackouts, timeslot)?;
+    grpc_info!(
+        "query_flight overlaps network blackout from {} to {}.",
+        blackout.time_start(),
+        blackout.time_end()
+    );
+
+    Some(QueryFlightResponse {
+        itineraries: vec![],
+        reason: Some(NoFlightsReason::NetworkBlackout as i32),
+        window_earliest_departure_time: None,
+        window_latest_arrival_time: None,
+        limiting_constraint: None,
+        partial: false,
+        alternative_destinations: vec![],
+    })
+}
+
+/// Returns an empty response with the hazard restricted reason if the
+///  cargo hazard class is barred from the requested route
+fn get_hazard_response(
+    restrictions: &[HazardRestriction],
+    request: &FlightQuery,
+) -> Option<QueryFlightResponse> {
+    let hazard_class = request.cargo_hazard_class.as_ref()?;
+    let restriction = get_route_restriction(
+        restrictions,
+        hazard_class,
         &request.origin_vertiport_id,
-        None,
         &request.arrival_vertiport_id,
-        None,
-        &request.required_loading_time,
-        &request.required_unloading_time,
-        &timeslot,
-        &existing_flight_plans,
-        clients,
-    )
-    .await
-    .map_err(|e| {
-        grpc_error!("{}", e);
-        let error_str = "Could not get timeslot pairs.";
-        Status::internal(error_str)
-    })?;
+    )?;
+    grpc_info!(
+        "query_flight hazard class {hazard_class} is barred from {:?}.",
+        restriction.node
+    );
+
+    Some(get_hazard_restricted_response())
+}
+
+/// Returns an empty response with the hazard restricted reason
+fn get_hazard_restricted_response() -> QueryFlightResponse {
+    QueryFlightResponse {
+        itineraries: vec![],
+        reason: Some(NoFlightsReason::HazardRestricted as i32),
+        window_earliest_departure_time: None,
+        window_latest_arrival_time: None,
+        limiting_constraint: None,
+        partial: false,
+        alternative_destinations: vec![],
+    }
+}
+
+/// Routes timeslot pairs around the corridors barring the cargo hazard
+///  class. Returns an empty response with the hazard restricted reason
+///  if there were routes and all of them enter a restricted corridor.
+#[cfg(not(tarpaulin_include))]
+// no_coverage: (R5) needs running backend, integration tests
+async fn remove_restricted_routes(
+    timeslot_pairs: &mut Vec<TimeslotPair>,
+    request: &FlightQuery,
+    restrictions: &[HazardRestriction],
+    corridor_width: Meters,
+    clients: &GrpcClients,
+) -> Result<Option<QueryFlightResponse>, Status> {
+    let Some(hazard_class) = request.cargo_hazard_class.as_ref() else {
+        return Ok(None);
+    };
 
     if timeslot_pairs.is_empty() {
-        let info_str = "No routes available for the given time.";
-        grpc_info!("{info_str}");
-        return Err(Status::not_found(info_str));
+        return Ok(None);
     }
 
-    //
-    // Get all aircraft availabilities
-    //
-    let aircraft = get_aircraft(clients, None).await.map_err(|e| {
-        grpc_error!("{}", e);
-        let error_str = "Could not get aircraft.";
-        Status::internal(error_str)
-    })?;
+    let corridors = get_restricted_corridors(restrictions, hazard_class, corridor_width, clients)
+        .await
+        .map_err(|e| {
+            grpc_error!("Could not locate restricted corridors: {e}");
+            Status::internal("Could not check hazard restrictions.")
+        })?;
 
-    let aircraft_gaps = get_aircraft_availabilities(
-        &existing_flight_plans,
-        &timeslot.time_start(),
-        &aircraft,
-        &timeslot,
-    )
-    .map_err(|e| {
-        grpc_error!("{}", e);
-        let error_str = "Could not get aircraft availabilities.";
-        Status::internal(error_str)
-    })?;
+    *timeslot_pairs = avoid_restricted_corridors(std::mem::take(timeslot_pairs), &corridors);
+    if !timeslot_pairs.is_empty() {
+        return Ok(None);
+    }
 
-    grpc_debug!("aircraft gaps: {:#?}", aircraft_gaps);
+    grpc_info!("query_flight routes all enter a corridor barring hazard class {hazard_class}.");
+    Ok(Some(get_hazard_restricted_response()))
+}
 
-    //
-    // See which aircraft are available to fly the route,
-    //  including deadhead flights
-    //
-    grpc_debug!("timeslot pairs count {:?}", timeslot_pairs);
-    let itineraries = calculate_itineraries(
-        &request.required_loading_time,
-        &request.required_unloading_time,
-        &timeslot_pairs,
-        &aircraft_gaps,
+/// Returns an empty response with the no aircraft on duty reason if
+///  every aircraft was excluded for being off duty in the window
+fn get_off_duty_response(
+    aircraft_count: usize,
+    exclusions: &HashMap<String, AircraftExclusion>,
+) -> Option<QueryFlightResponse> {
+    if aircraft_count == 0
+        || exclusions.len() < aircraft_count
+        || exclusions
+            .values()
+            .any(|e| *e != AircraftExclusion::OffDuty)
+    {
+        return None;
+    }
+
+    grpc_info!("query_flight window is outside the schedule of all {aircraft_count} aircraft.");
+    Some(QueryFlightResponse {
+        itineraries: vec![],
+        reason: Some(NoFlightsReason::NoAircraftOnDuty as i32),
+        window_earliest_departure_time: None,
+        window_latest_arrival_time: None,
+        limiting_constraint: None,
+        partial: false,
+        alternative_destinations: vec![],
+    })
+}
+
+/// Suggests the nearby vertiports that `is_available` accepts, nearest
+///  first, with their distance from the requested destination
+async fn get_alternative_destinations<F, Fut>(
+    nearby: Vec<(String, Meters)>,
+    mut is_available: F,
+) -> Vec<AlternativeDestination>
+where
+    F: FnMut(String) -> Fut,
+    Fut: Future<Output = bool>,
+{
+    let mut alternatives = vec![];
+    for (vertiport_id, distance) in nearby {
+        if !is_available(vertiport_id.clone()).await {
+            grpc_debug!("Alternative destination {vertiport_id} has no availability.");
+            continue;
+        }
+
+        alternatives.push(AlternativeDestination {
+            vertiport_id,
+            distance_meters: distance.0,
+        });
+
+        if alternatives.len() >= MAX_ALTERNATIVE_DESTINATIONS {
+            break;
+        }
+    }
+
+    alternatives
+}
+
+/// Suggests vertiports near the requested destination that can be
+///  flown to from the origin within the window, and that the cargo
+///  hazard class isn't barred from
+#[cfg(not(tarpaulin_include))]
+// no_coverage: (R5) needs running backend, integration tests
+async fn find_alternative_destinations(
+    request: &FlightQuery,
+    timeslot: &Timeslot,
+    existing_flight_plans: &[FlightPlanSchedule],
+    hazard_restrictions: &[HazardRestriction],
+    radius: Meters,
+    clients: &GrpcClients,
+) -> Vec<AlternativeDestination> {
+    let is_restricted = |id: &str| {
+        request.cargo_hazard_class.as_ref().is_some_and(|class| {
+            get_route_restriction(hazard_restrictions, class, &request.origin_vertiport_id, id)
+                .is_some()
+        })
+    };
+
+    let nearby = match get_nearby_vertiports(
+        &request.arrival_vertiport_id,
+        radius,
+        MAX_ALTERNATIVE_CANDIDATES,
         clients,
     )
     .await
-    .map_err(|e| {
-        let error_str = "Could not get itineraries";
-        grpc_error!("{error_str}: {e}");
-        Status::internal(error_str)
-    })?
-    .into_iter()
-    .map(|flight_plans| Itinerary { flight_plans })
-    .collect::<Vec<Itinerary>>();
+    {
+        Ok(nearby) => nearby
+            .into_iter()
+            .filter(|(id, _)| {
+                *id != request.origin_vertiport_id
+                    && !request.excluded_vertiport_ids.contains(id)
+                    && !is_restricted(id)
+            })
+            .collect::<Vec<(String, Meters)>>(),
+        Err(e) => {
+            grpc_warn!("Could not get vertiports to suggest: {e}");
+            return vec![];
+        }
+    };
 
-    grpc_debug!("itineraries count {:?}", itineraries);
+    let router = FallbackRouter::new(clients, clients, get_fallback_distance_percent().await);
+    let router = &router;
+
+    let is_available = |vertiport_id: String| async move {
+        get_timeslot_pairs(
+            &request.origin_vertiport_id,
+            None,
+            &vertiport_id,
+            None,
+            &request.required_loading_time,
+            &request.required_unloading_time,
+            timeslot,
+            request.arrival_window.as_ref(),
+            existing_flight_plans,
+            1,
+            clients,
+            router,
+        )
+        .await
+        .is_ok_and(|pairs| !pairs.is_empty())
+    };
+
+    get_alternative_destinations(nearby, is_available).await
+}
+
+/// Returns an empty response suggesting other destinations,
+///  if there are any
+fn get_alternative_destinations_response(
+    alternative_destinations: Vec<AlternativeDestination>,
+) -> Option<QueryFlightResponse> {
+    if alternative_destinations.is_empty() {
+        return None;
+    }
 
-    let response = QueryFlightResponse { itineraries };
     grpc_info!(
-        "query_flight returning: {} flight plans.",
-        &response.itineraries.len()
+        "query_flight destination unavailable, suggesting {} alternatives.",
+        alternative_destinations.len()
     );
 
-    Ok(Response::new(response))
+    Some(QueryFlightResponse {
+        itineraries: vec![],
+        reason: Some(NoFlightsReason::DestinationUnavailable as i32),
+        window_earliest_departure_time: None,
+        window_latest_arrival_time: None,
+        limiting_constraint: None,
+        partial: false,
+        alternative_destinations,
+    })
 }
 
-#[cfg(test)]
-#[cfg(feature = "stub_backends")]
-mod tests {
-    use super::*;
-    use crate::test_util::{ensure_storage_mock_data, get_vertiports_from_storage};
-    use lib_common::time::Utc;
-    use svc_storage_client_grpc::prelude::flight_plan::FlightPriority;
+/// Drops timeslot pairs with routes shorter than the minimum flight
+///  distance. Returns an empty response with the route too short reason
+///  if there were routes and all of them were dropped.
+fn remove_short_routes(
+    timeslot_pairs: &mut Vec<TimeslotPair>,
+    min_distance: Meters,
+) -> Option<QueryFlightResponse> {
+    if timeslot_pairs.is_empty() {
+        return None;
+    }
 
-    #[test]
-    fn test_duration_consts() {
-        Duration::try_minutes(MAX_FLIGHT_QUERY_WINDOW_MINUTES).unwrap();
-        Duration::try_minutes(ADVANCE_NOTICE_MINUTES).unwrap();
-        Duration::try_seconds(LOADING_AND_TAKEOFF_TIME_SECONDS).unwrap();
-        Duration::try_seconds(LANDING_AND_UNLOADING_TIME_SECONDS).unwrap();
+    timeslot_pairs.retain(|pair| pair.distance_meters >= min_distance);
+    if !timeslot_pairs.is_empty() {
+        return None;
     }
 
-    #[tokio::test]
-    async fn test_get_sorted_flight_plans() {
-        lib_common::logger::get_log_handle().await;
-        ut_info!("start");
+    grpc_info!("query_flight routes are all shorter than {min_distance}.");
+    Some(QueryFlightResponse {
+        itineraries: vec![],
+        reason: Some(NoFlightsReason::RouteTooShort as i32),
+        window_earliest_departure_time: None,
+        window_latest_arrival_time: None,
+        limiting_constraint: None,
+        partial: false,
+        alternative_destinations: vec![],
+    })
+}
 
-        ensure_storage_mock_data().await;
-        let clients = get_clients().await;
+/// Maps an error for a vertiport that can't be routed to a gRPC status
+pub fn get_endpoint_status(vertiport_id: &str, e: VertiportError) -> Status {
+    match e {
+        VertiportError::NotFound => {
+            Status::not_found(format!("Vertiport {vertiport_id} not found."))
+        }
+        VertiportError::NoVertipads => Status::failed_precondition(format!(
+            "Vertiport {vertiport_id} has no enabled vertipads, so no flights can be routed to or from it. Check that its vertipads were added to storage."
+        )),
+        _ => Status::internal(format!("Could not check vertiport {vertiport_id}.")),
+    }
+}
 
-        let expected_number_returned = 10;
-        let res = get_sorted_flight_plans(&clients).await;
-        ut_debug!("flight_plans returned: {:#?}", res);
+/// Returns when a query started at `started` must stop searching, or
+///  `None` if the budget is disabled. The budget covers the whole query,
+///  the route search and every window searched with `auto_widen` included.
+fn get_search_deadline(started: Instant, budget_ms: u32) -> Option<Instant> {
+    match budget_ms {
+        0 => None,
+        budget_ms => Some(started + std::time::Duration::from_millis(budget_ms.into())),
+    }
+}
 
-        assert!(res.is_ok());
-        assert_eq!(res.unwrap().len(), expected_number_returned);
-        ut_info!("success");
+/// Reports the constraint that limited the itineraries of a query, if
+///  fewer than the maximum number, or than `min_results`, were found
+fn get_limiting_constraint(
+    rejections: &Rejections,
+    itinerary_count: usize,
+    min_results: usize,
+) -> Option<i32> {
+    let constraint = match rejections.get_limiting_factor(itinerary_count, min_results)? {
+        LimitingFactor::PadCapacity => LimitingConstraint::PadCapacity,
+        LimitingFactor::AircraftAvailability => LimitingConstraint::AircraftAvailability,
+        LimitingFactor::Routing => LimitingConstraint::Routing,
+    };
+
+    grpc_info!("query_flight limited by {}.", constraint.as_str_name());
+    Some(constraint as i32)
+}
+
+/// Reports the criterion the vertipads were selected by: the ranking
+///  if it reordered the vertipad pairs, the configured selection otherwise
+fn get_pad_selection(
+    selection: &str,
+    ranking: Ranking,
+    windows: &[PricingWindow],
+) -> Result<RequestPadSelection, Status> {
+    let selection = match PadSelection::from_str(selection) {
+        Ok(PadSelection::EarliestAvailability) => RequestPadSelection::EarliestAvailability,
+        Ok(PadSelection::ShortestPath) => RequestPadSelection::ShortestPath,
+        Err(_) => return Err(Status::internal("Invalid vertipad selection.")),
+    };
+
+    match ranking {
+        Ranking::PreferredDeparture(_) => Ok(RequestPadSelection::PreferredDeparture),
+        // without pricing windows, every departure costs the same
+        Ranking::LowestCost if !windows.is_empty() => Ok(RequestPadSelection::LowestCost),
+        Ranking::Earliest | Ranking::EarliestArrival | Ranking::LowestCost => Ok(selection),
     }
+}
 
-    #[tokio::test]
-    #[cfg(feature = "stub_backends")]
-    async fn test_query_invalid() {
-        lib_common::logger::get_log_handle().await;
-        ut_info!("start");
+/// Returns the time the requested flight of an itinerary takes from
+///  loading to unloading. Waiting and deadhead flights aren't counted.
+fn get_travel_time(
+    flight_plans: &[flight_plan::Data],
+    deadhead_reasons: &[Option<DeadheadReason>],
+) -> Option<Duration> {
+    let (requested, _) = flight_plans
+        .iter()
+        .zip(deadhead_reasons)
+        .find(|(_, reason)| reason.is_none())?;
 
-        let vertiports = get_vertiports_from_storage().await.unwrap();
-        let mut query = QueryFlightRequest {
-            is_cargo: true,
-            persons: None,
-            weight_grams: Some(10),
-            priority: FlightPriority::Low as i32,
-            earliest_departure_time: None,
-            latest_arrival_time: None,
-            origin_vertiport_id: vertiports[0].id.clone(),
-            target_vertiport_id: vertiports[1].id.clone(),
-        };
+    let start: DateTime<Utc> = requested.origin_timeslot_start.clone()?.into();
+    let end: DateTime<Utc> = requested.target_timeslot_end.clone()?.into();
+    Some(end - start)
+}
 
-        // no latest arrival time
-        query.latest_arrival_time = None;
-        query.earliest_departure_time = Some(Utc::now().into());
-        let e = FlightQuery::try_from(query.clone()).unwrap_err();
-        assert_eq!(e, FlightQueryError::Time);
+/// Returns the time the requested flight of an itinerary lands
+fn get_arrival_time(
+    flight_plans: &[flight_plan::Data],
+    deadhead_reasons: &[Option<DeadheadReason>],
+) -> Option<DateTime<Utc>> {
+    let (requested, _) = flight_plans
+        .iter()
+        .zip(deadhead_reasons)
+        .find(|(_, reason)| reason.is_none())?;
 
-        // no earliest departure time
+    requested.target_timeslot_start.clone().map(Into::into)
+}
+
+/// Returns when the requested flight of an itinerary is likely to land
+fn get_arrival_estimate(
+    flight_plans: &[flight_plan::Data],
+    deadhead_reasons: &[Option<DeadheadReason>],
+    variance: &ArrivalVariance,
+) -> Option<RequestArrivalEstimate> {
+    let (requested, _) = flight_plans
+        .iter()
+        .zip(deadhead_reasons)
+        .find(|(_, reason)| reason.is_none())?;
+
+    // airborne from the end of the departure timeslot
+    let departure: DateTime<Utc> = requested.origin_timeslot_end.clone()?.into();
+    let arrival: DateTime<Utc> = requested.target_timeslot_start.clone()?.into();
+    let estimate = estimate_arrival(departure, arrival, variance)?;
+
+    Some(RequestArrivalEstimate {
+        p50: Some(estimate.p50.into()),
+        p90: Some(estimate.p90.into()),
+    })
+}
+
+/// Sorts itineraries by the arrival of their requested flight, earliest
+///  first. Itineraries landing at the same time keep their order.
+fn sort_by_arrival(itineraries: &mut [ItineraryOption]) {
+    itineraries.sort_by_key(|itinerary| {
+        get_arrival_time(&itinerary.flight_plans, &itinerary.deadhead_reasons)
+            .unwrap_or(DateTime::<Utc>::MAX_UTC)
+    });
+}
+
+/// Sorts itineraries by the cost multiplier when their requested flight
+///  departs, cheapest first. Equally priced itineraries keep their order.
+fn sort_by_cost(itineraries: &mut [ItineraryOption], windows: &[PricingWindow]) {
+    itineraries.sort_by(|a, b| {
+        let cost_a = get_cost_multiplier(windows, &a.quoted_flight.departure);
+        let cost_b = get_cost_multiplier(windows, &b.quoted_flight.departure);
+        cost_a.total_cmp(&cost_b)
+    });
+}
+
+/// Drops itineraries whose requested flight takes longer than
+///  `max_total_travel`
+fn remove_long_itineraries(itineraries: &mut Vec<ItineraryOption>, max_total_travel: Duration) {
+    let count = itineraries.len();
+    itineraries.retain(|itinerary| {
+        get_travel_time(&itinerary.flight_plans, &itinerary.deadhead_reasons)
+            .is_some_and(|travel_time| travel_time <= max_total_travel)
+    });
+
+    if itineraries.len() < count {
+        grpc_info!(
+            "query_flight dropped {} itineraries taking longer than {max_total_travel}.",
+            count - itineraries.len()
+        );
+    }
+}
+
+/// Converts the reasons for the flight plans of an itinerary,
+///  the requested flight is not a deadhead
+fn get_deadhead_reasons(reasons: &[Option<DeadheadReason>]) -> Vec<i32> {
+    reasons
+        .iter()
+        .map(|reason| {
+            let reason = match reason {
+                None => RequestDeadheadReason::NotDeadhead,
+                Some(DeadheadReason::PrePositioning) => RequestDeadheadReason::PrePositioning,
+                Some(DeadheadReason::Return) => RequestDeadheadReason::Return,
+                Some(DeadheadReason::Recharge) => RequestDeadheadReason::Recharge,
+            };
+
+            reason as i32
+        })
+        .collect()
+}
+
+/// Returns the latest arrival times to retry an `auto_widen` query with.
+/// The window doubles with each attempt until it reaches the horizon.
+fn get_widened_arrival_times(
+    earliest_departure_time: DateTime<Utc>,
+    latest_arrival_time: DateTime<Utc>,
+    horizon: Duration,
+) -> Vec<DateTime<Utc>> {
+    let mut window = latest_arrival_time - earliest_departure_time;
+    let mut arrival_times = vec![];
+    if window <= Duration::zero() {
+        return arrival_times;
+    }
+
+    while window < horizon {
+        window = (window * 2).min(horizon);
+        arrival_times.push(earliest_departure_time + window);
+    }
+
+    arrival_times
+}
+
+/// Queries progressively wider windows until flights are found, returning
+///  the first non-empty result along with the window that was searched.
+/// With `min_results`, wider windows are searched until that many flights
+///  are found, falling back to the most found if the horizon is reached.
+/// Only the widest attempt is waitlisted.
+async fn query_flight_widened<F, Fut>(
+    request: QueryFlightRequest,
+    horizon: Duration,
+    query: F,
+) -> Result<QueryFlightResponse, Status>
+where
+    F: Fn(QueryFlightRequest) -> Fut,
+    Fut: Future<Output = Result<QueryFlightResponse, Status>>,
+{
+    let (Some(earliest_departure_time), Some(latest_arrival_time)) = (
+        request.earliest_departure_time.clone(),
+        request.latest_arrival_time.clone(),
+    ) else {
+        return Err(Status::invalid_argument(
+            "earliest_departure_time and latest_arrival_time: must be provided.",
+        ));
+    };
+
+    let earliest_departure_time: DateTime<Utc> = earliest_departure_time.into();
+    let mut arrival_times: Vec<DateTime<Utc>> = vec![latest_arrival_time.into()];
+    arrival_times.extend(get_widened_arrival_times(
+        earliest_departure_time,
+        arrival_times[0],
+        horizon,
+    ));
+
+    let min_results = request.min_results.unwrap_or(0) as usize;
+    let mut fewer: Option<QueryFlightResponse> = None;
+    let attempts = arrival_times.len();
+    for (index, latest_arrival_time) in arrival_times.into_iter().enumerate() {
+        let is_last = index + 1 == attempts;
+        let mut attempt = request.clone();
+        attempt.latest_arrival_time = Some(latest_arrival_time.into());
+        if !is_last {
+            attempt.waitlist_user_id = None;
+        }
+
+        let result = match query(attempt).await {
+            Ok(result) => result,
+            Err(e) => match fewer.take() {
+                Some(fewer) => {
+                    grpc_info!(
+                        "No wider window searched ({}), returning fewer flights.",
+                        e.message()
+                    );
+                    return Ok(fewer);
+                }
+                None if e.code() == tonic::Code::NotFound && !is_last => {
+                    grpc_info!("No routes available until {latest_arrival_time}, widening window.");
+                    continue;
+                }
+                None => return Err(e),
+            },
+        };
+
+        // A wider window may find fewer flights within the time budget
+        if let Some(fewer) = fewer
+            .take()
+            .filter(|fewer| fewer.itineraries.len() > result.itineraries.len())
+        {
+            return Ok(fewer);
+        }
+
+        let result = QueryFlightResponse {
+            window_earliest_departure_time: Some(earliest_departure_time.into()),
+            window_latest_arrival_time: Some(latest_arrival_time.into()),
+            ..result
+        };
+
+        // The time budget is spent, no wider window can be searched
+        if result.partial {
+            grpc_info!("Flight query ran out of time until {latest_arrival_time}.");
+            return Ok(result);
+        }
+
+        // A blackout covers every wider window as well, while aircraft
+        //  may come on duty or be parked closer later in a wider window,
+        //  and the requested destination may free up
+        let is_final_reason = result.reason.is_some_and(|reason| {
+            reason != NoFlightsReason::NoAircraftOnDuty as i32
+                && reason != NoFlightsReason::TooManyDeadheads as i32
+                && reason != NoFlightsReason::DestinationUnavailable as i32
+        });
+        if result.itineraries.is_empty() && !is_final_reason && !is_last {
+            grpc_info!("No flights available until {latest_arrival_time}, widening window.");
+            continue;
+        }
+
+        if !result.itineraries.is_empty() && result.itineraries.len() < min_results && !is_last {
+            grpc_info!(
+                "{} of {min_results} flights available until {latest_arrival_time}, widening window.",
+                result.itineraries.len()
+            );
+            fewer = Some(result);
+            continue;
+        }
+
+        return Ok(result);
+    }
+
+    // there is always at least one attempt
+    Err(Status::internal("No flight query attempted."))
+}
+
+/// Finds the first possible flight for customer location, flight type and requested time.
+/// If no flights are found and a waitlist user ID was provided, the query is
+///  waitlisted and re-evaluated when a cancellation frees capacity on the route.
+/// If `auto_widen` is set, wider windows are searched before giving up.
+/// Operators' high priority and emergency queries may be offered the
+///  slots of lower priority return holds, which their booking evicts.
+/// TODO(R5): Return a stream of messages for live updates on query progress
+pub async fn query_flight(
+    request: QueryFlightRequest,
+    caller: &QueryCaller,
+) -> Result<Response<QueryFlightResponse>, Status> {
+    validate_query_request(&request).map_err(|e| {
+        grpc_warn!("Invalid flight query request: {}", e.message());
+        e
+    })?;
+
+    let config = crate::config::get_config().await;
+    let deadline = get_search_deadline(Instant::now(), config.query_budget_ms);
+    if !request.auto_widen {
+        return query_flight_window(request, caller, deadline)
+            .await
+            .map(Response::new);
+    }
+
+    let horizon_minutes: i64 = config.auto_widen_horizon_minutes.into();
+    let horizon = Duration::try_minutes(MAX_FLIGHT_QUERY_WINDOW_MINUTES.min(horizon_minutes))
+        .ok_or_else(|| {
+            grpc_error!("error creating auto widen horizon duration.");
+            Status::internal("Invalid auto widen horizon.")
+        })?;
+
+    query_flight_widened(request, horizon, |request| {
+        query_flight_window(request, caller, deadline)
+    })
+    .await
+    .map(Response::new)
+}
+
+/// Finds the itineraries one aircraft could fly for a flight query,
+///  without waitlisting or widening the window
+pub async fn query_aircraft_itineraries(
+    mut request: QueryFlightRequest,
+    vehicle_id: &str,
+) -> Result<QueryFlightResponse, Status> {
+    validate_query_request(&request).map_err(|e| {
+        grpc_warn!("Invalid flight query request: {}", e.message());
+        e
+    })?;
+
+    request.waitlist_user_id = None;
+    request.auto_widen = false;
+    let budget_ms = crate::config::get_config().await.query_budget_ms;
+    let deadline = get_search_deadline(Instant::now(), budget_ms);
+    query_flight_window_for(request, Some(vehicle_id), &QueryCaller::default(), deadline).await
+}
+
+/// Gets the free timeslots of the destination vertipads until the end of
+///  the searched aircraft availabilities, for parking aircraft there.
+/// Returns `None` if they can't be determined, aircraft aren't parked then.
+#[cfg(not(tarpaulin_include))]
+// no_coverage: (R5) needs running backend, integration tests
+async fn get_parking_limits(
+    vertiport_id: &str,
+    timeslot: &Timeslot,
+    existing_flight_plans: &[FlightPlanSchedule],
+    clients: &GrpcClients,
+) -> Option<ParkingLimits> {
+    let horizon = match get_availability_horizon(timeslot) {
+        Ok(horizon) => horizon,
+        Err(e) => {
+            grpc_error!("Could not get availability horizon: {e}");
+            return None;
+        }
+    };
+
+    let window = match Timeslot::new(timeslot.time_start(), horizon) {
+        Ok(window) => window,
+        Err(e) => {
+            grpc_error!("Invalid parking window: {e}");
+            return None;
+        }
+    };
+
+    match get_available_timeslots(
+        vertiport_id,
+        None,
+        existing_flight_plans,
+        &window,
+        &Duration::zero(),
+        clients,
+    )
+    .await
+    {
+        Ok(vertipad_timeslots) => Some(ParkingLimits {
+            horizon,
+            vertipad_timeslots,
+        }),
+        Err(e) => {
+            grpc_warn!("Could not get vertipad timeslots of {vertiport_id}: {e}");
+            None
+        }
+    }
+}
+
+/// Finds flights within the requested window, until the `deadline`
+async fn query_flight_window(
+    request: QueryFlightRequest,
+    caller: &QueryCaller,
+    deadline: Option<Instant>,
+) -> Result<QueryFlightResponse, Status> {
+    query_flight_window_for(request, None, caller, deadline).await
+}
+
+/// Finds flights within the requested window, flown by the given
+///  aircraft if provided. The search stops at the `deadline`, if any.
+async fn query_flight_window_for(
+    request: QueryFlightRequest,
+    vehicle_id: Option<&str>,
+    caller: &QueryCaller,
+    deadline: Option<Instant>,
+) -> Result<QueryFlightResponse, Status> {
+    let original_request = request.clone();
+    let request = FlightQuery::try_from(request).map_err(|e| {
+        grpc_error!("{}", e);
+        let error_str = "Invalid flight query request";
+        Status::invalid_argument(error_str)
+    })?;
+
+    if original_request.emergency {
+        grpc_warn!(
+            "(audit) emergency flight query from {} to {} departing at {}; advance notice waived and waitlist priority raised.",
+            request.origin_vertiport_id,
+            request.arrival_vertiport_id,
+            request.earliest_departure_time
+        );
+    }
+
+    let timeslot = Timeslot::new(request.earliest_departure_time, request.latest_arrival_time)
+        .map_err(|e| {
+            grpc_error!("Invalid timeslot: {e}");
+            Status::internal("Invalid timeslot")
+        })?;
+
+    //
+    // No flights can be scheduled during a network-wide blackout
+    //
+    let blackouts = get_blackout_periods().await.map_err(|e| {
+        grpc_error!("{e}");
+        Status::internal("Invalid blackout periods.")
+    })?;
+
+    if let Some(response) = get_blackout_response(&blackouts, &timeslot) {
+        return Ok(response);
+    }
+
+    //
+    // Hazardous cargo can't use restricted vertiports or corridors
+    //
+    let hazard_restrictions = get_hazard_restrictions().await.map_err(|e| {
+        grpc_error!("{e}");
+        Status::internal("Invalid hazard restrictions.")
+    })?;
+
+    if let Some(response) = get_hazard_response(&hazard_restrictions, &request) {
+        return Ok(response);
+    }
+
+    let clients = get_clients().await;
+    let config = crate::config::get_config().await;
+
+    // Routes, deadheads included, can't pass through excluded vertiports
+    let excluded_locations = get_excluded_locations(&request.excluded_vertiport_ids, clients)
+        .await
+        .map_err(|e| {
+            grpc_warn!("Could not locate excluded vertiports: {e}");
+            Status::internal("Could not locate excluded vertiports.")
+        })?;
+
+    let router = FallbackRouter::new(clients, clients, get_fallback_distance_percent().await);
+    let router = ExcludingRouter::new(&router, excluded_locations);
+
+    // Report vertiports that can't be routed up front, instead of
+    //  "no routes available" once the search comes up empty
+    let mut endpoints = vec![&request.origin_vertiport_id];
+    if request.arrival_vertiport_id != request.origin_vertiport_id {
+        endpoints.push(&request.arrival_vertiport_id);
+    }
+
+    for vertiport_id in endpoints {
+        check_vertiport_routable(vertiport_id, clients)
+            .await
+            .map_err(|e| {
+                grpc_warn!("Vertiport {vertiport_id} can't be routed: {e}");
+                get_endpoint_status(vertiport_id, e)
+            })?;
+    }
+
+    for name in request.loop_waypoints.iter() {
+        check_waypoint_known(name, clients).await.map_err(|e| {
+            grpc_warn!("Waypoint {name} can't be routed: {e}");
+            match e {
+                VertiportError::WaypointNotFound => {
+                    Status::not_found(format!("Waypoint {name} not found."))
+                }
+                _ => Status::internal(format!("Could not check waypoint {name}.")),
+            }
+        })?;
+    }
+
+    // Get all flight plans from this time to latest departure time (including partially fitting flight plans)
+    // - this assumes that all landed flights have updated vehicle.last_vertiport_id (otherwise we would need to look in to the past)
+    let mut existing_flight_plans: Vec<FlightPlanSchedule> =
+        get_sorted_flight_plans(clients).await.map_err(|e| {
+            grpc_error!("{}", e);
+            let error_str = "Could not get existing flight plans.";
+            Status::internal(error_str)
+        })?;
+
+    // Held returns are booked for the purposes of a query, unless held
+    //  for the querying user or for a lower priority booking that this
+    //  query's booking may evict
+    let priority = match original_request.emergency {
+        true => flight_plan::FlightPriority::Emergency as i32,
+        false => original_request.priority,
+    };
+
+    let eviction_priority = get_eviction_priority(priority, caller.is_operator);
+    match crate::tasks::return_hold::get_unavailable_held_flight_plans(
+        caller.user_id.as_deref(),
+        eviction_priority,
+    )
+    .await
+    {
+        Ok(held) => {
+            existing_flight_plans.extend(held);
+            existing_flight_plans.sort();
+        }
+        Err(e) => grpc_warn!("Could not get held returns: {e}"),
+    }
+
+    // Itineraries queued to be created are tentatively booked, so that
+    //  concurrent queries don't offer the same vertipad slots
+    if config.draft_occupancy {
+        match crate::tasks::get_queued_draft_flight_plans().await {
+            Ok(drafts) => {
+                existing_flight_plans.extend(drafts);
+                existing_flight_plans.sort();
+            }
+            Err(e) => grpc_warn!("Could not get draft itineraries: {e}"),
+        }
+    }
+
+    grpc_debug!("found existing flight plans: {:?}", existing_flight_plans);
+
+    //
+    // TODO(R5): Determine if there's an open space for cargo on an existing flight plan
+    //
+
+    //
+    // Get available timeslots for departure vertiport that are large enough to
+    //  fit the required loading and takeoff time.
+    //
+    let search = async {
+        if request.loop_waypoints.is_empty() {
+            get_timeslot_pairs(
+                &request.origin_vertiport_id,
+                None,
+                &request.arrival_vertiport_id,
+                None,
+                &request.required_loading_time,
+                &request.required_unloading_time,
+                &timeslot,
+                request.arrival_window.as_ref(),
+                &existing_flight_plans,
+                config.max_routes,
+                clients,
+                &router,
+            )
+            .await
+        } else {
+            get_loop_timeslot_pairs(
+                &request.origin_vertiport_id,
+                &request.loop_waypoints,
+                &request.required_loading_time,
+                &request.required_unloading_time,
+                &timeslot,
+                &existing_flight_plans,
+                clients,
+                &router,
+            )
+            .await
+        }
+    };
+
+    // Routes are searched within the time budget as well
+    let timeslot_pairs = match deadline {
+        Some(deadline) => timeout_at(deadline, search).await.map_err(|_| {
+            let error_str = "Flight query exceeded its time budget.";
+            grpc_warn!("{error_str} Searching routes.");
+            Status::deadline_exceeded(error_str)
+        })?,
+        None => search.await,
+    };
+
+    let mut timeslot_pairs = timeslot_pairs.map_err(|e| {
+        grpc_error!("{}", e);
+        let error_str = "Could not get timeslot pairs.";
+        Status::internal(error_str)
+    })?;
+
+    // Routed paths, loops included, can't enter restricted corridors
+    let corridor_width = Meters(config.hazard_corridor_width_meters.into());
+    if let Some(response) = remove_restricted_routes(
+        &mut timeslot_pairs,
+        &request,
+        &hazard_restrictions,
+        corridor_width,
+        clients,
+    )
+    .await?
+    {
+        return Ok(response);
+    }
+
+    // Hops between adjacent pads don't warrant a flight plan
+    let min_distance = Meters(config.min_flight_distance_meters.into());
+    if let Some(response) = remove_short_routes(&mut timeslot_pairs, min_distance) {
+        return Ok(response);
+    }
+
+    // Itineraries are built from the first pairs, so rank them up front
+    let pricing_schedule = get_pricing_schedule().await.map_err(|e| {
+        grpc_error!("{e}");
+        Status::internal("Invalid pricing schedule.")
+    })?;
+    rank_timeslot_pairs(&mut timeslot_pairs, request.ranking, &pricing_schedule);
+
+    if timeslot_pairs.is_empty() {
+        let info_str = "No routes available for the given time.";
+        grpc_info!("{info_str}");
+        waitlist_query(&original_request).await;
+
+        // Offer nearby destinations that can be flown to instead
+        let radius = Meters(config.alternative_destination_radius_meters.into());
+        if request.loop_waypoints.is_empty() && radius.0 > 0.0 {
+            let alternatives = find_alternative_destinations(
+                &request,
+                &timeslot,
+                &existing_flight_plans,
+                &hazard_restrictions,
+                radius,
+                clients,
+            )
+            .await;
+
+            if let Some(response) = get_alternative_destinations_response(alternatives) {
+                return Ok(response);
+            }
+        }
+
+        return Err(Status::not_found(info_str));
+    }
+
+    //
+    // Get all aircraft availabilities
+    //
+    let mut aircraft = get_aircraft(clients, vehicle_id.map(str::to_string))
+        .await
+        .map_err(|e| {
+            grpc_error!("{}", e);
+            match e {
+                VehicleError::NoSchedule | VehicleError::Schedule => {
+                    Status::failed_precondition("An aircraft has an invalid schedule.")
+                }
+                _ => Status::internal("Could not get aircraft."),
+            }
+        })?;
+
+    if let (Some(vehicle_id), true) = (vehicle_id, aircraft.is_empty()) {
+        grpc_warn!("Aircraft {vehicle_id} not found.");
+        return Err(Status::not_found("Aircraft not found."));
+    }
+
+    filter_aircraft_by_type(&mut aircraft, request.aircraft_type);
+
+    let connection_grace = Duration::try_minutes(config.connection_grace_minutes.into())
+        .ok_or_else(|| {
+            grpc_error!("error creating connection grace duration.");
+            Status::internal("Invalid connection grace.")
+        })?;
+
+    let mut aircraft_gaps = get_aircraft_availabilities(
+        &existing_flight_plans,
+        &timeslot.time_start(),
+        &aircraft,
+        &timeslot,
+        &connection_grace,
+    )
+    .map_err(|e| {
+        grpc_error!("{}", e);
+        let error_str = "Could not get aircraft availabilities.";
+        Status::internal(error_str)
+    })?;
+
+    // Aircraft can't be deadheaded out of or into excluded vertiports
+    remove_excluded_vertiports(&mut aircraft_gaps, &request.excluded_vertiport_ids);
+
+    // Only the aircraft best positioned for the query are considered
+    if config.max_aircraft_per_query > 0
+        && aircraft_gaps.len() > config.max_aircraft_per_query as usize
+    {
+        let vertiport_ids: HashSet<String> = aircraft_gaps
+            .values()
+            .flatten()
+            .map(|a| a.vertiport_id.clone())
+            .collect();
+
+        let distances =
+            get_vertiport_distances(&request.origin_vertiport_id, &vertiport_ids, clients)
+                .await
+                .unwrap_or_else(|e| {
+                    grpc_warn!("Could not rank aircraft by distance to the origin: {e}");
+                    HashMap::new()
+                });
+
+        limit_aircraft_availabilities(
+            &mut aircraft_gaps,
+            &distances,
+            config.max_aircraft_per_query,
+        );
+    }
+
+    let exclusions =
+        get_aircraft_exclusions(&aircraft, &aircraft_gaps, &timeslot).map_err(|e| {
+            grpc_error!("{}", e);
+            Status::internal("Could not get aircraft availabilities.")
+        })?;
+
+    if let Some(response) = get_off_duty_response(aircraft.len(), &exclusions) {
+        return Ok(response);
+    }
+
+    grpc_debug!("aircraft gaps: {:#?}", aircraft_gaps);
+
+    //
+    // See which aircraft are available to fly the route,
+    //  including deadhead flights
+    //
+    let return_policy = ReturnPolicy::from_str(&config.return_deadhead_policy)
+        .map_err(|_| Status::internal("Invalid return deadhead policy."))?;
+
+    // Aircraft may only be parked on destination vertipads free until
+    //  the end of the searched availabilities
+    let parking = match return_policy {
+        ReturnPolicy::Park => {
+            get_parking_limits(
+                &request.arrival_vertiport_id,
+                &timeslot,
+                &existing_flight_plans,
+                clients,
+            )
+            .await
+        }
+        _ => None,
+    };
+
+    let deadhead_search = DeadheadSearch {
+        enabled: config.enable_deadhead,
+        step: Duration::try_minutes(config.deadhead_search_step_minutes.into()).ok_or_else(
+            || {
+                grpc_error!("error creating deadhead search step duration.");
+                Status::internal("Invalid deadhead search step.")
+            },
+        )?,
+        attempts: config.deadhead_search_attempts,
+        max_legs: config.max_deadhead_legs,
+        return_policy,
+        parking: parking.as_ref(),
+    };
+
+    grpc_debug!("timeslot pairs count {:?}", timeslot_pairs);
+    let default_ground_time = GroundTime {
+        loading: request.required_loading_time,
+        unloading: request.required_unloading_time,
+    };
+    let aircraft_ground_times = get_aircraft_ground_times(&aircraft);
+    let aircraft_cruise_altitudes =
+        get_aircraft_cruise_altitudes(&aircraft, config.min_cruise_altitude_meters as f32);
+    let arrival_jitter = Duration::try_seconds(config.arrival_jitter_max_seconds.into())
+        .ok_or_else(|| {
+            grpc_error!("error creating arrival jitter duration.");
+            Status::internal("Invalid arrival jitter.")
+        })?;
+
+    let lead_times = get_lead_times().await.map_err(|e| {
+        grpc_error!("{e}");
+        Status::internal("Invalid vertiport lead times.")
+    })?;
+
+    let preferred_departure = match request.ranking {
+        Ranking::PreferredDeparture(preferred) => Some(preferred),
+        _ => None,
+    };
+
+    let (mut itineraries, reason, limiting_constraint, partial) =
+        match calculate_itineraries_with_rejections(
+            &default_ground_time,
+            &aircraft_ground_times,
+            &aircraft_cruise_altitudes,
+            &timeslot_pairs,
+            &aircraft_gaps,
+            &deadhead_search,
+            &arrival_jitter,
+            &lead_times,
+            get_clock(),
+            deadline,
+            request.min_results,
+            preferred_departure,
+            &router,
+        )
+        .await
+        {
+            Ok((itineraries, rejections)) if rejections.budget_exceeded => {
+                if itineraries.is_empty() {
+                    let error_str = "Flight query exceeded its time budget.";
+                    grpc_warn!("{error_str}");
+                    return Err(Status::deadline_exceeded(error_str));
+                }
+
+                (itineraries, None, None, true)
+            }
+            Ok((itineraries, rejections)) => {
+                let limiting_constraint =
+                    get_limiting_constraint(&rejections, itineraries.len(), request.min_results);
+                (itineraries, None, limiting_constraint, false)
+            }
+            Err(ItineraryError::TooManyDeadheads) => {
+                grpc_info!("query_flight needs more deadhead flights than allowed.");
+                (
+                    vec![],
+                    Some(NoFlightsReason::TooManyDeadheads as i32),
+                    None,
+                    false,
+                )
+            }
+            Err(e) => {
+                let error_str = "Could not get itineraries";
+                grpc_error!("{error_str}: {e}");
+                return Err(Status::internal(error_str));
+            }
+        };
+
+    // Deadhead flights may depart before the requested window,
+    //  strict lead times apply to the first flight of the itinerary
+    if let Some(lead) = get_departure_lead().await? {
+        let now = get_clock().now();
+        itineraries.retain(|itinerary| {
+            itinerary
+                .flight_plans
+                .iter()
+                .filter_map(|fp| fp.origin_timeslot_start.clone())
+                .map(DateTime::<Utc>::from)
+                .min()
+                .is_some_and(|departure| check_departure_lead(departure, now, lead).is_ok())
+        });
+    }
+
+    if let Some(max_total_travel) = request.max_total_travel {
+        remove_long_itineraries(&mut itineraries, max_total_travel);
+    }
+
+    // Pairs were ranked by when their vertipads are free, itineraries
+    //  by when they actually depart
+    match request.ranking {
+        Ranking::EarliestArrival => sort_by_arrival(&mut itineraries),
+        Ranking::LowestCost => sort_by_cost(&mut itineraries, &pricing_schedule),
+        Ranking::Earliest | Ranking::PreferredDeparture(_) => (),
+    }
+
+    // Vertiport timezones are only needed to present local times
+    let timezones = match original_request.include_local_times {
+        true => Some(get_vertiport_timezones().await.map_err(|e| {
+            grpc_error!("{e}");
+            Status::internal("Invalid vertiport timezones.")
+        })?),
+        false => None,
+    };
+
+    let price_rates = get_price_rates().await;
+    let pad_selection =
+        get_pad_selection(&config.pad_selection, request.ranking, &pricing_schedule)?;
+    let arrival_variance = get_arrival_variance().await.map_err(|e| {
+        grpc_error!("{e}");
+        Status::internal("Invalid arrival variance.")
+    })?;
+
+    // All flight plans in an itinerary use the same aircraft
+    let mut vehicle_cache = HashMap::new();
+    let mut itineraries_with_vehicles = vec![];
+    for itinerary in itineraries {
+        let vehicle = match itinerary.flight_plans.first() {
+            Some(fp) => get_vehicle_info(clients, &fp.vehicle_id, &mut vehicle_cache).await,
+            None => None,
+        };
+
+        let local_times = timezones
+            .as_ref()
+            .map(|timezones| get_local_times(&itinerary.flight_plans, timezones))
+            .unwrap_or_default();
+
+        let arrival_estimate = get_arrival_estimate(
+            &itinerary.flight_plans,
+            &itinerary.deadhead_reasons,
+            &arrival_variance,
+        );
+
+        itineraries_with_vehicles.push(Itinerary {
+            flight_plans: itinerary.flight_plans,
+            vehicle,
+            alternative_routes: get_alternative_routes(&itinerary.alternative_paths),
+            local_times,
+            estimated_price: estimate_price(
+                &price_rates,
+                &itinerary.quoted_flight,
+                &pricing_schedule,
+            ),
+            deadhead_reasons: get_deadhead_reasons(&itinerary.deadhead_reasons),
+            path_fallback: itinerary.path_fallback,
+            pad_selection: pad_selection as i32,
+            arrival_estimate,
+        });
+    }
+
+    let itineraries = itineraries_with_vehicles;
+
+    grpc_debug!("itineraries count {:?}", itineraries);
+
+    if itineraries.is_empty() {
+        waitlist_query(&original_request).await;
+    }
+
+    let response = QueryFlightResponse {
+        itineraries,
+        reason,
+        window_earliest_departure_time: None,
+        window_latest_arrival_time: None,
+        limiting_constraint,
+        partial,
+        alternative_destinations: vec![],
+    };
+    grpc_info!(
+        "query_flight returning: {} flight plans.",
+        &response.itineraries.len()
+    );
+
+    Ok(response)
+}
+
+#[cfg(test)]
+#[cfg(feature = "stub_backends")]
+mod tests {
+    use super::*;
+    use crate::router::pricing::QuotedFlight;
+    use crate::test_util::{ensure_storage_mock_data, get_vertiports_from_storage};
+    use lib_common::time::Utc;
+    use svc_storage_client_grpc::prelude::flight_plan::FlightPriority;
+    use svc_storage_client_grpc::prelude::AdvancedSearchFilter;
+
+    #[test]
+    fn test_duration_consts() {
+        Duration::try_minutes(MAX_FLIGHT_QUERY_WINDOW_MINUTES).unwrap();
+        Duration::try_minutes(ADVANCE_NOTICE_MINUTES).unwrap();
+        Duration::try_seconds(LOADING_AND_TAKEOFF_TIME_SECONDS).unwrap();
+        Duration::try_seconds(LANDING_AND_UNLOADING_TIME_SECONDS).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_get_sorted_flight_plans() {
+        lib_common::logger::get_log_handle().await;
+        ut_info!("start");
+
+        ensure_storage_mock_data().await;
+        let clients = get_clients().await;
+
+        let expected_number_returned = 10;
+        let res = get_sorted_flight_plans(&clients).await;
+        ut_debug!("flight_plans returned: {:#?}", res);
+
+        assert!(res.is_ok());
+        assert_eq!(res.unwrap().len(), expected_number_returned);
+        ut_info!("success");
+    }
+
+    #[tokio::test]
+    #[cfg(feature = "stub_backends")]
+    async fn test_query_invalid() {
+        lib_common::logger::get_log_handle().await;
+        ut_info!("start");
+
+        let vertiports = get_vertiports_from_storage().await.unwrap();
+        let mut query = QueryFlightRequest {
+            is_cargo: true,
+            persons: None,
+            weight_grams: Some(10),
+            priority: FlightPriority::Low as i32,
+            earliest_departure_time: None,
+            latest_arrival_time: None,
+            origin_vertiport_id: vertiports[0].id.clone(),
+            target_vertiport_id: vertiports[1].id.clone(),
+            waitlist_user_id: None,
+            excluded_vertiport_ids: vec![],
+            loop_waypoints: vec![],
+            aircraft_type: None,
+            emergency: false,
+            required_arrival_precision_seconds: None,
+            auto_widen: false,
+            ranking: None,
+            include_local_times: false,
+            preferred_departure_time: None,
+            origin_type: None,
+            target_type: None,
+            max_total_travel_seconds: None,
+            min_results: None,
+            cargo_hazard_class: None,
+        };
+
+        // no latest arrival time
+        query.latest_arrival_time = None;
+        query.earliest_departure_time = Some(Utc::now().into());
+        let e = FlightQuery::try_from(query.clone()).unwrap_err();
+        assert_eq!(e, FlightQueryError::Time);
+
+        // no earliest departure time
         query.latest_arrival_time = Some(Utc::now().into());
         query.earliest_departure_time = None;
         let e = FlightQuery::try_from(query.clone()).unwrap_err();
         assert_eq!(e, FlightQueryError::Time);
 
-        // latest arrival time is less than earliest departure time
-        query.earliest_departure_time = Some((Utc::now() + Duration::try_hours(4).unwrap()).into());
-        query.latest_arrival_time = Some((Utc::now() + Duration::try_hours(1).unwrap()).into());
+        // latest arrival time is less than earliest departure time
+        query.earliest_departure_time = Some((Utc::now() + Duration::try_hours(4).unwrap()).into());
+        query.latest_arrival_time = Some((Utc::now() + Duration::try_hours(1).unwrap()).into());
+
+        let e = FlightQuery::try_from(query.clone()).unwrap_err();
+        assert_eq!(e, FlightQueryError::Time);
+
+        // latest arrival time is in the past
+        query.latest_arrival_time = Some((Utc::now() - Duration::try_seconds(1).unwrap()).into());
+        let e = FlightQuery::try_from(query.clone()).unwrap_err();
+        assert_eq!(e, FlightQueryError::Time);
+
+        // Too large of a time range
+        query.earliest_departure_time = Some(Utc::now().into());
+        query.latest_arrival_time = Some(
+            (Utc::now() + Duration::try_minutes(MAX_FLIGHT_QUERY_WINDOW_MINUTES + 1).unwrap())
+                .into(),
+        );
+        let e = FlightQuery::try_from(query.clone()).unwrap_err();
+        assert_eq!(e, FlightQueryError::TimeRangeTooLarge);
+
+        // Earliest departure time must also include ADVANCE_NOTICE_MINUTES
+        query.earliest_departure_time = Some(Utc::now().into());
+        query.latest_arrival_time = Some(
+            (Utc::now() + Duration::try_minutes(MAX_FLIGHT_QUERY_WINDOW_MINUTES - 1).unwrap())
+                .into(),
+        );
+        let e = FlightQuery::try_from(query.clone()).unwrap_err();
+        assert_eq!(e, FlightQueryError::Time);
+
+        // Valid
+        query.earliest_departure_time =
+            Some((Utc::now() + Duration::try_minutes(ADVANCE_NOTICE_MINUTES + 1).unwrap()).into());
+        query.latest_arrival_time = Some(
+            (Utc::now() + Duration::try_minutes(MAX_FLIGHT_QUERY_WINDOW_MINUTES - 1).unwrap())
+                .into(),
+        );
+        FlightQuery::try_from(query.clone()).unwrap();
+
+        // Invalid vertiport IDs
+        query.origin_vertiport_id = "invalid".to_string();
+        let e = FlightQuery::try_from(query.clone()).unwrap_err();
+        assert_eq!(e, FlightQueryError::VertiportId);
+
+        query.origin_vertiport_id = Uuid::new_v4().to_string();
+        query.target_vertiport_id = "invalid".to_string();
+        let e = FlightQuery::try_from(query.clone()).unwrap_err();
+        assert_eq!(e, FlightQueryError::VertiportId);
+
+        query.target_vertiport_id = Uuid::new_v4().to_string();
+        FlightQuery::try_from(query.clone()).unwrap();
+
+        // Excluded departure or arrival vertiport
+        query.excluded_vertiport_ids = vec![query.origin_vertiport_id.clone()];
+        let e = FlightQuery::try_from(query.clone()).unwrap_err();
+        assert_eq!(e, FlightQueryError::ExcludedVertiport);
+
+        query.excluded_vertiport_ids = vec![query.target_vertiport_id.clone()];
+        let e = FlightQuery::try_from(query.clone()).unwrap_err();
+        assert_eq!(e, FlightQueryError::ExcludedVertiport);
+
+        // Invalid excluded vertiport ID
+        query.excluded_vertiport_ids = vec!["invalid".to_string()];
+        let e = FlightQuery::try_from(query.clone()).unwrap_err();
+        assert_eq!(e, FlightQueryError::VertiportId);
+
+        // Other vertiports may be excluded
+        query.excluded_vertiport_ids = vec![Uuid::new_v4().to_string()];
+        let flight_query = FlightQuery::try_from(query.clone()).unwrap();
+        assert_eq!(flight_query.excluded_vertiport_ids.len(), 1);
+        assert_eq!(flight_query.aircraft_type, None);
+
+        // Aircraft type filter
+        query.aircraft_type = Some(RequestAircraftType::Passenger as i32);
+        let flight_query = FlightQuery::try_from(query.clone()).unwrap();
+        assert_eq!(flight_query.aircraft_type, Some(AircraftType::Passenger));
+
+        query.aircraft_type = Some(-1);
+        let e = FlightQuery::try_from(query.clone()).unwrap_err();
+        assert_eq!(e, FlightQueryError::AircraftType);
+        query.aircraft_type = None;
+
+        // Cargo hazard class
+        query.cargo_hazard_class = Some(" 2.3 ".to_string());
+        let flight_query = FlightQuery::try_from(query.clone()).unwrap();
+        assert_eq!(flight_query.cargo_hazard_class, Some("2.3".to_string()));
+
+        query.cargo_hazard_class = Some(" ".to_string());
+        let e = FlightQuery::try_from(query.clone()).unwrap_err();
+        assert_eq!(e, FlightQueryError::HazardClass);
+
+        query.cargo_hazard_class = Some("2.3".to_string());
+        query.is_cargo = false;
+        let e = FlightQuery::try_from(query.clone()).unwrap_err();
+        assert_eq!(e, FlightQueryError::HazardClass);
+        query.cargo_hazard_class = None;
+        query.is_cargo = true;
+
+        // Ranking
+        let flight_query = FlightQuery::try_from(query.clone()).unwrap();
+        assert_eq!(flight_query.ranking, Ranking::Earliest);
+
+        query.ranking = Some(RequestRanking::LowestCost as i32);
+        let flight_query = FlightQuery::try_from(query.clone()).unwrap();
+        assert_eq!(flight_query.ranking, Ranking::LowestCost);
+
+        query.ranking = Some(RequestRanking::EarliestArrival as i32);
+        let flight_query = FlightQuery::try_from(query.clone()).unwrap();
+        assert_eq!(flight_query.ranking, Ranking::EarliestArrival);
+
+        query.ranking = Some(-1);
+        let e = FlightQuery::try_from(query.clone()).unwrap_err();
+        assert_eq!(e, FlightQueryError::Ranking);
+
+        ut_info!("success");
+    }
+
+    #[tokio::test]
+    async fn test_get_vehicle_info() {
+        lib_common::logger::get_log_handle().await;
+        ut_info!("start");
+
+        ensure_storage_mock_data().await;
+        let clients = get_clients().await;
+
+        let vehicle = clients
+            .storage()
+            .await
+            .vehicle
+            .search(AdvancedSearchFilter::search_is_null(
+                "deleted_at".to_owned(),
+            ))
+            .await
+            .unwrap()
+            .into_inner()
+            .list
+            .pop()
+            .unwrap();
+        let data = vehicle.data.clone().unwrap();
+
+        let mut cache = HashMap::new();
+        let info = get_vehicle_info(clients, &vehicle.id, &mut cache)
+            .await
+            .unwrap();
+        let models = get_vehicle_models().await.unwrap();
+        assert_eq!(info.vehicle_id, vehicle.id);
+        assert_eq!(
+            info.model_name,
+            models.get(&data.vehicle_model_id).unwrap().name
+        );
+        assert_eq!(info.registration_number, data.registration_number);
+
+        // second lookup is served from the cache
+        let cached = get_vehicle_info(clients, &vehicle.id, &mut cache)
+            .await
+            .unwrap();
+        assert_eq!(cached, info);
+        assert_eq!(cache.len(), 1);
+
+        ut_info!("success");
+    }
+
+    #[test]
+    fn test_validate_query_request() {
+        let mut query = QueryFlightRequest {
+            is_cargo: true,
+            persons: None,
+            weight_grams: Some(10),
+            priority: FlightPriority::Low as i32,
+            earliest_departure_time: Some(Utc::now().into()),
+            latest_arrival_time: Some((Utc::now() + Duration::try_hours(1).unwrap()).into()),
+            origin_vertiport_id: Uuid::new_v4().to_string(),
+            target_vertiport_id: Uuid::new_v4().to_string(),
+            waitlist_user_id: None,
+            excluded_vertiport_ids: vec![],
+            loop_waypoints: vec![],
+            aircraft_type: None,
+            emergency: false,
+            required_arrival_precision_seconds: None,
+            auto_widen: false,
+            ranking: None,
+            include_local_times: false,
+            preferred_departure_time: None,
+            origin_type: None,
+            target_type: None,
+            max_total_travel_seconds: None,
+            min_results: None,
+            cargo_hazard_class: None,
+        };
+
+        validate_query_request(&query).unwrap();
+
+        // empty vertiport IDs
+        let mut tmp = query.clone();
+        tmp.origin_vertiport_id = String::new();
+        let e = validate_query_request(&tmp).unwrap_err();
+        assert_eq!(e.code(), tonic::Code::InvalidArgument);
+        assert_eq!(e.message(), "origin_vertiport_id: must not be empty.");
+
+        let mut tmp = query.clone();
+        tmp.target_vertiport_id = String::new();
+        let e = validate_query_request(&tmp).unwrap_err();
+        assert_eq!(e.code(), tonic::Code::InvalidArgument);
+        assert_eq!(e.message(), "target_vertiport_id: must not be empty.");
+
+        // identical origin and target
+        let mut tmp = query.clone();
+        tmp.target_vertiport_id = tmp.origin_vertiport_id.clone();
+        let e = validate_query_request(&tmp).unwrap_err();
+        assert_eq!(e.code(), tonic::Code::InvalidArgument);
+        assert_eq!(
+            e.message(),
+            "target_vertiport_id: must differ from origin_vertiport_id."
+        );
+
+        // loop flights return to the origin vertiport
+        tmp.loop_waypoints = vec![Waypoint {
+            latitude: 52.37,
+            longitude: 4.89,
+            altitude_meters: 100.0,
+            name: Some("dam-square".to_string()),
+        }];
+        validate_query_request(&tmp).unwrap();
+
+        // loop waypoints are named and on the globe
+        let mut invalid = tmp.clone();
+        invalid.loop_waypoints[0].name = None;
+        let e = validate_query_request(&invalid).unwrap_err();
+        assert_eq!(e.code(), tonic::Code::InvalidArgument);
+        assert_eq!(
+            e.message(),
+            "loop_waypoints: must name a waypoint known to svc-gis."
+        );
+
+        invalid.loop_waypoints[0].name = Some(String::new());
+        let e = validate_query_request(&invalid).unwrap_err();
+        assert_eq!(e.code(), tonic::Code::InvalidArgument);
+
+        for (latitude, longitude) in [(90.1, 4.89), (-91.0, 4.89), (52.37, 180.5), (52.37, -181.0)]
+        {
+            let mut invalid = tmp.clone();
+            invalid.loop_waypoints[0].latitude = latitude;
+            invalid.loop_waypoints[0].longitude = longitude;
+            let e = validate_query_request(&invalid).unwrap_err();
+            assert_eq!(e.code(), tonic::Code::InvalidArgument);
+            assert_eq!(
+                e.message(),
+                "loop_waypoints: latitude must be within [-90, 90] and longitude within [-180, 180] degrees."
+            );
+        }
+
+        let mut tmp = tmp.clone();
+        tmp.target_vertiport_id = Uuid::new_v4().to_string();
+        let e = validate_query_request(&tmp).unwrap_err();
+        assert_eq!(e.code(), tonic::Code::InvalidArgument);
+        assert_eq!(
+            e.message(),
+            "loop_waypoints: origin and target vertiport must match for loop flights."
+        );
+
+        // missing times
+        let mut tmp = query.clone();
+        tmp.earliest_departure_time = None;
+        let e = validate_query_request(&tmp).unwrap_err();
+        assert_eq!(e.message(), "earliest_departure_time: must be provided.");
+
+        query.latest_arrival_time = None;
+        let e = validate_query_request(&query).unwrap_err();
+        assert_eq!(e.message(), "latest_arrival_time: must be provided.");
+    }
+
+    #[tokio::test]
+    async fn test_query_flight_identical_vertiports() {
+        let id = Uuid::new_v4().to_string();
+        let query = QueryFlightRequest {
+            is_cargo: true,
+            persons: None,
+            weight_grams: Some(10),
+            priority: FlightPriority::Low as i32,
+            earliest_departure_time: Some(Utc::now().into()),
+            latest_arrival_time: Some((Utc::now() + Duration::try_hours(1).unwrap()).into()),
+            origin_vertiport_id: id.clone(),
+            target_vertiport_id: id,
+            waitlist_user_id: None,
+            excluded_vertiport_ids: vec![],
+            loop_waypoints: vec![],
+            aircraft_type: None,
+            emergency: false,
+            required_arrival_precision_seconds: None,
+            auto_widen: false,
+            ranking: None,
+            include_local_times: false,
+            preferred_departure_time: None,
+            origin_type: None,
+            target_type: None,
+            max_total_travel_seconds: None,
+            min_results: None,
+            cargo_hazard_class: None,
+        };
+
+        let e = query_flight(query, &QueryCaller::default())
+            .await
+            .unwrap_err();
+        assert_eq!(e.code(), tonic::Code::InvalidArgument);
+        assert_eq!(
+            e.message(),
+            "target_vertiport_id: must differ from origin_vertiport_id."
+        );
+    }
+
+    #[test]
+    fn test_flight_query_emergency_advance_notice() {
+        let now = Utc::now();
+        let mut query = QueryFlightRequest {
+            is_cargo: true,
+            persons: None,
+            weight_grams: Some(100),
+            earliest_departure_time: Some((now + Duration::try_minutes(1).unwrap()).into()),
+            latest_arrival_time: Some((now + Duration::try_hours(1).unwrap()).into()),
+            origin_vertiport_id: Uuid::new_v4().to_string(),
+            target_vertiport_id: Uuid::new_v4().to_string(),
+            priority: FlightPriority::Low as i32,
+            waitlist_user_id: None,
+            excluded_vertiport_ids: vec![],
+            loop_waypoints: vec![],
+            aircraft_type: None,
+            emergency: false,
+            required_arrival_precision_seconds: None,
+            auto_widen: false,
+            ranking: None,
+            include_local_times: false,
+            preferred_departure_time: None,
+            origin_type: None,
+            target_type: None,
+            max_total_travel_seconds: None,
+            min_results: None,
+            cargo_hazard_class: None,
+        };
+
+        // Routine flights need advance notice
+        let e = FlightQuery::try_from(query.clone()).unwrap_err();
+        assert_eq!(e, FlightQueryError::Time);
+
+        // Emergency flights may depart right away
+        query.emergency = true;
+        FlightQuery::try_from(query.clone()).unwrap();
+
+        // ...but not in the past
+        query.earliest_departure_time = Some((now - Duration::try_minutes(1).unwrap()).into());
+        let e = FlightQuery::try_from(query).unwrap_err();
+        assert_eq!(e, FlightQueryError::Time);
+    }
+
+    #[test]
+    fn test_flight_query_arrival_precision() {
+        let now = Utc::now();
+        let earliest_departure_time = now + Duration::try_minutes(10).unwrap();
+        let latest_arrival_time = now + Duration::try_hours(1).unwrap();
+        let mut query = QueryFlightRequest {
+            is_cargo: true,
+            persons: None,
+            weight_grams: Some(100),
+            earliest_departure_time: Some(earliest_departure_time.into()),
+            latest_arrival_time: Some(latest_arrival_time.into()),
+            origin_vertiport_id: Uuid::new_v4().to_string(),
+            target_vertiport_id: Uuid::new_v4().to_string(),
+            priority: FlightPriority::Low as i32,
+            waitlist_user_id: None,
+            excluded_vertiport_ids: vec![],
+            loop_waypoints: vec![],
+            aircraft_type: None,
+            emergency: false,
+            required_arrival_precision_seconds: None,
+            auto_widen: false,
+            ranking: None,
+            include_local_times: false,
+            preferred_departure_time: None,
+            origin_type: None,
+            target_type: None,
+            max_total_travel_seconds: None,
+            min_results: None,
+            cargo_hazard_class: None,
+        };
+
+        // Land anywhere in the requested window
+        let request = FlightQuery::try_from(query.clone()).unwrap();
+        assert_eq!(request.arrival_window, None);
+
+        // Land within 5 minutes of the latest arrival time
+        query.required_arrival_precision_seconds = Some(300);
+        let request = FlightQuery::try_from(query.clone()).unwrap();
+        let window = request.arrival_window.unwrap();
+        assert_eq!(
+            window.time_start(),
+            latest_arrival_time - Duration::try_minutes(5).unwrap()
+        );
+        assert_eq!(window.time_end(), latest_arrival_time);
+
+        // Looser than the requested window
+        query.required_arrival_precision_seconds = Some(24 * 3600);
+        let request = FlightQuery::try_from(query).unwrap();
+        let window = request.arrival_window.unwrap();
+        assert_eq!(window.time_start(), earliest_departure_time);
+        assert_eq!(window.time_end(), latest_arrival_time);
+    }
+
+    #[test]
+    fn test_flight_query_preferred_departure_time() {
+        let now = Utc::now();
+        let earliest_departure_time = now + Duration::try_minutes(10).unwrap();
+        let latest_arrival_time = now + Duration::try_hours(1).unwrap();
+        let preferred = now + Duration::try_minutes(30).unwrap();
+        let mut query = QueryFlightRequest {
+            is_cargo: true,
+            persons: None,
+            weight_grams: Some(100),
+            earliest_departure_time: Some(earliest_departure_time.into()),
+            latest_arrival_time: Some(latest_arrival_time.into()),
+            origin_vertiport_id: Uuid::new_v4().to_string(),
+            target_vertiport_id: Uuid::new_v4().to_string(),
+            priority: FlightPriority::Low as i32,
+            waitlist_user_id: None,
+            excluded_vertiport_ids: vec![],
+            loop_waypoints: vec![],
+            aircraft_type: None,
+            emergency: false,
+            required_arrival_precision_seconds: None,
+            auto_widen: false,
+            ranking: Some(RequestRanking::LowestCost as i32),
+            include_local_times: false,
+            preferred_departure_time: Some(preferred.into()),
+            origin_type: None,
+            target_type: None,
+            max_total_travel_seconds: None,
+            min_results: None,
+            cargo_hazard_class: None,
+        };
+
+        // takes precedence over the requested ranking
+        let request = FlightQuery::try_from(query.clone()).unwrap();
+        assert_eq!(request.ranking, Ranking::PreferredDeparture(preferred));
+
+        // must be within the window
+        query.preferred_departure_time = Some((now + Duration::try_hours(2).unwrap()).into());
+        let e = FlightQuery::try_from(query).unwrap_err();
+        assert_eq!(e, FlightQueryError::Time);
+    }
+
+    #[test]
+    fn test_get_endpoint_type() {
+        // vertiports by default
+        assert_eq!(
+            get_endpoint_type(None).unwrap(),
+            RequestEndpointType::Vertiport
+        );
+        assert_eq!(
+            get_endpoint_type(Some(RequestEndpointType::Waypoint as i32)).unwrap(),
+            RequestEndpointType::Waypoint
+        );
+        assert_eq!(
+            get_endpoint_type(Some(-1)).unwrap_err(),
+            FlightQueryError::EndpointType
+        );
+
+        let vertiport_id = Uuid::new_v4().to_string();
+        assert_eq!(get_vertiport_id(&vertiport_id).unwrap(), vertiport_id);
+        assert_eq!(
+            get_vertiport_id("hub-north").unwrap_err(),
+            FlightQueryError::VertiportId
+        );
+    }
+
+    #[test]
+    fn test_flight_query_waypoint_endpoint() {
+        let now = Utc::now();
+        let mut query = QueryFlightRequest {
+            is_cargo: true,
+            persons: None,
+            weight_grams: Some(100),
+            earliest_departure_time: Some((now + Duration::try_minutes(10).unwrap()).into()),
+            latest_arrival_time: Some((now + Duration::try_hours(1).unwrap()).into()),
+            origin_vertiport_id: Uuid::new_v4().to_string(),
+            target_vertiport_id: "hub-north".to_string(),
+            priority: FlightPriority::Low as i32,
+            waitlist_user_id: None,
+            excluded_vertiport_ids: vec![],
+            loop_waypoints: vec![],
+            aircraft_type: None,
+            emergency: false,
+            required_arrival_precision_seconds: None,
+            auto_widen: false,
+            ranking: None,
+            include_local_times: false,
+            preferred_departure_time: None,
+            origin_type: None,
+            target_type: Some(RequestEndpointType::Waypoint as i32),
+            max_total_travel_seconds: None,
+            min_results: None,
+            cargo_hazard_class: None,
+        };
+
+        // flown out to the waypoint and back to a vertipad
+        let request = FlightQuery::try_from(query.clone()).unwrap();
+        assert_eq!(request.arrival_vertiport_id, query.origin_vertiport_id);
+        assert_eq!(request.loop_waypoints, vec!["hub-north".to_string()]);
+
+        // the declared type is used, whatever the identifier looks like
+        let mut tmp = query.clone();
+        tmp.target_type = None;
+        let e = FlightQuery::try_from(tmp).unwrap_err();
+        assert_eq!(e, FlightQueryError::VertiportId);
+
+        // flights depart from a vertiport
+        query.origin_vertiport_id = "hub-south".to_string();
+        query.origin_type = Some(RequestEndpointType::Waypoint as i32);
+        let e = FlightQuery::try_from(query).unwrap_err();
+        assert_eq!(e, FlightQueryError::EndpointType);
+    }
+
+    #[test]
+    fn test_get_blackout_response() {
+        let now = Utc::now();
+        let blackouts = crate::router::blackout::parse_blackout_periods(&format!(
+            "{}/{}",
+            (now - Duration::try_hours(1).unwrap()).to_rfc3339(),
+            (now + Duration::try_hours(1).unwrap()).to_rfc3339()
+        ))
+        .unwrap();
+
+        // Query during the blackout
+        let timeslot = Timeslot::new(now, now + Duration::try_hours(2).unwrap()).unwrap();
+        let response = get_blackout_response(&blackouts, &timeslot).unwrap();
+        assert!(response.itineraries.is_empty());
+        assert_eq!(
+            response.reason,
+            Some(NoFlightsReason::NetworkBlackout as i32)
+        );
+
+        // Query after the blackout
+        let timeslot = Timeslot::new(
+            now + Duration::try_hours(1).unwrap(),
+            now + Duration::try_hours(2).unwrap(),
+        )
+        .unwrap();
+        assert!(get_blackout_response(&blackouts, &timeslot).is_none());
+    }
 
-        let e = FlightQuery::try_from(query.clone()).unwrap_err();
-        assert_eq!(e, FlightQueryError::Time);
+    #[test]
+    fn test_get_hazard_response() {
+        let restricted = Uuid::new_v4().to_string();
+        let open = Uuid::new_v4().to_string();
+        let restrictions =
+            crate::router::hazard::parse_hazard_restrictions(&format!("{restricted}=1.1|3"))
+                .unwrap();
 
-        // latest arrival time is in the past
-        query.latest_arrival_time = Some((Utc::now() - Duration::try_seconds(1).unwrap()).into());
-        let e = FlightQuery::try_from(query.clone()).unwrap_err();
-        assert_eq!(e, FlightQueryError::Time);
+        let now = Utc::now();
+        let mut request = FlightQuery {
+            origin_vertiport_id: open.clone(),
+            arrival_vertiport_id: restricted.clone(),
+            earliest_departure_time: now,
+            latest_arrival_time: now + Duration::try_hours(2).unwrap(),
+            required_loading_time: Duration::zero(),
+            required_unloading_time: Duration::zero(),
+            excluded_vertiport_ids: HashSet::new(),
+            loop_waypoints: vec![],
+            aircraft_type: None,
+            arrival_window: None,
+            ranking: Ranking::Earliest,
+            max_total_travel: None,
+            min_results: 0,
+            cargo_hazard_class: Some("3".to_string()),
+        };
 
-        // Too large of a time range
-        query.earliest_departure_time = Some(Utc::now().into());
-        query.latest_arrival_time = Some(
-            (Utc::now() + Duration::try_minutes(MAX_FLIGHT_QUERY_WINDOW_MINUTES + 1).unwrap())
-                .into(),
+        // flammable liquids can't be flown to the restricted vertiport
+        let response = get_hazard_response(&restrictions, &request).unwrap();
+        assert!(response.itineraries.is_empty());
+        assert_eq!(
+            response.reason,
+            Some(NoFlightsReason::HazardRestricted as i32)
         );
-        let e = FlightQuery::try_from(query.clone()).unwrap_err();
-        assert_eq!(e, FlightQueryError::TimeRangeTooLarge);
 
-        // Earliest departure time must also include ADVANCE_NOTICE_MINUTES
-        query.earliest_departure_time = Some(Utc::now().into());
-        query.latest_arrival_time = Some(
-            (Utc::now() + Duration::try_minutes(MAX_FLIGHT_QUERY_WINDOW_MINUTES - 1).unwrap())
-                .into(),
+        // or from it
+        request.origin_vertiport_id = restricted.clone();
+        request.arrival_vertiport_id = open.clone();
+        assert!(get_hazard_response(&restrictions, &request).is_some());
+
+        // other cargo can
+        request.cargo_hazard_class = Some("8".to_string());
+        assert!(get_hazard_response(&restrictions, &request).is_none());
+        request.cargo_hazard_class = None;
+        assert!(get_hazard_response(&restrictions, &request).is_none());
+    }
+
+    #[test]
+    fn test_get_off_duty_response() {
+        let aircraft_1 = Uuid::new_v4().to_string();
+        let aircraft_2 = Uuid::new_v4().to_string();
+
+        // after hours for the whole fleet
+        let exclusions = HashMap::from([
+            (aircraft_1.clone(), AircraftExclusion::OffDuty),
+            (aircraft_2.clone(), AircraftExclusion::OffDuty),
+        ]);
+        let response = get_off_duty_response(2, &exclusions).unwrap();
+        assert!(response.itineraries.is_empty());
+        assert_eq!(
+            response.reason,
+            Some(NoFlightsReason::NoAircraftOnDuty as i32)
         );
-        let e = FlightQuery::try_from(query.clone()).unwrap_err();
-        assert_eq!(e, FlightQueryError::Time);
 
-        // Valid
-        query.earliest_departure_time =
-            Some((Utc::now() + Duration::try_minutes(ADVANCE_NOTICE_MINUTES + 1).unwrap()).into());
-        query.latest_arrival_time = Some(
-            (Utc::now() + Duration::try_minutes(MAX_FLIGHT_QUERY_WINDOW_MINUTES - 1).unwrap())
-                .into(),
+        // another aircraft is on duty, or one is merely booked
+        assert!(get_off_duty_response(3, &exclusions).is_none());
+        let exclusions = HashMap::from([
+            (aircraft_1, AircraftExclusion::OffDuty),
+            (aircraft_2, AircraftExclusion::Unavailable),
+        ]);
+        assert!(get_off_duty_response(2, &exclusions).is_none());
+
+        // no aircraft at all
+        assert!(get_off_duty_response(0, &HashMap::new()).is_none());
+    }
+
+    #[test]
+    fn test_remove_short_routes() {
+        let now = Utc::now();
+        let get_pair = |distance: f64| TimeslotPair {
+            origin_vertiport_id: Uuid::new_v4().to_string(),
+            origin_vertipad_id: Uuid::new_v4().to_string(),
+            origin_timeslot: Timeslot::new(now, now + Duration::try_minutes(30).unwrap()).unwrap(),
+            target_vertiport_id: Uuid::new_v4().to_string(),
+            target_vertipad_id: Uuid::new_v4().to_string(),
+            target_timeslot: Timeslot::new(
+                now + Duration::try_minutes(20).unwrap(),
+                now + Duration::try_minutes(50).unwrap(),
+            )
+            .unwrap(),
+            path: vec![],
+            distance_meters: Meters(distance),
+            alternative_paths: vec![],
+        };
+
+        // Every route is below the threshold
+        let mut pairs = vec![get_pair(100.0), get_pair(200.0)];
+        let response = remove_short_routes(&mut pairs, Meters(250.0)).unwrap();
+        assert!(pairs.is_empty());
+        assert!(response.itineraries.is_empty());
+        assert_eq!(response.reason, Some(NoFlightsReason::RouteTooShort as i32));
+
+        // Only short routes are dropped
+        let mut pairs = vec![get_pair(100.0), get_pair(250.0)];
+        assert!(remove_short_routes(&mut pairs, Meters(250.0)).is_none());
+        assert_eq!(pairs.len(), 1);
+        assert_eq!(pairs[0].distance_meters, Meters(250.0));
+
+        // Disabled
+        let mut pairs = vec![get_pair(100.0)];
+        assert!(remove_short_routes(&mut pairs, Meters(0.0)).is_none());
+        assert_eq!(pairs.len(), 1);
+
+        // No routes at all isn't a short route
+        assert!(remove_short_routes(&mut vec![], Meters(250.0)).is_none());
+    }
+
+    #[test]
+    fn test_get_local_times() {
+        use crate::router::timezone::parse_vertiport_timezones;
+        use std::str::FromStr;
+
+        let origin_id = Uuid::new_v4().to_string();
+        let target_id = Uuid::new_v4().to_string();
+        let timezones = parse_vertiport_timezones(&format!(
+            "{origin_id}|Europe/Amsterdam,{target_id}|America/New_York"
+        ))
+        .unwrap();
+
+        let time = |time: &str| DateTime::<Utc>::from_str(time).unwrap();
+        let origin_timeslot_start = time("2024-07-15T12:00:00Z");
+        let origin_timeslot_end = time("2024-07-15T12:05:00Z");
+        let target_timeslot_start = time("2024-07-15T20:00:00Z");
+        let target_timeslot_end = time("2024-07-15T20:05:00Z");
+        let flight_plan = flight_plan::Data {
+            origin_vertiport_id: Some(origin_id.clone()),
+            target_vertiport_id: Some(target_id.clone()),
+            origin_timeslot_start: Some(origin_timeslot_start.into()),
+            origin_timeslot_end: Some(origin_timeslot_end.into()),
+            target_timeslot_start: Some(target_timeslot_start.into()),
+            target_timeslot_end: Some(target_timeslot_end.into()),
+            ..Default::default()
+        };
+
+        let local_times = get_local_times(&[flight_plan.clone()], &timezones);
+        assert_eq!(local_times.len(), 1);
+        let local_times = &local_times[0];
+        assert_eq!(local_times.origin_timezone, "Europe/Amsterdam");
+        assert_eq!(local_times.target_timezone, "America/New_York");
+
+        // Same instant as the UTC time, converted to the vertiport's zone
+        let origin_tz = Tz::Europe__Amsterdam;
+        let target_tz = Tz::America__New_York;
+        assert_eq!(
+            local_times.origin_timeslot_start,
+            origin_timeslot_start.with_timezone(&origin_tz).to_rfc3339()
+        );
+        assert_eq!(
+            local_times.origin_timeslot_start,
+            "2024-07-15T14:00:00+02:00"
+        );
+        assert_eq!(
+            local_times.origin_timeslot_end,
+            origin_timeslot_end.with_timezone(&origin_tz).to_rfc3339()
+        );
+        assert_eq!(
+            local_times.target_timeslot_start,
+            target_timeslot_start.with_timezone(&target_tz).to_rfc3339()
+        );
+        assert_eq!(
+            local_times.target_timeslot_start,
+            "2024-07-15T16:00:00-04:00"
+        );
+        assert_eq!(
+            local_times.target_timeslot_end,
+            target_timeslot_end.with_timezone(&target_tz).to_rfc3339()
         );
-        FlightQuery::try_from(query.clone()).unwrap();
 
-        // Invalid vertiport IDs
-        query.origin_vertiport_id = "invalid".to_string();
-        let e = FlightQuery::try_from(query.clone()).unwrap_err();
-        assert_eq!(e, FlightQueryError::VertiportId);
+        // vertiports without a configured timezone use UTC
+        let local_times = get_local_times(&[flight_plan], &HashMap::new());
+        assert_eq!(local_times[0].origin_timezone, "UTC");
+        assert_eq!(
+            local_times[0].origin_timeslot_start,
+            "2024-07-15T12:00:00+00:00"
+        );
+    }
 
-        query.origin_vertiport_id = Uuid::new_v4().to_string();
-        query.target_vertiport_id = "invalid".to_string();
-        let e = FlightQuery::try_from(query.clone()).unwrap_err();
-        assert_eq!(e, FlightQueryError::VertiportId);
+    #[test]
+    fn test_get_endpoint_status() {
+        let vertiport_id = Uuid::new_v4().to_string();
+        let status = get_endpoint_status(&vertiport_id, VertiportError::NotFound);
+        assert_eq!(status.code(), tonic::Code::NotFound);
 
-        query.target_vertiport_id = Uuid::new_v4().to_string();
-        FlightQuery::try_from(query.clone()).unwrap();
+        // in storage, but nothing to route to
+        let status = get_endpoint_status(&vertiport_id, VertiportError::NoVertipads);
+        assert_eq!(status.code(), tonic::Code::FailedPrecondition);
+        assert!(status.message().contains(&vertiport_id));
+
+        let status = get_endpoint_status(&vertiport_id, VertiportError::ClientError);
+        assert_eq!(status.code(), tonic::Code::Internal);
+    }
+
+    #[test]
+    fn test_get_search_deadline() {
+        let started = Instant::now();
+        assert_eq!(get_search_deadline(started, 0), None);
+        assert_eq!(
+            get_search_deadline(started, 1500),
+            Some(started + std::time::Duration::from_millis(1500))
+        );
+    }
+
+    #[test]
+    fn test_get_limiting_constraint() {
+        // a single open pair of vertipad timeslots, no aircraft or route rejected
+        let rejections = Rejections {
+            timeslot_pairs: 1,
+            ..Default::default()
+        };
+        assert_eq!(
+            get_limiting_constraint(&rejections, 1, 0),
+            Some(LimitingConstraint::PadCapacity as i32)
+        );
+
+        // enough itineraries, nothing to report
+        assert_eq!(get_limiting_constraint(&rejections, 10, 0), None);
+
+        // more results were asked for
+        assert_eq!(
+            get_limiting_constraint(&rejections, 10, 12),
+            Some(LimitingConstraint::PadCapacity as i32)
+        );
+
+        let rejections = Rejections {
+            timeslot_pairs: 10,
+            aircraft_availability: 1,
+            routing: 4,
+            budget_exceeded: false,
+        };
+        assert_eq!(
+            get_limiting_constraint(&rejections, 0, 0),
+            Some(LimitingConstraint::Routing as i32)
+        );
+    }
+
+    #[tokio::test]
+    async fn test_get_alternative_destinations() {
+        let nearby = vec![
+            ("near_full".to_string(), Meters(1_112.0)),
+            ("near".to_string(), Meters(2_224.0)),
+        ];
+
+        // the destination and the nearest vertiport are full
+        let is_available = |vertiport_id: String| async move { vertiport_id != "near_full" };
+
+        let alternatives = get_alternative_destinations(nearby, is_available).await;
+        assert_eq!(alternatives.len(), 1);
+        assert_eq!(alternatives[0].vertiport_id, "near");
+        assert_eq!(alternatives[0].distance_meters, 2_224.0);
+
+        let response = get_alternative_destinations_response(alternatives.clone()).unwrap();
+        assert!(response.itineraries.is_empty());
+        assert_eq!(
+            response.reason,
+            Some(NoFlightsReason::DestinationUnavailable as i32)
+        );
+        assert_eq!(response.alternative_destinations, alternatives);
+
+        // nothing nearby is available
+        let nearby = vec![("near_full".to_string(), Meters(1_112.0))];
+        let alternatives = get_alternative_destinations(nearby, is_available).await;
+        assert!(alternatives.is_empty());
+        assert!(get_alternative_destinations_response(alternatives).is_none());
+    }
+
+    #[test]
+    fn test_get_deadhead_reasons() {
+        let reasons = get_deadhead_reasons(&[
+            Some(DeadheadReason::PrePositioning),
+            None,
+            Some(DeadheadReason::Return),
+        ]);
+
+        assert_eq!(
+            reasons,
+            vec![
+                RequestDeadheadReason::PrePositioning as i32,
+                RequestDeadheadReason::NotDeadhead as i32,
+                RequestDeadheadReason::Return as i32,
+            ]
+        );
+        assert!(get_deadhead_reasons(&[]).is_empty());
+    }
+
+    #[test]
+    fn test_remove_long_itineraries() {
+        let now = Utc::now();
+        let flight_plan = |start_minutes: i64, end_minutes: i64| flight_plan::Data {
+            origin_timeslot_start: Some(
+                (now + Duration::try_minutes(start_minutes).unwrap()).into(),
+            ),
+            target_timeslot_end: Some((now + Duration::try_minutes(end_minutes).unwrap()).into()),
+            ..Default::default()
+        };
+
+        let itinerary = |flight_plans: Vec<flight_plan::Data>, deadhead_reasons| ItineraryOption {
+            flight_plans,
+            deadhead_reasons,
+            alternative_paths: vec![],
+            quoted_flight: QuotedFlight {
+                distance_meters: Meters(0.0),
+                duration: Duration::zero(),
+                departure: now,
+            },
+            path_fallback: false,
+        };
+
+        // both land well within a two hour window
+        let short = itinerary(
+            vec![flight_plan(0, 40), flight_plan(60, 80)],
+            vec![Some(DeadheadReason::PrePositioning), None],
+        );
+        let long = itinerary(vec![flight_plan(10, 60)], vec![None]);
+        assert_eq!(
+            get_travel_time(&short.flight_plans, &short.deadhead_reasons),
+            Some(Duration::try_minutes(20).unwrap())
+        );
+
+        // the long route is dropped, the deadhead of the short one isn't counted
+        let mut itineraries = vec![short.clone(), long.clone()];
+        remove_long_itineraries(&mut itineraries, Duration::try_minutes(30).unwrap());
+        assert_eq!(itineraries.len(), 1);
+        assert_eq!(itineraries[0].flight_plans, short.flight_plans);
+
+        let mut itineraries = vec![short, long];
+        remove_long_itineraries(&mut itineraries, Duration::try_minutes(50).unwrap());
+        assert_eq!(itineraries.len(), 2);
+    }
+
+    #[test]
+    fn test_get_pad_selection() {
+        assert_eq!(
+            get_pad_selection("earliest_availability", Ranking::Earliest, &[]).unwrap(),
+            RequestPadSelection::EarliestAvailability
+        );
+        assert_eq!(
+            get_pad_selection("shortest_path", Ranking::EarliestArrival, &[]).unwrap(),
+            RequestPadSelection::ShortestPath
+        );
+        let e = get_pad_selection("nearest", Ranking::Earliest, &[]).unwrap_err();
+        assert_eq!(e.code(), tonic::Code::Internal);
+
+        // rankings that reorder the pairs are reported instead
+        let preferred = Ranking::PreferredDeparture(Utc::now());
+        assert_eq!(
+            get_pad_selection("shortest_path", preferred, &[]).unwrap(),
+            RequestPadSelection::PreferredDeparture
+        );
+
+        let windows = crate::router::pricing::parse_pricing_schedule("00:00-12:00=1.5").unwrap();
+        assert_eq!(
+            get_pad_selection("shortest_path", Ranking::LowestCost, &windows).unwrap(),
+            RequestPadSelection::LowestCost
+        );
+
+        // every departure costs the same without pricing windows
+        assert_eq!(
+            get_pad_selection("shortest_path", Ranking::LowestCost, &[]).unwrap(),
+            RequestPadSelection::ShortestPath
+        );
+    }
+
+    #[test]
+    fn test_sort_by_arrival() {
+        let now = Utc::now();
+        let flight_plan = |start_minutes: i64, arrival_minutes: i64| flight_plan::Data {
+            origin_timeslot_start: Some(
+                (now + Duration::try_minutes(start_minutes).unwrap()).into(),
+            ),
+            target_timeslot_start: Some(
+                (now + Duration::try_minutes(arrival_minutes).unwrap()).into(),
+            ),
+            ..Default::default()
+        };
+
+        let itinerary = |flight_plans: Vec<flight_plan::Data>, deadhead_reasons| ItineraryOption {
+            flight_plans,
+            deadhead_reasons,
+            alternative_paths: vec![],
+            quoted_flight: QuotedFlight {
+                distance_meters: Meters(0.0),
+                duration: Duration::zero(),
+                departure: now,
+            },
+            path_fallback: false,
+        };
+
+        // found first, but the aircraft has to fly in and lands last
+        let early_departure = itinerary(
+            vec![flight_plan(0, 20), flight_plan(30, 50), flight_plan(50, 70)],
+            vec![
+                Some(DeadheadReason::PrePositioning),
+                None,
+                Some(DeadheadReason::Return),
+            ],
+        );
+        let slow_route = itinerary(vec![flight_plan(10, 45)], vec![None]);
+        let early_arrival = itinerary(vec![flight_plan(20, 40)], vec![None]);
+        assert_eq!(
+            get_arrival_time(&slow_route.flight_plans, &slow_route.deadhead_reasons),
+            Some(now + Duration::try_minutes(45).unwrap())
+        );
+
+        let mut itineraries = vec![
+            early_departure.clone(),
+            slow_route.clone(),
+            early_arrival.clone(),
+        ];
+        sort_by_arrival(&mut itineraries);
+        assert_eq!(itineraries[0].flight_plans, early_arrival.flight_plans);
+        assert_eq!(itineraries[1].flight_plans, slow_route.flight_plans);
+        assert_eq!(itineraries[2].flight_plans, early_departure.flight_plans);
+    }
+
+    #[test]
+    fn test_sort_by_cost() {
+        let windows = crate::router::pricing::parse_pricing_schedule("00:00-12:00=1.5").unwrap();
+        let at =
+            |hour: u32| DateTime::<Utc>::from_str(&format!("2024-01-01T{hour:02}:00:00Z")).unwrap();
+
+        let itinerary = |departure: DateTime<Utc>| ItineraryOption {
+            flight_plans: vec![flight_plan::Data {
+                origin_timeslot_start: Some(departure.into()),
+                ..Default::default()
+            }],
+            deadhead_reasons: vec![None],
+            alternative_paths: vec![],
+            quoted_flight: QuotedFlight {
+                distance_meters: Meters(0.0),
+                duration: Duration::zero(),
+                departure,
+            },
+            path_fallback: false,
+        };
+
+        // the off-peak departure goes first, equally priced keep their order
+        let peak = itinerary(at(10));
+        let off_peak = itinerary(at(14));
+        let later_off_peak = itinerary(at(16));
+        let mut itineraries = vec![peak.clone(), off_peak.clone(), later_off_peak.clone()];
+        sort_by_cost(&mut itineraries, &windows);
+        assert_eq!(itineraries[0].flight_plans, off_peak.flight_plans);
+        assert_eq!(itineraries[1].flight_plans, later_off_peak.flight_plans);
+        assert_eq!(itineraries[2].flight_plans, peak.flight_plans);
+    }
+
+    #[test]
+    fn test_get_arrival_estimate() {
+        let now = Utc::now();
+        let at = |minutes: i64| now + Duration::try_minutes(minutes).unwrap();
+        let flight_plan = |takeoff_minutes: i64, landing_minutes: i64| flight_plan::Data {
+            origin_timeslot_end: Some(at(takeoff_minutes).into()),
+            target_timeslot_start: Some(at(landing_minutes).into()),
+            ..Default::default()
+        };
+
+        // the aircraft flies in first, the requested flight is airborne 20 minutes
+        let flight_plans = vec![flight_plan(0, 10), flight_plan(30, 50)];
+        let deadhead_reasons = vec![Some(DeadheadReason::PrePositioning), None];
+        let variance = ArrivalVariance::new(90, 110).unwrap();
+
+        let estimate = get_arrival_estimate(&flight_plans, &deadhead_reasons, &variance).unwrap();
+        let p50 = DateTime::<Utc>::from(estimate.p50.unwrap());
+        let p90 = DateTime::<Utc>::from(estimate.p90.unwrap());
+        assert!(p90 > p50);
+        assert_eq!(p50, at(48));
+        assert_eq!(p90, at(52));
+
+        // only deadhead flights
+        let deadhead_reasons = vec![Some(DeadheadReason::PrePositioning); 2];
+        assert!(get_arrival_estimate(&flight_plans, &deadhead_reasons, &variance).is_none());
+    }
+
+    #[test]
+    fn test_get_widened_arrival_times() {
+        let now = Utc::now();
+        let horizon = Duration::try_hours(6).unwrap();
+        let arrival_times =
+            get_widened_arrival_times(now, now + Duration::try_minutes(40).unwrap(), horizon);
+        let expected: Vec<DateTime<Utc>> = [80, 160, 320, 360]
+            .into_iter()
+            .map(|minutes| now + Duration::try_minutes(minutes).unwrap())
+            .collect();
+        assert_eq!(arrival_times, expected);
+
+        // already at the horizon
+        assert!(get_widened_arrival_times(now, now + horizon, horizon).is_empty());
+
+        // empty window can't be widened
+        assert!(get_widened_arrival_times(now, now, horizon).is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_query_flight_auto_widen() {
+        lib_common::logger::get_log_handle().await;
+        ut_info!("start");
+
+        let earliest_departure_time = Utc::now() + Duration::try_minutes(10).unwrap();
+        let latest_arrival_time = earliest_departure_time + Duration::try_minutes(30).unwrap();
+        let user_id = Uuid::new_v4().to_string();
+        let query = QueryFlightRequest {
+            is_cargo: true,
+            persons: None,
+            weight_grams: Some(100),
+            earliest_departure_time: Some(earliest_departure_time.into()),
+            latest_arrival_time: Some(latest_arrival_time.into()),
+            origin_vertiport_id: Uuid::new_v4().to_string(),
+            target_vertiport_id: Uuid::new_v4().to_string(),
+            priority: FlightPriority::Low as i32,
+            waitlist_user_id: Some(user_id.clone()),
+            excluded_vertiport_ids: vec![],
+            loop_waypoints: vec![],
+            aircraft_type: None,
+            emergency: false,
+            required_arrival_precision_seconds: None,
+            auto_widen: true,
+            ranking: None,
+            include_local_times: false,
+            preferred_departure_time: None,
+            origin_type: None,
+            target_type: None,
+            max_total_travel_seconds: None,
+            min_results: None,
+            cargo_hazard_class: None,
+        };
+
+        // The only flight arrives 100 minutes after the earliest departure time
+        let arrival = earliest_departure_time + Duration::try_minutes(100).unwrap();
+        let horizon = Duration::try_hours(6).unwrap();
+        let search = |attempt: QueryFlightRequest| async move {
+            let latest: DateTime<Utc> = attempt.latest_arrival_time.unwrap().into();
+            if latest < arrival {
+                // too narrow, and not waitlisted since a wider window follows
+                assert!(attempt.waitlist_user_id.is_none());
+                return Err(Status::not_found("No routes available for the given time."));
+            }
+
+            Ok(QueryFlightResponse {
+                itineraries: vec![Itinerary::default()],
+                reason: None,
+                window_earliest_departure_time: None,
+                window_latest_arrival_time: None,
+                limiting_constraint: None,
+                partial: false,
+                alternative_destinations: vec![],
+            })
+        };
+
+        let response = query_flight_widened(query.clone(), horizon, search)
+            .await
+            .unwrap();
+        assert_eq!(response.itineraries.len(), 1);
+        assert_eq!(
+            response.window_earliest_departure_time,
+            Some(earliest_departure_time.into())
+        );
+
+        // 30 minutes widened to 60, then 120 minutes
+        assert_eq!(
+            response.window_latest_arrival_time,
+            Some((earliest_departure_time + Duration::try_minutes(120).unwrap()).into())
+        );
+
+        // Nothing within the horizon, the widest window is waitlisted
+        let search = |attempt: QueryFlightRequest| {
+            let user_id = user_id.clone();
+            async move {
+                let latest: DateTime<Utc> = attempt.latest_arrival_time.unwrap().into();
+                if latest < earliest_departure_time + horizon {
+                    assert!(attempt.waitlist_user_id.is_none());
+                } else {
+                    assert_eq!(attempt.waitlist_user_id, Some(user_id));
+                }
+
+                Err::<QueryFlightResponse, Status>(Status::not_found(
+                    "No routes available for the given time.",
+                ))
+            }
+        };
+
+        let e = query_flight_widened(query.clone(), horizon, search)
+            .await
+            .unwrap_err();
+        assert_eq!(e.code(), tonic::Code::NotFound);
+
+        // Aircraft come on duty in a wider window
+        let search = |attempt: QueryFlightRequest| async move {
+            let latest: DateTime<Utc> = attempt.latest_arrival_time.unwrap().into();
+            let (itineraries, reason) = match latest < arrival {
+                true => (vec![], Some(NoFlightsReason::NoAircraftOnDuty as i32)),
+                false => (vec![Itinerary::default()], None),
+            };
+
+            Ok(QueryFlightResponse {
+                itineraries,
+                reason,
+                window_earliest_departure_time: None,
+                window_latest_arrival_time: None,
+                limiting_constraint: None,
+                partial: false,
+                alternative_destinations: vec![],
+            })
+        };
+
+        let response = query_flight_widened(query, horizon, search).await.unwrap();
+        assert_eq!(response.itineraries.len(), 1);
+        assert_eq!(response.reason, None);
+
+        ut_info!("success");
+    }
+
+    #[tokio::test]
+    async fn test_query_flight_min_results() {
+        lib_common::logger::get_log_handle().await;
+        ut_info!("start");
+
+        let earliest_departure_time = Utc::now() + Duration::try_minutes(10).unwrap();
+        let latest_arrival_time = earliest_departure_time + Duration::try_minutes(30).unwrap();
+        let query = QueryFlightRequest {
+            is_cargo: true,
+            persons: None,
+            weight_grams: Some(100),
+            earliest_departure_time: Some(earliest_departure_time.into()),
+            latest_arrival_time: Some(latest_arrival_time.into()),
+            origin_vertiport_id: Uuid::new_v4().to_string(),
+            target_vertiport_id: Uuid::new_v4().to_string(),
+            priority: FlightPriority::Low as i32,
+            waitlist_user_id: None,
+            excluded_vertiport_ids: vec![],
+            loop_waypoints: vec![],
+            aircraft_type: None,
+            emergency: false,
+            required_arrival_precision_seconds: None,
+            auto_widen: true,
+            ranking: None,
+            include_local_times: false,
+            preferred_departure_time: None,
+            origin_type: None,
+            target_type: None,
+            max_total_travel_seconds: None,
+            min_results: Some(3),
+            cargo_hazard_class: None,
+        };
+
+        // One more flight for every 30 minutes of window
+        let horizon = Duration::try_hours(6).unwrap();
+        let search = |attempt: QueryFlightRequest| async move {
+            let latest: DateTime<Utc> = attempt.latest_arrival_time.unwrap().into();
+            let count = (latest - earliest_departure_time).num_minutes() / 30;
+            Ok(QueryFlightResponse {
+                itineraries: vec![Itinerary::default(); count as usize],
+                reason: None,
+                window_earliest_departure_time: None,
+                window_latest_arrival_time: None,
+                limiting_constraint: None,
+                partial: false,
+                alternative_destinations: vec![],
+            })
+        };
+
+        // 30 minutes widened to 60, then 120 minutes
+        let response = query_flight_widened(query.clone(), horizon, search)
+            .await
+            .unwrap();
+        assert_eq!(response.itineraries.len(), 4);
+        assert_eq!(
+            response.window_latest_arrival_time,
+            Some((earliest_departure_time + Duration::try_minutes(120).unwrap()).into())
+        );
+
+        // without a minimum, the first flight found is enough
+        let response = query_flight_widened(
+            QueryFlightRequest {
+                min_results: None,
+                ..query.clone()
+            },
+            horizon,
+            search,
+        )
+        .await
+        .unwrap();
+        assert_eq!(response.itineraries.len(), 1);
+        assert_eq!(
+            response.window_latest_arrival_time,
+            Some(latest_arrival_time.into())
+        );
+
+        // the horizon is reached with fewer flights
+        let response = query_flight_widened(
+            QueryFlightRequest {
+                min_results: Some(100),
+                ..query.clone()
+            },
+            horizon,
+            search,
+        )
+        .await
+        .unwrap();
+        assert_eq!(response.itineraries.len(), 12);
+
+        // a wider window runs out of time, the flights found so far are kept
+        let search = |attempt: QueryFlightRequest| async move {
+            let latest: DateTime<Utc> = attempt.latest_arrival_time.unwrap().into();
+            if latest > latest_arrival_time {
+                return Err(Status::deadline_exceeded(
+                    "Flight query exceeded its time budget.",
+                ));
+            }
+
+            Ok(QueryFlightResponse {
+                itineraries: vec![Itinerary::default()],
+                reason: None,
+                window_earliest_departure_time: None,
+                window_latest_arrival_time: None,
+                limiting_constraint: None,
+                partial: false,
+                alternative_destinations: vec![],
+            })
+        };
+
+        let response = query_flight_widened(query.clone(), horizon, search)
+            .await
+            .unwrap();
+        assert_eq!(response.itineraries.len(), 1);
+        assert_eq!(
+            response.window_latest_arrival_time,
+            Some(latest_arrival_time.into())
+        );
+
+        // no wider window is searched once the time budget is spent
+        let search = |_: QueryFlightRequest| async move {
+            Ok(QueryFlightResponse {
+                itineraries: vec![Itinerary::default()],
+                reason: None,
+                window_earliest_departure_time: None,
+                window_latest_arrival_time: None,
+                limiting_constraint: None,
+                partial: true,
+                alternative_destinations: vec![],
+            })
+        };
+
+        let response = query_flight_widened(query, horizon, search).await.unwrap();
+        assert_eq!(response.itineraries.len(), 1);
+        assert!(response.partial);
+        assert_eq!(
+            response.window_latest_arrival_time,
+            Some(latest_arrival_time.into())
+        );
 
         ut_info!("success");
     }
 
+    #[test]
+    fn test_get_alternative_routes() {
+        let point = |latitude: f64| PointZ {
+            latitude,
+            longitude: 4.0,
+            altitude_meters: 100.0,
+        };
+
+        let paths = vec![
+            (vec![point(52.0), point(52.1)], Meters(1200.0)),
+            (vec![point(52.0), point(52.2), point(52.1)], Meters(1500.0)),
+        ];
+
+        let routes = get_alternative_routes(&paths);
+        assert_eq!(routes.len(), 2);
+        assert_eq!(routes[0].distance_meters, 1200.0);
+        assert_eq!(routes[0].waypoints.len(), 2);
+        assert_eq!(routes[1].distance_meters, 1500.0);
+        assert_eq!(routes[1].waypoints[1].latitude, 52.2);
+        assert_eq!(routes[1].waypoints[1].altitude_meters, 100.0);
+
+        assert!(get_alternative_routes(&[]).is_empty());
+    }
+
+    #[test]
+    fn test_vehicle_info_from_vehicle_object() {
+        let vehicle = vehicle::Object {
+            id: Uuid::new_v4().to_string(),
+            data: Some(vehicle::Data {
+                vehicle_model_id: Uuid::new_v4().to_string(),
+                registration_number: "N-DEMO-12345678".to_string(),
+                ..Default::default()
+            }),
+        };
+
+        let data = vehicle.data.clone().unwrap();
+        let mut models = VehicleModels::default();
+        models.models.insert(
+            data.vehicle_model_id.clone(),
+            crate::test_util::get_test_vehicle_models()
+                .other_models
+                .unwrap(),
+        );
+
+        let info = VehicleInfo::from((vehicle.clone(), &models));
+        assert_eq!(info.vehicle_id, vehicle.id);
+        assert_eq!(info.model_name, models.models[&data.vehicle_model_id].name);
+        assert_eq!(info.registration_number, data.registration_number);
+
+        // unknown models have no name
+        let info = VehicleInfo::from((vehicle, &VehicleModels::default()));
+        assert!(info.model_name.is_empty());
+    }
+
     #[test]
     fn test_flight_query_error_display() {
         assert_eq!(
@@ -408,6 +3237,23 @@ mod tests {
             format!("{}", FlightQueryError::TimeRangeTooLarge),
             "Time range too large"
         );
+        assert_eq!(
+            format!("{}", FlightQueryError::ExcludedVertiport),
+            "Vertiport is excluded"
+        );
+        assert_eq!(
+            format!("{}", FlightQueryError::AircraftType),
+            "Invalid aircraft type"
+        );
+        assert_eq!(format!("{}", FlightQueryError::Ranking), "Invalid ranking");
+        assert_eq!(
+            format!("{}", FlightQueryError::EndpointType),
+            "Invalid endpoint type"
+        );
+        assert_eq!(
+            format!("{}", FlightQueryError::HazardClass),
+            "Invalid cargo hazard class"
+        );
         assert_eq!(format!("{}", FlightQueryError::Internal), "Internal error");
     }
 }
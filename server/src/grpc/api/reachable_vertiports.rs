@@ -0,0 +1,205 @@
+//! This module contains the gRPC reachable_vertiports endpoint implementation.
+
+use lib_common::uuid::Uuid;
+use num_traits::FromPrimitive;
+use std::collections::{HashMap, HashSet};
+use tonic::{Response, Status};
+
+use super::query_flight::get_endpoint_status;
+use crate::grpc::client::get_clients;
+use crate::grpc::server::grpc_server::{
+    AircraftType as RequestAircraftType, ReachableVertiport, ReachableVertiportsRequest,
+    ReachableVertiportsResponse,
+};
+use crate::router::units::Meters;
+use crate::router::vehicle::{estimate_flight_time_seconds, get_aircraft_range, AircraftType};
+use crate::router::vehicle_model::{get_vehicle_models, VehicleModels};
+use crate::router::vertiport::{
+    check_vertiport_routable, get_vertiport_distances, get_vertiport_ids,
+};
+
+/// Gets the aircraft type of the request, if provided
+fn get_aircraft_type(aircraft_type: Option<i32>) -> Result<Option<AircraftType>, Status> {
+    let Some(aircraft_type) = aircraft_type else {
+        return Ok(None);
+    };
+
+    match FromPrimitive::from_i32(aircraft_type) {
+        Some(RequestAircraftType::Cargo) => Ok(Some(AircraftType::Cargo)),
+        Some(RequestAircraftType::Passenger) => Ok(Some(AircraftType::Passenger)),
+        None => {
+            grpc_warn!("Invalid aircraft type: {aircraft_type}");
+            Err(Status::invalid_argument("aircraft_type: invalid value."))
+        }
+    }
+}
+
+/// Returns the range of the aircraft type, or without one the longest
+///  range of any configured vehicle model
+fn get_range(aircraft_type: Option<AircraftType>, models: &VehicleModels) -> Meters {
+    match aircraft_type {
+        Some(aircraft_type) => get_aircraft_range(aircraft_type),
+        None => models
+            .models
+            .values()
+            .chain(models.other_models.iter())
+            .map(|model| get_aircraft_range(model.aircraft_type))
+            .max_by(|a, b| a.0.total_cmp(&b.0))
+            .unwrap_or(Meters(0.0)),
+    }
+}
+
+/// Finds the vertiports an aircraft can reach from the origin in a single
+///  flight: those within the aircraft range, by the distances between
+///  vertiports
+fn get_reachable_vertiports(
+    origin_vertiport_id: &str,
+    distances: &HashMap<String, Meters>,
+    range: Meters,
+) -> Result<Vec<ReachableVertiport>, Status> {
+    let mut reachable = vec![];
+    for (vertiport_id, distance) in distances {
+        if vertiport_id == origin_vertiport_id {
+            continue;
+        }
+
+        if *distance > range {
+            grpc_debug!("Vertiport {vertiport_id} is out of range: {distance}.");
+            continue;
+        }
+
+        let duration = estimate_flight_time_seconds(distance).map_err(|e| {
+            grpc_error!("Could not estimate the flight time to {vertiport_id}: {e}");
+            Status::internal("Could not estimate flight time.")
+        })?;
+
+        reachable.push(ReachableVertiport {
+            vertiport_id: vertiport_id.clone(),
+            distance_meters: distance.0,
+            flight_duration_seconds: u32::try_from(duration.num_seconds()).unwrap_or(u32::MAX),
+        });
+    }
+
+    // nearest first
+    reachable.sort_by(|a, b| a.distance_meters.total_cmp(&b.distance_meters));
+    Ok(reachable)
+}
+
+/// Lists the vertiports reachable from a vertiport in a single flight,
+///  with the distance and estimated duration of each flight.
+/// Distances come from the cached distances between vertiports rather
+///  than routing a path to every vertiport.
+#[cfg(not(tarpaulin_include))]
+// no_coverage: (R5) needs running backend, integration tests
+pub async fn reachable_vertiports(
+    request: ReachableVertiportsRequest,
+) -> Result<Response<ReachableVertiportsResponse>, Status> {
+    let origin_vertiport_id = request.origin_vertiport_id;
+    Uuid::parse_str(&origin_vertiport_id).map_err(|e| {
+        grpc_warn!("Invalid origin vertiport ID {origin_vertiport_id}: {e}");
+        Status::invalid_argument("origin_vertiport_id: must be a UUID.")
+    })?;
+
+    let aircraft_type = get_aircraft_type(request.aircraft_type)?;
+    let models = get_vehicle_models().await.map_err(|e| {
+        grpc_error!("Could not get vehicle models: {e}");
+        Status::internal("Invalid vehicle models.")
+    })?;
+
+    let clients = get_clients().await;
+    check_vertiport_routable(&origin_vertiport_id, clients)
+        .await
+        .map_err(|e| get_endpoint_status(&origin_vertiport_id, e))?;
+
+    let vertiport_ids: HashSet<String> = get_vertiport_ids(clients)
+        .await
+        .map_err(|e| {
+            grpc_error!("Could not get vertiports: {e}");
+            Status::internal("Could not get vertiports.")
+        })?
+        .into_iter()
+        .collect();
+
+    let distances = get_vertiport_distances(&origin_vertiport_id, &vertiport_ids, clients)
+        .await
+        .map_err(|e| get_endpoint_status(&origin_vertiport_id, e))?;
+
+    let vertiports = get_reachable_vertiports(
+        &origin_vertiport_id,
+        &distances,
+        get_range(aircraft_type, &models),
+    )?;
+
+    grpc_info!(
+        "{} vertiports reachable from {origin_vertiport_id}.",
+        vertiports.len()
+    );
+
+    Ok(Response::new(ReachableVertiportsResponse { vertiports }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::get_test_vehicle_models;
+    use lib_common::time::Duration;
+
+    #[test]
+    fn test_get_aircraft_type() {
+        assert_eq!(get_aircraft_type(None).unwrap(), None);
+        assert_eq!(
+            get_aircraft_type(Some(RequestAircraftType::Passenger as i32)).unwrap(),
+            Some(AircraftType::Passenger)
+        );
+
+        let e = get_aircraft_type(Some(-1)).unwrap_err();
+        assert_eq!(e.code(), tonic::Code::InvalidArgument);
+    }
+
+    #[test]
+    fn test_get_range() {
+        let models = get_test_vehicle_models();
+        assert_eq!(
+            get_range(Some(AircraftType::Passenger), &models),
+            get_aircraft_range(AircraftType::Passenger)
+        );
+
+        // the longest range of the configured models
+        assert_eq!(
+            get_range(None, &models),
+            get_aircraft_range(AircraftType::Cargo)
+        );
+        assert_eq!(get_range(None, &VehicleModels::default()), Meters(0.0));
+    }
+
+    #[test]
+    fn test_get_reachable_vertiports() {
+        let range = get_aircraft_range(AircraftType::Cargo);
+        let distances = HashMap::from([
+            ("origin".to_string(), Meters(0.0)),
+            ("near".to_string(), Meters(11_119.0)),
+            ("nearest".to_string(), Meters(2_000.0)),
+            ("far".to_string(), Meters(range.0 + 1.0)),
+        ]);
+
+        // every vertiport within range except the origin, nearest first
+        let reachable = get_reachable_vertiports("origin", &distances, range).unwrap();
+        let ids = reachable
+            .iter()
+            .map(|v| v.vertiport_id.clone())
+            .collect::<Vec<String>>();
+        assert_eq!(ids, vec!["nearest".to_string(), "near".to_string()]);
+
+        let near = &reachable[1];
+        assert_eq!(near.distance_meters, 11_119.0);
+        assert_eq!(
+            Duration::try_seconds(near.flight_duration_seconds as i64).unwrap(),
+            estimate_flight_time_seconds(&Meters(near.distance_meters)).unwrap()
+        );
+
+        // nothing is in reach without range
+        assert!(get_reachable_vertiports("origin", &distances, Meters(0.0))
+            .unwrap()
+            .is_empty());
+    }
+}
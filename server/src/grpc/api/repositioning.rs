@@ -0,0 +1,442 @@
+//! This module contains the gRPC suggest_repositioning endpoint implementation.
+
+use lib_common::time::{DateTime, Duration, Utc};
+use lib_common::uuid::Uuid;
+use std::cmp::Ordering;
+use std::collections::{HashMap, HashSet};
+use svc_gis_client_grpc::client::PointZ;
+use tonic::{Response, Status};
+
+use crate::clock::get_clock;
+use crate::grpc::client::{get_clients, GrpcClients};
+use crate::grpc::server::grpc_server::{SuggestRepositioningRequest, SuggestRepositioningResponse};
+
+use crate::router::flight_plan::*;
+use crate::router::itinerary::{
+    calculate_itineraries, DeadheadSearch, ItineraryError, ReturnPolicy,
+};
+use crate::router::lead_time::get_lead_times;
+use crate::router::schedule::*;
+use crate::router::units::Meters;
+use crate::router::vehicle::*;
+use crate::router::vertiport::*;
+
+/// An idle aircraft and the hub it should move to
+#[derive(Debug, Clone, PartialEq)]
+struct RepositioningMove {
+    aircraft_id: String,
+    availability: Availability,
+    hub_vertiport_id: String,
+}
+
+/// Checks the request and returns the deduplicated hub IDs and the
+///  time window for repositioning flights.
+fn validate_repositioning_request(
+    request: &SuggestRepositioningRequest,
+) -> Result<(Vec<String>, Timeslot), Status> {
+    if request.hub_vertiport_ids.is_empty() {
+        return Err(Status::invalid_argument(
+            "hub_vertiport_ids: must not be empty.",
+        ));
+    }
+
+    let mut seen = HashSet::new();
+    let mut hub_vertiport_ids = vec![];
+    for id in &request.hub_vertiport_ids {
+        let id = Uuid::parse_str(id)
+            .map_err(|_| Status::invalid_argument("hub_vertiport_ids: must be valid UUIDs."))?
+            .to_string();
+
+        if seen.insert(id.clone()) {
+            hub_vertiport_ids.push(id);
+        }
+    }
+
+    let earliest_departure_time: DateTime<Utc> = request
+        .earliest_departure_time
+        .clone()
+        .ok_or_else(|| Status::invalid_argument("earliest_departure_time: must be provided."))?
+        .into();
+
+    let latest_arrival_time: DateTime<Utc> = request
+        .latest_arrival_time
+        .clone()
+        .ok_or_else(|| Status::invalid_argument("latest_arrival_time: must be provided."))?
+        .into();
+
+    let timeslot = Timeslot::new(earliest_departure_time, latest_arrival_time).map_err(|_| {
+        Status::invalid_argument("latest_arrival_time: must be after earliest_departure_time.")
+    })?;
+
+    Ok((hub_vertiport_ids, timeslot))
+}
+
+/// Picks idle aircraft to move to hubs without any idle aircraft.
+/// Aircraft are taken from the vertiport nearest to the hub, keeping the
+///  deadhead flight short, then from the vertiport with the most idle
+///  aircraft, always leaving at least one aircraft behind. Vertiports
+///  missing from `locations` are taken from last.
+fn get_repositioning_moves(
+    aircraft_gaps: &HashMap<String, Vec<Availability>>,
+    hub_vertiport_ids: &[String],
+    locations: &HashMap<String, PointZ>,
+) -> Vec<RepositioningMove> {
+    // Where each aircraft is parked at the start of its first availability
+    let mut parked: HashMap<String, Vec<(String, Availability)>> = HashMap::new();
+    for (aircraft_id, availabilities) in aircraft_gaps {
+        let Some(first) = availabilities
+            .iter()
+            .min_by_key(|availability| availability.timeslot.time_start())
+        else {
+            continue;
+        };
+
+        parked
+            .entry(first.vertiport_id.clone())
+            .or_default()
+            .push((aircraft_id.clone(), first.clone()));
+    }
+
+    for aircraft in parked.values_mut() {
+        aircraft.sort_by(|a, b| b.0.cmp(&a.0));
+    }
+
+    let mut moves = vec![];
+    for hub_vertiport_id in hub_vertiport_ids {
+        if parked.get(hub_vertiport_id).is_some_and(|a| !a.is_empty()) {
+            continue;
+        }
+
+        let distance_to_hub = |vertiport_id: &str| match (
+            locations.get(vertiport_id),
+            locations.get(hub_vertiport_id),
+        ) {
+            (Some(location), Some(hub)) => get_distance_meters(location, hub),
+            _ => Meters(f64::INFINITY),
+        };
+
+        let Some(donor_vertiport_id) = parked
+            .iter()
+            .filter(|(_, aircraft)| aircraft.len() > 1)
+            .min_by(|a, b| {
+                distance_to_hub(a.0)
+                    .partial_cmp(&distance_to_hub(b.0))
+                    .unwrap_or(Ordering::Equal)
+                    .then_with(|| b.1.len().cmp(&a.1.len()))
+                    .then_with(|| a.0.cmp(b.0))
+            })
+            .map(|(vertiport_id, _)| vertiport_id.clone())
+        else {
+            grpc_debug!("No surplus aircraft left to reposition.");
+            break;
+        };
+
+        let Some((aircraft_id, availability)) = parked
+            .get_mut(&donor_vertiport_id)
+            .and_then(|aircraft| aircraft.pop())
+        else {
+            break;
+        };
+
+        moves.push(RepositioningMove {
+            aircraft_id: aircraft_id.clone(),
+            availability: availability.clone(),
+            hub_vertiport_id: hub_vertiport_id.clone(),
+        });
+
+        parked
+            .entry(hub_vertiport_id.clone())
+            .or_default()
+            .push((aircraft_id, availability));
+    }
+
+    moves
+}
+
+/// Locates the vertiports that aircraft are parked at and the hubs.
+/// Vertiports that can't be located are left out.
+#[cfg(not(tarpaulin_include))]
+// no_coverage: (R5) needs running backend, integration tests
+async fn get_vertiport_locations(
+    aircraft_gaps: &HashMap<String, Vec<Availability>>,
+    hub_vertiport_ids: &[String],
+    clients: &GrpcClients,
+) -> HashMap<String, PointZ> {
+    let vertiport_ids = aircraft_gaps
+        .values()
+        .flatten()
+        .map(|availability| availability.vertiport_id.clone())
+        .chain(hub_vertiport_ids.iter().cloned())
+        .collect::<HashSet<String>>();
+
+    let mut locations = HashMap::new();
+    for vertiport_id in vertiport_ids {
+        match get_vertiport_location(&vertiport_id, clients).await {
+            Ok(location) => {
+                locations.insert(vertiport_id, location);
+            }
+            Err(e) => grpc_warn!("Could not locate vertiport {vertiport_id}: {e}"),
+        }
+    }
+
+    locations
+}
+
+/// Suggests deadhead flights that move idle aircraft toward the provided
+///  hubs. The returned flight plans are not booked.
+#[cfg(not(tarpaulin_include))]
+// no_coverage: (R5) needs running backend, integration tests
+pub async fn suggest_repositioning(
+    request: SuggestRepositioningRequest,
+) -> Result<Response<SuggestRepositioningResponse>, Status> {
+    let (hub_vertiport_ids, timeslot) = validate_repositioning_request(&request).map_err(|e| {
+        grpc_warn!("Invalid repositioning request: {}", e.message());
+        e
+    })?;
+
+    let clients = get_clients().await;
+    let config = crate::config::get_config().await;
+
+    let existing_flight_plans: Vec<FlightPlanSchedule> =
+        get_sorted_flight_plans(clients).await.map_err(|e| {
+            grpc_error!("{}", e);
+            Status::internal("Could not get existing flight plans.")
+        })?;
+
+    let aircraft = get_aircraft(clients, None).await.map_err(|e| {
+        grpc_error!("{}", e);
+        Status::internal("Could not get aircraft.")
+    })?;
+
+    let connection_grace = Duration::try_minutes(config.connection_grace_minutes.into())
+        .ok_or_else(|| {
+            grpc_error!("error creating connection grace duration.");
+            Status::internal("Invalid connection grace.")
+        })?;
+
+    let aircraft_gaps = get_aircraft_availabilities(
+        &existing_flight_plans,
+        &timeslot.time_start(),
+        &aircraft,
+        &timeslot,
+        &connection_grace,
+    )
+    .map_err(|e| {
+        grpc_error!("{}", e);
+        Status::internal("Could not get aircraft availabilities.")
+    })?;
+
+    // Repositioning flights carry no cargo and are the deadhead themselves
+    let ground_time = GroundTime {
+        loading: Duration::zero(),
+        unloading: Duration::zero(),
+    };
+
+    let aircraft_cruise_altitudes =
+        get_aircraft_cruise_altitudes(&aircraft, config.min_cruise_altitude_meters as f32);
+
+    let deadhead_search = DeadheadSearch {
+        enabled: false,
+        step: Duration::zero(),
+        attempts: 0,
+        max_legs: 0,
+        return_policy: ReturnPolicy::Strict,
+        parking: None,
+    };
+
+    let lead_times = get_lead_times().await.map_err(|e| {
+        grpc_error!("{e}");
+        Status::internal("Invalid vertiport lead times.")
+    })?;
+
+    let locations = get_vertiport_locations(&aircraft_gaps, &hub_vertiport_ids, clients).await;
+
+    let mut flight_plans = vec![];
+    for m in get_repositioning_moves(&aircraft_gaps, &hub_vertiport_ids, &locations) {
+        let timeslot_pairs = match get_timeslot_pairs(
+            &m.availability.vertiport_id,
+            Some(&m.availability.vertipad_id),
+            &m.hub_vertiport_id,
+            None,
+            &ground_time.loading,
+            &ground_time.unloading,
+            &timeslot,
+            None,
+            &existing_flight_plans,
+            1,
+            clients,
+            clients,
+        )
+        .await
+        {
+            Ok(pairs) => pairs,
+            Err(e) => {
+                grpc_warn!(
+                    "No route for aircraft {} to hub {}: {e}",
+                    m.aircraft_id,
+                    m.hub_vertiport_id
+                );
+                continue;
+            }
+        };
+
+        let aircraft_gaps = HashMap::from([(m.aircraft_id.clone(), vec![m.availability])]);
+        let itineraries = match calculate_itineraries(
+            &ground_time,
+            &HashMap::new(),
+            &aircraft_cruise_altitudes,
+            &timeslot_pairs,
+            &aircraft_gaps,
+            &deadhead_search,
+            &Duration::zero(),
+            &lead_times,
+            get_clock(),
+            clients,
+        )
+        .await
+        {
+            Ok(itineraries) => itineraries,
+            Err(ItineraryError::ClientError) => {
+                return Err(Status::internal("Could not determine path."));
+            }
+            Err(e) => {
+                grpc_warn!("No itinerary for aircraft {}: {e}", m.aircraft_id);
+                continue;
+            }
+        };
+
+        if let Some(flight_plan) = itineraries
+            .into_iter()
+            .flat_map(|itinerary| itinerary.flight_plans)
+            .next()
+        {
+            flight_plans.push(flight_plan);
+        }
+    }
+
+    grpc_info!("suggested {} repositioning flights.", flight_plans.len());
+    Ok(Response::new(SuggestRepositioningResponse { flight_plans }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn availability(vertiport_id: &str) -> Availability {
+        let now = Utc::now();
+        Availability {
+            vertiport_id: vertiport_id.to_string(),
+            vertipad_id: Uuid::new_v4().to_string(),
+            timeslot: Timeslot::new(now, now + Duration::try_hours(1).unwrap()).unwrap(),
+        }
+    }
+
+    #[test]
+    fn test_validate_repositioning_request() {
+        let hub = Uuid::new_v4().to_string();
+        let request = SuggestRepositioningRequest {
+            hub_vertiport_ids: vec![hub.clone(), hub.clone()],
+            earliest_departure_time: Some(Utc::now().into()),
+            latest_arrival_time: Some((Utc::now() + Duration::try_hours(1).unwrap()).into()),
+        };
+
+        let (hubs, _) = validate_repositioning_request(&request).unwrap();
+        assert_eq!(hubs, vec![hub]);
+
+        let mut tmp = request.clone();
+        tmp.hub_vertiport_ids = vec![];
+        let e = validate_repositioning_request(&tmp).unwrap_err();
+        assert_eq!(e.code(), tonic::Code::InvalidArgument);
+        assert_eq!(e.message(), "hub_vertiport_ids: must not be empty.");
+
+        let mut tmp = request.clone();
+        tmp.hub_vertiport_ids = vec!["invalid".to_string()];
+        let e = validate_repositioning_request(&tmp).unwrap_err();
+        assert_eq!(e.message(), "hub_vertiport_ids: must be valid UUIDs.");
+
+        let mut tmp = request.clone();
+        tmp.earliest_departure_time = None;
+        let e = validate_repositioning_request(&tmp).unwrap_err();
+        assert_eq!(e.message(), "earliest_departure_time: must be provided.");
+
+        let mut tmp = request.clone();
+        tmp.latest_arrival_time = Some((Utc::now() - Duration::try_hours(1).unwrap()).into());
+        let e = validate_repositioning_request(&tmp).unwrap_err();
+        assert_eq!(
+            e.message(),
+            "latest_arrival_time: must be after earliest_departure_time."
+        );
+    }
+
+    #[test]
+    fn test_get_repositioning_moves_imbalanced_fleet() {
+        let crowded = Uuid::new_v4().to_string();
+        let hub = Uuid::new_v4().to_string();
+
+        // three aircraft parked at one vertiport, none at the hub
+        let aircraft_gaps: HashMap<String, Vec<Availability>> = (0..3)
+            .map(|_| (Uuid::new_v4().to_string(), vec![availability(&crowded)]))
+            .collect();
+
+        let moves = get_repositioning_moves(&aircraft_gaps, &[hub.clone()], &HashMap::new());
+        assert_eq!(moves.len(), 1);
+        assert_eq!(moves[0].hub_vertiport_id, hub);
+        assert_eq!(moves[0].availability.vertiport_id, crowded);
+        assert!(aircraft_gaps.contains_key(&moves[0].aircraft_id));
+    }
+
+    #[test]
+    fn test_get_repositioning_moves_balanced_fleet() {
+        let vertiport_1 = Uuid::new_v4().to_string();
+        let vertiport_2 = Uuid::new_v4().to_string();
+        let hub = Uuid::new_v4().to_string();
+
+        let aircraft_gaps = HashMap::from([
+            (Uuid::new_v4().to_string(), vec![availability(&vertiport_1)]),
+            (Uuid::new_v4().to_string(), vec![availability(&hub)]),
+        ]);
+
+        // hub already has an aircraft
+        assert!(
+            get_repositioning_moves(&aircraft_gaps, &[hub.clone()], &HashMap::new()).is_empty()
+        );
+
+        // the only aircraft at a vertiport is never taken
+        assert!(
+            get_repositioning_moves(&aircraft_gaps, &[vertiport_2], &HashMap::new()).is_empty()
+        );
+    }
+
+    #[test]
+    fn test_get_repositioning_moves_nearest_donor() {
+        let point = |latitude: f64, longitude: f64| PointZ {
+            latitude,
+            longitude,
+            altitude_meters: 0.0,
+        };
+
+        let near = Uuid::new_v4().to_string();
+        let far = Uuid::new_v4().to_string();
+        let hub = Uuid::new_v4().to_string();
+
+        // the far vertiport has more idle aircraft, the near one has a spare
+        let aircraft_gaps: HashMap<String, Vec<Availability>> = [&near, &near, &far, &far, &far]
+            .into_iter()
+            .map(|vertiport_id| (Uuid::new_v4().to_string(), vec![availability(vertiport_id)]))
+            .collect();
+
+        let locations = HashMap::from([
+            (near.clone(), point(52.01, 4.0)),
+            (far.clone(), point(53.0, 4.0)),
+            (hub.clone(), point(52.0, 4.0)),
+        ]);
+
+        let moves = get_repositioning_moves(&aircraft_gaps, &[hub.clone()], &locations);
+        assert_eq!(moves.len(), 1);
+        assert_eq!(moves[0].availability.vertiport_id, near);
+
+        // without locations, the vertiport with the most idle aircraft gives one up
+        let moves = get_repositioning_moves(&aircraft_gaps, &[hub], &HashMap::new());
+        assert_eq!(moves[0].availability.vertiport_id, far);
+    }
+}
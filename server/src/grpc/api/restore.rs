@@ -0,0 +1,95 @@
+//! This module contains the gRPC restore_itinerary endpoint implementation.
+
+use crate::grpc::server::grpc_server::{
+    CreateItineraryRequest, RestoreItineraryRequest, TaskResponse,
+};
+use lib_common::uuid::to_uuid;
+use tonic::Status;
+
+use crate::tasks::pool::RedisPool;
+use crate::tasks::soft_cancel::get_restorable_flight_plans;
+use crate::tasks::TaskError;
+
+/// Maps an error restoring an itinerary to a gRPC status
+fn get_restore_status(e: TaskError) -> Status {
+    match e {
+        TaskError::NotFound => Status::not_found(
+            "No restorable itinerary found; it was not soft-cancelled or the grace period expired.",
+        ),
+        TaskError::UserId => Status::permission_denied("Itinerary was cancelled by another user."),
+        _ => Status::internal("Could not restore itinerary."),
+    }
+}
+
+/// Restores a soft-cancelled itinerary within its grace period.
+/// The cancelled flight plans are booked again as a new itinerary, so the
+///  restore fails if their vertipads or aircraft have since been taken.
+/// The task result is the ID of the new itinerary. Once booked, the
+///  soft cancellation is removed and the audit trail links both itineraries.
+/// The restore is audited as requested by `actor_id`.
+#[cfg(not(tarpaulin_include))]
+// no_coverage: (R5) need redis backend to test this
+pub async fn restore_itinerary(
+    request: RestoreItineraryRequest,
+    actor_id: &str,
+) -> Result<TaskResponse, Status> {
+    let itinerary_id = to_uuid(&request.itinerary_id)
+        .ok_or_else(|| Status::invalid_argument("Invalid itinerary ID provided."))?;
+
+    let user_id = to_uuid(&request.user_id)
+        .ok_or_else(|| Status::invalid_argument("Invalid user ID provided."))?;
+
+    let mut pool = crate::tasks::pool::get_pool().await.ok_or_else(|| {
+        grpc_error!("Couldn't get the redis pool.");
+        Status::internal("Internal error.")
+    })?;
+
+    let cancellation = pool
+        .get_soft_cancellation(&itinerary_id)
+        .await
+        .map_err(|e| {
+            grpc_error!("Could not get soft cancellation of itinerary {itinerary_id}: {e}");
+            Status::internal("Internal error.")
+        })?;
+
+    let flight_plans =
+        get_restorable_flight_plans(cancellation, &user_id.to_string()).map_err(|e| {
+            grpc_warn!("Could not restore itinerary {itinerary_id}: {e}");
+            get_restore_status(e)
+        })?;
+
+    grpc_info!("Restoring itinerary {itinerary_id} for user {user_id}.");
+    // A restored itinerary takes back its own slots, without evicting others
+    super::create::create_itinerary(
+        CreateItineraryRequest {
+            priority: request.priority,
+            flight_plans: flight_plans.into_iter().map(Into::into).collect(),
+            expiry: None,
+            user_id: request.user_id,
+            return_flight_plans: vec![],
+        },
+        false,
+        actor_id,
+        Some(itinerary_id),
+    )
+    .await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tonic::Code;
+
+    #[test]
+    fn test_get_restore_status() {
+        assert_eq!(
+            get_restore_status(TaskError::NotFound).code(),
+            Code::NotFound
+        );
+        assert_eq!(
+            get_restore_status(TaskError::UserId).code(),
+            Code::PermissionDenied
+        );
+        assert_eq!(get_restore_status(TaskError::Data).code(), Code::Internal);
+    }
+}
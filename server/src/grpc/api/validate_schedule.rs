@@ -0,0 +1,322 @@
+//! This module contains the gRPC validate_schedule endpoint implementation.
+
+use lib_common::time::Duration;
+use tonic::{Response, Status};
+
+use crate::grpc::client::get_clients;
+use crate::grpc::server::grpc_server::{
+    FlightPlanValidation, ValidateScheduleRequest, ValidateScheduleResponse,
+};
+
+use super::check_flight_plan::{get_aircraft_gaps, get_schedule_conflict, get_vertipad_slots};
+use crate::router::flight_plan::*;
+use crate::router::schedule::*;
+use crate::router::vehicle::*;
+
+/// Parses each flight plan of the request, naming the first invalid one
+fn validate_schedule_request(
+    request: &ValidateScheduleRequest,
+) -> Result<Vec<FlightPlanSchedule>, Status> {
+    if request.flight_plans.is_empty() {
+        return Err(Status::invalid_argument("flight_plans: must not be empty."));
+    }
+
+    request
+        .flight_plans
+        .iter()
+        .enumerate()
+        .map(|(i, data)| {
+            let flight_plan = FlightPlanSchedule::try_from(data.clone()).map_err(|e| {
+                grpc_warn!("Invalid flight plan {i}: {e}");
+                Status::invalid_argument(format!(
+                    "flight_plans[{i}]: must have vertipads, aircraft and times."
+                ))
+            })?;
+
+            let windows = [
+                (
+                    flight_plan.origin_timeslot_start,
+                    flight_plan.target_timeslot_end,
+                ),
+                (
+                    flight_plan.origin_timeslot_start,
+                    flight_plan.origin_timeslot_end,
+                ),
+                (
+                    flight_plan.target_timeslot_start,
+                    flight_plan.target_timeslot_end,
+                ),
+            ];
+
+            if windows
+                .iter()
+                .any(|(start, end)| Timeslot::new(*start, *end).is_err())
+            {
+                return Err(Status::invalid_argument(format!(
+                    "flight_plans[{i}]: invalid timeslots."
+                )));
+            }
+
+            Ok(flight_plan)
+        })
+        .collect()
+}
+
+/// Checks a flight plan against the existing flight plans and the aircraft
+///  schedules, given the available timeslots of its vertipads
+fn get_flight_plan_validation(
+    flight_plan: &FlightPlanSchedule,
+    existing_flight_plans: &[FlightPlanSchedule],
+    aircraft: &[Aircraft],
+    connection_grace: &Duration,
+    origin_vertipad_slots: &[Timeslot],
+    target_vertipad_slots: &[Timeslot],
+) -> Result<FlightPlanValidation, Status> {
+    let aircraft_gaps = get_aircraft_gaps(
+        flight_plan,
+        existing_flight_plans,
+        aircraft,
+        connection_grace,
+    )?;
+
+    let conflict = get_schedule_conflict(
+        flight_plan,
+        &aircraft_gaps,
+        origin_vertipad_slots,
+        target_vertipad_slots,
+    )?;
+
+    Ok(FlightPlanValidation {
+        schedulable: conflict.is_none(),
+        conflict: conflict.map(|c| c as i32),
+    })
+}
+
+/// Checks each flight plan of a batch against current bookings.
+/// Nothing is booked, and the flight plans of the batch aren't
+///  checked against each other.
+#[cfg(not(tarpaulin_include))]
+// no_coverage: (R5) needs running backend, integration tests
+pub async fn validate_schedule(
+    request: ValidateScheduleRequest,
+) -> Result<Response<ValidateScheduleResponse>, Status> {
+    let flight_plans = validate_schedule_request(&request).map_err(|e| {
+        grpc_warn!("Invalid schedule: {}", e.message());
+        e
+    })?;
+
+    let clients = get_clients().await;
+    let config = crate::config::get_config().await;
+
+    let existing_flight_plans = get_sorted_flight_plans(clients).await.map_err(|e| {
+        grpc_error!("{}", e);
+        Status::internal("Could not get existing flight plans.")
+    })?;
+
+    let aircraft = get_aircraft(clients, None).await.map_err(|e| {
+        grpc_error!("{}", e);
+        Status::internal("Could not get aircraft.")
+    })?;
+
+    let connection_grace = Duration::try_minutes(config.connection_grace_minutes.into())
+        .ok_or_else(|| {
+            grpc_error!("error creating connection grace duration.");
+            Status::internal("Invalid connection grace.")
+        })?;
+
+    let mut validations = Vec::with_capacity(flight_plans.len());
+    for flight_plan in flight_plans.iter() {
+        let (origin_vertipad_slots, target_vertipad_slots) =
+            get_vertipad_slots(flight_plan, &existing_flight_plans, clients).await?;
+
+        validations.push(get_flight_plan_validation(
+            flight_plan,
+            &existing_flight_plans,
+            &aircraft,
+            &connection_grace,
+            &origin_vertipad_slots,
+            &target_vertipad_slots,
+        )?);
+    }
+
+    let conflicts = validations.iter().filter(|v| !v.schedulable).count();
+    grpc_info!(
+        "{conflicts} of {} flight plans conflict with current bookings.",
+        validations.len()
+    );
+
+    Ok(Response::new(ValidateScheduleResponse {
+        flight_plans: validations,
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::grpc::server::grpc_server::FlightPlanConflict;
+    use lib_common::time::{DateTime, Utc};
+    use lib_common::uuid::Uuid;
+    use std::str::FromStr;
+    use svc_storage_client_grpc::prelude::{flight_plan, vehicle};
+
+    fn get_aircraft_at(vehicle_id: &str, hangar_id: &str) -> Aircraft {
+        let models = crate::test_util::get_test_vehicle_models();
+        Aircraft::try_from((
+            vehicle::Object {
+                id: vehicle_id.to_string(),
+                data: Some(vehicle::Data {
+                    schedule: Some(
+                        "DTSTART:20221020T180000Z;DURATION:PT24H
+                    RRULE:FREQ=DAILY"
+                            .to_owned(),
+                    ),
+                    hangar_id: Some(hangar_id.to_string()),
+                    hangar_bay_id: Some(Uuid::new_v4().to_string()),
+                    ..Default::default()
+                }),
+            },
+            &models,
+        ))
+        .unwrap()
+    }
+
+    fn get_flight_plan(
+        vehicle_id: &str,
+        origin_vertiport_id: &str,
+        target_vertiport_id: &str,
+        departure: DateTime<Utc>,
+    ) -> FlightPlanSchedule {
+        FlightPlanSchedule {
+            origin_vertiport_id: origin_vertiport_id.to_string(),
+            origin_vertipad_id: Uuid::new_v4().to_string(),
+            origin_timeslot_start: departure,
+            origin_timeslot_end: departure + Duration::try_minutes(1).unwrap(),
+            target_vertiport_id: target_vertiport_id.to_string(),
+            target_vertipad_id: Uuid::new_v4().to_string(),
+            target_timeslot_start: departure + Duration::try_minutes(20).unwrap(),
+            target_timeslot_end: departure + Duration::try_minutes(21).unwrap(),
+            vehicle_id: vehicle_id.to_string(),
+            path: None,
+        }
+    }
+
+    #[test]
+    fn test_validate_schedule_request() {
+        let e = validate_schedule_request(&ValidateScheduleRequest::default()).unwrap_err();
+        assert_eq!(e.code(), tonic::Code::InvalidArgument);
+        assert_eq!(e.message(), "flight_plans: must not be empty.");
+
+        let now = Utc::now();
+        let vertiport_id = Uuid::new_v4().to_string();
+        let valid = flight_plan::Data::from(get_flight_plan(
+            &Uuid::new_v4().to_string(),
+            &vertiport_id,
+            &vertiport_id,
+            now,
+        ));
+
+        let request = ValidateScheduleRequest {
+            flight_plans: vec![valid.clone(), valid.clone()],
+        };
+        assert_eq!(validate_schedule_request(&request).unwrap().len(), 2);
+
+        let request = ValidateScheduleRequest {
+            flight_plans: vec![valid.clone(), flight_plan::Data::default()],
+        };
+        let e = validate_schedule_request(&request).unwrap_err();
+        assert_eq!(e.code(), tonic::Code::InvalidArgument);
+        assert_eq!(
+            e.message(),
+            "flight_plans[1]: must have vertipads, aircraft and times."
+        );
+
+        // lands before it takes off
+        let mut backwards = valid.clone();
+        backwards.target_timeslot_end = valid.origin_timeslot_start.clone();
+        let request = ValidateScheduleRequest {
+            flight_plans: vec![backwards, valid],
+        };
+        let e = validate_schedule_request(&request).unwrap_err();
+        assert_eq!(e.code(), tonic::Code::InvalidArgument);
+        assert_eq!(e.message(), "flight_plans[0]: invalid timeslots.");
+    }
+
+    #[test]
+    fn test_get_flight_plan_validation() {
+        // away from the 18:00 UTC boundary of the aircraft calendars
+        let now = DateTime::<Utc>::from_str("2030-01-01T08:00:00Z").unwrap();
+        let grace = Duration::try_minutes(2).unwrap();
+        let day = Timeslot::new(now, now + Duration::try_hours(24).unwrap()).unwrap();
+
+        let vertiport_1 = Uuid::new_v4().to_string();
+        let vertiport_2 = Uuid::new_v4().to_string();
+        let aircraft_a = Uuid::new_v4().to_string();
+        let aircraft_b = Uuid::new_v4().to_string();
+        let aircraft = vec![
+            get_aircraft_at(&aircraft_a, &vertiport_1),
+            get_aircraft_at(&aircraft_b, &vertiport_1),
+        ];
+
+        // aircraft A is already booked out of vertiport 1
+        let departure = now + Duration::try_hours(1).unwrap();
+        let existing = vec![get_flight_plan(
+            &aircraft_a,
+            &vertiport_1,
+            &vertiport_2,
+            departure,
+        )];
+
+        let later = departure + Duration::try_minutes(10).unwrap();
+        let schedule = [
+            // aircraft A is in the air
+            get_flight_plan(&aircraft_a, &vertiport_1, &vertiport_2, later),
+            // aircraft B is free
+            get_flight_plan(&aircraft_b, &vertiport_1, &vertiport_2, later),
+            // aircraft B is free, but its target vertipad is occupied
+            get_flight_plan(&aircraft_b, &vertiport_1, &vertiport_2, later),
+        ];
+
+        let occupied = Timeslot::new(
+            schedule[2].target_timeslot_start,
+            schedule[2].target_timeslot_end,
+        )
+        .unwrap();
+
+        let vertipad_slots = [
+            (vec![day], vec![day]),
+            (vec![day], vec![day]),
+            (vec![day], day - occupied),
+        ];
+
+        let validations = schedule
+            .iter()
+            .zip(vertipad_slots.iter())
+            .map(|(flight_plan, (origin_slots, target_slots))| {
+                get_flight_plan_validation(
+                    flight_plan,
+                    &existing,
+                    &aircraft,
+                    &grace,
+                    origin_slots,
+                    target_slots,
+                )
+                .unwrap()
+            })
+            .collect::<Vec<FlightPlanValidation>>();
+
+        assert!(!validations[0].schedulable);
+        assert_eq!(
+            validations[0].conflict,
+            Some(FlightPlanConflict::AircraftUnavailable as i32)
+        );
+
+        assert!(validations[1].schedulable);
+        assert_eq!(validations[1].conflict, None);
+
+        assert!(!validations[2].schedulable);
+        assert_eq!(
+            validations[2].conflict,
+            Some(FlightPlanConflict::VertipadUnavailable as i32)
+        );
+    }
+}
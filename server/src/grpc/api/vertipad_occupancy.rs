@@ -0,0 +1,141 @@
+//! This module contains the gRPC vertipad_occupancy endpoint implementation.
+
+use lib_common::time::{DateTime, Utc};
+use lib_common::uuid::Uuid;
+use tonic::{Response, Status};
+
+use crate::grpc::client::get_clients;
+use crate::grpc::server::grpc_server::{
+    VertipadOccupancyRequest, VertipadOccupancyResponse, VertipadSlot,
+};
+use crate::router::flight_plan::get_sorted_flight_plans;
+use crate::router::schedule::Timeslot;
+use crate::router::vertiport::{get_vertipad_occupancy, get_vertipad_vertiport_id, VertiportError};
+
+/// Checks the request and returns the vertipad ID and the window
+fn validate_occupancy_request(
+    request: &VertipadOccupancyRequest,
+) -> Result<(String, Timeslot), Status> {
+    let vertipad_id = Uuid::parse_str(&request.vertipad_id)
+        .map_err(|_| Status::invalid_argument("vertipad_id: must be a UUID."))?
+        .to_string();
+
+    let time_start: DateTime<Utc> = request
+        .time_start
+        .clone()
+        .ok_or_else(|| Status::invalid_argument("time_start: must be provided."))?
+        .into();
+
+    let time_end: DateTime<Utc> = request
+        .time_end
+        .clone()
+        .ok_or_else(|| Status::invalid_argument("time_end: must be provided."))?
+        .into();
+
+    let window = Timeslot::new(time_start, time_end)
+        .map_err(|_| Status::invalid_argument("time_end: must be after time_start."))?;
+
+    Ok((vertipad_id, window))
+}
+
+impl From<Timeslot> for VertipadSlot {
+    fn from(timeslot: Timeslot) -> Self {
+        VertipadSlot {
+            time_start: Some(timeslot.time_start().into()),
+            time_end: Some(timeslot.time_end().into()),
+        }
+    }
+}
+
+/// Gets the occupied and free time of a vertipad over a window.
+/// Useful for pad-level dashboards.
+#[cfg(not(tarpaulin_include))]
+// no_coverage: (R5) needs running backend, integration tests
+pub async fn vertipad_occupancy(
+    request: VertipadOccupancyRequest,
+) -> Result<Response<VertipadOccupancyResponse>, Status> {
+    let (vertipad_id, window) = validate_occupancy_request(&request).map_err(|e| {
+        grpc_warn!("Invalid vertipad occupancy request: {}", e.message());
+        e
+    })?;
+
+    let clients = get_clients().await;
+    let vertiport_id = get_vertipad_vertiport_id(&vertipad_id, clients)
+        .await
+        .map_err(|e| match e {
+            VertiportError::NotFound => {
+                Status::not_found(format!("Vertipad {vertipad_id} not found."))
+            }
+            _ => Status::internal(format!("Could not get vertipad {vertipad_id}.")),
+        })?;
+
+    let flight_plans = get_sorted_flight_plans(clients).await.map_err(|e| {
+        grpc_error!("{}", e);
+        Status::internal("Could not get existing flight plans.")
+    })?;
+
+    let occupancy = get_vertipad_occupancy(&vertiport_id, &vertipad_id, &flight_plans, &window)
+        .map_err(|e| {
+            grpc_error!("Could not get the occupancy of vertipad {vertipad_id}: {e}");
+            Status::internal("Could not get vertipad occupancy.")
+        })?;
+
+    grpc_info!(
+        "Vertipad {vertipad_id} has {} occupied slots.",
+        occupancy.occupied.len()
+    );
+
+    Ok(Response::new(VertipadOccupancyResponse {
+        vertiport_id,
+        occupied: occupancy.occupied.into_iter().map(Into::into).collect(),
+        free: occupancy.free.into_iter().map(Into::into).collect(),
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use lib_common::time::Duration;
+
+    #[test]
+    fn test_validate_occupancy_request() {
+        let now = Utc::now();
+        let later = now + Duration::try_hours(1).unwrap();
+        let vertipad_id = Uuid::new_v4().to_string();
+        let request = VertipadOccupancyRequest {
+            vertipad_id: vertipad_id.clone(),
+            time_start: Some(now.into()),
+            time_end: Some(later.into()),
+        };
+
+        let (id, window) = validate_occupancy_request(&request).unwrap();
+        assert_eq!(id, vertipad_id);
+        assert_eq!(window, Timeslot::new(now, later).unwrap());
+
+        let mut tmp = request.clone();
+        tmp.vertipad_id = "invalid".to_string();
+        let e = validate_occupancy_request(&tmp).unwrap_err();
+        assert_eq!(e.code(), tonic::Code::InvalidArgument);
+
+        let mut tmp = request.clone();
+        tmp.time_end = None;
+        let e = validate_occupancy_request(&tmp).unwrap_err();
+        assert_eq!(e.code(), tonic::Code::InvalidArgument);
+
+        // window ending before it starts
+        let mut tmp = request;
+        tmp.time_start = Some(later.into());
+        tmp.time_end = Some(now.into());
+        let e = validate_occupancy_request(&tmp).unwrap_err();
+        assert_eq!(e.code(), tonic::Code::InvalidArgument);
+    }
+
+    #[test]
+    fn test_vertipad_slot_from_timeslot() {
+        let now = Utc::now();
+        let later = now + Duration::try_minutes(5).unwrap();
+        let slot: VertipadSlot = Timeslot::new(now, later).unwrap().into();
+        assert_eq!(slot.time_start, Some(now.into()));
+        assert_eq!(slot.time_end, Some(later.into()));
+    }
+}
@@ -0,0 +1,406 @@
+//! Bearer token authentication and authorization for the gRPC server
+//! Callers send `authorization: Bearer <token>` metadata. Tokens are
+//!  configured for users (bound to a user ID) and for operators.
+
+use lib_common::uuid::to_uuid;
+use std::collections::HashMap;
+use std::fmt::{Display, Formatter, Result as FmtResult};
+use tonic::{Request, Status};
+
+/// Metadata key carrying the bearer token
+const AUTHORIZATION_KEY: &str = "authorization";
+
+/// Prefix of the authorization metadata value
+const BEARER_PREFIX: &str = "Bearer ";
+
+/// Separates configured tokens
+const TOKEN_SEPARATOR: char = ',';
+
+/// Separates a user token from its user ID
+const USER_SEPARATOR: char = ':';
+
+/// An authenticated caller
+#[derive(Debug, Clone, PartialEq)]
+pub enum Caller {
+    /// A user that may only act on their own behalf
+    User(String),
+
+    /// An operator that may act on behalf of any user
+    Operator,
+}
+
+/// Errors parsing the configured auth tokens
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum AuthError {
+    /// A user token isn't formatted as `token:user_id`
+    Format,
+
+    /// A user token has an invalid user ID
+    UserId,
+}
+
+impl Display for AuthError {
+    fn fmt(&self, f: &mut Formatter) -> FmtResult {
+        match self {
+            AuthError::Format => write!(f, "User token is not formatted as token:user_id"),
+            AuthError::UserId => write!(f, "User token has an invalid user ID"),
+        }
+    }
+}
+
+/// Known bearer tokens and their callers
+#[derive(Debug, Clone, Default)]
+pub struct AuthTokens {
+    tokens: HashMap<String, Caller>,
+}
+
+impl AuthTokens {
+    /// Parses the configured user and operator tokens
+    pub fn try_from_config(user_tokens: &str, operator_tokens: &str) -> Result<Self, AuthError> {
+        let split = |tokens: &str| {
+            tokens
+                .split(TOKEN_SEPARATOR)
+                .map(str::trim)
+                .filter(|token| !token.is_empty())
+                .map(str::to_string)
+                .collect::<Vec<String>>()
+        };
+
+        let mut tokens = HashMap::new();
+        for entry in split(user_tokens) {
+            let (token, user_id) = entry.split_once(USER_SEPARATOR).ok_or_else(|| {
+                grpc_error!("Invalid user token entry.");
+                AuthError::Format
+            })?;
+
+            let user_id = to_uuid(user_id.trim()).ok_or_else(|| {
+                grpc_error!("Invalid user ID for user token: {user_id}");
+                AuthError::UserId
+            })?;
+
+            tokens.insert(token.trim().to_string(), Caller::User(user_id.to_string()));
+        }
+
+        for token in split(operator_tokens) {
+            tokens.insert(token, Caller::Operator);
+        }
+
+        Ok(AuthTokens { tokens })
+    }
+
+    /// Authentication is disabled if no tokens are configured
+    pub fn is_empty(&self) -> bool {
+        self.tokens.is_empty()
+    }
+}
+
+/// Interceptor that rejects requests without a known bearer token.
+/// The authenticated [`Caller`] is attached to the request for [`authorize`].
+pub fn authenticate(tokens: &AuthTokens, mut request: Request<()>) -> Result<Request<()>, Status> {
+    if tokens.is_empty() {
+        return Ok(request);
+    }
+
+    let token = request
+        .metadata()
+        .get(AUTHORIZATION_KEY)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix(BEARER_PREFIX))
+        .ok_or_else(|| {
+            grpc_warn!("Request without a bearer token.");
+            Status::unauthenticated("Missing bearer token.")
+        })?;
+
+    let caller = tokens.tokens.get(token.trim()).cloned().ok_or_else(|| {
+        grpc_warn!("Request with an unknown bearer token.");
+        Status::unauthenticated("Invalid bearer token.")
+    })?;
+
+    request.extensions_mut().insert(caller);
+    Ok(request)
+}
+
+/// Returns true if both IDs are the same user
+fn is_same_user(user_id: &str, other_id: &str) -> bool {
+    match (to_uuid(user_id), to_uuid(other_id)) {
+        (Some(user_id), Some(other_id)) => user_id == other_id,
+        _ => false,
+    }
+}
+
+/// Checks that the caller may act on behalf of the given user.
+/// Allows every request if authentication is disabled.
+pub fn authorize<T>(request: &Request<T>, user_id: &str) -> Result<(), Status> {
+    match request.extensions().get::<Caller>() {
+        None | Some(Caller::Operator) => Ok(()),
+        Some(Caller::User(caller_id)) => {
+            if is_same_user(caller_id, user_id) {
+                return Ok(());
+            }
+
+            grpc_warn!("User {caller_id} tried to act on behalf of user {user_id}.");
+            Err(Status::permission_denied(
+                "Only the owning user or an operator may do this.",
+            ))
+        }
+    }
+}
+
+/// Checks that the caller is an operator.
+/// Allows every request if authentication is disabled.
+pub fn authorize_operator<T>(request: &Request<T>) -> Result<(), Status> {
+    match request.extensions().get::<Caller>() {
+        None | Some(Caller::Operator) => Ok(()),
+        Some(Caller::User(caller_id)) => {
+            grpc_warn!("User {caller_id} tried to perform an operator action.");
+            Err(Status::permission_denied("Only an operator may do this."))
+        }
+    }
+}
+
+/// Returns true if the caller is an operator.
+/// Every caller is an operator if authentication is disabled.
+pub fn is_operator<T>(request: &Request<T>) -> bool {
+    matches!(
+        request.extensions().get::<Caller>(),
+        None | Some(Caller::Operator)
+    )
+}
+
+/// Recorded in the audit trail as the actor of changes made by operators,
+///  whose tokens don't identify them
+pub const OPERATOR_ACTOR_ID: &str = "operator";
+
+/// Returns who is acting, for the audit trail: the authenticated user,
+///  or [`OPERATOR_ACTOR_ID`] for operators.
+/// If authentication is disabled, the caller is the `user_id` in the request.
+pub fn get_actor_id<T>(request: &Request<T>, user_id: &str) -> String {
+    match request.extensions().get::<Caller>() {
+        Some(Caller::User(caller_id)) => caller_id.clone(),
+        Some(Caller::Operator) => OPERATOR_ACTOR_ID.to_string(),
+        None => user_id.to_string(),
+    }
+}
+
+/// Returns the ID of the authenticated user, if the caller is a user.
+pub fn get_caller_id<T>(request: &Request<T>) -> Option<String> {
+    match request.extensions().get::<Caller>() {
+        Some(Caller::User(caller_id)) => Some(caller_id.clone()),
+        None | Some(Caller::Operator) => None,
+    }
+}
+
+/// Checks that the caller owns a resource or is an operator.
+/// If authentication is disabled, the caller is the `user_id` in the request.
+pub fn authorize_owner<T>(
+    request: &Request<T>,
+    user_id: &str,
+    owner_id: &str,
+) -> Result<(), Status> {
+    let caller_id = match request.extensions().get::<Caller>() {
+        Some(Caller::Operator) => return Ok(()),
+        Some(Caller::User(caller_id)) => caller_id.as_str(),
+        None => user_id,
+    };
+
+    if is_same_user(caller_id, owner_id) {
+        return Ok(());
+    }
+
+    grpc_warn!("User {caller_id} tried to act on a resource owned by user {owner_id}.");
+    Err(Status::permission_denied(
+        "Only the owning user or an operator may do this.",
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::grpc::server::CancelItineraryRequest;
+    use lib_common::uuid::Uuid;
+    use tonic::Code;
+
+    const USER_TOKEN: &str = "user-token";
+    const OPERATOR_TOKEN: &str = "operator-token";
+
+    fn get_tokens(user_id: &str) -> AuthTokens {
+        AuthTokens::try_from_config(&format!("{USER_TOKEN}:{user_id}"), OPERATOR_TOKEN).unwrap()
+    }
+
+    /// Builds a cancellation request as seen by the server after the interceptor
+    fn get_cancel_request(
+        tokens: &AuthTokens,
+        token: Option<&str>,
+        user_id: &str,
+    ) -> Result<Request<CancelItineraryRequest>, Status> {
+        let mut request = Request::new(());
+        if let Some(token) = token {
+            request.metadata_mut().insert(
+                AUTHORIZATION_KEY,
+                format!("{BEARER_PREFIX}{token}").parse().unwrap(),
+            );
+        }
+
+        let request = authenticate(tokens, request)?;
+        let (metadata, extensions, _) = request.into_parts();
+        Ok(Request::from_parts(
+            metadata,
+            extensions,
+            CancelItineraryRequest {
+                priority: 0,
+                itinerary_id: Uuid::new_v4().to_string(),
+                user_id: user_id.to_string(),
+                cancellation_kind: 0,
+            },
+        ))
+    }
+
+    #[test]
+    fn test_auth_tokens_try_from_config() {
+        let user_id = Uuid::new_v4().to_string();
+        let tokens = get_tokens(&user_id);
+        assert_eq!(
+            tokens.tokens.get(USER_TOKEN),
+            Some(&Caller::User(user_id.clone()))
+        );
+        assert_eq!(tokens.tokens.get(OPERATOR_TOKEN), Some(&Caller::Operator));
+
+        assert!(AuthTokens::try_from_config("", "").unwrap().is_empty());
+
+        let e = AuthTokens::try_from_config(USER_TOKEN, "").unwrap_err();
+        assert_eq!(e, AuthError::Format);
+
+        let e = AuthTokens::try_from_config(&format!("{USER_TOKEN}:invalid"), "").unwrap_err();
+        assert_eq!(e, AuthError::UserId);
+    }
+
+    #[test]
+    fn test_authenticate() {
+        let tokens = get_tokens(&Uuid::new_v4().to_string());
+        let user_id = Uuid::new_v4().to_string();
+
+        let e = get_cancel_request(&tokens, None, &user_id).unwrap_err();
+        assert_eq!(e.code(), Code::Unauthenticated);
+
+        let e = get_cancel_request(&tokens, Some("unknown"), &user_id).unwrap_err();
+        assert_eq!(e.code(), Code::Unauthenticated);
+
+        // no tokens configured
+        get_cancel_request(&AuthTokens::default(), None, &user_id).unwrap();
+    }
+
+    #[test]
+    fn test_authorize_cancel() {
+        let owner_id = Uuid::new_v4().to_string();
+        let tokens = get_tokens(&owner_id);
+
+        // the owner can cancel their itinerary
+        let request = get_cancel_request(&tokens, Some(USER_TOKEN), &owner_id).unwrap();
+        authorize(&request, &request.get_ref().user_id).unwrap();
+
+        // but not another user's itinerary
+        let other_id = Uuid::new_v4().to_string();
+        let request = get_cancel_request(&tokens, Some(USER_TOKEN), &other_id).unwrap();
+        let e = authorize(&request, &request.get_ref().user_id).unwrap_err();
+        assert_eq!(e.code(), Code::PermissionDenied);
+
+        // operators can cancel any itinerary
+        let request = get_cancel_request(&tokens, Some(OPERATOR_TOKEN), &other_id).unwrap();
+        authorize(&request, &request.get_ref().user_id).unwrap();
+    }
+
+    #[test]
+    fn test_authorize_operator() {
+        let user_id = Uuid::new_v4().to_string();
+        let tokens = get_tokens(&user_id);
+
+        let request = get_cancel_request(&tokens, Some(USER_TOKEN), &user_id).unwrap();
+        let e = authorize_operator(&request).unwrap_err();
+        assert_eq!(e.code(), Code::PermissionDenied);
+        assert!(!is_operator(&request));
+        assert_eq!(get_caller_id(&request), Some(user_id.clone()));
+        assert_eq!(get_actor_id(&request, "other"), user_id);
+
+        let request = get_cancel_request(&tokens, Some(OPERATOR_TOKEN), &user_id).unwrap();
+        authorize_operator(&request).unwrap();
+        assert!(is_operator(&request));
+        assert_eq!(get_caller_id(&request), None);
+        assert_eq!(get_actor_id(&request, &user_id), OPERATOR_ACTOR_ID);
+
+        // no tokens configured
+        let request = get_cancel_request(&AuthTokens::default(), None, &user_id).unwrap();
+        authorize_operator(&request).unwrap();
+        assert!(is_operator(&request));
+    }
+
+    #[test]
+    fn test_authorize_owner_cancel() {
+        let user_a = Uuid::new_v4().to_string();
+        let user_b = Uuid::new_v4().to_string();
+        let tokens = AuthTokens::try_from_config(
+            &format!("token-a:{user_a},token-b:{user_b}"),
+            OPERATOR_TOKEN,
+        )
+        .unwrap();
+
+        // user B can't cancel user A's itinerary, even naming user A in the request
+        let request = get_cancel_request(&tokens, Some("token-b"), &user_a).unwrap();
+        let e = authorize_owner(&request, &request.get_ref().user_id, &user_a).unwrap_err();
+        assert_eq!(e.code(), Code::PermissionDenied);
+
+        // user A can
+        let request = get_cancel_request(&tokens, Some("token-a"), &user_a).unwrap();
+        authorize_owner(&request, &request.get_ref().user_id, &user_a).unwrap();
+
+        // and so can an operator
+        let request = get_cancel_request(&tokens, Some(OPERATOR_TOKEN), &user_b).unwrap();
+        authorize_owner(&request, &request.get_ref().user_id, &user_a).unwrap();
+
+        // without authentication, the request's user must own the itinerary
+        let tokens = AuthTokens::default();
+        let request = get_cancel_request(&tokens, None, &user_b).unwrap();
+        let e = authorize_owner(&request, &request.get_ref().user_id, &user_a).unwrap_err();
+        assert_eq!(e.code(), Code::PermissionDenied);
+
+        let request = get_cancel_request(&tokens, None, &user_a).unwrap();
+        authorize_owner(&request, &request.get_ref().user_id, &user_a).unwrap();
+    }
+
+    #[test]
+    fn test_authorize_owner_without_request_user() {
+        // requests without a user ID, such as task cancellations, name the owner
+        let user_a = Uuid::new_v4().to_string();
+        let user_b = Uuid::new_v4().to_string();
+        let tokens = AuthTokens::try_from_config(
+            &format!("token-a:{user_a},token-b:{user_b}"),
+            OPERATOR_TOKEN,
+        )
+        .unwrap();
+
+        let request = get_cancel_request(&tokens, Some("token-b"), "").unwrap();
+        let e = authorize_owner(&request, &user_a, &user_a).unwrap_err();
+        assert_eq!(e.code(), Code::PermissionDenied);
+
+        let request = get_cancel_request(&tokens, Some("token-a"), "").unwrap();
+        authorize_owner(&request, &user_a, &user_a).unwrap();
+
+        let request = get_cancel_request(&tokens, Some(OPERATOR_TOKEN), "").unwrap();
+        authorize_owner(&request, &user_a, &user_a).unwrap();
+
+        // no tokens configured
+        let request = get_cancel_request(&AuthTokens::default(), None, "").unwrap();
+        authorize_owner(&request, &user_a, &user_a).unwrap();
+    }
+
+    #[test]
+    fn test_auth_error_display() {
+        assert_eq!(
+            format!("{}", AuthError::Format),
+            "User token is not formatted as token:user_id"
+        );
+        assert_eq!(
+            format!("{}", AuthError::UserId),
+            "User token has an invalid user ID"
+        );
+    }
+}
@@ -1,11 +1,19 @@
 //! gRPC client helpers implementation
+use std::future::Future;
+use std::ops::Deref;
+use std::sync::Arc;
 use svc_gis_client_grpc::prelude::Client;
 use svc_gis_client_grpc::prelude::GisClient;
-use svc_storage_client_grpc::prelude::Clients;
-use tokio::sync::OnceCell;
+use svc_storage_client_grpc::prelude::{AdvancedSearchFilter, Clients};
+use svc_storage_client_grpc::simple_service::Client as _;
+use tokio::sync::{OnceCell, Semaphore, SemaphorePermit};
 
 pub(crate) static CLIENTS: OnceCell<GrpcClients> = OnceCell::const_new();
 
+/// Upper bound on pages fetched for one search, in case a backend
+///  ignores the page number and keeps returning full pages
+const MAX_SEARCH_PAGES: i32 = 1000;
+
 /// Returns CLIENTS, a GrpcClients object with default values.
 /// Uses host and port configurations using a Config object generated from
 /// environment variables.
@@ -22,27 +30,167 @@ pub async fn get_clients() -> &'static GrpcClients {
 /// Struct to hold all gRPC client connections
 #[derive(Clone, Debug)]
 pub struct GrpcClients {
-    /// All clients enabled from the svc_storage_grpc_client module
-    pub storage: Clients,
+    /// All clients enabled from the svc_storage_grpc_client module,
+    ///  accessed through [`GrpcClients::storage`]
+    storage: Clients,
+    /// Limits the svc-storage calls in flight, shared between clones
+    storage_permits: Option<Arc<Semaphore>>,
     /// A GrpcClient provided by the svc_gis_grpc_client module
     pub gis: GisClient,
 }
 
+/// The svc-storage clients, holding a slot of the concurrency limit
+///  until dropped
+#[derive(Debug)]
+pub struct StorageClients<'a> {
+    clients: &'a Clients,
+    _permit: Option<SemaphorePermit<'a>>,
+}
+
+impl Deref for StorageClients<'_> {
+    type Target = Clients;
+
+    fn deref(&self) -> &Clients {
+        self.clients
+    }
+}
+
 impl GrpcClients {
     /// Create new GrpcClients with defaults
     pub fn default(config: crate::config::Config) -> Self {
         let storage_clients = Clients::new(config.storage_host_grpc, config.storage_port_grpc);
+        let storage_permits = match config.storage_concurrency_limit {
+            0 => None,
+            limit => Some(Arc::new(Semaphore::new(limit as usize))),
+        };
 
         GrpcClients {
             storage: storage_clients,
+            storage_permits,
             gis: GisClient::new_client(&config.gis_host_grpc, config.gis_port_grpc, "gis"),
         }
     }
+
+    /// Waits for a free slot and returns the svc-storage clients.
+    /// The slot is held until the returned value is dropped, keep it
+    ///  only for the duration of a call so that a burst of queries
+    ///  can't overwhelm svc-storage.
+    pub async fn storage(&self) -> StorageClients<'_> {
+        let permit = match &self.storage_permits {
+            Some(permits) => permits
+                .acquire()
+                .await
+                .map_err(|e| {
+                    // only if the semaphore was closed, which it never is
+                    grpc_error!("could not limit svc-storage calls: {e}");
+                })
+                .ok(),
+            None => None,
+        };
+
+        StorageClients {
+            clients: &self.storage,
+            _permit: permit,
+        }
+    }
+}
+
+/// Runs a svc-storage search one page at a time until every result is loaded.
+/// A page with fewer than `page_size` results is the last page.
+pub async fn search_all_pages<T, E, F, Fut>(
+    mut filter: AdvancedSearchFilter,
+    page_size: u32,
+    mut search: F,
+) -> Result<Vec<T>, E>
+where
+    F: FnMut(AdvancedSearchFilter) -> Fut,
+    Fut: Future<Output = Result<Vec<T>, E>>,
+{
+    let page_size = i32::try_from(page_size).unwrap_or(i32::MAX).max(1);
+    filter.results_per_page = page_size;
+
+    let mut results = vec![];
+    for page_number in 0..MAX_SEARCH_PAGES {
+        filter.page_number = page_number;
+
+        let page = search(filter.clone()).await?;
+        let is_last = page.len() < page_size as usize;
+        results.extend(page);
+
+        if is_last {
+            return Ok(results);
+        }
+    }
+
+    grpc_warn!("search stopped after {MAX_SEARCH_PAGES} pages, results may be incomplete.");
+    Ok(results)
+}
+
+/// Runs a svc-storage call until it succeeds, at most `attempts` times.
+/// The wait between attempts starts at `backoff` and doubles after each
+///  failure. The last error is returned if every attempt fails.
+pub async fn retry_with_backoff<T, E, F, Fut>(
+    attempts: u32,
+    backoff: std::time::Duration,
+    mut call: F,
+) -> Result<T, E>
+where
+    F: FnMut() -> Fut,
+    Fut: Future<Output = Result<T, E>>,
+    E: std::fmt::Display,
+{
+    let attempts = attempts.max(1);
+    let mut backoff = backoff;
+    let mut attempt = 1;
+    loop {
+        match call().await {
+            Ok(result) => return Ok(result),
+            Err(e) if attempt < attempts => {
+                grpc_warn!(
+                    "attempt {attempt} of {attempts} failed, retrying in {} ms: {e}",
+                    backoff.as_millis()
+                );
+                tokio::time::sleep(backoff).await;
+                backoff = backoff.saturating_mul(2);
+                attempt += 1;
+            }
+            Err(e) => {
+                grpc_error!("all {attempts} attempts failed: {e}");
+                return Err(e);
+            }
+        }
+    }
+}
+
+/// Returns the longest wait between two of `attempts` attempts made by
+///  [`retry_with_backoff`], and at least `backoff`
+pub fn get_backoff_cap(attempts: u32, backoff: std::time::Duration) -> std::time::Duration {
+    let waits = attempts.max(2) - 1;
+    backoff.saturating_mul(2u32.saturating_pow(waits - 1))
+}
+
+/// Checks that vertiports can be read from svc-storage, at most
+///  `attempts` times with backoff between attempts
+#[cfg(not(tarpaulin_include))]
+// no_coverage: (R5) needs running backend, integration tests
+pub async fn check_storage(
+    clients: &GrpcClients,
+    attempts: u32,
+    backoff: std::time::Duration,
+) -> Result<(), tonic::Status> {
+    retry_with_backoff(attempts, backoff, || async {
+        let mut filter = AdvancedSearchFilter::search_is_null("deleted_at".to_owned());
+        filter.results_per_page = 1;
+        clients.storage().await.vertiport.search(filter).await
+    })
+    .await
+    .map(|_| ())
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
     use svc_gis_client_grpc::prelude::Client as GisClient;
     use svc_storage_client_grpc::prelude::Client as StorageClient;
 
@@ -53,30 +201,31 @@ mod tests {
 
         let clients = get_clients().await;
 
-        let vehicle = &clients.storage.vehicle;
+        let storage = clients.storage().await;
+        let vehicle = &storage.vehicle;
         ut_debug!("vehicle: {:?}", vehicle);
         assert_eq!(vehicle.get_name(), "vehicle");
 
-        let vertipad = &clients.storage.vertipad;
+        let vertipad = &storage.vertipad;
         ut_debug!("vertipad: {:?}", vertipad);
         assert_eq!(vertipad.get_name(), "vertipad");
 
-        let vertiport = &clients.storage.vertiport;
+        let vertiport = &storage.vertiport;
         ut_debug!("vertiport: {:?}", vertiport);
         assert_eq!(vertiport.get_name(), "vertiport");
 
-        let itinerary = &clients.storage.itinerary;
+        let itinerary = &storage.itinerary;
         ut_debug!("itinerary: {:?}", itinerary);
         assert_eq!(itinerary.get_name(), "itinerary");
 
-        let itinerary_flight_plan = &clients.storage.itinerary_flight_plan_link;
+        let itinerary_flight_plan = &storage.itinerary_flight_plan_link;
         ut_debug!("itinerary_flight_plan: {:?}", itinerary_flight_plan);
         assert_eq!(
             itinerary_flight_plan.get_name(),
             "itinerary_flight_plan_link"
         );
 
-        let flight_plan = &clients.storage.flight_plan;
+        let flight_plan = &storage.flight_plan;
         ut_debug!("flight_plan: {:?}", flight_plan);
         assert_eq!(flight_plan.get_name(), "flight_plan");
 
@@ -86,4 +235,147 @@ mod tests {
 
         ut_info!("Success.");
     }
+
+    #[tokio::test]
+    async fn test_storage_concurrency_limit() {
+        lib_common::logger::get_log_handle().await;
+        ut_info!("Start.");
+
+        let limit = 3;
+        let config = crate::Config {
+            storage_concurrency_limit: limit,
+            ..Default::default()
+        };
+        let clients = GrpcClients::default(config);
+        let in_flight = AtomicUsize::new(0);
+        let max_in_flight = AtomicUsize::new(0);
+
+        // a burst of calls larger than the limit
+        let calls = (0..limit * 4).map(|_| async {
+            let _storage = clients.storage().await;
+            let current = in_flight.fetch_add(1, Ordering::SeqCst) + 1;
+            max_in_flight.fetch_max(current, Ordering::SeqCst);
+            tokio::time::sleep(std::time::Duration::from_millis(10)).await;
+            in_flight.fetch_sub(1, Ordering::SeqCst);
+        });
+        futures::future::join_all(calls).await;
+
+        assert_eq!(max_in_flight.load(Ordering::SeqCst), limit as usize);
+
+        // no limit
+        let config = crate::Config {
+            storage_concurrency_limit: 0,
+            ..Default::default()
+        };
+        let clients = GrpcClients::default(config);
+        let held = futures::future::join_all((0..limit * 4).map(|_| clients.storage())).await;
+        assert_eq!(held.len(), limit as usize * 4);
+
+        ut_info!("Success.");
+    }
+
+    /// Fake storage search that honours paging
+    async fn search_page(
+        vertiport_ids: &[String],
+        filter: AdvancedSearchFilter,
+    ) -> Result<Vec<String>, ()> {
+        let page_size = filter.results_per_page as usize;
+        let start = filter.page_number as usize * page_size;
+        Ok(vertiport_ids
+            .iter()
+            .skip(start)
+            .take(page_size)
+            .cloned()
+            .collect())
+    }
+
+    #[tokio::test]
+    async fn test_search_all_pages() {
+        lib_common::logger::get_log_handle().await;
+        ut_info!("Start.");
+
+        // more vertiports than fit in one page
+        let vertiport_ids: Vec<String> = (0..25).map(|i| format!("vertiport-{i}")).collect();
+        let mut calls = 0;
+        let result = search_all_pages(AdvancedSearchFilter::default(), 10, |filter| {
+            calls += 1;
+            search_page(&vertiport_ids, filter)
+        })
+        .await
+        .unwrap();
+        assert_eq!(result, vertiport_ids);
+        assert_eq!(calls, 3);
+
+        // exactly one full page needs a second, empty page to confirm the end
+        let mut calls = 0;
+        let result = search_all_pages(AdvancedSearchFilter::default(), 25, |filter| {
+            calls += 1;
+            search_page(&vertiport_ids, filter)
+        })
+        .await
+        .unwrap();
+        assert_eq!(result.len(), 25);
+        assert_eq!(calls, 2);
+
+        // errors are passed through
+        let result: Result<Vec<String>, ()> =
+            search_all_pages(AdvancedSearchFilter::default(), 10, |_| async { Err(()) }).await;
+        assert!(result.is_err());
+
+        ut_info!("Success.");
+    }
+
+    #[tokio::test]
+    async fn test_retry_with_backoff() {
+        lib_common::logger::get_log_handle().await;
+        ut_info!("Start.");
+
+        let backoff = std::time::Duration::from_millis(1);
+
+        // storage fails twice, then answers
+        let mut calls = 0;
+        let result = retry_with_backoff(5, backoff, || {
+            calls += 1;
+            let result = match calls {
+                1 | 2 => Err("storage unavailable"),
+                _ => Ok(calls),
+            };
+            async move { result }
+        })
+        .await;
+        assert_eq!(result, Ok(3));
+        assert_eq!(calls, 3);
+
+        // storage fails more often than retried
+        let mut calls = 0;
+        let result: Result<(), &str> = retry_with_backoff(3, backoff, || {
+            calls += 1;
+            async { Err("storage unavailable") }
+        })
+        .await;
+        assert_eq!(result, Err("storage unavailable"));
+        assert_eq!(calls, 3);
+
+        // always tried at least once
+        let mut calls = 0;
+        let result: Result<(), &str> = retry_with_backoff(0, backoff, || {
+            calls += 1;
+            async { Err("storage unavailable") }
+        })
+        .await;
+        assert!(result.is_err());
+        assert_eq!(calls, 1);
+
+        ut_info!("Success.");
+    }
+
+    #[test]
+    fn test_get_backoff_cap() {
+        let backoff = std::time::Duration::from_millis(500);
+        assert_eq!(get_backoff_cap(0, backoff), backoff);
+        assert_eq!(get_backoff_cap(1, backoff), backoff);
+        assert_eq!(get_backoff_cap(2, backoff), backoff);
+        assert_eq!(get_backoff_cap(5, backoff), backoff * 8);
+        assert_eq!(get_backoff_cap(u32::MAX, backoff), backoff * u32::MAX);
+    }
 }
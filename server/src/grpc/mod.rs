@@ -4,5 +4,6 @@
 #[macro_use]
 pub mod macros;
 pub mod api;
+pub mod auth;
 pub mod client;
 pub mod server;
@@ -6,11 +6,20 @@ pub mod grpc_server {
 }
 pub use grpc_server::rpc_service_server::{RpcService, RpcServiceServer};
 pub use grpc_server::{
-    CancelItineraryRequest, CreateItineraryRequest, Itinerary, QueryFlightRequest,
-    QueryFlightResponse, ReadyRequest, ReadyResponse, TaskAction, TaskMetadata, TaskRequest,
-    TaskResponse, TaskStatus,
+    CancelItineraryRequest, CancelUserDraftsRequest, CancelUserDraftsResponse,
+    CancelVehicleItinerariesRequest, CancelVehicleItinerariesResponse, CheckFlightPlanRequest,
+    CheckFlightPlanResponse, CreateItineraryRequest, EstimateDeadheadsRequest,
+    EstimateDeadheadsResponse, GetFleetStatusRequest, GetFleetStatusResponse, GetItineraryRequest,
+    Itinerary, ListQueuedTasksRequest, ListQueuedTasksResponse, ListUserItinerariesRequest,
+    ListUserItinerariesResponse, QueryFlightRequest, QueryFlightResponse, QueuedTask,
+    ReachableVertiportsRequest, ReachableVertiportsResponse, ReadyRequest, ReadyResponse,
+    RequeueTaskRequest, RestoreItineraryRequest, SuggestRepositioningRequest,
+    SuggestRepositioningResponse, TaskAction, TaskMetadata, TaskRequest, TaskResponse, TaskStatus,
+    ValidateScheduleRequest, ValidateScheduleResponse, VehicleInfo, VertipadOccupancyRequest,
+    VertipadOccupancyResponse,
 };
 
+use super::auth::{authenticate, AuthTokens};
 use crate::shutdown_signal;
 use crate::Config;
 
@@ -19,8 +28,29 @@ use std::net::SocketAddr;
 use tonic::transport::Server;
 use tonic::{Request, Response, Status};
 
+#[cfg(not(feature = "stub_server"))]
+use super::api::cancel::CancelItineraryError;
+#[cfg(not(feature = "stub_server"))]
+use super::auth::{
+    authorize, authorize_operator, authorize_owner, get_actor_id, get_caller_id, is_operator,
+    OPERATOR_ACTOR_ID,
+};
+#[cfg(not(feature = "stub_server"))]
+use crate::tasks::TaskError;
+#[cfg(not(feature = "stub_server"))]
+use num_traits::FromPrimitive;
 #[cfg(feature = "stub_server")]
 use rand::Rng;
+#[cfg(not(feature = "stub_server"))]
+use std::sync::atomic::{AtomicBool, Ordering};
+#[cfg(not(feature = "stub_server"))]
+use tonic::Code;
+#[cfg(not(feature = "stub_server"))]
+use tonic_health::server::HealthReporter;
+
+/// Set once svc-storage could be read at startup
+#[cfg(not(feature = "stub_server"))]
+static STORAGE_READY: AtomicBool = AtomicBool::new(false);
 
 /// struct to implement the gRPC server functions
 #[derive(Debug, Copy, Clone, Default)]
@@ -38,12 +68,28 @@ impl RpcService for ServerImpl {
         grpc_info!("scheduler server.");
         grpc_debug!("request: {:?}", request);
 
+        // Only the user or an operator may waitlist on the user's behalf
+        if let Some(user_id) = &request.get_ref().waitlist_user_id {
+            authorize(&request, user_id)?;
+        }
+
+        // Only an operator may waive the advance notice for an emergency
+        if request.get_ref().emergency {
+            authorize_operator(&request)?;
+        }
+
+        // The querying user's own return holds are available to the query
+        let caller = super::api::query_flight::QueryCaller {
+            user_id: get_caller_id(&request).or(request.get_ref().waitlist_user_id.clone()),
+            is_operator: is_operator(&request),
+        };
+
         let request = request.into_inner();
-        super::api::query_flight::query_flight(request)
+        super::api::query_flight::query_flight(request, &caller)
             .await
             .map_err(|e| {
                 grpc_error!("error: {}", e);
-                Status::internal("Could not query flight.")
+                e
             })
     }
 
@@ -58,12 +104,18 @@ impl RpcService for ServerImpl {
         grpc_info!("scheduler server.");
         grpc_debug!("request: {:?}", request);
 
+        authorize(&request, &request.get_ref().user_id)?;
+        let is_operator = is_operator(&request);
+        let actor_id = get_actor_id(&request, &request.get_ref().user_id);
         let request = request.into_inner();
-        let response = super::api::create::create_itinerary(request)
+        let response = super::api::create::create_itinerary(request, is_operator, &actor_id, None)
             .await
-            .map_err(|e| {
-                grpc_error!("error: {}", e);
-                Status::internal("Could not create itinerary.")
+            .map_err(|e| match e.code() {
+                Code::ResourceExhausted => e,
+                _ => {
+                    grpc_error!("error: {}", e);
+                    Status::internal("Could not create itinerary.")
+                }
             })?;
 
         Ok(Response::new(response))
@@ -80,17 +132,141 @@ impl RpcService for ServerImpl {
         grpc_info!("scheduler server.");
         grpc_debug!("request: {:?}", request);
 
+        // Only the owner or an operator may cancel an itinerary
+        let owner_id =
+            super::api::get_itinerary::get_itinerary_owner(&request.get_ref().itinerary_id).await?;
+
+        authorize_owner(&request, &request.get_ref().user_id, &owner_id)?;
+        let actor_id = get_actor_id(&request, &request.get_ref().user_id);
+        let mut request = request.into_inner();
+
+        // Operators cancel on behalf of the owner
+        request.user_id = owner_id;
+        let response = super::api::cancel::cancel_itinerary(request, &actor_id)
+            .await
+            .map_err(|e| match e {
+                CancelItineraryError::QueueFull => {
+                    Status::resource_exhausted("Task queue is full, try again later.")
+                }
+                e => {
+                    grpc_error!("error: {}", e);
+                    Status::internal("Could not cancel itinerary.")
+                }
+            })?;
+
+        Ok(Response::new(response))
+    }
+
+    /// Cancels all upcoming itineraries for a grounded vehicle.
+    async fn cancel_vehicle_itineraries(
+        &self,
+        request: Request<CancelVehicleItinerariesRequest>,
+    ) -> Result<Response<CancelVehicleItinerariesResponse>, Status>
+    where
+        Self: Send,
+    {
+        grpc_info!("scheduler server.");
+        grpc_debug!("request: {:?}", request);
+
+        // Grounding an aircraft cancels itineraries of many users
+        authorize_operator(&request)?;
+        let actor_id = get_actor_id(&request, OPERATOR_ACTOR_ID);
         let request = request.into_inner();
-        let response = super::api::cancel::cancel_itinerary(request)
+        let response = super::api::cancel::cancel_vehicle_itineraries(request, &actor_id)
             .await
-            .map_err(|e| {
-                grpc_error!("error: {}", e);
-                Status::internal("Could not cancel itinerary.")
+            .map_err(|e| match e {
+                CancelItineraryError::VehicleId => {
+                    Status::invalid_argument("vehicle_id: must be a valid UUID.")
+                }
+                e => {
+                    grpc_error!("error: {}", e);
+                    Status::internal("Could not cancel vehicle itineraries.")
+                }
             })?;
 
         Ok(Response::new(response))
     }
 
+    /// Suggests deadhead flights to move idle aircraft toward under-served hubs.
+    /// Suggested flight plans are not booked (operators only).
+    async fn suggest_repositioning(
+        &self,
+        request: Request<SuggestRepositioningRequest>,
+    ) -> Result<Response<SuggestRepositioningResponse>, Status> {
+        grpc_info!("scheduler server.");
+        grpc_debug!("request: {:?}", request);
+        authorize_operator(&request)?;
+
+        let request = request.into_inner();
+        super::api::repositioning::suggest_repositioning(request)
+            .await
+            .map_err(|e| {
+                grpc_error!("error: {}", e);
+                e
+            })
+    }
+
+    /// Checks whether a fully specified flight plan can be booked as-is
+    ///  (operators only)
+    async fn check_flight_plan(
+        &self,
+        request: Request<CheckFlightPlanRequest>,
+    ) -> Result<Response<CheckFlightPlanResponse>, Status> {
+        grpc_info!("scheduler server.");
+        grpc_debug!("request: {:?}", request);
+        authorize_operator(&request)?;
+
+        let request = request.into_inner();
+        super::api::check_flight_plan::check_flight_plan(request)
+            .await
+            .map_err(|e| {
+                grpc_error!("error: {}", e);
+                e
+            })
+    }
+
+    /// Returns a booked itinerary with its flight plans
+    async fn get_itinerary(
+        &self,
+        request: Request<GetItineraryRequest>,
+    ) -> Result<Response<Itinerary>, Status> {
+        grpc_info!("scheduler server.");
+        grpc_debug!("request: {:?}", request);
+
+        // Only the owner or an operator may see an itinerary
+        let owner_id =
+            super::api::get_itinerary::get_itinerary_owner(&request.get_ref().itinerary_id).await?;
+
+        authorize_owner(&request, &owner_id, &owner_id)?;
+        let request = request.into_inner();
+        super::api::get_itinerary::get_itinerary(request)
+            .await
+            .map_err(|e| {
+                grpc_error!("error: {}", e);
+                e
+            })
+    }
+
+    /// Rebooks a soft-cancelled itinerary within its grace period
+    async fn restore_itinerary(
+        &self,
+        request: Request<RestoreItineraryRequest>,
+    ) -> Result<Response<TaskResponse>, Status> {
+        grpc_info!("scheduler server.");
+        grpc_debug!("request: {:?}", request);
+
+        authorize(&request, &request.get_ref().user_id)?;
+        let actor_id = get_actor_id(&request, &request.get_ref().user_id);
+        let request = request.into_inner();
+        super::api::restore::restore_itinerary(request, &actor_id)
+            .await
+            .map(Response::new)
+            .map_err(|e| {
+                grpc_error!("error: {}", e);
+                e
+            })
+    }
+
     /// Cancels a scheduler task before it can be processed
     async fn cancel_task(
         &self,
@@ -101,6 +277,20 @@ impl RpcService for ServerImpl {
     {
         grpc_info!("scheduler server.");
         grpc_debug!("request: {:?}", request);
+
+        // Only the user that queued the task or an operator may cancel it
+        let owner_id = crate::tasks::get_task_status(request.get_ref().task_id)
+            .await
+            .map_err(|e| match e {
+                TaskError::NotFound => Status::not_found("Task not found."),
+                e => {
+                    grpc_error!("error: {}", e);
+                    Status::internal("Could not get task.")
+                }
+            })?
+            .user_id;
+
+        authorize_owner(&request, &owner_id, &owner_id)?;
         let request = request.into_inner();
 
         crate::tasks::cancel_task(request.task_id)
@@ -128,13 +318,88 @@ impl RpcService for ServerImpl {
     {
         grpc_info!("scheduler server.");
         grpc_debug!("request: {:?}", request);
+
+        let task_metadata = crate::tasks::get_task_status(request.get_ref().task_id)
+            .await
+            .map_err(|e| match e {
+                TaskError::NotFound => Status::not_found("Task not found."),
+                e => {
+                    grpc_error!("error: {}", e);
+                    Status::internal("Could not get task status.")
+                }
+            })?;
+
+        // Only the user that queued the task or an operator may see it
+        authorize_owner(&request, &task_metadata.user_id, &task_metadata.user_id)?;
+        let request = request.into_inner();
+
+        let response = TaskResponse {
+            task_id: request.task_id,
+            task_metadata: Some(task_metadata),
+        };
+
+        Ok(Response::new(response))
+    }
+
+    /// Lists the queued scheduler tasks in processing order (operators only)
+    async fn list_queued_tasks(
+        &self,
+        request: Request<ListQueuedTasksRequest>,
+    ) -> Result<Response<ListQueuedTasksResponse>, Status>
+    where
+        Self: Send,
+    {
+        grpc_info!("scheduler server.");
+        grpc_debug!("request: {:?}", request);
+        authorize_operator(&request)?;
+
+        let tasks = crate::tasks::list_queued_tasks()
+            .await
+            .map_err(|e| {
+                grpc_error!("error: {}", e);
+                Status::internal("Could not list queued tasks.")
+            })?
+            .into_iter()
+            .map(|(queued_task, task_metadata)| QueuedTask {
+                task_id: queued_task.task_id,
+                priority: queued_task.priority as i32,
+                task_metadata: Some(task_metadata),
+            })
+            .collect();
+
+        Ok(Response::new(ListQueuedTasksResponse { tasks }))
+    }
+
+    /// Moves a queued scheduler task to another priority queue, or
+    ///  queues a rejected task again (operators only)
+    async fn requeue_task(
+        &self,
+        request: Request<RequeueTaskRequest>,
+    ) -> Result<Response<TaskResponse>, Status>
+    where
+        Self: Send,
+    {
+        grpc_info!("scheduler server.");
+        grpc_debug!("request: {:?}", request);
+        authorize_operator(&request)?;
         let request = request.into_inner();
 
-        let task_metadata = crate::tasks::get_task_status(request.task_id)
+        let priority = FromPrimitive::from_i32(request.priority).ok_or_else(|| {
+            grpc_warn!("Invalid priority provided: {}", request.priority);
+            Status::invalid_argument("priority: invalid priority.")
+        })?;
+
+        let task_metadata = crate::tasks::requeue_task(request.task_id, priority)
             .await
             .map_err(|e| {
                 grpc_error!("error: {}", e);
-                Status::internal("Could not get task status.")
+                match e {
+                    TaskError::NotFound => Status::not_found("Task not found."),
+                    TaskError::AlreadyProcessed => {
+                        Status::failed_precondition("Task already completed.")
+                    }
+                    _ => Status::internal("Could not requeue task."),
+                }
             })?;
 
         let response = TaskResponse {
@@ -145,6 +410,143 @@ impl RpcService for ServerImpl {
         Ok(Response::new(response))
     }
 
+    /// Gets where each aircraft is scheduled to be and its next flight
+    ///  (operators only)
+    async fn get_fleet_status(
+        &self,
+        request: Request<GetFleetStatusRequest>,
+    ) -> Result<Response<GetFleetStatusResponse>, Status> {
+        grpc_info!("scheduler server.");
+        grpc_debug!("request: {:?}", request);
+        authorize_operator(&request)?;
+
+        let request = request.into_inner();
+        super::api::fleet_status::get_fleet_status(request)
+            .await
+            .map_err(|e| {
+                grpc_error!("error: {}", e);
+                e
+            })
+    }
+
+    /// Checks a batch of flight plans against current bookings
+    ///  (operators only)
+    async fn validate_schedule(
+        &self,
+        request: Request<ValidateScheduleRequest>,
+    ) -> Result<Response<ValidateScheduleResponse>, Status> {
+        grpc_info!("scheduler server.");
+        grpc_debug!("request: {:?}", request);
+        authorize_operator(&request)?;
+
+        let request = request.into_inner();
+        super::api::validate_schedule::validate_schedule(request)
+            .await
+            .map_err(|e| {
+                grpc_error!("error: {}", e);
+                e
+            })
+    }
+
+    /// Estimates the deadhead flights an aircraft would fly for a
+    ///  flight query, without booking (operators only)
+    async fn estimate_deadheads(
+        &self,
+        request: Request<EstimateDeadheadsRequest>,
+    ) -> Result<Response<EstimateDeadheadsResponse>, Status> {
+        grpc_info!("scheduler server.");
+        grpc_debug!("request: {:?}", request);
+        authorize_operator(&request)?;
+
+        let request = request.into_inner();
+        super::api::estimate_deadheads::estimate_deadheads(request)
+            .await
+            .map_err(|e| {
+                grpc_error!("error: {}", e);
+                e
+            })
+    }
+
+    /// Lists the vertiports reachable from a vertiport in a single flight
+    async fn reachable_vertiports(
+        &self,
+        request: Request<ReachableVertiportsRequest>,
+    ) -> Result<Response<ReachableVertiportsResponse>, Status> {
+        grpc_info!("scheduler server.");
+        grpc_debug!("request: {:?}", request);
+
+        // Open to every caller: the service area of a vertiport holds
+        //  neither user data nor the whereabouts of the fleet
+        let request = request.into_inner();
+        super::api::reachable_vertiports::reachable_vertiports(request)
+            .await
+            .map_err(|e| {
+                grpc_error!("error: {}", e);
+                e
+            })
+    }
+
+    /// Gets the occupied and free time of a vertipad over a window
+    ///  (operators only)
+    async fn vertipad_occupancy(
+        &self,
+        request: Request<VertipadOccupancyRequest>,
+    ) -> Result<Response<VertipadOccupancyResponse>, Status> {
+        grpc_info!("scheduler server.");
+        grpc_debug!("request: {:?}", request);
+        authorize_operator(&request)?;
+
+        let request = request.into_inner();
+        super::api::vertipad_occupancy::vertipad_occupancy(request)
+            .await
+            .map_err(|e| {
+                grpc_error!("error: {}", e);
+                e
+            })
+    }
+
+    /// Releases the unconfirmed return holds of a user
+    async fn cancel_user_drafts(
+        &self,
+        request: Request<CancelUserDraftsRequest>,
+    ) -> Result<Response<CancelUserDraftsResponse>, Status> {
+        grpc_info!("scheduler server.");
+        grpc_debug!("request: {:?}", request);
+
+        // Only the user or an operator may release the user's holds
+        authorize(&request, &request.get_ref().user_id)?;
+        let request = request.into_inner();
+        let response = super::api::cancel::cancel_user_drafts(request)
+            .await
+            .map_err(|e| match e {
+                CancelItineraryError::UserId => {
+                    Status::invalid_argument("user_id: must be a UUID.")
+                }
+                e => {
+                    grpc_error!("error: {}", e);
+                    Status::internal("Could not release return holds.")
+                }
+            })?;
+
+        Ok(Response::new(response))
+    }
+
+    /// Lists the itineraries of a user
+    async fn list_user_itineraries(
+        &self,
+        request: Request<ListUserItinerariesRequest>,
+    ) -> Result<Response<ListUserItinerariesResponse>, Status> {
+        grpc_info!("scheduler server.");
+        grpc_debug!("request: {:?}", request);
+
+        // Only the user or an operator may list the user's itineraries
+        authorize(&request, &request.get_ref().user_id)?;
+        let request = request.into_inner();
+        super::api::list_itineraries::list_user_itineraries(request)
+            .await
+            .map(Response::new)
+    }
+
     /// Returns ready:true when service is available
     async fn is_ready(
         &self,
@@ -152,11 +554,44 @@ impl RpcService for ServerImpl {
     ) -> Result<Response<ReadyResponse>, Status> {
         grpc_info!("scheduler server.");
         grpc_debug!("request: {:?}", request);
-        let response = ReadyResponse { ready: true };
+        let response = ReadyResponse {
+            ready: STORAGE_READY.load(Ordering::Relaxed),
+        };
         Ok(Response::new(response))
     }
 }
 
+/// Reports the service as serving once svc-storage can be read.
+/// If svc-storage is still unavailable after the configured retries
+///  the service keeps running in a degraded state, not serving, and
+///  checks again at the longest backoff until svc-storage recovers.
+#[cfg(not(feature = "stub_server"))]
+#[cfg(not(tarpaulin_include))]
+// no_coverage: (R5) needs running backend, integration tests
+async fn report_storage_health(config: Config, mut health_reporter: HealthReporter) {
+    use crate::grpc::client::{check_storage, get_backoff_cap};
+
+    let clients = crate::grpc::client::get_clients().await;
+    let backoff = std::time::Duration::from_millis(config.storage_retry_backoff_ms.into());
+    let recheck = get_backoff_cap(config.storage_retry_attempts, backoff);
+
+    let mut result = check_storage(clients, config.storage_retry_attempts, backoff).await;
+    while let Err(e) = result {
+        grpc_error!(
+            "svc-storage is unavailable, service is degraded, checking again in {} ms: {e}",
+            recheck.as_millis()
+        );
+        tokio::time::sleep(recheck).await;
+        result = check_storage(clients, 1, backoff).await;
+    }
+
+    grpc_info!("svc-storage is available.");
+    STORAGE_READY.store(true, Ordering::Relaxed);
+    health_reporter
+        .set_serving::<RpcServiceServer<ServerImpl>>()
+        .await;
+}
+
 /// Starts the grpc servers for this microservice using the provided configuration
 ///
 /// # Example:
@@ -182,18 +617,43 @@ pub async fn grpc_server(config: Config, shutdown_rx: Option<tokio::sync::onesho
         }
     };
 
+    let tokens = match AuthTokens::try_from_config(&config.user_tokens, &config.operator_tokens) {
+        Ok(tokens) => tokens,
+        Err(e) => {
+            grpc_error!("Failed to parse auth tokens: {}", e);
+            return;
+        }
+    };
+
+    if tokens.is_empty() {
+        grpc_warn!("No auth tokens configured, authentication is disabled.");
+    }
+
     let imp = ServerImpl {};
 
     let (mut health_reporter, health_service) = tonic_health::server::health_reporter();
+
+    #[cfg(feature = "stub_server")]
     health_reporter
         .set_serving::<RpcServiceServer<ServerImpl>>()
         .await;
 
+    // Serving once svc-storage can be read
+    #[cfg(not(feature = "stub_server"))]
+    {
+        health_reporter
+            .set_not_serving::<RpcServiceServer<ServerImpl>>()
+            .await;
+        tokio::spawn(report_storage_health(config.clone(), health_reporter));
+    }
+
     //start server
     grpc_info!("Starting gRPC services on: {}.", full_grpc_addr);
     match Server::builder()
         .add_service(health_service)
-        .add_service(RpcServiceServer::new(imp))
+        .add_service(RpcServiceServer::with_interceptor(imp, move |request| {
+            authenticate(&tokens, request)
+        }))
         .serve_with_shutdown(full_grpc_addr, shutdown_signal("grpc", shutdown_rx))
         .await
     {
@@ -219,9 +679,25 @@ impl RpcService for ServerImpl {
 
         let itineraries = vec![Itinerary {
             flight_plans: vec![flight_plan_data],
+            vehicle: None,
+            alternative_routes: vec![],
+            local_times: vec![],
+            estimated_price: 0,
+            deadhead_reasons: vec![],
+            path_fallback: false,
+            pad_selection: grpc_server::PadSelection::Unspecified as i32,
+            arrival_estimate: None,
         }];
 
-        Ok(tonic::Response::new(QueryFlightResponse { itineraries }))
+        Ok(tonic::Response::new(QueryFlightResponse {
+            itineraries,
+            reason: None,
+            window_earliest_departure_time: None,
+            window_latest_arrival_time: None,
+            limiting_constraint: None,
+            partial: false,
+            alternative_destinations: vec![],
+        }))
     }
 
     /// Creates an itinerary given a list of proposed flight plans, if possible.
@@ -262,6 +738,82 @@ impl RpcService for ServerImpl {
         }))
     }
 
+    /// Cancels all upcoming itineraries for a grounded vehicle.
+    async fn cancel_vehicle_itineraries(
+        &self,
+        request: Request<CancelVehicleItinerariesRequest>,
+    ) -> Result<Response<CancelVehicleItinerariesResponse>, Status> {
+        grpc_warn!("(MOCK) scheduler server.");
+        grpc_debug!("(MOCK) request: {:?}", request);
+        Ok(tonic::Response::new(CancelVehicleItinerariesResponse {
+            itinerary_ids: vec![],
+        }))
+    }
+
+    /// Suggests deadhead flights to move idle aircraft toward under-served hubs.
+    async fn suggest_repositioning(
+        &self,
+        request: Request<SuggestRepositioningRequest>,
+    ) -> Result<Response<SuggestRepositioningResponse>, Status> {
+        grpc_warn!("(MOCK) scheduler server.");
+        grpc_debug!("(MOCK) request: {:?}", request);
+        Ok(tonic::Response::new(SuggestRepositioningResponse {
+            flight_plans: vec![],
+        }))
+    }
+
+    /// Checks whether a fully specified flight plan can be booked as-is
+    async fn check_flight_plan(
+        &self,
+        request: Request<CheckFlightPlanRequest>,
+    ) -> Result<Response<CheckFlightPlanResponse>, Status> {
+        grpc_warn!("(MOCK) scheduler server.");
+        grpc_debug!("(MOCK) request: {:?}", request);
+        Ok(tonic::Response::new(CheckFlightPlanResponse {
+            schedulable: true,
+            conflict: None,
+        }))
+    }
+
+    /// Returns a booked itinerary with its flight plans
+    async fn get_itinerary(
+        &self,
+        request: Request<GetItineraryRequest>,
+    ) -> Result<Response<Itinerary>, Status> {
+        grpc_warn!("(MOCK) scheduler server.");
+        grpc_debug!("(MOCK) request: {:?}", request);
+        Ok(tonic::Response::new(Itinerary {
+            flight_plans: vec![],
+            vehicle: None,
+            alternative_routes: vec![],
+            local_times: vec![],
+            estimated_price: 0,
+            deadhead_reasons: vec![],
+            path_fallback: false,
+            pad_selection: grpc_server::PadSelection::Unspecified as i32,
+            arrival_estimate: None,
+        }))
+    }
+
+    /// Rebooks a soft-cancelled itinerary within its grace period
+    async fn restore_itinerary(
+        &self,
+        request: Request<RestoreItineraryRequest>,
+    ) -> Result<Response<TaskResponse>, Status> {
+        grpc_warn!("(MOCK) scheduler server.");
+        grpc_debug!("(MOCK) request: {:?}", request);
+        let mut rng = rand::thread_rng();
+        Ok(tonic::Response::new(TaskResponse {
+            task_id: rng.gen_range(0..1000),
+            task_metadata: Some(TaskMetadata {
+                status: TaskStatus::Queued as i32,
+                action: TaskAction::CreateItinerary as i32,
+                user_id: request.into_inner().user_id,
+                ..Default::default()
+            }),
+        }))
+    }
+
     /// Cancels a scheduler task
     async fn cancel_task(
         &self,
@@ -295,6 +847,126 @@ impl RpcService for ServerImpl {
         Ok(Response::new(response))
     }
 
+    /// Lists the queued scheduler tasks
+    async fn list_queued_tasks(
+        &self,
+        request: Request<ListQueuedTasksRequest>,
+    ) -> Result<Response<ListQueuedTasksResponse>, Status> {
+        grpc_warn!("(MOCK) scheduler server.");
+        grpc_debug!("(MOCK) request: {:?}", request);
+        Ok(Response::new(ListQueuedTasksResponse { tasks: vec![] }))
+    }
+
+    /// Requeues a scheduler task
+    async fn requeue_task(
+        &self,
+        request: Request<RequeueTaskRequest>,
+    ) -> Result<Response<TaskResponse>, Status> {
+        grpc_warn!("(MOCK) scheduler server.");
+        grpc_debug!("(MOCK) request: {:?}", request);
+
+        let response = TaskResponse {
+            task_id: request.into_inner().task_id,
+            task_metadata: Some(TaskMetadata {
+                status: TaskStatus::Queued as i32,
+                ..Default::default()
+            }),
+        };
+
+        Ok(Response::new(response))
+    }
+
+    /// Gets the status of the fleet
+    async fn get_fleet_status(
+        &self,
+        request: Request<GetFleetStatusRequest>,
+    ) -> Result<Response<GetFleetStatusResponse>, Status> {
+        grpc_warn!("(MOCK) scheduler server.");
+        grpc_debug!("(MOCK) request: {:?}", request);
+        Ok(Response::new(GetFleetStatusResponse { vehicles: vec![] }))
+    }
+
+    /// Checks a batch of flight plans against current bookings
+    async fn validate_schedule(
+        &self,
+        request: Request<ValidateScheduleRequest>,
+    ) -> Result<Response<ValidateScheduleResponse>, Status> {
+        grpc_warn!("(MOCK) scheduler server.");
+        grpc_debug!("(MOCK) request: {:?}", request);
+        let flight_plans = request
+            .into_inner()
+            .flight_plans
+            .iter()
+            .map(|_| grpc_server::FlightPlanValidation {
+                schedulable: true,
+                conflict: None,
+            })
+            .collect();
+
+        Ok(Response::new(ValidateScheduleResponse { flight_plans }))
+    }
+
+    /// Estimates the deadhead flights of a flight query
+    async fn estimate_deadheads(
+        &self,
+        request: Request<EstimateDeadheadsRequest>,
+    ) -> Result<Response<EstimateDeadheadsResponse>, Status> {
+        grpc_warn!("(MOCK) scheduler server.");
+        grpc_debug!("(MOCK) request: {:?}", request);
+        Ok(Response::new(EstimateDeadheadsResponse {
+            pre_deadhead: None,
+            post_deadhead: None,
+        }))
+    }
+
+    /// Lists the vertiports reachable from a vertiport
+    async fn reachable_vertiports(
+        &self,
+        request: Request<ReachableVertiportsRequest>,
+    ) -> Result<Response<ReachableVertiportsResponse>, Status> {
+        grpc_warn!("(MOCK) scheduler server.");
+        grpc_debug!("(MOCK) request: {:?}", request);
+        Ok(Response::new(ReachableVertiportsResponse {
+            vertiports: vec![],
+        }))
+    }
+
+    /// Gets the occupied and free time of a vertipad
+    async fn vertipad_occupancy(
+        &self,
+        request: Request<VertipadOccupancyRequest>,
+    ) -> Result<Response<VertipadOccupancyResponse>, Status> {
+        grpc_warn!("(MOCK) scheduler server.");
+        grpc_debug!("(MOCK) request: {:?}", request);
+        Ok(Response::new(VertipadOccupancyResponse {
+            vertiport_id: String::new(),
+            occupied: vec![],
+            free: vec![],
+        }))
+    }
+
+    /// Releases the unconfirmed return holds of a user
+    async fn cancel_user_drafts(
+        &self,
+        request: Request<CancelUserDraftsRequest>,
+    ) -> Result<Response<CancelUserDraftsResponse>, Status> {
+        grpc_warn!("(MOCK) scheduler server.");
+        grpc_debug!("(MOCK) request: {:?}", request);
+        Ok(Response::new(CancelUserDraftsResponse { hold_ids: vec![] }))
+    }
+
+    /// Lists the itineraries of a user
+    async fn list_user_itineraries(
+        &self,
+        request: Request<ListUserItinerariesRequest>,
+    ) -> Result<Response<ListUserItinerariesResponse>, Status> {
+        grpc_warn!("(MOCK) scheduler server.");
+        grpc_debug!("(MOCK) request: {:?}", request);
+        Ok(Response::new(ListUserItinerariesResponse {
+            itineraries: vec![],
+        }))
+    }
+
     /// Returns ready:true when service is available
     async fn is_ready(
         &self,
@@ -306,3 +978,65 @@ impl RpcService for ServerImpl {
         Ok(Response::new(response))
     }
 }
+
+#[cfg(test)]
+#[cfg(not(feature = "stub_server"))]
+mod tests {
+    use super::*;
+    use crate::grpc::auth::Caller;
+
+    #[tokio::test]
+    async fn test_query_flight_invalid_argument() {
+        lib_common::logger::get_log_handle().await;
+        ut_info!("Start.");
+
+        let request = Request::new(QueryFlightRequest {
+            origin_vertiport_id: String::new(),
+            ..Default::default()
+        });
+
+        let e = ServerImpl::default()
+            .query_flight(request)
+            .await
+            .unwrap_err();
+        assert_eq!(e.code(), Code::InvalidArgument);
+        assert_eq!(e.message(), "origin_vertiport_id: must not be empty.");
+
+        ut_info!("Success.");
+    }
+
+    #[tokio::test]
+    async fn test_query_flight_emergency_permission_denied() {
+        lib_common::logger::get_log_handle().await;
+        ut_info!("Start.");
+
+        let mut request = Request::new(QueryFlightRequest {
+            emergency: true,
+            ..Default::default()
+        });
+        request
+            .extensions_mut()
+            .insert(Caller::User(lib_common::uuid::Uuid::new_v4().to_string()));
+
+        let e = ServerImpl::default()
+            .query_flight(request)
+            .await
+            .unwrap_err();
+        assert_eq!(e.code(), Code::PermissionDenied);
+
+        // operators get past authorization to the request validation
+        let mut request = Request::new(QueryFlightRequest {
+            emergency: true,
+            ..Default::default()
+        });
+        request.extensions_mut().insert(Caller::Operator);
+
+        let e = ServerImpl::default()
+            .query_flight(request)
+            .await
+            .unwrap_err();
+        assert_eq!(e.code(), Code::InvalidArgument);
+
+        ut_info!("Success.");
+    }
+}
@@ -4,11 +4,17 @@
 #[macro_use]
 pub mod test_util;
 
+pub mod clock;
 pub mod config;
 pub mod grpc;
 mod router;
 pub mod tasks;
+
 pub use crate::config::Config;
+pub use router::settings;
+#[cfg(feature = "simulate")]
+pub use router::simulate;
+pub use router::warmup;
 
 /// Tokio signal handler that will wait for a user to press CTRL+C.
 /// This signal handler can be used in our [`tonic::transport::Server`] method `serve_with_shutdown`.
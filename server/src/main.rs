@@ -21,6 +21,14 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
 
     info!("(main) Server startup.");
 
+    // Refuse to start with a malformed setting rather than failing every query
+    settings::init_settings(&config)
+        .await
+        .map_err(|e| format!("Invalid configuration: {e}"))?;
+
+    // Build the router clients now rather than on the first query
+    warmup::warmup(&config).await;
+
     // Spawn the main control loop, don't `await` it
     tokio::spawn(tasks::task_loop(config.clone()));
 
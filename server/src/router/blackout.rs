@@ -0,0 +1,100 @@
+//! Network-wide blackout periods
+//! During a blackout the whole service is grounded (severe weather,
+//!  regulatory stand-down) and no flights can be scheduled.
+
+use crate::router::schedule::Timeslot;
+use crate::router::settings::{get_settings, Setting, SettingError};
+
+/// Blackout periods setting
+pub static BLACKOUT_PERIODS: Setting = Setting {
+    name: "BLACKOUT_PERIODS",
+    format: "start/end",
+};
+
+/// Parses comma-separated RFC3339 `start/end` intervals into timeslots
+pub fn parse_blackout_periods(periods: &str) -> Result<Vec<Timeslot>, SettingError> {
+    BLACKOUT_PERIODS.parse_list(periods, |period| BLACKOUT_PERIODS.parse_period(period))
+}
+
+/// Returns the first blackout period overlapping the given timeslot, if any
+pub fn get_overlapping_blackout(blackouts: &[Timeslot], timeslot: &Timeslot) -> Option<Timeslot> {
+    blackouts
+        .iter()
+        .find(|blackout| blackout.overlap(timeslot).is_ok())
+        .copied()
+}
+
+/// Returns the configured blackout periods
+pub async fn get_blackout_periods() -> Result<Vec<Timeslot>, SettingError> {
+    Ok(get_settings().await?.blackout_periods.clone())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use lib_common::time::{DateTime, Duration, Utc};
+    use std::str::FromStr;
+
+    #[test]
+    fn test_parse_blackout_periods() {
+        assert!(parse_blackout_periods("").unwrap().is_empty());
+
+        let blackouts = parse_blackout_periods(
+            "2024-01-01T00:00:00Z/2024-01-01T06:00:00Z, 2024-02-01T12:00:00Z/2024-02-02T00:00:00Z",
+        )
+        .unwrap();
+        assert_eq!(blackouts.len(), 2);
+        assert_eq!(
+            blackouts[0].time_start(),
+            DateTime::<Utc>::from_str("2024-01-01T00:00:00Z").unwrap()
+        );
+        assert_eq!(blackouts[1].duration(), Duration::try_hours(12).unwrap());
+
+        let e = parse_blackout_periods("2024-01-01T00:00:00Z").unwrap_err();
+        assert_eq!(e, SettingError::Format(&BLACKOUT_PERIODS));
+
+        let e = parse_blackout_periods("2024-01-01T00:00:00Z/tomorrow").unwrap_err();
+        assert_eq!(e, SettingError::Time(&BLACKOUT_PERIODS));
+
+        let e = parse_blackout_periods("2024-01-01T06:00:00Z/2024-01-01T00:00:00Z").unwrap_err();
+        assert_eq!(e, SettingError::Timeslot(&BLACKOUT_PERIODS));
+    }
+
+    #[test]
+    fn test_get_overlapping_blackout() {
+        let now = Utc::now();
+        let blackout = Timeslot::new(now, now + Duration::try_hours(2).unwrap()).unwrap();
+        let blackouts = vec![blackout];
+
+        // Fully inside the blackout
+        let timeslot = Timeslot::new(
+            now + Duration::try_minutes(30).unwrap(),
+            now + Duration::try_minutes(90).unwrap(),
+        )
+        .unwrap();
+        assert_eq!(
+            get_overlapping_blackout(&blackouts, &timeslot),
+            Some(blackout)
+        );
+
+        // Partially overlapping the blackout
+        let timeslot = Timeslot::new(
+            now + Duration::try_hours(1).unwrap(),
+            now + Duration::try_hours(3).unwrap(),
+        )
+        .unwrap();
+        assert_eq!(
+            get_overlapping_blackout(&blackouts, &timeslot),
+            Some(blackout)
+        );
+
+        // Starts when the blackout ends
+        let timeslot = Timeslot::new(
+            now + Duration::try_hours(2).unwrap(),
+            now + Duration::try_hours(3).unwrap(),
+        )
+        .unwrap();
+        assert_eq!(get_overlapping_blackout(&blackouts, &timeslot), None);
+        assert_eq!(get_overlapping_blackout(&[], &timeslot), None);
+    }
+}
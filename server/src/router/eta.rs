@@ -0,0 +1,147 @@
+//! Arrival time estimates
+//! Flight times are estimated generously, so most flights land before
+//!  their scheduled arrival. How early they are likely to land is given
+//!  as percentages of the scheduled flight time.
+//!
+//! TODO(R5): Widen the interval with wind forecasts once they are
+//!  available to the scheduler
+
+use lib_common::time::{DateTime, Duration, Utc};
+use std::fmt::{Display, Formatter, Result as FmtResult};
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum EtaError {
+    /// The p90 percentage isn't greater than the p50 percentage
+    Percentiles,
+}
+
+impl Display for EtaError {
+    fn fmt(&self, f: &mut Formatter) -> FmtResult {
+        match self {
+            EtaError::Percentiles => write!(
+                f,
+                "Arrival p90 percentage is not greater than the p50 percentage"
+            ),
+        }
+    }
+}
+
+/// Shares of the scheduled flight time within which flights land
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct ArrivalVariance {
+    /// Percentage of the scheduled flight time by which half of
+    ///  flights land
+    p50_percent: u32,
+
+    /// Percentage of the scheduled flight time by which nine in ten
+    ///  flights land
+    p90_percent: u32,
+}
+
+impl ArrivalVariance {
+    /// Creates the variance, the p90 percentage must be greater than
+    ///  the p50 percentage
+    pub fn new(p50_percent: u32, p90_percent: u32) -> Result<Self, EtaError> {
+        if p90_percent <= p50_percent {
+            router_error!(
+                "Arrival p90 percentage {p90_percent} is not greater than p50 percentage {p50_percent}."
+            );
+            return Err(EtaError::Percentiles);
+        }
+
+        Ok(ArrivalVariance {
+            p50_percent,
+            p90_percent,
+        })
+    }
+}
+
+/// Likely landing times of a flight
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct ArrivalEstimate {
+    /// Half of flights land by this time
+    pub p50: DateTime<Utc>,
+
+    /// Nine in ten flights land by this time
+    pub p90: DateTime<Utc>,
+}
+
+/// Returns the given percentage of a flight time
+fn scale_flight_time(flight_time: Duration, percent: u32) -> Option<Duration> {
+    let milliseconds = flight_time
+        .num_milliseconds()
+        .checked_mul(i64::from(percent))?
+        / 100;
+
+    Duration::try_milliseconds(milliseconds)
+}
+
+/// Returns when a flight taking off at `departure` and scheduled to
+///  land at `scheduled_arrival` is likely to land.
+/// Returns `None` if the flight lands before it takes off.
+pub fn estimate_arrival(
+    departure: DateTime<Utc>,
+    scheduled_arrival: DateTime<Utc>,
+    variance: &ArrivalVariance,
+) -> Option<ArrivalEstimate> {
+    let flight_time = scheduled_arrival - departure;
+    if flight_time < Duration::zero() {
+        router_warn!("Flight lands at {scheduled_arrival} before taking off at {departure}.");
+        return None;
+    }
+
+    Some(ArrivalEstimate {
+        p50: departure + scale_flight_time(flight_time, variance.p50_percent)?,
+        p90: departure + scale_flight_time(flight_time, variance.p90_percent)?,
+    })
+}
+
+/// Returns the configured arrival variance
+pub async fn get_arrival_variance() -> Result<ArrivalVariance, EtaError> {
+    let config = crate::config::get_config().await;
+    ArrivalVariance::new(config.eta_p50_percent, config.eta_p90_percent)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_arrival_variance_new() {
+        assert!(ArrivalVariance::new(90, 100).is_ok());
+
+        let e = ArrivalVariance::new(100, 100).unwrap_err();
+        assert_eq!(e, EtaError::Percentiles);
+
+        let e = ArrivalVariance::new(110, 90).unwrap_err();
+        assert_eq!(e, EtaError::Percentiles);
+    }
+
+    #[test]
+    fn test_estimate_arrival() {
+        let departure = Utc::now();
+        let scheduled_arrival = departure + Duration::try_minutes(20).unwrap();
+        let variance = ArrivalVariance::new(90, 100).unwrap();
+
+        let estimate = estimate_arrival(departure, scheduled_arrival, &variance).unwrap();
+        assert!(estimate.p90 > estimate.p50);
+        assert_eq!(estimate.p50, departure + Duration::try_minutes(18).unwrap());
+        assert_eq!(estimate.p90, scheduled_arrival);
+
+        // later than scheduled in bad conditions
+        let variance = ArrivalVariance::new(95, 115).unwrap();
+        let estimate = estimate_arrival(departure, scheduled_arrival, &variance).unwrap();
+        assert!(estimate.p90 > estimate.p50);
+        assert_eq!(estimate.p90, departure + Duration::try_minutes(23).unwrap());
+
+        assert!(estimate_arrival(scheduled_arrival, departure, &variance).is_none());
+    }
+
+    #[test]
+    fn test_eta_error_display() {
+        assert_eq!(
+            format!("{}", EtaError::Percentiles),
+            "Arrival p90 percentage is not greater than the p50 percentage"
+        );
+    }
+}
@@ -0,0 +1,199 @@
+//! Routing around excluded vertiports
+//! Operators may exclude vertiports from a flight query (e.g. during an
+//!  incident) without disabling them in storage. svc-gis doesn't take
+//!  exclusions, so paths passing through an excluded vertiport are
+//!  dropped in favour of the alternatives, if there are any.
+
+use super::units::Meters;
+use super::vertiport::{get_distance_meters, get_vertiport_location, VertiportError};
+use super::{BestPathError, BestPathRequest, PathFinder};
+use crate::grpc::client::GrpcClients;
+use std::collections::HashSet;
+use svc_gis_client_grpc::prelude::gis::*;
+use tonic::async_trait;
+
+/// Distance in meters from an excluded vertiport within which a path
+///  point passes through it
+const EXCLUSION_RADIUS_METERS: f64 = 50.0;
+
+/// Returns true if a point of the path between its origin and target
+///  is at one of the excluded locations
+pub fn passes_through(path: &[PointZ], excluded: &[PointZ]) -> bool {
+    let [_, points @ .., _] = path else {
+        return false;
+    };
+
+    points.iter().any(|point| {
+        excluded
+            .iter()
+            .any(|location| get_distance_meters(point, location).0 < EXCLUSION_RADIUS_METERS)
+    })
+}
+
+/// Locates the excluded vertiports
+#[cfg(not(tarpaulin_include))]
+// no_coverage: (R5) needs running backend, integration tests
+pub async fn get_excluded_locations(
+    vertiport_ids: &HashSet<String>,
+    clients: &GrpcClients,
+) -> Result<Vec<PointZ>, VertiportError> {
+    let mut locations = vec![];
+    for vertiport_id in vertiport_ids {
+        locations.push(get_vertiport_location(vertiport_id, clients).await?);
+    }
+
+    Ok(locations)
+}
+
+/// Asks a router for paths, dropping those that pass through an
+///  excluded vertiport
+#[derive(Debug)]
+pub struct ExcludingRouter<'a, P> {
+    /// Router asked for the paths
+    router: &'a P,
+
+    /// Locations of the excluded vertiports
+    excluded: Vec<PointZ>,
+}
+
+impl<'a, P> ExcludingRouter<'a, P> {
+    /// Creates a router avoiding the `excluded` vertiport locations
+    pub fn new(router: &'a P, excluded: Vec<PointZ>) -> Self {
+        ExcludingRouter { router, excluded }
+    }
+}
+
+#[async_trait]
+impl<P: PathFinder + Sync> PathFinder for ExcludingRouter<'_, P> {
+    async fn best_path(
+        &self,
+        request: &BestPathRequest,
+    ) -> Result<Vec<(Vec<PointZ>, Meters)>, BestPathError> {
+        let paths = self.router.best_path(request).await?;
+        if self.excluded.is_empty() {
+            return Ok(paths);
+        }
+
+        let paths = paths
+            .into_iter()
+            .filter(|(path, _)| !passes_through(path, &self.excluded))
+            .collect::<Vec<(Vec<PointZ>, Meters)>>();
+
+        if paths.is_empty() {
+            router_debug!(
+                "Every path from {} to {} passes through an excluded vertiport.",
+                request.origin_identifier,
+                request.target_identifier
+            );
+            return Err(BestPathError::NoPathFound);
+        }
+
+        Ok(paths)
+    }
+
+    fn is_fallback_path(&self, origin_identifier: &str, target_identifier: &str) -> bool {
+        self.router
+            .is_fallback_path(origin_identifier, target_identifier)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn point(latitude: f64, longitude: f64) -> PointZ {
+        PointZ {
+            latitude,
+            longitude,
+            altitude_meters: 100.0,
+        }
+    }
+
+    /// Answers with the same paths for every request
+    struct StaticRouter(Vec<(Vec<PointZ>, Meters)>);
+
+    #[async_trait]
+    impl PathFinder for StaticRouter {
+        async fn best_path(
+            &self,
+            _request: &BestPathRequest,
+        ) -> Result<Vec<(Vec<PointZ>, Meters)>, BestPathError> {
+            Ok(self.0.clone())
+        }
+    }
+
+    fn request() -> BestPathRequest {
+        BestPathRequest {
+            origin_identifier: lib_common::uuid::Uuid::new_v4().to_string(),
+            target_identifier: lib_common::uuid::Uuid::new_v4().to_string(),
+            origin_type: NodeType::Vertiport as i32,
+            target_type: NodeType::Vertiport as i32,
+            time_start: None,
+            time_end: None,
+            limit: 2,
+        }
+    }
+
+    #[test]
+    fn test_passes_through() {
+        let excluded = point(52.05, 4.05);
+        let (origin, target) = (point(52.0, 4.0), point(52.1, 4.1));
+
+        assert!(passes_through(
+            &[origin.clone(), excluded.clone(), target.clone()],
+            &[excluded.clone()]
+        ));
+        assert!(passes_through(
+            &[origin.clone(), point(52.0502, 4.05), target.clone()],
+            &[excluded.clone()]
+        ));
+        assert!(!passes_through(
+            &[origin.clone(), point(52.05, 4.0), target.clone()],
+            &[excluded.clone()]
+        ));
+
+        // the origin and target aren't passed through
+        assert!(!passes_through(
+            &[excluded.clone(), target.clone()],
+            &[excluded.clone()]
+        ));
+        assert!(!passes_through(&[origin, excluded.clone()], &[excluded]));
+    }
+
+    #[tokio::test]
+    async fn test_excluding_router() {
+        let excluded = point(52.05, 4.05);
+        let (origin, target) = (point(52.0, 4.0), point(52.1, 4.1));
+        let through = (
+            vec![origin.clone(), excluded.clone(), target.clone()],
+            Meters(13_000.0),
+        );
+        let around = (
+            vec![origin.clone(), point(52.05, 4.0), target.clone()],
+            Meters(15_000.0),
+        );
+
+        // the shorter path through the excluded vertiport is dropped
+        let router = StaticRouter(vec![through.clone(), around.clone()]);
+        let paths = ExcludingRouter::new(&router, vec![excluded.clone()])
+            .best_path(&request())
+            .await
+            .unwrap();
+        assert_eq!(paths, vec![around]);
+
+        // without another path there's no path
+        let router = StaticRouter(vec![through.clone()]);
+        let e = ExcludingRouter::new(&router, vec![excluded])
+            .best_path(&request())
+            .await
+            .unwrap_err();
+        assert_eq!(e, BestPathError::NoPathFound);
+
+        // nothing excluded
+        let paths = ExcludingRouter::new(&router, vec![])
+            .best_path(&request())
+            .await
+            .unwrap();
+        assert_eq!(paths, vec![through]);
+    }
+}
@@ -0,0 +1,280 @@
+//! Straight-line paths while svc-gis is unavailable
+//! For degraded operation, flights between vertiports may be planned
+//!  as a straight line when svc-gis can't be reached. The distance is
+//!  scaled up so flight times stay on the safe side; such paths ignore
+//!  no-fly zones, so itineraries using them are flagged.
+
+use super::units::Meters;
+use super::vertiport::{get_distance_meters, get_vertiport_location};
+use super::{BestPathError, BestPathRequest, PathFinder};
+use crate::grpc::client::GrpcClients;
+use std::collections::HashSet;
+use std::sync::Mutex;
+use svc_gis_client_grpc::prelude::gis::*;
+use tonic::async_trait;
+
+/// Returns a straight path between two points, its length scaled
+///  by `distance_percent`
+pub fn get_straight_line_path(
+    origin: &PointZ,
+    target: &PointZ,
+    distance_percent: u32,
+) -> (Vec<PointZ>, Meters) {
+    let Meters(distance) = get_distance_meters(origin, target);
+    let distance_meters = Meters(distance * f64::from(distance_percent) / 100.0);
+
+    (vec![origin.clone(), target.clone()], distance_meters)
+}
+
+/// Asks a router for paths, falling back to straight-line paths
+///  between vertiports if the router's client is unavailable
+#[derive(Debug)]
+pub struct FallbackRouter<'a, P> {
+    /// Router asked first
+    router: &'a P,
+
+    /// Clients to look up the vertiport locations with
+    clients: &'a GrpcClients,
+
+    /// Percentage of the straight-line distance used as the length of
+    ///  a fallback path, `None` if falling back is disabled
+    distance_percent: Option<u32>,
+
+    /// Origin and target identifiers of the fallback paths returned
+    fallback_paths: Mutex<HashSet<(String, String)>>,
+}
+
+impl<'a, P> FallbackRouter<'a, P> {
+    /// Creates a router falling back to straight-line paths, if a
+    ///  `distance_percent` is provided
+    pub fn new(router: &'a P, clients: &'a GrpcClients, distance_percent: Option<u32>) -> Self {
+        FallbackRouter {
+            router,
+            clients,
+            distance_percent,
+            fallback_paths: Mutex::new(HashSet::new()),
+        }
+    }
+}
+
+#[async_trait]
+impl<P: PathFinder + Sync> PathFinder for FallbackRouter<'_, P> {
+    async fn best_path(
+        &self,
+        request: &BestPathRequest,
+    ) -> Result<Vec<(Vec<PointZ>, Meters)>, BestPathError> {
+        let result = self.router.best_path(request).await;
+        let (Err(BestPathError::ClientError), Some(distance_percent)) =
+            (&result, self.distance_percent)
+        else {
+            return result;
+        };
+
+        // only vertiports have a location in storage
+        if request.origin_type != NodeType::Vertiport as i32
+            || request.target_type != NodeType::Vertiport as i32
+        {
+            router_warn!("No fallback path to or from a waypoint.");
+            return result;
+        }
+
+        let origin = get_vertiport_location(&request.origin_identifier, self.clients)
+            .await
+            .map_err(|_| BestPathError::ClientError)?;
+        let target = get_vertiport_location(&request.target_identifier, self.clients)
+            .await
+            .map_err(|_| BestPathError::ClientError)?;
+
+        router_warn!(
+            "Router unavailable, flying straight from {} to {}.",
+            request.origin_identifier,
+            request.target_identifier
+        );
+
+        if let Ok(mut fallback_paths) = self.fallback_paths.lock() {
+            fallback_paths.insert((
+                request.origin_identifier.clone(),
+                request.target_identifier.clone(),
+            ));
+        }
+
+        Ok(vec![get_straight_line_path(
+            &origin,
+            &target,
+            distance_percent,
+        )])
+    }
+
+    fn is_fallback_path(&self, origin_identifier: &str, target_identifier: &str) -> bool {
+        self.fallback_paths.lock().is_ok_and(|fallback_paths| {
+            fallback_paths.contains(&(origin_identifier.to_string(), target_identifier.to_string()))
+        })
+    }
+}
+
+/// Returns the configured length of fallback paths as a percentage
+///  of the straight-line distance, `None` if falling back is disabled
+pub async fn get_fallback_distance_percent() -> Option<u32> {
+    let config = crate::config::get_config().await;
+    match config.path_fallback {
+        true => Some(config.path_fallback_distance_percent),
+        false => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Answers as if svc-gis were down
+    struct UnavailableRouter;
+
+    #[async_trait]
+    impl PathFinder for UnavailableRouter {
+        async fn best_path(
+            &self,
+            _request: &BestPathRequest,
+        ) -> Result<Vec<(Vec<PointZ>, Meters)>, BestPathError> {
+            Err(BestPathError::ClientError)
+        }
+    }
+
+    #[test]
+    fn test_get_straight_line_path() {
+        let origin = PointZ {
+            latitude: 52.0,
+            longitude: 4.0,
+            altitude_meters: 100.0,
+        };
+        let target = PointZ {
+            latitude: 52.1,
+            longitude: 4.1,
+            altitude_meters: 100.0,
+        };
+
+        let Meters(distance) = get_distance_meters(&origin, &target);
+        let (points, Meters(fallback_distance)) = get_straight_line_path(&origin, &target, 150);
+        assert_eq!(points, vec![origin, target]);
+        assert!((fallback_distance - distance * 1.5).abs() < 1e-6);
+    }
+
+    #[tokio::test]
+    async fn test_fallback_router_disabled() {
+        let clients = crate::grpc::client::get_clients().await;
+        let router = FallbackRouter::new(&UnavailableRouter, clients, None);
+        let request = BestPathRequest {
+            origin_identifier: lib_common::uuid::Uuid::new_v4().to_string(),
+            target_identifier: lib_common::uuid::Uuid::new_v4().to_string(),
+            origin_type: NodeType::Vertiport as i32,
+            target_type: NodeType::Vertiport as i32,
+            time_start: None,
+            time_end: None,
+            limit: 1,
+        };
+
+        let e = router.best_path(&request).await.unwrap_err();
+        assert_eq!(e, BestPathError::ClientError);
+        assert!(!router.is_fallback_path(&request.origin_identifier, &request.target_identifier));
+    }
+
+    #[tokio::test]
+    #[cfg(feature = "stub_backends")]
+    async fn test_fallback_router_itinerary() {
+        use crate::clock::SystemClock;
+        use crate::router::itinerary::{calculate_itineraries, DeadheadSearch, ReturnPolicy};
+        use crate::router::lead_time::LeadTimes;
+        use crate::router::schedule::Timeslot;
+        use crate::router::vehicle::{Availability, GroundTime};
+        use crate::router::vertiport::get_vertipad_timeslot_pairs;
+        use lib_common::time::{Duration, Utc};
+        use std::collections::HashMap;
+
+        lib_common::logger::get_log_handle().await;
+        ut_info!("Start.");
+
+        crate::test_util::ensure_storage_mock_data().await;
+        let clients = crate::grpc::client::get_clients().await;
+        let vertiports = crate::test_util::get_vertiports_from_storage()
+            .await
+            .unwrap();
+        let (origin, target) = (&vertiports[0].id, &vertiports[1].id);
+
+        let time_start = Utc::now();
+        let window =
+            Timeslot::new(time_start, time_start + Duration::try_hours(2).unwrap()).unwrap();
+        let origin_vertipad_id = lib_common::uuid::Uuid::new_v4().to_string();
+        let target_vertipad_id = lib_common::uuid::Uuid::new_v4().to_string();
+
+        // svc-gis is down, without the fallback nothing can be planned
+        let router = FallbackRouter::new(&UnavailableRouter, clients, None);
+        let pairs = get_vertipad_timeslot_pairs(
+            origin,
+            target,
+            HashMap::from([(origin_vertipad_id.clone(), vec![window])]),
+            HashMap::from([(target_vertipad_id.clone(), vec![window])]),
+            None,
+            1,
+            &router,
+        )
+        .await
+        .unwrap_or_default();
+        assert!(pairs.is_empty());
+
+        let router = FallbackRouter::new(&UnavailableRouter, clients, Some(150));
+        let pairs = get_vertipad_timeslot_pairs(
+            origin,
+            target,
+            HashMap::from([(origin_vertipad_id.clone(), vec![window])]),
+            HashMap::from([(target_vertipad_id, vec![window])]),
+            None,
+            1,
+            &router,
+        )
+        .await
+        .unwrap();
+        assert!(!pairs.is_empty());
+
+        let availabilities = HashMap::from([(
+            lib_common::uuid::Uuid::new_v4().to_string(),
+            vec![Availability {
+                vertiport_id: origin.clone(),
+                vertipad_id: origin_vertipad_id,
+                timeslot: Timeslot::new(
+                    time_start - Duration::try_hours(1).unwrap(),
+                    time_start + Duration::try_hours(3).unwrap(),
+                )
+                .unwrap(),
+            }],
+        )]);
+
+        let itineraries = calculate_itineraries(
+            &GroundTime {
+                loading: Duration::try_seconds(30).unwrap(),
+                unloading: Duration::try_seconds(30).unwrap(),
+            },
+            &HashMap::new(),
+            &HashMap::new(),
+            &pairs,
+            &availabilities,
+            &DeadheadSearch {
+                enabled: true,
+                step: Duration::try_minutes(10).unwrap(),
+                attempts: 6,
+                max_legs: 0,
+                return_policy: ReturnPolicy::Strict,
+                parking: None,
+            },
+            &Duration::zero(),
+            &LeadTimes::default(),
+            &SystemClock,
+            &router,
+        )
+        .await
+        .unwrap();
+
+        assert!(!itineraries.is_empty());
+        assert!(itineraries.iter().all(|itinerary| itinerary.path_fallback));
+
+        ut_info!("Success.");
+    }
+}
@@ -1,9 +1,11 @@
 //! Helper Functions for Flight Plans
 
-use crate::grpc::client::GrpcClients;
-use lib_common::time::{DateTime, Utc};
+use crate::clock::get_clock;
+use crate::grpc::client::{search_all_pages, GrpcClients};
+use lib_common::time::{DateTime, Duration, Utc};
 use lib_common::uuid::Uuid;
 use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
 use svc_gis_client_grpc::client::PointZ;
 use svc_storage_client_grpc::prelude::*;
 
@@ -223,9 +225,65 @@ impl From<FlightPlanSchedule> for flight_plan::Data {
     }
 }
 
+/// Returns the earliest arrival time of the flight plans worth loading,
+///  or `None` if all flight plans are loaded (0 hours lookback).
+/// Flight plans that landed before then can't affect new flights, while
+///  flights that took off earlier but haven't landed yet still can.
+pub fn get_lookback_cutoff(now: DateTime<Utc>, lookback_hours: u32) -> Option<DateTime<Utc>> {
+    if lookback_hours == 0 {
+        return None;
+    }
+
+    Some(now - Duration::try_hours(lookback_hours.into())?)
+}
+
+/// Removes flight plans that landed before the cutoff
+fn remove_stale_flight_plans(
+    flight_plans: &mut Vec<FlightPlanSchedule>,
+    cutoff: Option<DateTime<Utc>>,
+) {
+    let Some(cutoff) = cutoff else {
+        return;
+    };
+
+    flight_plans.retain(|fp| fp.target_timeslot_end >= cutoff);
+}
+
+/// Removes flight plans that duplicate an earlier one: the same aircraft
+///  flying between the same vertipads at the same times.
+/// Storage may hold such copies, which would otherwise be counted twice
+///  when checking the occupancy of aircraft and vertipads.
+fn remove_duplicate_flight_plans(flight_plans: &mut Vec<FlightPlanSchedule>) {
+    let mut seen = HashSet::new();
+    flight_plans.retain(|fp| {
+        let identity = (
+            fp.vehicle_id.clone(),
+            fp.origin_vertipad_id.clone(),
+            fp.origin_timeslot_start,
+            fp.origin_timeslot_end,
+            fp.target_vertipad_id.clone(),
+            fp.target_timeslot_start,
+            fp.target_timeslot_end,
+        );
+
+        if seen.insert(identity) {
+            return true;
+        }
+
+        router_warn!(
+            "Skipping duplicate flight plan of aircraft {} departing {}.",
+            fp.vehicle_id,
+            fp.origin_timeslot_start
+        );
+        false
+    });
+}
+
 /// Gets flight plans from storage in sorted order from
 ///  earliest to latest arrival time, for the provided aircraft ids
 ///  or for all aircraft if none are specified.
+/// Flight plans that landed before the configured lookback are skipped,
+///  as are duplicates if configured.
 pub async fn get_sorted_flight_plans(
     clients: &GrpcClients,
 ) -> Result<Vec<FlightPlanSchedule>, FlightPlanError> {
@@ -240,6 +298,13 @@ pub async fn get_sorted_flight_plans(
         ],
     );
 
+    let config = crate::config::get_config().await;
+    let cutoff = get_lookback_cutoff(get_clock().now(), config.flight_plan_lookback_hours);
+    if let Some(cutoff) = cutoff {
+        // flights still in the air are kept, however early they took off
+        filter = filter.and_greater_or_equal("target_timeslot_end".to_owned(), cutoff.to_rfc3339());
+    }
+
     filter.order_by = vec![
         SortOption {
             sort_field: "origin_timeslot_start".to_owned(),
@@ -251,20 +316,29 @@ pub async fn get_sorted_flight_plans(
         },
     ];
 
-    let mut flight_plans = clients
-        .storage
-        .flight_plan
-        .search(filter)
-        .await
-        .map_err(|e| {
-            router_error!("Failed to get flight plans from storage: {}", e);
-            FlightPlanError::ClientError
-        })?
-        .into_inner()
-        .list
-        .into_iter()
-        .filter_map(|fp| FlightPlanSchedule::try_from(fp).ok())
-        .collect::<Vec<FlightPlanSchedule>>();
+    let page_size = config.storage_page_size;
+    let mut flight_plans = search_all_pages(filter, page_size, |filter| async move {
+        clients
+            .storage()
+            .await
+            .flight_plan
+            .search(filter)
+            .await
+            .map(|response| response.into_inner().list)
+    })
+    .await
+    .map_err(|e| {
+        router_error!("Failed to get flight plans from storage: {}", e);
+        FlightPlanError::ClientError
+    })?
+    .into_iter()
+    .filter_map(|fp| FlightPlanSchedule::try_from(fp).ok())
+    .collect::<Vec<FlightPlanSchedule>>();
+
+    remove_stale_flight_plans(&mut flight_plans, cutoff);
+    if config.deduplicate_flight_plans {
+        remove_duplicate_flight_plans(&mut flight_plans);
+    }
 
     flight_plans.sort(); // should already be sorted due to the ORDER BY args to storage
     Ok(flight_plans)
@@ -576,4 +650,97 @@ mod tests {
         assert_ne!(f1, f2);
         assert!(f1 < f2);
     }
+
+    #[test]
+    fn test_get_lookback_cutoff() {
+        let now = Utc::now();
+        assert_eq!(
+            get_lookback_cutoff(now, 24),
+            Some(now - Duration::try_hours(24).unwrap())
+        );
+
+        // disabled
+        assert_eq!(get_lookback_cutoff(now, 0), None);
+    }
+
+    #[test]
+    fn test_remove_stale_flight_plans() {
+        let now = Utc::now();
+        let flight_plan = |departure: DateTime<Utc>, arrival: DateTime<Utc>| FlightPlanSchedule {
+            origin_vertiport_id: Uuid::new_v4().to_string(),
+            origin_vertipad_id: Uuid::new_v4().to_string(),
+            origin_timeslot_start: departure,
+            origin_timeslot_end: departure + Duration::try_minutes(1).unwrap(),
+            target_vertiport_id: Uuid::new_v4().to_string(),
+            target_vertipad_id: Uuid::new_v4().to_string(),
+            target_timeslot_start: arrival - Duration::try_minutes(1).unwrap(),
+            target_timeslot_end: arrival,
+            vehicle_id: Uuid::new_v4().to_string(),
+            path: None,
+        };
+
+        let stale = flight_plan(
+            now - Duration::try_hours(50).unwrap(),
+            now - Duration::try_hours(49).unwrap(),
+        );
+        // took off before the lookback, still in the air
+        let active = flight_plan(
+            now - Duration::try_hours(30).unwrap(),
+            now + Duration::try_hours(1).unwrap(),
+        );
+        let upcoming = flight_plan(
+            now + Duration::try_hours(2).unwrap(),
+            now + Duration::try_hours(3).unwrap(),
+        );
+        let all = vec![stale, active.clone(), upcoming.clone()];
+
+        let cutoff = get_lookback_cutoff(now, 24);
+        let mut flight_plans = all.clone();
+        remove_stale_flight_plans(&mut flight_plans, cutoff);
+        assert_eq!(flight_plans, vec![active, upcoming]);
+
+        // no lookback, everything is kept
+        let mut flight_plans = all.clone();
+        remove_stale_flight_plans(&mut flight_plans, get_lookback_cutoff(now, 0));
+        assert_eq!(flight_plans, all);
+    }
+
+    #[test]
+    fn test_remove_duplicate_flight_plans() {
+        let now = Utc::now();
+        let flight_plan = FlightPlanSchedule {
+            origin_vertiport_id: Uuid::new_v4().to_string(),
+            origin_vertipad_id: Uuid::new_v4().to_string(),
+            origin_timeslot_start: now,
+            origin_timeslot_end: now + Duration::try_minutes(1).unwrap(),
+            target_vertiport_id: Uuid::new_v4().to_string(),
+            target_vertipad_id: Uuid::new_v4().to_string(),
+            target_timeslot_start: now + Duration::try_minutes(10).unwrap(),
+            target_timeslot_end: now + Duration::try_minutes(11).unwrap(),
+            vehicle_id: Uuid::new_v4().to_string(),
+            path: None,
+        };
+
+        // another aircraft at the same time is not a duplicate
+        let other_aircraft = FlightPlanSchedule {
+            vehicle_id: Uuid::new_v4().to_string(),
+            ..flight_plan.clone()
+        };
+
+        let mut flight_plans = vec![
+            flight_plan.clone(),
+            other_aircraft.clone(),
+            flight_plan.clone(),
+        ];
+        remove_duplicate_flight_plans(&mut flight_plans);
+        assert_eq!(flight_plans.len(), 2);
+
+        // the duplicated flight plan is counted once
+        let count = flight_plans
+            .iter()
+            .filter(|fp| fp.vehicle_id == flight_plan.vehicle_id)
+            .count();
+        assert_eq!(count, 1);
+        assert_eq!(flight_plans[1].vehicle_id, other_aircraft.vehicle_id);
+    }
 }
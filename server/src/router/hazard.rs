@@ -0,0 +1,459 @@
+//! Hazardous cargo restrictions
+//! Cargo of some hazard classes may be barred from vertiports (e.g.
+//!  next to a hospital) or from the corridor between two vertiports
+//!  (e.g. over a residential area). Routes touching a restricted
+//!  vertiport can't carry that cargo. A corridor is a band centered on
+//!  the straight line between its vertiports, and any routed path
+//!  entering it, loop flights included, can't carry that cargo either.
+//!
+//! TODO(R5): Read restrictions from svc-storage vertiports and svc-gis
+//!  zones once they carry hazard classes, instead of from the configuration
+
+use crate::grpc::client::GrpcClients;
+use crate::router::settings::{get_settings, Setting, SettingError, FIELD_SEPARATOR};
+use crate::router::units::Meters;
+use crate::router::vertiport::{
+    get_vertiport_location, TimeslotPair, VertiportError, EARTH_RADIUS_METERS,
+};
+use std::collections::HashSet;
+use svc_gis_client_grpc::prelude::gis::PointZ;
+
+/// Hazard restrictions setting
+pub static HAZARD_RESTRICTIONS: Setting = Setting {
+    name: "HAZARD_RESTRICTIONS",
+    format: "node=classes",
+};
+
+/// Separates the restricted vertiport or corridor from the hazard classes
+const CLASSES_SEPARATOR: char = '=';
+
+/// Separates the two vertiports of a corridor
+const CORRIDOR_SEPARATOR: char = '~';
+
+/// Where hazardous cargo is restricted
+#[derive(Debug, Clone, PartialEq)]
+pub enum RestrictedNode {
+    /// Flights can't depart from or arrive at the vertiport
+    Vertiport(String),
+
+    /// Flights can't fly between the two vertiports, in either direction
+    Corridor(String, String),
+}
+
+/// Hazard classes barred from a vertiport or corridor
+#[derive(Debug, Clone)]
+pub struct HazardRestriction {
+    /// Where the hazard classes are barred
+    pub node: RestrictedNode,
+
+    /// Barred hazard classes
+    pub hazard_classes: HashSet<String>,
+}
+
+impl HazardRestriction {
+    /// Returns true if the restriction bars `hazard_class` from a
+    ///  flight between the two vertiports
+    fn applies(&self, hazard_class: &str, origin_id: &str, target_id: &str) -> bool {
+        if !self.hazard_classes.contains(hazard_class) {
+            return false;
+        }
+
+        match &self.node {
+            RestrictedNode::Vertiport(id) => id == origin_id || id == target_id,
+            RestrictedNode::Corridor(a, b) => {
+                (a == origin_id && b == target_id) || (a == target_id && b == origin_id)
+            }
+        }
+    }
+}
+
+/// Parses comma-separated `node=class|class` restrictions, where the
+///  node is a vertiport ID or two vertiport IDs separated by `~` for
+///  the corridor between them, e.g.
+///  `vertiport_id=1.1|2.3,vertiport_id~vertiport_id=3`
+pub fn parse_hazard_restrictions(
+    restrictions: &str,
+) -> Result<Vec<HazardRestriction>, SettingError> {
+    HAZARD_RESTRICTIONS.parse_list(restrictions, |restriction| {
+        let [node, classes] = HAZARD_RESTRICTIONS.split::<2>(restriction, CLASSES_SEPARATOR)?;
+        let node = match node.split_once(CORRIDOR_SEPARATOR) {
+            Some((a, b)) => RestrictedNode::Corridor(
+                HAZARD_RESTRICTIONS.parse_id(a)?,
+                HAZARD_RESTRICTIONS.parse_id(b)?,
+            ),
+            None => RestrictedNode::Vertiport(HAZARD_RESTRICTIONS.parse_id(node)?),
+        };
+
+        let hazard_classes: HashSet<String> = classes
+            .split(FIELD_SEPARATOR)
+            .map(str::trim)
+            .filter(|class| !class.is_empty())
+            .map(String::from)
+            .collect();
+
+        if hazard_classes.is_empty() {
+            router_error!("Hazard restriction without hazard classes: {restriction}");
+            return Err(SettingError::Value(&HAZARD_RESTRICTIONS));
+        }
+
+        Ok(HazardRestriction {
+            node,
+            hazard_classes,
+        })
+    })
+}
+
+/// Returns the first restriction barring `hazard_class` from a flight
+///  between the two vertiports, if any
+pub fn get_route_restriction<'a>(
+    restrictions: &'a [HazardRestriction],
+    hazard_class: &str,
+    origin_id: &str,
+    target_id: &str,
+) -> Option<&'a HazardRestriction> {
+    restrictions
+        .iter()
+        .find(|restriction| restriction.applies(hazard_class, origin_id, target_id))
+}
+
+/// Returns the vertiports at the ends of the corridors barring
+///  `hazard_class`
+pub fn get_restricted_corridor_ends<'a>(
+    restrictions: &'a [HazardRestriction],
+    hazard_class: &str,
+) -> Vec<(&'a str, &'a str)> {
+    restrictions
+        .iter()
+        .filter(|restriction| restriction.hazard_classes.contains(hazard_class))
+        .filter_map(|restriction| match &restriction.node {
+            RestrictedNode::Corridor(a, b) => Some((a.as_str(), b.as_str())),
+            RestrictedNode::Vertiport(_) => None,
+        })
+        .collect()
+}
+
+/// A restricted corridor, located
+#[derive(Debug, Clone)]
+pub struct RestrictedCorridor {
+    /// Location of the vertiport at one end
+    pub a: PointZ,
+
+    /// Location of the vertiport at the other end
+    pub b: PointZ,
+
+    /// Half the width of the corridor
+    pub half_width: Meters,
+}
+
+impl RestrictedCorridor {
+    /// Projects a point onto a plane tangent to the Earth at the
+    ///  corridor's midpoint, in meters east and north of it.
+    /// Corridors are short enough for the projection to hold.
+    fn project(&self, point: &PointZ) -> (f64, f64) {
+        let latitude = (self.a.latitude + self.b.latitude) / 2.0;
+        let longitude = (self.a.longitude + self.b.longitude) / 2.0;
+        let x = (point.longitude - longitude).to_radians()
+            * latitude.to_radians().cos()
+            * EARTH_RADIUS_METERS;
+        let y = (point.latitude - latitude).to_radians() * EARTH_RADIUS_METERS;
+        (x, y)
+    }
+
+    /// Returns true if a projected point is within the corridor, but not
+    ///  next to the vertiports at its ends, which flights may still use
+    fn contains(&self, point: (f64, f64), a: (f64, f64), b: (f64, f64)) -> bool {
+        let half_width = self.half_width.0;
+        distance_to_segment(point, a, b) < half_width
+            && distance_to_segment(point, a, a) > half_width
+            && distance_to_segment(point, b, b) > half_width
+    }
+
+    /// Returns true if the path enters the corridor.
+    /// Each leg is sampled at a quarter of the corridor width, so a leg
+    ///  can't cut across the corridor between two samples.
+    pub fn crosses(&self, path: &[PointZ]) -> bool {
+        let a = self.project(&self.a);
+        let b = self.project(&self.b);
+        let step = (self.half_width.0 / 2.0).max(1.0);
+        let points = path
+            .iter()
+            .map(|point| self.project(point))
+            .collect::<Vec<(f64, f64)>>();
+
+        if let [point] = points[..] {
+            return self.contains(point, a, b);
+        }
+
+        points.windows(2).any(|leg| {
+            let (start, end) = (leg[0], leg[1]);
+            let length = distance_to_segment(start, end, end);
+            let samples = (length / step).ceil().max(1.0) as u32;
+            (0..=samples).any(|i| {
+                let t = f64::from(i) / f64::from(samples);
+                let point = (
+                    start.0 + t * (end.0 - start.0),
+                    start.1 + t * (end.1 - start.1),
+                );
+                self.contains(point, a, b)
+            })
+        })
+    }
+}
+
+/// Distance between a point and the segment from `a` to `b`, in the
+///  units of the coordinates
+fn distance_to_segment(point: (f64, f64), a: (f64, f64), b: (f64, f64)) -> f64 {
+    let (dx, dy) = (b.0 - a.0, b.1 - a.1);
+    let length_squared = dx * dx + dy * dy;
+    let t = match length_squared > 0.0 {
+        true => (((point.0 - a.0) * dx + (point.1 - a.1) * dy) / length_squared).clamp(0.0, 1.0),
+        false => 0.0,
+    };
+
+    (point.0 - a.0 - t * dx).hypot(point.1 - a.1 - t * dy)
+}
+
+/// Locates the corridors barring `hazard_class`
+#[cfg(not(tarpaulin_include))]
+// no_coverage: (R5) needs running backend, integration tests
+pub async fn get_restricted_corridors(
+    restrictions: &[HazardRestriction],
+    hazard_class: &str,
+    width: Meters,
+    clients: &GrpcClients,
+) -> Result<Vec<RestrictedCorridor>, VertiportError> {
+    let mut corridors = vec![];
+    for (a, b) in get_restricted_corridor_ends(restrictions, hazard_class) {
+        corridors.push(RestrictedCorridor {
+            a: get_vertiport_location(a, clients).await?,
+            b: get_vertiport_location(b, clients).await?,
+            half_width: Meters(width.0 / 2.0),
+        });
+    }
+
+    Ok(corridors)
+}
+
+/// Routes timeslot pairs around restricted corridors. A pair whose path
+///  enters one takes its shortest alternative path that doesn't, and is
+///  dropped if there is none.
+pub fn avoid_restricted_corridors(
+    timeslot_pairs: Vec<TimeslotPair>,
+    corridors: &[RestrictedCorridor],
+) -> Vec<TimeslotPair> {
+    if corridors.is_empty() {
+        return timeslot_pairs;
+    }
+
+    let is_clear = |path: &[PointZ]| !corridors.iter().any(|corridor| corridor.crosses(path));
+    timeslot_pairs
+        .into_iter()
+        .filter_map(|mut pair| {
+            pair.alternative_paths.retain(|(path, _)| is_clear(path));
+            if is_clear(&pair.path) {
+                return Some(pair);
+            }
+
+            router_debug!(
+                "Path from {} to {} enters a restricted corridor.",
+                pair.origin_vertiport_id,
+                pair.target_vertiport_id
+            );
+
+            if pair.alternative_paths.is_empty() {
+                return None;
+            }
+
+            let (path, distance_meters) = pair.alternative_paths.remove(0);
+            pair.path = path;
+            pair.distance_meters = distance_meters;
+            Some(pair)
+        })
+        .collect()
+}
+
+/// Returns the configured hazardous cargo restrictions
+pub async fn get_hazard_restrictions() -> Result<Vec<HazardRestriction>, SettingError> {
+    Ok(get_settings().await?.hazard_restrictions.clone())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::router::schedule::Timeslot;
+    use lib_common::time::{Duration, Utc};
+    use lib_common::uuid::Uuid;
+
+    fn point(latitude: f64, longitude: f64) -> PointZ {
+        PointZ {
+            latitude,
+            longitude,
+            altitude_meters: 100.0,
+        }
+    }
+
+    /// A corridor 1 km wide, about 11 km long from south to north
+    fn corridor() -> RestrictedCorridor {
+        RestrictedCorridor {
+            a: point(52.0, 4.0),
+            b: point(52.1, 4.0),
+            half_width: Meters(500.0),
+        }
+    }
+
+    #[test]
+    fn test_parse_hazard_restrictions() {
+        assert!(parse_hazard_restrictions("").unwrap().is_empty());
+
+        let vertiport_1 = Uuid::new_v4().to_string();
+        let vertiport_2 = Uuid::new_v4().to_string();
+        let restrictions = parse_hazard_restrictions(&format!(
+            "{vertiport_1}=1.1|2.3, {vertiport_1}~{vertiport_2}=3"
+        ))
+        .unwrap();
+
+        assert_eq!(restrictions.len(), 2);
+        assert_eq!(
+            restrictions[0].node,
+            RestrictedNode::Vertiport(vertiport_1.clone())
+        );
+        assert_eq!(
+            restrictions[0].hazard_classes,
+            HashSet::from(["1.1".to_string(), "2.3".to_string()])
+        );
+        assert_eq!(
+            restrictions[1].node,
+            RestrictedNode::Corridor(vertiport_1.clone(), vertiport_2.clone())
+        );
+
+        let e = parse_hazard_restrictions(&vertiport_1).unwrap_err();
+        assert_eq!(e, SettingError::Format(&HAZARD_RESTRICTIONS));
+
+        let e = parse_hazard_restrictions("invalid=3").unwrap_err();
+        assert_eq!(e, SettingError::Id(&HAZARD_RESTRICTIONS));
+
+        let e = parse_hazard_restrictions(&format!("{vertiport_1}~invalid=3")).unwrap_err();
+        assert_eq!(e, SettingError::Id(&HAZARD_RESTRICTIONS));
+
+        let e = parse_hazard_restrictions(&format!("{vertiport_1}=|")).unwrap_err();
+        assert_eq!(e, SettingError::Value(&HAZARD_RESTRICTIONS));
+    }
+
+    #[test]
+    fn test_get_route_restriction() {
+        let restricted = Uuid::new_v4().to_string();
+        let origin = Uuid::new_v4().to_string();
+        let target = Uuid::new_v4().to_string();
+        let restrictions =
+            parse_hazard_restrictions(&format!("{restricted}=1.1, {origin}~{target}=3")).unwrap();
+
+        // explosives can't depart from or arrive at the restricted vertiport
+        let restriction = get_route_restriction(&restrictions, "1.1", &restricted, &target);
+        assert_eq!(
+            restriction.unwrap().node,
+            RestrictedNode::Vertiport(restricted.clone())
+        );
+        assert!(get_route_restriction(&restrictions, "1.1", &origin, &restricted).is_some());
+        assert!(get_route_restriction(&restrictions, "1.1", &origin, &target).is_none());
+
+        // other hazard classes can
+        assert!(get_route_restriction(&restrictions, "8", &restricted, &target).is_none());
+
+        // flammable liquids can't fly the corridor in either direction
+        assert!(get_route_restriction(&restrictions, "3", &origin, &target).is_some());
+        assert!(get_route_restriction(&restrictions, "3", &target, &origin).is_some());
+        assert!(get_route_restriction(&restrictions, "3", &origin, &restricted).is_none());
+    }
+
+    #[test]
+    fn test_get_restricted_corridor_ends() {
+        let restricted = Uuid::new_v4().to_string();
+        let origin = Uuid::new_v4().to_string();
+        let target = Uuid::new_v4().to_string();
+        let restrictions =
+            parse_hazard_restrictions(&format!("{restricted}=3, {origin}~{target}=3|8")).unwrap();
+
+        assert_eq!(
+            get_restricted_corridor_ends(&restrictions, "3"),
+            vec![(origin.as_str(), target.as_str())]
+        );
+        assert_eq!(get_restricted_corridor_ends(&restrictions, "8").len(), 1);
+        assert!(get_restricted_corridor_ends(&restrictions, "1.1").is_empty());
+    }
+
+    #[test]
+    fn test_restricted_corridor_crosses() {
+        let corridor = corridor();
+
+        // flying the corridor between its vertiports
+        assert!(corridor.crosses(&[corridor.a.clone(), corridor.b.clone()]));
+
+        // cutting across it, with both waypoints outside
+        assert!(corridor.crosses(&[point(52.05, 3.9), point(52.05, 4.1)]));
+
+        // a loop waypoint inside it
+        assert!(corridor.crosses(&[point(52.05, 4.002)]));
+        assert!(corridor.crosses(&[point(51.9, 3.9), point(52.05, 4.002), point(51.9, 3.9)]));
+
+        // flying parallel to it, outside
+        assert!(!corridor.crosses(&[point(52.0, 4.02), point(52.1, 4.02)]));
+
+        // departing from a vertiport at its end, away from it
+        assert!(!corridor.crosses(&[corridor.a.clone(), point(51.9, 4.0)]));
+        assert!(!corridor.crosses(&[corridor.b.clone(), point(52.1, 4.1)]));
+    }
+
+    #[test]
+    fn test_distance_to_segment() {
+        assert_eq!(
+            distance_to_segment((0.0, 1.0), (-1.0, 0.0), (1.0, 0.0)),
+            1.0
+        );
+        assert_eq!(
+            distance_to_segment((4.0, 4.0), (-1.0, 0.0), (1.0, 0.0)),
+            5.0
+        );
+        assert_eq!(distance_to_segment((3.0, 4.0), (0.0, 0.0), (0.0, 0.0)), 5.0);
+    }
+
+    #[test]
+    fn test_avoid_restricted_corridors() {
+        let corridor = corridor();
+        let now = Utc::now();
+        let timeslot = Timeslot::new(now, now + Duration::try_hours(1).unwrap()).unwrap();
+        let direct = vec![corridor.a.clone(), corridor.b.clone()];
+        let detour = vec![
+            corridor.a.clone(),
+            point(52.0, 4.05),
+            point(52.1, 4.05),
+            corridor.b.clone(),
+        ];
+        let pair = TimeslotPair {
+            origin_vertiport_id: Uuid::new_v4().to_string(),
+            origin_vertipad_id: Uuid::new_v4().to_string(),
+            origin_timeslot: timeslot,
+            target_vertiport_id: Uuid::new_v4().to_string(),
+            target_vertipad_id: Uuid::new_v4().to_string(),
+            target_timeslot: timeslot,
+            path: direct.clone(),
+            distance_meters: Meters(11_000.0),
+            alternative_paths: vec![(detour.clone(), Meters(12_000.0))],
+        };
+
+        // no corridors, the pair is unchanged
+        let pairs = avoid_restricted_corridors(vec![pair.clone()], &[]);
+        assert_eq!(pairs[0].path, direct);
+
+        // the pair takes the detour around the corridor
+        let pairs = avoid_restricted_corridors(vec![pair.clone()], &[corridor.clone()]);
+        assert_eq!(pairs.len(), 1);
+        assert_eq!(pairs[0].path, detour);
+        assert_eq!(pairs[0].distance_meters, Meters(12_000.0));
+        assert!(pairs[0].alternative_paths.is_empty());
+
+        // and is dropped without one
+        let mut pair = pair;
+        pair.alternative_paths.clear();
+        assert!(avoid_restricted_corridors(vec![pair], &[corridor]).is_empty());
+    }
+}
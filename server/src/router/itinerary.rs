@@ -1,18 +1,24 @@
 //! Build an itinerary given aircraft availability and the flight window
 
 use super::flight_plan::FlightPlanSchedule;
+use super::lead_time::LeadTimes;
+use super::pricing::QuotedFlight;
 use super::schedule::*;
+use super::units::Meters;
 use super::vehicle::*;
 use super::vertiport::TimeslotPair;
-use super::{best_path, BestPathError, BestPathRequest};
-use crate::grpc::client::GrpcClients;
+use super::weather::{avoid_weather_cells, get_weather_cells};
+use super::{is_flyable_path, BestPathError, BestPathRequest, PathFinder};
+use crate::clock::Clock;
 use svc_gis_client_grpc::prelude::gis::*;
 use svc_storage_client_grpc::prelude::*;
 
 use lib_common::time::{DateTime, Duration, Utc};
-use std::cmp::max;
+use std::cmp::{max, min, Ordering};
 use std::collections::{HashMap, HashSet};
 use std::fmt::{Display, Formatter, Result as FmtResult};
+use std::str::FromStr;
+use tokio::time::{timeout_at, Instant};
 
 const MAX_ITINERARIES: usize = 2;
 
@@ -46,6 +52,9 @@ pub enum ItineraryError {
     /// There was a schedule conflict
     ScheduleConflict,
 
+    /// More deadhead flights are needed than allowed
+    TooManyDeadheads,
+
     /// An internal error occurred
     Internal,
 }
@@ -62,11 +71,91 @@ impl Display for ItineraryError {
             ItineraryError::PathTooShort => write!(f, "Path is too short."),
             ItineraryError::NoPathFound => write!(f, "No path found."),
             ItineraryError::ScheduleConflict => write!(f, "Schedule conflict."),
+            ItineraryError::TooManyDeadheads => write!(f, "Too many deadhead flights."),
             ItineraryError::Internal => write!(f, "Internal error."),
         }
     }
 }
 
+/// Constraints that may limit the number of itineraries found
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum LimitingFactor {
+    /// Not enough open vertipad timeslots at the vertiports
+    PadCapacity,
+
+    /// Aircraft were busy or couldn't reach the origin in time
+    AircraftAvailability,
+
+    /// No route could be found for the aircraft
+    Routing,
+}
+
+/// Tally of the candidate itineraries that were rejected, by cause
+#[derive(Debug, Copy, Clone, Default, PartialEq)]
+pub struct Rejections {
+    /// Number of timeslot pairs that were searched
+    pub timeslot_pairs: usize,
+
+    /// Candidates rejected because the aircraft wasn't available
+    pub aircraft_availability: usize,
+
+    /// Candidates rejected because no route was found
+    pub routing: usize,
+
+    /// The search was cut short by its time budget, more itineraries
+    ///  may have been found with more time
+    pub budget_exceeded: bool,
+}
+
+impl Rejections {
+    /// Records why a candidate itinerary was rejected
+    fn record(&mut self, e: &ItineraryError) {
+        match e {
+            ItineraryError::ScheduleConflict
+            | ItineraryError::TimeWindow
+            | ItineraryError::TooManyDeadheads => self.aircraft_availability += 1,
+            ItineraryError::NoPath | ItineraryError::PathTooShort | ItineraryError::NoPathFound => {
+                self.routing += 1
+            }
+            _ => (),
+        }
+    }
+
+    /// Returns the dominant constraint if fewer than the maximum number of
+    ///  itineraries were found, `None` otherwise. The maximum is raised to
+    ///  `min_results` if the search went on for that many.
+    /// Each itinerary missing for lack of timeslot pairs counts against the
+    ///  vertipads, ties go to the vertipads, then the aircraft.
+    pub fn get_limiting_factor(
+        &self,
+        itinerary_count: usize,
+        min_results: usize,
+    ) -> Option<LimitingFactor> {
+        let max_itineraries = max(MAX_ITINERARIES, min_results);
+        if itinerary_count >= max_itineraries {
+            return None;
+        }
+
+        let pad_capacity = max_itineraries.saturating_sub(self.timeslot_pairs);
+        let factor = [
+            (LimitingFactor::PadCapacity, pad_capacity),
+            (
+                LimitingFactor::AircraftAvailability,
+                self.aircraft_availability,
+            ),
+            (LimitingFactor::Routing, self.routing),
+        ]
+        .into_iter()
+        .reduce(|a, b| if b.1 > a.1 { b } else { a })
+        .filter(|(_, count)| *count > 0)
+        .map(|(factor, _)| factor)
+        // nothing was rejected, there weren't enough aircraft to go around
+        .unwrap_or(LimitingFactor::AircraftAvailability);
+
+        Some(factor)
+    }
+}
+
 // Verify that the provided flight plans are structured correctly
 // 1) A single aircraft per itinerary.
 // 2) A connecting flight plan should leave from the same pad the previous flight plan landed on.
@@ -140,16 +229,14 @@ pub fn validate_itinerary(
         }
 
         for fp in [fp_1, fp_2] {
-            let length = fp
-                .path
-                .as_ref()
-                .ok_or_else(|| {
-                    router_error!("Flight plan should have a path: {:#?}", flight_plans);
-                    ItineraryError::NoPath
-                })?
-                .len();
-
-            if length < 2 {
+            let path = fp.path.as_ref().ok_or_else(|| {
+                router_error!("Flight plan should have a path: {:#?}", flight_plans);
+                ItineraryError::NoPath
+            })?;
+
+            // Direct flights between vertiports have no waypoints, only the
+            //  origin and target points are needed
+            if !is_flyable_path(path) {
                 router_error!(
                     "Flight plan path needs two or more points: {:#?}",
                     flight_plans
@@ -168,20 +255,288 @@ pub fn validate_itinerary(
     Ok(())
 }
 
-/// Given timeslot pairs for departure and arrival vertiport and the
-///  availabilities of the aircraft, get possible itineraries for each
-///  aircraft.
-/// Returns a maximum of 1 itinerary per aircraft.
-#[cfg(not(tarpaulin_include))]
-// no_coverage: (R5) need running backend, integration tests
-pub async fn calculate_itineraries(
-    required_loading_time: &Duration,
-    required_unloading_time: &Duration,
-    timeslot_pairs: &[TimeslotPair],
+/// Settings for retrying a deadhead flight at later departure times
+///  when no path is available (e.g. a temporary no-fly zone)
+#[derive(Debug, Clone, Copy)]
+pub struct DeadheadSearch<'a> {
+    /// Whether deadhead flights may be scheduled at all. When false,
+    ///  only aircraft already parked at the origin are considered.
+    pub enabled: bool,
+
+    /// Time between departure attempts
+    pub step: Duration,
+
+    /// Number of departure times to try
+    pub attempts: u32,
+
+    /// Most deadhead flights in one itinerary (0 for no limit)
+    pub max_legs: u32,
+
+    /// What to do when the aircraft can't be flown back after the flight
+    pub return_policy: ReturnPolicy,
+
+    /// Where the aircraft may stay parked after the flight with
+    ///  [`ReturnPolicy::Park`]. Aircraft are never parked without.
+    pub parking: Option<&'a ParkingLimits>,
+}
+
+impl DeadheadSearch<'_> {
+    /// Returns true if an itinerary may not include this many deadhead flights
+    fn is_too_many_legs(&self, legs: u32) -> bool {
+        self.max_legs > 0 && legs > self.max_legs
+    }
+}
+
+/// How an itinerary is handled when no deadhead flight can take the
+///  aircraft back in time after the requested flight
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum ReturnPolicy {
+    /// Reject the itinerary
+    Strict,
+
+    /// Leave the aircraft parked at the destination, if its vertipad
+    ///  stays open for as long as the aircraft is idle
+    Park,
+}
+
+impl FromStr for ReturnPolicy {
+    type Err = ItineraryError;
+
+    fn from_str(policy: &str) -> Result<Self, Self::Err> {
+        match policy {
+            "strict" => Ok(ReturnPolicy::Strict),
+            "park" => Ok(ReturnPolicy::Park),
+            _ => {
+                router_error!("Unknown return deadhead policy: {policy}");
+                Err(ItineraryError::Internal)
+            }
+        }
+    }
+}
+
+/// What an aircraft parked at the destination of a flight must not
+///  get in the way of
+#[derive(Debug, Clone)]
+pub struct ParkingLimits {
+    /// End of the searched aircraft availabilities, see
+    ///  [`get_availability_horizon`]
+    pub horizon: DateTime<Utc>,
+
+    /// Free timeslots of the destination vertipads until the horizon
+    pub vertipad_timeslots: HashMap<String, Vec<Timeslot>>,
+}
+
+/// Returns true if the aircraft may stay on the destination vertipad
+///  of a flight instead of flying back: the aircraft must not be needed
+///  anywhere afterwards, and the vertipad must stay free from landing
+///  until the horizon
+fn can_park_at_target(
+    parking: &ParkingLimits,
+    availability: &Availability,
+    vertipad_id: &str,
+    landing: DateTime<Utc>,
+) -> bool {
+    let is_open_ended = availability.timeslot.time_end() >= parking.horizon;
+    let is_pad_free = parking
+        .vertipad_timeslots
+        .get(vertipad_id)
+        .is_some_and(|timeslots| {
+            timeslots.iter().any(|timeslot| {
+                timeslot.time_start() <= landing && timeslot.time_end() >= parking.horizon
+            })
+        });
+
+    is_open_ended && is_pad_free
+}
+
+/// Why a deadhead flight is part of an itinerary
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum DeadheadReason {
+    /// The aircraft is elsewhere and flies to the departure vertiport
+    PrePositioning,
+
+    /// The aircraft returns to where it is needed next after the flight
+    Return,
+
+    /// The aircraft flies to a vertiport to recharge.
+    /// Not assigned yet, recharging isn't planned by the router.
+    Recharge,
+}
+
+/// A possible itinerary for a flight query
+#[derive(Debug, Clone)]
+pub struct ItineraryOption {
+    /// Flight plans for the requested flight and any deadhead flights
+    pub flight_plans: Vec<flight_plan::Data>,
+
+    /// Why each flight plan is flown, in the same order as the flight
+    ///  plans. `None` for the requested flight
+    pub deadhead_reasons: Vec<Option<DeadheadReason>>,
+
+    /// Other routes for the requested flight, shortest first
+    pub alternative_paths: Vec<(Vec<PointZ>, Meters)>,
+
+    /// The requested flight, as used to quote a price
+    pub quoted_flight: QuotedFlight,
+
+    /// Whether the itinerary uses a straight-line fallback path,
+    ///  planned while svc-gis was unavailable
+    pub path_fallback: bool,
+}
+
+/// Returns true if any flight plan of an itinerary flies a straight-line
+///  fallback path of the router
+fn uses_fallback_path<P: PathFinder>(flight_plans: &[flight_plan::Data], router: &P) -> bool {
+    flight_plans.iter().any(|fp| {
+        let (Some(origin), Some(target)) = (&fp.origin_vertiport_id, &fp.target_vertiport_id)
+        else {
+            return false;
+        };
+
+        router.is_fallback_path(origin, target)
+    })
+}
+
+/// Tags the deadhead flights of an itinerary: flights before the
+///  requested flight pre-position the aircraft, flights after it
+///  return the aircraft.
+pub fn get_deadhead_reasons(
+    flight_plans: &[flight_plan::Data],
+    requested: &flight_plan::Data,
+) -> Vec<Option<DeadheadReason>> {
+    let Some(index) = get_requested_index(flight_plans, requested) else {
+        router_warn!("Requested flight not found in itinerary.");
+        return vec![None; flight_plans.len()];
+    };
+
+    (0..flight_plans.len())
+        .map(|i| match i.cmp(&index) {
+            Ordering::Less => Some(DeadheadReason::PrePositioning),
+            Ordering::Equal => None,
+            Ordering::Greater => Some(DeadheadReason::Return),
+        })
+        .collect()
+}
+
+/// Finds the requested flight among the flight plans of an itinerary
+fn get_requested_index(
+    flight_plans: &[flight_plan::Data],
+    requested: &flight_plan::Data,
+) -> Option<usize> {
+    flight_plans.iter().position(|fp| {
+        fp.origin_vertiport_id == requested.origin_vertiport_id
+            && fp.origin_vertipad_id == requested.origin_vertipad_id
+            && fp.target_vertiport_id == requested.target_vertiport_id
+            && fp.target_vertipad_id == requested.target_vertipad_id
+    })
+}
+
+/// Returns when the requested flight of an itinerary is scheduled to depart
+fn get_requested_departure(
+    flight_plans: &[flight_plan::Data],
+    requested: &flight_plan::Data,
+) -> Option<DateTime<Utc>> {
+    let index = get_requested_index(flight_plans, requested)?;
+    flight_plans[index]
+        .origin_timeslot_start
+        .clone()
+        .map(DateTime::<Utc>::from)
+}
+
+/// Returns the departure delays to try for a deadhead flight,
+///  starting with no delay
+fn get_deadhead_departure_offsets(search: &DeadheadSearch) -> Vec<Duration> {
+    (0..search.attempts)
+        .filter_map(|i| i32::try_from(i).ok())
+        .map(|i| search.step * i)
+        .collect()
+}
+
+/// Removes the departure delays of flights that can't land before
+///  `arrival_latest`, whatever their flight time.
+/// Departures are rounded up to the second, so a delay may push the
+///  arrival past the deadline although the delay itself fits.
+fn trim_departure_offsets(
+    offsets: Vec<Duration>,
+    departure_earliest: DateTime<Utc>,
+    ground_time: Duration,
+    arrival_latest: DateTime<Utc>,
+) -> Vec<Duration> {
+    offsets
+        .into_iter()
+        .take_while(|offset| {
+            ceil_to_second(departure_earliest + *offset) + ground_time < arrival_latest
+        })
+        .collect()
+}
+
+/// Returns a departure delay for the aircraft between zero and
+///  `max_jitter`, in whole seconds.
+/// The delay is derived from the aircraft id so that aircraft which
+///  would otherwise land at the same instant are spread out, while
+///  the same query still returns the same itinerary.
+fn get_arrival_jitter(vehicle_id: &str, max_jitter: &Duration) -> Duration {
+    let max_seconds = max_jitter.num_seconds();
+    if max_seconds <= 0 {
+        return Duration::zero();
+    }
+
+    // FNV-1a, stable across builds unlike the std hasher
+    let hash = vehicle_id
+        .bytes()
+        .fold(0xcbf29ce484222325_u64, |hash, byte| {
+            (hash ^ u64::from(byte)).wrapping_mul(0x100000001b3)
+        });
+
+    // max_seconds is positive, cast is lossless
+    let offset = hash % (max_seconds as u64 + 1);
+    Duration::try_seconds(offset as i64).unwrap_or_else(Duration::zero)
+}
+
+/// Converts a route into flight plan geometry, flown at the aircraft's
+///  cruise altitude. The first and last points keep their altitudes so
+///  that the aircraft takes off from and lands on the pads, and points
+///  of the route higher than the cruise altitude aren't lowered.
+/// Without a cruise altitude the altitudes of the route are kept.
+fn get_cruise_path(path: &[PointZ], cruise_altitude_meters: Option<f32>) -> GeoLineString {
+    let last = path.len().saturating_sub(1);
+    let points = path
+        .iter()
+        .enumerate()
+        .map(|(index, point)| {
+            let altitude = match cruise_altitude_meters {
+                Some(cruise) if index != 0 && index != last => point.altitude_meters.max(cruise),
+                _ => point.altitude_meters,
+            };
+
+            GeoPoint {
+                latitude: point.latitude,
+                longitude: point.longitude,
+                altitude: altitude as f64,
+            }
+        })
+        .collect();
+
+    GeoLineString { points }
+}
+
+/// Returns the number of deadhead flights taking an aircraft parked at
+///  `parked_vertiport_id` to the origin and back from the target
+fn get_deadhead_legs(
+    origin_vertiport_id: &str,
+    target_vertiport_id: &str,
+    parked_vertiport_id: &str,
+) -> u32 {
+    u32::from(origin_vertiport_id != parked_vertiport_id)
+        + u32::from(target_vertiport_id != parked_vertiport_id)
+}
+
+/// Flattens the availabilities of each aircraft, earliest first.
+/// Ties are broken by aircraft ID so that identical queries try the
+///  aircraft in the same order, regardless of the map's iteration order.
+fn order_availabilities(
     aircraft_gaps: &HashMap<String, Vec<Availability>>,
-    clients: &GrpcClients,
-) -> Result<Vec<Vec<flight_plan::Data>>, ItineraryError> {
-    let mut itineraries: Vec<Vec<flight_plan::Data>> = vec![];
+) -> Vec<(String, Availability)> {
     let mut ordered: Vec<(String, Availability)> = aircraft_gaps
         .iter()
         .flat_map(|(k, vs)| {
@@ -191,60 +546,204 @@ pub async fn calculate_itineraries(
         })
         .collect();
 
-    ordered.sort_by(|a, b| a.1.timeslot.time_start().cmp(&b.1.timeslot.time_start()));
+    ordered.sort_by(|a, b| {
+        a.1.timeslot
+            .time_start()
+            .cmp(&b.1.timeslot.time_start())
+            .then_with(|| a.0.cmp(&b.0))
+    });
+
+    ordered
+}
+
+/// Given timeslot pairs for departure and arrival vertiport and the
+///  availabilities of the aircraft, get possible itineraries for each
+///  aircraft.
+/// Returns a maximum of 1 itinerary per aircraft.
+/// Aircraft without an entry in `aircraft_ground_times` use
+///  `default_ground_time` for loading and unloading.
+/// Flights are flown at the altitude in `aircraft_cruise_altitudes`,
+///  aircraft without an entry keep the altitudes of the route.
+/// Departures are delayed by up to `arrival_jitter` where the flight
+///  window allows, so that arrivals don't all land on the same instant.
+/// Deadhead flights are routed with `router`. Vertiport lead times
+///  count from the current time of `clock`.
+/// If no itinerary is found and some aircraft could only fly the request
+///  with more deadhead flights than allowed, returns `TooManyDeadheads`.
+#[cfg(not(tarpaulin_include))]
+// no_coverage: (R5) need running backend, integration tests
+#[allow(clippy::too_many_arguments)]
+pub async fn calculate_itineraries<P: PathFinder>(
+    default_ground_time: &GroundTime,
+    aircraft_ground_times: &HashMap<String, GroundTime>,
+    aircraft_cruise_altitudes: &HashMap<String, f32>,
+    timeslot_pairs: &[TimeslotPair],
+    aircraft_gaps: &HashMap<String, Vec<Availability>>,
+    deadhead_search: &DeadheadSearch,
+    arrival_jitter: &Duration,
+    lead_times: &LeadTimes,
+    clock: &dyn Clock,
+    router: &P,
+) -> Result<Vec<ItineraryOption>, ItineraryError> {
+    calculate_itineraries_with_rejections(
+        default_ground_time,
+        aircraft_ground_times,
+        aircraft_cruise_altitudes,
+        timeslot_pairs,
+        aircraft_gaps,
+        deadhead_search,
+        arrival_jitter,
+        lead_times,
+        clock,
+        None,
+        0,
+        None,
+        router,
+    )
+    .await
+    .map(|(itineraries, _)| itineraries)
+}
+
+/// Same as [`calculate_itineraries`], also returning why candidate
+///  itineraries were rejected.
+/// If a `deadline` is provided, the search stops when it passes and
+///  returns the itineraries found so far, flagged as `budget_exceeded`.
+/// The search goes on past the usual number of itineraries until
+///  `min_results` are found or every candidate has been tried.
+/// With a `preferred_departure`, the requested flight departs as close
+///  to it as each timeslot pair allows instead of as early as possible.
+#[cfg(not(tarpaulin_include))]
+// no_coverage: (R5) need running backend, integration tests
+#[allow(clippy::too_many_arguments)]
+pub async fn calculate_itineraries_with_rejections<P: PathFinder>(
+    default_ground_time: &GroundTime,
+    aircraft_ground_times: &HashMap<String, GroundTime>,
+    aircraft_cruise_altitudes: &HashMap<String, f32>,
+    timeslot_pairs: &[TimeslotPair],
+    aircraft_gaps: &HashMap<String, Vec<Availability>>,
+    deadhead_search: &DeadheadSearch,
+    arrival_jitter: &Duration,
+    lead_times: &LeadTimes,
+    clock: &dyn Clock,
+    deadline: Option<Instant>,
+    min_results: usize,
+    preferred_departure: Option<DateTime<Utc>>,
+    router: &P,
+) -> Result<(Vec<ItineraryOption>, Rejections), ItineraryError> {
+    let max_itineraries = max(MAX_ITINERARIES, min_results);
+    let mut itineraries: Vec<ItineraryOption> = vec![];
+    let mut rejections = Rejections {
+        timeslot_pairs: timeslot_pairs.len(),
+        ..Default::default()
+    };
+    let mut too_many_deadheads = false;
+
+    // Deadhead flights can't leave vertiports within their lead time
+    let now = clock.now();
+    let aircraft_gaps = lead_times.trim_availabilities(aircraft_gaps, now);
+    let ordered = order_availabilities(&aircraft_gaps);
 
     // For each available aircraft, see if it can do the flight
     'outer: for pair in timeslot_pairs {
         // TODO(R5): Include vehicle model to improve estimate
-        let flight_duration = estimate_flight_time_seconds(&pair.distance_meters).map_err(|e| {
-            router_error!("Could not estimate flight time: {e}.",);
+        let flight_duration = estimate_blocked_flight_time(&pair.distance_meters)
+            .await
+            .map_err(|e| {
+                router_error!("Could not estimate flight time: {e}.",);
 
-            ItineraryError::Internal
-        })?;
+                ItineraryError::Internal
+            })?;
+
+        // The flight can't depart or land within a vertiport's lead time
+        let earliest_departure = lead_times.get_earliest_departure(
+            &pair.origin_vertiport_id,
+            &pair.target_vertiport_id,
+            &flight_duration,
+            now,
+        );
 
         let Ok(flight_window) = Timeslot::new(
-            pair.origin_timeslot.time_start(),
+            earliest_departure.map_or(pair.origin_timeslot.time_start(), |earliest| {
+                max(pair.origin_timeslot.time_start(), earliest)
+            }),
             pair.target_timeslot.time_end(),
         ) else {
-            router_error!("Could not create flight window.");
+            router_debug!("Flight window ends within the vertiport lead times.");
             continue;
         };
 
-        let path = Some(GeoLineString {
-            points: pair
-                .path
-                .clone()
-                .into_iter()
-                .map(|point| GeoPoint {
-                    latitude: point.latitude,
-                    longitude: point.longitude,
-                    altitude: point.altitude_meters as f64,
-                })
-                .collect(),
-        });
+        // Quote the flight time without the safety margin, departing
+        //  when each itinerary's requested flight does
+        let quoted_flight = QuotedFlight {
+            distance_meters: pair.distance_meters,
+            duration: estimate_flight_time_seconds(&pair.distance_meters).map_err(|e| {
+                router_error!("Could not estimate flight time: {e}.");
+                ItineraryError::Internal
+            })?,
+            departure: pair.origin_timeslot.time_start(),
+        };
 
         for (aircraft_id, availability) in &ordered {
+            // Slower aircraft hold the vertipads for longer
+            let ground_time = aircraft_ground_times
+                .get(aircraft_id)
+                .unwrap_or(default_ground_time);
+
+            let cruise_altitude_meters = aircraft_cruise_altitudes.get(aircraft_id).copied();
+            let path = Some(get_cruise_path(&pair.path, cruise_altitude_meters));
+            let flight_window = match preferred_departure {
+                Some(preferred) => get_preferred_window(
+                    &flight_window,
+                    &pair.origin_timeslot,
+                    preferred,
+                    ground_time,
+                    &flight_duration,
+                ),
+                None => flight_window,
+            };
+
             let flight_plan = svc_storage_client_grpc::prelude::flight_plan::Data {
                 origin_vertiport_id: Some(pair.origin_vertiport_id.clone()),
                 target_vertiport_id: Some(pair.target_vertiport_id.clone()),
                 origin_vertipad_id: pair.origin_vertipad_id.clone(),
                 target_vertipad_id: pair.target_vertipad_id.clone(),
-                path: path.clone(),
+                path,
                 vehicle_id: aircraft_id.clone(),
                 ..Default::default()
             };
 
-            let itinerary = match get_itinerary(
+            let search = get_itinerary(
                 flight_plan.clone(),
                 availability,
                 &flight_duration,
-                required_loading_time,
-                required_unloading_time,
+                &ground_time.loading,
+                &ground_time.unloading,
                 &flight_window,
-                clients,
-            )
-            .await
-            {
+                &pair.origin_timeslot,
+                arrival_jitter,
+                deadhead_search,
+                lead_times,
+                now,
+                cruise_altitude_meters,
+                router,
+            );
+
+            let result = match deadline {
+                Some(deadline) => timeout_at(deadline, search).await,
+                None => Ok(search.await),
+            };
+
+            let Ok(result) = result else {
+                router_warn!(
+                    "Search time budget exceeded, returning {} itineraries.",
+                    itineraries.len()
+                );
+
+                rejections.budget_exceeded = true;
+                break 'outer;
+            };
+
+            let itinerary = match result {
                 Ok(itinerary) => itinerary,
                 Err(ItineraryError::ClientError) => {
                     // exit immediately if svc-gis is down, don't allow new flights
@@ -252,14 +751,32 @@ pub async fn calculate_itineraries(
 
                     return Err(ItineraryError::ClientError);
                 }
-                _ => {
-                    router_debug!("No itinerary found for aircraft {}.", aircraft_id);
+                Err(ItineraryError::TooManyDeadheads) => {
+                    rejections.record(&ItineraryError::TooManyDeadheads);
+                    too_many_deadheads = true;
+                    continue;
+                }
+                Err(e) => {
+                    router_debug!("No itinerary found for aircraft {}: {e}", aircraft_id);
+                    rejections.record(&e);
                     continue;
                 }
             };
 
-            itineraries.push(itinerary);
-            if itineraries.len() >= MAX_ITINERARIES {
+            let quoted_flight = QuotedFlight {
+                departure: get_requested_departure(&itinerary, &flight_plan)
+                    .unwrap_or(quoted_flight.departure),
+                ..quoted_flight
+            };
+
+            itineraries.push(ItineraryOption {
+                deadhead_reasons: get_deadhead_reasons(&itinerary, &flight_plan),
+                path_fallback: uses_fallback_path(&itinerary, router),
+                flight_plans: itinerary,
+                alternative_paths: pair.alternative_paths.clone(),
+                quoted_flight,
+            });
+            if itineraries.len() >= max_itineraries {
                 router_info!("max itineraries reached {}.", itineraries.len());
 
                 break 'outer;
@@ -269,7 +786,30 @@ pub async fn calculate_itineraries(
 
     router_info!("found {} itineraries.", itineraries.len());
 
-    Ok(itineraries)
+    if itineraries.is_empty() && too_many_deadheads && !rejections.budget_exceeded {
+        return Err(ItineraryError::TooManyDeadheads);
+    }
+
+    Ok((itineraries, rejections))
+}
+
+/// Narrows the flight window to depart as close to the preferred time
+///  as the origin timeslot allows, leaving time to load before the
+///  origin pad is needed again and to land before the window ends.
+fn get_preferred_window(
+    flight_window: &Timeslot,
+    origin_timeslot: &Timeslot,
+    preferred: DateTime<Utc>,
+    ground_time: &GroundTime,
+    flight_duration: &Duration,
+) -> Timeslot {
+    let latest = min(
+        origin_timeslot.time_end() - ground_time.loading,
+        flight_window.time_end() - ground_time.loading - *flight_duration - ground_time.unloading,
+    );
+
+    let departure = max(min(preferred, latest), flight_window.time_start());
+    Timeslot::new(departure, flight_window.time_end()).unwrap_or(*flight_window)
 }
 
 /// Struct to hold flight plan metadata
@@ -282,78 +822,124 @@ struct DeadheadHelperArgs<'a> {
     aircraft_earliest: DateTime<Utc>,
     vertipad_earliest: DateTime<Utc>,
     arrival_latest: DateTime<Utc>,
+    arrival_earliest: Option<DateTime<Utc>>,
     required_loading_time: Duration,
     required_unloading_time: Duration,
+    search: DeadheadSearch<'a>,
+    cruise_altitude_meters: Option<f32>,
 }
 
 /// Helper function to create a flight plan for a deadhead flight
 #[cfg(not(tarpaulin_include))]
 // no_coverage: (R5) need running backend, integration tests
-async fn deadhead_helper(
-    clients: &GrpcClients,
+async fn deadhead_helper<P: PathFinder>(
+    router: &P,
     args: DeadheadHelperArgs<'_>,
 ) -> Result<flight_plan::Data, ItineraryError> {
     router_debug!("Deadhead to departure vertiport.");
-    // See what the path and cost would be for a flight between the starting
-    // available timeslot and the ending flight time
-    let best_path_request = BestPathRequest {
-        origin_type: NodeType::Vertiport as i32,
-        target_type: NodeType::Vertiport as i32,
-        origin_identifier: args.origin_vertiport_id.to_owned(),
-        target_identifier: args.target_vertiport_id.to_owned(),
-        time_start: Some(args.aircraft_earliest.into()),
-        time_end: Some(args.vertipad_earliest.into()),
-        limit: 1,
-    };
+    let offsets = trim_departure_offsets(
+        get_deadhead_departure_offsets(&args.search),
+        args.aircraft_earliest,
+        args.required_loading_time + args.required_unloading_time,
+        args.arrival_latest,
+    );
 
-    let mut paths = match best_path(&best_path_request, clients).await {
-        Ok(paths) => paths,
-        Err(BestPathError::NoPathFound) => {
-            // no path found, perhaps temporary no-fly zone
-            //  is blocking journeys from this depart timeslot
-            // Break out and try the next depart timeslot
-            router_debug!(
-                "No path found from vertiport {}
-            to vertiport {} (from {} to {}).",
-                best_path_request.origin_identifier,
-                best_path_request.target_identifier,
-                args.aircraft_earliest,
-                args.vertipad_earliest
-            );
+    if offsets.len() < args.search.attempts as usize {
+        router_debug!(
+            "Deadhead departure attempts trimmed to {} by the arrival deadline.",
+            offsets.len()
+        );
+    }
 
-            return Err(ItineraryError::NoPathFound);
-        }
-        Err(BestPathError::ClientError) => {
-            // exit immediately if svc-gis is down, don't allow new flights
-            router_error!("Could not determine path.");
-            return Err(ItineraryError::ClientError);
+    let weather_cells = get_weather_cells().await.map_err(|e| {
+        router_error!("Could not get weather cells: {e}");
+        ItineraryError::Internal
+    })?;
+
+    let mut found = None;
+    for offset in offsets {
+        let aircraft_earliest = args.aircraft_earliest + offset;
+        let vertipad_earliest = max(args.vertipad_earliest, aircraft_earliest);
+
+        // See what the path and cost would be for a flight between the starting
+        // available timeslot and the ending flight time
+        let best_path_request = BestPathRequest {
+            origin_type: NodeType::Vertiport as i32,
+            target_type: NodeType::Vertiport as i32,
+            origin_identifier: args.origin_vertiport_id.to_owned(),
+            target_identifier: args.target_vertiport_id.to_owned(),
+            time_start: Some(aircraft_earliest.into()),
+            time_end: Some(vertipad_earliest.into()),
+            limit: 1,
+        };
+
+        // a route crossing an active weather cell is tried again later
+        let paths = router
+            .best_path(&best_path_request)
+            .await
+            .and_then(|paths| avoid_weather_cells(paths, &weather_cells, &best_path_request));
+
+        match paths {
+            // routers other than svc-gis may answer without any path
+            Ok(paths) => match paths.into_iter().next() {
+                Some(path) => {
+                    found = Some((aircraft_earliest, vertipad_earliest, path));
+                    break;
+                }
+                None => router_warn!("Router returned no paths for the deadhead flight."),
+            },
+            Err(BestPathError::NoPathFound) => {
+                // no path found, perhaps temporary no-fly zone
+                //  is blocking journeys from this depart timeslot
+                // Try the next depart timeslot
+                router_debug!(
+                    "No path found from vertiport {}
+                to vertiport {} (from {} to {}).",
+                    best_path_request.origin_identifier,
+                    best_path_request.target_identifier,
+                    aircraft_earliest,
+                    vertipad_earliest
+                );
+            }
+            Err(BestPathError::ClientError) => {
+                // exit immediately if svc-gis is down, don't allow new flights
+                router_error!("Could not determine path.");
+                return Err(ItineraryError::ClientError);
+            }
         }
-    };
+    }
 
-    let (path, distance_meters) = paths.remove(0);
-    let points = path
-        .into_iter()
-        .map(|point| GeoPoint {
-            latitude: point.latitude,
-            longitude: point.longitude,
-            altitude: point.altitude_meters as f64,
-        })
-        .collect();
+    let Some((aircraft_earliest, vertipad_earliest, (path, distance_meters))) = found else {
+        router_debug!(
+            "No deadhead path found after {} attempts.",
+            args.search.attempts
+        );
+        return Err(ItineraryError::NoPathFound);
+    };
 
-    let path = Some(GeoLineString { points });
+    let path = Some(get_cruise_path(&path, args.cruise_altitude_meters));
 
-    let flight_duration = estimate_flight_time_seconds(&distance_meters).map_err(|e| {
-        router_error!("Could not estimate flight time: {e}");
-        ItineraryError::Internal
-    })?;
+    let flight_duration = estimate_blocked_flight_time(&distance_meters)
+        .await
+        .map_err(|e| {
+            router_error!("Could not estimate flight time: {e}");
+            ItineraryError::Internal
+        })?;
 
     let total_duration =
         flight_duration + args.required_loading_time + args.required_unloading_time;
 
-    let origin_timeslot_start = max(
-        args.aircraft_earliest,
-        args.vertipad_earliest - total_duration,
-    );
+    let mut origin_timeslot_start = max(aircraft_earliest, vertipad_earliest - total_duration);
+
+    // The flight can't land within the target vertiport's lead time
+    if let Some(arrival_earliest) = args.arrival_earliest {
+        origin_timeslot_start = max(
+            origin_timeslot_start,
+            arrival_earliest - args.required_loading_time - flight_duration,
+        );
+    }
+
+    let origin_timeslot_start = ceil_to_second(origin_timeslot_start);
 
     let origin_timeslot_end = origin_timeslot_start + args.required_loading_time;
     let target_timeslot_start = origin_timeslot_end + flight_duration;
@@ -384,16 +970,44 @@ async fn deadhead_helper(
     Ok(data)
 }
 
+/// Shortens a departure delay to the time left in the origin vertipad
+///  timeslot after loading and in the flight window after unloading
+fn clamp_jitter(jitter: Duration, origin_slack: Duration, target_slack: Duration) -> Duration {
+    jitter
+        .min(origin_slack)
+        .min(target_slack)
+        .max(Duration::zero())
+}
+
+/// Keeps the reason a deadhead flight couldn't be routed, so the rejection
+///  counts against routing. Other failures are schedule conflicts.
+fn get_deadhead_error(e: ItineraryError) -> ItineraryError {
+    match e {
+        ItineraryError::NoPath | ItineraryError::PathTooShort | ItineraryError::NoPathFound => e,
+        _ => ItineraryError::ScheduleConflict,
+    }
+}
+
 /// Determines if the aircraft is available for the requested flight,
 ///  given that it may require multiple deadhead trips.
-async fn get_itinerary(
+/// The requested flight leaves up to `arrival_jitter` after the earliest
+///  possible time, as long as it is still loaded while the origin vertipad
+///  is free (`origin_timeslot`) and lands within the flight window.
+#[allow(clippy::too_many_arguments)]
+async fn get_itinerary<P: PathFinder>(
     flight_plan: flight_plan::Data,
     availability: &Availability,
     flight_duration: &Duration,
     required_loading_time: &Duration,
     required_unloading_time: &Duration,
     flight_window: &Timeslot,
-    clients: &GrpcClients,
+    origin_timeslot: &Timeslot,
+    arrival_jitter: &Duration,
+    deadhead_search: &DeadheadSearch,
+    lead_times: &LeadTimes,
+    now: DateTime<Utc>,
+    cruise_altitude_meters: Option<f32>,
+    router: &P,
 ) -> Result<Vec<flight_plan::Data>, ItineraryError> {
     router_debug!("entry.");
 
@@ -420,11 +1034,30 @@ async fn get_itinerary(
     let vehicle_id = flight_plan.vehicle_id.clone();
     let deadhead_loading_time = Duration::zero();
 
+    // Reject itineraries with an absurd amount of repositioning
+    let deadhead_legs = get_deadhead_legs(
+        origin_vertiport_id,
+        target_vertiport_id,
+        &availability.vertiport_id,
+    );
+    if deadhead_search.enabled && deadhead_search.is_too_many_legs(deadhead_legs) {
+        router_debug!(
+            "Aircraft {vehicle_id} needs {deadhead_legs} deadhead flights, at most {} allowed.",
+            deadhead_search.max_legs
+        );
+        return Err(ItineraryError::TooManyDeadheads);
+    }
+
     //
     // 1) Create the flight plan for the deadhead flight to the requested departure vertiport
     //
     let mut flight_plans = vec![];
     if *origin_vertiport_id != availability.vertiport_id {
+        if !deadhead_search.enabled {
+            router_debug!("Aircraft not at origin and deadhead flights are disabled.");
+            return Err(ItineraryError::ScheduleConflict);
+        }
+
         router_debug!("plotting deadhead to origin.");
         println!("(get_itinerary) plotting deadhead to origin.");
 
@@ -437,15 +1070,18 @@ async fn get_itinerary(
             aircraft_earliest: availability.timeslot.time_start(),
             vertipad_earliest: overlap.time_start(),
             arrival_latest: overlap.time_end(),
+            arrival_earliest: lead_times.get_earliest(origin_vertiport_id, now),
             required_loading_time: deadhead_loading_time, // deadhead - no loading
             required_unloading_time: deadhead_loading_time, // deadhead - no unloading
+            search: *deadhead_search,
+            cruise_altitude_meters,
         };
 
-        let deadhead = match deadhead_helper(clients, args).await {
+        let deadhead = match deadhead_helper(router, args).await {
             Ok(deadhead) => deadhead,
             Err(e) => {
                 router_error!("Couldn't schedule deadhead flight: {e}");
-                return Err(ItineraryError::ScheduleConflict);
+                return Err(get_deadhead_error(e));
             }
         };
 
@@ -457,7 +1093,7 @@ async fn get_itinerary(
     //
     router_debug!("plotting primary flight plan.");
     println!("(get_itinerary) plotting primary flight plan.");
-    let origin_timeslot_start: DateTime<Utc> = match flight_plans.last() {
+    let mut origin_timeslot_start: DateTime<Utc> = match flight_plans.last() {
         Some(last) => last
             .target_timeslot_end
             .clone()
@@ -467,15 +1103,15 @@ async fn get_itinerary(
             })?
             .into(),
         // leave at earliest possible time
-        None => max(
+        None => ceil_to_second(max(
             flight_window.time_start(),
             availability.timeslot.time_start(),
-        ),
+        )),
     };
 
-    let origin_timeslot_end = origin_timeslot_start + *required_loading_time;
-    let target_timeslot_start = origin_timeslot_end + *flight_duration;
-    let target_timeslot_end = target_timeslot_start + *required_unloading_time;
+    let mut origin_timeslot_end = origin_timeslot_start + *required_loading_time;
+    let mut target_timeslot_start = origin_timeslot_end + *flight_duration;
+    let mut target_timeslot_end = target_timeslot_start + *required_unloading_time;
 
     if target_timeslot_end > overlap.time_end() {
         // This flight plan would exceed the flight window
@@ -484,6 +1120,23 @@ async fn get_itinerary(
         return Err(ItineraryError::ScheduleConflict);
     }
 
+    // Spread out arrivals, never past the end of the flight window or
+    //  of the origin vertipad timeslot.
+    //  After a deadhead the aircraft would be holding the origin pad
+    //  while waiting, so it leaves right away instead.
+    let jitter = match flight_plans.is_empty() {
+        true => clamp_jitter(
+            get_arrival_jitter(&vehicle_id, arrival_jitter),
+            origin_timeslot.time_end() - origin_timeslot_end,
+            overlap.time_end() - target_timeslot_end,
+        ),
+        false => Duration::zero(),
+    };
+    origin_timeslot_start += jitter;
+    origin_timeslot_end += jitter;
+    target_timeslot_start += jitter;
+    target_timeslot_end += jitter;
+
     // Flight requested by user
     let mut main_flight_plan = flight_plan.clone();
     main_flight_plan.origin_timeslot_start = Some(origin_timeslot_start.into());
@@ -494,9 +1147,10 @@ async fn get_itinerary(
 
     //
     // 3) Create the post deadhead flight to take the aircraft away from the pad
-    //  when flight is completed
+    //  when flight is completed. Skipped when deadheads are disabled,
+    //  repositioning is then handled outside of the scheduler.
     //
-    if *target_vertiport_id != availability.vertiport_id {
+    if deadhead_search.enabled && *target_vertiport_id != availability.vertiport_id {
         router_debug!("plotting deadhead from target.");
         println!("(get_itinerary) plotting deadhead from target.");
 
@@ -522,20 +1176,37 @@ async fn get_itinerary(
             aircraft_earliest: (*last_arrival).clone().into(),
             vertipad_earliest: (*last_arrival).clone().into(), // reserved pad can be accessed any time
             arrival_latest: availability.timeslot.time_end(),
+            arrival_earliest: lead_times.get_earliest(&availability.vertiport_id, now),
             required_loading_time: deadhead_loading_time, // deadhead - no loading
             required_unloading_time: deadhead_loading_time, // deadhead - no unloading
+            search: *deadhead_search,
+            cruise_altitude_meters,
         };
 
-        let deadhead = match deadhead_helper(clients, args).await {
-            Ok(deadhead) => deadhead,
+        match deadhead_helper(router, args).await {
+            Ok(deadhead) => flight_plans.push(deadhead),
+            Err(e)
+                if e != ItineraryError::ClientError
+                    && deadhead_search.return_policy == ReturnPolicy::Park
+                    && deadhead_search.parking.is_some_and(|parking| {
+                        can_park_at_target(
+                            parking,
+                            availability,
+                            &main_flight_plan.target_vertipad_id,
+                            target_timeslot_start,
+                        )
+                    }) =>
+            {
+                router_info!(
+                    "No deadhead flight back for aircraft {vehicle_id} ({e}), parking at {target_vertiport_id}."
+                );
+            }
             Err(e) => {
                 router_error!("Couldn't schedule deadhead flight: {e}");
                 println!("(get_itinerary) Couldn't schedule deadhead flight: {e}");
-                return Err(ItineraryError::ScheduleConflict);
+                return Err(get_deadhead_error(e));
             }
-        };
-
-        flight_plans.push(deadhead);
+        }
     }
 
     router_debug!("flight_plans: {:#?}", flight_plans);
@@ -545,131 +1216,402 @@ async fn get_itinerary(
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::clock::SystemClock;
     use crate::grpc::client::get_clients;
     use lib_common::uuid::Uuid;
 
-    #[tokio::test]
-    #[cfg(feature = "stub_backends")]
-    async fn test_get_itinerary_valid_pre_post_deadheads() {
-        let clients = get_clients().await;
-        let time_start = Utc::now();
-        let time_end = Utc::now() + Duration::try_seconds(1000).unwrap();
-        let vertiport_1 = Uuid::new_v4().to_string();
-        let vertiport_2 = Uuid::new_v4().to_string();
-        let vertiport_3 = Uuid::new_v4().to_string();
-        let vertipad_1 = Uuid::new_v4().to_string();
-        let vertipad_2 = Uuid::new_v4().to_string();
-        let vehicle_id = Uuid::new_v4().to_string();
-        let required_loading_time = Duration::try_seconds(30).unwrap();
-        let required_unloading_time = Duration::try_seconds(30).unwrap();
-
-        let aircraft_availability = Availability {
-            vertiport_id: vertiport_1.clone(),
-            vertipad_id: vertipad_1.clone(),
-            timeslot: Timeslot::new(time_start - Duration::try_seconds(1000).unwrap(), time_end)
-                .unwrap(),
-        };
-
-        let distance_meters = 50.0;
-        let flight_duration = estimate_flight_time_seconds(&distance_meters).unwrap();
-        let flight_window = Timeslot::new(time_start, time_end).unwrap();
+    fn deadhead_search() -> DeadheadSearch<'static> {
+        DeadheadSearch {
+            enabled: true,
+            step: Duration::try_minutes(10).unwrap(),
+            attempts: 6,
+            max_legs: 2,
+            return_policy: ReturnPolicy::Strict,
+            parking: None,
+        }
+    }
 
-        let flight_plan = flight_plan::Data {
-            origin_vertiport_id: Some(vertiport_3.clone()),
-            target_vertiport_id: Some(vertiport_2.clone()),
-            origin_vertipad_id: vertipad_1.clone(),
-            target_vertipad_id: vertipad_2.clone(),
-            vehicle_id,
-            path: Some(GeoLineString { points: vec![] }),
+    #[test]
+    fn test_get_cruise_path() {
+        let path = vec![
+            PointZ {
+                latitude: 52.0,
+                longitude: 4.0,
+                altitude_meters: 0.0,
+            },
+            PointZ {
+                latitude: 52.1,
+                longitude: 4.1,
+                altitude_meters: 80.0,
+            },
+            PointZ {
+                latitude: 52.2,
+                longitude: 4.2,
+                altitude_meters: 200.0,
+            },
+            PointZ {
+                latitude: 52.3,
+                longitude: 4.3,
+                altitude_meters: 10.0,
+            },
+        ];
+
+        // flown at the aircraft's cruise altitude between the pads
+        let cruise = get_cruise_path(&path, Some(120.0));
+        assert_eq!(cruise.points.len(), 4);
+        assert_eq!(cruise.points[0].altitude, 0.0);
+        assert_eq!(cruise.points[1].altitude, 120.0);
+        assert_eq!(cruise.points[3].altitude, 10.0);
+        assert_eq!(cruise.points[1].latitude, 52.1);
+        assert_eq!(cruise.points[1].longitude, 4.1);
+
+        // higher points of the route aren't lowered
+        assert_eq!(cruise.points[2].altitude, 200.0);
+
+        // without one, the route's altitudes are kept
+        let raw = get_cruise_path(&path, None);
+        assert_eq!(raw.points[0].altitude, 0.0);
+        assert_eq!(raw.points[1].altitude, 80.0);
+        assert_eq!(raw.points[3].altitude, 10.0);
+    }
+
+    #[test]
+    fn test_order_availabilities() {
+        let start = Utc::now();
+        let availability = |minutes: i64| Availability {
+            timeslot: Timeslot::new(
+                start + Duration::try_minutes(minutes).unwrap(),
+                start + Duration::try_minutes(minutes + 60).unwrap(),
+            )
+            .unwrap(),
+            vertiport_id: Uuid::new_v4().to_string(),
+            vertipad_id: Uuid::new_v4().to_string(),
+        };
+
+        // several aircraft available at the same time
+        let mut entries: Vec<(String, Vec<Availability>)> = (0..8)
+            .map(|_| (Uuid::new_v4().to_string(), vec![availability(0)]))
+            .collect();
+        let later_id = entries[0].0.clone();
+        entries[0].1.insert(0, availability(-30));
+        entries.push((Uuid::new_v4().to_string(), vec![availability(30)]));
+
+        let expected = order_availabilities(&entries.iter().cloned().collect());
+        assert_eq!(expected.len(), 10);
+        assert_eq!(expected[0].0, later_id);
+        assert!(expected
+            .windows(2)
+            .all(|w| w[0].1.timeslot.time_start() <= w[1].1.timeslot.time_start()));
+
+        // same aircraft, different map iteration orders
+        for _ in 0..10 {
+            let aircraft_gaps: HashMap<String, Vec<Availability>> =
+                entries.iter().rev().cloned().collect();
+            assert_eq!(order_availabilities(&aircraft_gaps), expected);
+        }
+    }
+
+    #[test]
+    fn test_get_deadhead_reasons() {
+        let get_flight_plan = |origin: &str, target: &str| flight_plan::Data {
+            origin_vertiport_id: Some(origin.to_string()),
+            origin_vertipad_id: format!("{origin}-pad"),
+            target_vertiport_id: Some(target.to_string()),
+            target_vertipad_id: format!("{target}-pad"),
             ..Default::default()
         };
 
-        let itinerary = get_itinerary(
-            flight_plan,
-            &aircraft_availability,
-            &flight_duration,
-            &required_loading_time,
-            &required_unloading_time,
-            &flight_window,
-            &clients,
-        )
-        .await
-        .unwrap();
+        let requested = get_flight_plan("origin", "target");
+        let pre = get_flight_plan("hangar", "origin");
+        let post = get_flight_plan("target", "hangar");
 
-        // 3 flight plans: deadhead to vertiport_3, flight to vertiport_2, deadhead to vertiport_1
-        assert_eq!(itinerary.len(), 3);
         assert_eq!(
-            itinerary[0].origin_vertiport_id.clone().unwrap(),
-            vertiport_1
+            get_deadhead_reasons(&[pre.clone(), requested.clone(), post.clone()], &requested),
+            vec![
+                Some(DeadheadReason::PrePositioning),
+                None,
+                Some(DeadheadReason::Return)
+            ]
         );
         assert_eq!(
-            itinerary[0].target_vertiport_id.clone().unwrap(),
-            vertiport_3
+            get_deadhead_reasons(&[requested.clone(), post], &requested),
+            vec![None, Some(DeadheadReason::Return)]
         );
-        assert_eq!(itinerary[0].target_vertipad_id.clone(), vertipad_1);
-
         assert_eq!(
-            itinerary[1].origin_vertiport_id.clone().unwrap(),
-            vertiport_3
+            get_deadhead_reasons(&[pre, requested.clone()], &requested),
+            vec![Some(DeadheadReason::PrePositioning), None]
         );
-        assert_eq!(itinerary[1].origin_vertipad_id.clone(), vertipad_1);
+
+        // requested flight missing, nothing is tagged
+        let other = get_flight_plan("target", "origin");
+        assert_eq!(get_deadhead_reasons(&[requested], &other), vec![None]);
+    }
+
+    #[test]
+    fn test_get_requested_departure() {
+        let now = Utc::now();
+        let get_flight_plan =
+            |origin: &str, target: &str, departure: DateTime<Utc>| flight_plan::Data {
+                origin_vertiport_id: Some(origin.to_string()),
+                origin_vertipad_id: format!("{origin}-pad"),
+                target_vertiport_id: Some(target.to_string()),
+                target_vertipad_id: format!("{target}-pad"),
+                origin_timeslot_start: Some(departure.into()),
+                ..Default::default()
+            };
+
+        // the requested flight departs after the deadhead flight
+        let departure = now + Duration::try_minutes(20).unwrap();
+        let pre = get_flight_plan("hangar", "origin", now);
+        let requested = get_flight_plan("origin", "target", departure);
         assert_eq!(
-            itinerary[1].target_vertiport_id.clone().unwrap(),
-            vertiport_2
+            get_requested_departure(&[pre, requested.clone()], &requested),
+            Some(departure)
         );
-        assert_eq!(itinerary[1].target_vertipad_id.clone(), vertipad_2);
 
-        assert_eq!(
-            itinerary[2].origin_vertiport_id.clone().unwrap(),
-            vertiport_2
+        let other = get_flight_plan("target", "origin", now);
+        assert_eq!(get_requested_departure(&[requested], &other), None);
+    }
+
+    /// Falls back to straight lines from `fallback_origin` only
+    struct PartialFallbackRouter {
+        fallback_origin: String,
+    }
+
+    #[tonic::async_trait]
+    impl PathFinder for PartialFallbackRouter {
+        async fn best_path(
+            &self,
+            _request: &BestPathRequest,
+        ) -> Result<Vec<(Vec<PointZ>, Meters)>, BestPathError> {
+            Ok(vec![])
+        }
+
+        fn is_fallback_path(&self, origin_identifier: &str, _target_identifier: &str) -> bool {
+            origin_identifier == self.fallback_origin
+        }
+    }
+
+    #[test]
+    fn test_uses_fallback_path() {
+        let get_flight_plan = |origin: &str, target: &str| flight_plan::Data {
+            origin_vertiport_id: Some(origin.to_string()),
+            target_vertiport_id: Some(target.to_string()),
+            ..Default::default()
+        };
+
+        let router = PartialFallbackRouter {
+            fallback_origin: "hangar".to_string(),
+        };
+
+        // only itineraries flying the fallback path are flagged
+        let requested = get_flight_plan("origin", "target");
+        let pre = get_flight_plan("hangar", "origin");
+        assert!(uses_fallback_path(&[pre, requested.clone()], &router));
+        assert!(!uses_fallback_path(&[requested.clone()], &router));
+        assert!(!uses_fallback_path(
+            &[
+                requested,
+                flight_plan::Data {
+                    origin_vertiport_id: None,
+                    ..get_flight_plan("hangar", "origin")
+                }
+            ],
+            &router
+        ));
+    }
+
+    #[test]
+    fn test_get_deadhead_departure_offsets() {
+        let offsets = get_deadhead_departure_offsets(&deadhead_search());
+        assert_eq!(offsets.len(), 6);
+        assert_eq!(offsets[0], Duration::zero());
+        assert_eq!(offsets[5], Duration::try_minutes(50).unwrap());
+
+        let search = DeadheadSearch {
+            attempts: 0,
+            ..deadhead_search()
+        };
+        assert!(get_deadhead_departure_offsets(&search).is_empty());
+    }
+
+    #[test]
+    fn test_get_deadhead_legs() {
+        // parked at the origin, or flying back to where it's parked
+        assert_eq!(get_deadhead_legs("a", "b", "a"), 1);
+        assert_eq!(get_deadhead_legs("b", "a", "a"), 1);
+        assert_eq!(get_deadhead_legs("a", "a", "a"), 0);
+
+        // repositioned to the origin and away from the target
+        assert_eq!(get_deadhead_legs("a", "b", "c"), 2);
+
+        let search = deadhead_search();
+        assert!(!search.is_too_many_legs(2));
+
+        let search = DeadheadSearch {
+            max_legs: 1,
+            ..deadhead_search()
+        };
+        assert!(!search.is_too_many_legs(1));
+        assert!(search.is_too_many_legs(2));
+
+        // no limit
+        let search = DeadheadSearch {
+            max_legs: 0,
+            ..deadhead_search()
+        };
+        assert!(!search.is_too_many_legs(10));
+    }
+
+    #[test]
+    fn test_get_preferred_window() {
+        let now = Utc::now();
+        let minutes = |minutes: i64| now + Duration::try_minutes(minutes).unwrap();
+        let flight_window = Timeslot::new(minutes(0), minutes(120)).unwrap();
+        let origin_timeslot = Timeslot::new(minutes(0), minutes(60)).unwrap();
+        let ground_time = GroundTime {
+            loading: Duration::try_minutes(5).unwrap(),
+            unloading: Duration::try_minutes(5).unwrap(),
+        };
+        let flight_duration = Duration::try_minutes(30).unwrap();
+        let window = |preferred: DateTime<Utc>| {
+            get_preferred_window(
+                &flight_window,
+                &origin_timeslot,
+                preferred,
+                &ground_time,
+                &flight_duration,
+            )
+        };
+
+        // departs at the preferred time within the origin timeslot
+        let preferred = window(minutes(20));
+        assert_eq!(preferred.time_start(), minutes(20));
+        assert_eq!(preferred.time_end(), flight_window.time_end());
+
+        // clamped to the origin timeslot, leaving time to load
+        assert_eq!(window(minutes(-10)).time_start(), minutes(0));
+        assert_eq!(window(minutes(90)).time_start(), minutes(55));
+
+        // and to land before the flight window ends
+        let flight_window = Timeslot::new(minutes(0), minutes(60)).unwrap();
+        let preferred = get_preferred_window(
+            &flight_window,
+            &origin_timeslot,
+            minutes(50),
+            &ground_time,
+            &flight_duration,
         );
-        assert_eq!(itinerary[2].origin_vertipad_id.clone(), vertipad_2);
+        assert_eq!(preferred.time_start(), minutes(20));
+    }
+
+    #[test]
+    fn test_clamp_jitter() {
+        let minutes = |m: i64| Duration::try_minutes(m).unwrap();
         assert_eq!(
-            itinerary[2].target_vertiport_id.clone().unwrap(),
-            vertiport_1
+            clamp_jitter(minutes(5), minutes(10), minutes(10)),
+            minutes(5)
         );
 
-        // Land at earliest possible time
+        // loading must end while the origin vertipad is free
         assert_eq!(
-            // deadhead
-            itinerary[0].target_timeslot_start.clone().unwrap(),
-            time_start.into() // early as possible in vertipad timeslot
+            clamp_jitter(minutes(5), minutes(2), minutes(10)),
+            minutes(2)
         );
+
+        // unloading must end within the flight window
         assert_eq!(
-            itinerary[0].target_timeslot_end.clone().unwrap(),
-            time_start.into() // no unloading time needed for deadhead
+            clamp_jitter(minutes(5), minutes(10), minutes(3)),
+            minutes(3)
         );
 
+        // never departs earlier than planned
         assert_eq!(
-            // main flight
-            itinerary[1].origin_timeslot_start.clone().unwrap(),
-            time_start.into()
+            clamp_jitter(minutes(5), minutes(-1), minutes(10)),
+            Duration::zero()
         );
+    }
 
+    #[test]
+    fn test_get_arrival_jitter() {
+        let max_jitter = Duration::try_minutes(10).unwrap();
+        let jitter = get_arrival_jitter("aircraft-1", &max_jitter);
+        assert!(jitter >= Duration::zero() && jitter <= max_jitter);
+        assert_eq!(jitter, get_arrival_jitter("aircraft-1", &max_jitter));
+        assert_ne!(jitter, get_arrival_jitter("aircraft-2", &max_jitter));
+
+        // disabled
         assert_eq!(
-            // main flight
-            itinerary[1].target_timeslot_start.clone().unwrap(),
-            (time_start + required_loading_time + flight_duration).into()
+            get_arrival_jitter("aircraft-1", &Duration::zero()),
+            Duration::zero()
         );
+    }
+
+    #[test]
+    fn test_get_deadhead_departure_offsets_extended_horizon() {
+        // path only opens up 70 minutes after the earliest departure
+        let gap_start = Duration::try_minutes(70).unwrap();
+
+        let offsets = get_deadhead_departure_offsets(&deadhead_search());
+        assert!(!offsets.iter().any(|offset| *offset >= gap_start));
+
+        let search = DeadheadSearch {
+            attempts: 8,
+            ..deadhead_search()
+        };
+        let offsets = get_deadhead_departure_offsets(&search);
         assert_eq!(
-            // main flight
-            itinerary[1].target_timeslot_end.clone().unwrap(),
-            (time_start + required_loading_time + flight_duration + required_unloading_time).into()
+            offsets.iter().find(|offset| **offset >= gap_start),
+            Some(&gap_start)
         );
-        assert_eq!(
-            // deadhead
-            itinerary[2].origin_timeslot_start.clone().unwrap(),
-            (time_start + required_loading_time + flight_duration + required_unloading_time).into()
+    }
+
+    #[test]
+    fn test_trim_departure_offsets() {
+        let offsets = get_deadhead_departure_offsets(&deadhead_search());
+        let ground_time = Duration::try_minutes(1).unwrap();
+
+        // departure with a fraction of a second, rounded up
+        let departure_earliest =
+            DateTime::<Utc>::from_timestamp(1_700_000_000, 500_000_000).unwrap();
+
+        // every attempt lands in time
+        let arrival_latest = departure_earliest + Duration::try_hours(2).unwrap();
+        let trimmed = trim_departure_offsets(
+            offsets.clone(),
+            departure_earliest,
+            ground_time,
+            arrival_latest,
+        );
+        assert_eq!(trimmed, offsets);
+
+        // the last attempt would only land in time without the rounding
+        let arrival_latest = departure_earliest + offsets[5] + ground_time;
+        let trimmed = trim_departure_offsets(
+            offsets.clone(),
+            departure_earliest,
+            ground_time,
+            arrival_latest,
         );
+        assert_eq!(trimmed, offsets[..5].to_vec());
+        for offset in trimmed {
+            assert!(ceil_to_second(departure_earliest + offset) + ground_time < arrival_latest);
+        }
+
+        // no attempt lands in time
+        let trimmed =
+            trim_departure_offsets(offsets, departure_earliest, ground_time, departure_earliest);
+        assert!(trimmed.is_empty());
     }
 
     #[tokio::test]
     #[cfg(feature = "stub_backends")]
-    async fn test_get_itinerary_valid_pre_deadhead() {
+    async fn test_get_itinerary_valid_pre_post_deadheads() {
         let clients = get_clients().await;
         let time_start = Utc::now();
         let time_end = Utc::now() + Duration::try_seconds(1000).unwrap();
         let vertiport_1 = Uuid::new_v4().to_string();
+        let vertiport_2 = Uuid::new_v4().to_string();
         let vertiport_3 = Uuid::new_v4().to_string();
         let vertipad_1 = Uuid::new_v4().to_string();
         let vertipad_2 = Uuid::new_v4().to_string();
@@ -680,20 +1622,17 @@ mod tests {
         let aircraft_availability = Availability {
             vertiport_id: vertiport_1.clone(),
             vertipad_id: vertipad_1.clone(),
-            timeslot: Timeslot::new(
-                time_start - Duration::try_seconds(1000).unwrap(),
-                time_end + Duration::try_seconds(1000).unwrap(),
-            )
-            .unwrap(),
+            timeslot: Timeslot::new(time_start - Duration::try_seconds(1000).unwrap(), time_end)
+                .unwrap(),
         };
 
-        let distance_meters = 50.0;
+        let distance_meters = Meters(50.0);
         let flight_duration = estimate_flight_time_seconds(&distance_meters).unwrap();
         let flight_window = Timeslot::new(time_start, time_end).unwrap();
 
         let flight_plan = flight_plan::Data {
             origin_vertiport_id: Some(vertiport_3.clone()),
-            target_vertiport_id: Some(vertiport_1.clone()),
+            target_vertiport_id: Some(vertiport_2.clone()),
             origin_vertipad_id: vertipad_1.clone(),
             target_vertipad_id: vertipad_2.clone(),
             vehicle_id,
@@ -702,19 +1641,33 @@ mod tests {
         };
 
         let itinerary = get_itinerary(
-            flight_plan,
+            flight_plan.clone(),
             &aircraft_availability,
             &flight_duration,
             &required_loading_time,
             &required_unloading_time,
             &flight_window,
-            &clients,
+            &flight_window,
+            &Duration::zero(),
+            &deadhead_search(),
+            &LeadTimes::default(),
+            Utc::now(),
+            None,
+            clients,
         )
         .await
         .unwrap();
 
-        // 2 flight plans: deadhead to vertiport_3, flight to vertiport_1
-        assert_eq!(itinerary.len(), 2);
+        // 3 flight plans: deadhead to vertiport_3, flight to vertiport_2, deadhead to vertiport_1
+        assert_eq!(itinerary.len(), 3);
+        assert_eq!(
+            get_deadhead_reasons(&itinerary, &flight_plan),
+            vec![
+                Some(DeadheadReason::PrePositioning),
+                None,
+                Some(DeadheadReason::Return)
+            ]
+        );
         assert_eq!(
             itinerary[0].origin_vertiport_id.clone().unwrap(),
             vertiport_1
@@ -732,62 +1685,174 @@ mod tests {
         assert_eq!(itinerary[1].origin_vertipad_id.clone(), vertipad_1);
         assert_eq!(
             itinerary[1].target_vertiport_id.clone().unwrap(),
-            vertiport_1
+            vertiport_2
         );
         assert_eq!(itinerary[1].target_vertipad_id.clone(), vertipad_2);
 
+        assert_eq!(
+            itinerary[2].origin_vertiport_id.clone().unwrap(),
+            vertiport_2
+        );
+        assert_eq!(itinerary[2].origin_vertipad_id.clone(), vertipad_2);
+        assert_eq!(
+            itinerary[2].target_vertiport_id.clone().unwrap(),
+            vertiport_1
+        );
+
         // Land at earliest possible time
         assert_eq!(
-            // deadhead flight
-            itinerary[0].target_timeslot_end.clone().unwrap(),
-            (time_start).into() // deadhead flight should arrive as early as possible to time_start
+            // deadhead
+            itinerary[0].target_timeslot_start.clone().unwrap(),
+            ceil_to_second(time_start).into() // early as possible in vertipad timeslot
         );
         assert_eq!(
-            itinerary[1].origin_timeslot_start.clone().unwrap(),
-            time_start.into()
+            itinerary[0].target_timeslot_end.clone().unwrap(),
+            ceil_to_second(time_start).into() // no unloading time needed for deadhead
         );
+
         assert_eq!(
-            itinerary[1].origin_timeslot_end.clone().unwrap(),
-            (time_start + required_loading_time).into()
+            // main flight
+            itinerary[1].origin_timeslot_start.clone().unwrap(),
+            ceil_to_second(time_start).into()
         );
+
         assert_eq!(
+            // main flight
             itinerary[1].target_timeslot_start.clone().unwrap(),
-            (time_start + required_loading_time + flight_duration).into()
+            (ceil_to_second(time_start) + required_loading_time + flight_duration).into()
         );
         assert_eq!(
+            // main flight
             itinerary[1].target_timeslot_end.clone().unwrap(),
-            (time_start + required_loading_time + flight_duration + required_unloading_time).into()
+            (ceil_to_second(time_start)
+                + required_loading_time
+                + flight_duration
+                + required_unloading_time)
+                .into()
+        );
+        assert_eq!(
+            // deadhead
+            itinerary[2].origin_timeslot_start.clone().unwrap(),
+            (ceil_to_second(time_start)
+                + required_loading_time
+                + flight_duration
+                + required_unloading_time)
+                .into()
         );
+
+        // All scheduled times are whole seconds
+        for flight_plan in &itinerary {
+            for time in [
+                &flight_plan.origin_timeslot_start,
+                &flight_plan.origin_timeslot_end,
+                &flight_plan.target_timeslot_start,
+                &flight_plan.target_timeslot_end,
+            ] {
+                assert_eq!(time.clone().unwrap().nanos, 0);
+            }
+        }
     }
 
     #[tokio::test]
     #[cfg(feature = "stub_backends")]
-    async fn test_get_itinerary_valid_post_deadhead() {
+    async fn test_get_itinerary_arrival_jitter() {
+        let clients = get_clients().await;
+        let time_start = Utc::now();
+        let time_end = Utc::now() + Duration::try_hours(1).unwrap();
+        let vertiport_1 = Uuid::new_v4().to_string();
+        let vertiport_2 = Uuid::new_v4().to_string();
+        let vertipad_1 = Uuid::new_v4().to_string();
+        let vertipad_2 = Uuid::new_v4().to_string();
+        let required_loading_time = Duration::try_seconds(30).unwrap();
+        let required_unloading_time = Duration::try_seconds(30).unwrap();
+        let arrival_jitter = Duration::try_minutes(10).unwrap();
+
+        let aircraft_availability = Availability {
+            vertiport_id: vertiport_1.clone(),
+            vertipad_id: vertipad_1.clone(),
+            timeslot: Timeslot::new(time_start - Duration::try_hours(1).unwrap(), time_end)
+                .unwrap(),
+        };
+
+        let distance_meters = Meters(50.0);
+        let flight_duration = estimate_flight_time_seconds(&distance_meters).unwrap();
+        let flight_window = Timeslot::new(time_start, time_end).unwrap();
+
+        let mut arrivals = vec![];
+        for vehicle_id in ["aircraft-1", "aircraft-2"] {
+            let flight_plan = flight_plan::Data {
+                origin_vertiport_id: Some(vertiport_1.clone()),
+                target_vertiport_id: Some(vertiport_2.clone()),
+                origin_vertipad_id: vertipad_1.clone(),
+                target_vertipad_id: vertipad_2.clone(),
+                vehicle_id: vehicle_id.to_string(),
+                path: Some(GeoLineString { points: vec![] }),
+                ..Default::default()
+            };
+
+            let itinerary = get_itinerary(
+                flight_plan,
+                &aircraft_availability,
+                &flight_duration,
+                &required_loading_time,
+                &required_unloading_time,
+                &flight_window,
+                &flight_window,
+                &arrival_jitter,
+                &deadhead_search(),
+                &LeadTimes::default(),
+                Utc::now(),
+                None,
+                clients,
+            )
+            .await
+            .unwrap();
+
+            assert_eq!(itinerary.len(), 1);
+            let arrival: DateTime<Utc> = itinerary[0].target_timeslot_start.clone().unwrap().into();
+            let earliest = ceil_to_second(time_start) + required_loading_time + flight_duration;
+            assert!(arrival >= earliest);
+            assert!(arrival <= earliest + arrival_jitter);
+            arrivals.push(arrival);
+        }
+
+        // Identical requests for different aircraft don't land together
+        assert_ne!(arrivals[0], arrivals[1]);
+    }
+
+    #[tokio::test]
+    #[cfg(feature = "stub_backends")]
+    async fn test_get_itinerary_deadhead_disabled() {
         let clients = get_clients().await;
         let time_start = Utc::now();
         let time_end = Utc::now() + Duration::try_seconds(1000).unwrap();
         let vertiport_1 = Uuid::new_v4().to_string();
+        let vertiport_2 = Uuid::new_v4().to_string();
         let vertiport_3 = Uuid::new_v4().to_string();
         let vertipad_1 = Uuid::new_v4().to_string();
         let vertipad_2 = Uuid::new_v4().to_string();
         let vehicle_id = Uuid::new_v4().to_string();
         let required_loading_time = Duration::try_seconds(30).unwrap();
         let required_unloading_time = Duration::try_seconds(30).unwrap();
+        let search = DeadheadSearch {
+            enabled: false,
+            ..deadhead_search()
+        };
 
-        let aircraft_availability = Availability {
+        let mut aircraft_availability = Availability {
             vertiport_id: vertiport_1.clone(),
             vertipad_id: vertipad_1.clone(),
             timeslot: Timeslot::new(time_start - Duration::try_seconds(1000).unwrap(), time_end)
                 .unwrap(),
         };
 
-        let distance_meters = 50.0;
+        let distance_meters = Meters(50.0);
         let flight_duration = estimate_flight_time_seconds(&distance_meters).unwrap();
         let flight_window = Timeslot::new(time_start, time_end).unwrap();
 
         let flight_plan = flight_plan::Data {
-            origin_vertiport_id: Some(vertiport_1.clone()),
-            target_vertiport_id: Some(vertiport_3.clone()),
+            origin_vertiport_id: Some(vertiport_3.clone()),
+            target_vertiport_id: Some(vertiport_2.clone()),
             origin_vertipad_id: vertipad_1.clone(),
             target_vertipad_id: vertipad_2.clone(),
             vehicle_id,
@@ -795,6 +1860,28 @@ mod tests {
             ..Default::default()
         };
 
+        // aircraft is parked elsewhere, would need a deadhead to the origin
+        let e = get_itinerary(
+            flight_plan.clone(),
+            &aircraft_availability,
+            &flight_duration,
+            &required_loading_time,
+            &required_unloading_time,
+            &flight_window,
+            &flight_window,
+            &Duration::zero(),
+            &search,
+            &LeadTimes::default(),
+            Utc::now(),
+            None,
+            clients,
+        )
+        .await
+        .unwrap_err();
+        assert_eq!(e, ItineraryError::ScheduleConflict);
+
+        // aircraft is already at the origin, no deadhead legs are produced
+        aircraft_availability.vertiport_id = vertiport_3.clone();
         let itinerary = get_itinerary(
             flight_plan,
             &aircraft_availability,
@@ -802,26 +1889,203 @@ mod tests {
             &required_loading_time,
             &required_unloading_time,
             &flight_window,
-            &clients,
+            &flight_window,
+            &Duration::zero(),
+            &search,
+            &LeadTimes::default(),
+            Utc::now(),
+            None,
+            clients,
         )
         .await
         .unwrap();
 
-        // 2 flight plans: flight to vertiport_3, deadhead to vertiport_1
-        assert_eq!(itinerary.len(), 2);
+        assert_eq!(itinerary.len(), 1);
         assert_eq!(
             itinerary[0].origin_vertiport_id.clone().unwrap(),
-            vertiport_1
+            vertiport_3
         );
-        assert_eq!(itinerary[0].origin_vertipad_id.clone(), vertipad_1);
         assert_eq!(
             itinerary[0].target_vertiport_id.clone().unwrap(),
-            vertiport_3
+            vertiport_2
         );
-        assert_eq!(itinerary[0].target_vertipad_id.clone(), vertipad_2);
+    }
 
-        assert_eq!(
-            itinerary[1].origin_vertiport_id.clone().unwrap(),
+    #[tokio::test]
+    #[cfg(feature = "stub_backends")]
+    async fn test_get_itinerary_valid_pre_deadhead() {
+        let clients = get_clients().await;
+        let time_start = Utc::now();
+        let time_end = Utc::now() + Duration::try_seconds(1000).unwrap();
+        let vertiport_1 = Uuid::new_v4().to_string();
+        let vertiport_3 = Uuid::new_v4().to_string();
+        let vertipad_1 = Uuid::new_v4().to_string();
+        let vertipad_2 = Uuid::new_v4().to_string();
+        let vehicle_id = Uuid::new_v4().to_string();
+        let required_loading_time = Duration::try_seconds(30).unwrap();
+        let required_unloading_time = Duration::try_seconds(30).unwrap();
+
+        let aircraft_availability = Availability {
+            vertiport_id: vertiport_1.clone(),
+            vertipad_id: vertipad_1.clone(),
+            timeslot: Timeslot::new(
+                time_start - Duration::try_seconds(1000).unwrap(),
+                time_end + Duration::try_seconds(1000).unwrap(),
+            )
+            .unwrap(),
+        };
+
+        let distance_meters = Meters(50.0);
+        let flight_duration = estimate_flight_time_seconds(&distance_meters).unwrap();
+        let flight_window = Timeslot::new(time_start, time_end).unwrap();
+
+        let flight_plan = flight_plan::Data {
+            origin_vertiport_id: Some(vertiport_3.clone()),
+            target_vertiport_id: Some(vertiport_1.clone()),
+            origin_vertipad_id: vertipad_1.clone(),
+            target_vertipad_id: vertipad_2.clone(),
+            vehicle_id,
+            path: Some(GeoLineString { points: vec![] }),
+            ..Default::default()
+        };
+
+        let itinerary = get_itinerary(
+            flight_plan,
+            &aircraft_availability,
+            &flight_duration,
+            &required_loading_time,
+            &required_unloading_time,
+            &flight_window,
+            &flight_window,
+            &Duration::zero(),
+            &deadhead_search(),
+            &LeadTimes::default(),
+            Utc::now(),
+            None,
+            clients,
+        )
+        .await
+        .unwrap();
+
+        // 2 flight plans: deadhead to vertiport_3, flight to vertiport_1
+        assert_eq!(itinerary.len(), 2);
+        assert_eq!(
+            itinerary[0].origin_vertiport_id.clone().unwrap(),
+            vertiport_1
+        );
+        assert_eq!(
+            itinerary[0].target_vertiport_id.clone().unwrap(),
+            vertiport_3
+        );
+        assert_eq!(itinerary[0].target_vertipad_id.clone(), vertipad_1);
+
+        assert_eq!(
+            itinerary[1].origin_vertiport_id.clone().unwrap(),
+            vertiport_3
+        );
+        assert_eq!(itinerary[1].origin_vertipad_id.clone(), vertipad_1);
+        assert_eq!(
+            itinerary[1].target_vertiport_id.clone().unwrap(),
+            vertiport_1
+        );
+        assert_eq!(itinerary[1].target_vertipad_id.clone(), vertipad_2);
+
+        // Land at earliest possible time
+        assert_eq!(
+            // deadhead flight
+            itinerary[0].target_timeslot_end.clone().unwrap(),
+            ceil_to_second(time_start).into() // deadhead flight should arrive as early as possible to time_start
+        );
+        assert_eq!(
+            itinerary[1].origin_timeslot_start.clone().unwrap(),
+            ceil_to_second(time_start).into()
+        );
+        assert_eq!(
+            itinerary[1].origin_timeslot_end.clone().unwrap(),
+            (ceil_to_second(time_start) + required_loading_time).into()
+        );
+        assert_eq!(
+            itinerary[1].target_timeslot_start.clone().unwrap(),
+            (ceil_to_second(time_start) + required_loading_time + flight_duration).into()
+        );
+        assert_eq!(
+            itinerary[1].target_timeslot_end.clone().unwrap(),
+            (ceil_to_second(time_start)
+                + required_loading_time
+                + flight_duration
+                + required_unloading_time)
+                .into()
+        );
+    }
+
+    #[tokio::test]
+    #[cfg(feature = "stub_backends")]
+    async fn test_get_itinerary_valid_post_deadhead() {
+        let clients = get_clients().await;
+        let time_start = Utc::now();
+        let time_end = Utc::now() + Duration::try_seconds(1000).unwrap();
+        let vertiport_1 = Uuid::new_v4().to_string();
+        let vertiport_3 = Uuid::new_v4().to_string();
+        let vertipad_1 = Uuid::new_v4().to_string();
+        let vertipad_2 = Uuid::new_v4().to_string();
+        let vehicle_id = Uuid::new_v4().to_string();
+        let required_loading_time = Duration::try_seconds(30).unwrap();
+        let required_unloading_time = Duration::try_seconds(30).unwrap();
+
+        let aircraft_availability = Availability {
+            vertiport_id: vertiport_1.clone(),
+            vertipad_id: vertipad_1.clone(),
+            timeslot: Timeslot::new(time_start - Duration::try_seconds(1000).unwrap(), time_end)
+                .unwrap(),
+        };
+
+        let distance_meters = Meters(50.0);
+        let flight_duration = estimate_flight_time_seconds(&distance_meters).unwrap();
+        let flight_window = Timeslot::new(time_start, time_end).unwrap();
+
+        let flight_plan = flight_plan::Data {
+            origin_vertiport_id: Some(vertiport_1.clone()),
+            target_vertiport_id: Some(vertiport_3.clone()),
+            origin_vertipad_id: vertipad_1.clone(),
+            target_vertipad_id: vertipad_2.clone(),
+            vehicle_id,
+            path: Some(GeoLineString { points: vec![] }),
+            ..Default::default()
+        };
+
+        let itinerary = get_itinerary(
+            flight_plan,
+            &aircraft_availability,
+            &flight_duration,
+            &required_loading_time,
+            &required_unloading_time,
+            &flight_window,
+            &flight_window,
+            &Duration::zero(),
+            &deadhead_search(),
+            &LeadTimes::default(),
+            Utc::now(),
+            None,
+            clients,
+        )
+        .await
+        .unwrap();
+
+        // 2 flight plans: flight to vertiport_3, deadhead to vertiport_1
+        assert_eq!(itinerary.len(), 2);
+        assert_eq!(
+            itinerary[0].origin_vertiport_id.clone().unwrap(),
+            vertiport_1
+        );
+        assert_eq!(itinerary[0].origin_vertipad_id.clone(), vertipad_1);
+        assert_eq!(
+            itinerary[0].target_vertiport_id.clone().unwrap(),
+            vertiport_3
+        );
+        assert_eq!(itinerary[0].target_vertipad_id.clone(), vertipad_2);
+
+        assert_eq!(
+            itinerary[1].origin_vertiport_id.clone().unwrap(),
             vertiport_3
         );
         assert_eq!(itinerary[1].origin_vertipad_id.clone(), vertipad_2);
@@ -833,23 +2097,31 @@ mod tests {
         // Land at earliest possible time
         assert_eq!(
             itinerary[0].origin_timeslot_start.clone().unwrap(),
-            time_start.into()
+            ceil_to_second(time_start).into()
         );
         assert_eq!(
             itinerary[0].origin_timeslot_end.clone().unwrap(),
-            (time_start + required_loading_time).into()
+            (ceil_to_second(time_start) + required_loading_time).into()
         );
         assert_eq!(
             itinerary[0].target_timeslot_start.clone().unwrap(),
-            (time_start + required_loading_time + flight_duration).into()
+            (ceil_to_second(time_start) + required_loading_time + flight_duration).into()
         );
         assert_eq!(
             itinerary[0].target_timeslot_end.clone().unwrap(),
-            (time_start + required_loading_time + flight_duration + required_unloading_time).into()
+            (ceil_to_second(time_start)
+                + required_loading_time
+                + flight_duration
+                + required_unloading_time)
+                .into()
         );
         assert_eq!(
             itinerary[1].origin_timeslot_start.clone().unwrap(),
-            (time_start + required_loading_time + flight_duration + required_unloading_time).into()
+            (ceil_to_second(time_start)
+                + required_loading_time
+                + flight_duration
+                + required_unloading_time)
+                .into()
         );
     }
 
@@ -882,7 +2154,7 @@ mod tests {
             .unwrap(),
         };
 
-        let distance_meters = 50.0;
+        let distance_meters = Meters(50.0);
         let flight_duration = estimate_flight_time_seconds(&distance_meters).unwrap();
         let flight_window = Timeslot::new(time_start, time_end).unwrap();
 
@@ -903,7 +2175,13 @@ mod tests {
             &required_loading_time,
             &required_unloading_time,
             &flight_window,
-            &clients,
+            &flight_window,
+            &Duration::zero(),
+            &deadhead_search(),
+            &LeadTimes::default(),
+            Utc::now(),
+            None,
+            clients,
         )
         .await
         .unwrap();
@@ -940,171 +2218,1499 @@ mod tests {
             itinerary[2].target_vertiport_id.clone().unwrap(),
             vertiport_1
         );
-
-        // First itinerary for aircraft leaves at earliest aircraft convenience
+
+        // First itinerary for aircraft leaves at earliest aircraft convenience
+        assert_eq!(
+            itinerary[0].origin_timeslot_start.clone().unwrap(),
+            ceil_to_second(aircraft_availability.timeslot.time_start()).into()
+        );
+    }
+
+    #[tokio::test]
+    #[cfg(feature = "stub_backends")]
+    async fn test_get_itinerary_valid_incompatible_flight_window() {
+        let clients = get_clients().await;
+        let time_start = Utc::now();
+        let time_end = Utc::now() + Duration::try_hours(1).unwrap();
+        let vertiport_1 = Uuid::new_v4().to_string();
+        let vertiport_2 = Uuid::new_v4().to_string();
+        let vertiport_3 = Uuid::new_v4().to_string();
+        let vertipad_1 = Uuid::new_v4().to_string();
+        let vertipad_2 = Uuid::new_v4().to_string();
+        let vehicle_id = Uuid::new_v4().to_string();
+        let required_loading_time = Duration::try_seconds(30).unwrap();
+        let required_unloading_time = Duration::try_seconds(30).unwrap();
+
+        //                                       |    flight window    |
+        //  |     takeoff and land time window     |
+        //
+
+        let aircraft_availability = Availability {
+            vertiport_id: vertiport_1.clone(),
+            vertipad_id: vertipad_1.clone(),
+            timeslot: Timeslot::new(
+                time_end - Duration::try_seconds(30).unwrap(),
+                time_end + Duration::try_minutes(20).unwrap(),
+            )
+            .unwrap(),
+        };
+
+        let distance_meters = Meters(1000.0); // too far to fly
+        let flight_duration = estimate_flight_time_seconds(&distance_meters).unwrap();
+        let flight_window = Timeslot::new(time_start, time_end).unwrap();
+
+        let flight_plan = flight_plan::Data {
+            origin_vertiport_id: Some(vertiport_3.clone()),
+            target_vertiport_id: Some(vertiport_2.clone()),
+            origin_vertipad_id: vertipad_1.clone(),
+            target_vertipad_id: vertipad_2.clone(),
+            vehicle_id,
+            path: Some(GeoLineString { points: vec![] }),
+            ..Default::default()
+        };
+
+        let e = get_itinerary(
+            flight_plan,
+            &aircraft_availability,
+            &flight_duration,
+            &required_loading_time,
+            &required_unloading_time,
+            &flight_window,
+            &flight_window,
+            &Duration::zero(),
+            &deadhead_search(),
+            &LeadTimes::default(),
+            Utc::now(),
+            None,
+            clients,
+        )
+        .await
+        .unwrap_err();
+        assert_eq!(e, ItineraryError::ScheduleConflict);
+    }
+
+    #[tokio::test]
+    #[cfg(feature = "stub_backends")]
+    async fn test_calculate_itineraries() {
+        let clients = get_clients().await;
+        let time_start = Utc::now();
+        let time_end = Utc::now() + Duration::try_seconds(1000).unwrap();
+        let vertiport_1 = Uuid::new_v4().to_string();
+        let vertiport_2 = Uuid::new_v4().to_string();
+        let vertiport_3 = Uuid::new_v4().to_string();
+        let vertipad_1 = Uuid::new_v4().to_string();
+        let vertipad_3 = Uuid::new_v4().to_string();
+        let vehicle_1 = Uuid::new_v4().to_string();
+        let vehicle_2 = Uuid::new_v4().to_string();
+        let required_loading_time = Duration::try_seconds(30).unwrap();
+        let required_unloading_time = Duration::try_seconds(30).unwrap();
+
+        let availabilities = HashMap::from([
+            (
+                vehicle_1.clone(),
+                vec![Availability {
+                    vertiport_id: vertiport_1.clone(),
+                    vertipad_id: vertipad_1.clone(),
+                    timeslot: Timeslot::new(
+                        time_start - Duration::try_hours(1).unwrap(),
+                        time_end + Duration::try_hours(1).unwrap(),
+                    )
+                    .unwrap(),
+                }],
+            ),
+            (
+                vehicle_2.clone(),
+                vec![Availability {
+                    vertiport_id: vertiport_3.clone(),
+                    vertipad_id: vertipad_3.clone(),
+                    timeslot: Timeslot::new(
+                        time_end + Duration::try_hours(1).unwrap(),
+                        time_end + Duration::try_hours(2).unwrap(),
+                    )
+                    .unwrap(),
+                }],
+            ),
+        ]);
+
+        let distance_meters = Meters(50.0);
+        let flight_duration = estimate_flight_time_seconds(&distance_meters).unwrap();
+        let alternative_paths = vec![(
+            vec![PointZ {
+                latitude: 52.0,
+                longitude: 4.0,
+                altitude_meters: 100.0,
+            }],
+            Meters(75.0),
+        )];
+        let timeslot_pairs = vec![
+            TimeslotPair {
+                origin_vertiport_id: vertiport_1.clone(),
+                origin_vertipad_id: vertipad_1.clone(),
+                origin_timeslot: Timeslot::new(time_start.clone(), time_end.clone()).unwrap(),
+                target_vertiport_id: vertiport_2.clone(),
+                target_vertipad_id: vertiport_2.clone(),
+                target_timeslot: Timeslot::new(
+                    time_start + flight_duration,
+                    time_end + flight_duration,
+                )
+                .unwrap(),
+                path: vec![],
+                distance_meters,
+                alternative_paths: alternative_paths.clone(),
+            },
+            TimeslotPair {
+                origin_vertiport_id: vertiport_1.clone(),
+                origin_vertipad_id: vertipad_1.clone(),
+                origin_timeslot: Timeslot::new(
+                    time_end + Duration::try_hours(1).unwrap(),
+                    time_end + Duration::try_hours(2).unwrap(),
+                )
+                .unwrap(),
+                target_vertiport_id: vertiport_2.clone(),
+                target_vertipad_id: vertiport_2.clone(),
+                target_timeslot: Timeslot::new(
+                    time_end + Duration::try_hours(1).unwrap() + flight_duration,
+                    time_end + Duration::try_hours(2).unwrap() + flight_duration,
+                )
+                .unwrap(),
+                path: vec![],
+                distance_meters,
+                alternative_paths: alternative_paths.clone(),
+            },
+        ];
+
+        let ground_time = GroundTime {
+            loading: required_loading_time,
+            unloading: required_unloading_time,
+        };
+
+        let itineraries = calculate_itineraries(
+            &ground_time,
+            &HashMap::new(),
+            &HashMap::new(),
+            &timeslot_pairs,
+            &availabilities,
+            &deadhead_search(),
+            &Duration::zero(),
+            &LeadTimes::default(),
+            &SystemClock,
+            clients,
+        )
+        .await
+        .unwrap();
+
+        // Expect two matches
+        println!("{:?}", itineraries);
+        for (i, itinerary) in itineraries.iter().enumerate() {
+            println!("\n\n----- Itinerary {}", i);
+            for (fp_i, fp) in itinerary.flight_plans.iter().enumerate() {
+                println!("{}: {:?}\n", fp_i, fp);
+            }
+        }
+
+        assert_eq!(itineraries.len(), 2);
+        for itinerary in itineraries {
+            assert_eq!(itinerary.alternative_paths, alternative_paths);
+        }
+    }
+
+    #[tokio::test]
+    #[cfg(feature = "stub_backends")]
+    async fn test_calculate_itineraries_vertiport_lead_time() {
+        use crate::test_util::MockClock;
+
+        let clients = get_clients().await;
+
+        // stopped on a whole second, so departures aren't rounded
+        let clock = MockClock::new(ceil_to_second(Utc::now()));
+        let time_start = clock.now();
+        let time_end = time_start + Duration::try_seconds(1000).unwrap();
+        let vertiport_1 = Uuid::new_v4().to_string();
+        let vertiport_2 = Uuid::new_v4().to_string();
+        let vertipad_1 = Uuid::new_v4().to_string();
+        let vertipad_2 = Uuid::new_v4().to_string();
+        let vehicle_id = Uuid::new_v4().to_string();
+
+        let ground_time = GroundTime {
+            loading: Duration::try_seconds(30).unwrap(),
+            unloading: Duration::try_seconds(30).unwrap(),
+        };
+
+        let availabilities = HashMap::from([(
+            vehicle_id,
+            vec![Availability {
+                vertiport_id: vertiport_1.clone(),
+                vertipad_id: vertipad_1.clone(),
+                timeslot: Timeslot::new(
+                    time_start - Duration::try_hours(1).unwrap(),
+                    time_end + Duration::try_hours(1).unwrap(),
+                )
+                .unwrap(),
+            }],
+        )]);
+
+        let distance_meters = Meters(50.0);
+        let flight_duration = estimate_flight_time_seconds(&distance_meters).unwrap();
+        let timeslot_pairs = vec![TimeslotPair {
+            origin_vertiport_id: vertiport_1.clone(),
+            origin_vertipad_id: vertipad_1,
+            origin_timeslot: Timeslot::new(time_start, time_end).unwrap(),
+            target_vertiport_id: vertiport_2,
+            target_vertipad_id: vertipad_2,
+            target_timeslot: Timeslot::new(
+                time_start + flight_duration,
+                time_end + flight_duration,
+            )
+            .unwrap(),
+            path: vec![],
+            distance_meters,
+            alternative_paths: vec![],
+        }];
+
+        let get_departure = |lead_times: LeadTimes| {
+            let timeslot_pairs = &timeslot_pairs;
+            let availabilities = &availabilities;
+            let clock = &clock;
+            async move {
+                let itineraries = calculate_itineraries(
+                    &ground_time,
+                    &HashMap::new(),
+                    &HashMap::new(),
+                    timeslot_pairs,
+                    availabilities,
+                    &deadhead_search(),
+                    &Duration::zero(),
+                    &lead_times,
+                    clock,
+                    clients,
+                )
+                .await
+                .unwrap();
+
+                itineraries.first().map(|itinerary| {
+                    DateTime::<Utc>::from(
+                        itinerary.flight_plans[0]
+                            .origin_timeslot_start
+                            .clone()
+                            .unwrap(),
+                    )
+                })
+            }
+        };
+
+        // without a lead time, the flight leaves right away
+        let departure = get_departure(LeadTimes::default()).await.unwrap();
+        assert_eq!(departure, time_start);
+
+        // the origin needs 10 minutes of notice
+        let lead = Duration::try_minutes(10).unwrap();
+        let lead_times = LeadTimes {
+            default: Duration::zero(),
+            vertiports: HashMap::from([(vertiport_1, lead)]),
+        };
+        let departure = get_departure(lead_times.clone()).await.unwrap();
+        assert_eq!(departure, time_start + lead);
+
+        // counted from the time of the clock, not of the system
+        clock.advance(Duration::try_minutes(1).unwrap());
+        let departure = get_departure(lead_times).await.unwrap();
+        assert_eq!(
+            departure,
+            time_start + lead + Duration::try_minutes(1).unwrap()
+        );
+
+        // every vertiport needs more notice than the window allows
+        let departure = get_departure(LeadTimes {
+            default: Duration::try_hours(1).unwrap(),
+            vertiports: HashMap::new(),
+        })
+        .await;
+        assert!(departure.is_none());
+    }
+
+    #[tokio::test]
+    #[cfg(feature = "stub_backends")]
+    async fn test_calculate_itineraries_latest_arrival() {
+        let clients = get_clients().await;
+
+        // a fraction of a second, so departures get rounded up
+        let time_start = ceil_to_second(Utc::now()) + Duration::try_milliseconds(500).unwrap();
+        let vertiport_1 = Uuid::new_v4().to_string();
+        let vertiport_2 = Uuid::new_v4().to_string();
+        let vertipad_1 = Uuid::new_v4().to_string();
+        let vertipad_2 = Uuid::new_v4().to_string();
+        let vehicle_id = Uuid::new_v4().to_string();
+
+        let ground_time = GroundTime {
+            loading: Duration::try_seconds(30).unwrap(),
+            unloading: Duration::try_seconds(30).unwrap(),
+        };
+
+        let availabilities = HashMap::from([(
+            vehicle_id,
+            vec![Availability {
+                vertiport_id: vertiport_1.clone(),
+                vertipad_id: vertipad_1.clone(),
+                timeslot: Timeslot::new(
+                    time_start - Duration::try_hours(1).unwrap(),
+                    time_start + Duration::try_hours(2).unwrap(),
+                )
+                .unwrap(),
+            }],
+        )]);
+
+        let distance_meters = Meters(50.0);
+        let flight_duration = estimate_blocked_flight_time(&distance_meters)
+            .await
+            .unwrap();
+        let total_duration = ground_time.loading + flight_duration + ground_time.unloading;
+
+        // the window only fits the flight before its departure is rounded,
+        //  or has a few seconds to spare
+        for spare_seconds in [0, 1, 5, 60] {
+            let latest_arrival =
+                time_start + total_duration + Duration::try_seconds(spare_seconds).unwrap();
+            let timeslot_pairs = vec![TimeslotPair {
+                origin_vertiport_id: vertiport_1.clone(),
+                origin_vertipad_id: vertipad_1.clone(),
+                origin_timeslot: Timeslot::new(time_start, latest_arrival).unwrap(),
+                target_vertiport_id: vertiport_2.clone(),
+                target_vertipad_id: vertipad_2.clone(),
+                target_timeslot: Timeslot::new(time_start, latest_arrival).unwrap(),
+                path: vec![],
+                distance_meters,
+                alternative_paths: vec![],
+            }];
+
+            let itineraries = calculate_itineraries(
+                &ground_time,
+                &HashMap::new(),
+                &HashMap::new(),
+                &timeslot_pairs,
+                &availabilities,
+                &deadhead_search(),
+                &Duration::try_seconds(30).unwrap(),
+                &LeadTimes::default(),
+                &SystemClock,
+                clients,
+            )
+            .await
+            .unwrap();
+
+            // rounding the departure up would land it too late
+            if spare_seconds == 0 {
+                assert!(itineraries.is_empty());
+            }
+
+            for itinerary in itineraries {
+                for flight_plan in itinerary.flight_plans {
+                    let arrival: DateTime<Utc> = flight_plan.target_timeslot_end.unwrap().into();
+                    assert!(arrival <= latest_arrival);
+                }
+            }
+        }
+    }
+
+    #[tokio::test]
+    #[cfg(feature = "stub_backends")]
+    async fn test_calculate_itineraries_slow_loading_aircraft() {
+        let clients = get_clients().await;
+        let time_start = Utc::now();
+        let time_end = Utc::now() + Duration::try_seconds(1000).unwrap();
+        let vertiport_1 = Uuid::new_v4().to_string();
+        let vertiport_2 = Uuid::new_v4().to_string();
+        let vertipad_1 = Uuid::new_v4().to_string();
+        let vertipad_2 = Uuid::new_v4().to_string();
+        let vehicle_1 = Uuid::new_v4().to_string();
+        let vehicle_2 = Uuid::new_v4().to_string();
+
+        let default_ground_time = GroundTime {
+            loading: Duration::try_seconds(30).unwrap(),
+            unloading: Duration::try_seconds(30).unwrap(),
+        };
+
+        // vehicle_2 takes four times as long to load and unload
+        let slow_ground_time = GroundTime {
+            loading: Duration::try_seconds(120).unwrap(),
+            unloading: Duration::try_seconds(120).unwrap(),
+        };
+        let aircraft_ground_times = HashMap::from([(vehicle_2.clone(), slow_ground_time)]);
+
+        let availability = Availability {
+            vertiport_id: vertiport_1.clone(),
+            vertipad_id: vertipad_1.clone(),
+            timeslot: Timeslot::new(
+                time_start - Duration::try_hours(1).unwrap(),
+                time_end + Duration::try_hours(1).unwrap(),
+            )
+            .unwrap(),
+        };
+
+        let availabilities = HashMap::from([
+            (vehicle_1.clone(), vec![availability.clone()]),
+            (vehicle_2.clone(), vec![availability]),
+        ]);
+
+        let distance_meters = Meters(50.0);
+        let flight_duration = estimate_flight_time_seconds(&distance_meters).unwrap();
+        let timeslot_pairs = vec![TimeslotPair {
+            origin_vertiport_id: vertiport_1.clone(),
+            origin_vertipad_id: vertipad_1.clone(),
+            origin_timeslot: Timeslot::new(time_start, time_end).unwrap(),
+            target_vertiport_id: vertiport_2.clone(),
+            target_vertipad_id: vertipad_2.clone(),
+            target_timeslot: Timeslot::new(
+                time_start + flight_duration,
+                time_end + flight_duration,
+            )
+            .unwrap(),
+            path: vec![],
+            distance_meters,
+            alternative_paths: vec![],
+        }];
+
+        let itineraries = calculate_itineraries(
+            &default_ground_time,
+            &aircraft_ground_times,
+            &HashMap::new(),
+            &timeslot_pairs,
+            &availabilities,
+            &DeadheadSearch {
+                enabled: false,
+                ..deadhead_search()
+            },
+            &Duration::zero(),
+            &LeadTimes::default(),
+            &SystemClock,
+            clients,
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(itineraries.len(), 2);
+        for itinerary in itineraries {
+            assert_eq!(itinerary.flight_plans.len(), 1);
+            let flight_plan = &itinerary.flight_plans[0];
+            let expected = if flight_plan.vehicle_id == vehicle_2 {
+                slow_ground_time
+            } else {
+                default_ground_time
+            };
+
+            let origin_start: DateTime<Utc> =
+                flight_plan.origin_timeslot_start.clone().unwrap().into();
+            let origin_end: DateTime<Utc> = flight_plan.origin_timeslot_end.clone().unwrap().into();
+            let target_start: DateTime<Utc> =
+                flight_plan.target_timeslot_start.clone().unwrap().into();
+            let target_end: DateTime<Utc> = flight_plan.target_timeslot_end.clone().unwrap().into();
+
+            assert_eq!(origin_end - origin_start, expected.loading);
+            assert_eq!(target_end - target_start, expected.unloading);
+        }
+    }
+
+    #[tokio::test]
+    #[cfg(feature = "stub_backends")]
+    async fn test_calculate_itineraries_too_many_deadheads() {
+        let clients = get_clients().await;
+        let time_start = Utc::now();
+        let time_end = Utc::now() + Duration::try_seconds(1000).unwrap();
+        let vertiport_1 = Uuid::new_v4().to_string();
+        let vertiport_2 = Uuid::new_v4().to_string();
+        let vertiport_3 = Uuid::new_v4().to_string();
+        let vertipad_1 = Uuid::new_v4().to_string();
+        let vertipad_2 = Uuid::new_v4().to_string();
+        let vertipad_3 = Uuid::new_v4().to_string();
+
+        // parked at neither vertiport, repositioned there and back
+        let availabilities = HashMap::from([(
+            Uuid::new_v4().to_string(),
+            vec![Availability {
+                vertiport_id: vertiport_3.clone(),
+                vertipad_id: vertipad_3.clone(),
+                timeslot: Timeslot::new(
+                    time_start - Duration::try_hours(1).unwrap(),
+                    time_end + Duration::try_hours(2).unwrap(),
+                )
+                .unwrap(),
+            }],
+        )]);
+
+        let distance_meters = Meters(50.0);
+        let flight_duration = estimate_flight_time_seconds(&distance_meters).unwrap();
+        let timeslot_pairs = vec![TimeslotPair {
+            origin_vertiport_id: vertiport_1.clone(),
+            origin_vertipad_id: vertipad_1.clone(),
+            origin_timeslot: Timeslot::new(time_start, time_end).unwrap(),
+            target_vertiport_id: vertiport_2.clone(),
+            target_vertipad_id: vertipad_2.clone(),
+            target_timeslot: Timeslot::new(
+                time_start + flight_duration,
+                time_end + flight_duration,
+            )
+            .unwrap(),
+            path: vec![],
+            distance_meters,
+            alternative_paths: vec![],
+        }];
+
+        let ground_time = GroundTime {
+            loading: Duration::try_seconds(30).unwrap(),
+            unloading: Duration::try_seconds(30).unwrap(),
+        };
+
+        let search = |max_legs: u32| {
+            let timeslot_pairs = timeslot_pairs.clone();
+            let availabilities = availabilities.clone();
+            async move {
+                calculate_itineraries(
+                    &ground_time,
+                    &HashMap::new(),
+                    &HashMap::new(),
+                    &timeslot_pairs,
+                    &availabilities,
+                    &DeadheadSearch {
+                        max_legs,
+                        ..deadhead_search()
+                    },
+                    &Duration::zero(),
+                    &LeadTimes::default(),
+                    &SystemClock,
+                    clients,
+                )
+                .await
+            }
+        };
+
+        // two deadhead flights are allowed
+        let itineraries = search(2).await.unwrap();
+        assert_eq!(itineraries.len(), 1);
+        assert_eq!(itineraries[0].flight_plans.len(), 3);
+
+        // rejected with a single deadhead flight allowed
+        let e = search(1).await.unwrap_err();
+        assert_eq!(e, ItineraryError::TooManyDeadheads);
+    }
+
+    #[tokio::test]
+    #[cfg(feature = "stub_backends")]
+    async fn test_calculate_itineraries_pad_capacity_limited() {
+        let clients = get_clients().await;
+        let time_start = Utc::now();
+        let time_end = Utc::now() + Duration::try_seconds(1000).unwrap();
+        let vertiport_1 = Uuid::new_v4().to_string();
+        let vertiport_2 = Uuid::new_v4().to_string();
+        let vertipad_1 = Uuid::new_v4().to_string();
+        let vertipad_2 = Uuid::new_v4().to_string();
+
+        // an idle aircraft parked at the origin, a single open pair of vertipads
+        let availabilities = HashMap::from([(
+            Uuid::new_v4().to_string(),
+            vec![Availability {
+                vertiport_id: vertiport_1.clone(),
+                vertipad_id: vertipad_1.clone(),
+                timeslot: Timeslot::new(
+                    time_start - Duration::try_hours(1).unwrap(),
+                    time_end + Duration::try_hours(2).unwrap(),
+                )
+                .unwrap(),
+            }],
+        )]);
+
+        let distance_meters = Meters(50.0);
+        let flight_duration = estimate_flight_time_seconds(&distance_meters).unwrap();
+        let timeslot_pairs = vec![TimeslotPair {
+            origin_vertiport_id: vertiport_1.clone(),
+            origin_vertipad_id: vertipad_1.clone(),
+            origin_timeslot: Timeslot::new(time_start, time_end).unwrap(),
+            target_vertiport_id: vertiport_2.clone(),
+            target_vertipad_id: vertipad_2.clone(),
+            target_timeslot: Timeslot::new(
+                time_start + flight_duration,
+                time_end + flight_duration,
+            )
+            .unwrap(),
+            path: vec![],
+            distance_meters,
+            alternative_paths: vec![],
+        }];
+
+        let ground_time = GroundTime {
+            loading: Duration::try_seconds(30).unwrap(),
+            unloading: Duration::try_seconds(30).unwrap(),
+        };
+
+        let (itineraries, rejections) = calculate_itineraries_with_rejections(
+            &ground_time,
+            &HashMap::new(),
+            &HashMap::new(),
+            &timeslot_pairs,
+            &availabilities,
+            &deadhead_search(),
+            &Duration::zero(),
+            &LeadTimes::default(),
+            &SystemClock,
+            None,
+            0,
+            None,
+            clients,
+        )
+        .await
+        .unwrap();
+
+        // fewer options than requested, none rejected
+        assert_eq!(itineraries.len(), 1);
+        assert_eq!(rejections.timeslot_pairs, 1);
+        assert_eq!(
+            rejections.get_limiting_factor(itineraries.len(), 0),
+            Some(LimitingFactor::PadCapacity)
+        );
+    }
+
+    /// Answers without error but without any path either
+    struct EmptyRouter;
+
+    #[tonic::async_trait]
+    impl PathFinder for EmptyRouter {
+        async fn best_path(
+            &self,
+            _request: &BestPathRequest,
+        ) -> Result<Vec<(Vec<PointZ>, Meters)>, BestPathError> {
+            Ok(vec![])
+        }
+    }
+
+    #[tokio::test]
+    async fn test_deadhead_helper_no_paths() {
+        let now = Utc::now();
+        let args = DeadheadHelperArgs {
+            origin_vertiport_id: "origin",
+            origin_vertipad_id: "origin_pad",
+            target_vertiport_id: "target",
+            target_vertipad_id: "target_pad",
+            vehicle_id: "vehicle",
+            aircraft_earliest: now,
+            vertipad_earliest: now,
+            arrival_latest: now + Duration::try_hours(1).unwrap(),
+            arrival_earliest: None,
+            required_loading_time: Duration::try_seconds(60).unwrap(),
+            required_unloading_time: Duration::try_seconds(60).unwrap(),
+            search: DeadheadSearch {
+                enabled: true,
+                step: Duration::try_minutes(5).unwrap(),
+                attempts: 3,
+                max_legs: 0,
+                return_policy: ReturnPolicy::Strict,
+                parking: None,
+            },
+            cruise_altitude_meters: None,
+        };
+
+        // treated as no path found rather than panicking
+        let e = deadhead_helper(&EmptyRouter, args).await.unwrap_err();
+        assert_eq!(e, ItineraryError::NoPathFound);
+    }
+
+    /// Finds no path for departures before `opens_at`, counting requests
+    struct BlockedRouter {
+        opens_at: DateTime<Utc>,
+        requests: std::sync::atomic::AtomicUsize,
+    }
+
+    #[tonic::async_trait]
+    impl PathFinder for BlockedRouter {
+        async fn best_path(
+            &self,
+            request: &BestPathRequest,
+        ) -> Result<Vec<(Vec<PointZ>, Meters)>, BestPathError> {
+            self.requests
+                .fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+
+            let departure: DateTime<Utc> = request.time_start.clone().unwrap().into();
+            if departure < self.opens_at {
+                return Err(BestPathError::NoPathFound);
+            }
+
+            let point = |latitude: f64| PointZ {
+                latitude,
+                longitude: 4.0,
+                altitude_meters: 100.0,
+            };
+
+            Ok(vec![(vec![point(52.0), point(52.01)], Meters(1112.0))])
+        }
+    }
+
+    #[tokio::test]
+    async fn test_deadhead_helper_retries() {
+        let now = Utc::now();
+        let args = |attempts: u32| DeadheadHelperArgs {
+            origin_vertiport_id: "origin",
+            origin_vertipad_id: "origin_pad",
+            target_vertiport_id: "target",
+            target_vertipad_id: "target_pad",
+            vehicle_id: "vehicle",
+            aircraft_earliest: now,
+            vertipad_earliest: now,
+            arrival_latest: now + Duration::try_hours(2).unwrap(),
+            arrival_earliest: None,
+            required_loading_time: Duration::try_seconds(60).unwrap(),
+            required_unloading_time: Duration::try_seconds(60).unwrap(),
+            search: DeadheadSearch {
+                enabled: true,
+                step: Duration::try_minutes(10).unwrap(),
+                attempts,
+                max_legs: 0,
+                return_policy: ReturnPolicy::Strict,
+                parking: None,
+            },
+            cruise_altitude_meters: None,
+        };
+
+        // path only opens up 25 minutes after the earliest departure
+        let router = |opens_at| BlockedRouter {
+            opens_at,
+            requests: std::sync::atomic::AtomicUsize::new(0),
+        };
+
+        // departures at 0, 10 and 20 minutes are all blocked
+        let blocked = router(now + Duration::try_minutes(25).unwrap());
+        let e = deadhead_helper(&blocked, args(3)).await.unwrap_err();
+        assert_eq!(e, ItineraryError::NoPathFound);
+        assert_eq!(
+            blocked.requests.load(std::sync::atomic::Ordering::Relaxed),
+            3
+        );
+
+        // the fourth attempt, at 30 minutes, finds the path
+        let blocked = router(now + Duration::try_minutes(25).unwrap());
+        let data = deadhead_helper(&blocked, args(6)).await.unwrap();
+        assert_eq!(
+            blocked.requests.load(std::sync::atomic::Ordering::Relaxed),
+            4
+        );
+
+        let departure: DateTime<Utc> = data.origin_timeslot_start.unwrap().into();
+        assert!(departure >= now + Duration::try_minutes(30).unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_deadhead_helper_arrival_lead_time() {
+        let now = Utc::now();
+        let args = |arrival_earliest| DeadheadHelperArgs {
+            origin_vertiport_id: "origin",
+            origin_vertipad_id: "origin_pad",
+            target_vertiport_id: "target",
+            target_vertipad_id: "target_pad",
+            vehicle_id: "vehicle",
+            aircraft_earliest: now,
+            vertipad_earliest: now,
+            arrival_latest: now + Duration::try_hours(2).unwrap(),
+            arrival_earliest,
+            required_loading_time: Duration::zero(),
+            required_unloading_time: Duration::zero(),
+            search: deadhead_search(),
+            cruise_altitude_meters: None,
+        };
+
+        let router = BlockedRouter {
+            opens_at: now,
+            requests: std::sync::atomic::AtomicUsize::new(0),
+        };
+
+        // without notice the aircraft lands right away
+        let data = deadhead_helper(&router, args(None)).await.unwrap();
+        let arrival: DateTime<Utc> = data.target_timeslot_start.unwrap().into();
+        assert!(arrival < now + Duration::try_minutes(30).unwrap());
+
+        // the target needs an hour of notice, the aircraft leaves later
+        let notice = now + Duration::try_hours(1).unwrap();
+        let data = deadhead_helper(&router, args(Some(notice))).await.unwrap();
+        let arrival: DateTime<Utc> = data.target_timeslot_start.unwrap().into();
+        assert!(arrival >= notice);
+
+        // the notice runs past the deadline
+        let notice = now + Duration::try_hours(3).unwrap();
+        let e = deadhead_helper(&router, args(Some(notice)))
+            .await
+            .unwrap_err();
+        assert_eq!(e, ItineraryError::ScheduleConflict);
+    }
+
+    /// Answers like a straight line router, slowly for paths out of
+    ///  the given vertiports
+    struct SlowRouter {
+        router: crate::router::simulate::StraightLineRouter,
+        slow_vertiport_ids: HashSet<String>,
+        delay: std::time::Duration,
+    }
+
+    #[tonic::async_trait]
+    impl PathFinder for SlowRouter {
+        async fn best_path(
+            &self,
+            request: &BestPathRequest,
+        ) -> Result<Vec<(Vec<PointZ>, Meters)>, BestPathError> {
+            if self.slow_vertiport_ids.contains(&request.origin_identifier) {
+                tokio::time::sleep(self.delay).await;
+            }
+
+            self.router.best_path(request).await
+        }
+    }
+
+    #[tokio::test]
+    async fn test_calculate_itineraries_budget_exceeded() {
+        let time_start = Utc::now();
+        let time_end = time_start + Duration::try_hours(1).unwrap();
+        let vertiport_1 = Uuid::new_v4().to_string();
+        let vertiport_2 = Uuid::new_v4().to_string();
+        let vertiport_3 = Uuid::new_v4().to_string();
+        let vertipad_1 = Uuid::new_v4().to_string();
+        let vertipad_2 = Uuid::new_v4().to_string();
+        let vertipad_3 = Uuid::new_v4().to_string();
+
+        let location = |latitude: f64| PointZ {
+            latitude,
+            longitude: 4.0,
+            altitude_meters: 100.0,
+        };
+
+        // paths out of the third vertiport take far longer than the budget
+        let router = SlowRouter {
+            router: crate::router::simulate::StraightLineRouter {
+                locations: HashMap::from([
+                    (vertiport_1.clone(), location(52.0)),
+                    (vertiport_2.clone(), location(52.001)),
+                    (vertiport_3.clone(), location(52.002)),
+                ]),
+            },
+            slow_vertiport_ids: HashSet::from([vertiport_3.clone()]),
+            delay: std::time::Duration::from_secs(5),
+        };
+
+        // the first aircraft is parked at the origin, the second one
+        //  needs a slow deadhead flight to get there
+        let availability = |vertiport_id: &str, vertipad_id: &str, minutes: i64| Availability {
+            vertiport_id: vertiport_id.to_string(),
+            vertipad_id: vertipad_id.to_string(),
+            timeslot: Timeslot::new(
+                time_start - Duration::try_minutes(minutes).unwrap(),
+                time_end + Duration::try_hours(2).unwrap(),
+            )
+            .unwrap(),
+        };
+        let availabilities = HashMap::from([
+            (
+                Uuid::new_v4().to_string(),
+                vec![availability(&vertiport_1, &vertipad_1, 60)],
+            ),
+            (
+                Uuid::new_v4().to_string(),
+                vec![availability(&vertiport_3, &vertipad_3, 30)],
+            ),
+        ]);
+
+        let distance_meters = Meters(111.0);
+        let flight_duration = estimate_flight_time_seconds(&distance_meters).unwrap();
+        let timeslot_pairs = vec![TimeslotPair {
+            origin_vertiport_id: vertiport_1.clone(),
+            origin_vertipad_id: vertipad_1.clone(),
+            origin_timeslot: Timeslot::new(time_start, time_end).unwrap(),
+            target_vertiport_id: vertiport_2.clone(),
+            target_vertipad_id: vertipad_2.clone(),
+            target_timeslot: Timeslot::new(
+                time_start + flight_duration,
+                time_end + flight_duration,
+            )
+            .unwrap(),
+            path: vec![],
+            distance_meters,
+            alternative_paths: vec![],
+        }];
+
+        let ground_time = GroundTime {
+            loading: Duration::try_seconds(30).unwrap(),
+            unloading: Duration::try_seconds(30).unwrap(),
+        };
+
+        let started = Instant::now();
+        let deadline = started + std::time::Duration::from_millis(200);
+        let (itineraries, rejections) = calculate_itineraries_with_rejections(
+            &ground_time,
+            &HashMap::new(),
+            &HashMap::new(),
+            &timeslot_pairs,
+            &availabilities,
+            &deadhead_search(),
+            &Duration::zero(),
+            &LeadTimes::default(),
+            &SystemClock,
+            Some(deadline),
+            0,
+            None,
+            &router,
+        )
+        .await
+        .unwrap();
+
+        // the itinerary of the first aircraft is kept
+        assert!(rejections.budget_exceeded);
+        assert_eq!(itineraries.len(), 1);
+        assert!(started.elapsed() < std::time::Duration::from_secs(5));
+
+        // with time to spare, the search waits for the slow router
+        let (itineraries, rejections) = calculate_itineraries_with_rejections(
+            &ground_time,
+            &HashMap::new(),
+            &HashMap::new(),
+            &timeslot_pairs,
+            &availabilities,
+            &deadhead_search(),
+            &Duration::zero(),
+            &LeadTimes::default(),
+            &SystemClock,
+            Some(Instant::now() + std::time::Duration::from_secs(60)),
+            0,
+            None,
+            &SlowRouter {
+                delay: std::time::Duration::from_millis(10),
+                ..router
+            },
+        )
+        .await
+        .unwrap();
+
+        assert!(!rejections.budget_exceeded);
+        assert_eq!(itineraries.len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_calculate_itineraries_min_results() {
+        let time_start = Utc::now();
+        let time_end = time_start + Duration::try_hours(1).unwrap();
+        let vertiport_1 = Uuid::new_v4().to_string();
+        let vertiport_2 = Uuid::new_v4().to_string();
+        let vertipad_1 = Uuid::new_v4().to_string();
+        let vertipad_2 = Uuid::new_v4().to_string();
+
+        let location = |latitude: f64| PointZ {
+            latitude,
+            longitude: 4.0,
+            altitude_meters: 100.0,
+        };
+
+        let router = crate::router::simulate::StraightLineRouter {
+            locations: HashMap::from([
+                (vertiport_1.clone(), location(52.0)),
+                (vertiport_2.clone(), location(52.001)),
+            ]),
+        };
+
+        // four idle aircraft parked at the origin
+        let availabilities = (0..4)
+            .map(|_| {
+                (
+                    Uuid::new_v4().to_string(),
+                    vec![Availability {
+                        vertiport_id: vertiport_1.clone(),
+                        vertipad_id: vertipad_1.clone(),
+                        timeslot: Timeslot::new(
+                            time_start - Duration::try_hours(1).unwrap(),
+                            time_end + Duration::try_hours(2).unwrap(),
+                        )
+                        .unwrap(),
+                    }],
+                )
+            })
+            .collect::<HashMap<String, Vec<Availability>>>();
+
+        let distance_meters = Meters(111.0);
+        let flight_duration = estimate_flight_time_seconds(&distance_meters).unwrap();
+        let timeslot_pairs = vec![TimeslotPair {
+            origin_vertiport_id: vertiport_1.clone(),
+            origin_vertipad_id: vertipad_1.clone(),
+            origin_timeslot: Timeslot::new(time_start, time_end).unwrap(),
+            target_vertiport_id: vertiport_2.clone(),
+            target_vertipad_id: vertipad_2.clone(),
+            target_timeslot: Timeslot::new(
+                time_start + flight_duration,
+                time_end + flight_duration,
+            )
+            .unwrap(),
+            path: vec![],
+            distance_meters,
+            alternative_paths: vec![],
+        }];
+
+        let ground_time = GroundTime {
+            loading: Duration::try_seconds(30).unwrap(),
+            unloading: Duration::try_seconds(30).unwrap(),
+        };
+
+        let router = &router;
+        let search = |min_results: usize| {
+            let timeslot_pairs = timeslot_pairs.clone();
+            let availabilities = availabilities.clone();
+            async move {
+                calculate_itineraries_with_rejections(
+                    &ground_time,
+                    &HashMap::new(),
+                    &HashMap::new(),
+                    &timeslot_pairs,
+                    &availabilities,
+                    &deadhead_search(),
+                    &Duration::zero(),
+                    &LeadTimes::default(),
+                    &SystemClock,
+                    None,
+                    min_results,
+                    None,
+                    router,
+                )
+                .await
+            }
+        };
+
+        // the search stops at the usual number of itineraries
+        let (itineraries, _) = search(0).await.unwrap();
+        assert_eq!(itineraries.len(), MAX_ITINERARIES);
+
+        // and goes on until enough are found
+        let (itineraries, _) = search(MAX_ITINERARIES + 1).await.unwrap();
+        assert_eq!(itineraries.len(), MAX_ITINERARIES + 1);
+
+        // or every aircraft has been tried
+        let (itineraries, _) = search(10).await.unwrap();
+        assert_eq!(itineraries.len(), availabilities.len());
+    }
+
+    #[test]
+    fn test_return_policy_from_str() {
+        assert_eq!(
+            ReturnPolicy::from_str("strict").unwrap(),
+            ReturnPolicy::Strict
+        );
+        assert_eq!(ReturnPolicy::from_str("park").unwrap(), ReturnPolicy::Park);
         assert_eq!(
-            itinerary[0].origin_timeslot_start.clone().unwrap(),
-            aircraft_availability.timeslot.time_start().into()
+            ReturnPolicy::from_str("invalid").unwrap_err(),
+            ItineraryError::Internal
         );
     }
 
+    #[test]
+    fn test_can_park_at_target() {
+        let now = Utc::now();
+        let horizon = now + Duration::try_hours(3).unwrap();
+        let landing = now + Duration::try_hours(1).unwrap();
+        let vertipad_id = Uuid::new_v4().to_string();
+        let parking = ParkingLimits {
+            horizon,
+            vertipad_timeslots: HashMap::from([(
+                vertipad_id.clone(),
+                vec![Timeslot::new(now, horizon).unwrap()],
+            )]),
+        };
+
+        // the aircraft isn't needed anywhere afterwards
+        let availability = Availability {
+            vertiport_id: Uuid::new_v4().to_string(),
+            vertipad_id: Uuid::new_v4().to_string(),
+            timeslot: Timeslot::new(now, horizon).unwrap(),
+        };
+        assert!(can_park_at_target(
+            &parking,
+            &availability,
+            &vertipad_id,
+            landing
+        ));
+
+        // the aircraft is needed for another flight
+        let needed = Availability {
+            timeslot: Timeslot::new(now, horizon - Duration::try_minutes(1).unwrap()).unwrap(),
+            ..availability.clone()
+        };
+        assert!(!can_park_at_target(
+            &parking,
+            &needed,
+            &vertipad_id,
+            landing
+        ));
+
+        // the vertipad is taken while the aircraft would still be parked
+        let busy = ParkingLimits {
+            horizon,
+            vertipad_timeslots: HashMap::from([(
+                vertipad_id.clone(),
+                vec![
+                    Timeslot::new(now, landing + Duration::try_minutes(30).unwrap()).unwrap(),
+                    Timeslot::new(landing + Duration::try_hours(1).unwrap(), horizon).unwrap(),
+                ],
+            )]),
+        };
+        assert!(!can_park_at_target(
+            &busy,
+            &availability,
+            &vertipad_id,
+            landing
+        ));
+
+        // the vertipad is taken at landing
+        let late = ParkingLimits {
+            horizon,
+            vertipad_timeslots: HashMap::from([(
+                vertipad_id.clone(),
+                vec![Timeslot::new(landing + Duration::try_minutes(1).unwrap(), horizon).unwrap()],
+            )]),
+        };
+        assert!(!can_park_at_target(
+            &late,
+            &availability,
+            &vertipad_id,
+            landing
+        ));
+
+        // unknown vertipad
+        assert!(!can_park_at_target(
+            &parking,
+            &availability,
+            &Uuid::new_v4().to_string(),
+            landing
+        ));
+    }
+
+    /// Routes in straight lines, but never out of the given vertiport
+    struct OneWayRouter {
+        router: crate::router::simulate::StraightLineRouter,
+        blocked_origin: String,
+    }
+
+    #[tonic::async_trait]
+    impl PathFinder for OneWayRouter {
+        async fn best_path(
+            &self,
+            request: &BestPathRequest,
+        ) -> Result<Vec<(Vec<PointZ>, Meters)>, BestPathError> {
+            if request.origin_identifier == self.blocked_origin {
+                return Err(BestPathError::NoPathFound);
+            }
+
+            self.router.best_path(request).await
+        }
+    }
+
     #[tokio::test]
-    #[cfg(feature = "stub_backends")]
-    async fn test_get_itinerary_valid_incompatible_flight_window() {
-        let clients = get_clients().await;
+    async fn test_calculate_itineraries_park_at_target() {
         let time_start = Utc::now();
-        let time_end = Utc::now() + Duration::try_hours(1).unwrap();
+        let time_end = time_start + Duration::try_hours(1).unwrap();
+        let horizon = time_end + Duration::try_hours(2).unwrap();
         let vertiport_1 = Uuid::new_v4().to_string();
         let vertiport_2 = Uuid::new_v4().to_string();
-        let vertiport_3 = Uuid::new_v4().to_string();
         let vertipad_1 = Uuid::new_v4().to_string();
         let vertipad_2 = Uuid::new_v4().to_string();
-        let vehicle_id = Uuid::new_v4().to_string();
-        let required_loading_time = Duration::try_seconds(30).unwrap();
-        let required_unloading_time = Duration::try_seconds(30).unwrap();
-
-        //                                       |    flight window    |
-        //  |     takeoff and land time window     |
-        //
 
-        let aircraft_availability = Availability {
-            vertiport_id: vertiport_1.clone(),
-            vertipad_id: vertipad_1.clone(),
-            timeslot: Timeslot::new(
-                time_end - Duration::try_seconds(30).unwrap(),
-                time_end + Duration::try_minutes(20).unwrap(),
-            )
-            .unwrap(),
+        let location = |latitude: f64| PointZ {
+            latitude,
+            longitude: 4.0,
+            altitude_meters: 100.0,
         };
 
-        let distance_meters = 1000.0; // too far to fly
-        let flight_duration = estimate_flight_time_seconds(&distance_meters).unwrap();
-        let flight_window = Timeslot::new(time_start, time_end).unwrap();
+        // no flight can leave the destination to take the aircraft back
+        let router = OneWayRouter {
+            router: crate::router::simulate::StraightLineRouter {
+                locations: HashMap::from([
+                    (vertiport_1.clone(), location(52.0)),
+                    (vertiport_2.clone(), location(52.001)),
+                ]),
+            },
+            blocked_origin: vertiport_2.clone(),
+        };
 
-        let flight_plan = flight_plan::Data {
-            origin_vertiport_id: Some(vertiport_3.clone()),
-            target_vertiport_id: Some(vertiport_2.clone()),
+        let distance_meters = Meters(111.0);
+        let flight_duration = estimate_blocked_flight_time(&distance_meters)
+            .await
+            .unwrap();
+        let timeslot_pairs = vec![TimeslotPair {
+            origin_vertiport_id: vertiport_1.clone(),
             origin_vertipad_id: vertipad_1.clone(),
+            origin_timeslot: Timeslot::new(time_start, time_end).unwrap(),
+            target_vertiport_id: vertiport_2.clone(),
             target_vertipad_id: vertipad_2.clone(),
-            vehicle_id,
-            path: Some(GeoLineString { points: vec![] }),
-            ..Default::default()
+            target_timeslot: Timeslot::new(time_start, time_end + flight_duration).unwrap(),
+            path: vec![],
+            distance_meters,
+            alternative_paths: vec![],
+        }];
+
+        let ground_time = GroundTime {
+            loading: Duration::try_seconds(30).unwrap(),
+            unloading: Duration::try_seconds(30).unwrap(),
         };
 
-        let e = get_itinerary(
-            flight_plan,
-            &aircraft_availability,
-            &flight_duration,
-            &required_loading_time,
-            &required_unloading_time,
-            &flight_window,
-            &clients,
+        let get_availabilities = |time_end: DateTime<Utc>| {
+            HashMap::from([(
+                Uuid::new_v4().to_string(),
+                vec![Availability {
+                    vertiport_id: vertiport_1.clone(),
+                    vertipad_id: vertipad_1.clone(),
+                    timeslot: Timeslot::new(time_start - Duration::try_hours(1).unwrap(), time_end)
+                        .unwrap(),
+                }],
+            )])
+        };
+
+        let get_parking = |pad_free_until: DateTime<Utc>| ParkingLimits {
+            horizon,
+            vertipad_timeslots: HashMap::from([(
+                vertipad_2.clone(),
+                vec![Timeslot::new(time_start, pad_free_until).unwrap()],
+            )]),
+        };
+
+        let router = &router;
+        let timeslot_pairs = &timeslot_pairs;
+        let search = |return_policy: ReturnPolicy,
+                      parking: Option<ParkingLimits>,
+                      availabilities: HashMap<String, Vec<Availability>>| async move {
+            calculate_itineraries(
+                &ground_time,
+                &HashMap::new(),
+                &HashMap::new(),
+                timeslot_pairs,
+                &availabilities,
+                &DeadheadSearch {
+                    return_policy,
+                    parking: parking.as_ref(),
+                    ..deadhead_search()
+                },
+                &Duration::zero(),
+                &LeadTimes::default(),
+                &SystemClock,
+                router,
+            )
+            .await
+            .unwrap()
+        };
+
+        // the aircraft can't get back
+        let itineraries = search(
+            ReturnPolicy::Strict,
+            Some(get_parking(horizon)),
+            get_availabilities(horizon),
         )
-        .await
-        .unwrap_err();
-        assert_eq!(e, ItineraryError::ScheduleConflict);
+        .await;
+        assert!(itineraries.is_empty());
+
+        // it stays at the destination, without a flight back
+        let itineraries = search(
+            ReturnPolicy::Park,
+            Some(get_parking(horizon)),
+            get_availabilities(horizon),
+        )
+        .await;
+        assert_eq!(itineraries.len(), 1);
+        assert_eq!(itineraries[0].flight_plans.len(), 1);
+        assert_eq!(
+            itineraries[0].flight_plans[0].target_vertiport_id,
+            Some(vertiport_2)
+        );
+
+        // not without knowing the destination vertipads are free
+        let itineraries = search(ReturnPolicy::Park, None, get_availabilities(horizon)).await;
+        assert!(itineraries.is_empty());
+
+        // nor if the aircraft is needed back at the origin later on
+        let itineraries = search(
+            ReturnPolicy::Park,
+            Some(get_parking(horizon)),
+            get_availabilities(horizon - Duration::try_minutes(30).unwrap()),
+        )
+        .await;
+        assert!(itineraries.is_empty());
+
+        // nor if another flight lands on the destination vertipad later on
+        let itineraries = search(
+            ReturnPolicy::Park,
+            Some(get_parking(horizon - Duration::try_minutes(30).unwrap())),
+            get_availabilities(horizon),
+        )
+        .await;
+        assert!(itineraries.is_empty());
     }
 
     #[tokio::test]
-    #[cfg(feature = "stub_backends")]
-    async fn test_calculate_itineraries() {
-        let clients = get_clients().await;
+    async fn test_calculate_itineraries_deadhead_no_path() {
         let time_start = Utc::now();
-        let time_end = Utc::now() + Duration::try_seconds(1000).unwrap();
+        let time_end = time_start + Duration::try_hours(1).unwrap();
         let vertiport_1 = Uuid::new_v4().to_string();
         let vertiport_2 = Uuid::new_v4().to_string();
-        let vertiport_3 = Uuid::new_v4().to_string();
         let vertipad_1 = Uuid::new_v4().to_string();
-        let vertipad_3 = Uuid::new_v4().to_string();
-        let vehicle_1 = Uuid::new_v4().to_string();
-        let vehicle_2 = Uuid::new_v4().to_string();
-        let required_loading_time = Duration::try_seconds(30).unwrap();
-        let required_unloading_time = Duration::try_seconds(30).unwrap();
+        let vertipad_2 = Uuid::new_v4().to_string();
+
+        let location = |latitude: f64| PointZ {
+            latitude,
+            longitude: 4.0,
+            altitude_meters: 100.0,
+        };
+
+        // no flight can leave the destination to take the aircraft back
+        let router = OneWayRouter {
+            router: crate::router::simulate::StraightLineRouter {
+                locations: HashMap::from([
+                    (vertiport_1.clone(), location(52.0)),
+                    (vertiport_2.clone(), location(52.001)),
+                ]),
+            },
+            blocked_origin: vertiport_2.clone(),
+        };
 
+        let distance_meters = Meters(111.0);
+        let flight_duration = estimate_blocked_flight_time(&distance_meters)
+            .await
+            .unwrap();
+        let timeslot_pairs = vec![TimeslotPair {
+            origin_vertiport_id: vertiport_1.clone(),
+            origin_vertipad_id: vertipad_1.clone(),
+            origin_timeslot: Timeslot::new(time_start, time_end).unwrap(),
+            target_vertiport_id: vertiport_2.clone(),
+            target_vertipad_id: vertipad_2.clone(),
+            target_timeslot: Timeslot::new(time_start, time_end + flight_duration).unwrap(),
+            path: vec![],
+            distance_meters,
+            alternative_paths: vec![],
+        }];
+
+        // two idle aircraft parked at the origin
+        let availability = vec![Availability {
+            vertiport_id: vertiport_1.clone(),
+            vertipad_id: vertipad_1.clone(),
+            timeslot: Timeslot::new(
+                time_start - Duration::try_hours(1).unwrap(),
+                time_end + Duration::try_hours(2).unwrap(),
+            )
+            .unwrap(),
+        }];
         let availabilities = HashMap::from([
-            (
-                vehicle_1.clone(),
-                vec![Availability {
-                    vertiport_id: vertiport_1.clone(),
-                    vertipad_id: vertipad_1.clone(),
-                    timeslot: Timeslot::new(
-                        time_start - Duration::try_hours(1).unwrap(),
-                        time_end + Duration::try_hours(1).unwrap(),
-                    )
-                    .unwrap(),
-                }],
-            ),
-            (
-                vehicle_2.clone(),
-                vec![Availability {
-                    vertiport_id: vertiport_3.clone(),
-                    vertipad_id: vertipad_3.clone(),
-                    timeslot: Timeslot::new(
-                        time_end + Duration::try_hours(1).unwrap(),
-                        time_end + Duration::try_hours(2).unwrap(),
-                    )
-                    .unwrap(),
-                }],
-            ),
+            (Uuid::new_v4().to_string(), availability.clone()),
+            (Uuid::new_v4().to_string(), availability),
         ]);
 
-        let distance_meters = 50.0;
-        let flight_duration = estimate_flight_time_seconds(&distance_meters).unwrap();
-        let timeslot_pairs = vec![
-            TimeslotPair {
-                origin_vertiport_id: vertiport_1.clone(),
-                origin_vertipad_id: vertipad_1.clone(),
-                origin_timeslot: Timeslot::new(time_start.clone(), time_end.clone()).unwrap(),
-                target_vertiport_id: vertiport_2.clone(),
-                target_vertipad_id: vertiport_2.clone(),
-                target_timeslot: Timeslot::new(
-                    time_start + flight_duration,
-                    time_end + flight_duration,
-                )
-                .unwrap(),
-                path: vec![],
-                distance_meters,
-            },
-            TimeslotPair {
-                origin_vertiport_id: vertiport_1.clone(),
-                origin_vertipad_id: vertipad_1.clone(),
-                origin_timeslot: Timeslot::new(
-                    time_end + Duration::try_hours(1).unwrap(),
-                    time_end + Duration::try_hours(2).unwrap(),
-                )
-                .unwrap(),
-                target_vertiport_id: vertiport_2.clone(),
-                target_vertipad_id: vertiport_2.clone(),
-                target_timeslot: Timeslot::new(
-                    time_end + Duration::try_hours(1).unwrap() + flight_duration,
-                    time_end + Duration::try_hours(2).unwrap() + flight_duration,
-                )
-                .unwrap(),
-                path: vec![],
-                distance_meters,
-            },
-        ];
+        let ground_time = GroundTime {
+            loading: Duration::try_seconds(30).unwrap(),
+            unloading: Duration::try_seconds(30).unwrap(),
+        };
 
-        let itineraries = calculate_itineraries(
-            &required_loading_time,
-            &required_unloading_time,
+        let (itineraries, rejections) = calculate_itineraries_with_rejections(
+            &ground_time,
+            &HashMap::new(),
+            &HashMap::new(),
             &timeslot_pairs,
             &availabilities,
-            &clients,
+            &deadhead_search(),
+            &Duration::zero(),
+            &LeadTimes::default(),
+            &SystemClock,
+            None,
+            0,
+            None,
+            &router,
         )
         .await
         .unwrap();
 
-        // Expect two matches
-        println!("{:?}", itineraries);
-        for (i, itinerary) in itineraries.iter().enumerate() {
-            println!("\n\n----- Itinerary {}", i);
-            for (fp_i, fp) in itinerary.iter().enumerate() {
-                println!("{}: {:?}\n", fp_i, fp);
-            }
-        }
+        // the aircraft are free, there's just no way back
+        assert!(itineraries.is_empty());
+        assert_eq!(rejections.routing, 2);
+        assert_eq!(rejections.aircraft_availability, 0);
+        assert_eq!(
+            rejections.get_limiting_factor(0, 0),
+            Some(LimitingFactor::Routing)
+        );
+    }
 
-        assert_eq!(itineraries.len(), 2);
+    #[test]
+    fn test_get_deadhead_error() {
+        assert_eq!(
+            get_deadhead_error(ItineraryError::NoPathFound),
+            ItineraryError::NoPathFound
+        );
+        assert_eq!(
+            get_deadhead_error(ItineraryError::NoPath),
+            ItineraryError::NoPath
+        );
+        assert_eq!(
+            get_deadhead_error(ItineraryError::PathTooShort),
+            ItineraryError::PathTooShort
+        );
+        assert_eq!(
+            get_deadhead_error(ItineraryError::ClientError),
+            ItineraryError::ScheduleConflict
+        );
+        assert_eq!(
+            get_deadhead_error(ItineraryError::TimeWindow),
+            ItineraryError::ScheduleConflict
+        );
+    }
+
+    #[test]
+    fn test_rejections_get_limiting_factor() {
+        let rejections = Rejections {
+            timeslot_pairs: 1,
+            ..Default::default()
+        };
+
+        // enough itineraries were found
+        assert_eq!(rejections.get_limiting_factor(MAX_ITINERARIES, 0), None);
+
+        // too few vertipad timeslots
+        assert_eq!(
+            rejections.get_limiting_factor(1, 0),
+            Some(LimitingFactor::PadCapacity)
+        );
+
+        // the search went on for more results than usual
+        assert_eq!(
+            rejections.get_limiting_factor(MAX_ITINERARIES, MAX_ITINERARIES + 3),
+            Some(LimitingFactor::PadCapacity)
+        );
+        assert_eq!(
+            rejections.get_limiting_factor(MAX_ITINERARIES + 3, MAX_ITINERARIES + 3),
+            None
+        );
+
+        // busy aircraft outweigh the missing timeslots
+        let mut tmp = rejections;
+        tmp.record(&ItineraryError::ScheduleConflict);
+        tmp.record(&ItineraryError::TooManyDeadheads);
+        assert_eq!(
+            tmp.get_limiting_factor(0, 0),
+            Some(LimitingFactor::AircraftAvailability)
+        );
+
+        // most candidates had no route
+        tmp.record(&ItineraryError::NoPathFound);
+        tmp.record(&ItineraryError::NoPathFound);
+        tmp.record(&ItineraryError::PathTooShort);
+        tmp.record(&ItineraryError::Internal);
+        assert_eq!(tmp.aircraft_availability, 2);
+        assert_eq!(tmp.routing, 3);
+        assert_eq!(tmp.get_limiting_factor(0, 0), Some(LimitingFactor::Routing));
+
+        // plenty of timeslots and nothing rejected: too few aircraft
+        let tmp = Rejections {
+            timeslot_pairs: MAX_ITINERARIES,
+            ..Default::default()
+        };
+        assert_eq!(
+            tmp.get_limiting_factor(0, 0),
+            Some(LimitingFactor::AircraftAvailability)
+        );
+
+        // unless more results were asked for than there were timeslots
+        assert_eq!(
+            tmp.get_limiting_factor(0, MAX_ITINERARIES + 1),
+            Some(LimitingFactor::PadCapacity)
+        );
     }
 
     #[test]
@@ -1233,6 +3839,58 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn test_validate_itinerary_direct_paths() {
+        let mut vertipad_ids = HashSet::<String>::new();
+        let mut aircraft_id = String::new();
+
+        let vehicle_id = Uuid::new_v4().to_string();
+        let vertipad_1 = Uuid::new_v4().to_string();
+        let vertipad_2 = Uuid::new_v4().to_string();
+        let vertipad_3 = Uuid::new_v4().to_string();
+
+        // svc-gis paths between neighbouring vertiports have no waypoints
+        let point = |latitude: f64, longitude: f64| PointZ {
+            latitude,
+            longitude,
+            altitude_meters: 0.0,
+        };
+
+        let flight_plans = vec![
+            FlightPlanSchedule {
+                origin_vertiport_id: Uuid::new_v4().to_string(),
+                origin_vertipad_id: vertipad_1.clone(),
+                origin_timeslot_start: Utc::now() + Duration::try_minutes(10).unwrap(),
+                origin_timeslot_end: Utc::now() + Duration::try_minutes(11).unwrap(),
+                target_vertiport_id: Uuid::new_v4().to_string(),
+                target_vertipad_id: vertipad_2.clone(),
+                target_timeslot_start: Utc::now() + Duration::try_minutes(30).unwrap(),
+                target_timeslot_end: Utc::now() + Duration::try_minutes(31).unwrap(),
+                vehicle_id: vehicle_id.clone(),
+                path: Some(vec![point(52.0, 4.0), point(52.1, 4.1)]),
+            },
+            FlightPlanSchedule {
+                origin_vertiport_id: Uuid::new_v4().to_string(),
+                origin_vertipad_id: vertipad_2.clone(),
+                origin_timeslot_start: Utc::now() + Duration::try_minutes(31).unwrap(),
+                origin_timeslot_end: Utc::now() + Duration::try_minutes(32).unwrap(),
+                target_vertiport_id: Uuid::new_v4().to_string(),
+                target_vertipad_id: vertipad_3.clone(),
+                target_timeslot_start: Utc::now() + Duration::try_minutes(50).unwrap(),
+                target_timeslot_end: Utc::now() + Duration::try_minutes(51).unwrap(),
+                vehicle_id: vehicle_id.clone(),
+                path: Some(vec![point(52.1, 4.1), point(52.2, 4.2)]),
+            },
+        ];
+
+        validate_itinerary(&flight_plans, &mut vertipad_ids, &mut aircraft_id).unwrap();
+        assert_eq!(
+            vertipad_ids,
+            HashSet::from([vertipad_1, vertipad_2, vertipad_3])
+        );
+        assert_eq!(aircraft_id, vehicle_id);
+    }
+
     #[test]
     fn test_validate_itinerary_inconsistent_vertipads() -> Result<(), ItineraryError> {
         let mut vertipad_ids = HashSet::<String>::new();
@@ -1404,5 +4062,9 @@ mod tests {
         );
         assert_eq!(ItineraryError::Internal.to_string(), "Internal error.");
         assert_eq!(ItineraryError::NoPathFound.to_string(), "No path found.");
+        assert_eq!(
+            ItineraryError::TooManyDeadheads.to_string(),
+            "Too many deadhead flights."
+        );
     }
 }
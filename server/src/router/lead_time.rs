@@ -0,0 +1,228 @@
+//! Advance notice required by vertiports
+//! Some vertiports need notice ahead of a flight (staffing, security).
+//!  No flight may depart from or land at such a vertiport sooner than
+//!  its lead time from now.
+
+use crate::router::schedule::Timeslot;
+use crate::router::settings::{get_settings, Setting, SettingError, FIELD_SEPARATOR};
+use crate::router::vehicle::Availability;
+use lib_common::time::{DateTime, Duration, Utc};
+use std::cmp::max;
+use std::collections::HashMap;
+
+/// Vertiport lead times setting
+pub static VERTIPORT_LEAD_TIMES: Setting = Setting {
+    name: "VERTIPORT_LEAD_TIMES",
+    format: "vertiport_id|seconds",
+};
+
+/// Default vertiport lead time setting
+pub static DEFAULT_VERTIPORT_LEAD_SECONDS: Setting = Setting {
+    name: "DEFAULT_VERTIPORT_LEAD_SECONDS",
+    format: "seconds",
+};
+
+/// Lead times of vertiports
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct LeadTimes {
+    /// Lead time of vertiports without a lead time of their own
+    pub default: Duration,
+
+    /// Lead times of specific vertiports
+    pub vertiports: HashMap<String, Duration>,
+}
+
+impl LeadTimes {
+    /// Returns the lead time of a vertiport
+    pub fn get(&self, vertiport_id: &str) -> Duration {
+        self.vertiports
+            .get(vertiport_id)
+            .copied()
+            .unwrap_or(self.default)
+    }
+
+    /// Returns the earliest time a flight may depart from or land at
+    ///  a vertiport, `None` if the vertiport needs no notice
+    pub fn get_earliest(&self, vertiport_id: &str, now: DateTime<Utc>) -> Option<DateTime<Utc>> {
+        let lead_time = self.get(vertiport_id);
+        match lead_time.is_zero() {
+            true => None,
+            false => Some(now + lead_time),
+        }
+    }
+
+    /// Returns the earliest departure of a flight that lands no sooner
+    ///  than the lead time of the target vertiport either.
+    /// `None` if neither vertiport needs notice.
+    pub fn get_earliest_departure(
+        &self,
+        origin_vertiport_id: &str,
+        target_vertiport_id: &str,
+        flight_duration: &Duration,
+        now: DateTime<Utc>,
+    ) -> Option<DateTime<Utc>> {
+        max(
+            self.get_earliest(origin_vertiport_id, now),
+            self.get_earliest(target_vertiport_id, now)
+                .map(|earliest| earliest - *flight_duration),
+        )
+    }
+
+    /// Removes the time of aircraft availabilities within the lead
+    ///  time of their vertiport, so that deadhead flights don't
+    ///  depart from it too soon
+    pub fn trim_availabilities(
+        &self,
+        aircraft_availabilities: &HashMap<String, Vec<Availability>>,
+        now: DateTime<Utc>,
+    ) -> HashMap<String, Vec<Availability>> {
+        aircraft_availabilities
+            .iter()
+            .map(|(aircraft_id, availabilities)| {
+                let availabilities = availabilities
+                    .iter()
+                    .filter_map(|a| {
+                        let Some(earliest) = self.get_earliest(&a.vertiport_id, now) else {
+                            return Some(a.clone());
+                        };
+
+                        let time_start = max(a.timeslot.time_start(), earliest);
+                        let timeslot = Timeslot::new(time_start, a.timeslot.time_end()).ok()?;
+                        Some(Availability {
+                            timeslot,
+                            ..a.clone()
+                        })
+                    })
+                    .collect();
+
+                (aircraft_id.clone(), availabilities)
+            })
+            .collect()
+    }
+}
+
+/// Parses comma-separated `vertiport_id|seconds` entries
+pub fn parse_vertiport_lead_times(
+    lead_times: &str,
+) -> Result<HashMap<String, Duration>, SettingError> {
+    let entries = VERTIPORT_LEAD_TIMES.parse_list(lead_times, |entry| {
+        let [vertiport_id, seconds] = VERTIPORT_LEAD_TIMES.split::<2>(entry, FIELD_SEPARATOR)?;
+        Ok((
+            VERTIPORT_LEAD_TIMES.parse_id(vertiport_id)?,
+            VERTIPORT_LEAD_TIMES.parse_seconds(seconds)?,
+        ))
+    })?;
+
+    Ok(entries.into_iter().collect())
+}
+
+/// Returns the configured vertiport lead times
+///
+/// TODO(R5): Read the lead times from svc-storage once vertiports
+///  carry them, the configuration is a stand-in until then
+pub async fn get_lead_times() -> Result<LeadTimes, SettingError> {
+    Ok(get_settings().await?.lead_times.clone())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use lib_common::uuid::Uuid;
+
+    #[test]
+    fn test_parse_vertiport_lead_times() {
+        assert!(parse_vertiport_lead_times("").unwrap().is_empty());
+
+        let vertiport_1 = Uuid::new_v4().to_string();
+        let vertiport_2 = Uuid::new_v4().to_string();
+        let lead_times =
+            parse_vertiport_lead_times(&format!("{vertiport_1}|600, {vertiport_2}|0")).unwrap();
+
+        assert_eq!(lead_times.len(), 2);
+        assert_eq!(lead_times[&vertiport_1], Duration::try_minutes(10).unwrap());
+        assert_eq!(lead_times[&vertiport_2], Duration::zero());
+
+        let e = parse_vertiport_lead_times(&vertiport_1).unwrap_err();
+        assert_eq!(e, SettingError::Format(&VERTIPORT_LEAD_TIMES));
+
+        let e = parse_vertiport_lead_times("invalid|600").unwrap_err();
+        assert_eq!(e, SettingError::Id(&VERTIPORT_LEAD_TIMES));
+
+        let e = parse_vertiport_lead_times(&format!("{vertiport_1}|-1")).unwrap_err();
+        assert_eq!(e, SettingError::Value(&VERTIPORT_LEAD_TIMES));
+    }
+
+    #[test]
+    fn test_get_earliest_departure() {
+        let now = Utc::now();
+        let origin_id = Uuid::new_v4().to_string();
+        let target_id = Uuid::new_v4().to_string();
+        let flight_duration = Duration::try_minutes(10).unwrap();
+        let mut lead_times = LeadTimes::default();
+
+        // no notice needed
+        assert!(lead_times
+            .get_earliest_departure(&origin_id, &target_id, &flight_duration, now)
+            .is_none());
+
+        // default lead time at both ends
+        lead_times.default = Duration::try_minutes(5).unwrap();
+        assert_eq!(
+            lead_times.get_earliest_departure(&origin_id, &target_id, &flight_duration, now),
+            Some(now + Duration::try_minutes(5).unwrap())
+        );
+
+        // the origin needs more notice
+        lead_times
+            .vertiports
+            .insert(origin_id.clone(), Duration::try_minutes(30).unwrap());
+        assert_eq!(
+            lead_times.get_earliest_departure(&origin_id, &target_id, &flight_duration, now),
+            Some(now + Duration::try_minutes(30).unwrap())
+        );
+
+        // the target needs even more, the flight can't land before
+        lead_times
+            .vertiports
+            .insert(target_id.clone(), Duration::try_hours(1).unwrap());
+        assert_eq!(
+            lead_times.get_earliest_departure(&origin_id, &target_id, &flight_duration, now),
+            Some(now + Duration::try_minutes(50).unwrap())
+        );
+    }
+
+    #[test]
+    fn test_trim_availabilities() {
+        let now = Utc::now();
+        let vertiport_id = Uuid::new_v4().to_string();
+        let aircraft_id = Uuid::new_v4().to_string();
+        let availability = |minutes: i64| Availability {
+            vertiport_id: vertiport_id.clone(),
+            vertipad_id: Uuid::new_v4().to_string(),
+            timeslot: Timeslot::new(now, now + Duration::try_minutes(minutes).unwrap()).unwrap(),
+        };
+
+        let availabilities =
+            HashMap::from([(aircraft_id.clone(), vec![availability(60), availability(5)])]);
+
+        // no lead time
+        let trimmed = LeadTimes::default().trim_availabilities(&availabilities, now);
+        assert_eq!(trimmed, availabilities);
+
+        // the start is pushed back, availabilities within the lead time are removed
+        let lead_times = LeadTimes {
+            default: Duration::zero(),
+            vertiports: HashMap::from([(vertiport_id, Duration::try_minutes(10).unwrap())]),
+        };
+        let trimmed = lead_times.trim_availabilities(&availabilities, now);
+        assert_eq!(trimmed[&aircraft_id].len(), 1);
+        assert_eq!(
+            trimmed[&aircraft_id][0].timeslot,
+            Timeslot::new(
+                now + Duration::try_minutes(10).unwrap(),
+                now + Duration::try_hours(1).unwrap()
+            )
+            .unwrap()
+        );
+    }
+}
@@ -2,15 +2,39 @@
 
 #[macro_use]
 pub mod macros;
+pub mod blackout;
+pub mod eta;
+pub mod exclusion;
+pub mod fallback;
 pub mod flight_plan;
+pub mod hazard;
 pub mod itinerary;
+pub mod lead_time;
+pub mod operating_hours;
+pub mod pricing;
+pub mod reservation;
 pub mod schedule;
+pub mod settings;
+#[cfg(any(test, feature = "simulate"))]
+pub mod simulate;
+pub mod timezone;
+pub mod units;
 pub mod vehicle;
+pub mod vehicle_model;
 pub mod vertiport;
+pub mod warmup;
+pub mod weather;
 
 use crate::grpc::client::GrpcClients;
+use std::cmp::Ordering;
 use svc_gis_client_grpc::prelude::{gis::*, *};
+use tonic::async_trait;
+use units::Meters;
 
+/// Minimum number of points in a flyable path: the origin and the target
+pub const MIN_PATH_POINTS: usize = 2;
+
+#[derive(Debug, Copy, Clone, PartialEq)]
 pub enum BestPathError {
     ClientError,
     NoPathFound,
@@ -25,12 +49,40 @@ impl std::fmt::Display for BestPathError {
     }
 }
 
+/// Finds paths between vertiports for the itineraries.
+/// Implemented by the gRPC clients, which ask svc-gis, and by stubbed
+///  routers that answer from memory.
+#[async_trait]
+pub trait PathFinder {
+    /// Get the flyable paths for the request and their lengths, shortest first
+    async fn best_path(
+        &self,
+        request: &BestPathRequest,
+    ) -> Result<Vec<(Vec<PointZ>, Meters)>, BestPathError>;
+
+    /// Returns true if the path returned between two nodes is a
+    ///  straight-line fallback rather than a route from svc-gis
+    fn is_fallback_path(&self, _origin_identifier: &str, _target_identifier: &str) -> bool {
+        false
+    }
+}
+
+#[async_trait]
+impl PathFinder for GrpcClients {
+    async fn best_path(
+        &self,
+        request: &BestPathRequest,
+    ) -> Result<Vec<(Vec<PointZ>, Meters)>, BestPathError> {
+        best_path(request, self).await
+    }
+}
+
 /// Get the best path between two vertiports or a between an aircraft and a vertiport
 ///  and the total length of the path in meters.
 pub async fn best_path(
     request: &BestPathRequest,
     clients: &GrpcClients,
-) -> Result<Vec<(Vec<PointZ>, f64)>, BestPathError> {
+) -> Result<Vec<(Vec<PointZ>, Meters)>, BestPathError> {
     let mut paths = match clients.gis.best_path(request.clone()).await {
         Ok(response) => response.into_inner().paths,
         Err(e) => {
@@ -44,19 +96,10 @@ pub async fn best_path(
         return Err(BestPathError::NoPathFound);
     }
 
-    paths.sort_by(|a, b| {
-        if a.distance_meters == b.distance_meters {
-            std::cmp::Ordering::Equal
-        } else if a.distance_meters < b.distance_meters {
-            std::cmp::Ordering::Less
-        } else {
-            std::cmp::Ordering::Greater
-        }
-    });
     router_debug!("svc-gis paths: {:?}", paths);
 
     // convert segments to GeoLineString
-    let mut result: Vec<(Vec<PointZ>, f64)> = vec![];
+    let mut result: Vec<(Vec<PointZ>, Meters)> = vec![];
     for path in paths {
         let Ok(points) = path
             .path
@@ -73,8 +116,176 @@ pub async fn best_path(
             continue;
         };
 
+        if !is_flyable_path(&points) {
+            router_warn!("Path has no origin or target point: {:?}", points);
+            continue;
+        }
+
         result.push((points, path.distance_meters.into()));
     }
 
+    // Callers expect at least one path if Ok
+    if result.is_empty() {
+        router_error!("No flyable path found.");
+        return Err(BestPathError::NoPathFound);
+    }
+
+    let tolerance = get_distance_tolerance().await;
+    result.sort_by(|a, b| cmp_paths(a, b, tolerance));
+
     Ok(result)
 }
+
+/// Returns the configured difference in length below which routes are
+///  ranked as equally short
+pub async fn get_distance_tolerance() -> Meters {
+    let config = crate::config::get_config().await;
+    Meters::from_millimeters(config.distance_tolerance_millimeters)
+}
+
+/// Orders paths shortest first, treating lengths within `tolerance` of
+///  each other as equal.
+/// Ties go to the path with fewer points, then to the path whose first
+///  differing point comes first, so that the order doesn't depend on
+///  the order svc-gis returned the paths in.
+pub fn cmp_paths(
+    a: &(Vec<PointZ>, Meters),
+    b: &(Vec<PointZ>, Meters),
+    tolerance: Meters,
+) -> Ordering {
+    a.1.cmp_within(&b.1, tolerance)
+        .then_with(|| a.0.len().cmp(&b.0.len()))
+        .then_with(|| {
+            a.0.iter()
+                .zip(b.0.iter())
+                .map(|(a, b)| {
+                    a.latitude
+                        .total_cmp(&b.latitude)
+                        .then_with(|| a.longitude.total_cmp(&b.longitude))
+                        .then_with(|| a.altitude_meters.total_cmp(&b.altitude_meters))
+                })
+                .find(|ordering| ordering.is_ne())
+                .unwrap_or(Ordering::Equal)
+        })
+}
+
+/// Returns true if the path has both an origin and a target point.
+/// A direct path between two vertiports has no waypoints in between
+///  and can still be flown.
+pub fn is_flyable_path(points: &[PointZ]) -> bool {
+    points.len() >= MIN_PATH_POINTS
+}
+
+/// Splits the paths returned by [`best_path`] into the shortest path
+///  and the distinct alternatives to it, shortest first.
+/// Returns `None` if there are no paths.
+pub fn split_alternative_paths(
+    mut paths: Vec<(Vec<PointZ>, Meters)>,
+) -> Option<((Vec<PointZ>, Meters), Vec<(Vec<PointZ>, Meters)>)> {
+    if paths.is_empty() {
+        return None;
+    }
+
+    let shortest = paths.remove(0);
+    let mut alternatives: Vec<(Vec<PointZ>, Meters)> = vec![];
+    for (points, distance) in paths {
+        // svc-gis may return the same corridor more than once
+        if points == shortest.0 || alternatives.iter().any(|(p, _)| *p == points) {
+            continue;
+        }
+
+        alternatives.push((points, distance));
+    }
+
+    Some((shortest, alternatives))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn point(latitude: f64, longitude: f64) -> PointZ {
+        PointZ {
+            latitude,
+            longitude,
+            altitude_meters: 100.0,
+        }
+    }
+
+    #[test]
+    fn test_is_flyable_path() {
+        assert!(!is_flyable_path(&[]));
+        assert!(!is_flyable_path(&[point(52.0, 4.0)]));
+
+        // direct path, no waypoints between the vertiports
+        assert!(is_flyable_path(&[point(52.0, 4.0), point(52.1, 4.1)]));
+        assert!(is_flyable_path(&[
+            point(52.0, 4.0),
+            point(52.2, 4.0),
+            point(52.1, 4.1)
+        ]));
+    }
+
+    #[test]
+    fn test_cmp_paths_near_equal_distances() {
+        let tolerance = Meters::from_millimeters(1);
+        let north = vec![point(52.0, 4.0), point(52.2, 4.0), point(52.1, 4.1)];
+        let south = vec![point(52.0, 4.0), point(52.0, 4.2), point(52.1, 4.1)];
+        let direct = vec![point(52.0, 4.0), point(52.1, 4.1)];
+
+        // a nanometer apart, in either order from svc-gis
+        let paths = vec![
+            (north.clone(), Meters(1000.0 + 1e-9)),
+            (south.clone(), Meters(1000.0)),
+            (direct.clone(), Meters(1000.0 + 2e-9)),
+        ];
+
+        let mut sorted = paths.clone();
+        sorted.sort_by(|a, b| cmp_paths(a, b, tolerance));
+        let mut reversed: Vec<_> = paths.into_iter().rev().collect();
+        reversed.sort_by(|a, b| cmp_paths(a, b, tolerance));
+        assert_eq!(sorted, reversed);
+
+        // fewer points first, then by the first differing point
+        let order: Vec<_> = sorted.into_iter().map(|(points, _)| points).collect();
+        assert_eq!(order, vec![direct, south, north]);
+
+        // clearly shorter paths still come first
+        let mut sorted = vec![
+            (vec![point(52.0, 4.0), point(52.1, 4.1)], Meters(1200.0)),
+            (
+                vec![point(52.0, 4.0), point(52.2, 4.0), point(52.1, 4.1)],
+                Meters(1000.0),
+            ),
+        ];
+        sorted.sort_by(|a, b| cmp_paths(a, b, tolerance));
+        assert_eq!(sorted[0].1, Meters(1000.0));
+    }
+
+    #[test]
+    fn test_split_alternative_paths() {
+        assert!(split_alternative_paths(vec![]).is_none());
+
+        let shortest = vec![point(52.0, 4.0), point(52.1, 4.1)];
+        let north = vec![point(52.0, 4.0), point(52.2, 4.0), point(52.1, 4.1)];
+        let south = vec![point(52.0, 4.0), point(52.0, 4.2), point(52.1, 4.1)];
+        let paths = vec![
+            (shortest.clone(), Meters(1000.0)),
+            (north.clone(), Meters(1200.0)),
+            (shortest.clone(), Meters(1000.0)),
+            (south.clone(), Meters(1300.0)),
+            (north.clone(), Meters(1200.0)),
+        ];
+
+        let (first, alternatives) = split_alternative_paths(paths).unwrap();
+        assert_eq!(first, (shortest.clone(), Meters(1000.0)));
+        assert_eq!(
+            alternatives,
+            vec![(north, Meters(1200.0)), (south, Meters(1300.0))]
+        );
+
+        // single path has no alternatives
+        let (_, alternatives) = split_alternative_paths(vec![(shortest, Meters(1000.0))]).unwrap();
+        assert!(alternatives.is_empty());
+    }
+}
@@ -0,0 +1,277 @@
+//! Operating-hours overrides for vertiports
+//! Overrides take precedence over a vertiport's RRULE schedule for
+//!  specific dates (events, inspections). An override either opens a
+//!  normally-closed period or closes a normally-open period.
+
+use crate::router::schedule::Timeslot;
+use crate::router::settings::{get_settings, Setting, SettingError, FIELD_SEPARATOR};
+
+/// Operating-hours overrides setting
+pub static VERTIPORT_HOURS_OVERRIDES: Setting = Setting {
+    name: "VERTIPORT_HOURS_OVERRIDES",
+    format: "vertiport_id|open|start/end",
+};
+
+/// Whether an override opens or closes a vertiport
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum OverrideKind {
+    /// Open during a normally-closed period
+    Open,
+
+    /// Closed during a normally-open period
+    Closed,
+}
+
+/// An operating-hours override for a single vertiport
+#[derive(Debug, Clone, PartialEq)]
+pub struct HoursOverride {
+    /// Vertiport the override applies to
+    pub vertiport_id: String,
+
+    /// Whether the vertiport is opened or closed
+    pub kind: OverrideKind,
+
+    /// Period the override applies to
+    pub timeslot: Timeslot,
+}
+
+/// Parses comma-separated `vertiport_id|open|start/end` or
+///  `vertiport_id|closed|start/end` overrides with RFC3339 times
+pub fn parse_hours_overrides(overrides: &str) -> Result<Vec<HoursOverride>, SettingError> {
+    VERTIPORT_HOURS_OVERRIDES.parse_list(overrides, |entry| {
+        let [vertiport_id, kind, period] =
+            VERTIPORT_HOURS_OVERRIDES.split::<3>(entry, FIELD_SEPARATOR)?;
+
+        let kind = match kind {
+            "open" => OverrideKind::Open,
+            "closed" => OverrideKind::Closed,
+            _ => {
+                router_error!("Invalid hours override kind: {kind}");
+                return Err(SettingError::Value(&VERTIPORT_HOURS_OVERRIDES));
+            }
+        };
+
+        Ok(HoursOverride {
+            vertiport_id: VERTIPORT_HOURS_OVERRIDES.parse_id(vertiport_id)?,
+            kind,
+            timeslot: VERTIPORT_HOURS_OVERRIDES.parse_period(period)?,
+        })
+    })
+}
+
+/// Sorts timeslots and merges the ones that overlap or touch
+fn merge_timeslots(mut timeslots: Vec<Timeslot>) -> Vec<Timeslot> {
+    timeslots.sort_by_key(|slot| slot.time_start());
+
+    let mut merged: Vec<Timeslot> = vec![];
+    for slot in timeslots {
+        match merged.last_mut() {
+            Some(last) if slot.time_start() <= last.time_end() => {
+                if slot.time_end() > last.time_end() {
+                    // can't fail, the start is unchanged and the end moves later
+                    if let Ok(extended) = Timeslot::new(last.time_start(), slot.time_end()) {
+                        *last = extended;
+                    }
+                }
+            }
+            _ => merged.push(slot),
+        }
+    }
+
+    merged
+}
+
+/// Applies a vertiport's overrides to the timeslots from its schedule.
+/// Openings within `window` are added first, then closures are removed,
+///  so a closure wins over an opening for the same period.
+pub fn apply_hours_overrides(
+    vertiport_id: &str,
+    timeslots: Vec<Timeslot>,
+    overrides: &[HoursOverride],
+    window: &Timeslot,
+) -> Vec<Timeslot> {
+    let overrides = overrides
+        .iter()
+        .filter(|o| o.vertiport_id == vertiport_id)
+        .collect::<Vec<&HoursOverride>>();
+
+    if overrides.is_empty() {
+        return timeslots;
+    }
+
+    let mut timeslots = timeslots;
+    timeslots.extend(
+        overrides
+            .iter()
+            .filter(|o| o.kind == OverrideKind::Open)
+            .filter_map(|o| o.timeslot.overlap(window).ok()),
+    );
+
+    let mut timeslots = merge_timeslots(timeslots);
+    for closure in overrides.iter().filter(|o| o.kind == OverrideKind::Closed) {
+        timeslots = timeslots
+            .into_iter()
+            .flat_map(|slot| slot - closure.timeslot)
+            .collect();
+    }
+
+    router_debug!("timeslots for vertiport {vertiport_id} after overrides: {timeslots:?}");
+    timeslots
+}
+
+/// Returns the configured operating-hours overrides
+pub async fn get_hours_overrides() -> Result<Vec<HoursOverride>, SettingError> {
+    Ok(get_settings().await?.hours_overrides.clone())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use lib_common::time::{DateTime, Utc};
+    use lib_common::uuid::Uuid;
+    use std::str::FromStr;
+
+    fn time(time: &str) -> DateTime<Utc> {
+        DateTime::<Utc>::from_str(time).unwrap()
+    }
+
+    fn slot(start: &str, end: &str) -> Timeslot {
+        Timeslot::new(time(start), time(end)).unwrap()
+    }
+
+    #[test]
+    fn test_parse_hours_overrides() {
+        assert!(parse_hours_overrides("").unwrap().is_empty());
+
+        let vertiport_id = Uuid::new_v4().to_string();
+        let overrides = parse_hours_overrides(&format!(
+            "{vertiport_id}|closed|2024-01-01T12:00:00Z/2024-01-01T14:00:00Z, \
+            {vertiport_id}|open|2024-01-01T20:00:00Z/2024-01-01T22:00:00Z"
+        ))
+        .unwrap();
+
+        assert_eq!(
+            overrides,
+            vec![
+                HoursOverride {
+                    vertiport_id: vertiport_id.clone(),
+                    kind: OverrideKind::Closed,
+                    timeslot: slot("2024-01-01T12:00:00Z", "2024-01-01T14:00:00Z"),
+                },
+                HoursOverride {
+                    vertiport_id: vertiport_id.clone(),
+                    kind: OverrideKind::Open,
+                    timeslot: slot("2024-01-01T20:00:00Z", "2024-01-01T22:00:00Z"),
+                },
+            ]
+        );
+
+        let e = parse_hours_overrides(&format!("{vertiport_id}|closed")).unwrap_err();
+        assert_eq!(e, SettingError::Format(&VERTIPORT_HOURS_OVERRIDES));
+
+        let e = parse_hours_overrides("invalid|closed|2024-01-01T12:00:00Z/2024-01-01T14:00:00Z")
+            .unwrap_err();
+        assert_eq!(e, SettingError::Id(&VERTIPORT_HOURS_OVERRIDES));
+
+        let e = parse_hours_overrides(&format!(
+            "{vertiport_id}|ajar|2024-01-01T12:00:00Z/2024-01-01T14:00:00Z"
+        ))
+        .unwrap_err();
+        assert_eq!(e, SettingError::Value(&VERTIPORT_HOURS_OVERRIDES));
+
+        let e = parse_hours_overrides(&format!("{vertiport_id}|open|2024-01-01T12:00:00Z/later"))
+            .unwrap_err();
+        assert_eq!(e, SettingError::Time(&VERTIPORT_HOURS_OVERRIDES));
+
+        let e = parse_hours_overrides(&format!(
+            "{vertiport_id}|open|2024-01-01T14:00:00Z/2024-01-01T12:00:00Z"
+        ))
+        .unwrap_err();
+        assert_eq!(e, SettingError::Timeslot(&VERTIPORT_HOURS_OVERRIDES));
+    }
+
+    #[test]
+    fn test_apply_hours_overrides_closed() {
+        let vertiport_id = Uuid::new_v4().to_string();
+        let window = slot("2024-01-01T00:00:00Z", "2024-01-02T00:00:00Z");
+        let open_hours = vec![slot("2024-01-01T08:00:00Z", "2024-01-01T18:00:00Z")];
+
+        // an inspection closes the vertiport during normal hours
+        let overrides = vec![HoursOverride {
+            vertiport_id: vertiport_id.clone(),
+            kind: OverrideKind::Closed,
+            timeslot: slot("2024-01-01T12:00:00Z", "2024-01-01T14:00:00Z"),
+        }];
+
+        let timeslots =
+            apply_hours_overrides(&vertiport_id, open_hours.clone(), &overrides, &window);
+        assert_eq!(
+            timeslots,
+            vec![
+                slot("2024-01-01T08:00:00Z", "2024-01-01T12:00:00Z"),
+                slot("2024-01-01T14:00:00Z", "2024-01-01T18:00:00Z"),
+            ]
+        );
+
+        // other vertiports are unaffected
+        let timeslots = apply_hours_overrides(
+            &Uuid::new_v4().to_string(),
+            open_hours.clone(),
+            &overrides,
+            &window,
+        );
+        assert_eq!(timeslots, open_hours);
+    }
+
+    #[test]
+    fn test_apply_hours_overrides_open() {
+        let vertiport_id = Uuid::new_v4().to_string();
+        let window = slot("2024-01-01T00:00:00Z", "2024-01-01T21:00:00Z");
+        let open_hours = vec![slot("2024-01-01T08:00:00Z", "2024-01-01T18:00:00Z")];
+
+        // an event extends the evening hours and opens late at night
+        let overrides = vec![
+            HoursOverride {
+                vertiport_id: vertiport_id.clone(),
+                kind: OverrideKind::Open,
+                timeslot: slot("2024-01-01T17:00:00Z", "2024-01-01T19:00:00Z"),
+            },
+            HoursOverride {
+                vertiport_id: vertiport_id.clone(),
+                kind: OverrideKind::Open,
+                timeslot: slot("2024-01-01T20:00:00Z", "2024-01-01T23:00:00Z"),
+            },
+        ];
+
+        let timeslots = apply_hours_overrides(&vertiport_id, open_hours, &overrides, &window);
+        assert_eq!(
+            timeslots,
+            vec![
+                slot("2024-01-01T08:00:00Z", "2024-01-01T19:00:00Z"),
+                // limited to the requested window
+                slot("2024-01-01T20:00:00Z", "2024-01-01T21:00:00Z"),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_apply_hours_overrides_closed_wins() {
+        let vertiport_id = Uuid::new_v4().to_string();
+        let window = slot("2024-01-01T00:00:00Z", "2024-01-02T00:00:00Z");
+        let overrides = vec![
+            HoursOverride {
+                vertiport_id: vertiport_id.clone(),
+                kind: OverrideKind::Open,
+                timeslot: slot("2024-01-01T20:00:00Z", "2024-01-01T22:00:00Z"),
+            },
+            HoursOverride {
+                vertiport_id: vertiport_id.clone(),
+                kind: OverrideKind::Closed,
+                timeslot: slot("2024-01-01T19:00:00Z", "2024-01-01T23:00:00Z"),
+            },
+        ];
+
+        let timeslots = apply_hours_overrides(&vertiport_id, vec![], &overrides, &window);
+        assert!(timeslots.is_empty());
+    }
+}
@@ -0,0 +1,371 @@
+//! Time-of-use pricing
+//! Energy and landing fees vary over the day. A pricing schedule assigns
+//!  cost multipliers to times of day so that cheaper departures can be
+//!  preferred when itineraries are ranked by lowest cost, and quoted
+//!  prices follow the same multipliers.
+
+use super::units::Meters;
+use super::vertiport::TimeslotPair;
+use crate::router::settings::{get_settings, Setting, SettingError};
+use lib_common::time::{DateTime, Duration, NaiveTime, Utc};
+
+/// Pricing schedule setting
+pub static PRICING_SCHEDULE: Setting = Setting {
+    name: "PRICING_SCHEDULE",
+    format: "start-end=multiplier",
+};
+
+/// Separates a pricing window from its multiplier
+const MULTIPLIER_SEPARATOR: char = '=';
+
+/// Separates the start and end of a pricing window
+const INTERVAL_SEPARATOR: char = '-';
+
+/// Time of day format of pricing windows
+const TIME_FORMAT: &str = "%H:%M";
+
+/// Cost multiplier of times outside every pricing window
+pub const BASE_COST_MULTIPLIER: f64 = 1.0;
+
+/// Orders in which itineraries can be returned
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Ranking {
+    /// Earliest departure first
+    Earliest,
+
+    /// Cheapest departure time first
+    LowestCost,
+
+    /// Earliest arrival of the requested flight first
+    EarliestArrival,
+
+    /// Departures closest to the preferred time first
+    PreferredDeparture(DateTime<Utc>),
+}
+
+/// A time of day window (UTC) with a cost multiplier
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct PricingWindow {
+    start: NaiveTime,
+    end: NaiveTime,
+    multiplier: f64,
+}
+
+impl PricingWindow {
+    /// Returns true if the time of day falls within the window.
+    /// Windows that end before they start wrap around midnight.
+    fn contains(&self, time: NaiveTime) -> bool {
+        match self.start <= self.end {
+            true => self.start <= time && time < self.end,
+            false => time >= self.start || time < self.end,
+        }
+    }
+}
+
+/// Parses comma-separated `HH:MM-HH:MM=multiplier` windows
+pub fn parse_pricing_schedule(schedule: &str) -> Result<Vec<PricingWindow>, SettingError> {
+    PRICING_SCHEDULE.parse_list(schedule, |window| {
+        let [interval, multiplier] = PRICING_SCHEDULE.split::<2>(window, MULTIPLIER_SEPARATOR)?;
+        let [start, end] = PRICING_SCHEDULE.split::<2>(interval, INTERVAL_SEPARATOR)?;
+        let parse = |time: &str| {
+            NaiveTime::parse_from_str(time, TIME_FORMAT).map_err(|e| {
+                router_error!("Invalid pricing window time {time}: {e}");
+                SettingError::Time(&PRICING_SCHEDULE)
+            })
+        };
+
+        let multiplier = Some(PRICING_SCHEDULE.parse_value::<f64>(multiplier)?)
+            .filter(|multiplier| multiplier.is_finite() && *multiplier >= 0.0)
+            .ok_or_else(|| {
+                router_error!("Invalid pricing window multiplier: {multiplier}");
+                SettingError::Value(&PRICING_SCHEDULE)
+            })?;
+
+        Ok(PricingWindow {
+            start: parse(start)?,
+            end: parse(end)?,
+            multiplier,
+        })
+    })
+}
+
+/// Returns the cost multiplier of the first window containing the time
+pub fn get_cost_multiplier(windows: &[PricingWindow], time: &DateTime<Utc>) -> f64 {
+    windows
+        .iter()
+        .find(|window| window.contains(time.time()))
+        .map(|window| window.multiplier)
+        .unwrap_or(BASE_COST_MULTIPLIER)
+}
+
+/// Orders timeslot pairs by the given ranking.
+/// Pairs are found earliest departure first, so ties between equally
+///  priced or equally close departures keep the earlier one first.
+pub fn rank_timeslot_pairs(
+    pairs: &mut [TimeslotPair],
+    ranking: Ranking,
+    windows: &[PricingWindow],
+) {
+    match ranking {
+        // on the same route, the earliest departures land first as well.
+        //  Itineraries are sorted by arrival once deadhead flights are known
+        Ranking::Earliest | Ranking::EarliestArrival => (),
+        Ranking::LowestCost => pairs.sort_by(|a, b| {
+            let cost_a = get_cost_multiplier(windows, &a.origin_timeslot.time_start());
+            let cost_b = get_cost_multiplier(windows, &b.origin_timeslot.time_start());
+            cost_a.total_cmp(&cost_b)
+        }),
+        Ranking::PreferredDeparture(preferred) => {
+            pairs.sort_by_key(|pair| get_departure_offset(pair, preferred))
+        }
+    }
+}
+
+/// How far from the preferred time the pair can depart at the closest,
+///  zero if the origin timeslot contains it
+pub fn get_departure_offset(pair: &TimeslotPair, preferred: DateTime<Utc>) -> Duration {
+    let timeslot = &pair.origin_timeslot;
+    if preferred < timeslot.time_start() {
+        timeslot.time_start() - preferred
+    } else if preferred > timeslot.time_end() {
+        preferred - timeslot.time_end()
+    } else {
+        Duration::zero()
+    }
+}
+
+/// Rates used to quote flights, in the smallest currency unit
+#[derive(Debug, Copy, Clone, PartialEq, Default)]
+pub struct PriceRates {
+    /// Price per kilometer flown
+    pub per_km: u32,
+
+    /// Price per minute of flight time
+    pub per_minute: u32,
+
+    /// Fee for landing at the target vertiport
+    pub landing_fee: u32,
+}
+
+/// The requested flight of an itinerary, as used to quote it
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct QuotedFlight {
+    /// Length of the flight's route
+    pub distance_meters: Meters,
+
+    /// Estimated flight time
+    pub duration: Duration,
+
+    /// Departure time, for the time-of-use multiplier
+    pub departure: DateTime<Utc>,
+}
+
+/// Estimates the price of a flight from its distance and flight time
+///  plus the landing fee, scaled by the cost multiplier at departure.
+/// Rounded up to the smallest currency unit.
+pub fn estimate_price(rates: &PriceRates, flight: &QuotedFlight, windows: &[PricingWindow]) -> u64 {
+    let minutes = flight.duration.num_seconds() as f64 / 60.0;
+    let base = flight.distance_meters.as_kilometers() * rates.per_km as f64
+        + minutes * rates.per_minute as f64
+        + rates.landing_fee as f64;
+
+    (base * get_cost_multiplier(windows, &flight.departure)).ceil() as u64
+}
+
+/// Returns the configured rates for quoting flights
+pub async fn get_price_rates() -> PriceRates {
+    let config = crate::config::get_config().await;
+    PriceRates {
+        per_km: config.price_per_km,
+        per_minute: config.price_per_minute,
+        landing_fee: config.landing_fee,
+    }
+}
+
+/// Returns the configured pricing schedule
+pub async fn get_pricing_schedule() -> Result<Vec<PricingWindow>, SettingError> {
+    Ok(get_settings().await?.pricing_schedule.clone())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::router::schedule::Timeslot;
+    use std::str::FromStr;
+
+    fn at(time: &str) -> DateTime<Utc> {
+        DateTime::<Utc>::from_str(&format!("2024-01-01T{time}:00Z")).unwrap()
+    }
+
+    fn get_pair(departure: DateTime<Utc>) -> TimeslotPair {
+        TimeslotPair {
+            origin_vertiport_id: "origin".to_string(),
+            origin_vertipad_id: "origin-pad".to_string(),
+            origin_timeslot: Timeslot::new(
+                departure,
+                departure + Duration::try_minutes(30).unwrap(),
+            )
+            .unwrap(),
+            target_vertiport_id: "target".to_string(),
+            target_vertipad_id: "target-pad".to_string(),
+            target_timeslot: Timeslot::new(
+                departure + Duration::try_minutes(20).unwrap(),
+                departure + Duration::try_minutes(50).unwrap(),
+            )
+            .unwrap(),
+            path: vec![],
+            distance_meters: Meters(1000.0),
+            alternative_paths: vec![],
+        }
+    }
+
+    #[test]
+    fn test_parse_pricing_schedule() {
+        assert!(parse_pricing_schedule("").unwrap().is_empty());
+
+        let windows = parse_pricing_schedule("07:00-09:00=1.5, 22:00-06:00=0.5").unwrap();
+        assert_eq!(windows.len(), 2);
+        assert_eq!(windows[0].multiplier, 1.5);
+        assert_eq!(windows[1].start, NaiveTime::from_hms_opt(22, 0, 0).unwrap());
+
+        let e = parse_pricing_schedule("07:00-09:00").unwrap_err();
+        assert_eq!(e, SettingError::Format(&PRICING_SCHEDULE));
+
+        let e = parse_pricing_schedule("07:00=1.5").unwrap_err();
+        assert_eq!(e, SettingError::Format(&PRICING_SCHEDULE));
+
+        let e = parse_pricing_schedule("7am-09:00=1.5").unwrap_err();
+        assert_eq!(e, SettingError::Time(&PRICING_SCHEDULE));
+
+        let e = parse_pricing_schedule("07:00-09:00=cheap").unwrap_err();
+        assert_eq!(e, SettingError::Value(&PRICING_SCHEDULE));
+
+        let e = parse_pricing_schedule("07:00-09:00=-1").unwrap_err();
+        assert_eq!(e, SettingError::Value(&PRICING_SCHEDULE));
+    }
+
+    #[test]
+    fn test_get_cost_multiplier() {
+        let windows = parse_pricing_schedule("07:00-09:00=1.5,22:00-06:00=0.5").unwrap();
+        assert_eq!(get_cost_multiplier(&windows, &at("08:00")), 1.5);
+        assert_eq!(
+            get_cost_multiplier(&windows, &at("09:00")),
+            BASE_COST_MULTIPLIER
+        );
+
+        // wraps around midnight
+        assert_eq!(get_cost_multiplier(&windows, &at("23:30")), 0.5);
+        assert_eq!(get_cost_multiplier(&windows, &at("05:59")), 0.5);
+
+        assert_eq!(get_cost_multiplier(&[], &at("08:00")), BASE_COST_MULTIPLIER);
+    }
+
+    #[test]
+    fn test_rank_timeslot_pairs_prefers_off_peak() {
+        let windows = parse_pricing_schedule("07:00-09:00=1.5,09:00-16:00=0.8").unwrap();
+        let peak = get_pair(at("08:00"));
+        let off_peak = get_pair(at("10:00"));
+
+        // Both pairs can be flown, the peak departure is earlier
+        let mut pairs = vec![peak.clone(), off_peak.clone()];
+        rank_timeslot_pairs(&mut pairs, Ranking::Earliest, &windows);
+        assert_eq!(pairs[0].origin_timeslot, peak.origin_timeslot);
+
+        rank_timeslot_pairs(&mut pairs, Ranking::LowestCost, &windows);
+        assert_eq!(pairs[0].origin_timeslot, off_peak.origin_timeslot);
+        assert_eq!(pairs[1].origin_timeslot, peak.origin_timeslot);
+
+        // equally priced departures stay earliest first
+        let later = get_pair(at("11:00"));
+        let mut pairs = vec![off_peak.clone(), later.clone()];
+        rank_timeslot_pairs(&mut pairs, Ranking::LowestCost, &windows);
+        assert_eq!(pairs[0].origin_timeslot, off_peak.origin_timeslot);
+    }
+
+    #[test]
+    fn test_rank_timeslot_pairs_preferred_departure() {
+        let early = get_pair(at("08:00"));
+        let preferred = get_pair(at("10:00"));
+        let late = get_pair(at("11:00"));
+
+        let mut pairs = vec![early.clone(), preferred.clone(), late.clone()];
+        rank_timeslot_pairs(&mut pairs, Ranking::PreferredDeparture(at("10:15")), &[]);
+        assert_eq!(pairs[0].origin_timeslot, preferred.origin_timeslot);
+        assert_eq!(pairs[1].origin_timeslot, late.origin_timeslot);
+        assert_eq!(pairs[2].origin_timeslot, early.origin_timeslot);
+
+        // equally close departures stay earliest first
+        let mut pairs = vec![early.clone(), preferred.clone()];
+        rank_timeslot_pairs(&mut pairs, Ranking::PreferredDeparture(at("09:15")), &[]);
+        assert_eq!(pairs[0].origin_timeslot, early.origin_timeslot);
+
+        // a pair that can depart at the preferred time goes first, even
+        //  if its timeslot starts further from it
+        let soon_after = get_pair(at("10:40"));
+        let mut pairs = vec![soon_after.clone(), preferred.clone()];
+        rank_timeslot_pairs(&mut pairs, Ranking::PreferredDeparture(at("10:28")), &[]);
+        assert_eq!(pairs[0].origin_timeslot, preferred.origin_timeslot);
+    }
+
+    #[test]
+    fn test_get_departure_offset() {
+        // 10:00-10:30
+        let pair = get_pair(at("10:00"));
+        assert_eq!(
+            get_departure_offset(&pair, at("09:45")),
+            Duration::try_minutes(15).unwrap()
+        );
+        assert_eq!(get_departure_offset(&pair, at("10:00")), Duration::zero());
+        assert_eq!(get_departure_offset(&pair, at("10:20")), Duration::zero());
+        assert_eq!(
+            get_departure_offset(&pair, at("10:40")),
+            Duration::try_minutes(10).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_estimate_price() {
+        let rates = PriceRates {
+            per_km: 150,
+            per_minute: 40,
+            landing_fee: 500,
+        };
+
+        let flight = QuotedFlight {
+            distance_meters: Meters::from_kilometers(10.0),
+            duration: Duration::try_minutes(20).unwrap(),
+            departure: at("12:00"),
+        };
+
+        // 10 km * 150 + 20 min * 40 + 500
+        assert_eq!(estimate_price(&rates, &flight, &[]), 2800);
+
+        // twice the distance adds 10 km
+        let longer = QuotedFlight {
+            distance_meters: Meters::from_kilometers(20.0),
+            ..flight
+        };
+        assert_eq!(estimate_price(&rates, &longer, &[]), 2800 + 1500);
+
+        // ten more minutes
+        let slower = QuotedFlight {
+            duration: Duration::try_minutes(30).unwrap(),
+            ..flight
+        };
+        assert_eq!(estimate_price(&rates, &slower, &[]), 2800 + 400);
+
+        // time-of-use multiplier at departure
+        let windows = parse_pricing_schedule("11:00-13:00=1.5").unwrap();
+        assert_eq!(estimate_price(&rates, &flight, &windows), 4200);
+
+        // rounded up
+        let flight = QuotedFlight {
+            distance_meters: Meters(1.0),
+            duration: Duration::zero(),
+            departure: at("12:00"),
+        };
+        assert_eq!(estimate_price(&rates, &flight, &[]), 501);
+
+        assert_eq!(estimate_price(&PriceRates::default(), &flight, &[]), 0);
+    }
+}
@@ -0,0 +1,189 @@
+//! Vertipad slots reserved for scheduled services
+//! Operators running scheduled services reserve recurring vertipad
+//!  slots for demand that isn't booked yet. Reserved slots aren't
+//!  available to other flights, whether or not a flight plan occupies
+//!  them.
+//!
+//! TODO(R5): Read reservations from a svc-storage vertipad reservation
+//!  table once there is one, instead of from the configuration
+
+use crate::router::schedule::{Schedule, Timeslot};
+use crate::router::settings::{get_settings, Setting, SettingError, FIELD_SEPARATOR};
+use std::collections::HashMap;
+use std::str::FromStr;
+
+/// Vertipad reservations setting
+pub static VERTIPAD_RESERVATIONS: Setting = Setting {
+    name: "VERTIPAD_RESERVATIONS",
+    format: "vertipad_id|calendar",
+};
+
+/// Recurring slots reserved on a vertipad
+#[derive(Debug, Clone)]
+pub struct VertipadReservation {
+    /// Vertipad the slots are reserved on
+    pub vertipad_id: String,
+
+    /// When the slots recur
+    pub schedule: Schedule,
+}
+
+/// Parses whitespace-separated `vertipad_id|calendar` reservations.
+/// The lines of the RRULE calendar are separated by `|` as well, e.g.
+///  `vertipad_id|DTSTART:20240101T080000Z;DURATION:PT15M|RRULE:FREQ=DAILY`
+pub fn parse_vertipad_reservations(
+    reservations: &str,
+) -> Result<Vec<VertipadReservation>, SettingError> {
+    VERTIPAD_RESERVATIONS.parse_entries(reservations.split_whitespace(), |entry| {
+        let [vertipad_id, calendar] = VERTIPAD_RESERVATIONS.split::<2>(entry, FIELD_SEPARATOR)?;
+        let vertipad_id = VERTIPAD_RESERVATIONS.parse_id(vertipad_id)?;
+
+        // the lines of the calendar are separated by `|` as well
+        let calendar = calendar.replace(FIELD_SEPARATOR, "\n");
+        let schedule = Schedule::from_str(&calendar).map_err(|e| {
+            router_error!("Invalid vertipad reservation calendar {calendar}: {e}");
+            SettingError::Value(&VERTIPAD_RESERVATIONS)
+        })?;
+
+        Ok(VertipadReservation {
+            vertipad_id,
+            schedule,
+        })
+    })
+}
+
+/// Removes the slots reserved within `window` from the available
+///  timeslots of each vertipad
+pub fn remove_reserved_timeslots(
+    timeslots: &mut HashMap<String, Vec<Timeslot>>,
+    reservations: &[VertipadReservation],
+    window: &Timeslot,
+) {
+    for reservation in reservations {
+        let Some(vertipad_slots) = timeslots.get_mut(&reservation.vertipad_id) else {
+            continue;
+        };
+
+        let reserved_slots = match reservation
+            .schedule
+            .to_timeslots(&window.time_start(), &window.time_end())
+        {
+            Ok(slots) => slots,
+            Err(e) => {
+                router_error!(
+                    "Could not get reserved slots of vertipad {}: {e}",
+                    reservation.vertipad_id
+                );
+                continue;
+            }
+        };
+
+        for reserved_slot in reserved_slots {
+            *vertipad_slots = vertipad_slots
+                .iter()
+                .flat_map(|slot| *slot - reserved_slot)
+                .collect();
+        }
+
+        router_debug!(
+            "timeslots for vertipad {} after reservations: {:?}",
+            reservation.vertipad_id,
+            vertipad_slots
+        );
+    }
+}
+
+/// Returns the configured vertipad reservations
+pub async fn get_vertipad_reservations() -> Result<Vec<VertipadReservation>, SettingError> {
+    Ok(get_settings().await?.vertipad_reservations.clone())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::router::vertiport::get_unoccupied_timeslots;
+    use lib_common::time::{DateTime, Duration, Utc};
+    use lib_common::uuid::Uuid;
+
+    #[test]
+    fn test_parse_vertipad_reservations() {
+        assert!(parse_vertipad_reservations("").unwrap().is_empty());
+
+        let vertipad_1 = Uuid::new_v4().to_string();
+        let vertipad_2 = Uuid::new_v4().to_string();
+        let reservations = parse_vertipad_reservations(&format!(
+            "{vertipad_1}|DTSTART:20240101T080000Z;DURATION:PT15M|RRULE:FREQ=DAILY \
+            {vertipad_2}|DTSTART:20240101T090000Z;DURATION:PT1H|RRULE:FREQ=WEEKLY;BYDAY=MO,FR"
+        ))
+        .unwrap();
+
+        assert_eq!(reservations.len(), 2);
+        assert_eq!(reservations[0].vertipad_id, vertipad_1);
+        assert_eq!(reservations[1].vertipad_id, vertipad_2);
+
+        let e = parse_vertipad_reservations(&vertipad_1).unwrap_err();
+        assert_eq!(e, SettingError::Format(&VERTIPAD_RESERVATIONS));
+
+        let e = parse_vertipad_reservations(
+            "invalid|DTSTART:20240101T080000Z;DURATION:PT15M|RRULE:FREQ=DAILY",
+        )
+        .unwrap_err();
+        assert_eq!(e, SettingError::Id(&VERTIPAD_RESERVATIONS));
+
+        let e = parse_vertipad_reservations(&format!("{vertipad_1}|DTSTART:20240101T080000Z"))
+            .unwrap_err();
+        assert_eq!(e, SettingError::Value(&VERTIPAD_RESERVATIONS));
+    }
+
+    #[test]
+    fn test_reserved_slot_without_flight_plan() {
+        let vertiport_id = Uuid::new_v4().to_string();
+        let reserved_pad = Uuid::new_v4().to_string();
+        let other_pad = Uuid::new_v4().to_string();
+        let start = DateTime::<Utc>::from_str("2024-01-01T06:00:00Z").unwrap();
+        let day = Timeslot::new(start, start + Duration::try_hours(12).unwrap()).unwrap();
+
+        // 08:00-08:15 every day on one of the vertipads
+        let reservations = parse_vertipad_reservations(&format!(
+            "{reserved_pad}|DTSTART:20240101T080000Z;DURATION:PT15M|RRULE:FREQ=DAILY"
+        ))
+        .unwrap();
+
+        let mut timeslots = HashMap::from([
+            (reserved_pad.clone(), vec![day]),
+            (other_pad.clone(), vec![day]),
+        ]);
+        remove_reserved_timeslots(&mut timeslots, &reservations, &day);
+
+        // no flight plans occupy either vertipad
+        let timeslots = get_unoccupied_timeslots(
+            &vertiport_id,
+            timeslots,
+            &[],
+            &Duration::try_minutes(1).unwrap(),
+        )
+        .unwrap();
+
+        let reserved = Timeslot::new(
+            start + Duration::try_hours(2).unwrap(),
+            start + Duration::try_hours(2).unwrap() + Duration::try_minutes(15).unwrap(),
+        )
+        .unwrap();
+
+        let is_available = |pad: &str| {
+            timeslots[pad]
+                .iter()
+                .any(|slot| slot.overlap(&reserved).is_ok())
+        };
+        assert!(!is_available(&reserved_pad));
+        assert!(is_available(&other_pad));
+
+        // the rest of the day is still available on the reserved vertipad
+        assert_eq!(
+            timeslots[&reserved_pad]
+                .iter()
+                .fold(Duration::zero(), |total, slot| total + slot.duration()),
+            Duration::try_hours(12).unwrap() - Duration::try_minutes(15).unwrap()
+        );
+    }
+}
@@ -170,6 +170,16 @@ impl Sub for Timeslot {
     }
 }
 
+/// Rounds a time up to the next whole second (zero nanoseconds)
+/// Scheduled times are stored as whole seconds so that comparisons
+///  on `Timestamp.seconds` and on `DateTime<Utc>` agree.
+pub fn ceil_to_second(time: DateTime<Utc>) -> DateTime<Utc> {
+    match time.timestamp_subsec_nanos() {
+        0 => time,
+        nanos => time + Duration::nanoseconds(1_000_000_000 - nanos as i64),
+    }
+}
+
 // /// formats DateTime to string in format: `YYYYMMDDThhmmssZ`, e.g. 20221026T133000Z
 fn datetime_to_ical_format(dt: &DateTime<RRuleTz>) -> String {
     router_debug!("{:?}", dt);
@@ -391,6 +401,65 @@ impl Calendar {
     }
 }
 
+/// A schedule from storage that has been validated as a [`Calendar`].
+/// Built once where the raw schedule string enters the service so
+///  downstream code never handles an unparsed RRULE string.
+#[derive(Debug, Clone)]
+pub struct Schedule {
+    calendar: Calendar,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum ScheduleError {
+    /// No schedule was provided
+    Missing,
+
+    /// The schedule could not be parsed
+    Invalid,
+}
+
+impl Display for ScheduleError {
+    fn fmt(&self, f: &mut Formatter) -> FmtResult {
+        match self {
+            ScheduleError::Missing => write!(f, "No schedule provided"),
+            ScheduleError::Invalid => write!(f, "Invalid schedule"),
+        }
+    }
+}
+
+impl FromStr for Schedule {
+    type Err = ScheduleError;
+
+    fn from_str(schedule_str: &str) -> Result<Self, Self::Err> {
+        let calendar = Calendar::from_str(schedule_str).map_err(|e| {
+            router_debug!("Invalid schedule {schedule_str}; {e}");
+            ScheduleError::Invalid
+        })?;
+
+        Ok(Schedule { calendar })
+    }
+}
+
+impl TryFrom<Option<String>> for Schedule {
+    type Error = ScheduleError;
+
+    fn try_from(schedule: Option<String>) -> Result<Self, Self::Error> {
+        let schedule = schedule.ok_or(ScheduleError::Missing)?;
+        Schedule::from_str(&schedule)
+    }
+}
+
+impl Schedule {
+    /// Converts the schedule into a sorted list of timeslots, see [`Calendar::to_timeslots`]
+    pub fn to_timeslots(
+        &self,
+        time_start: &DateTime<Utc>,
+        time_end: &DateTime<Utc>,
+    ) -> Result<Vec<Timeslot>, CalendarError> {
+        self.calendar.to_timeslots(time_start, time_end)
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -447,6 +516,35 @@ mod tests {
         let _calendar = Calendar::from_str(INVALID_CALENDAR).unwrap();
     }
 
+    #[test]
+    fn test_schedule_try_from() {
+        let schedule = Schedule::try_from(Some(CAL_WORKDAYS_8AM_6PM.to_string())).unwrap();
+        assert_eq!(schedule.calendar.events.len(), 2);
+
+        let e = Schedule::try_from(None).unwrap_err();
+        assert_eq!(e, ScheduleError::Missing);
+
+        let e = Schedule::try_from(Some(INVALID_CALENDAR.to_string())).unwrap_err();
+        assert_eq!(e, ScheduleError::Invalid);
+
+        // Invalid RRULE with a valid header
+        let e = Schedule::from_str(
+            "DTSTART:20221020T180000Z;DURATION:PT14H\n\
+            RRULE:FREQ=SOMETIMES;BYDAY=MO",
+        )
+        .unwrap_err();
+        assert_eq!(e, ScheduleError::Invalid);
+    }
+
+    #[test]
+    fn test_schedule_error_display() {
+        assert_eq!(
+            format!("{}", ScheduleError::Missing),
+            "No schedule provided"
+        );
+        assert_eq!(format!("{}", ScheduleError::Invalid), "Invalid schedule");
+    }
+
     #[test]
     fn test_calendar_to_timeslots() {
         // 8AM to 12PM, 2PM to 6PM
@@ -756,6 +854,20 @@ mod tests {
         assert_eq!(format!("{}", CalendarError::Internal), "Internal error");
     }
 
+    #[test]
+    fn test_ceil_to_second() {
+        let time = Utc.with_ymd_and_hms(2024, 1, 1, 12, 0, 0).unwrap();
+        assert_eq!(ceil_to_second(time), time);
+
+        let unaligned = time + Duration::try_milliseconds(1).unwrap();
+        let expected = time + Duration::try_seconds(1).unwrap();
+        assert_eq!(ceil_to_second(unaligned), expected);
+        assert_eq!(ceil_to_second(unaligned).timestamp_subsec_nanos(), 0);
+
+        let unaligned = time - Duration::nanoseconds(1);
+        assert_eq!(ceil_to_second(unaligned), time);
+    }
+
     #[test]
     fn test_timeslot_split() {
         let now = Utc::now();
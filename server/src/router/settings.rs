@@ -0,0 +1,357 @@
+//! Settings listing entries in the configuration
+//! Blackout periods, vertiport timezones and lead times, weather cells,
+//!  vertipad reservations, hazard restrictions, operating-hours overrides
+//!  and the pricing schedule are each configured as a list of entries.
+//!  They share one parser and are parsed once, when the configuration
+//!  is loaded, so that a malformed setting stops the service on startup
+//!  instead of failing every query.
+
+use super::blackout::{parse_blackout_periods, BLACKOUT_PERIODS};
+use super::hazard::{parse_hazard_restrictions, HazardRestriction};
+use super::lead_time::{parse_vertiport_lead_times, LeadTimes, DEFAULT_VERTIPORT_LEAD_SECONDS};
+use super::operating_hours::{parse_hours_overrides, HoursOverride};
+use super::pricing::{parse_pricing_schedule, PricingWindow};
+use super::reservation::{parse_vertipad_reservations, VertipadReservation};
+use super::schedule::Timeslot;
+use super::timezone::parse_vertiport_timezones;
+use super::vehicle_model::{parse_vehicle_models, VehicleModels};
+use super::weather::{parse_weather_cells, WeatherCell};
+use crate::config::Config;
+use lib_common::time::chrono_tz::Tz;
+use lib_common::time::{DateTime, Duration, Utc};
+use lib_common::uuid::to_uuid;
+use std::collections::HashMap;
+use std::fmt::{Display, Formatter, Result as FmtResult};
+use std::str::FromStr;
+use tokio::sync::OnceCell;
+
+/// Separates the entries of a setting
+pub const ENTRY_SEPARATOR: char = ',';
+
+/// Separates the fields of an entry
+pub const FIELD_SEPARATOR: char = '|';
+
+/// Separates the start and end of a period
+pub const INTERVAL_SEPARATOR: char = '/';
+
+/// A setting listing entries
+#[derive(Debug, PartialEq)]
+pub struct Setting {
+    /// Name of the environment variable
+    pub name: &'static str,
+
+    /// Format of an entry
+    pub format: &'static str,
+}
+
+/// Errors parsing a setting, naming the offending setting
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum SettingError {
+    /// An entry isn't formatted as the setting expects
+    Format(&'static Setting),
+
+    /// An entry has an invalid ID
+    Id(&'static Setting),
+
+    /// An entry has an invalid timestamp
+    Time(&'static Setting),
+
+    /// An entry ends before it starts
+    Timeslot(&'static Setting),
+
+    /// An entry has an invalid value
+    Value(&'static Setting),
+}
+
+impl Display for SettingError {
+    fn fmt(&self, f: &mut Formatter) -> FmtResult {
+        match self {
+            SettingError::Format(setting) => write!(
+                f,
+                "{} entry is not formatted as {}",
+                setting.name, setting.format
+            ),
+            SettingError::Id(setting) => write!(f, "{} entry has an invalid ID", setting.name),
+            SettingError::Time(setting) => {
+                write!(f, "{} entry has an invalid timestamp", setting.name)
+            }
+            SettingError::Timeslot(setting) => {
+                write!(f, "{} entry ends before it starts", setting.name)
+            }
+            SettingError::Value(setting) => {
+                write!(f, "{} entry has an invalid value", setting.name)
+            }
+        }
+    }
+}
+
+impl Setting {
+    /// Parses the given entries of the setting, skipping blank ones
+    pub fn parse_entries<'a, T>(
+        &'static self,
+        entries: impl Iterator<Item = &'a str>,
+        parse: impl FnMut(&'a str) -> Result<T, SettingError>,
+    ) -> Result<Vec<T>, SettingError> {
+        entries
+            .map(str::trim)
+            .filter(|entry| !entry.is_empty())
+            .map(parse)
+            .collect()
+    }
+
+    /// Parses the comma-separated entries of the setting
+    pub fn parse_list<'a, T>(
+        &'static self,
+        value: &'a str,
+        parse: impl FnMut(&'a str) -> Result<T, SettingError>,
+    ) -> Result<Vec<T>, SettingError> {
+        self.parse_entries(value.split(ENTRY_SEPARATOR), parse)
+    }
+
+    /// Splits an entry into `N` trimmed fields. The last field keeps
+    ///  any further separators.
+    pub fn split<'a, const N: usize>(
+        &'static self,
+        entry: &'a str,
+        separator: char,
+    ) -> Result<[&'a str; N], SettingError> {
+        let fields = entry
+            .splitn(N, separator)
+            .map(str::trim)
+            .collect::<Vec<&str>>();
+
+        <[&str; N]>::try_from(fields).map_err(|_| {
+            router_error!("Invalid {} entry: {entry}", self.name);
+            SettingError::Format(self)
+        })
+    }
+
+    /// Parses a UUID field
+    pub fn parse_id(&'static self, id: &str) -> Result<String, SettingError> {
+        to_uuid(id.trim()).map(|id| id.to_string()).ok_or_else(|| {
+            router_error!("Invalid {} ID: {id}", self.name);
+            SettingError::Id(self)
+        })
+    }
+
+    /// Parses a field holding a value
+    pub fn parse_value<T: FromStr>(&'static self, value: &str) -> Result<T, SettingError> {
+        value.trim().parse::<T>().map_err(|_| {
+            router_error!("Invalid {} value: {value}", self.name);
+            SettingError::Value(self)
+        })
+    }
+
+    /// Parses a field holding a number of seconds
+    pub fn parse_seconds(&'static self, seconds: &str) -> Result<Duration, SettingError> {
+        self.parse_value::<u32>(seconds)
+            .ok()
+            .and_then(|seconds| Duration::try_seconds(seconds.into()))
+            .ok_or(SettingError::Value(self))
+    }
+
+    /// Parses a field holding an RFC3339 `start/end` period
+    pub fn parse_period(&'static self, period: &str) -> Result<Timeslot, SettingError> {
+        let [start, end] = self.split::<2>(period, INTERVAL_SEPARATOR)?;
+        let parse = |time: &str| {
+            DateTime::<Utc>::from_str(time).map_err(|e| {
+                router_error!("Invalid {} time {time}: {e}", self.name);
+                SettingError::Time(self)
+            })
+        };
+
+        Timeslot::new(parse(start)?, parse(end)?).map_err(|e| {
+            router_error!("Invalid {} period {period}: {e}", self.name);
+            SettingError::Timeslot(self)
+        })
+    }
+}
+
+/// Settings of the configuration, parsed
+#[derive(Debug, Clone)]
+pub struct Settings {
+    /// Network-wide blackout periods
+    pub blackout_periods: Vec<Timeslot>,
+
+    /// Local timezones of vertiports, keyed by vertiport ID
+    pub vertiport_timezones: HashMap<String, Tz>,
+
+    /// Advance notice required by vertiports
+    pub lead_times: LeadTimes,
+
+    /// Adverse weather cells
+    pub weather_cells: Vec<WeatherCell>,
+
+    /// Vertipad slots reserved for scheduled services
+    pub vertipad_reservations: Vec<VertipadReservation>,
+
+    /// Hazardous cargo restrictions
+    pub hazard_restrictions: Vec<HazardRestriction>,
+
+    /// Operating-hours overrides of vertiports
+    pub hours_overrides: Vec<HoursOverride>,
+
+    /// Time-of-use pricing windows
+    pub pricing_schedule: Vec<PricingWindow>,
+
+    /// Vehicle models the scheduler knows
+    pub vehicle_models: VehicleModels,
+}
+
+impl Settings {
+    /// Parses the settings of a configuration
+    pub fn try_from_config(config: &Config) -> Result<Self, SettingError> {
+        let default_lead_time = Duration::try_seconds(config.default_vertiport_lead_seconds.into())
+            .ok_or(SettingError::Value(&DEFAULT_VERTIPORT_LEAD_SECONDS))?;
+
+        Ok(Settings {
+            blackout_periods: parse_blackout_periods(&config.blackout_periods)?,
+            vertiport_timezones: parse_vertiport_timezones(&config.vertiport_timezones)?,
+            lead_times: LeadTimes {
+                default: default_lead_time,
+                vertiports: parse_vertiport_lead_times(&config.vertiport_lead_times)?,
+            },
+            weather_cells: parse_weather_cells(&config.weather_cells)?,
+            vertipad_reservations: parse_vertipad_reservations(&config.vertipad_reservations)?,
+            hazard_restrictions: parse_hazard_restrictions(&config.hazard_restrictions)?,
+            hours_overrides: parse_hours_overrides(&config.vertiport_hours_overrides)?,
+            pricing_schedule: parse_pricing_schedule(&config.pricing_schedule)?,
+            vehicle_models: parse_vehicle_models(&config.vehicle_models)?,
+        })
+    }
+}
+
+/// Parsed settings of the global configuration
+static SETTINGS: OnceCell<Settings> = OnceCell::const_new();
+
+/// Parses the settings of the configuration, unless they are parsed
+///  already. Called on startup, so that the service doesn't start with
+///  a malformed setting.
+pub async fn init_settings(config: &Config) -> Result<&'static Settings, SettingError> {
+    SETTINGS
+        .get_or_try_init(|| async { Settings::try_from_config(config) })
+        .await
+}
+
+/// Returns the parsed settings of the global configuration
+pub async fn get_settings() -> Result<&'static Settings, SettingError> {
+    init_settings(crate::config::get_config().await).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Setting to test the parser with
+    static TEST_SETTING: Setting = Setting {
+        name: "TEST_SETTING",
+        format: "id|seconds",
+    };
+
+    #[test]
+    fn test_parse_list() {
+        let parse = |entry| TEST_SETTING.split::<2>(entry, FIELD_SEPARATOR);
+        assert!(TEST_SETTING.parse_list("", parse).unwrap().is_empty());
+        assert_eq!(
+            TEST_SETTING.parse_list(" a|1, ,b|2|3 ", parse).unwrap(),
+            vec![["a", "1"], ["b", "2|3"]]
+        );
+
+        let e = TEST_SETTING.parse_list("a|1,b", parse).unwrap_err();
+        assert_eq!(e, SettingError::Format(&TEST_SETTING));
+    }
+
+    #[test]
+    fn test_parse_fields() {
+        let id = lib_common::uuid::Uuid::new_v4().to_string();
+        assert_eq!(TEST_SETTING.parse_id(&format!(" {id} ")).unwrap(), id);
+        assert_eq!(
+            TEST_SETTING.parse_id("invalid").unwrap_err(),
+            SettingError::Id(&TEST_SETTING)
+        );
+
+        assert_eq!(
+            TEST_SETTING.parse_seconds("600").unwrap(),
+            Duration::try_minutes(10).unwrap()
+        );
+        assert_eq!(
+            TEST_SETTING.parse_seconds("-1").unwrap_err(),
+            SettingError::Value(&TEST_SETTING)
+        );
+
+        let timeslot = TEST_SETTING
+            .parse_period("2024-01-01T08:00:00Z/2024-01-01T10:00:00Z")
+            .unwrap();
+        assert_eq!(
+            timeslot.time_end() - timeslot.time_start(),
+            Duration::try_hours(2).unwrap()
+        );
+        assert_eq!(
+            TEST_SETTING
+                .parse_period("2024-01-01T08:00:00Z")
+                .unwrap_err(),
+            SettingError::Format(&TEST_SETTING)
+        );
+        assert_eq!(
+            TEST_SETTING
+                .parse_period("yesterday/2024-01-01T10:00:00Z")
+                .unwrap_err(),
+            SettingError::Time(&TEST_SETTING)
+        );
+        assert_eq!(
+            TEST_SETTING
+                .parse_period("2024-01-01T10:00:00Z/2024-01-01T08:00:00Z")
+                .unwrap_err(),
+            SettingError::Timeslot(&TEST_SETTING)
+        );
+    }
+
+    #[test]
+    fn test_settings_try_from_config() {
+        let settings = Settings::try_from_config(&Config::default()).unwrap();
+        assert!(settings.blackout_periods.is_empty());
+        assert!(settings.weather_cells.is_empty());
+
+        // a malformed setting is reported by name
+        let config = Config {
+            weather_cells: "invalid".to_string(),
+            ..Config::default()
+        };
+        let e = Settings::try_from_config(&config).unwrap_err();
+        assert_eq!(
+            e,
+            SettingError::Format(&crate::router::weather::WEATHER_CELLS)
+        );
+
+        let config = Config {
+            blackout_periods: "invalid".to_string(),
+            ..Config::default()
+        };
+        let e = Settings::try_from_config(&config).unwrap_err();
+        assert_eq!(e, SettingError::Format(&BLACKOUT_PERIODS));
+    }
+
+    #[test]
+    fn test_setting_error_display() {
+        assert_eq!(
+            format!("{}", SettingError::Format(&TEST_SETTING)),
+            "TEST_SETTING entry is not formatted as id|seconds"
+        );
+        assert_eq!(
+            format!("{}", SettingError::Id(&TEST_SETTING)),
+            "TEST_SETTING entry has an invalid ID"
+        );
+        assert_eq!(
+            format!("{}", SettingError::Time(&TEST_SETTING)),
+            "TEST_SETTING entry has an invalid timestamp"
+        );
+        assert_eq!(
+            format!("{}", SettingError::Timeslot(&TEST_SETTING)),
+            "TEST_SETTING entry ends before it starts"
+        );
+        assert_eq!(
+            format!("{}", SettingError::Value(&TEST_SETTING)),
+            "TEST_SETTING entry has an invalid value"
+        );
+    }
+}
@@ -0,0 +1,640 @@
+//! Simulates flight queries against in-memory data, for load tests,
+//!  CI and benchmarks.
+//! The `stub_backends` feature stubs the svc-storage and svc-gis clients;
+//!  a simulation doesn't use the clients at all. Vertiports, aircraft and
+//!  flight plans are provided up front and paths come from a [`PathFinder`].
+//! Network-wide blackouts, operating-hours overrides, vertiport lead
+//!  times, held returns and pricing aren't simulated.
+
+use super::flight_plan::FlightPlanSchedule;
+use super::itinerary::{
+    calculate_itineraries, DeadheadSearch, ItineraryError, ParkingLimits, ReturnPolicy,
+};
+use super::lead_time::LeadTimes;
+use super::vehicle::*;
+use super::vehicle_model::get_vehicle_models;
+use super::vertiport::{
+    find_vertipad_timeslot_pairs, get_distance_meters, get_unoccupied_timeslots,
+    TimeslotPairLimits, VertiportError,
+};
+use crate::clock::SystemClock;
+use lib_common::time::{DateTime, Duration, Utc};
+use std::collections::HashMap;
+use std::str::FromStr;
+use svc_storage_client_grpc::prelude::{flight_plan, vehicle};
+
+pub use super::itinerary::ItineraryOption;
+pub use super::schedule::Timeslot;
+pub use super::units::Meters;
+pub use super::{BestPathError, PathFinder};
+pub use svc_gis_client_grpc::client::PointZ;
+pub use svc_gis_client_grpc::prelude::gis::BestPathRequest;
+pub use tonic::async_trait;
+
+/// Errors that may occur while simulating a query
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum SimulationError {
+    /// The scenario or query was invalid
+    Data,
+
+    /// The router could not be reached
+    Router,
+
+    /// An internal error occurred
+    Internal,
+}
+
+impl std::fmt::Display for SimulationError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            SimulationError::Data => write!(f, "Invalid data."),
+            SimulationError::Router => write!(f, "Could not contact router."),
+            SimulationError::Internal => write!(f, "Internal error."),
+        }
+    }
+}
+
+/// The vertiports, aircraft and bookings a query is simulated against
+#[derive(Debug, Clone, Default)]
+pub struct Scenario {
+    /// Open timeslots of each vertipad, keyed by vertiport ID then vertipad ID
+    pub vertiports: HashMap<String, HashMap<String, Vec<Timeslot>>>,
+
+    /// Aircraft, as stored in svc-storage
+    pub vehicles: Vec<vehicle::Object>,
+
+    /// Booked flight plans
+    pub flight_plans: Vec<flight_plan::Data>,
+}
+
+/// A flight query to simulate
+#[derive(Debug, Clone)]
+pub struct SimulatedQuery {
+    /// Vertiport to depart from
+    pub origin_vertiport_id: String,
+
+    /// Vertiport to arrive at
+    pub target_vertiport_id: String,
+
+    /// Earliest time of departure
+    pub earliest_departure_time: DateTime<Utc>,
+
+    /// Latest time of arrival
+    pub latest_arrival_time: DateTime<Utc>,
+
+    /// Time needed to load and take off
+    pub required_loading_time: Duration,
+
+    /// Time needed to land and unload
+    pub required_unloading_time: Duration,
+}
+
+/// A stubbed router flying straight between vertiports
+#[derive(Debug, Clone, Default)]
+pub struct StraightLineRouter {
+    /// Location of each vertiport, keyed by vertiport ID
+    pub locations: HashMap<String, PointZ>,
+}
+
+#[async_trait]
+impl PathFinder for StraightLineRouter {
+    async fn best_path(
+        &self,
+        request: &BestPathRequest,
+    ) -> Result<Vec<(Vec<PointZ>, Meters)>, BestPathError> {
+        let (Some(origin), Some(target)) = (
+            self.locations.get(&request.origin_identifier),
+            self.locations.get(&request.target_identifier),
+        ) else {
+            router_debug!(
+                "No location for {} or {}.",
+                request.origin_identifier,
+                request.target_identifier
+            );
+            return Err(BestPathError::NoPathFound);
+        };
+
+        let distance_meters = get_distance_meters(origin, target);
+        Ok(vec![(
+            vec![origin.clone(), target.clone()],
+            distance_meters,
+        )])
+    }
+}
+
+/// Gets the unoccupied timeslots of each vertipad at a vertiport of the
+///  scenario, within the query window
+fn get_vertipad_timeslots(
+    scenario: &Scenario,
+    vertiport_id: &str,
+    window: &Timeslot,
+    existing_flight_plans: &[FlightPlanSchedule],
+    minimum_duration: &Duration,
+) -> Result<HashMap<String, Vec<Timeslot>>, SimulationError> {
+    let Some(vertipads) = scenario.vertiports.get(vertiport_id) else {
+        router_warn!("Vertiport {vertiport_id} isn't part of the scenario.");
+        return Err(SimulationError::Data);
+    };
+
+    let timeslots = vertipads
+        .iter()
+        .map(|(vertipad_id, slots)| {
+            let slots = slots
+                .iter()
+                .filter_map(|slot| slot.overlap(window).ok())
+                .collect::<Vec<Timeslot>>();
+
+            (vertipad_id.clone(), slots)
+        })
+        .collect::<HashMap<String, Vec<Timeslot>>>();
+
+    get_unoccupied_timeslots(
+        vertiport_id,
+        timeslots,
+        existing_flight_plans,
+        minimum_duration,
+    )
+    .map_err(|e| {
+        router_error!("Could not get timeslots of vertiport {vertiport_id}: {e}");
+        match e {
+            VertiportError::Data => SimulationError::Data,
+            _ => SimulationError::Internal,
+        }
+    })
+}
+
+/// Simulates a flight query, returning the itineraries the scenario
+///  allows for. Settings are taken from the configuration as for a
+///  `query_flight` request.
+pub async fn simulate_query<P: PathFinder>(
+    scenario: &Scenario,
+    query: &SimulatedQuery,
+    router: &P,
+) -> Result<Vec<ItineraryOption>, SimulationError> {
+    let config = crate::config::get_config().await;
+    let timeslot = Timeslot::new(query.earliest_departure_time, query.latest_arrival_time)
+        .map_err(|e| {
+            router_warn!("Invalid query window: {e}");
+            SimulationError::Data
+        })?;
+
+    let mut existing_flight_plans = scenario
+        .flight_plans
+        .iter()
+        .cloned()
+        .map(FlightPlanSchedule::try_from)
+        .collect::<Result<Vec<FlightPlanSchedule>, _>>()
+        .map_err(|e| {
+            router_warn!("Invalid flight plan in scenario: {e}");
+            SimulationError::Data
+        })?;
+    existing_flight_plans.sort();
+
+    let origin_timeslots = get_vertipad_timeslots(
+        scenario,
+        &query.origin_vertiport_id,
+        &timeslot,
+        &existing_flight_plans,
+        &query.required_loading_time,
+    )?;
+
+    let target_timeslots = get_vertipad_timeslots(
+        scenario,
+        &query.target_vertiport_id,
+        &timeslot,
+        &existing_flight_plans,
+        &query.required_unloading_time,
+    )?;
+
+    let limits = TimeslotPairLimits {
+        max_checks: config.max_timeslot_pair_checks,
+        max_pairs: config.max_timeslot_pairs,
+        min_origin_window_seconds: config.min_origin_window_seconds,
+        distance_tolerance: Meters::from_millimeters(config.distance_tolerance_millimeters),
+    };
+
+    let timeslot_pairs = find_vertipad_timeslot_pairs(
+        &query.origin_vertiport_id,
+        &query.target_vertiport_id,
+        origin_timeslots,
+        target_timeslots,
+        None,
+        config.max_routes,
+        &limits,
+        |request| async move { router.best_path(&request).await },
+    )
+    .await
+    .map_err(|e| {
+        router_error!("Could not get timeslot pairs: {e}");
+        match e {
+            VertiportError::ClientError => SimulationError::Router,
+            _ => SimulationError::Internal,
+        }
+    })?;
+
+    let models = get_vehicle_models().await.map_err(|e| {
+        router_error!("Could not get vehicle models: {e}");
+        SimulationError::Internal
+    })?;
+
+    // Vehicles that aren't valid aircraft are skipped, as when read from storage
+    let aircraft = scenario
+        .vehicles
+        .iter()
+        .cloned()
+        .filter_map(|v| Aircraft::try_from((v, &models)).ok())
+        .collect::<Vec<Aircraft>>();
+
+    let connection_grace = Duration::try_minutes(config.connection_grace_minutes.into())
+        .ok_or_else(|| {
+            router_error!("error creating connection grace duration.");
+            SimulationError::Internal
+        })?;
+
+    let aircraft_gaps = get_aircraft_availabilities(
+        &existing_flight_plans,
+        &timeslot.time_start(),
+        &aircraft,
+        &timeslot,
+        &connection_grace,
+    )
+    .map_err(|e| {
+        router_error!("Could not get aircraft availabilities: {e}");
+        SimulationError::Internal
+    })?;
+
+    let return_policy = ReturnPolicy::from_str(&config.return_deadhead_policy)
+        .map_err(|_| SimulationError::Internal)?;
+
+    // Aircraft may only be parked on destination vertipads free until
+    //  the end of the searched availabilities
+    let parking = match return_policy {
+        ReturnPolicy::Strict => None,
+        ReturnPolicy::Park => {
+            let horizon = get_availability_horizon(&timeslot).map_err(|e| {
+                router_error!("Could not get availability horizon: {e}");
+                SimulationError::Internal
+            })?;
+
+            let window = Timeslot::new(timeslot.time_start(), horizon).map_err(|e| {
+                router_error!("Invalid parking window: {e}");
+                SimulationError::Internal
+            })?;
+
+            Some(ParkingLimits {
+                horizon,
+                vertipad_timeslots: get_vertipad_timeslots(
+                    scenario,
+                    &query.target_vertiport_id,
+                    &window,
+                    &existing_flight_plans,
+                    &Duration::zero(),
+                )?,
+            })
+        }
+    };
+
+    let deadhead_search = DeadheadSearch {
+        enabled: config.enable_deadhead,
+        step: Duration::try_minutes(config.deadhead_search_step_minutes.into()).ok_or_else(
+            || {
+                router_error!("error creating deadhead search step duration.");
+                SimulationError::Internal
+            },
+        )?,
+        attempts: config.deadhead_search_attempts,
+        max_legs: config.max_deadhead_legs,
+        return_policy,
+        parking: parking.as_ref(),
+    };
+
+    let default_ground_time = GroundTime {
+        loading: query.required_loading_time,
+        unloading: query.required_unloading_time,
+    };
+    let aircraft_ground_times = get_aircraft_ground_times(&aircraft);
+    let aircraft_cruise_altitudes =
+        get_aircraft_cruise_altitudes(&aircraft, config.min_cruise_altitude_meters as f32);
+    let arrival_jitter = Duration::try_seconds(config.arrival_jitter_max_seconds.into())
+        .ok_or_else(|| {
+            router_error!("error creating arrival jitter duration.");
+            SimulationError::Internal
+        })?;
+
+    let itineraries = match calculate_itineraries(
+        &default_ground_time,
+        &aircraft_ground_times,
+        &aircraft_cruise_altitudes,
+        &timeslot_pairs,
+        &aircraft_gaps,
+        &deadhead_search,
+        &arrival_jitter,
+        &LeadTimes::default(),
+        &SystemClock,
+        router,
+    )
+    .await
+    {
+        Ok(itineraries) => itineraries,
+        // no flights, as in a live query
+        Err(ItineraryError::TooManyDeadheads) => vec![],
+        Err(e) => {
+            router_error!("Could not get itineraries: {e}");
+            return Err(match e {
+                ItineraryError::ClientError => SimulationError::Router,
+                _ => SimulationError::Internal,
+            });
+        }
+    };
+
+    router_info!("simulated {} itineraries.", itineraries.len());
+    Ok(itineraries)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use lib_common::uuid::Uuid;
+    use std::str::FromStr;
+
+    /// Two vertiports with one vertipad each and an aircraft parked
+    ///  at the origin, open for the whole day
+    fn get_scenario(start: DateTime<Utc>) -> (Scenario, SimulatedQuery) {
+        let origin_vertiport_id = Uuid::new_v4().to_string();
+        let target_vertiport_id = Uuid::new_v4().to_string();
+        let day = Timeslot::new(start, start + Duration::try_hours(24).unwrap()).unwrap();
+
+        let vertiports = HashMap::from([
+            (
+                origin_vertiport_id.clone(),
+                HashMap::from([(Uuid::new_v4().to_string(), vec![day])]),
+            ),
+            (
+                target_vertiport_id.clone(),
+                HashMap::from([(Uuid::new_v4().to_string(), vec![day])]),
+            ),
+        ]);
+
+        let vehicle = vehicle::Object {
+            id: Uuid::new_v4().to_string(),
+            data: Some(vehicle::Data {
+                schedule: Some(
+                    "DTSTART:20221020T180000Z;DURATION:PT24H
+                    RRULE:FREQ=DAILY"
+                        .to_owned(),
+                ),
+                hangar_id: Some(origin_vertiport_id.clone()),
+                hangar_bay_id: Some(Uuid::new_v4().to_string()),
+                ..Default::default()
+            }),
+        };
+
+        let scenario = Scenario {
+            vertiports,
+            vehicles: vec![vehicle],
+            flight_plans: vec![],
+        };
+
+        let query = SimulatedQuery {
+            origin_vertiport_id,
+            target_vertiport_id,
+            earliest_departure_time: start + Duration::try_hours(1).unwrap(),
+            latest_arrival_time: start + Duration::try_hours(3).unwrap(),
+            required_loading_time: Duration::try_minutes(1).unwrap(),
+            required_unloading_time: Duration::try_minutes(1).unwrap(),
+        };
+
+        (scenario, query)
+    }
+
+    fn get_router(query: &SimulatedQuery) -> StraightLineRouter {
+        let location = |latitude: f64| PointZ {
+            latitude,
+            longitude: 4.0,
+            altitude_meters: 100.0,
+        };
+
+        StraightLineRouter {
+            locations: HashMap::from([
+                (query.origin_vertiport_id.clone(), location(52.0)),
+                (query.target_vertiport_id.clone(), location(52.05)),
+            ]),
+        }
+    }
+
+    #[test]
+    fn test_simulation_error_display() {
+        assert_eq!(SimulationError::Data.to_string(), "Invalid data.");
+        assert_eq!(
+            SimulationError::Router.to_string(),
+            "Could not contact router."
+        );
+        assert_eq!(SimulationError::Internal.to_string(), "Internal error.");
+    }
+
+    #[tokio::test]
+    async fn test_straight_line_router() {
+        let start = DateTime::<Utc>::from_str("2030-01-01T08:00:00Z").unwrap();
+        let (_, query) = get_scenario(start);
+        let router = get_router(&query);
+
+        let mut request = BestPathRequest {
+            origin_identifier: query.origin_vertiport_id.clone(),
+            target_identifier: query.target_vertiport_id.clone(),
+            ..Default::default()
+        };
+        let paths = router.best_path(&request).await.unwrap();
+        assert_eq!(paths.len(), 1);
+        assert_eq!(paths[0].0.len(), 2);
+        assert!(paths[0].1 .0 > 5000.0 && paths[0].1 .0 < 6000.0);
+
+        request.target_identifier = Uuid::new_v4().to_string();
+        let e = router.best_path(&request).await.unwrap_err();
+        assert_eq!(e, BestPathError::NoPathFound);
+    }
+
+    #[test]
+    fn test_get_vertipad_timeslots() {
+        let start = DateTime::<Utc>::from_str("2030-01-01T08:00:00Z").unwrap();
+        let (scenario, query) = get_scenario(start);
+        let window =
+            Timeslot::new(query.earliest_departure_time, query.latest_arrival_time).unwrap();
+
+        // clipped to the query window
+        let timeslots = get_vertipad_timeslots(
+            &scenario,
+            &query.origin_vertiport_id,
+            &window,
+            &[],
+            &query.required_loading_time,
+        )
+        .unwrap();
+        assert_eq!(timeslots.len(), 1);
+        assert!(timeslots.values().all(|slots| slots == &vec![window]));
+
+        let e = get_vertipad_timeslots(
+            &scenario,
+            &Uuid::new_v4().to_string(),
+            &window,
+            &[],
+            &query.required_loading_time,
+        )
+        .unwrap_err();
+        assert_eq!(e, SimulationError::Data);
+    }
+
+    #[tokio::test]
+    async fn test_simulate_query() {
+        lib_common::logger::get_log_handle().await;
+        ut_info!("start");
+
+        // away from the 18:00 UTC boundary of the aircraft calendars
+        let start = DateTime::<Utc>::from_str("2030-01-01T08:00:00Z").unwrap();
+        let (scenario, query) = get_scenario(start);
+        let router = get_router(&query);
+
+        let itineraries = simulate_query(&scenario, &query, &router).await.unwrap();
+        assert!(!itineraries.is_empty());
+
+        let flight_plan = &itineraries[0].flight_plans[0];
+        assert_eq!(flight_plan.vehicle_id, scenario.vehicles[0].id);
+        assert_eq!(
+            flight_plan.origin_vertiport_id,
+            Some(query.origin_vertiport_id.clone())
+        );
+        assert_eq!(
+            flight_plan.target_vertiport_id,
+            Some(query.target_vertiport_id.clone())
+        );
+
+        // unknown vertiport
+        let mut unknown = query.clone();
+        unknown.target_vertiport_id = Uuid::new_v4().to_string();
+        let e = simulate_query(&scenario, &unknown, &router)
+            .await
+            .unwrap_err();
+        assert_eq!(e, SimulationError::Data);
+
+        ut_info!("success");
+    }
+
+    #[tokio::test]
+    async fn test_simulate_query_cruise_altitude() {
+        lib_common::logger::get_log_handle().await;
+        ut_info!("start");
+
+        let start = DateTime::<Utc>::from_str("2030-01-01T08:00:00Z").unwrap();
+        let (mut scenario, query) = get_scenario(start);
+        let router = get_router(&query);
+
+        // parked at the target, deadheads to the origin first
+        let vehicle = scenario.vehicles[0].data.as_mut().unwrap();
+        vehicle.hangar_id = Some(query.target_vertiport_id.clone());
+
+        let itineraries = simulate_query(&scenario, &query, &router).await.unwrap();
+        assert!(!itineraries.is_empty());
+        assert_eq!(itineraries[0].flight_plans.len(), 2);
+
+        // the router's waypoints are at 100 meters and the cargo
+        //  aircraft cruises higher, but a straight route has no points
+        //  between the pads, which keep their altitudes
+        for flight_plan in itineraries[0].flight_plans.iter() {
+            let points = &flight_plan.path.as_ref().unwrap().points;
+            assert_eq!(points.len(), 2);
+            assert!(points.iter().all(|p| p.altitude == 100.0));
+        }
+
+        ut_info!("success");
+    }
+
+    #[tokio::test]
+    #[cfg(feature = "stub_backends")]
+    async fn ut_simulate_query_matches_stub_backends() {
+        use crate::grpc::client::get_clients;
+        use crate::router::vertiport::get_vertipad_timeslot_pairs;
+
+        lib_common::logger::get_log_handle().await;
+        ut_info!("start");
+
+        let start = DateTime::<Utc>::from_str("2030-01-01T08:00:00Z").unwrap();
+        let (scenario, query) = get_scenario(start);
+        let config = crate::config::get_config().await;
+        let clients = get_clients().await;
+
+        // The full path, routed by the stubbed svc-gis client.
+        //  Storage would return the vertipad timeslots within the window.
+        let window =
+            Timeslot::new(query.earliest_departure_time, query.latest_arrival_time).unwrap();
+        let get_open_vertipads = |vertiport_id: &String| {
+            scenario.vertiports[vertiport_id]
+                .keys()
+                .map(|vertipad_id| (vertipad_id.clone(), vec![window]))
+                .collect::<HashMap<String, Vec<Timeslot>>>()
+        };
+        let timeslot_pairs = get_vertipad_timeslot_pairs(
+            &query.origin_vertiport_id,
+            &query.target_vertiport_id,
+            get_open_vertipads(&query.origin_vertiport_id),
+            get_open_vertipads(&query.target_vertiport_id),
+            None,
+            config.max_routes,
+            clients,
+        )
+        .await
+        .unwrap();
+
+        let models = crate::test_util::get_test_vehicle_models();
+        let aircraft = scenario
+            .vehicles
+            .iter()
+            .cloned()
+            .map(|v| Aircraft::try_from((v, &models)).unwrap())
+            .collect::<Vec<Aircraft>>();
+        let aircraft_gaps = get_aircraft_availabilities(
+            &[],
+            &window.time_start(),
+            &aircraft,
+            &window,
+            &Duration::try_minutes(config.connection_grace_minutes.into()).unwrap(),
+        )
+        .unwrap();
+
+        let ground_time = GroundTime {
+            loading: query.required_loading_time,
+            unloading: query.required_unloading_time,
+        };
+        let deadhead_search = DeadheadSearch {
+            enabled: config.enable_deadhead,
+            step: Duration::try_minutes(config.deadhead_search_step_minutes.into()).unwrap(),
+            attempts: config.deadhead_search_attempts,
+            max_legs: config.max_deadhead_legs,
+            return_policy: ReturnPolicy::from_str(&config.return_deadhead_policy).unwrap(),
+            parking: None,
+        };
+        let expected = calculate_itineraries(
+            &ground_time,
+            &get_aircraft_ground_times(&aircraft),
+            &get_aircraft_cruise_altitudes(&aircraft, config.min_cruise_altitude_meters as f32),
+            &timeslot_pairs,
+            &aircraft_gaps,
+            &deadhead_search,
+            &Duration::try_seconds(config.arrival_jitter_max_seconds.into()).unwrap(),
+            &LeadTimes::default(),
+            &SystemClock,
+            clients,
+        )
+        .await
+        .unwrap();
+        assert!(!expected.is_empty());
+
+        // The simulation, with the same scenario and router
+        let itineraries = simulate_query(&scenario, &query, clients).await.unwrap();
+        assert_eq!(itineraries.len(), expected.len());
+        for (itinerary, expected) in itineraries.iter().zip(expected.iter()) {
+            assert_eq!(itinerary.flight_plans, expected.flight_plans);
+            assert_eq!(itinerary.alternative_paths, expected.alternative_paths);
+        }
+
+        ut_info!("success");
+    }
+}
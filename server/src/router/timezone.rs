@@ -0,0 +1,116 @@
+//! Local timezones of vertiports
+//! All scheduling is done in UTC. Vertiport timezones are only used to
+//!  present scheduled times in the local time of each vertiport.
+
+use crate::router::settings::{get_settings, Setting, SettingError, FIELD_SEPARATOR};
+use lib_common::time::chrono_tz::Tz;
+use lib_common::time::{DateTime, Utc};
+use std::collections::HashMap;
+
+/// Vertiport timezones setting
+pub static VERTIPORT_TIMEZONES: Setting = Setting {
+    name: "VERTIPORT_TIMEZONES",
+    format: "vertiport_id|timezone",
+};
+
+/// Timezone of vertiports without a configured timezone
+pub const DEFAULT_TIMEZONE: Tz = Tz::UTC;
+
+/// Parses comma-separated `vertiport_id|timezone` entries with IANA
+///  timezone names (e.g. `Europe/Amsterdam`)
+pub fn parse_vertiport_timezones(timezones: &str) -> Result<HashMap<String, Tz>, SettingError> {
+    let entries = VERTIPORT_TIMEZONES.parse_list(timezones, |entry| {
+        let [vertiport_id, timezone] = VERTIPORT_TIMEZONES.split::<2>(entry, FIELD_SEPARATOR)?;
+        Ok((
+            VERTIPORT_TIMEZONES.parse_id(vertiport_id)?,
+            VERTIPORT_TIMEZONES.parse_value::<Tz>(timezone)?,
+        ))
+    })?;
+
+    Ok(entries.into_iter().collect())
+}
+
+/// Returns the timezone of a vertiport, UTC if none is configured
+pub fn get_timezone(timezones: &HashMap<String, Tz>, vertiport_id: &str) -> Tz {
+    timezones
+        .get(vertiport_id)
+        .copied()
+        .unwrap_or(DEFAULT_TIMEZONE)
+}
+
+/// Formats a time as RFC3339 with the UTC offset of the timezone
+pub fn to_local_time(time: DateTime<Utc>, timezone: Tz) -> String {
+    time.with_timezone(&timezone).to_rfc3339()
+}
+
+/// Returns the configured vertiport timezones
+pub async fn get_vertiport_timezones() -> Result<HashMap<String, Tz>, SettingError> {
+    Ok(get_settings().await?.vertiport_timezones.clone())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use lib_common::uuid::Uuid;
+    use std::str::FromStr;
+
+    #[test]
+    fn test_parse_vertiport_timezones() {
+        assert!(parse_vertiport_timezones("").unwrap().is_empty());
+
+        let vertiport_1 = Uuid::new_v4().to_string();
+        let vertiport_2 = Uuid::new_v4().to_string();
+        let timezones = parse_vertiport_timezones(&format!(
+            "{vertiport_1}|Europe/Amsterdam, {vertiport_2}|America/New_York"
+        ))
+        .unwrap();
+
+        assert_eq!(timezones.len(), 2);
+        assert_eq!(timezones[&vertiport_1], Tz::Europe__Amsterdam);
+        assert_eq!(timezones[&vertiport_2], Tz::America__New_York);
+
+        let e = parse_vertiport_timezones(&vertiport_1).unwrap_err();
+        assert_eq!(e, SettingError::Format(&VERTIPORT_TIMEZONES));
+
+        let e = parse_vertiport_timezones("invalid|Europe/Amsterdam").unwrap_err();
+        assert_eq!(e, SettingError::Id(&VERTIPORT_TIMEZONES));
+
+        let e = parse_vertiport_timezones(&format!("{vertiport_1}|Europe/Atlantis")).unwrap_err();
+        assert_eq!(e, SettingError::Value(&VERTIPORT_TIMEZONES));
+    }
+
+    #[test]
+    fn test_get_timezone() {
+        let vertiport_id = Uuid::new_v4().to_string();
+        let timezones = parse_vertiport_timezones(&format!("{vertiport_id}|Asia/Tokyo")).unwrap();
+
+        assert_eq!(get_timezone(&timezones, &vertiport_id), Tz::Asia__Tokyo);
+        assert_eq!(
+            get_timezone(&timezones, &Uuid::new_v4().to_string()),
+            DEFAULT_TIMEZONE
+        );
+    }
+
+    #[test]
+    fn test_to_local_time() {
+        let winter = DateTime::<Utc>::from_str("2024-01-15T12:00:00Z").unwrap();
+        let summer = DateTime::<Utc>::from_str("2024-07-15T12:00:00Z").unwrap();
+
+        assert_eq!(
+            to_local_time(winter, Tz::Europe__Amsterdam),
+            "2024-01-15T13:00:00+01:00"
+        );
+        assert_eq!(
+            to_local_time(summer, Tz::Europe__Amsterdam),
+            "2024-07-15T14:00:00+02:00"
+        );
+        assert_eq!(
+            to_local_time(winter, Tz::America__New_York),
+            "2024-01-15T07:00:00-05:00"
+        );
+        assert_eq!(
+            to_local_time(winter, DEFAULT_TIMEZONE),
+            "2024-01-15T12:00:00+00:00"
+        );
+    }
+}
@@ -0,0 +1,134 @@
+//! Typed units used by the routing pipeline
+
+use std::cmp::Ordering;
+use std::fmt::{Display, Formatter, Result as FmtResult};
+use std::iter::Sum;
+use std::ops::Add;
+
+/// Number of meters in a kilometer
+const METERS_PER_KILOMETER: f64 = 1000.0;
+
+/// Number of millimeters in a meter
+const MILLIMETERS_PER_METER: f64 = 1000.0;
+
+/// A distance in meters
+/// Distances from svc-gis and between waypoints are wrapped in this type
+///  so that meters and kilometers can't be mixed up.
+#[derive(Debug, Copy, Clone, Default, PartialEq, PartialOrd)]
+pub struct Meters(pub f64);
+
+impl Meters {
+    /// Creates a distance from kilometers
+    pub fn from_kilometers(kilometers: f64) -> Self {
+        Meters(kilometers * METERS_PER_KILOMETER)
+    }
+
+    /// Returns the distance in kilometers
+    pub fn as_kilometers(&self) -> f64 {
+        self.0 / METERS_PER_KILOMETER
+    }
+
+    /// Creates a distance from millimeters
+    pub fn from_millimeters(millimeters: u32) -> Self {
+        Meters(f64::from(millimeters) / MILLIMETERS_PER_METER)
+    }
+
+    /// Compares two distances, treating those within `tolerance` of
+    ///  each other as equal.
+    /// Distances are rounded to a multiple of the tolerance first so the
+    ///  comparison stays a total order, as sorting requires. A tolerance
+    ///  of zero compares the exact distances.
+    pub fn cmp_within(&self, other: &Meters, tolerance: Meters) -> Ordering {
+        if tolerance.0 <= 0.0 {
+            return self.0.total_cmp(&other.0);
+        }
+
+        let round = |meters: f64| (meters / tolerance.0).round();
+        round(self.0).total_cmp(&round(other.0))
+    }
+}
+
+impl From<f32> for Meters {
+    fn from(meters: f32) -> Self {
+        Meters(meters.into())
+    }
+}
+
+impl Add for Meters {
+    type Output = Meters;
+
+    fn add(self, other: Meters) -> Meters {
+        Meters(self.0 + other.0)
+    }
+}
+
+impl Sum for Meters {
+    fn sum<I: Iterator<Item = Meters>>(iter: I) -> Meters {
+        iter.fold(Meters::default(), |a, b| a + b)
+    }
+}
+
+impl Display for Meters {
+    fn fmt(&self, f: &mut Formatter) -> FmtResult {
+        write!(f, "{} m", self.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_meters_kilometers() {
+        assert_eq!(Meters::from_kilometers(1.5), Meters(1500.0));
+        assert_eq!(Meters(2500.0).as_kilometers(), 2.5);
+        assert_eq!(
+            Meters::from_kilometers(Meters(42.0).as_kilometers()),
+            Meters(42.0)
+        );
+    }
+
+    #[test]
+    fn test_meters_from_f32() {
+        assert_eq!(Meters::from(100.0_f32), Meters(100.0));
+    }
+
+    #[test]
+    fn test_meters_sum() {
+        let total: Meters = vec![Meters(1.0), Meters(2.0), Meters(3.5)]
+            .into_iter()
+            .sum();
+        assert_eq!(total, Meters(6.5));
+        assert_eq!(Meters(1.0) + Meters(2.0), Meters(3.0));
+
+        let empty: Meters = Vec::<Meters>::new().into_iter().sum();
+        assert_eq!(empty, Meters(0.0));
+    }
+
+    #[test]
+    fn test_meters_cmp_within() {
+        let tolerance = Meters::from_millimeters(1);
+        assert_eq!(tolerance, Meters(0.001));
+
+        // a nanometer apart
+        let a = Meters(1000.0);
+        let b = Meters(1000.0 + 1e-9);
+        assert_eq!(a.cmp_within(&b, tolerance), Ordering::Equal);
+        assert_eq!(b.cmp_within(&a, tolerance), Ordering::Equal);
+        assert_eq!(a.cmp_within(&b, Meters(0.0)), Ordering::Less);
+
+        assert_eq!(
+            Meters(1000.0).cmp_within(&Meters(1000.5), tolerance),
+            Ordering::Less
+        );
+        assert_eq!(
+            Meters(1001.0).cmp_within(&Meters(1000.0), tolerance),
+            Ordering::Greater
+        );
+    }
+
+    #[test]
+    fn test_meters_display() {
+        assert_eq!(format!("{}", Meters(12.5)), "12.5 m");
+    }
+}
@@ -1,24 +1,79 @@
-use crate::grpc::client::GrpcClients;
+use crate::grpc::client::{search_all_pages, GrpcClients};
 use crate::router::flight_plan::*;
 use crate::router::schedule::*;
+use crate::router::units::Meters;
+use crate::router::vehicle_model::{get_vehicle_models, VehicleModel, VehicleModels};
 use svc_storage_client_grpc::prelude::*;
 
 use lib_common::time::{DateTime, Duration, Utc};
 use lib_common::uuid::Uuid;
-use std::collections::HashMap;
+use std::cmp::max;
+use std::collections::{HashMap, HashSet};
+use std::future::Future;
 use std::str::FromStr;
+use std::sync::atomic::{AtomicU64, Ordering};
 
 /// Enum with all Aircraft types
-#[derive(Debug, Copy, Clone)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
 pub enum AircraftType {
     /// Cargo aircraft
     Cargo,
+
+    /// Passenger aircraft
+    Passenger,
 }
 
 /// TODO(R5): Hardcoded for the demo. This is solely used to
 ///  estimate a duration of a flight.
 const AVERAGE_CARGO_AIRCRAFT_CRUISE_VELOCITY_M_PER_S: f32 = 10.0;
 
+/// TODO(R5): Hardcoded for the demo. Furthest an aircraft can
+///  fly without recharging.
+const MAX_CARGO_AIRCRAFT_RANGE_METERS: f64 = 50_000.0;
+
+/// TODO(R5): Hardcoded for the demo. Preferred cruise altitudes
+///  of each aircraft type.
+const CARGO_AIRCRAFT_CRUISE_ALTITUDE_METERS: f32 = 120.0;
+const PASSENGER_AIRCRAFT_CRUISE_ALTITUDE_METERS: f32 = 300.0;
+
+/// Aircraft availabilities are searched this long before and after a
+///  timeslot, leaving time for deadhead flights
+const DEADHEAD_PADDING_HOURS: i64 = 2;
+
+/// Number of vehicles found with a missing or invalid schedule
+static INVALID_SCHEDULE_VEHICLES: AtomicU64 = AtomicU64::new(0);
+
+/// How vehicles with a missing or invalid schedule are handled
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum SchedulePolicy {
+    /// Fail the whole request
+    Strict,
+
+    /// Skip the vehicle with a warning
+    Lenient,
+}
+
+impl FromStr for SchedulePolicy {
+    type Err = VehicleError;
+
+    fn from_str(policy: &str) -> Result<Self, Self::Err> {
+        match policy {
+            "strict" => Ok(SchedulePolicy::Strict),
+            "lenient" => Ok(SchedulePolicy::Lenient),
+            _ => {
+                router_error!("Unknown invalid schedule policy: {policy}");
+                Err(VehicleError::Internal)
+            }
+        }
+    }
+}
+
+/// Returns the number of vehicles found with a missing or invalid
+///  schedule since the service started, whatever the policy
+pub fn get_invalid_schedule_count() -> u64 {
+    INVALID_SCHEDULE_VEHICLES.load(Ordering::Relaxed)
+}
+
 /// Reasons for unavailable aircraft
 #[derive(Debug, PartialEq, Copy, Clone)]
 pub enum VehicleError {
@@ -43,6 +98,9 @@ pub enum VehicleError {
     /// Vehicle has an invalid schedule
     Schedule,
 
+    /// Vehicle model isn't known
+    Model,
+
     /// Internal error
     Internal,
 }
@@ -57,6 +115,7 @@ impl std::fmt::Display for VehicleError {
             VehicleError::HangarBayId => write!(f, "Vehicle doesn't have a hangar_bay_id"),
             VehicleError::NoSchedule => write!(f, "Vehicle doesn't have a schedule"),
             VehicleError::Schedule => write!(f, "Vehicle has an invalid schedule"),
+            VehicleError::Model => write!(f, "Vehicle model is not known"),
             VehicleError::Internal => write!(f, "Internal error"),
         }
     }
@@ -65,11 +124,22 @@ impl std::fmt::Display for VehicleError {
 #[derive(Debug, Clone)]
 pub struct Aircraft {
     vehicle_uuid: String,
-    vehicle_calendar: Calendar,
+    model: VehicleModel,
+    vehicle_calendar: Schedule,
     hangar_id: String,
     hangar_bay_id: String,
 }
 
+/// Time an aircraft blocks a vertipad before takeoff and after landing
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct GroundTime {
+    /// Time to load and take off
+    pub loading: Duration,
+
+    /// Time to land and unload
+    pub unloading: Duration,
+}
+
 #[derive(Clone, Debug, PartialEq)]
 pub struct Availability {
     pub timeslot: Timeslot,
@@ -78,12 +148,18 @@ pub struct Availability {
 }
 
 impl Availability {
-    fn subtract(&self, flight_plan: &FlightPlanSchedule) -> Vec<Self> {
+    /// Removes the time occupied by a flight plan from this availability.
+    /// An aircraft landing within `connection_grace` of the end of the
+    ///  flight plan is treated as already parked at the target vertiport.
+    fn subtract(&self, flight_plan: &FlightPlanSchedule, connection_grace: &Duration) -> Vec<Self> {
         let mut slots = vec![];
 
         let Ok(flight_plan_timeslot) = Timeslot::new(
             flight_plan.origin_timeslot_start,
-            flight_plan.target_timeslot_start,
+            max(
+                flight_plan.origin_timeslot_start,
+                flight_plan.target_timeslot_start - *connection_grace,
+            ),
         ) else {
             router_error!(
                 "Invalid flight plan timeslot, returning no availabilities: {:?} {:?}",
@@ -122,10 +198,12 @@ impl Availability {
     }
 }
 
-impl TryFrom<vehicle::Object> for Aircraft {
+impl<'a> TryFrom<(vehicle::Object, &'a VehicleModels)> for Aircraft {
     type Error = VehicleError;
 
-    fn try_from(vehicle: vehicle::Object) -> Result<Self, VehicleError> {
+    fn try_from(
+        (vehicle, models): (vehicle::Object, &'a VehicleModels),
+    ) -> Result<Self, VehicleError> {
         let vehicle_uuid = Uuid::parse_str(&vehicle.id)
             .map_err(|e| {
                 router_error!("Vehicle {} has invalid UUID: {}", vehicle.id, e);
@@ -138,8 +216,23 @@ impl TryFrom<vehicle::Object> for Aircraft {
             VehicleError::Data
         })?;
 
+        // The type, ground times and payload of the aircraft depend on its model
+        let model = models.get(&data.vehicle_model_id).cloned().ok_or_else(|| {
+            router_warn!(
+                "Vehicle {} has unknown model {}, unavailable for scheduling.",
+                vehicle_uuid,
+                data.vehicle_model_id
+            );
+            VehicleError::Model
+        })?;
+
+        // Freshly added vehicles may not have a known location yet,
+        //  they can't be scheduled until they are assigned a hangar
         let hangar_id = data.hangar_id.clone().ok_or_else(|| {
-            router_error!("Vehicle {} doesn't have hangar_id.", vehicle_uuid);
+            router_warn!(
+                "Vehicle {} doesn't have hangar_id, unavailable for scheduling.",
+                vehicle_uuid
+            );
             VehicleError::HangarId
         })?;
 
@@ -152,7 +245,10 @@ impl TryFrom<vehicle::Object> for Aircraft {
             .to_string();
 
         let hangar_bay_id = data.hangar_bay_id.clone().ok_or_else(|| {
-            router_error!("Vehicle {} doesn't have hangar_bay_id.", vehicle_uuid);
+            router_warn!(
+                "Vehicle {} doesn't have hangar_bay_id, unavailable for scheduling.",
+                vehicle_uuid
+            );
             VehicleError::HangarBayId
         })?;
 
@@ -164,26 +260,20 @@ impl TryFrom<vehicle::Object> for Aircraft {
             })?
             .to_string();
 
-        let calendar = data.schedule.clone().ok_or_else(|| {
-            // If vehicle doesn't have a schedule, it is not available
-            //  MUST have a schedule to be a valid aircraft choice, even if the
-            //  schedule is 24/7. Must be explicit.
-            router_error!("Vehicle {} doesn't have a schedule.", vehicle_uuid);
-            VehicleError::NoSchedule
-        })?;
-
-        let vehicle_calendar = Calendar::from_str(&calendar).map_err(|e| {
-            router_debug!(
-                "Invalid schedule for vehicle {} {}; {e}",
-                vehicle_uuid,
-                calendar
-            );
-
-            VehicleError::Schedule
+        // If vehicle doesn't have a schedule, it is not available
+        //  MUST have a schedule to be a valid aircraft choice, even if the
+        //  schedule is 24/7. Must be explicit.
+        let vehicle_calendar = Schedule::try_from(data.schedule.clone()).map_err(|e| {
+            router_error!("Vehicle {} schedule error: {e}", vehicle_uuid);
+            match e {
+                ScheduleError::Missing => VehicleError::NoSchedule,
+                ScheduleError::Invalid => VehicleError::Schedule,
+            }
         })?;
 
         Ok(Aircraft {
             vehicle_uuid,
+            model,
             vehicle_calendar,
             hangar_id,
             hangar_bay_id,
@@ -209,49 +299,104 @@ pub async fn get_aircraft(
     //  Need something to sort by, ascending distance from the
     //  departure vertiport or charge level before cutting off the list
 
-    let mut filter = match aircraft_id {
+    let filter = match aircraft_id {
         Some(id) => AdvancedSearchFilter::search_equals("vehicle_id".to_string(), id.to_string()),
         None => AdvancedSearchFilter::default(),
     };
 
-    filter.results_per_page = 1000;
+    let config = crate::config::get_config().await;
+    let policy = SchedulePolicy::from_str(&config.invalid_schedule_policy)?;
+    let models = get_vehicle_models().await.map_err(|e| {
+        router_error!("Could not get vehicle models: {e}");
+        VehicleError::Internal
+    })?;
+
+    search_aircraft(
+        filter,
+        config.storage_page_size,
+        policy,
+        &models,
+        |filter| async move {
+            clients
+                .storage()
+                .await
+                .vehicle
+                .search(filter)
+                .await
+                .map(|response| response.into_inner().list)
+        },
+    )
+    .await
+}
 
-    let response = clients
-        .storage
-        .vehicle
-        .search(filter)
+/// Loads every page of a vehicle search so that large fleets aren't cut
+///  off at the storage page size. Vehicles that aren't valid aircraft,
+///  including vehicles of unknown models, are skipped.
+/// Vehicles with a missing or invalid schedule are counted, then skipped
+///  or fail the search depending on the `policy`.
+async fn search_aircraft<E, F, Fut>(
+    filter: AdvancedSearchFilter,
+    page_size: u32,
+    policy: SchedulePolicy,
+    models: &VehicleModels,
+    search: F,
+) -> Result<Vec<Aircraft>, VehicleError>
+where
+    E: std::fmt::Display,
+    F: FnMut(AdvancedSearchFilter) -> Fut,
+    Fut: Future<Output = Result<Vec<vehicle::Object>, E>>,
+{
+    let vehicles = search_all_pages(filter, page_size, search)
         .await
         .map_err(|e| {
             router_error!("request to svc-storage failed: {e}");
             VehicleError::ClientError
-        })?
-        .into_inner()
-        .list
-        .into_iter()
-        .filter_map(|v| Aircraft::try_from(v).ok())
-        .collect();
+        })?;
 
-    Ok(response)
+    let mut aircraft = vec![];
+    for vehicle in vehicles {
+        let vehicle_id = vehicle.id.clone();
+        match Aircraft::try_from((vehicle, models)) {
+            Ok(a) => aircraft.push(a),
+            Err(e @ (VehicleError::NoSchedule | VehicleError::Schedule)) => {
+                let count = INVALID_SCHEDULE_VEHICLES.fetch_add(1, Ordering::Relaxed) + 1;
+                router_warn!("(metric) invalid_schedule_vehicles: {count}");
+
+                if policy == SchedulePolicy::Strict {
+                    router_error!("Vehicle {vehicle_id} can't be scheduled: {e}");
+                    return Err(e);
+                }
+
+                router_warn!("Skipping vehicle {vehicle_id}: {e}");
+            }
+            Err(_) => router_debug!("Skipping vehicle {vehicle_id}."),
+        }
+    }
+
+    router_debug!("found {} aircraft.", aircraft.len());
+    Ok(aircraft)
 }
 
 /// Estimates the time needed to travel between two locations including loading and unloading
 /// Estimate should be rather generous to block resources instead of potentially overloading them
-pub fn estimate_flight_time_seconds(distance_meters: &f64) -> Result<Duration, VehicleError> {
-    router_debug!("distance_meters: {}", *distance_meters);
+pub fn estimate_flight_time_seconds(distance: &Meters) -> Result<Duration, VehicleError> {
+    router_debug!("distance: {}", distance);
 
     let aircraft = AircraftType::Cargo; // TODO(R5): Hardcoded for demo
     router_debug!("aircraft: {:?}", aircraft);
 
     match aircraft {
-        AircraftType::Cargo => {
+        // TODO(R5): Passenger aircraft cruise velocity
+        AircraftType::Cargo | AircraftType::Passenger => {
             let liftoff_duration_s: f32 = 10.0; // TODO(R5): Calculate from altitude of corridor
             let landing_duration_s: f32 = 10.0; // TODO(R5): Calculate from altitude of corridor
 
             let cruise_duration_s: f32 =
-                (*distance_meters as f32) / AVERAGE_CARGO_AIRCRAFT_CRUISE_VELOCITY_M_PER_S;
+                (distance.0 as f32) / AVERAGE_CARGO_AIRCRAFT_CRUISE_VELOCITY_M_PER_S;
 
+            // Round up to whole seconds so scheduled times stay second-aligned
             let total_duration_s: f32 = liftoff_duration_s + cruise_duration_s + landing_duration_s;
-            Duration::try_milliseconds((total_duration_s * 1000.0) as i64).ok_or_else(|| {
+            Duration::try_seconds(total_duration_s.ceil() as i64).ok_or_else(|| {
                 router_error!("error creating time delta.");
                 VehicleError::Internal
             })
@@ -259,19 +404,104 @@ pub fn estimate_flight_time_seconds(distance_meters: &f64) -> Result<Duration, V
     }
 }
 
+/// Safety margin added on top of estimated flight times, so that the
+///  resources blocked for a flight are conservative
+#[derive(Debug, Copy, Clone, PartialEq, Default)]
+pub struct FlightTimeMargin {
+    /// Absolute margin in seconds
+    pub seconds: u32,
+
+    /// Relative margin in percent of the estimate
+    pub percent: u32,
+}
+
+impl FlightTimeMargin {
+    /// Adds the margin to a flight time estimate, the percentage first.
+    /// Rounds up to whole seconds so scheduled times stay second-aligned.
+    pub fn apply(&self, estimate: Duration) -> Result<Duration, VehicleError> {
+        let relative_s = (estimate.num_milliseconds() * self.percent as i64 + 99_999) / 100_000;
+        let margin = Duration::try_seconds(relative_s + self.seconds as i64).ok_or_else(|| {
+            router_error!("error creating time delta.");
+            VehicleError::Internal
+        })?;
+
+        Ok(estimate + margin)
+    }
+}
+
+/// Estimates the time a flight blocks resources for: the estimated
+///  flight time plus the configured safety margin
+pub async fn estimate_blocked_flight_time(distance: &Meters) -> Result<Duration, VehicleError> {
+    let config = crate::config::get_config().await;
+    let margin = FlightTimeMargin {
+        seconds: config.flight_time_margin_seconds,
+        percent: config.flight_time_margin_percent,
+    };
+
+    margin.apply(estimate_flight_time_seconds(distance)?)
+}
+
+/// Returns the furthest an aircraft of the type can fly without recharging
+pub fn get_aircraft_range(aircraft_type: AircraftType) -> Meters {
+    match aircraft_type {
+        // TODO(R5): Passenger aircraft range
+        AircraftType::Cargo | AircraftType::Passenger => Meters(MAX_CARGO_AIRCRAFT_RANGE_METERS),
+    }
+}
+
+/// Returns true if an aircraft can fly the distance without recharging
+pub fn is_within_range(distance: &Meters) -> bool {
+    // TODO(R5): Use the range of the vehicle model
+    distance.0 <= MAX_CARGO_AIRCRAFT_RANGE_METERS
+}
+
+/// Get the loading and unloading times of each aircraft, keyed by vehicle id.
+/// Ground times depend on the vehicle model of the aircraft.
+pub fn get_aircraft_ground_times(aircraft: &[Aircraft]) -> HashMap<String, GroundTime> {
+    aircraft
+        .iter()
+        .map(|a| (a.vehicle_uuid.clone(), a.model.ground_time))
+        .collect()
+}
+
+/// Get the preferred cruise altitude of each aircraft, keyed by vehicle id.
+/// Altitudes are never below `min_altitude_meters`, to keep clear of
+///  terrain and obstacles.
+pub fn get_aircraft_cruise_altitudes(
+    aircraft: &[Aircraft],
+    min_altitude_meters: f32,
+) -> HashMap<String, f32> {
+    aircraft
+        .iter()
+        .map(|a| {
+            // TODO(R5): Get the cruise altitude of the vehicle model
+            //  from svc-storage, hardcoded for the demo
+            let altitude_meters = match a.model.aircraft_type {
+                AircraftType::Cargo => CARGO_AIRCRAFT_CRUISE_ALTITUDE_METERS,
+                AircraftType::Passenger => PASSENGER_AIRCRAFT_CRUISE_ALTITUDE_METERS,
+            };
+
+            (
+                a.vehicle_uuid.clone(),
+                altitude_meters.max(min_altitude_meters),
+            )
+        })
+        .collect()
+}
+
 /// Build out a list of available aircraft (and their scheduled locations)
 ///  given a list of existing flight plans.
+/// Aircraft landing within `connection_grace` of a connection are considered
+///  available at their target vertiport.
 pub fn get_aircraft_availabilities(
     existing_flight_plans: &[FlightPlanSchedule],
     earliest_departure_time: &DateTime<Utc>,
     aircraft: &[Aircraft],
     timeslot: &Timeslot,
+    connection_grace: &Duration,
 ) -> Result<HashMap<String, Vec<Availability>>, VehicleError> {
     router_debug!("aircraft: {:?}", aircraft);
-    let deadhead_padding: Duration = Duration::try_hours(2).ok_or_else(|| {
-        router_error!("error creating time delta.");
-        VehicleError::Internal
-    })?;
+    let deadhead_padding = get_deadhead_padding()?;
 
     let mut aircraft_availabilities: HashMap<String, Vec<Availability>> = HashMap::new();
     for a in aircraft.iter() {
@@ -323,7 +553,7 @@ pub fn get_aircraft_availabilities(
         if let Some(availabilities) = aircraft_availabilities.get_mut(&fp.vehicle_id) {
             *availabilities = availabilities
                 .iter()
-                .flat_map(|a| a.subtract(fp))
+                .flat_map(|a| a.subtract(fp, connection_grace))
                 .collect::<Vec<Availability>>();
         } else {
             router_warn!("Flight plan for unknown aircraft: {}", fp.vehicle_id);
@@ -338,10 +568,232 @@ pub fn get_aircraft_availabilities(
     Ok(aircraft_availabilities)
 }
 
+/// Returns how long before and after a timeslot aircraft availabilities
+///  are searched
+fn get_deadhead_padding() -> Result<Duration, VehicleError> {
+    Duration::try_hours(DEADHEAD_PADDING_HOURS).ok_or_else(|| {
+        router_error!("error creating time delta.");
+        VehicleError::Internal
+    })
+}
+
+/// Returns the end of the aircraft availabilities searched for a
+///  timeslot. An availability lasting until then is open-ended: the
+///  aircraft isn't needed anywhere afterwards.
+pub fn get_availability_horizon(timeslot: &Timeslot) -> Result<DateTime<Utc>, VehicleError> {
+    Ok(timeslot.time_end() + get_deadhead_padding()?)
+}
+
+/// Removes availabilities at excluded vertiports, so that aircraft
+///  parked there aren't deadheaded out of or back into them.
+pub fn remove_excluded_vertiports(
+    aircraft_availabilities: &mut HashMap<String, Vec<Availability>>,
+    excluded_vertiport_ids: &HashSet<String>,
+) {
+    if excluded_vertiport_ids.is_empty() {
+        return;
+    }
+
+    aircraft_availabilities.retain(|aircraft_id, availabilities| {
+        availabilities.retain(|a| !excluded_vertiport_ids.contains(&a.vertiport_id));
+        if availabilities.is_empty() {
+            router_debug!("Aircraft {aircraft_id} only available at excluded vertiports.");
+            return false;
+        }
+
+        true
+    });
+}
+
+/// Keeps the `max_aircraft` aircraft best positioned for a query,
+///  so that large fleets don't slow down the search (0 for no limit).
+/// Aircraft are ranked by the distance from their first availability
+///  to the origin, nearest first, then by when that availability starts.
+/// Aircraft at vertiports with an unknown distance are ranked last.
+pub fn limit_aircraft_availabilities(
+    aircraft_availabilities: &mut HashMap<String, Vec<Availability>>,
+    distances: &HashMap<String, Meters>,
+    max_aircraft: u32,
+) {
+    let max_aircraft = max_aircraft as usize;
+    if max_aircraft == 0 || aircraft_availabilities.len() <= max_aircraft {
+        return;
+    }
+
+    let mut ranked: Vec<(f64, DateTime<Utc>, String)> = aircraft_availabilities
+        .iter()
+        .filter_map(|(aircraft_id, availabilities)| {
+            let first = availabilities
+                .iter()
+                .min_by_key(|a| a.timeslot.time_start())?;
+
+            let distance = distances
+                .get(&first.vertiport_id)
+                .map_or(f64::MAX, |meters| meters.0);
+
+            Some((distance, first.timeslot.time_start(), aircraft_id.clone()))
+        })
+        .collect();
+
+    ranked.sort_by(|a, b| {
+        a.0.total_cmp(&b.0)
+            .then_with(|| a.1.cmp(&b.1))
+            .then_with(|| a.2.cmp(&b.2))
+    });
+
+    let kept: HashSet<String> = ranked
+        .into_iter()
+        .take(max_aircraft)
+        .map(|(_, _, aircraft_id)| aircraft_id)
+        .collect();
+
+    router_debug!(
+        "Considering {} of {} aircraft.",
+        kept.len(),
+        aircraft_availabilities.len()
+    );
+
+    aircraft_availabilities.retain(|aircraft_id, _| kept.contains(aircraft_id));
+}
+
+/// Removes aircraft that aren't of the requested type.
+/// Without a requested type, all aircraft are kept.
+pub fn filter_aircraft_by_type(aircraft: &mut Vec<Aircraft>, aircraft_type: Option<AircraftType>) {
+    let Some(aircraft_type) = aircraft_type else {
+        return;
+    };
+
+    aircraft.retain(|a| {
+        if a.model.aircraft_type != aircraft_type {
+            router_debug!(
+                "Aircraft {} is {:?}, not {:?}.",
+                a.vehicle_uuid,
+                a.model.aircraft_type,
+                aircraft_type
+            );
+            return false;
+        }
+
+        true
+    });
+}
+
+/// Why an aircraft has no availability for a requested window
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum AircraftExclusion {
+    /// The aircraft's schedule doesn't cover the window
+    OffDuty,
+
+    /// The aircraft is on duty, but booked or only available at
+    ///  excluded vertiports
+    Unavailable,
+}
+
+/// Gets why each aircraft without any availabilities was excluded
+///  from the requested window
+pub fn get_aircraft_exclusions(
+    aircraft: &[Aircraft],
+    aircraft_availabilities: &HashMap<String, Vec<Availability>>,
+    timeslot: &Timeslot,
+) -> Result<HashMap<String, AircraftExclusion>, VehicleError> {
+    let mut exclusions = HashMap::new();
+    for a in aircraft.iter() {
+        let is_available = aircraft_availabilities
+            .get(&a.vehicle_uuid)
+            .is_some_and(|availabilities| !availabilities.is_empty());
+
+        if is_available {
+            continue;
+        }
+
+        let on_duty = a
+            .vehicle_calendar
+            .to_timeslots(&timeslot.time_start(), &timeslot.time_end())
+            .map_err(|e| {
+                router_error!("error creating timeslots: {}", e);
+                VehicleError::Internal
+            })?;
+
+        let exclusion = match on_duty.is_empty() {
+            true => AircraftExclusion::OffDuty,
+            false => AircraftExclusion::Unavailable,
+        };
+
+        router_debug!("Aircraft {} excluded: {:?}.", a.vehicle_uuid, exclusion);
+        exclusions.insert(a.vehicle_uuid.clone(), exclusion);
+    }
+
+    Ok(exclusions)
+}
+
+/// Where an aircraft is scheduled to be and the next flight it departs on
+#[derive(Debug, Clone, PartialEq)]
+pub struct AircraftStatus {
+    /// The aircraft's vehicle ID
+    pub vehicle_id: String,
+
+    /// Vertiport the aircraft is parked at or headed to
+    pub vertiport_id: String,
+
+    /// Vertipad the aircraft is parked at or headed to
+    pub vertipad_id: String,
+
+    /// First flight departing after the time of the status
+    pub next_flight_plan: Option<FlightPlanSchedule>,
+}
+
+/// Gets where an aircraft is scheduled to be at a given time.
+/// An aircraft that departed on a flight is reported at the flight's
+///  target, and otherwise at its hangar. Like
+///  [`get_aircraft_availabilities`], this assumes landed flights have
+///  updated the aircraft's hangar.
+/// `flight_plans` must be sorted by departure time.
+pub fn get_aircraft_status(
+    aircraft: &Aircraft,
+    flight_plans: &[FlightPlanSchedule],
+    time: &DateTime<Utc>,
+) -> AircraftStatus {
+    let mut status = AircraftStatus {
+        vehicle_id: aircraft.vehicle_uuid.clone(),
+        vertiport_id: aircraft.hangar_id.clone(),
+        vertipad_id: aircraft.hangar_bay_id.clone(),
+        next_flight_plan: None,
+    };
+
+    for fp in flight_plans
+        .iter()
+        .filter(|fp| fp.vehicle_id == aircraft.vehicle_uuid)
+    {
+        if fp.origin_timeslot_start > *time {
+            status.next_flight_plan = Some(fp.clone());
+            break;
+        }
+
+        status.vertiport_id = fp.target_vertiport_id.clone();
+        status.vertipad_id = fp.target_vertipad_id.clone();
+    }
+
+    status
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::test_util::get_test_vehicle_models;
     use lib_common::time::{Datelike, LocalResult, TimeZone, Utc};
+    use std::str::FromStr;
+
+    fn get_model(aircraft_type: AircraftType) -> VehicleModel {
+        VehicleModel {
+            name: format!("{aircraft_type:?} aircraft"),
+            aircraft_type,
+            ground_time: GroundTime {
+                loading: Duration::try_minutes(1).unwrap(),
+                unloading: Duration::try_minutes(1).unwrap(),
+            },
+            max_payload_grams: 20000,
+        }
+    }
 
     #[test]
     fn test_subtract_flight_plan() {
@@ -389,7 +841,7 @@ mod tests {
             },
         ];
 
-        let result = availability.subtract(&flight_plans[0]);
+        let result = availability.subtract(&flight_plans[0], &Duration::zero());
         assert_eq!(result.len(), 2);
         assert_eq!(
             result[0],
@@ -412,7 +864,7 @@ mod tests {
             }
         );
 
-        let result = availability.subtract(&flight_plans[1]);
+        let result = availability.subtract(&flight_plans[1], &Duration::zero());
         assert_eq!(result.len(), 2);
         assert_eq!(
             result[0],
@@ -442,7 +894,7 @@ mod tests {
         for fp in &flight_plans {
             availabilities = availabilities
                 .iter_mut()
-                .flat_map(|availability| availability.subtract(&fp))
+                .flat_map(|availability| availability.subtract(&fp, &Duration::zero()))
                 .collect::<Vec<Availability>>();
         }
 
@@ -484,7 +936,7 @@ mod tests {
     #[test]
     fn test_get_aircraft_availabilities() {
         let vehicle_duration_hours = 3;
-        let schedule = Calendar::from_str(&format!(
+        let schedule = Schedule::from_str(&format!(
             "DTSTART:20230920T000000Z;DURATION:PT{vehicle_duration_hours}H\n\
         RRULE:FREQ=DAILY;BYDAY=MO,TU,WE,TH,FR,SA,SU"
         ))
@@ -513,6 +965,7 @@ mod tests {
 
         let aircraft = vec![Aircraft {
             vehicle_uuid: aircraft_id.clone(),
+            model: get_model(AircraftType::Cargo),
             vehicle_calendar: schedule,
             hangar_id: vertiport_start_id.clone(),
             hangar_bay_id: vertipad_start_id.clone(),
@@ -552,6 +1005,7 @@ mod tests {
             &timeslot.time_start(),
             &aircraft,
             &timeslot,
+            &Duration::zero(),
         )
         .unwrap();
 
@@ -603,6 +1057,497 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_get_availability_horizon() {
+        let now = Utc::now();
+        let timeslot = Timeslot::new(now, now + Duration::try_hours(1).unwrap()).unwrap();
+        assert_eq!(
+            get_availability_horizon(&timeslot).unwrap(),
+            now + Duration::try_hours(1 + DEADHEAD_PADDING_HOURS).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_get_aircraft_availabilities_connection_grace() {
+        let schedule = Schedule::from_str(
+            "DTSTART:20230920T000000Z;DURATION:PT24H\n\
+        RRULE:FREQ=DAILY;BYDAY=MO,TU,WE,TH,FR,SA,SU",
+        )
+        .unwrap();
+
+        let year = Utc::now().year() + 1;
+        let LocalResult::Single(dt_start) = Utc.with_ymd_and_hms(year, 10, 20, 0, 0, 0) else {
+            panic!();
+        };
+
+        let vertiport_start_id = Uuid::new_v4().to_string();
+        let vertipad_start_id = Uuid::new_v4().to_string();
+        let vertiport_target_id = Uuid::new_v4().to_string();
+        let vertipad_target_id = Uuid::new_v4().to_string();
+        let aircraft_id = Uuid::new_v4().to_string();
+
+        let aircraft = vec![Aircraft {
+            vehicle_uuid: aircraft_id.clone(),
+            model: get_model(AircraftType::Cargo),
+            vehicle_calendar: schedule,
+            hangar_id: vertiport_start_id.clone(),
+            hangar_bay_id: vertipad_start_id.clone(),
+        }];
+
+        // Aircraft lands at the target vertiport 1 minute after the window opens
+        let timeslot = Timeslot::new(dt_start, dt_start + Duration::try_hours(1).unwrap()).unwrap();
+        let flight_plans = vec![FlightPlanSchedule {
+            vehicle_id: aircraft_id.clone(),
+            origin_vertiport_id: vertiport_start_id.clone(),
+            origin_vertipad_id: vertipad_start_id.clone(),
+            target_vertiport_id: vertiport_target_id.clone(),
+            target_vertipad_id: vertipad_target_id.clone(),
+            origin_timeslot_start: dt_start - Duration::try_minutes(10).unwrap(),
+            origin_timeslot_end: dt_start - Duration::try_minutes(10).unwrap(),
+            target_timeslot_start: dt_start + Duration::try_minutes(1).unwrap(),
+            target_timeslot_end: dt_start + Duration::try_minutes(1).unwrap(),
+            path: Some(vec![]),
+        }];
+
+        // Without grace, the aircraft isn't at the target vertiport until it lands
+        let gaps = get_aircraft_availabilities(
+            &flight_plans,
+            &timeslot.time_start(),
+            &aircraft,
+            &timeslot,
+            &Duration::zero(),
+        )
+        .unwrap();
+
+        let gaps = gaps.get(&aircraft_id).unwrap();
+        assert!(gaps
+            .iter()
+            .all(|gap| gap.timeslot.time_start() >= flight_plans[0].target_timeslot_start));
+
+        // With a 2 minute grace, the aircraft is considered parked at the window start
+        let gaps = get_aircraft_availabilities(
+            &flight_plans,
+            &timeslot.time_start(),
+            &aircraft,
+            &timeslot,
+            &Duration::try_minutes(2).unwrap(),
+        )
+        .unwrap();
+
+        let gaps = gaps.get(&aircraft_id).unwrap();
+        assert!(gaps.iter().any(|gap| {
+            gap.timeslot.time_start() == dt_start
+                && gap.vertiport_id == vertiport_target_id
+                && gap.vertipad_id == vertipad_target_id
+        }));
+    }
+
+    #[test]
+    fn test_filter_aircraft_by_type() {
+        let schedule = Schedule::from_str(
+            "DTSTART:20230920T000000Z;DURATION:PT24H\n\
+            RRULE:FREQ=DAILY;BYDAY=MO,TU,WE,TH,FR,SA,SU",
+        )
+        .unwrap();
+
+        let new_aircraft = |aircraft_type| Aircraft {
+            vehicle_uuid: Uuid::new_v4().to_string(),
+            model: get_model(aircraft_type),
+            vehicle_calendar: schedule.clone(),
+            hangar_id: Uuid::new_v4().to_string(),
+            hangar_bay_id: Uuid::new_v4().to_string(),
+        };
+
+        let cargo = new_aircraft(AircraftType::Cargo);
+        let passenger = new_aircraft(AircraftType::Passenger);
+        let fleet = vec![cargo.clone(), passenger.clone()];
+
+        // no filter, all types are eligible
+        let mut aircraft = fleet.clone();
+        filter_aircraft_by_type(&mut aircraft, None);
+        assert_eq!(aircraft.len(), 2);
+
+        // passenger request ignores cargo-only aircraft
+        let mut aircraft = fleet.clone();
+        filter_aircraft_by_type(&mut aircraft, Some(AircraftType::Passenger));
+        assert_eq!(aircraft.len(), 1);
+        assert_eq!(aircraft[0].vehicle_uuid, passenger.vehicle_uuid);
+
+        let mut aircraft = fleet;
+        filter_aircraft_by_type(&mut aircraft, Some(AircraftType::Cargo));
+        assert_eq!(aircraft.len(), 1);
+        assert_eq!(aircraft[0].vehicle_uuid, cargo.vehicle_uuid);
+    }
+
+    #[test]
+    fn test_get_aircraft_ground_times() {
+        let schedule = Schedule::from_str(
+            "DTSTART:20230920T000000Z;DURATION:PT24H\n\
+            RRULE:FREQ=DAILY;BYDAY=MO,TU,WE,TH,FR,SA,SU",
+        )
+        .unwrap();
+
+        let new_aircraft = |model| Aircraft {
+            vehicle_uuid: Uuid::new_v4().to_string(),
+            model,
+            vehicle_calendar: schedule.clone(),
+            hangar_id: Uuid::new_v4().to_string(),
+            hangar_bay_id: Uuid::new_v4().to_string(),
+        };
+
+        // a passenger model boarding slower than the cargo model
+        let cargo = new_aircraft(get_model(AircraftType::Cargo));
+        let passenger = new_aircraft(VehicleModel {
+            ground_time: GroundTime {
+                loading: Duration::try_minutes(5).unwrap(),
+                unloading: Duration::try_minutes(3).unwrap(),
+            },
+            ..get_model(AircraftType::Passenger)
+        });
+
+        let ground_times = get_aircraft_ground_times(&[cargo.clone(), passenger.clone()]);
+        assert_eq!(ground_times.len(), 2);
+        assert_eq!(ground_times[&cargo.vehicle_uuid], cargo.model.ground_time);
+        assert_eq!(
+            ground_times[&passenger.vehicle_uuid],
+            passenger.model.ground_time
+        );
+        assert_ne!(
+            ground_times[&cargo.vehicle_uuid],
+            ground_times[&passenger.vehicle_uuid]
+        );
+    }
+
+    #[test]
+    fn test_get_aircraft_cruise_altitudes() {
+        let schedule = Schedule::from_str(
+            "DTSTART:20230920T000000Z;DURATION:PT24H\n\
+            RRULE:FREQ=DAILY;BYDAY=MO,TU,WE,TH,FR,SA,SU",
+        )
+        .unwrap();
+
+        let new_aircraft = |aircraft_type| Aircraft {
+            vehicle_uuid: Uuid::new_v4().to_string(),
+            model: get_model(aircraft_type),
+            vehicle_calendar: schedule.clone(),
+            hangar_id: Uuid::new_v4().to_string(),
+            hangar_bay_id: Uuid::new_v4().to_string(),
+        };
+
+        let cargo = new_aircraft(AircraftType::Cargo);
+        let passenger = new_aircraft(AircraftType::Passenger);
+        let fleet = vec![cargo.clone(), passenger.clone()];
+
+        let altitudes = get_aircraft_cruise_altitudes(&fleet, 50.0);
+        assert_eq!(
+            altitudes.get(&cargo.vehicle_uuid),
+            Some(&CARGO_AIRCRAFT_CRUISE_ALTITUDE_METERS)
+        );
+        assert_eq!(
+            altitudes.get(&passenger.vehicle_uuid),
+            Some(&PASSENGER_AIRCRAFT_CRUISE_ALTITUDE_METERS)
+        );
+
+        // clamped to the minimum altitude
+        let altitudes = get_aircraft_cruise_altitudes(&fleet, 200.0);
+        assert_eq!(altitudes.get(&cargo.vehicle_uuid), Some(&200.0));
+        assert_eq!(
+            altitudes.get(&passenger.vehicle_uuid),
+            Some(&PASSENGER_AIRCRAFT_CRUISE_ALTITUDE_METERS)
+        );
+    }
+
+    #[test]
+    fn test_get_aircraft_exclusions() {
+        // on duty from 08:00 to 18:00 UTC
+        let schedule = Schedule::from_str(
+            "DTSTART:20230920T080000Z;DURATION:PT10H\n\
+            RRULE:FREQ=DAILY;BYDAY=MO,TU,WE,TH,FR,SA,SU",
+        )
+        .unwrap();
+
+        let new_aircraft = || Aircraft {
+            vehicle_uuid: Uuid::new_v4().to_string(),
+            model: get_model(AircraftType::Cargo),
+            vehicle_calendar: schedule.clone(),
+            hangar_id: Uuid::new_v4().to_string(),
+            hangar_bay_id: Uuid::new_v4().to_string(),
+        };
+        let aircraft = vec![new_aircraft(), new_aircraft()];
+        let grace = Duration::zero();
+
+        // after hours, every aircraft is off duty
+        let start = DateTime::<Utc>::from_str("2030-01-01T20:00:00Z").unwrap();
+        let timeslot = Timeslot::new(start, start + Duration::try_hours(1).unwrap()).unwrap();
+        let availabilities =
+            get_aircraft_availabilities(&[], &start, &aircraft, &timeslot, &grace).unwrap();
+        let exclusions = get_aircraft_exclusions(&aircraft, &availabilities, &timeslot).unwrap();
+        assert_eq!(exclusions.len(), 2);
+        assert!(exclusions
+            .values()
+            .all(|e| *e == AircraftExclusion::OffDuty));
+
+        // during operating hours, only the booked aircraft is excluded
+        let start = DateTime::<Utc>::from_str("2030-01-01T10:00:00Z").unwrap();
+        let timeslot = Timeslot::new(start, start + Duration::try_hours(1).unwrap()).unwrap();
+        let mut availabilities =
+            get_aircraft_availabilities(&[], &start, &aircraft, &timeslot, &grace).unwrap();
+        availabilities.insert(aircraft[0].vehicle_uuid.clone(), vec![]);
+        let exclusions = get_aircraft_exclusions(&aircraft, &availabilities, &timeslot).unwrap();
+        assert_eq!(exclusions.len(), 1);
+        assert_eq!(
+            exclusions.get(&aircraft[0].vehicle_uuid),
+            Some(&AircraftExclusion::Unavailable)
+        );
+    }
+
+    #[test]
+    fn test_limit_aircraft_availabilities() {
+        let now = Utc::now();
+        let origin_id = Uuid::new_v4().to_string();
+        let nearby_id = Uuid::new_v4().to_string();
+        let unknown_id = Uuid::new_v4().to_string();
+
+        let availability = |vertiport_id: &String, minutes: i64| Availability {
+            timeslot: Timeslot::new(
+                now + Duration::try_minutes(minutes).unwrap(),
+                now + Duration::try_hours(2).unwrap(),
+            )
+            .unwrap(),
+            vertiport_id: vertiport_id.clone(),
+            vertipad_id: Uuid::new_v4().to_string(),
+        };
+
+        let at_origin_late = Uuid::new_v4().to_string();
+        let at_origin_soon = Uuid::new_v4().to_string();
+        let nearby = Uuid::new_v4().to_string();
+        let unknown = Uuid::new_v4().to_string();
+        let availabilities = HashMap::from([
+            (at_origin_late.clone(), vec![availability(&origin_id, 30)]),
+            (at_origin_soon.clone(), vec![availability(&origin_id, 10)]),
+            (nearby.clone(), vec![availability(&nearby_id, 0)]),
+            (unknown.clone(), vec![availability(&unknown_id, 0)]),
+        ]);
+
+        let distances = HashMap::from([
+            (origin_id.clone(), Meters(0.0)),
+            (nearby_id.clone(), Meters(5000.0)),
+        ]);
+
+        // no limit, or fewer aircraft than the limit
+        let mut tmp = availabilities.clone();
+        limit_aircraft_availabilities(&mut tmp, &distances, 0);
+        assert_eq!(tmp, availabilities);
+        limit_aircraft_availabilities(&mut tmp, &distances, 4);
+        assert_eq!(tmp, availabilities);
+
+        // nearest first, then soonest available
+        let mut tmp = availabilities.clone();
+        limit_aircraft_availabilities(&mut tmp, &distances, 1);
+        assert_eq!(tmp.len(), 1);
+        assert!(tmp.contains_key(&at_origin_soon));
+
+        let mut tmp = availabilities.clone();
+        limit_aircraft_availabilities(&mut tmp, &distances, 3);
+        assert_eq!(tmp.len(), 3);
+        assert!(!tmp.contains_key(&unknown));
+
+        // without distances, the soonest available aircraft are kept
+        let mut tmp = availabilities;
+        limit_aircraft_availabilities(&mut tmp, &HashMap::new(), 2);
+        assert_eq!(tmp.len(), 2);
+        assert!(tmp.contains_key(&nearby));
+        assert!(tmp.contains_key(&unknown));
+    }
+
+    #[test]
+    fn test_remove_excluded_vertiports() {
+        let timeslot =
+            Timeslot::new(Utc::now(), Utc::now() + Duration::try_hours(1).unwrap()).unwrap();
+        let vertiport_open_id = Uuid::new_v4().to_string();
+        let vertiport_excluded_id = Uuid::new_v4().to_string();
+        let aircraft_1 = Uuid::new_v4().to_string();
+        let aircraft_2 = Uuid::new_v4().to_string();
+
+        let availability = |vertiport_id: &String| Availability {
+            timeslot,
+            vertiport_id: vertiport_id.clone(),
+            vertipad_id: Uuid::new_v4().to_string(),
+        };
+
+        let mut availabilities = HashMap::from([
+            (
+                aircraft_1.clone(),
+                vec![
+                    availability(&vertiport_excluded_id),
+                    availability(&vertiport_open_id),
+                ],
+            ),
+            (
+                aircraft_2.clone(),
+                vec![availability(&vertiport_excluded_id)],
+            ),
+        ]);
+
+        // nothing excluded
+        let mut tmp = availabilities.clone();
+        remove_excluded_vertiports(&mut tmp, &HashSet::new());
+        assert_eq!(tmp, availabilities);
+
+        let excluded = HashSet::from([vertiport_excluded_id.clone()]);
+        remove_excluded_vertiports(&mut availabilities, &excluded);
+
+        // aircraft only at the excluded vertiport is no longer a candidate
+        assert!(!availabilities.contains_key(&aircraft_2));
+
+        // other aircraft can only be routed from the open vertiport
+        let remaining = availabilities.get(&aircraft_1).unwrap();
+        assert_eq!(remaining.len(), 1);
+        assert_eq!(remaining[0].vertiport_id, vertiport_open_id);
+    }
+
+    #[test]
+    fn test_get_aircraft_status() {
+        let schedule = Schedule::from_str(
+            "DTSTART:20230920T000000Z;DURATION:PT24H\n\
+            RRULE:FREQ=DAILY;BYDAY=MO,TU,WE,TH,FR,SA,SU",
+        )
+        .unwrap();
+
+        let hangar_id = Uuid::new_v4().to_string();
+        let hangar_bay_id = Uuid::new_v4().to_string();
+        let target_vertiport_id = Uuid::new_v4().to_string();
+        let target_vertipad_id = Uuid::new_v4().to_string();
+        let aircraft = Aircraft {
+            vehicle_uuid: Uuid::new_v4().to_string(),
+            model: get_model(AircraftType::Cargo),
+            vehicle_calendar: schedule,
+            hangar_id: hangar_id.clone(),
+            hangar_bay_id: hangar_bay_id.clone(),
+        };
+
+        let departure = Utc::now() + Duration::try_hours(1).unwrap();
+        let flight_plans = vec![
+            // another aircraft's flight
+            FlightPlanSchedule {
+                vehicle_id: Uuid::new_v4().to_string(),
+                origin_vertiport_id: hangar_id.clone(),
+                origin_vertipad_id: hangar_bay_id.clone(),
+                target_vertiport_id: Uuid::new_v4().to_string(),
+                target_vertipad_id: Uuid::new_v4().to_string(),
+                origin_timeslot_start: departure - Duration::try_minutes(30).unwrap(),
+                origin_timeslot_end: departure - Duration::try_minutes(30).unwrap(),
+                target_timeslot_start: departure - Duration::try_minutes(20).unwrap(),
+                target_timeslot_end: departure - Duration::try_minutes(20).unwrap(),
+                path: None,
+            },
+            FlightPlanSchedule {
+                vehicle_id: aircraft.vehicle_uuid.clone(),
+                origin_vertiport_id: hangar_id.clone(),
+                origin_vertipad_id: hangar_bay_id.clone(),
+                target_vertiport_id: target_vertiport_id.clone(),
+                target_vertipad_id: target_vertipad_id.clone(),
+                origin_timeslot_start: departure,
+                origin_timeslot_end: departure,
+                target_timeslot_start: departure + Duration::try_minutes(10).unwrap(),
+                target_timeslot_end: departure + Duration::try_minutes(10).unwrap(),
+                path: None,
+            },
+        ];
+
+        // parked at its hangar until the future flight departs
+        let status = get_aircraft_status(&aircraft, &flight_plans, &Utc::now());
+        assert_eq!(status.vehicle_id, aircraft.vehicle_uuid);
+        assert_eq!(status.vertiport_id, hangar_id);
+        assert_eq!(status.vertipad_id, hangar_bay_id);
+        assert_eq!(
+            status.next_flight_plan.unwrap().target_vertiport_id,
+            target_vertiport_id
+        );
+
+        // predicted at the flight's target after departure
+        let later = departure + Duration::try_minutes(5).unwrap();
+        let status = get_aircraft_status(&aircraft, &flight_plans, &later);
+        assert_eq!(status.vertiport_id, target_vertiport_id);
+        assert_eq!(status.vertipad_id, target_vertipad_id);
+        assert!(status.next_flight_plan.is_none());
+
+        // no flights
+        let status = get_aircraft_status(&aircraft, &[], &later);
+        assert_eq!(status.vertiport_id, hangar_id);
+        assert!(status.next_flight_plan.is_none());
+    }
+
+    #[test]
+    fn test_estimate_flight_time_seconds() {
+        // 10s liftoff + 100s cruise + 10s landing
+        let expected = Duration::try_seconds(120).unwrap();
+        assert_eq!(
+            estimate_flight_time_seconds(&Meters(1000.0)).unwrap(),
+            expected
+        );
+        assert_eq!(
+            estimate_flight_time_seconds(&Meters::from_kilometers(1.0)).unwrap(),
+            expected
+        );
+    }
+
+    #[test]
+    fn test_flight_time_margin() {
+        let estimate = estimate_flight_time_seconds(&Meters(1000.0)).unwrap();
+
+        // no margin by default
+        assert_eq!(
+            FlightTimeMargin::default().apply(estimate).unwrap(),
+            estimate
+        );
+
+        let margin = FlightTimeMargin {
+            seconds: 30,
+            percent: 0,
+        };
+        let blocked = margin.apply(estimate).unwrap();
+        assert_eq!(blocked - estimate, Duration::try_seconds(30).unwrap());
+
+        // 10% of 120s, then 30s
+        let margin = FlightTimeMargin {
+            seconds: 30,
+            percent: 10,
+        };
+        let blocked = margin.apply(estimate).unwrap();
+        assert!(blocked > estimate);
+        assert_eq!(blocked - estimate, Duration::try_seconds(42).unwrap());
+
+        // relative margin rounds up to whole seconds
+        let margin = FlightTimeMargin {
+            seconds: 0,
+            percent: 1,
+        };
+        let blocked = margin.apply(estimate).unwrap();
+        assert_eq!(blocked - estimate, Duration::try_seconds(2).unwrap());
+    }
+
+    #[test]
+    fn test_get_aircraft_range() {
+        assert_eq!(
+            get_aircraft_range(AircraftType::Cargo),
+            Meters(MAX_CARGO_AIRCRAFT_RANGE_METERS)
+        );
+        assert_eq!(
+            get_aircraft_range(AircraftType::Passenger),
+            Meters(MAX_CARGO_AIRCRAFT_RANGE_METERS)
+        );
+    }
+
+    #[test]
+    fn test_is_within_range() {
+        assert!(is_within_range(&Meters(0.0)));
+        assert!(is_within_range(&Meters(MAX_CARGO_AIRCRAFT_RANGE_METERS)));
+        assert!(!is_within_range(&Meters(
+            MAX_CARGO_AIRCRAFT_RANGE_METERS + 1.0
+        )));
+    }
+
     #[test]
     fn test_vehicle_error_display() {
         assert_eq!(
@@ -633,9 +1578,168 @@ mod tests {
             format!("{}", VehicleError::Schedule),
             "Vehicle has an invalid schedule"
         );
+        assert_eq!(
+            format!("{}", VehicleError::Model),
+            "Vehicle model is not known"
+        );
         assert_eq!(format!("{}", VehicleError::Internal), "Internal error");
     }
 
+    #[tokio::test]
+    async fn test_search_aircraft_all_pages() {
+        const CAL_STR: &str = "DTSTART:20221020T180000Z;DURATION:PT14H
+            RRULE:FREQ=WEEKLY;BYDAY=MO,TU,WE,TH,FR";
+
+        // a fleet larger than a single page
+        let page_size = 10;
+        let vehicles: Vec<vehicle::Object> = (0..25)
+            .map(|_| vehicle::Object {
+                id: Uuid::new_v4().to_string(),
+                data: Some(vehicle::Data {
+                    schedule: Some(CAL_STR.to_owned()),
+                    hangar_id: Some(Uuid::new_v4().to_string()),
+                    hangar_bay_id: Some(Uuid::new_v4().to_string()),
+                    ..Default::default()
+                }),
+            })
+            .collect();
+
+        let mut pages = 0;
+        let aircraft = search_aircraft(
+            AdvancedSearchFilter::default(),
+            page_size,
+            SchedulePolicy::Lenient,
+            &get_test_vehicle_models(),
+            |filter| {
+                pages += 1;
+                let start = filter.page_number as usize * filter.results_per_page as usize;
+                let page: Vec<vehicle::Object> = vehicles
+                    .iter()
+                    .skip(start)
+                    .take(filter.results_per_page as usize)
+                    .cloned()
+                    .collect();
+
+                async move { Ok::<_, String>(page) }
+            },
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(pages, 3);
+        assert_eq!(aircraft.len(), vehicles.len());
+        for (aircraft, vehicle) in aircraft.iter().zip(vehicles.iter()) {
+            assert_eq!(aircraft.vehicle_uuid, vehicle.id);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_search_aircraft_without_location() {
+        const CAL_STR: &str = "DTSTART:20221020T180000Z;DURATION:PT14H
+            RRULE:FREQ=WEEKLY;BYDAY=MO,TU,WE,TH,FR";
+
+        let located = vehicle::Object {
+            id: Uuid::new_v4().to_string(),
+            data: Some(vehicle::Data {
+                schedule: Some(CAL_STR.to_owned()),
+                hangar_id: Some(Uuid::new_v4().to_string()),
+                hangar_bay_id: Some(Uuid::new_v4().to_string()),
+                ..Default::default()
+            }),
+        };
+
+        // never flown, not yet assigned a hangar
+        let unlocated = vehicle::Object {
+            id: Uuid::new_v4().to_string(),
+            data: Some(vehicle::Data {
+                schedule: Some(CAL_STR.to_owned()),
+                hangar_id: None,
+                hangar_bay_id: None,
+                ..Default::default()
+            }),
+        };
+
+        let vehicles = vec![unlocated, located.clone()];
+        let aircraft = search_aircraft(
+            AdvancedSearchFilter::default(),
+            10,
+            SchedulePolicy::Lenient,
+            &get_test_vehicle_models(),
+            |_| {
+                let page = vehicles.clone();
+                async move { Ok::<_, String>(page) }
+            },
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(aircraft.len(), 1);
+        assert_eq!(aircraft[0].vehicle_uuid, located.id);
+    }
+
+    #[tokio::test]
+    async fn test_search_aircraft_invalid_schedule_policy() {
+        const CAL_STR: &str = "DTSTART:20221020T180000Z;DURATION:PT14H
+            RRULE:FREQ=WEEKLY;BYDAY=MO,TU,WE,TH,FR";
+
+        // malformed recurrence rule
+        const INVALID_CAL_STR: &str = "DTSTART:20221020T180000Z;DURATION:PT14H
+            RRULE:FREQ=SOMETIMES;BYDAY=XX";
+
+        let vehicle = |schedule: &str| vehicle::Object {
+            id: Uuid::new_v4().to_string(),
+            data: Some(vehicle::Data {
+                schedule: Some(schedule.to_owned()),
+                hangar_id: Some(Uuid::new_v4().to_string()),
+                hangar_bay_id: Some(Uuid::new_v4().to_string()),
+                ..Default::default()
+            }),
+        };
+
+        let valid = vehicle(CAL_STR);
+        let vehicles = vec![vehicle(INVALID_CAL_STR), valid.clone()];
+        let search = |policy: SchedulePolicy| {
+            let vehicles = vehicles.clone();
+            async move {
+                let models = get_test_vehicle_models();
+                search_aircraft(AdvancedSearchFilter::default(), 10, policy, &models, |_| {
+                    let page = vehicles.clone();
+                    async move { Ok::<_, String>(page) }
+                })
+                .await
+            }
+        };
+
+        // skipped with a warning
+        let count = get_invalid_schedule_count();
+        let aircraft = search(SchedulePolicy::Lenient).await.unwrap();
+        assert_eq!(aircraft.len(), 1);
+        assert_eq!(aircraft[0].vehicle_uuid, valid.id);
+        assert!(get_invalid_schedule_count() > count);
+
+        // fails the whole search
+        let count = get_invalid_schedule_count();
+        let e = search(SchedulePolicy::Strict).await.unwrap_err();
+        assert_eq!(e, VehicleError::Schedule);
+        assert!(get_invalid_schedule_count() > count);
+    }
+
+    #[test]
+    fn test_schedule_policy_from_str() {
+        assert_eq!(
+            SchedulePolicy::from_str("strict").unwrap(),
+            SchedulePolicy::Strict
+        );
+        assert_eq!(
+            SchedulePolicy::from_str("lenient").unwrap(),
+            SchedulePolicy::Lenient
+        );
+        assert_eq!(
+            SchedulePolicy::from_str("sometimes").unwrap_err(),
+            VehicleError::Internal
+        );
+    }
+
     #[test]
     fn test_try_from_vehicle_object_aircraft() {
         const CAL_STR: &str = "DTSTART:20221020T180000Z;DURATION:PT14H
@@ -661,15 +1765,36 @@ mod tests {
             data: Some(vehicle_data.clone()),
         };
 
+        // the type of the aircraft is that of its model
+        let models = VehicleModels {
+            models: HashMap::from([(
+                vehicle_data.vehicle_model_id.clone(),
+                get_model(AircraftType::Passenger),
+            )]),
+            other_models: None,
+        };
+
         // valid
-        let _ = Aircraft::try_from(vehicle.clone()).unwrap();
+        let aircraft = Aircraft::try_from((vehicle.clone(), &models)).unwrap();
+        assert_eq!(aircraft.model, get_model(AircraftType::Passenger));
+
+        // Unknown model
+        let tmp = vehicle::Object {
+            id: Uuid::new_v4().to_string(),
+            data: Some(vehicle::Data {
+                vehicle_model_id: Uuid::new_v4().to_string(),
+                ..vehicle_data.clone()
+            }),
+        };
+        let e = Aircraft::try_from((tmp, &VehicleModels::default())).unwrap_err();
+        assert_eq!(e, VehicleError::Model);
 
         // Invalid vehicle UUID
         let tmp = vehicle::Object {
             id: "invalid".to_string(),
             data: None,
         };
-        let e = Aircraft::try_from(tmp).unwrap_err();
+        let e = Aircraft::try_from((tmp, &models)).unwrap_err();
         assert_eq!(e, VehicleError::VehicleId);
 
         // Missing data
@@ -677,7 +1802,7 @@ mod tests {
             id: Uuid::new_v4().to_string(),
             data: None,
         };
-        let e = Aircraft::try_from(tmp).unwrap_err();
+        let e = Aircraft::try_from((tmp, &models)).unwrap_err();
         assert_eq!(e, VehicleError::Data);
 
         // Missing hangar_id
@@ -688,7 +1813,7 @@ mod tests {
                 ..vehicle_data.clone()
             }),
         };
-        let e = Aircraft::try_from(tmp).unwrap_err();
+        let e = Aircraft::try_from((tmp, &models)).unwrap_err();
         assert_eq!(e, VehicleError::HangarId);
 
         // Missing hangar_bay_id
@@ -699,7 +1824,7 @@ mod tests {
                 ..vehicle_data.clone()
             }),
         };
-        let e = Aircraft::try_from(tmp).unwrap_err();
+        let e = Aircraft::try_from((tmp, &models)).unwrap_err();
         assert_eq!(e, VehicleError::HangarBayId);
 
         // Invalid hangar id
@@ -710,7 +1835,7 @@ mod tests {
                 ..vehicle_data.clone()
             }),
         };
-        let e = Aircraft::try_from(tmp).unwrap_err();
+        let e = Aircraft::try_from((tmp, &models)).unwrap_err();
         assert_eq!(e, VehicleError::HangarId);
 
         // Invalid hangar bay id
@@ -721,7 +1846,7 @@ mod tests {
                 ..vehicle_data.clone()
             }),
         };
-        let e = Aircraft::try_from(tmp).unwrap_err();
+        let e = Aircraft::try_from((tmp, &models)).unwrap_err();
         assert_eq!(e, VehicleError::HangarBayId);
 
         // Missing schedule
@@ -732,7 +1857,7 @@ mod tests {
                 ..vehicle_data.clone()
             }),
         };
-        let e = Aircraft::try_from(tmp).unwrap_err();
+        let e = Aircraft::try_from((tmp, &models)).unwrap_err();
         assert_eq!(e, VehicleError::NoSchedule);
 
         // Invalid schedule
@@ -743,7 +1868,7 @@ mod tests {
                 ..vehicle_data.clone()
             }),
         };
-        let e = Aircraft::try_from(tmp).unwrap_err();
+        let e = Aircraft::try_from((tmp, &models)).unwrap_err();
         assert_eq!(e, VehicleError::Schedule);
     }
 }
@@ -0,0 +1,176 @@
+//! Vehicle models
+//! The scheduler needs the type, ground times and payload of each
+//!  aircraft, which depend on its model. Vehicles in svc-storage only
+//!  carry the ID of their model, the models themselves are described
+//!  in the configuration.
+//!
+//! TODO(R5): Read vehicle models from svc-storage once it has them,
+//!  instead of from the configuration
+
+use crate::router::settings::{get_settings, Setting, SettingError, FIELD_SEPARATOR};
+use crate::router::vehicle::{AircraftType, GroundTime};
+use std::collections::HashMap;
+
+/// Vehicle models setting
+pub static VEHICLE_MODELS: Setting = Setting {
+    name: "VEHICLE_MODELS",
+    format: "model_id|name|type|loading_seconds|unloading_seconds|max_payload_grams",
+};
+
+/// Model ID describing the vehicles whose model isn't listed
+pub const OTHER_MODELS_ID: &str = "*";
+
+/// What the scheduler needs to know about a vehicle model
+#[derive(Debug, Clone, PartialEq)]
+pub struct VehicleModel {
+    /// Name of the model, for humans
+    pub name: String,
+
+    /// What the model carries
+    pub aircraft_type: AircraftType,
+
+    /// Time the model blocks a vertipad before takeoff and after landing
+    pub ground_time: GroundTime,
+
+    /// Heaviest payload the model can carry
+    pub max_payload_grams: u32,
+}
+
+/// The configured vehicle models
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct VehicleModels {
+    /// Models keyed by model ID
+    pub models: HashMap<String, VehicleModel>,
+
+    /// Model of vehicles whose model isn't listed. Without one, such
+    ///  vehicles can't be scheduled.
+    pub other_models: Option<VehicleModel>,
+}
+
+impl VehicleModels {
+    /// Returns the model of a vehicle, `None` if it isn't known
+    pub fn get(&self, model_id: &str) -> Option<&VehicleModel> {
+        self.models.get(model_id).or(self.other_models.as_ref())
+    }
+
+    /// Returns the heaviest payload any known model can carry
+    pub fn max_payload_grams(&self) -> u32 {
+        self.models
+            .values()
+            .chain(self.other_models.iter())
+            .map(|model| model.max_payload_grams)
+            .max()
+            .unwrap_or(0)
+    }
+}
+
+/// Parses a model type, `cargo` or `passenger`
+fn parse_aircraft_type(aircraft_type: &str) -> Result<AircraftType, SettingError> {
+    match aircraft_type {
+        "cargo" => Ok(AircraftType::Cargo),
+        "passenger" => Ok(AircraftType::Passenger),
+        _ => {
+            router_error!("Invalid vehicle model type: {aircraft_type}");
+            Err(SettingError::Value(&VEHICLE_MODELS))
+        }
+    }
+}
+
+/// Parses comma-separated
+///  `model_id|name|type|loading_seconds|unloading_seconds|max_payload_grams`
+///  entries. The model ID `*` describes every model not listed.
+pub fn parse_vehicle_models(models: &str) -> Result<VehicleModels, SettingError> {
+    let entries = VEHICLE_MODELS.parse_list(models, |entry| {
+        let [model_id, name, aircraft_type, loading, unloading, max_payload_grams] =
+            VEHICLE_MODELS.split::<6>(entry, FIELD_SEPARATOR)?;
+
+        let model_id = match model_id {
+            OTHER_MODELS_ID => None,
+            _ => Some(VEHICLE_MODELS.parse_id(model_id)?),
+        };
+
+        let model = VehicleModel {
+            name: name.to_string(),
+            aircraft_type: parse_aircraft_type(aircraft_type)?,
+            ground_time: GroundTime {
+                loading: VEHICLE_MODELS.parse_seconds(loading)?,
+                unloading: VEHICLE_MODELS.parse_seconds(unloading)?,
+            },
+            max_payload_grams: VEHICLE_MODELS.parse_value(max_payload_grams)?,
+        };
+
+        Ok((model_id, model))
+    })?;
+
+    let mut models = VehicleModels::default();
+    for (model_id, model) in entries {
+        match model_id {
+            Some(model_id) => {
+                models.models.insert(model_id, model);
+            }
+            None => models.other_models = Some(model),
+        }
+    }
+
+    Ok(models)
+}
+
+/// Returns the configured vehicle models
+pub async fn get_vehicle_models() -> Result<VehicleModels, SettingError> {
+    Ok(get_settings().await?.vehicle_models.clone())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use lib_common::time::Duration;
+    use lib_common::uuid::Uuid;
+
+    #[test]
+    fn test_parse_vehicle_models() {
+        assert_eq!(parse_vehicle_models("").unwrap(), VehicleModels::default());
+
+        let model_id = Uuid::new_v4().to_string();
+        let models = parse_vehicle_models(&format!(
+            "{model_id}|Air Taxi 4|passenger|120|90|400000, *|Cargo drone|cargo|60|60|20000"
+        ))
+        .unwrap();
+
+        let model = models.get(&model_id).unwrap();
+        assert_eq!(model.name, "Air Taxi 4");
+        assert_eq!(model.aircraft_type, AircraftType::Passenger);
+        assert_eq!(model.ground_time.loading, Duration::try_minutes(2).unwrap());
+        assert_eq!(
+            model.ground_time.unloading,
+            Duration::try_seconds(90).unwrap()
+        );
+        assert_eq!(model.max_payload_grams, 400000);
+
+        // other models
+        let model = models.get(&Uuid::new_v4().to_string()).unwrap();
+        assert_eq!(model.name, "Cargo drone");
+        assert_eq!(model.aircraft_type, AircraftType::Cargo);
+        assert_eq!(models.max_payload_grams(), 400000);
+
+        // unknown without a catch-all entry
+        let models =
+            parse_vehicle_models(&format!("{model_id}|Air Taxi 4|passenger|120|90|400000"))
+                .unwrap();
+        assert!(models.get(&Uuid::new_v4().to_string()).is_none());
+        assert_eq!(VehicleModels::default().max_payload_grams(), 0);
+
+        let e = parse_vehicle_models(&format!("{model_id}|Air Taxi 4|passenger")).unwrap_err();
+        assert_eq!(e, SettingError::Format(&VEHICLE_MODELS));
+
+        let e = parse_vehicle_models("invalid|Air Taxi 4|passenger|120|90|400000").unwrap_err();
+        assert_eq!(e, SettingError::Id(&VEHICLE_MODELS));
+
+        let e = parse_vehicle_models(&format!("{model_id}|Air Taxi 4|balloon|120|90|400000"))
+            .unwrap_err();
+        assert_eq!(e, SettingError::Value(&VEHICLE_MODELS));
+
+        let e = parse_vehicle_models(&format!("{model_id}|Air Taxi 4|passenger|-1|90|400000"))
+            .unwrap_err();
+        assert_eq!(e, SettingError::Value(&VEHICLE_MODELS));
+    }
+}
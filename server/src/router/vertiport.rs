@@ -1,20 +1,35 @@
 //! Vertiport-related utilities
 
 use super::flight_plan::*;
+use super::operating_hours::{apply_hours_overrides, get_hours_overrides};
+use super::reservation::{get_vertipad_reservations, remove_reserved_timeslots};
 use super::schedule::*;
+use super::units::Meters;
 use super::vehicle::*;
-use super::{best_path, BestPathError, BestPathRequest};
-use crate::grpc::client::GrpcClients;
+use super::weather::{avoid_weather_cells, get_weather_cells};
+use super::{split_alternative_paths, BestPathError, BestPathRequest, PathFinder};
+use crate::grpc::client::{search_all_pages, GrpcClients};
 use lib_common::time::Duration;
-use std::cmp::{max, min};
-use std::collections::HashMap;
+use std::cmp::{max, min, Ordering};
+use std::collections::{HashMap, HashSet};
+use std::future::Future;
 use std::str::FromStr;
 use svc_gis_client_grpc::prelude::gis::*;
 use svc_storage_client_grpc::prelude::*;
+use tokio::sync::OnceCell;
 
 /// Chop up larger timeslots into smaller durations to avoid temporary no-fly zones
 const MAX_DURATION_TIMESLOT_MINUTES: i64 = 30;
 
+/// Mean radius of the Earth, used to estimate loop flight distances
+pub(crate) const EARTH_RADIUS_METERS: f64 = 6_371_000.0;
+
+/// Distances between vertiports looked up while warming up, keyed by
+///  origin then target vertiport ID. Vertiports don't move, so the
+///  distances stay valid; vertiports added since are looked up again.
+pub static VERTIPORT_DISTANCES: OnceCell<HashMap<String, HashMap<String, Meters>>> =
+    OnceCell::const_new();
+
 /// Error type for vertiport-related errors
 #[derive(Debug, Copy, Clone)]
 pub enum VertiportError {
@@ -33,6 +48,12 @@ pub enum VertiportError {
     /// Invalid schedule
     Schedule,
 
+    /// Vertiport not found in storage
+    NotFound,
+
+    /// Waypoint not known to svc-gis
+    WaypointNotFound,
+
     /// Internal error
     Internal,
 }
@@ -45,6 +66,8 @@ impl std::fmt::Display for VertiportError {
             VertiportError::NoVertipads => write!(f, "No vertipads"),
             VertiportError::NoSchedule => write!(f, "No schedule"),
             VertiportError::Schedule => write!(f, "Invalid schedule"),
+            VertiportError::NotFound => write!(f, "Vertiport not found"),
+            VertiportError::WaypointNotFound => write!(f, "Waypoint not found"),
             VertiportError::Internal => write!(f, "Internal error"),
         }
     }
@@ -56,59 +79,114 @@ pub enum GetVertipadsArg {
     VertipadIds(Vec<String>),
 }
 
+/// Builds the svc-storage search for vertipads.
+/// Soft-deleted vertipads are excluded here, the returned objects
+///  don't carry their deletion time.
+fn get_vertipads_filter(arg: GetVertipadsArg) -> AdvancedSearchFilter {
+    let filter = AdvancedSearchFilter::search_is_null("deleted_at".to_owned());
+    match arg {
+        GetVertipadsArg::VertiportId(vertiport_id) => {
+            filter.and_equals("vertiport_id".to_string(), vertiport_id)
+        }
+        GetVertipadsArg::VertipadIds(ids) => filter.and_in("vertipad_id".to_string(), ids),
+    }
+}
+
+/// Returns true if flights can be scheduled on the vertipad.
+/// Disabled vertipads are only included if configured for diagnostics.
+fn is_schedulable_vertipad(vertipad: &vertipad::Object, include_disabled: bool) -> bool {
+    let Some(data) = &vertipad.data else {
+        router_warn!("Vertipad {} has no data.", vertipad.id);
+        return false;
+    };
+
+    if data.enabled {
+        return true;
+    }
+
+    if include_disabled {
+        router_warn!(
+            "Including disabled vertipad {} for diagnostics.",
+            vertipad.id
+        );
+    }
+
+    include_disabled
+}
+
 /// Gets all vertipads for a vertiport
 pub async fn get_vertipads(
     clients: &GrpcClients,
     arg: GetVertipadsArg,
 ) -> Result<Vec<String>, VertiportError> {
-    let mut filter = AdvancedSearchFilter::search_is_null("deleted_at".to_owned());
-    // TODO(R5): factor in enabled vs disabled
+    let filter = get_vertipads_filter(arg);
+    router_info!("proposed filter: {:?}", filter.clone());
 
-    match arg {
-        GetVertipadsArg::VertiportId(vertiport_id) => {
-            filter = filter.and_equals("vertiport_id".to_string(), vertiport_id);
-        }
-        GetVertipadsArg::VertipadIds(ids) => {
-            filter = filter.and_in("vertipad_id".to_string(), ids);
-        }
-    }
+    let config = crate::config::get_config().await;
+    let response = search_all_pages(filter, config.storage_page_size, |filter| async move {
+        clients
+            .storage()
+            .await
+            .vertipad
+            .search(filter)
+            .await
+            .map(|response| response.into_inner().list)
+    })
+    .await
+    .map_err(|e| {
+        router_error!("Failed to get vertipads: {e}");
+        VertiportError::NoVertipads
+    })?
+    .into_iter()
+    .filter(|vp| is_schedulable_vertipad(vp, config.include_disabled_vertipads))
+    .map(|vp| vp.id)
+    .collect::<Vec<String>>();
 
-    router_info!("proposed filter: {:?}", filter.clone());
+    router_info!("response: {:?}", response);
+    Ok(response)
+}
 
-    let response = clients
-        .storage
-        .vertipad
-        .search(filter)
+/// Checks that flights can be routed to or from a vertiport: it must
+///  exist in storage and have at least one enabled vertipad
+pub async fn check_vertiport_routable(
+    vertiport_id: &str,
+    clients: &GrpcClients,
+) -> Result<(), VertiportError> {
+    clients
+        .storage()
+        .await
+        .vertiport
+        .get_by_id(Id {
+            id: vertiport_id.to_string(),
+        })
         .await
         .map_err(|e| {
-            router_error!("Failed to get vertipads: {e}");
-            VertiportError::NoVertipads
-        })?
-        .into_inner()
-        .list
-        .into_iter()
-        // in R3 the search filter is not working, do an extra filter here
-        .filter_map(|vp| {
-            let Some(data) = vp.data else {
-                return None;
-            };
-
-            if !data.enabled {
-                return None;
+            router_warn!("Could not retrieve vertiport {vertiport_id}: {e}");
+            match e.code() {
+                tonic::Code::NotFound => VertiportError::NotFound,
+                _ => VertiportError::ClientError,
             }
+        })?;
 
-            Some(vp.id)
-        })
-        .collect::<Vec<String>>();
+    let vertipads = get_vertipads(
+        clients,
+        GetVertipadsArg::VertiportId(vertiport_id.to_string()),
+    )
+    .await?;
 
-    router_info!("response: {:?}", response);
-    Ok(response)
+    if vertipads.is_empty() {
+        router_warn!("Vertiport {vertiport_id} has no enabled vertipads.");
+        return Err(VertiportError::NoVertipads);
+    }
+
+    Ok(())
 }
 
 /// Get pairs of timeslots where a flight can leave within the origin timeslot
-///  and land within the target timeslot
+///  and land within the target timeslot.
+/// Routes between the vertiports are requested from `router`.
 #[allow(clippy::too_many_arguments)]
-pub async fn get_timeslot_pairs(
+pub async fn get_timeslot_pairs<P: PathFinder + Sync>(
     origin_vertiport_id: &str,
     origin_vertipad_id: Option<&str>,
     target_vertiport_id: &str,
@@ -116,8 +194,11 @@ pub async fn get_timeslot_pairs(
     origin_time_block: &Duration,
     target_time_block: &Duration,
     timeslot: &Timeslot,
+    arrival_window: Option<&Timeslot>,
     existing_flight_plans: &[FlightPlanSchedule],
+    route_limit: u32,
     clients: &GrpcClients,
+    router: &P,
 ) -> Result<Vec<TimeslotPair>, VertiportError> {
     let origin_timeslots = get_available_timeslots(
         origin_vertiport_id,
@@ -144,7 +225,9 @@ pub async fn get_timeslot_pairs(
         target_vertiport_id,
         origin_timeslots,
         target_timeslots,
-        clients,
+        arrival_window,
+        route_limit,
+        router,
     )
     .await
 }
@@ -163,25 +246,25 @@ pub async fn get_available_timeslots(
     clients: &GrpcClients,
 ) -> Result<HashMap<String, Vec<Timeslot>>, VertiportError> {
     // Get vertiport schedule
-    let calendar = get_vertiport_calendar(vertiport_id, clients).await?;
+    let schedule = get_vertiport_schedule(vertiport_id, clients).await?;
 
     // TODO(R5): Use each vertipad's calendar
-    let base_timeslots = calendar
+    let base_timeslots = schedule
         .to_timeslots(&timeslot.time_start(), &timeslot.time_end())
         .map_err(|e| {
-            router_error!("Could not convert calendar to timeslots: {e}");
+            router_error!("Could not convert schedule to timeslots: {e}");
             VertiportError::Internal
         })?;
 
-    router_debug!("base_timeslots: {:?}", base_timeslots);
-
-    // TODO(R5): This is currently hardcoded, get the duration of the timeslot
-    // try min and max both the necessary landing time
-    let max_duration = Duration::try_minutes(MAX_DURATION_TIMESLOT_MINUTES).ok_or_else(|| {
-        router_error!("error creating time delta.");
-        VertiportError::Internal
+    // Operating-hours overrides take precedence over the schedule
+    let overrides = get_hours_overrides().await.map_err(|e| {
+        router_error!("Could not get operating-hours overrides: {e}");
+        VertiportError::Schedule
     })?;
 
+    let base_timeslots = apply_hours_overrides(vertiport_id, base_timeslots, &overrides, timeslot);
+    router_debug!("base_timeslots: {:?}", base_timeslots);
+
     let filter = match vertipad_id {
         Some(id) => GetVertipadsArg::VertipadIds(vec![id.to_string()]),
         None => GetVertipadsArg::VertiportId(vertiport_id.to_string()),
@@ -195,22 +278,72 @@ pub async fn get_available_timeslots(
         .map(|id| (id, base_timeslots.clone()))
         .collect::<HashMap<String, Vec<Timeslot>>>();
 
+    // Slots reserved for scheduled services are held like occupied slots
+    let reservations = get_vertipad_reservations().await.map_err(|e| {
+        router_error!("Could not get vertipad reservations: {e}");
+        VertiportError::Schedule
+    })?;
+
+    remove_reserved_timeslots(&mut timeslots, &reservations, timeslot);
+
+    get_unoccupied_timeslots(
+        vertiport_id,
+        timeslots,
+        existing_flight_plans,
+        minimum_duration,
+    )
+}
+
+/// Removes the slots occupied by existing flight plans from the open
+///  timeslots of each vertipad at the vertiport
+pub fn get_unoccupied_timeslots(
+    vertiport_id: &str,
+    mut timeslots: HashMap<String, Vec<Timeslot>>,
+    existing_flight_plans: &[FlightPlanSchedule],
+    minimum_duration: &Duration,
+) -> Result<HashMap<String, Vec<Timeslot>>, VertiportError> {
+    // TODO(R5): This is currently hardcoded, get the duration of the timeslot
+    // try min and max both the necessary landing time
+    let max_duration = Duration::try_minutes(MAX_DURATION_TIMESLOT_MINUTES).ok_or_else(|| {
+        router_error!("error creating time delta.");
+        VertiportError::Internal
+    })?;
+
     // Get occupied slots
     // TODO(R5): This will be replaced with a call to svc-storage vertipad_timeslots to
     //  return a list of occupied timeslots for each vertipad, so we don't
     //  need to rebuild each pad's schedule from flight plans each time
     let occupied_slots = build_timeslots_from_flight_plans(vertiport_id, existing_flight_plans)?;
 
-    router_debug!("(get_available_timeslots): vertiport: {:?}", vertiport_id);
-    router_debug!("(get_available_timeslots): vertipads {:?}", timeslots);
+    router_debug!("(get_unoccupied_timeslots): vertiport: {:?}", vertiport_id);
+    router_debug!("(get_unoccupied_timeslots): vertipads {:?}", timeslots);
     router_debug!(
-        "(get_available_timeslots): occupied {:?}",
+        "(get_unoccupied_timeslots): occupied {:?}",
         occupied_slots
             .iter()
             .map(|(id, _)| id)
             .collect::<Vec<&String>>()
     );
 
+    remove_occupied_timeslots(
+        &mut timeslots,
+        &occupied_slots,
+        minimum_duration,
+        &max_duration,
+    );
+
+    Ok(timeslots)
+}
+
+/// Removes occupied slots from the available timeslots of each vertipad.
+/// Capacity is tracked per vertipad, so a vertiport with one vertipad
+///  and a vertiport with many vertipads go through the same code path.
+fn remove_occupied_timeslots(
+    timeslots: &mut HashMap<String, Vec<Timeslot>>,
+    occupied_slots: &[(String, Timeslot)],
+    minimum_duration: &Duration,
+    max_duration: &Duration,
+) {
     // For each occupied slot, remove it from the list of available slots
     for (vertipad_id, occupied_slot) in occupied_slots.iter() {
         let Some(vertipad_slots) = timeslots.get_mut(vertipad_id) else {
@@ -228,20 +361,19 @@ pub async fn get_available_timeslots(
             // Split any slots that are too long. A short temporary no-fly zone overlapping
             //  any part of the timeslot will invalidate the entire timeslot, so we split it
             //  into smaller timeslots to avoid this.
-            .flat_map(|slot| slot.split(minimum_duration, &max_duration))
+            .flat_map(|slot| slot.split(minimum_duration, max_duration))
             .collect::<Vec<Timeslot>>();
     }
-
-    Ok(timeslots)
 }
 
-/// Gets vertiport schedule from storage and converts it to a Calendar object.
-async fn get_vertiport_calendar(
+/// Gets vertiport schedule from storage and validates it as a [`Schedule`].
+async fn get_vertiport_schedule(
     vertiport_id: &str,
     clients: &GrpcClients,
-) -> Result<Calendar, VertiportError> {
+) -> Result<Schedule, VertiportError> {
     let vertiport_schedule = clients
-        .storage
+        .storage()
+        .await
         .vertiport
         .get_by_id(Id {
             id: vertiport_id.to_string(),
@@ -257,15 +389,14 @@ async fn get_vertiport_calendar(
             router_error!("Date invalid for vertiport {}.", vertiport_id);
             VertiportError::Data
         })?
-        .schedule
-        .ok_or_else(|| {
-            router_error!("No schedule for vertiport {}.", vertiport_id);
-            VertiportError::NoSchedule
-        })?;
+        .schedule;
 
-    Calendar::from_str(&vertiport_schedule).map_err(|e| {
-        router_error!("Schedule invalid for vertiport {vertiport_id}; {e}.");
-        VertiportError::Schedule
+    Schedule::try_from(vertiport_schedule).map_err(|e| {
+        router_error!("Schedule error for vertiport {vertiport_id}; {e}.");
+        match e {
+            ScheduleError::Missing => VertiportError::NoSchedule,
+            ScheduleError::Invalid => VertiportError::Schedule,
+        }
     })
 }
 
@@ -330,6 +461,86 @@ fn build_timeslots_from_flight_plans(
     Ok(results)
 }
 
+/// Occupied and free time of a single vertipad over a window
+#[derive(Debug, Clone, PartialEq)]
+pub struct VertipadOccupancy {
+    /// Slots taken by flights taking off or landing, in time order.
+    /// Overlapping slots are merged.
+    pub occupied: Vec<Timeslot>,
+
+    /// The rest of the window, in time order
+    pub free: Vec<Timeslot>,
+}
+
+/// Splits a window into the slots of a vertipad occupied by existing
+///  flight plans and the slots left free.
+/// Only flight plans are considered, the vertiport schedule is not.
+pub fn get_vertipad_occupancy(
+    vertiport_id: &str,
+    vertipad_id: &str,
+    existing_flight_plans: &[FlightPlanSchedule],
+    window: &Timeslot,
+) -> Result<VertipadOccupancy, VertiportError> {
+    let mut slots = build_timeslots_from_flight_plans(vertiport_id, existing_flight_plans)?
+        .into_iter()
+        .filter(|(id, _)| id == vertipad_id)
+        .filter_map(|(_, slot)| slot.overlap(window).ok())
+        .collect::<Vec<Timeslot>>();
+
+    slots.sort_by_key(|slot| slot.time_start());
+
+    let mut occupied: Vec<Timeslot> = vec![];
+    for slot in slots {
+        match occupied.last_mut() {
+            Some(last) if slot.time_start() <= last.time_end() => {
+                *last = Timeslot::new(last.time_start(), max(last.time_end(), slot.time_end()))
+                    .map_err(|e| {
+                        router_error!("Could not merge occupied slots: {e}");
+                        VertiportError::Internal
+                    })?;
+            }
+            _ => occupied.push(slot),
+        }
+    }
+
+    let free = occupied.iter().fold(vec![*window], |free, slot| {
+        free.into_iter()
+            .flat_map(|available| available - *slot)
+            .collect()
+    });
+
+    Ok(VertipadOccupancy { occupied, free })
+}
+
+/// Gets the ID of the vertiport a vertipad belongs to
+pub async fn get_vertipad_vertiport_id(
+    vertipad_id: &str,
+    clients: &GrpcClients,
+) -> Result<String, VertiportError> {
+    clients
+        .storage()
+        .await
+        .vertipad
+        .get_by_id(Id {
+            id: vertipad_id.to_string(),
+        })
+        .await
+        .map_err(|e| {
+            router_warn!("Could not retrieve vertipad {vertipad_id}: {e}");
+            match e.code() {
+                tonic::Code::NotFound => VertiportError::NotFound,
+                _ => VertiportError::ClientError,
+            }
+        })?
+        .into_inner()
+        .data
+        .map(|data| data.vertiport_id)
+        .ok_or_else(|| {
+            router_error!("Data invalid for vertipad {vertipad_id}.");
+            VertiportError::Data
+        })
+}
+
 /// Gets all available timeslot pairs and a path for each pair
 #[derive(Debug, Clone)]
 pub struct TimeslotPair {
@@ -340,7 +551,9 @@ pub struct TimeslotPair {
     pub target_vertipad_id: String,
     pub target_timeslot: Timeslot,
     pub path: Vec<PointZ>,
-    pub distance_meters: f64,
+    pub distance_meters: Meters,
+    /// Other routes between the vertiports, shortest first
+    pub alternative_paths: Vec<(Vec<PointZ>, Meters)>,
 }
 
 impl From<TimeslotPair> for flight_plan::Data {
@@ -371,16 +584,180 @@ impl From<TimeslotPair> for flight_plan::Data {
     }
 }
 
+/// Limits on the search for timeslot pairs between two vertiports
+#[derive(Debug, Copy, Clone, PartialEq, Default)]
+pub struct TimeslotPairLimits {
+    /// Maximum origin and target timeslot combinations checked
+    ///  for a route (0 for no limit)
+    pub max_checks: u32,
+
+    /// Stop searching once this many pairs are found (0 for no limit)
+    pub max_pairs: u32,
+
+    /// Discard pairs whose departure window is shorter than this
+    ///  (0 for no minimum)
+    pub min_origin_window_seconds: u32,
+
+    /// Routes whose lengths differ by less than this are ranked as
+    ///  equally short (0 to compare exact lengths)
+    pub distance_tolerance: Meters,
+}
+
+impl TimeslotPairLimits {
+    /// Returns true if no more timeslot combinations may be checked
+    fn is_checks_exhausted(&self, checks: u32) -> bool {
+        self.max_checks > 0 && checks >= self.max_checks
+    }
+
+    /// Returns true if enough pairs were found
+    fn is_enough_pairs(&self, pairs: usize) -> bool {
+        self.max_pairs > 0 && pairs >= self.max_pairs as usize
+    }
+
+    /// Returns true if the departure window is too tight to offer
+    fn is_window_too_short(&self, origin_timeslot: &Timeslot) -> bool {
+        origin_timeslot.duration().num_seconds() < i64::from(self.min_origin_window_seconds)
+    }
+}
+
+/// How the vertipad pairs found for a route are ordered, the first
+///  pairs being offered first
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum PadSelection {
+    /// Pads free soonest first
+    EarliestAvailability,
+
+    /// Pads with the shortest route first, the soonest free among
+    ///  equally short routes
+    ShortestPath,
+}
+
+impl FromStr for PadSelection {
+    type Err = VertiportError;
+
+    fn from_str(selection: &str) -> Result<Self, Self::Err> {
+        match selection {
+            "earliest_availability" => Ok(PadSelection::EarliestAvailability),
+            "shortest_path" => Ok(PadSelection::ShortestPath),
+            _ => {
+                router_error!("Unknown vertipad selection: {selection}");
+                Err(VertiportError::Internal)
+            }
+        }
+    }
+}
+
+/// Orders pairs shortest route first, treating route lengths within
+///  `tolerance` of each other as equal.
+/// Ties go to the soonest departure, then to the vertipad IDs, so that
+///  near-equal routes are always offered in the same order.
+fn cmp_pair_distances(a: &TimeslotPair, b: &TimeslotPair, tolerance: Meters) -> Ordering {
+    a.distance_meters
+        .cmp_within(&b.distance_meters, tolerance)
+        .then_with(|| {
+            a.origin_timeslot
+                .time_start()
+                .cmp(&b.origin_timeslot.time_start())
+        })
+        .then_with(|| a.origin_vertipad_id.cmp(&b.origin_vertipad_id))
+        .then_with(|| a.target_vertipad_id.cmp(&b.target_vertipad_id))
+}
+
+/// Orders the vertipad pairs of a route by the selection criterion,
+///  the shortest route first among pairs departing at the same time.
+pub fn select_vertipad_pairs(
+    pairs: &mut [TimeslotPair],
+    selection: PadSelection,
+    tolerance: Meters,
+) {
+    match selection {
+        PadSelection::EarliestAvailability => pairs.sort_by(|a, b| {
+            a.origin_timeslot
+                .time_start()
+                .cmp(&b.origin_timeslot.time_start())
+                .then_with(|| cmp_pair_distances(a, b, tolerance))
+        }),
+        PadSelection::ShortestPath => pairs.sort_by(|a, b| cmp_pair_distances(a, b, tolerance)),
+    }
+}
+
 /// Attempts to find a pairing of origin and target pad
 ///  timeslots wherein a flight could occur.
-pub async fn get_vertipad_timeslot_pairs(
+/// Up to `route_limit` routes are requested from `router`; the shortest
+///  is used for the flight and the others are kept as alternatives.
+/// If an `arrival_window` is provided, flights must land within it.
+/// The number of combinations checked against svc-gis is bounded by
+///  the configured limits, and pairs leaving only a sliver of a
+///  departure window are discarded.
+/// Routes crossing a weather cell during the flight are skipped.
+/// Pairs are ordered by the configured vertipad selection.
+pub async fn get_vertipad_timeslot_pairs<P: PathFinder + Sync>(
     origin_vertiport_id: &str,
     target_vertiport_id: &str,
     origin_vertipads: HashMap<String, Vec<Timeslot>>,
     target_vertipads: HashMap<String, Vec<Timeslot>>,
-    clients: &GrpcClients,
+    arrival_window: Option<&Timeslot>,
+    route_limit: u32,
+    router: &P,
 ) -> Result<Vec<TimeslotPair>, VertiportError> {
+    let config = crate::config::get_config().await;
+    let limits = TimeslotPairLimits {
+        max_checks: config.max_timeslot_pair_checks,
+        max_pairs: config.max_timeslot_pairs,
+        min_origin_window_seconds: config.min_origin_window_seconds,
+        distance_tolerance: Meters::from_millimeters(config.distance_tolerance_millimeters),
+    };
+    let selection = PadSelection::from_str(&config.pad_selection)?;
+
+    let weather_cells = get_weather_cells().await.map_err(|e| {
+        router_error!("Could not get weather cells: {e}");
+        VertiportError::Internal
+    })?;
+
+    let mut pairs = find_vertipad_timeslot_pairs(
+        origin_vertiport_id,
+        target_vertiport_id,
+        origin_vertipads,
+        target_vertipads,
+        arrival_window,
+        route_limit,
+        &limits,
+        |request| {
+            let weather_cells = &weather_cells;
+            async move {
+                let paths = router.best_path(&request).await?;
+                avoid_weather_cells(paths, weather_cells, &request)
+            }
+        },
+    )
+    .await?;
+
+    select_vertipad_pairs(&mut pairs, selection, limits.distance_tolerance);
+    Ok(pairs)
+}
+
+/// Pairs origin and target pad timeslots, using `find_path` to get
+///  the routes for each combination.
+/// Combinations are checked earliest departure first, then earliest
+///  arrival, so the search stops with the soonest flights once a limit
+///  is reached.
+#[allow(clippy::too_many_arguments)]
+pub async fn find_vertipad_timeslot_pairs<F, Fut>(
+    origin_vertiport_id: &str,
+    target_vertiport_id: &str,
+    origin_vertipads: HashMap<String, Vec<Timeslot>>,
+    target_vertipads: HashMap<String, Vec<Timeslot>>,
+    arrival_window: Option<&Timeslot>,
+    route_limit: u32,
+    limits: &TimeslotPairLimits,
+    mut find_path: F,
+) -> Result<Vec<TimeslotPair>, VertiportError>
+where
+    F: FnMut(BestPathRequest) -> Fut,
+    Fut: Future<Output = Result<Vec<(Vec<PointZ>, Meters)>, BestPathError>>,
+{
     let mut pairs = vec![];
+    let mut checks: u32 = 0;
     let mut best_path_request = BestPathRequest {
         origin_identifier: origin_vertiport_id.to_string(),
         target_identifier: target_vertiport_id.to_string(),
@@ -388,25 +765,37 @@ pub async fn get_vertipad_timeslot_pairs(
         target_type: NodeType::Vertiport as i32,
         time_start: None,
         time_end: None,
-        limit: 5,
+        limit: i32::try_from(route_limit).unwrap_or(i32::MAX),
     };
 
     let mut origin_timeslots = origin_vertipads
         .into_iter()
         .flat_map(|(id, slots)| slots.into_iter().map(move |slot| (id.clone(), slot)))
         .collect::<Vec<(String, Timeslot)>>();
-    origin_timeslots.sort_by(|a, b| a.1.time_start().cmp(&b.1.time_start()));
+    origin_timeslots.sort_by(|a, b| {
+        a.1.time_start()
+            .cmp(&b.1.time_start())
+            .then_with(|| a.0.cmp(&b.0))
+    });
 
     let mut target_timeslots = target_vertipads
         .into_iter()
         .flat_map(|(id, slots)| slots.into_iter().map(move |slot| (id.clone(), slot)))
+        .filter_map(|(id, slot)| match arrival_window {
+            Some(window) => slot.overlap(window).ok().map(|slot| (id, slot)),
+            None => Some((id, slot)),
+        })
         .collect::<Vec<(String, Timeslot)>>();
-    target_timeslots.sort_by(|a, b| a.1.time_start().cmp(&b.1.time_start()));
+    target_timeslots.sort_by(|a, b| {
+        a.1.time_start()
+            .cmp(&b.1.time_start())
+            .then_with(|| a.0.cmp(&b.0))
+    });
 
     // Iterate through origin pads and their schedules
-    for (origin_vertipad_id, ots) in origin_timeslots.iter_mut() {
+    'origin: for (origin_vertipad_id, ots) in origin_timeslots.iter() {
         // Iterate through target pads and their schedules
-        'target: for (target_vertipad_id, tts) in target_timeslots.iter_mut() {
+        'target: for (target_vertipad_id, tts) in target_timeslots.iter() {
             // no timeslot overlap possible
             //                    | origin timeslot |
             // | target timeslot |
@@ -414,12 +803,23 @@ pub async fn get_vertipad_timeslot_pairs(
                 continue;
             }
 
+            if limits.is_checks_exhausted(checks) {
+                router_warn!(
+                    "Checked {checks} timeslot combinations from vertiport {} to vertiport {}, skipping the rest.",
+                    origin_vertiport_id,
+                    target_vertiport_id
+                );
+
+                break 'origin;
+            }
+
             // Temporary no-fly zones make checking the same route
             //  multiple times necessary for different timeslots
             best_path_request.time_start = Some(ots.time_start().into());
             best_path_request.time_end = Some(tts.time_end().into());
 
-            let mut paths = match best_path(&best_path_request, clients).await {
+            checks += 1;
+            let paths = match find_path(best_path_request.clone()).await {
                 Ok(paths) => paths,
                 Err(BestPathError::NoPathFound) => {
                     // no path found, perhaps temporary no-fly zone
@@ -443,26 +843,27 @@ pub async fn get_vertipad_timeslot_pairs(
                 }
             };
 
-            // For now only get the first path
-            let (path, distance_meters) = paths.remove(0);
-            //  else {
-            //     // no path found, perhaps temporary no-fly zone
-            //     //  is blocking journeys from this depart timeslot
-            //     // Break out and try the next depart timeslot
-            //     router_debug!(
-            //         "No path found from vertiport {}
-            //         to vertiport {} (from {} to {}).",
-            //         origin_vertiport_id,
-            //         target_vertiport_id,
-            //         ots.time_start(),
-            //         tts.time_end()
-            //     );
-
-            //     break 'target;
-            // };
-
-            let estimated_duration_s =
-                estimate_flight_time_seconds(&distance_meters).map_err(|e| {
+            // Fly the shortest path, keep the others as alternatives
+            let Some(((path, distance_meters), alternative_paths)) = split_alternative_paths(paths)
+            else {
+                // no path found, perhaps temporary no-fly zone
+                //  is blocking journeys from this depart timeslot
+                // Break out and try the next depart timeslot
+                router_debug!(
+                    "No path found from vertiport {}
+                    to vertiport {} (from {} to {}).",
+                    origin_vertiport_id,
+                    target_vertiport_id,
+                    ots.time_start(),
+                    tts.time_end()
+                );
+
+                break 'target;
+            };
+
+            let estimated_duration_s = estimate_blocked_flight_time(&distance_meters)
+                .await
+                .map_err(|e| {
                     router_error!("Could not estimate flight time: {e}");
                     VertiportError::Internal
                 })?;
@@ -495,6 +896,19 @@ pub async fn get_vertipad_timeslot_pairs(
                 VertiportError::Internal
             })?;
 
+            // the timeslots barely overlap once the flight time is
+            //  accounted for, too tight a departure window to offer
+            if limits.is_window_too_short(&origin_timeslot) {
+                router_debug!(
+                    "Departure window {} to {} from vertipad {} is too short.",
+                    origin_timeslot.time_start(),
+                    origin_timeslot.time_end(),
+                    origin_vertipad_id
+                );
+
+                continue;
+            }
+
             //
             //  |     ots     |             (depart timeslot)
             //   ----->       ----->        (flight time)
@@ -526,91 +940,813 @@ pub async fn get_vertipad_timeslot_pairs(
                 target_timeslot,
                 path,
                 distance_meters,
+                alternative_paths,
             });
-        }
-    }
 
-    // Sort available options by shortest distance first
-    pairs.sort_by(
-        |a, b| match a.distance_meters.partial_cmp(&b.distance_meters) {
-            Some(ord) => ord,
-            None => {
-                router_error!(
-                    "Could not compare distances: {}, {}",
-                    a.distance_meters,
-                    b.distance_meters
+            if limits.is_enough_pairs(pairs.len()) {
+                router_debug!(
+                    "Found {} timeslot pairs after {checks} checks.",
+                    pairs.len()
                 );
 
-                std::cmp::Ordering::Equal
+                break 'origin;
             }
-        },
-    );
+        }
+    }
+
+    // Sort available options by shortest distance first
+    pairs.sort_by(|a, b| cmp_pair_distances(a, b, limits.distance_tolerance));
 
     Ok(pairs)
 }
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use crate::grpc::client::get_clients;
-    use crate::router::vehicle::estimate_flight_time_seconds;
-    use lib_common::time::{DateTime, Utc};
-    use lib_common::uuid::Uuid;
-
-    #[tokio::test]
-    #[cfg(feature = "stub_backends")]
-    async fn ut_get_vertipad_pairs_no_overlap() {
-        let origin_vertiport_id: String = Uuid::new_v4().to_string();
-        let target_vertiport_id: String = Uuid::new_v4().to_string();
-        let origin_vertipad_id: String = Uuid::new_v4().to_string();
-        let target_vertipad_id: String = Uuid::new_v4().to_string();
-        let clients = get_clients().await;
-
-        //
-        // CASE 1: No overlap, even leaving at the last minute of the origin window
-        //                          |-----v2----|
-        //             >>>>>>>>>>x
-        // |-----v1----|
-        // |           |            |           |
-        // 3           6            10          13
-
-        let origin_start = DateTime::from_str("2021-01-01T03:00:00Z").unwrap();
-        let origin_end = DateTime::from_str("2021-01-01T06:00:00Z").unwrap();
-        let origin_vertipads = HashMap::from([(
-            origin_vertipad_id.clone(),
-            vec![Timeslot::new(origin_start, origin_end).unwrap()],
-        )]);
+/// The vertipad chosen for a fixed time window
+#[derive(Debug, Clone, PartialEq)]
+pub struct VertipadChoice {
+    /// Vertipad that is free for the whole window
+    pub vertipad_id: String,
 
-        let target_vertipads = HashMap::from([(
-            target_vertipad_id.clone(),
-            vec![Timeslot::new(
-                DateTime::from_str("2021-01-01T10:00:00Z").unwrap(),
-                DateTime::from_str("2021-01-01T13:00:00Z").unwrap(),
-            )
-            .unwrap()],
-        )]);
+    /// True if the preferred vertipad was busy and a sibling
+    ///  vertipad at the same vertiport was chosen instead
+    pub is_fallback: bool,
+}
 
-        let pairs = get_vertipad_timeslot_pairs(
-            &origin_vertiport_id,
-            &target_vertiport_id,
-            origin_vertipads,
-            target_vertipads,
-            &clients,
-        )
-        .await
-        .unwrap();
+/// Picks a vertipad that is free for the whole `window`, preferring
+///  `preferred_vertipad_id` and falling back to any other vertipad.
+fn select_vertipad(
+    preferred_vertipad_id: &str,
+    vertipad_timeslots: &HashMap<String, Vec<Timeslot>>,
+    window: &Timeslot,
+) -> Option<VertipadChoice> {
+    let is_free = |vertipad_id: &str| {
+        vertipad_timeslots.get(vertipad_id).is_some_and(|slots| {
+            slots.iter().any(|slot| {
+                slot.time_start() <= window.time_start() && slot.time_end() >= window.time_end()
+            })
+        })
+    };
 
-        assert!(pairs.is_empty());
+    if is_free(preferred_vertipad_id) {
+        return Some(VertipadChoice {
+            vertipad_id: preferred_vertipad_id.to_string(),
+            is_fallback: false,
+        });
     }
 
-    #[tokio::test]
-    #[cfg(feature = "stub_backends")]
-    async fn ut_get_vertipad_pairs_no_overlap_target_lead() {
-        let origin_vertiport_id: String = Uuid::new_v4().to_string();
-        let target_vertiport_id: String = Uuid::new_v4().to_string();
-        let origin_vertipad_id: String = Uuid::new_v4().to_string();
-        let target_vertipad_id: String = Uuid::new_v4().to_string();
-        let clients = get_clients().await;
+    let mut siblings = vertipad_timeslots
+        .keys()
+        .filter(|id| id.as_str() != preferred_vertipad_id)
+        .collect::<Vec<&String>>();
+    siblings.sort();
+
+    siblings
+        .into_iter()
+        .find(|id| is_free(id))
+        .map(|id| VertipadChoice {
+            vertipad_id: id.clone(),
+            is_fallback: true,
+        })
+}
+
+/// Gets a vertipad at the vertiport that is free for the whole `window`,
+///  preferring `preferred_vertipad_id` if it is free.
+/// Returns `None` if every vertipad at the vertiport is busy.
+#[cfg(not(tarpaulin_include))]
+// no_coverage: (R5) needs running backend, integration tests
+pub async fn get_vertipad_with_fallback(
+    vertiport_id: &str,
+    preferred_vertipad_id: &str,
+    existing_flight_plans: &[FlightPlanSchedule],
+    timeslot: &Timeslot,
+    window: &Timeslot,
+    clients: &GrpcClients,
+) -> Result<Option<VertipadChoice>, VertiportError> {
+    let vertipad_timeslots = get_available_timeslots(
+        vertiport_id,
+        None,
+        existing_flight_plans,
+        timeslot,
+        &window.duration(),
+        clients,
+    )
+    .await?;
+
+    let choice = select_vertipad(preferred_vertipad_id, &vertipad_timeslots, window);
+    if let Some(choice) = choice.as_ref().filter(|c| c.is_fallback) {
+        router_info!(
+            "Vertipad {} is busy, using vertipad {} at vertiport {}.",
+            preferred_vertipad_id,
+            choice.vertipad_id,
+            vertiport_id
+        );
+    }
+
+    Ok(choice)
+}
+
+/// Great-circle distance between two points in meters
+pub fn get_distance_meters(a: &PointZ, b: &PointZ) -> Meters {
+    let (lat_a, lat_b) = (a.latitude.to_radians(), b.latitude.to_radians());
+    let d_lat = lat_b - lat_a;
+    let d_lon = (b.longitude - a.longitude).to_radians();
+
+    let h = (d_lat / 2.0).sin().powi(2) + lat_a.cos() * lat_b.cos() * (d_lon / 2.0).sin().powi(2);
+    Meters(2.0 * EARTH_RADIUS_METERS * h.sqrt().asin())
+}
+
+/// Gets the center of a vertiport's footprint
+fn get_centroid(points: &[GeoPoint]) -> Option<PointZ> {
+    if points.is_empty() {
+        return None;
+    }
+
+    let count = points.len() as f64;
+    Some(PointZ {
+        latitude: points.iter().map(|p| p.latitude).sum::<f64>() / count,
+        longitude: points.iter().map(|p| p.longitude).sum::<f64>() / count,
+        altitude_meters: (points.iter().map(|p| p.altitude).sum::<f64>() / count) as f32,
+    })
+}
+
+/// Joins the routed legs of a loop flight into one path, each leg
+///  starting where the previous one ended. Returns the path and its
+///  length in meters.
+fn join_loop_legs(legs: Vec<(Vec<PointZ>, Meters)>) -> (Vec<PointZ>, Meters) {
+    let mut path: Vec<PointZ> = vec![];
+    let mut distance_meters = vec![];
+    for (points, leg_distance) in legs {
+        let skip = usize::from(!path.is_empty());
+        path.extend(points.into_iter().skip(skip));
+        distance_meters.push(leg_distance);
+    }
+
+    (path, distance_meters.into_iter().sum())
+}
+
+/// Routes a closed path that leaves the vertiport, visits each named
+///  waypoint in order and returns to the vertiport. Each leg is routed
+///  by the router around the no-fly zones and weather cells of the
+///  timeslot.
+/// Returns the path and its length in meters, `None` if a leg has no path.
+pub async fn get_loop_path<P: PathFinder + Sync>(
+    vertiport_id: &str,
+    waypoint_names: &[String],
+    timeslot: &Timeslot,
+    router: &P,
+) -> Result<Option<(Vec<PointZ>, Meters)>, VertiportError> {
+    if waypoint_names.is_empty() {
+        router_error!("Loop flight requires at least one waypoint.");
+        return Err(VertiportError::Data);
+    }
+
+    let weather_cells = get_weather_cells().await.map_err(|e| {
+        router_error!("Could not get weather cells: {e}");
+        VertiportError::Internal
+    })?;
+
+    let nodes = std::iter::once(vertiport_id)
+        .chain(waypoint_names.iter().map(String::as_str))
+        .chain(std::iter::once(vertiport_id))
+        .collect::<Vec<&str>>();
+
+    // the first leg departs from the vertiport and the last returns to it
+    let last_leg = nodes.len() - 2;
+    let mut legs = vec![];
+    for (index, leg) in nodes.windows(2).enumerate() {
+        let request = BestPathRequest {
+            origin_identifier: leg[0].to_string(),
+            target_identifier: leg[1].to_string(),
+            origin_type: get_loop_node_type(index == 0) as i32,
+            target_type: get_loop_node_type(index == last_leg) as i32,
+            time_start: Some(timeslot.time_start().into()),
+            time_end: Some(timeslot.time_end().into()),
+            limit: 1,
+        };
+
+        let paths = router
+            .best_path(&request)
+            .await
+            .and_then(|paths| avoid_weather_cells(paths, &weather_cells, &request));
+
+        match paths.map(|paths| paths.into_iter().next()) {
+            Ok(Some(path)) => legs.push(path),
+            Ok(None) | Err(BestPathError::NoPathFound) => {
+                router_debug!("No path from {} to {}.", leg[0], leg[1]);
+                return Ok(None);
+            }
+            Err(BestPathError::ClientError) => {
+                router_error!("Could not determine path - client error.");
+                return Err(VertiportError::ClientError);
+            }
+        }
+    }
+
+    Ok(Some(join_loop_legs(legs)))
+}
+
+/// Type of a node of a loop flight, the vertiport at either end and
+///  waypoints in between
+fn get_loop_node_type(is_vertiport: bool) -> NodeType {
+    match is_vertiport {
+        true => NodeType::Vertiport,
+        false => NodeType::Waypoint,
+    }
+}
+
+/// Checks that svc-gis knows a waypoint, so that flights can be routed
+///  through it
+#[cfg(not(tarpaulin_include))]
+// no_coverage: (R5) needs running backend, integration tests
+pub async fn check_waypoint_known(name: &str, clients: &GrpcClients) -> Result<(), VertiportError> {
+    let request = NearestNeighborRequest {
+        start_node_id: name.to_string(),
+        start_type: NodeType::Waypoint as i32,
+        end_type: NodeType::Vertiport as i32,
+        limit: 1,
+        max_range_meters: f32::MAX,
+    };
+
+    clients
+        .gis
+        .nearest_neighbors(request)
+        .await
+        .map(|_| ())
+        .map_err(|e| {
+            router_warn!("Could not find waypoint {name}: {e}");
+            match e.code() {
+                tonic::Code::NotFound => VertiportError::WaypointNotFound,
+                _ => VertiportError::ClientError,
+            }
+        })
+}
+
+/// Gets the location of a vertiport from storage
+#[cfg(not(tarpaulin_include))]
+// no_coverage: (R5) needs running backend, integration tests
+pub async fn get_vertiport_location(
+    vertiport_id: &str,
+    clients: &GrpcClients,
+) -> Result<PointZ, VertiportError> {
+    let points = clients
+        .storage()
+        .await
+        .vertiport
+        .get_by_id(Id {
+            id: vertiport_id.to_string(),
+        })
+        .await
+        .map_err(|e| {
+            router_error!("Could not retrieve data for vertiport {vertiport_id}: {e}");
+            VertiportError::ClientError
+        })?
+        .into_inner()
+        .data
+        .and_then(|data| data.geo_location)
+        .and_then(|geo_location| geo_location.exterior)
+        .map(|exterior| exterior.points)
+        .unwrap_or_default();
+
+    get_centroid(&points).ok_or_else(|| {
+        router_error!("No location for vertiport {}.", vertiport_id);
+        VertiportError::Data
+    })
+}
+
+/// Gets the IDs of all vertiports in storage
+#[cfg(not(tarpaulin_include))]
+// no_coverage: (R5) needs running backend, integration tests
+pub async fn get_vertiport_ids(clients: &GrpcClients) -> Result<Vec<String>, VertiportError> {
+    let page_size = crate::config::get_config().await.storage_page_size;
+    let filter = AdvancedSearchFilter::search_is_null("deleted_at".to_owned());
+    search_all_pages(filter, page_size, |filter| async move {
+        clients
+            .storage()
+            .await
+            .vertiport
+            .search(filter)
+            .await
+            .map(|response| response.into_inner().list)
+    })
+    .await
+    .map(|vertiports| {
+        vertiports
+            .into_iter()
+            .map(|vertiport| vertiport.id)
+            .collect()
+    })
+    .map_err(|e| {
+        router_error!("Could not get vertiports: {e}");
+        VertiportError::ClientError
+    })
+}
+
+/// Gets up to `limit` vertiports within `radius` of a vertiport from
+///  svc-gis, nearest first, with their distance from it
+#[cfg(not(tarpaulin_include))]
+// no_coverage: (R5) needs running backend, integration tests
+pub async fn get_nearby_vertiports(
+    vertiport_id: &str,
+    radius: Meters,
+    limit: i32,
+    clients: &GrpcClients,
+) -> Result<Vec<(String, Meters)>, VertiportError> {
+    let request = NearestNeighborRequest {
+        start_node_id: vertiport_id.to_string(),
+        start_type: NodeType::Vertiport as i32,
+        end_type: NodeType::Vertiport as i32,
+        limit,
+        max_range_meters: radius.0 as f32,
+    };
+
+    let distances = clients
+        .gis
+        .nearest_neighbors(request)
+        .await
+        .map_err(|e| {
+            router_error!("Could not get vertiports near {vertiport_id}: {e}");
+            VertiportError::ClientError
+        })?
+        .into_inner()
+        .distances;
+
+    Ok(get_nearby(distances, vertiport_id, radius))
+}
+
+/// Keeps the vertiports found by svc-gis within `radius`, other than the
+///  vertiport searched from, nearest first
+fn get_nearby(
+    distances: Vec<DistanceTo>,
+    vertiport_id: &str,
+    radius: Meters,
+) -> Vec<(String, Meters)> {
+    let mut nearby = distances
+        .into_iter()
+        .filter(|distance| {
+            distance.target_type == NodeType::Vertiport as i32 && distance.label != vertiport_id
+        })
+        .map(|distance| (distance.label, Meters::from(distance.distance_meters)))
+        .filter(|(_, distance)| *distance <= radius)
+        .collect::<Vec<(String, Meters)>>();
+
+    nearby.sort_by(|a, b| a.1 .0.total_cmp(&b.1 .0));
+    nearby
+}
+
+/// Returns the cached distances from a vertiport to each of the
+///  provided vertiports, `None` unless all of them are cached
+fn get_cached_distances(
+    cache: &HashMap<String, HashMap<String, Meters>>,
+    origin_vertiport_id: &str,
+    vertiport_ids: &HashSet<String>,
+) -> Option<HashMap<String, Meters>> {
+    let cached = cache.get(origin_vertiport_id)?;
+    vertiport_ids
+        .iter()
+        .map(|id| cached.get(id).map(|distance| (id.clone(), *distance)))
+        .collect()
+}
+
+/// Gets the distance from a vertiport to each of the provided vertiports,
+///  from the distances cached while warming up if they cover them all.
+/// Vertiports without a known location are left out.
+#[cfg(not(tarpaulin_include))]
+// no_coverage: (R5) needs running backend, integration tests
+pub async fn get_vertiport_distances(
+    origin_vertiport_id: &str,
+    vertiport_ids: &HashSet<String>,
+    clients: &GrpcClients,
+) -> Result<HashMap<String, Meters>, VertiportError> {
+    if let Some(distances) = VERTIPORT_DISTANCES
+        .get()
+        .and_then(|cache| get_cached_distances(cache, origin_vertiport_id, vertiport_ids))
+    {
+        return Ok(distances);
+    }
+
+    let origin = get_vertiport_location(origin_vertiport_id, clients).await?;
+
+    let mut distances = HashMap::new();
+    for vertiport_id in vertiport_ids {
+        match get_vertiport_location(vertiport_id, clients).await {
+            Ok(location) => {
+                distances.insert(
+                    vertiport_id.clone(),
+                    get_distance_meters(&origin, &location),
+                );
+            }
+            Err(e) => router_warn!("No distance to vertiport {vertiport_id}: {e}"),
+        }
+    }
+
+    Ok(distances)
+}
+
+/// Get timeslot pairs for a loop flight that departs from and returns to
+///  the same vertipad, flying through the named waypoints
+#[cfg(not(tarpaulin_include))]
+// no_coverage: (R5) needs running backend, integration tests
+#[allow(clippy::too_many_arguments)]
+pub async fn get_loop_timeslot_pairs<P: PathFinder + Sync>(
+    vertiport_id: &str,
+    waypoint_names: &[String],
+    required_loading_time: &Duration,
+    required_unloading_time: &Duration,
+    timeslot: &Timeslot,
+    existing_flight_plans: &[FlightPlanSchedule],
+    clients: &GrpcClients,
+    router: &P,
+) -> Result<Vec<TimeslotPair>, VertiportError> {
+    let Some((path, distance_meters)) =
+        get_loop_path(vertiport_id, waypoint_names, timeslot, router).await?
+    else {
+        router_info!("No loop path from vertiport {vertiport_id}.");
+        return Ok(vec![]);
+    };
+    let flight_duration = estimate_blocked_flight_time(&distance_meters)
+        .await
+        .map_err(|e| {
+            router_error!("Could not estimate flight time: {e}");
+            VertiportError::Internal
+        })?;
+
+    // The pad is held for the whole loop, the aircraft returns to it
+    let minimum_duration = *required_loading_time + flight_duration + *required_unloading_time;
+    let vertipad_timeslots = get_available_timeslots(
+        vertiport_id,
+        None,
+        existing_flight_plans,
+        timeslot,
+        &minimum_duration,
+        clients,
+    )
+    .await?;
+
+    let mut pairs = vec![];
+    for (vertipad_id, slots) in vertipad_timeslots {
+        for slot in slots {
+            pairs.push(TimeslotPair {
+                origin_vertiport_id: vertiport_id.to_string(),
+                origin_vertipad_id: vertipad_id.clone(),
+                origin_timeslot: slot,
+                target_vertiport_id: vertiport_id.to_string(),
+                target_vertipad_id: vertipad_id.clone(),
+                target_timeslot: slot,
+                path: path.clone(),
+                distance_meters,
+                // loop flights follow the requested waypoints
+                alternative_paths: vec![],
+            });
+        }
+    }
+
+    pairs.sort_by(|a, b| {
+        a.origin_timeslot
+            .time_start()
+            .cmp(&b.origin_timeslot.time_start())
+    });
+
+    Ok(pairs)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::grpc::client::get_clients;
+    use crate::router::vehicle::estimate_flight_time_seconds;
+    use crate::router::weather::WeatherCell;
+    use lib_common::time::{DateTime, Utc};
+    use lib_common::uuid::Uuid;
+    use std::str::FromStr;
+
+    /// Number of vertipads with a timeslot covering the whole window
+    fn count_free_vertipads(
+        timeslots: &HashMap<String, Vec<Timeslot>>,
+        window: &Timeslot,
+    ) -> usize {
+        timeslots
+            .values()
+            .filter(|slots| {
+                slots.iter().any(|slot| {
+                    slot.time_start() <= window.time_start() && slot.time_end() >= window.time_end()
+                })
+            })
+            .count()
+    }
+
+    #[test]
+    fn test_get_vertipad_occupancy() {
+        let now = Utc::now();
+        let minutes = |n: i64| now + Duration::try_minutes(n).unwrap();
+        let origin_vertiport_id = Uuid::new_v4().to_string();
+        let target_vertiport_id = Uuid::new_v4().to_string();
+        let vertipad_id = Uuid::new_v4().to_string();
+        let window = Timeslot::new(now, minutes(180)).unwrap();
+        let flight_plan = FlightPlanSchedule {
+            origin_vertiport_id: origin_vertiport_id.clone(),
+            origin_vertipad_id: vertipad_id.clone(),
+            origin_timeslot_start: minutes(60),
+            origin_timeslot_end: minutes(60),
+            target_vertiport_id: target_vertiport_id.clone(),
+            target_vertipad_id: Uuid::new_v4().to_string(),
+            target_timeslot_start: minutes(90),
+            target_timeslot_end: minutes(90),
+            vehicle_id: Uuid::new_v4().to_string(),
+            path: None,
+        };
+
+        // the pad is taken while the aircraft loads and takes off
+        let takeoff_end = minutes(60)
+            + Duration::try_seconds(
+                crate::grpc::api::query_flight::LOADING_AND_TAKEOFF_TIME_SECONDS,
+            )
+            .unwrap();
+        let occupancy = get_vertipad_occupancy(
+            &origin_vertiport_id,
+            &vertipad_id,
+            &[flight_plan.clone()],
+            &window,
+        )
+        .unwrap();
+        assert_eq!(
+            occupancy.occupied,
+            vec![Timeslot::new(minutes(60), takeoff_end).unwrap()]
+        );
+        assert_eq!(
+            occupancy.free,
+            vec![
+                Timeslot::new(now, minutes(60)).unwrap(),
+                Timeslot::new(takeoff_end, minutes(180)).unwrap(),
+            ]
+        );
+
+        // a second flight overlapping the first one is merged into one slot
+        let mut overlapping = flight_plan.clone();
+        overlapping.origin_timeslot_start = minutes(60) + Duration::try_seconds(30).unwrap();
+        let occupancy = get_vertipad_occupancy(
+            &origin_vertiport_id,
+            &vertipad_id,
+            &[overlapping.clone(), flight_plan.clone()],
+            &window,
+        )
+        .unwrap();
+        assert_eq!(occupancy.occupied.len(), 1);
+        assert_eq!(occupancy.occupied[0].time_start(), minutes(60));
+        assert!(occupancy.occupied[0].time_end() > takeoff_end);
+
+        // other pads of the vertiport are free for the whole window
+        let occupancy = get_vertipad_occupancy(
+            &origin_vertiport_id,
+            &Uuid::new_v4().to_string(),
+            &[flight_plan.clone()],
+            &window,
+        )
+        .unwrap();
+        assert!(occupancy.occupied.is_empty());
+        assert_eq!(occupancy.free, vec![window]);
+
+        // flights outside the window don't count
+        let later = Timeslot::new(minutes(120), minutes(180)).unwrap();
+        let occupancy =
+            get_vertipad_occupancy(&origin_vertiport_id, &vertipad_id, &[flight_plan], &later)
+                .unwrap();
+        assert!(occupancy.occupied.is_empty());
+        assert_eq!(occupancy.free, vec![later]);
+    }
+
+    #[test]
+    fn test_remove_occupied_timeslots_single_and_multi_pad() {
+        let now = Utc::now();
+        let minimum_duration = Duration::try_minutes(1).unwrap();
+        let max_duration = Duration::try_minutes(MAX_DURATION_TIMESLOT_MINUTES).unwrap();
+        let day = Timeslot::new(now, now + Duration::try_hours(24).unwrap()).unwrap();
+        let occupied = Timeslot::new(
+            now + Duration::try_hours(1).unwrap(),
+            now + Duration::try_hours(1).unwrap() + Duration::try_minutes(2).unwrap(),
+        )
+        .unwrap();
+        let before = Timeslot::new(
+            now + Duration::try_minutes(10).unwrap(),
+            now + Duration::try_minutes(12).unwrap(),
+        )
+        .unwrap();
+
+        // Same occupancy: one flight on the first vertipad
+        let vertipad_ids = (0..3)
+            .map(|_| Uuid::new_v4().to_string())
+            .collect::<Vec<String>>();
+        let occupied_slots = vec![(vertipad_ids[0].clone(), occupied)];
+
+        // 1-pad vertiport is full during the occupied window
+        let mut single = HashMap::from([(vertipad_ids[0].clone(), vec![day])]);
+        remove_occupied_timeslots(
+            &mut single,
+            &occupied_slots,
+            &minimum_duration,
+            &max_duration,
+        );
+        assert_eq!(count_free_vertipads(&single, &occupied), 0);
+        assert_eq!(count_free_vertipads(&single, &before), 1);
+
+        // 3-pad vertiport has two pads left during the occupied window
+        let mut multi = vertipad_ids
+            .iter()
+            .map(|id| (id.clone(), vec![day]))
+            .collect::<HashMap<String, Vec<Timeslot>>>();
+        remove_occupied_timeslots(
+            &mut multi,
+            &occupied_slots,
+            &minimum_duration,
+            &max_duration,
+        );
+        assert_eq!(count_free_vertipads(&multi, &occupied), 2);
+        assert_eq!(count_free_vertipads(&multi, &before), 3);
+
+        // Flight plans on unknown vertipads are ignored
+        let unknown = vec![(Uuid::new_v4().to_string(), occupied)];
+        remove_occupied_timeslots(&mut multi, &unknown, &minimum_duration, &max_duration);
+        assert_eq!(count_free_vertipads(&multi, &occupied), 2);
+    }
+
+    #[test]
+    fn test_select_vertipad() {
+        let now = Utc::now();
+        let preferred = Uuid::new_v4().to_string();
+        let sibling = Uuid::new_v4().to_string();
+        let day = Timeslot::new(now, now + Duration::try_hours(24).unwrap()).unwrap();
+        let window = Timeslot::new(
+            now + Duration::try_hours(1).unwrap(),
+            now + Duration::try_hours(1).unwrap() + Duration::try_minutes(2).unwrap(),
+        )
+        .unwrap();
+
+        // preferred pad is free
+        let timeslots =
+            HashMap::from([(preferred.clone(), vec![day]), (sibling.clone(), vec![day])]);
+        let choice = select_vertipad(&preferred, &timeslots, &window).unwrap();
+        assert_eq!(choice.vertipad_id, preferred);
+        assert!(!choice.is_fallback);
+
+        // preferred pad is fully booked, the sibling is selected
+        let timeslots = HashMap::from([(preferred.clone(), vec![]), (sibling.clone(), vec![day])]);
+        let choice = select_vertipad(&preferred, &timeslots, &window).unwrap();
+        assert_eq!(choice.vertipad_id, sibling);
+        assert!(choice.is_fallback);
+
+        // sibling is only free for part of the window
+        let partial =
+            Timeslot::new(now, window.time_end() - Duration::try_seconds(1).unwrap()).unwrap();
+        let timeslots = HashMap::from([(preferred.clone(), vec![]), (sibling, vec![partial])]);
+        assert!(select_vertipad(&preferred, &timeslots, &window).is_none());
+    }
+
+    #[test]
+    fn test_get_vertipads_filter() {
+        let fields = |filter: AdvancedSearchFilter| {
+            filter
+                .filters
+                .into_iter()
+                .map(|f| f.search_field)
+                .collect::<Vec<String>>()
+        };
+
+        // soft-deleted vertipads are never searched for
+        let vertiport_id = Uuid::new_v4().to_string();
+        let filter = get_vertipads_filter(GetVertipadsArg::VertiportId(vertiport_id.clone()));
+        assert_eq!(fields(filter.clone()), vec!["deleted_at", "vertiport_id"]);
+        assert_eq!(filter.filters[1].search_value, vec![vertiport_id]);
+
+        let ids = vec![Uuid::new_v4().to_string(), Uuid::new_v4().to_string()];
+        let filter = get_vertipads_filter(GetVertipadsArg::VertipadIds(ids.clone()));
+        assert_eq!(fields(filter.clone()), vec!["deleted_at", "vertipad_id"]);
+        assert_eq!(filter.filters[1].search_value, ids);
+    }
+
+    #[test]
+    fn test_is_schedulable_vertipad() {
+        let get_vertipad = |enabled: bool| vertipad::Object {
+            id: Uuid::new_v4().to_string(),
+            data: Some(vertipad::Data {
+                enabled,
+                ..vertipad::mock::get_data_obj()
+            }),
+        };
+
+        let enabled = get_vertipad(true);
+        assert!(is_schedulable_vertipad(&enabled, false));
+        assert!(is_schedulable_vertipad(&enabled, true));
+
+        // disabled vertipads are only included for diagnostics
+        let disabled = get_vertipad(false);
+        assert!(!is_schedulable_vertipad(&disabled, false));
+        assert!(is_schedulable_vertipad(&disabled, true));
+
+        let no_data = vertipad::Object {
+            id: Uuid::new_v4().to_string(),
+            data: None,
+        };
+        assert!(!is_schedulable_vertipad(&no_data, false));
+        assert!(!is_schedulable_vertipad(&no_data, true));
+    }
+
+    #[tokio::test]
+    #[cfg(feature = "stub_backends")]
+    async fn ut_check_vertiport_routable() {
+        lib_common::logger::get_log_handle().await;
+        ut_info!("start");
+
+        crate::test_util::ensure_storage_mock_data().await;
+        let clients = get_clients().await;
+
+        // Mock vertiports have vertipads
+        let vertiports = crate::test_util::get_vertiports_from_storage()
+            .await
+            .unwrap();
+        check_vertiport_routable(&vertiports[0].id, clients)
+            .await
+            .unwrap();
+
+        // A vertiport added to storage without vertipads can't be routed
+        let vertiport_id = clients
+            .storage()
+            .await
+            .vertiport
+            .insert(vertiport::mock::get_data_obj())
+            .await
+            .unwrap()
+            .into_inner()
+            .object
+            .unwrap()
+            .id;
+        let e = check_vertiport_routable(&vertiport_id, clients)
+            .await
+            .unwrap_err();
+        assert!(matches!(e, VertiportError::NoVertipads));
+
+        // Unknown vertiport
+        let e = check_vertiport_routable(&Uuid::new_v4().to_string(), clients)
+            .await
+            .unwrap_err();
+        assert!(matches!(e, VertiportError::NotFound));
+
+        ut_info!("success");
+    }
+
+    #[tokio::test]
+    #[cfg(feature = "stub_backends")]
+    async fn ut_get_vertipad_pairs_no_overlap() {
+        let origin_vertiport_id: String = Uuid::new_v4().to_string();
+        let target_vertiport_id: String = Uuid::new_v4().to_string();
+        let origin_vertipad_id: String = Uuid::new_v4().to_string();
+        let target_vertipad_id: String = Uuid::new_v4().to_string();
+        let clients = get_clients().await;
+
+        //
+        // CASE 1: No overlap, even leaving at the last minute of the origin window
+        //                          |-----v2----|
+        //             >>>>>>>>>>x
+        // |-----v1----|
+        // |           |            |           |
+        // 3           6            10          13
+
+        let origin_start = DateTime::from_str("2021-01-01T03:00:00Z").unwrap();
+        let origin_end = DateTime::from_str("2021-01-01T06:00:00Z").unwrap();
+        let origin_vertipads = HashMap::from([(
+            origin_vertipad_id.clone(),
+            vec![Timeslot::new(origin_start, origin_end).unwrap()],
+        )]);
+
+        let target_vertipads = HashMap::from([(
+            target_vertipad_id.clone(),
+            vec![Timeslot::new(
+                DateTime::from_str("2021-01-01T10:00:00Z").unwrap(),
+                DateTime::from_str("2021-01-01T13:00:00Z").unwrap(),
+            )
+            .unwrap()],
+        )]);
+
+        let pairs = get_vertipad_timeslot_pairs(
+            &origin_vertiport_id,
+            &target_vertiport_id,
+            origin_vertipads,
+            target_vertipads,
+            None,
+            5,
+            clients,
+        )
+        .await
+        .unwrap();
+
+        assert!(pairs.is_empty());
+    }
+
+    #[tokio::test]
+    #[cfg(feature = "stub_backends")]
+    async fn ut_get_vertipad_pairs_no_overlap_target_lead() {
+        let origin_vertiport_id: String = Uuid::new_v4().to_string();
+        let target_vertiport_id: String = Uuid::new_v4().to_string();
+        let origin_vertipad_id: String = Uuid::new_v4().to_string();
+        let target_vertipad_id: String = Uuid::new_v4().to_string();
+        let clients = get_clients().await;
 
         //
         // No overlap, target window is earlier
@@ -641,7 +1777,9 @@ mod tests {
             &target_vertiport_id,
             origin_vertipads,
             target_vertipads,
-            &clients,
+            None,
+            5,
+            clients,
         )
         .await
         .unwrap();
@@ -688,7 +1826,9 @@ mod tests {
             &target_vertiport_id,
             origin_vertipads,
             target_vertipads,
-            &clients,
+            None,
+            5,
+            clients,
         )
         .await
         .unwrap();
@@ -749,7 +1889,9 @@ mod tests {
             &target_vertiport_id,
             origin_vertipads,
             target_vertipads,
-            &clients,
+            None,
+            5,
+            clients,
         )
         .await
         .unwrap();
@@ -808,7 +1950,9 @@ mod tests {
             &target_vertiport_id,
             origin_vertipads,
             target_vertipads,
-            &clients,
+            None,
+            5,
+            clients,
         )
         .await
         .unwrap();
@@ -877,7 +2021,9 @@ mod tests {
             &target_vertiport_id,
             origin_vertipads,
             target_vertipads,
-            &clients,
+            None,
+            5,
+            clients,
         )
         .await
         .unwrap();
@@ -933,6 +2079,624 @@ mod tests {
         }
     }
 
+    #[tokio::test]
+    #[cfg(feature = "stub_backends")]
+    async fn ut_get_vertipad_pairs_arrival_window() {
+        let origin_vertiport_id: String = Uuid::new_v4().to_string();
+        let target_vertiport_id: String = Uuid::new_v4().to_string();
+        let origin_vertipad_id: String = Uuid::new_v4().to_string();
+        let target_vertipad_id: String = Uuid::new_v4().to_string();
+        let clients = get_clients().await;
+
+        //
+        // Tight precision only keeps landings close to the latest arrival time
+        //       |-----v2-p1--|    |-----v2-p2--|
+        //                         |-- tight ---|
+        // |---------------- loose -------------|
+        // |-----v1----------------|
+        // |           |           |            |
+        // 3           6           9            10
+
+        let origin_vertipads = HashMap::from([(
+            origin_vertipad_id.clone(),
+            vec![Timeslot::new(
+                DateTime::from_str("2021-01-01T03:00:00Z").unwrap(),
+                DateTime::from_str("2021-01-01T09:00:00Z").unwrap(),
+            )
+            .unwrap()],
+        )]);
+
+        let target_vertipads = HashMap::from([(
+            target_vertipad_id.clone(),
+            vec![
+                Timeslot::new(
+                    DateTime::from_str("2021-01-01T05:00:00Z").unwrap(),
+                    DateTime::from_str("2021-01-01T07:00:00Z").unwrap(),
+                )
+                .unwrap(),
+                Timeslot::new(
+                    DateTime::from_str("2021-01-01T09:00:00Z").unwrap(),
+                    DateTime::from_str("2021-01-01T10:00:00Z").unwrap(),
+                )
+                .unwrap(),
+            ],
+        )]);
+
+        let latest_arrival_time = DateTime::from_str("2021-01-01T10:00:00Z").unwrap();
+        let loose = Timeslot::new(
+            latest_arrival_time - Duration::try_hours(7).unwrap(),
+            latest_arrival_time,
+        )
+        .unwrap();
+
+        let tight = Timeslot::new(
+            latest_arrival_time - Duration::try_hours(1).unwrap(),
+            latest_arrival_time,
+        )
+        .unwrap();
+
+        let loose_pairs = get_vertipad_timeslot_pairs(
+            &origin_vertiport_id,
+            &target_vertiport_id,
+            origin_vertipads.clone(),
+            target_vertipads.clone(),
+            Some(&loose),
+            5,
+            clients,
+        )
+        .await
+        .unwrap();
+
+        let tight_pairs = get_vertipad_timeslot_pairs(
+            &origin_vertiport_id,
+            &target_vertiport_id,
+            origin_vertipads,
+            target_vertipads,
+            Some(&tight),
+            5,
+            clients,
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(loose_pairs.len(), 2);
+        assert_eq!(tight_pairs.len(), 1);
+        assert!(tight_pairs[0].target_timeslot.time_start() >= tight.time_start());
+        assert!(tight_pairs[0].target_timeslot.time_end() <= tight.time_end());
+    }
+
+    #[tokio::test]
+    async fn test_find_vertipad_timeslot_pairs_limits() {
+        lib_common::logger::get_log_handle().await;
+        ut_info!("start");
+
+        // a dense schedule: a day split into short timeslots on several pads
+        let start = DateTime::<Utc>::from_str("2030-01-01T00:00:00Z").unwrap();
+        let slot = Duration::try_minutes(MAX_DURATION_TIMESLOT_MINUTES).unwrap();
+        let get_vertipads = || {
+            (0..3)
+                .map(|_| {
+                    let slots = (0..48)
+                        .map(|i| Timeslot::new(start + slot * i, start + slot * (i + 1)).unwrap())
+                        .collect::<Vec<Timeslot>>();
+                    (Uuid::new_v4().to_string(), slots)
+                })
+                .collect::<HashMap<String, Vec<Timeslot>>>()
+        };
+
+        let origin_vertipads = get_vertipads();
+        let target_vertipads = get_vertipads();
+        let origin_vertiport_id = Uuid::new_v4().to_string();
+        let target_vertiport_id = Uuid::new_v4().to_string();
+        let search = |limits: TimeslotPairLimits| {
+            let origin_vertipads = origin_vertipads.clone();
+            let target_vertipads = target_vertipads.clone();
+            let origin_vertiport_id = origin_vertiport_id.clone();
+            let target_vertiport_id = target_vertiport_id.clone();
+            async move {
+                let mut calls: u32 = 0;
+                let pairs = find_vertipad_timeslot_pairs(
+                    &origin_vertiport_id,
+                    &target_vertiport_id,
+                    origin_vertipads,
+                    target_vertipads,
+                    None,
+                    1,
+                    &limits,
+                    |_| {
+                        calls += 1;
+                        async { Ok(vec![(vec![], Meters(1000.0))]) }
+                    },
+                )
+                .await
+                .unwrap();
+
+                (pairs, calls)
+            }
+        };
+
+        // without limits, many more combinations are checked
+        let (all_pairs, all_calls) = search(TimeslotPairLimits::default()).await;
+        assert!(all_calls > 200);
+        assert!(all_pairs.len() > 200);
+
+        // the number of checks is capped
+        let limits = TimeslotPairLimits {
+            max_checks: 50,
+            ..Default::default()
+        };
+        let (pairs, calls) = search(limits).await;
+        assert_eq!(calls, 50);
+        assert!(!pairs.is_empty());
+        assert!(pairs.len() <= 50);
+
+        // stops once enough pairs are found, soonest departures first
+        let limits = TimeslotPairLimits {
+            max_pairs: 10,
+            ..Default::default()
+        };
+        let (pairs, calls) = search(limits).await;
+        assert_eq!(pairs.len(), 10);
+        // each origin timeslot also checks the first target out of reach
+        assert_eq!(calls, 11);
+        assert!(pairs
+            .iter()
+            .all(|p| p.origin_timeslot.time_end() <= start + slot));
+
+        ut_info!("success");
+    }
+
+    #[test]
+    fn test_pad_selection_from_str() {
+        assert_eq!(
+            PadSelection::from_str("earliest_availability").unwrap(),
+            PadSelection::EarliestAvailability
+        );
+        assert_eq!(
+            PadSelection::from_str("shortest_path").unwrap(),
+            PadSelection::ShortestPath
+        );
+        assert!(PadSelection::from_str("nearest").is_err());
+    }
+
+    #[tokio::test]
+    async fn test_select_vertipad_pairs() {
+        lib_common::logger::get_log_handle().await;
+        ut_info!("start");
+
+        // the early pad is only reachable around a temporary no-fly zone
+        let start = DateTime::<Utc>::from_str("2030-01-01T00:00:00Z").unwrap();
+        let early_pad = Uuid::new_v4().to_string();
+        let late_pad = Uuid::new_v4().to_string();
+        let window = |hours: i64| {
+            Timeslot::new(
+                start + Duration::try_hours(hours).unwrap(),
+                start + Duration::try_hours(hours + 1).unwrap(),
+            )
+            .unwrap()
+        };
+
+        let pairs = find_vertipad_timeslot_pairs(
+            &Uuid::new_v4().to_string(),
+            &Uuid::new_v4().to_string(),
+            HashMap::from([
+                (early_pad.clone(), vec![window(0)]),
+                (late_pad.clone(), vec![window(1)]),
+            ]),
+            HashMap::from([(Uuid::new_v4().to_string(), vec![window(0), window(1)])]),
+            None,
+            1,
+            &TimeslotPairLimits::default(),
+            |request| {
+                let detour =
+                    request.time_start.map(DateTime::<Utc>::from) < Some(window(1).time_start());
+                let distance = if detour { 3000.0 } else { 1000.0 };
+                async move { Ok(vec![(vec![], Meters(distance))]) }
+            },
+        )
+        .await
+        .unwrap();
+        assert!(pairs.len() >= 2);
+
+        let tolerance = Meters::from_millimeters(1);
+
+        // soonest free pad first
+        let mut selected = pairs.clone();
+        select_vertipad_pairs(&mut selected, PadSelection::EarliestAvailability, tolerance);
+        assert_eq!(selected[0].origin_vertipad_id, early_pad);
+
+        // shortest route first
+        let mut selected = pairs.clone();
+        select_vertipad_pairs(&mut selected, PadSelection::ShortestPath, tolerance);
+        assert_eq!(selected[0].origin_vertipad_id, late_pad);
+        assert_eq!(selected[0].distance_meters.0, 1000.0);
+        assert!(selected
+            .windows(2)
+            .all(|w| w[0].distance_meters.0 <= w[1].distance_meters.0));
+
+        // routes a nanometer apart are ranked the same whatever order
+        //  they were found in
+        let mut near_equal = vec![selected[0].clone(), selected[0].clone()];
+        near_equal[0].origin_vertipad_id = Uuid::new_v4().to_string();
+        near_equal[1].origin_vertipad_id = Uuid::new_v4().to_string();
+        near_equal[1].distance_meters = Meters(1000.0 + 1e-9);
+        let mut selected = near_equal.clone();
+        select_vertipad_pairs(&mut selected, PadSelection::ShortestPath, tolerance);
+        let mut reversed: Vec<_> = near_equal.iter().rev().cloned().collect();
+        select_vertipad_pairs(&mut reversed, PadSelection::ShortestPath, tolerance);
+        let pads = |pairs: &[TimeslotPair]| {
+            pairs
+                .iter()
+                .map(|pair| pair.origin_vertipad_id.clone())
+                .collect::<Vec<String>>()
+        };
+        assert_eq!(pads(&selected), pads(&reversed));
+        assert!(selected[0].origin_vertipad_id < selected[1].origin_vertipad_id);
+
+        ut_info!("success");
+    }
+
+    #[tokio::test]
+    async fn test_find_vertipad_timeslot_pairs_weather_cell() {
+        lib_common::logger::get_log_handle().await;
+        ut_info!("start");
+
+        let start = DateTime::<Utc>::from_str("2030-01-01T08:00:00Z").unwrap();
+        let hour = Duration::try_hours(1).unwrap();
+        let timeslot = Timeslot::new(start, start + hour).unwrap();
+        let origin_vertipads = HashMap::from([(Uuid::new_v4().to_string(), vec![timeslot])]);
+        let target_vertipads = HashMap::from([(Uuid::new_v4().to_string(), vec![timeslot])]);
+
+        let point = |latitude: f64, longitude: f64| PointZ {
+            latitude,
+            longitude,
+            altitude_meters: 100.0,
+        };
+
+        let direct = vec![point(52.0, 4.0), point(52.01, 4.01)];
+        let detour = vec![point(52.0, 4.0), point(52.01, 4.0), point(52.01, 4.01)];
+        let paths = vec![
+            (direct.clone(), Meters(1300.0)),
+            (detour.clone(), Meters(1800.0)),
+        ];
+
+        // a storm over the direct path during the flight
+        let cells = vec![WeatherCell {
+            timeslot: Timeslot::new(start - hour, start + hour * 2).unwrap(),
+            polygon: vec![
+                (52.004, 4.005),
+                (52.005, 4.004),
+                (52.006, 4.005),
+                (52.005, 4.006),
+            ],
+        }];
+
+        let search = |cells: Vec<WeatherCell>| {
+            let origin_vertipads = origin_vertipads.clone();
+            let target_vertipads = target_vertipads.clone();
+            let paths = paths.clone();
+            async move {
+                find_vertipad_timeslot_pairs(
+                    "origin",
+                    "target",
+                    origin_vertipads,
+                    target_vertipads,
+                    None,
+                    2,
+                    &TimeslotPairLimits::default(),
+                    |request| {
+                        let paths = paths.clone();
+                        let cells = cells.clone();
+                        async move { avoid_weather_cells(paths, &cells, &request) }
+                    },
+                )
+                .await
+                .unwrap()
+            }
+        };
+
+        // clear skies, fly the direct path
+        let pairs = search(vec![]).await;
+        assert_eq!(pairs.len(), 1);
+        assert_eq!(pairs[0].path, direct);
+        assert_eq!(
+            pairs[0].alternative_paths,
+            vec![(detour.clone(), Meters(1800.0))]
+        );
+
+        // the weather cell forces the longer detour
+        let pairs = search(cells).await;
+        assert_eq!(pairs.len(), 1);
+        assert_eq!(pairs[0].path, detour);
+        assert_eq!(pairs[0].distance_meters, Meters(1800.0));
+        assert!(pairs[0].alternative_paths.is_empty());
+
+        ut_info!("success");
+    }
+
+    #[tokio::test]
+    async fn test_find_vertipad_timeslot_pairs_min_origin_window() {
+        lib_common::logger::get_log_handle().await;
+        ut_info!("start");
+
+        let start = DateTime::<Utc>::from_str("2030-01-01T08:00:00Z").unwrap();
+        let hour = Duration::try_hours(1).unwrap();
+        let distance_meters = Meters(1000.0);
+        let flight_time = estimate_blocked_flight_time(&distance_meters)
+            .await
+            .unwrap();
+
+        // the target timeslot opens 10 seconds before the aircraft
+        //  would land when leaving at the end of the origin timeslot
+        let origin_timeslot = Timeslot::new(start, start + hour).unwrap();
+        let target_timeslot = Timeslot::new(
+            start + hour + flight_time - Duration::try_seconds(10).unwrap(),
+            start + hour * 3,
+        )
+        .unwrap();
+        let origin_vertipads = HashMap::from([(Uuid::new_v4().to_string(), vec![origin_timeslot])]);
+        let target_vertipads = HashMap::from([(Uuid::new_v4().to_string(), vec![target_timeslot])]);
+
+        let search = |min_origin_window_seconds: u32| {
+            let origin_vertipads = origin_vertipads.clone();
+            let target_vertipads = target_vertipads.clone();
+            async move {
+                find_vertipad_timeslot_pairs(
+                    "origin",
+                    "target",
+                    origin_vertipads,
+                    target_vertipads,
+                    None,
+                    1,
+                    &TimeslotPairLimits {
+                        min_origin_window_seconds,
+                        ..Default::default()
+                    },
+                    |_| async { Ok(vec![(vec![], Meters(1000.0))]) },
+                )
+                .await
+                .unwrap()
+            }
+        };
+
+        // without a minimum, the 10 second sliver is offered
+        let pairs = search(0).await;
+        assert_eq!(pairs.len(), 1);
+        assert_eq!(pairs[0].origin_timeslot.duration().num_seconds(), 10);
+
+        // filtered out under the minimum
+        assert!(search(30).await.is_empty());
+
+        ut_info!("success");
+    }
+
+    #[tokio::test]
+    async fn test_find_vertipad_timeslot_pairs_no_paths() {
+        let start = Utc::now();
+        let timeslot = Timeslot::new(start, start + Duration::try_hours(3).unwrap()).unwrap();
+        let origin_vertipads = HashMap::from([(Uuid::new_v4().to_string(), vec![timeslot])]);
+        let target_vertipads = HashMap::from([(Uuid::new_v4().to_string(), vec![timeslot])]);
+
+        // an empty answer is treated as no path found
+        let pairs = find_vertipad_timeslot_pairs(
+            "origin",
+            "target",
+            origin_vertipads,
+            target_vertipads,
+            None,
+            1,
+            &TimeslotPairLimits::default(),
+            |_| async { Ok(vec![]) },
+        )
+        .await
+        .unwrap();
+
+        assert!(pairs.is_empty());
+    }
+
+    #[test]
+    fn test_join_loop_legs() {
+        let point = |latitude: f64| PointZ {
+            latitude,
+            longitude: 4.0,
+            altitude_meters: 100.0,
+        };
+
+        let (path, distance_meters) = join_loop_legs(vec![
+            (vec![point(52.0), point(52.05), point(52.1)], Meters(1000.0)),
+            (vec![point(52.1), point(52.0)], Meters(800.0)),
+        ]);
+
+        // the shared point between legs isn't repeated
+        assert_eq!(
+            path,
+            vec![point(52.0), point(52.05), point(52.1), point(52.0)]
+        );
+        assert_eq!(distance_meters, Meters(1800.0));
+    }
+
+    #[tokio::test]
+    async fn test_get_loop_path() {
+        use crate::router::simulate::StraightLineRouter;
+
+        let location = |latitude: f64, longitude: f64| PointZ {
+            latitude,
+            longitude,
+            altitude_meters: 100.0,
+        };
+
+        let vertiport_id = Uuid::new_v4().to_string();
+        let origin = location(37.7931, -122.46283);
+        let waypoints = vec![location(37.8031, -122.46283), location(37.8031, -122.45283)];
+        let router = StraightLineRouter {
+            locations: HashMap::from([
+                (vertiport_id.clone(), origin.clone()),
+                ("north".to_string(), waypoints[0].clone()),
+                ("north-east".to_string(), waypoints[1].clone()),
+            ]),
+        };
+
+        let now = Utc::now();
+        let timeslot = Timeslot::new(now, now + Duration::try_hours(1).unwrap()).unwrap();
+        let names = vec!["north".to_string(), "north-east".to_string()];
+        let (path, distance_meters) = get_loop_path(&vertiport_id, &names, &timeslot, &router)
+            .await
+            .unwrap()
+            .unwrap();
+
+        // starts and ends at the origin vertiport, visiting each waypoint
+        assert_eq!(path.len(), waypoints.len() + 2);
+        assert_eq!(path.first(), Some(&origin));
+        assert_eq!(path.last(), Some(&origin));
+        assert_eq!(path[1..=waypoints.len()], waypoints[..]);
+
+        // ~1.1km north, ~0.9km east, ~1.4km back
+        assert!(distance_meters > Meters(3000.0));
+        assert!(distance_meters < Meters(4000.0));
+
+        // a waypoint the router doesn't know has no path
+        let names = vec!["north".to_string(), "unknown".to_string()];
+        assert!(get_loop_path(&vertiport_id, &names, &timeslot, &router)
+            .await
+            .unwrap()
+            .is_none());
+
+        // no waypoints, no loop
+        assert!(get_loop_path(&vertiport_id, &[], &timeslot, &router)
+            .await
+            .is_err());
+    }
+
+    #[test]
+    fn test_get_nearby() {
+        let distance = |label: &str, target_type: NodeType, distance_meters: f32| DistanceTo {
+            label: label.to_string(),
+            target_type: target_type as i32,
+            distance_meters,
+        };
+
+        let distances = vec![
+            distance("far", NodeType::Vertiport, 4_000.0),
+            distance("target", NodeType::Vertiport, 0.0),
+            distance("waypoint", NodeType::Waypoint, 500.0),
+            distance("near", NodeType::Vertiport, 1_000.0),
+            distance("too-far", NodeType::Vertiport, 6_000.0),
+        ];
+
+        // other vertiports within the radius, nearest first
+        assert_eq!(
+            get_nearby(distances, "target", Meters(5_000.0)),
+            vec![
+                ("near".to_string(), Meters(1_000.0)),
+                ("far".to_string(), Meters(4_000.0))
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_get_loop_path_node_types() {
+        use crate::router::simulate::StraightLineRouter;
+
+        /// Checks that each leg is routed between the declared node types
+        struct NodeTypeRouter {
+            vertiport_id: String,
+            router: StraightLineRouter,
+        }
+
+        #[tonic::async_trait]
+        impl PathFinder for NodeTypeRouter {
+            async fn best_path(
+                &self,
+                request: &BestPathRequest,
+            ) -> Result<Vec<(Vec<PointZ>, Meters)>, BestPathError> {
+                let node_type = |identifier: &str| match identifier == self.vertiport_id {
+                    true => NodeType::Vertiport as i32,
+                    false => NodeType::Waypoint as i32,
+                };
+
+                assert_eq!(request.origin_type, node_type(&request.origin_identifier));
+                assert_eq!(request.target_type, node_type(&request.target_identifier));
+                self.router.best_path(request).await
+            }
+        }
+
+        let location = |latitude: f64| PointZ {
+            latitude,
+            longitude: -122.46283,
+            altitude_meters: 100.0,
+        };
+
+        let vertiport_id = Uuid::new_v4().to_string();
+        let router = NodeTypeRouter {
+            vertiport_id: vertiport_id.clone(),
+            router: StraightLineRouter {
+                locations: HashMap::from([
+                    (vertiport_id.clone(), location(37.7931)),
+                    ("north".to_string(), location(37.8031)),
+                    ("far-north".to_string(), location(37.8131)),
+                ]),
+            },
+        };
+
+        let now = Utc::now();
+        let timeslot = Timeslot::new(now, now + Duration::try_hours(1).unwrap()).unwrap();
+        let names = vec!["north".to_string(), "far-north".to_string()];
+        let (path, _) = get_loop_path(&vertiport_id, &names, &timeslot, &router)
+            .await
+            .unwrap()
+            .unwrap();
+
+        assert_eq!(path.len(), names.len() + 2);
+        assert_eq!(get_loop_node_type(true), NodeType::Vertiport);
+        assert_eq!(get_loop_node_type(false), NodeType::Waypoint);
+    }
+
+    #[test]
+    fn test_get_centroid() {
+        assert!(get_centroid(&[]).is_none());
+
+        let points = vec![
+            GeoPoint {
+                latitude: 1.0,
+                longitude: 2.0,
+                altitude: 0.0,
+            },
+            GeoPoint {
+                latitude: 3.0,
+                longitude: 4.0,
+                altitude: 10.0,
+            },
+        ];
+
+        let centroid = get_centroid(&points).unwrap();
+        assert_eq!(centroid.latitude, 2.0);
+        assert_eq!(centroid.longitude, 3.0);
+        assert_eq!(centroid.altitude_meters, 5.0);
+    }
+
+    #[test]
+    fn test_get_cached_distances() {
+        let cache = HashMap::from([(
+            "a".to_string(),
+            HashMap::from([
+                ("a".to_string(), Meters(0.0)),
+                ("b".to_string(), Meters(1000.0)),
+            ]),
+        )]);
+
+        let ids = HashSet::from(["b".to_string()]);
+        let distances = get_cached_distances(&cache, "a", &ids).unwrap();
+        assert_eq!(
+            distances,
+            HashMap::from([("b".to_string(), Meters(1000.0))])
+        );
+
+        // vertiports added since warming up aren't cached
+        let ids = HashSet::from(["b".to_string(), "c".to_string()]);
+        assert!(get_cached_distances(&cache, "a", &ids).is_none());
+        assert!(get_cached_distances(&cache, "b", &ids).is_none());
+    }
+
     #[test]
     fn test_vertiport_error_display() {
         assert_eq!(format!("{}", VertiportError::ClientError), "Client error");
@@ -940,6 +2704,14 @@ mod tests {
         assert_eq!(format!("{}", VertiportError::NoVertipads), "No vertipads");
         assert_eq!(format!("{}", VertiportError::NoSchedule), "No schedule");
         assert_eq!(format!("{}", VertiportError::Schedule), "Invalid schedule");
+        assert_eq!(
+            format!("{}", VertiportError::NotFound),
+            "Vertiport not found"
+        );
+        assert_eq!(
+            format!("{}", VertiportError::WaypointNotFound),
+            "Waypoint not found"
+        );
         assert_eq!(format!("{}", VertiportError::Internal), "Internal error");
     }
 
@@ -969,7 +2741,8 @@ mod tests {
                 now + Duration::try_seconds(3).unwrap(),
             )
             .unwrap(),
-            distance_meters: 100.0,
+            distance_meters: Meters(100.0),
+            alternative_paths: vec![],
         };
 
         let data: flight_plan::Data = val.into();
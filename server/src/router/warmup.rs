@@ -0,0 +1,96 @@
+//! Warmup of the router on startup
+//! The configuration and the router clients are built on first use,
+//!  which slows down the first query after a restart. Warming up
+//!  builds them ahead of time.
+
+use crate::config::Config;
+use crate::grpc::client::{get_clients, GrpcClients, CLIENTS};
+use crate::router::vertiport::{get_vertiport_distances, get_vertiport_ids, VERTIPORT_DISTANCES};
+use std::collections::{HashMap, HashSet};
+
+/// Returns true if the router clients have been built
+pub fn is_router_initialized() -> bool {
+    CLIENTS.initialized()
+}
+
+/// Looks up the distances between all vertiports, opening the
+///  connections the first queries will need. The distances are cached
+///  for the queries to use.
+/// Returns the number of routes with a known distance.
+#[cfg(not(tarpaulin_include))]
+// no_coverage: (R5) needs running backend, integration tests
+async fn warmup_route_distances(clients: &GrpcClients) -> usize {
+    let vertiport_ids: HashSet<String> = match get_vertiport_ids(clients).await {
+        Ok(ids) => ids.into_iter().collect(),
+        Err(e) => {
+            router_warn!("Could not get vertiports to warm up: {e}");
+            return 0;
+        }
+    };
+
+    let mut routes = 0;
+    let mut cache = HashMap::new();
+    for origin_vertiport_id in vertiport_ids.iter() {
+        match get_vertiport_distances(origin_vertiport_id, &vertiport_ids, clients).await {
+            Ok(distances) => {
+                // the distance to the origin itself isn't a route
+                routes += distances.len().saturating_sub(1);
+                cache.insert(origin_vertiport_id.clone(), distances);
+            }
+            Err(e) => router_warn!("No distances from vertiport {origin_vertiport_id}: {e}"),
+        }
+    }
+
+    if VERTIPORT_DISTANCES.set(cache).is_err() {
+        router_warn!("Vertiport distances were already cached.");
+    }
+
+    routes
+}
+
+/// Builds the configuration and the router clients if
+///  `warmup_on_startup` is set, and looks up the distances between
+///  vertiports if `warmup_route_distances` is set as well.
+/// Failures are logged, the service starts regardless.
+pub async fn warmup(config: &Config) {
+    if !config.warmup_on_startup {
+        router_debug!("Warmup disabled.");
+        return;
+    }
+
+    router_info!("Warming up the router.");
+    crate::config::get_config().await;
+    let clients = get_clients().await;
+
+    if config.warmup_route_distances {
+        let routes = warmup_route_distances(clients).await;
+        router_info!("Looked up the distances of {routes} routes.");
+    }
+
+    router_info!("Router warmed up.");
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_warmup() {
+        lib_common::logger::get_log_handle().await;
+        ut_info!("Start.");
+
+        let config = Config {
+            warmup_on_startup: true,
+            warmup_route_distances: false,
+            ..Config::default()
+        };
+
+        warmup(&config).await;
+        assert!(is_router_initialized());
+
+        // distances aren't looked up or cached
+        assert!(VERTIPORT_DISTANCES.get().is_none());
+
+        ut_info!("Success.");
+    }
+}
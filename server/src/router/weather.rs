@@ -0,0 +1,289 @@
+//! Adverse weather cells
+//! A weather cell is a temporary no-fly polygon. Paths crossing a cell
+//!  while it is active are dropped in favour of longer alternatives.
+//!
+//! svc-gis doesn't know about the cells: it routes as if the sky were
+//!  clear and the scheduler drops the routes crossing a cell afterwards.
+//!  A flight therefore only avoids a cell if svc-gis happens to return
+//!  a route around it among the requested alternatives, and deadhead
+//!  flights, which request a single route, wait for the cell to pass.
+
+use super::schedule::Timeslot;
+use super::units::Meters;
+use super::BestPathError;
+use crate::router::settings::{get_settings, Setting, SettingError, FIELD_SEPARATOR};
+use std::str::FromStr;
+use svc_gis_client_grpc::prelude::{gis::*, *};
+
+/// Weather cells setting
+pub static WEATHER_CELLS: Setting = Setting {
+    name: "WEATHER_CELLS",
+    format: "start/end|polygon",
+};
+
+/// Separates the corners of the polygon
+const POINT_SEPARATOR: char = ';';
+
+/// Minimum number of corners of a polygon
+const MIN_POLYGON_POINTS: usize = 3;
+
+/// Adverse weather over an area for a period of time
+#[derive(Debug, Clone, PartialEq)]
+pub struct WeatherCell {
+    /// When the cell is active
+    pub timeslot: Timeslot,
+
+    /// Corners of the area as (latitude, longitude)
+    pub polygon: Vec<(f64, f64)>,
+}
+
+/// Returns true if the segments a-b and c-d cross
+fn segments_cross(a: (f64, f64), b: (f64, f64), c: (f64, f64), d: (f64, f64)) -> bool {
+    let orientation = |p: (f64, f64), q: (f64, f64), r: (f64, f64)| {
+        (q.0 - p.0) * (r.1 - p.1) - (q.1 - p.1) * (r.0 - p.0)
+    };
+
+    let d1 = orientation(c, d, a);
+    let d2 = orientation(c, d, b);
+    let d3 = orientation(a, b, c);
+    let d4 = orientation(a, b, d);
+
+    ((d1 > 0.0 && d2 < 0.0) || (d1 < 0.0 && d2 > 0.0))
+        && ((d3 > 0.0 && d4 < 0.0) || (d3 < 0.0 && d4 > 0.0))
+}
+
+impl WeatherCell {
+    /// Returns true if the point is inside the polygon
+    fn contains(&self, point: (f64, f64)) -> bool {
+        if self.polygon.len() < MIN_POLYGON_POINTS {
+            return false;
+        }
+
+        let mut inside = false;
+        let mut j = self.polygon.len() - 1;
+        for (i, &(lat_i, lon_i)) in self.polygon.iter().enumerate() {
+            let (lat_j, lon_j) = self.polygon[j];
+            if (lon_i > point.1) != (lon_j > point.1)
+                && point.0 < (lat_j - lat_i) * (point.1 - lon_i) / (lon_j - lon_i) + lat_i
+            {
+                inside = !inside;
+            }
+
+            j = i;
+        }
+
+        inside
+    }
+
+    /// Returns true if the path enters the polygon
+    pub fn crosses(&self, path: &[PointZ]) -> bool {
+        let points = path
+            .iter()
+            .map(|p| (p.latitude, p.longitude))
+            .collect::<Vec<(f64, f64)>>();
+
+        if points.iter().any(|p| self.contains(*p)) {
+            return true;
+        }
+
+        let edges = self
+            .polygon
+            .iter()
+            .zip(self.polygon.iter().cycle().skip(1))
+            .collect::<Vec<_>>();
+
+        points.windows(2).any(|segment| {
+            edges
+                .iter()
+                .any(|(c, d)| segments_cross(segment[0], segment[1], **c, **d))
+        })
+    }
+}
+
+/// Parses comma-separated `start/end|lat lon;lat lon;...` weather cells
+pub fn parse_weather_cells(cells: &str) -> Result<Vec<WeatherCell>, SettingError> {
+    WEATHER_CELLS.parse_list(cells, |cell| {
+        let [period, polygon] = WEATHER_CELLS.split::<2>(cell, FIELD_SEPARATOR)?;
+        let timeslot = WEATHER_CELLS.parse_period(period)?;
+        let polygon = polygon
+            .split(POINT_SEPARATOR)
+            .map(|point| {
+                let mut coordinates = point.split_whitespace().map(f64::from_str);
+                match (coordinates.next(), coordinates.next(), coordinates.next()) {
+                    (Some(Ok(latitude)), Some(Ok(longitude)), None) => Ok((latitude, longitude)),
+                    _ => {
+                        router_error!("Invalid weather cell point: {point}");
+                        Err(SettingError::Value(&WEATHER_CELLS))
+                    }
+                }
+            })
+            .collect::<Result<Vec<(f64, f64)>, SettingError>>()?;
+
+        if polygon.len() < MIN_POLYGON_POINTS {
+            router_error!("Weather cell polygon has too few points: {cell}");
+            return Err(SettingError::Value(&WEATHER_CELLS));
+        }
+
+        Ok(WeatherCell { timeslot, polygon })
+    })
+}
+
+/// Drops the paths crossing a weather cell active during the request's
+///  time window, keeping the order of the remaining paths.
+/// Without a time window every cell is considered active.
+pub fn avoid_weather_cells(
+    paths: Vec<(Vec<PointZ>, Meters)>,
+    cells: &[WeatherCell],
+    request: &BestPathRequest,
+) -> Result<Vec<(Vec<PointZ>, Meters)>, BestPathError> {
+    let window = match (request.time_start.clone(), request.time_end.clone()) {
+        (Some(start), Some(end)) => Timeslot::new(start.into(), end.into()).ok(),
+        _ => None,
+    };
+
+    let active = cells
+        .iter()
+        .filter(|cell| match window {
+            Some(window) => cell.timeslot.overlap(&window).is_ok(),
+            None => true,
+        })
+        .collect::<Vec<&WeatherCell>>();
+
+    if active.is_empty() {
+        return Ok(paths);
+    }
+
+    let paths = paths
+        .into_iter()
+        .filter(|(path, _)| !active.iter().any(|cell| cell.crosses(path)))
+        .collect::<Vec<(Vec<PointZ>, Meters)>>();
+
+    if paths.is_empty() {
+        router_debug!("Every path crosses a weather cell.");
+        return Err(BestPathError::NoPathFound);
+    }
+
+    Ok(paths)
+}
+
+/// Returns the configured weather cells
+// TODO(R5): get the cells reported by svc-gis when it exposes them
+pub async fn get_weather_cells() -> Result<Vec<WeatherCell>, SettingError> {
+    Ok(get_settings().await?.weather_cells.clone())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use lib_common::time::{DateTime, Duration, Utc};
+
+    fn point(latitude: f64, longitude: f64) -> PointZ {
+        PointZ {
+            latitude,
+            longitude,
+            altitude_meters: 100.0,
+        }
+    }
+
+    fn get_request(start: DateTime<Utc>, end: DateTime<Utc>) -> BestPathRequest {
+        BestPathRequest {
+            origin_identifier: "origin".to_string(),
+            target_identifier: "target".to_string(),
+            origin_type: NodeType::Vertiport as i32,
+            target_type: NodeType::Vertiport as i32,
+            time_start: Some(start.into()),
+            time_end: Some(end.into()),
+            limit: 2,
+        }
+    }
+
+    #[test]
+    fn test_parse_weather_cells() {
+        assert!(parse_weather_cells("").unwrap().is_empty());
+
+        let cells = parse_weather_cells(
+            "2024-01-01T00:00:00Z/2024-01-01T06:00:00Z|52.0 4.0;52.1 4.0;52.1 4.1",
+        )
+        .unwrap();
+        assert_eq!(cells.len(), 1);
+        assert_eq!(
+            cells[0].polygon,
+            vec![(52.0, 4.0), (52.1, 4.0), (52.1, 4.1)]
+        );
+        assert_eq!(
+            cells[0].timeslot.duration(),
+            Duration::try_hours(6).unwrap()
+        );
+
+        let e = parse_weather_cells("52.0 4.0;52.1 4.0;52.1 4.1").unwrap_err();
+        assert_eq!(e, SettingError::Format(&WEATHER_CELLS));
+
+        let e = parse_weather_cells("2024-01-01T00:00:00Z/tomorrow|52.0 4.0;52.1 4.0;52.1 4.1")
+            .unwrap_err();
+        assert_eq!(e, SettingError::Time(&WEATHER_CELLS));
+
+        let e = parse_weather_cells(
+            "2024-01-01T06:00:00Z/2024-01-01T00:00:00Z|52.0 4.0;52.1 4.0;52.1 4.1",
+        )
+        .unwrap_err();
+        assert_eq!(e, SettingError::Timeslot(&WEATHER_CELLS));
+
+        let e = parse_weather_cells("2024-01-01T00:00:00Z/2024-01-01T06:00:00Z|52.0 4.0;52.1 4.0")
+            .unwrap_err();
+        assert_eq!(e, SettingError::Value(&WEATHER_CELLS));
+
+        let e = parse_weather_cells("2024-01-01T00:00:00Z/2024-01-01T06:00:00Z|52.0;52.1 4.0;x 4")
+            .unwrap_err();
+        assert_eq!(e, SettingError::Value(&WEATHER_CELLS));
+    }
+
+    #[test]
+    fn test_weather_cell_crosses() {
+        let now = Utc::now();
+        let cell = WeatherCell {
+            timeslot: Timeslot::new(now, now + Duration::try_hours(1).unwrap()).unwrap(),
+            polygon: vec![(52.03, 4.05), (52.05, 4.03), (52.07, 4.05), (52.05, 4.07)],
+        };
+
+        // straight through the cell, without a point inside it
+        assert!(cell.crosses(&[point(52.0, 4.0), point(52.1, 4.1)]));
+
+        // a waypoint inside the cell
+        assert!(cell.crosses(&[point(52.05, 4.05), point(52.2, 4.2)]));
+
+        // around the cell
+        assert!(!cell.crosses(&[point(52.0, 4.0), point(52.1, 4.0), point(52.1, 4.1)]));
+    }
+
+    #[test]
+    fn test_weather_cell_forces_alternate_path() {
+        let now = Utc::now();
+        let hour = Duration::try_hours(1).unwrap();
+        let direct = vec![point(52.0, 4.0), point(52.1, 4.1)];
+        let detour = vec![point(52.0, 4.0), point(52.1, 4.0), point(52.1, 4.1)];
+        let paths = vec![
+            (direct.clone(), Meters(13000.0)),
+            (detour.clone(), Meters(18000.0)),
+        ];
+
+        let cells = vec![WeatherCell {
+            timeslot: Timeslot::new(now, now + hour).unwrap(),
+            polygon: vec![(52.03, 4.05), (52.05, 4.03), (52.07, 4.05), (52.05, 4.07)],
+        }];
+
+        // the cell is active during the flight, fly the longer detour
+        let request = get_request(now, now + hour);
+        let result = avoid_weather_cells(paths.clone(), &cells, &request).unwrap();
+        assert_eq!(result, vec![(detour.clone(), Meters(18000.0))]);
+
+        // the cell has passed before the flight
+        let request = get_request(now + hour, now + hour * 2);
+        let result = avoid_weather_cells(paths.clone(), &cells, &request).unwrap();
+        assert_eq!(result, paths);
+
+        // no way around the cell
+        let request = get_request(now, now + hour);
+        let e = avoid_weather_cells(vec![(direct, Meters(13000.0))], &cells, &request).unwrap_err();
+        assert_eq!(e, BestPathError::NoPathFound);
+    }
+}
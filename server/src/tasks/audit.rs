@@ -0,0 +1,226 @@
+//! Audit trail of booking and cancellation events.
+//! Every state change of an itinerary appends one record to the
+//!  configured sink, records are never updated or removed.
+
+use crate::tasks::pool::RedisPool;
+use deadpool_redis::redis::{self, ToRedisArgs};
+use lib_common::time::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::fmt::{Display, Formatter, Result as FmtResult};
+use tonic::async_trait;
+
+/// Errors that may occur while emitting an audit record
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum AuditError {
+    /// The sink could not be reached
+    Unavailable,
+
+    /// The sink rejected the record
+    Rejected,
+}
+
+impl Display for AuditError {
+    fn fmt(&self, f: &mut Formatter) -> FmtResult {
+        match self {
+            AuditError::Unavailable => write!(f, "Audit sink unavailable."),
+            AuditError::Rejected => write!(f, "Audit record rejected."),
+        }
+    }
+}
+
+/// State changes of an itinerary
+#[derive(Serialize, Deserialize, Debug, Copy, Clone, PartialEq)]
+pub enum AuditEvent {
+    /// The user confirmed an itinerary, it is queued for booking
+    Confirmed,
+
+    /// The itinerary was booked in storage
+    Booked,
+
+    /// The itinerary was cancelled
+    Cancelled,
+
+    /// The itinerary was cancelled, restorable for a grace period
+    SoftCancelled,
+
+    /// A soft-cancelled itinerary was booked again as a new itinerary
+    Restored,
+}
+
+/// A single entry of the audit trail
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct AuditRecord {
+    /// What happened
+    pub event: AuditEvent,
+
+    /// Authenticated caller that made the change, the user the itinerary
+    ///  belongs to unless an operator acted on their behalf
+    pub user_id: String,
+
+    /// Task that carried out the change, if any
+    pub task_id: Option<i64>,
+
+    /// Itinerary changed, once it exists in storage
+    pub itinerary_id: Option<String>,
+
+    /// Flight plans of the itinerary, once they exist in storage
+    pub flight_plan_ids: Vec<String>,
+
+    /// Soft-cancelled itinerary that this itinerary restores, if any
+    #[serde(default)]
+    pub restored_from: Option<String>,
+
+    /// When the change happened
+    pub timestamp: DateTime<Utc>,
+}
+
+impl ToRedisArgs for AuditRecord {
+    fn write_redis_args<W: ?Sized>(&self, out: &mut W)
+    where
+        W: redis::RedisWrite,
+    {
+        let Ok(result) = serde_json::to_string(&self) else {
+            tasks_warn!("error serializing audit record");
+            return;
+        };
+
+        out.write_arg(result.as_bytes());
+    }
+}
+
+/// Destination of the audit trail
+#[async_trait]
+pub trait AuditSink {
+    /// Appends a record to the audit trail
+    async fn emit(&mut self, record: &AuditRecord) -> Result<(), AuditError>;
+}
+
+/// Appends records to the `scheduler:audit` Redis stream
+#[derive(Debug)]
+pub struct RedisAuditSink {
+    /// Connections to the Redis server
+    pub pool: crate::tasks::pool::TaskPool,
+}
+
+#[async_trait]
+impl AuditSink for RedisAuditSink {
+    #[cfg(not(tarpaulin_include))]
+    // no_coverage: (R5) need redis backend to test this
+    async fn emit(&mut self, record: &AuditRecord) -> Result<(), AuditError> {
+        self.pool.add_audit_record(record).await.map_err(|e| {
+            tasks_error!("Could not add audit record: {e}");
+            AuditError::Rejected
+        })
+    }
+}
+
+/// Writes records to the service log
+#[derive(Debug, Copy, Clone, Default)]
+pub struct LogAuditSink;
+
+#[async_trait]
+impl AuditSink for LogAuditSink {
+    async fn emit(&mut self, record: &AuditRecord) -> Result<(), AuditError> {
+        let record = serde_json::to_string(record).map_err(|e| {
+            tasks_error!("Could not serialize audit record: {e}");
+            AuditError::Rejected
+        })?;
+
+        tasks_info!("audit: {record}");
+        Ok(())
+    }
+}
+
+/// Emits a record to the provided sink.
+/// Failing to audit is logged, it never fails the change being audited.
+pub async fn emit_audit_record<S>(sink: &mut S, record: AuditRecord)
+where
+    S: AuditSink + Send + ?Sized,
+{
+    if let Err(e) = sink.emit(&record).await {
+        tasks_warn!("Could not emit audit record {:?}: {e}", record);
+    }
+}
+
+/// Returns the sink configured in `audit_sink`: "redis" or "log"
+#[cfg(not(tarpaulin_include))]
+// no_coverage: (R5) need redis backend to test this
+pub async fn get_audit_sink() -> Result<Box<dyn AuditSink + Send>, AuditError> {
+    let config = crate::config::get_config().await;
+    match config.audit_sink.as_str() {
+        "log" => Ok(Box::new(LogAuditSink)),
+        "redis" => {
+            let pool = crate::tasks::pool::get_pool().await.ok_or_else(|| {
+                tasks_error!("Couldn't get the redis pool.");
+                AuditError::Unavailable
+            })?;
+
+            Ok(Box::new(RedisAuditSink { pool }))
+        }
+        sink => {
+            tasks_error!("Unknown audit sink: {sink}");
+            Err(AuditError::Unavailable)
+        }
+    }
+}
+
+/// Emits a record to the configured sink
+#[cfg(not(tarpaulin_include))]
+// no_coverage: (R5) need redis backend to test this
+pub async fn audit(record: AuditRecord) {
+    match get_audit_sink().await {
+        Ok(mut sink) => emit_audit_record(sink.as_mut(), record).await,
+        Err(e) => tasks_warn!("Could not emit audit record {:?}: {e}", record),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::MemoryAuditSink;
+
+    fn get_record() -> AuditRecord {
+        AuditRecord {
+            event: AuditEvent::Cancelled,
+            user_id: "user".to_string(),
+            task_id: Some(1),
+            itinerary_id: Some("itinerary".to_string()),
+            flight_plan_ids: vec!["a".to_string(), "b".to_string()],
+            restored_from: None,
+            timestamp: Utc::now(),
+        }
+    }
+
+    #[test]
+    fn test_audit_error_display() {
+        assert_eq!(
+            AuditError::Unavailable.to_string(),
+            "Audit sink unavailable."
+        );
+        assert_eq!(AuditError::Rejected.to_string(), "Audit record rejected.");
+    }
+
+    #[tokio::test]
+    async fn test_emit_audit_record() {
+        let record = get_record();
+
+        let mut sink = MemoryAuditSink::default();
+        emit_audit_record(&mut sink, record.clone()).await;
+        assert_eq!(sink.records, vec![record.clone()]);
+
+        // boxed sinks as returned by the configuration
+        let mut sink: Box<dyn AuditSink + Send> = Box::new(LogAuditSink);
+        emit_audit_record(sink.as_mut(), record).await;
+    }
+
+    #[test]
+    fn test_audit_record_serialization() {
+        let record = get_record();
+        let json = serde_json::to_string(&record).unwrap();
+        assert!(json.contains("\"event\":\"Cancelled\""));
+        assert!(json.contains("\"flight_plan_ids\":[\"a\",\"b\"]"));
+
+        let parsed: AuditRecord = serde_json::from_str(&json).unwrap();
+        assert_eq!(parsed, record);
+    }
+}
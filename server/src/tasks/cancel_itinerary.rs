@@ -1,71 +1,26 @@
 //! This module contains the gRPC cancel_itinerary endpoint implementation.
 
-use crate::grpc::client::get_clients;
-use crate::grpc::server::grpc_server::TaskStatus;
+use crate::clock::get_clock;
+use crate::grpc::client::{get_clients, GrpcClients};
+use crate::grpc::server::grpc_server::{CancellationKind, TaskStatus};
+use crate::router::flight_plan::FlightPlanSchedule;
+use crate::tasks::audit::{audit, AuditEvent, AuditRecord};
+use crate::tasks::soft_cancel::{get_soft_cancellation, record_soft_cancellation};
 use crate::tasks::{Task, TaskAction, TaskBody, TaskError};
+use lib_common::uuid::Uuid;
 use num_traits::FromPrimitive;
 use svc_storage_client_grpc::prelude::Id as StorageId;
 use svc_storage_client_grpc::prelude::*;
 
-/// Cancels an itinerary
+/// Marks an active itinerary and its flight plans as cancelled in storage.
+/// Returns the IDs and schedules of the cancelled flight plans.
 #[cfg(not(tarpaulin_include))]
-pub async fn cancel_itinerary(task: &mut Task) -> Result<(), TaskError> {
-    let Some(TaskAction::CancelItinerary) = FromPrimitive::from_i32(task.metadata.action) else {
-        tasks_error!("Invalid task action: {}", task.metadata.action);
-        return Err(TaskError::Metadata);
-    };
-
-    let TaskBody::CancelItinerary(itinerary_id) = &task.body else {
-        tasks_error!("Invalid task body: {:?}", task.body);
-        return Err(TaskError::Data);
-    };
-
-    tasks_info!("for id {}.", &itinerary_id);
-
-    let clients = get_clients().await;
-
-    // prevent cancellations by a different user
-    let filter = AdvancedSearchFilter::search_equals("id".to_string(), itinerary_id.to_string())
-        .and_equals("user_id".to_string(), task.metadata.user_id.clone())
-        .and_equals(
-            "status".to_string(),
-            (itinerary::ItineraryStatus::Active as i32).to_string(),
-        );
-
-    let data = clients
-        .storage
-        .itinerary
-        .search(filter)
-        .await
-        .map_err(|e| {
-            tasks_warn!("Could not find itinerary with ID {itinerary_id}: {e}");
-            TaskError::Data
-        })?
-        .into_inner()
-        .list
-        .pop()
-        .ok_or_else(|| {
-            tasks_warn!(
-                "Could not find active itinerary with ID {itinerary_id} for user ID {}.",
-                task.metadata.user_id
-            );
-            TaskError::Data
-        })?
-        .data
-        .ok_or_else(|| {
-            tasks_warn!("Itinerary has invalid data: {}", itinerary_id);
-            TaskError::Internal
-        })?;
-
-    if data.status != itinerary::ItineraryStatus::Active as i32 {
-        tasks_warn!("Itinerary with ID: {} is not active.", itinerary_id);
-        return Err(TaskError::AlreadyProcessed);
-    }
-
-    //
-    // TODO(R5) Don't allow cancellations within X minutes of the first flight
-    //
-
+// no_coverage: (R5) needs running backend, integration tests
+pub(crate) async fn cancel_itinerary_in_storage(
+    clients: &GrpcClients,
+    itinerary_id: &Uuid,
+    data: itinerary::Data,
+) -> Result<Vec<(String, FlightPlanSchedule)>, TaskError> {
     //
     // TODO(R5): Heal the gap created by the removed flight plans
     //
@@ -85,7 +40,8 @@ pub async fn cancel_itinerary(task: &mut Task) -> Result<(), TaskError> {
     };
 
     clients
-        .storage
+        .storage()
+        .await
         .itinerary
         .update(update_object)
         .await
@@ -100,7 +56,8 @@ pub async fn cancel_itinerary(task: &mut Task) -> Result<(), TaskError> {
     );
 
     let response = clients
-        .storage
+        .storage()
+        .await
         .itinerary_flight_plan_link
         .get_linked_ids(StorageId {
             id: itinerary_id.to_string(),
@@ -119,10 +76,12 @@ pub async fn cancel_itinerary(task: &mut Task) -> Result<(), TaskError> {
     // with temp code in for loop, but should be:
     // let mut flight_plan_data = flight_plan::Data::default();
     // flight_plan_data.flight_status = flight_plan::FlightStatus::Cancelled as i32;
+    let mut cancelled = vec![];
     for id in response.into_inner().ids {
         // begin temp code
         let Ok(flight_plan) = clients
-            .storage
+            .storage()
+            .await
             .flight_plan
             .get_by_id(StorageId { id: id.clone() })
             .await
@@ -152,9 +111,13 @@ pub async fn cancel_itinerary(task: &mut Task) -> Result<(), TaskError> {
             }),
         };
 
-        match clients.storage.flight_plan.update(request).await {
+        match clients.storage().await.flight_plan.update(request).await {
             Ok(_) => {
                 tasks_info!("Cancelled flight plan with ID: {id}");
+                match FlightPlanSchedule::try_from(flight_plan_data) {
+                    Ok(schedule) => cancelled.push((id, schedule)),
+                    Err(e) => tasks_warn!("Invalid schedule for flight plan {id}: {e}"),
+                }
             }
             Err(e) => {
                 tasks_error!("WARNING: Could not cancel flight plan with ID: {id}; {e}");
@@ -162,8 +125,107 @@ pub async fn cancel_itinerary(task: &mut Task) -> Result<(), TaskError> {
         }
     }
 
+    Ok(cancelled)
+}
+
+/// Cancels an itinerary
+#[cfg(not(tarpaulin_include))]
+pub async fn cancel_itinerary(task: &mut Task, task_id: i64) -> Result<(), TaskError> {
+    let Some(TaskAction::CancelItinerary) = FromPrimitive::from_i32(task.metadata.action) else {
+        tasks_error!("Invalid task action: {}", task.metadata.action);
+        return Err(TaskError::Metadata);
+    };
+
+    let (itinerary_id, kind) = match &task.body {
+        TaskBody::CancelItinerary(itinerary_id) => (itinerary_id, CancellationKind::Hard),
+        TaskBody::SoftCancelItinerary(itinerary_id) => (itinerary_id, CancellationKind::Soft),
+        _ => {
+            tasks_error!("Invalid task body: {:?}", task.body);
+            return Err(TaskError::Data);
+        }
+    };
+
+    tasks_info!("for id {}.", &itinerary_id);
+
+    let clients = get_clients().await;
+
+    // prevent cancellations by a different user
+    let filter = AdvancedSearchFilter::search_equals("id".to_string(), itinerary_id.to_string())
+        .and_equals("user_id".to_string(), task.metadata.user_id.clone())
+        .and_equals(
+            "status".to_string(),
+            (itinerary::ItineraryStatus::Active as i32).to_string(),
+        );
+
+    let data = clients
+        .storage()
+        .await
+        .itinerary
+        .search(filter)
+        .await
+        .map_err(|e| {
+            tasks_warn!("Could not find itinerary with ID {itinerary_id}: {e}");
+            TaskError::Data
+        })?
+        .into_inner()
+        .list
+        .pop()
+        .ok_or_else(|| {
+            tasks_warn!(
+                "Could not find active itinerary with ID {itinerary_id} for user ID {}.",
+                task.metadata.user_id
+            );
+            TaskError::Data
+        })?
+        .data
+        .ok_or_else(|| {
+            tasks_warn!("Itinerary has invalid data: {}", itinerary_id);
+            TaskError::Internal
+        })?;
+
+    if data.status != itinerary::ItineraryStatus::Active as i32 {
+        tasks_warn!("Itinerary with ID: {} is not active.", itinerary_id);
+        return Err(TaskError::AlreadyProcessed);
+    }
+
+    //
+    // TODO(R5) Don't allow cancellations within X minutes of the first flight
+    //
+
+    let (flight_plan_ids, cancelled): (Vec<String>, Vec<FlightPlanSchedule>) =
+        cancel_itinerary_in_storage(clients, itinerary_id, data)
+            .await?
+            .into_iter()
+            .unzip();
+
+    audit(AuditRecord {
+        event: match kind {
+            CancellationKind::Hard => AuditEvent::Cancelled,
+            CancellationKind::Soft => AuditEvent::SoftCancelled,
+        },
+        user_id: task.get_actor_id().to_string(),
+        task_id: Some(task_id),
+        itinerary_id: Some(itinerary_id.to_string()),
+        flight_plan_ids,
+        restored_from: None,
+        timestamp: get_clock().now(),
+    })
+    .await;
+
+    // Soft cancellations can be restored by the same user during the grace period
+    if let Some(cancellation) = get_soft_cancellation(kind, &task.metadata.user_id, &cancelled) {
+        if let Err(e) = record_soft_cancellation(itinerary_id, &cancellation).await {
+            tasks_warn!("Itinerary {itinerary_id} will not be restorable: {e}");
+        }
+    }
+
     task.metadata.status = TaskStatus::Complete.into();
 
+    // Freed capacity may satisfy waitlisted requests for the same route
+    if let Err(e) = crate::tasks::waitlist::rescan_waitlist(&cancelled).await {
+        tasks_warn!("Could not re-evaluate waitlist: {e}");
+    }
+
     // TODO(R5): Internal cancellations should change this to InternalCancelled
     // task.body.status_rationale = TaskStatusRationale::ClientCancelled;
 
@@ -174,7 +236,6 @@ pub async fn cancel_itinerary(task: &mut Task) -> Result<(), TaskError> {
 mod tests {
     use super::*;
     use crate::tasks::{TaskAction, TaskBody, TaskMetadata};
-    use lib_common::uuid::Uuid;
 
     type TaskResult = Result<(), TaskError>;
 
@@ -186,9 +247,13 @@ mod tests {
                 ..Default::default()
             },
             body: TaskBody::CreateItinerary(vec![]),
+            eviction_priority: None,
+            return_hold: None,
+            restores: None,
+            actor_id: None,
         };
 
-        let e = cancel_itinerary(&mut task).await.unwrap_err();
+        let e = cancel_itinerary(&mut task, 1).await.unwrap_err();
         assert_eq!(e, TaskError::Data);
 
         Ok(())
@@ -202,9 +267,13 @@ mod tests {
                 ..Default::default()
             },
             body: TaskBody::CancelItinerary(Uuid::new_v4()),
+            eviction_priority: None,
+            return_hold: None,
+            restores: None,
+            actor_id: None,
         };
 
-        let e = cancel_itinerary(&mut task).await.unwrap_err();
+        let e = cancel_itinerary(&mut task, 1).await.unwrap_err();
         assert_eq!(e, TaskError::Metadata);
 
         Ok(())
@@ -219,9 +288,13 @@ mod tests {
                 ..Default::default()
             },
             body: TaskBody::CancelItinerary(Uuid::new_v4()),
+            eviction_priority: None,
+            return_hold: None,
+            restores: None,
+            actor_id: None,
         };
 
-        let e = cancel_itinerary(&mut task).await.unwrap_err();
+        let e = cancel_itinerary(&mut task, 1).await.unwrap_err();
         assert_eq!(e, TaskError::Data);
 
         Ok(())
@@ -0,0 +1,254 @@
+//! Consolidation of waitlisted cargo shipments.
+//! Small shipments between the same vertiports with overlapping windows
+//!  can share one flight if their combined weight fits the aircraft.
+//! An itinerary belongs to a single user, so the shared flight is booked
+//!  for the first shipment; the others stay waitlisted until they are
+//!  booked themselves.
+
+use crate::tasks::waitlist::WaitlistEntry;
+
+/// Waitlisted entries planned onto one shared flight
+#[derive(Debug, Clone, PartialEq)]
+pub struct Consolidation {
+    /// IDs of the waitlist entries on the flight
+    pub entry_ids: Vec<String>,
+
+    /// Combined entry to query and book the flight for: the window
+    ///  all entries share and their total weight.
+    /// Booked on behalf of the user of the first entry.
+    pub entry: WaitlistEntry,
+}
+
+impl Consolidation {
+    /// Returns the ID of the entry the flight is booked for
+    pub fn booked_entry_id(&self) -> Option<&String> {
+        self.entry_ids.first()
+    }
+
+    /// Returns true if an aircraft carrying at most `max_payload_grams`
+    ///  can fly the shipments. A single entry isn't consolidated and
+    ///  fits any aircraft the query returns.
+    pub fn fits_payload(&self, max_payload_grams: u32) -> bool {
+        self.entry_ids.len() < 2 || self.entry.weight_grams.unwrap_or(0) <= max_payload_grams
+    }
+}
+
+/// Returns true if the entry is a cargo shipment that may share a flight
+fn is_consolidatable(entry: &WaitlistEntry) -> bool {
+    // emergency flights are never held back for other shipments
+    entry.is_cargo && !entry.emergency && entry.weight_grams.is_some()
+}
+
+/// Combines two cargo shipments into one, if they fly the same route
+///  in overlapping windows and their combined weight fits
+fn merge_entries(
+    a: &WaitlistEntry,
+    b: &WaitlistEntry,
+    max_weight_grams: u32,
+) -> Option<WaitlistEntry> {
+    if !is_consolidatable(a)
+        || !is_consolidatable(b)
+        || a.origin_vertiport_id != b.origin_vertiport_id
+        || a.target_vertiport_id != b.target_vertiport_id
+        || a.aircraft_type != b.aircraft_type
+        || a.required_arrival_precision_seconds != b.required_arrival_precision_seconds
+        || a.max_total_travel_seconds != b.max_total_travel_seconds
+    {
+        return None;
+    }
+
+    let earliest_departure_time = a.earliest_departure_time.max(b.earliest_departure_time);
+    let latest_arrival_time = a.latest_arrival_time.min(b.latest_arrival_time);
+    if earliest_departure_time >= latest_arrival_time {
+        return None;
+    }
+
+    let weight_grams = a.weight_grams?.checked_add(b.weight_grams?)?;
+    if weight_grams > max_weight_grams {
+        return None;
+    }
+
+    let mut excluded_vertiport_ids = a.excluded_vertiport_ids.clone();
+    for id in b.excluded_vertiport_ids.iter() {
+        if !excluded_vertiport_ids.contains(id) {
+            excluded_vertiport_ids.push(id.clone());
+        }
+    }
+
+    Some(WaitlistEntry {
+        priority: a.priority.max(b.priority),
+        weight_grams: Some(weight_grams),
+        earliest_departure_time,
+        latest_arrival_time,
+        excluded_vertiport_ids,
+        ..a.clone()
+    })
+}
+
+/// Groups waitlisted entries into the flights to plan for them.
+/// Cargo shipments are added to the first earlier group they fit,
+///  every other entry gets a flight of its own. `max_weight_grams` is
+///  the heaviest payload of any aircraft, 0 disables consolidation; the
+///  aircraft assigned to a group must still carry its weight.
+pub fn consolidate_entries(
+    mut entries: Vec<(String, WaitlistEntry)>,
+    max_weight_grams: u32,
+) -> Vec<Consolidation> {
+    // earliest departures first, so shared windows stay as wide as possible
+    entries.sort_by(|(_, a), (_, b)| a.earliest_departure_time.cmp(&b.earliest_departure_time));
+
+    let mut consolidations: Vec<Consolidation> = vec![];
+    for (entry_id, entry) in entries {
+        let merged = match max_weight_grams {
+            0 => None,
+            _ => consolidations.iter_mut().find_map(|group| {
+                merge_entries(&group.entry, &entry, max_weight_grams).map(|merged| (group, merged))
+            }),
+        };
+
+        match merged {
+            Some((group, merged)) => {
+                tasks_debug!(
+                    "Waitlist entry {entry_id} shares a flight with {:?}.",
+                    group.entry_ids
+                );
+                group.entry_ids.push(entry_id);
+                group.entry = merged;
+            }
+            None => consolidations.push(Consolidation {
+                entry_ids: vec![entry_id],
+                entry,
+            }),
+        }
+    }
+
+    consolidations
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use lib_common::time::{DateTime, Duration, Utc};
+    use lib_common::uuid::Uuid;
+    use svc_storage_client_grpc::prelude::flight_plan;
+
+    fn get_entry(
+        origin_vertiport_id: &str,
+        target_vertiport_id: &str,
+        earliest_departure_time: DateTime<Utc>,
+        weight_grams: u32,
+    ) -> WaitlistEntry {
+        WaitlistEntry {
+            user_id: Uuid::new_v4().to_string(),
+            priority: flight_plan::FlightPriority::Low as i32,
+            is_cargo: true,
+            persons: None,
+            weight_grams: Some(weight_grams),
+            origin_vertiport_id: origin_vertiport_id.to_string(),
+            target_vertiport_id: target_vertiport_id.to_string(),
+            earliest_departure_time,
+            latest_arrival_time: earliest_departure_time + Duration::try_hours(2).unwrap(),
+            excluded_vertiport_ids: vec![],
+            aircraft_type: None,
+            emergency: false,
+            required_arrival_precision_seconds: None,
+            ranking: None,
+            preferred_departure_time: None,
+            origin_type: None,
+            target_type: None,
+            max_total_travel_seconds: None,
+        }
+    }
+
+    #[test]
+    fn test_consolidate_two_cargo_entries() {
+        let now = Utc::now();
+        let origin = Uuid::new_v4().to_string();
+        let target = Uuid::new_v4().to_string();
+        let first = get_entry(&origin, &target, now, 4000);
+        let mut second = get_entry(
+            &origin,
+            &target,
+            now + Duration::try_hours(1).unwrap(),
+            5000,
+        );
+        second.priority = flight_plan::FlightPriority::High as i32;
+
+        let entries = vec![
+            ("b".to_string(), second.clone()),
+            ("a".to_string(), first.clone()),
+        ];
+
+        // both fit on one flight, in the window they share
+        let consolidations = consolidate_entries(entries.clone(), 10000);
+        assert_eq!(consolidations.len(), 1);
+        let group = &consolidations[0];
+        assert_eq!(group.entry_ids, vec!["a".to_string(), "b".to_string()]);
+        assert_eq!(group.entry.user_id, first.user_id);
+        assert_eq!(group.entry.weight_grams, Some(9000));
+        assert_eq!(group.entry.priority, second.priority);
+        assert_eq!(
+            group.entry.earliest_departure_time,
+            second.earliest_departure_time
+        );
+        assert_eq!(group.entry.latest_arrival_time, first.latest_arrival_time);
+
+        // the flight is booked for the first shipment
+        assert_eq!(group.booked_entry_id(), Some(&"a".to_string()));
+
+        // the assigned aircraft must carry both
+        assert!(group.fits_payload(9000));
+        assert!(!group.fits_payload(8000));
+
+        // too heavy together, or consolidation disabled
+        let consolidations = consolidate_entries(entries.clone(), 8000);
+        assert_eq!(consolidations.len(), 2);
+        assert!(consolidations[0].fits_payload(0));
+        assert_eq!(consolidate_entries(entries, 0).len(), 2);
+    }
+
+    #[test]
+    fn test_consolidate_incompatible_entries() {
+        let now = Utc::now();
+        let origin = Uuid::new_v4().to_string();
+        let target = Uuid::new_v4().to_string();
+        let entry = get_entry(&origin, &target, now, 1000);
+
+        // windows don't overlap
+        let later = get_entry(
+            &origin,
+            &target,
+            now + Duration::try_hours(2).unwrap(),
+            1000,
+        );
+        assert!(merge_entries(&entry, &later, 10000).is_none());
+
+        // different route
+        let other = get_entry(&target, &origin, now, 1000);
+        assert!(merge_entries(&entry, &other, 10000).is_none());
+
+        // people, emergencies and different aircraft types fly alone
+        let mut tmp = entry.clone();
+        tmp.is_cargo = false;
+        assert!(merge_entries(&entry, &tmp, 10000).is_none());
+
+        let mut tmp = entry.clone();
+        tmp.emergency = true;
+        assert!(merge_entries(&entry, &tmp, 10000).is_none());
+
+        let mut tmp = entry.clone();
+        tmp.aircraft_type = Some(1);
+        assert!(merge_entries(&entry, &tmp, 10000).is_none());
+
+        // excluded vertiports of both shipments are avoided
+        let mut a = entry.clone();
+        a.excluded_vertiport_ids = vec!["x".to_string()];
+        let mut b = entry.clone();
+        b.excluded_vertiport_ids = vec!["x".to_string(), "y".to_string()];
+        let merged = merge_entries(&a, &b, 10000).unwrap();
+        assert_eq!(
+            merged.excluded_vertiport_ids,
+            vec!["x".to_string(), "y".to_string()]
+        );
+    }
+}
@@ -1,8 +1,16 @@
+use crate::clock::get_clock;
 use crate::grpc::client::{get_clients, GrpcClients};
 use crate::router::flight_plan::{get_sorted_flight_plans, FlightPlanSchedule};
 use crate::router::schedule::Timeslot;
 use crate::router::vehicle::{get_aircraft, get_aircraft_availabilities};
+use crate::tasks::audit::{audit, AuditEvent, AuditRecord};
+use crate::tasks::pool::RedisPool;
+use crate::tasks::return_hold::{
+    evict_contending_holds, get_unavailable_held_flight_plans, record_return_hold,
+    release_confirmed_holds, ReturnHoldRequest,
+};
 use crate::tasks::{Task, TaskAction, TaskBody, TaskError};
+use lib_common::time::Duration;
 use lib_common::uuid::Uuid;
 use num_traits::FromPrimitive;
 use std::collections::HashSet;
@@ -17,13 +25,14 @@ use svc_storage_client_grpc::simple_service::Client as SimpleClient;
 const SESSION_ID_PREFIX: &str = "AETH";
 
 /// Register flight plans with svc-storage and return the itinerary ID
+///  and the flight plan IDs
 #[cfg(not(tarpaulin_include))]
 // no_coverage: (R5) needs running backend, integration tests
 async fn register_flight_plans(
     clients: &GrpcClients,
     user_id: &Uuid,
     flight_plans: &[FlightPlanSchedule],
-) -> Result<String, TaskError> {
+) -> Result<(String, Vec<String>), TaskError> {
     //
     // TODO(R5): Do this in a transaction if possible, so that flight plans
     //  are rolled back if any part of the itinerary fails to be created.
@@ -46,7 +55,8 @@ async fn register_flight_plans(
         })?;
 
         let result = clients
-            .storage
+            .storage()
+            .await
             .flight_plan
             .insert(tmp)
             .await
@@ -71,7 +81,8 @@ async fn register_flight_plans(
             .session_id; // the short flight id (i.e. KLM 1234)
 
         let registration_id = clients
-            .storage
+            .storage()
+            .await
             .vehicle
             .get_by_id(Id {
                 id: flight_plan.vehicle_id.to_string(),
@@ -118,7 +129,8 @@ async fn register_flight_plans(
     };
 
     let itinerary_id = clients
-        .storage
+        .storage()
+        .await
         .itinerary
         .insert(data)
         .await
@@ -138,12 +150,13 @@ async fn register_flight_plans(
     // 3) Link flight plans to itinerary in `itinerary_flight_plan`
     //
     let _ = clients
-        .storage
+        .storage()
+        .await
         .itinerary_flight_plan_link
         .link(itinerary::ItineraryFlightPlans {
             id: itinerary_id.clone(),
             other_id_list: Some(IdList {
-                ids: flight_plan_ids,
+                ids: flight_plan_ids.clone(),
             }),
         })
         .await
@@ -153,29 +166,22 @@ async fn register_flight_plans(
         })?;
 
     tasks_info!("Registered itinerary: {}", itinerary_id);
-    Ok(itinerary_id)
+    Ok((itinerary_id, flight_plan_ids))
 }
 
-/// Creates an itinerary given a list of flight plans, if valid
+/// Checks that the flight plans of an itinerary can be flown: the
+///  aircraft and vertipads are free, counting the returns held for
+///  other users as booked unless the `eviction_priority` may evict them.
+/// Returns the flight plans to book, landing on a sibling vertipad
+///  where the requested vertipad is busy.
 #[cfg(not(tarpaulin_include))]
 // no_coverage: (R5) needs running backend, integration tests
-pub async fn create_itinerary(task: &mut Task) -> Result<(), TaskError> {
-    let Some(TaskAction::CreateItinerary) = FromPrimitive::from_i32(task.metadata.action) else {
-        tasks_error!("Invalid task action: {}", task.metadata.action);
-
-        return Err(TaskError::Metadata);
-    };
-
-    let user_id = Uuid::parse_str(&task.metadata.user_id.clone()).map_err(|e| {
-        tasks_error!("Invalid user_id: {}", e);
-        TaskError::UserId
-    })?;
-
-    let TaskBody::CreateItinerary(ref proposed_flight_plans) = task.body else {
-        tasks_error!("Invalid task body: {:?}", task.body);
-        return Err(TaskError::Data);
-    };
-
+async fn get_bookable_flight_plans(
+    clients: &GrpcClients,
+    user_id: &Uuid,
+    proposed_flight_plans: &[FlightPlanSchedule],
+    eviction_priority: Option<i32>,
+) -> Result<Vec<FlightPlanSchedule>, TaskError> {
     // For retrieving asset information in one go
     let mut vertipad_ids = HashSet::new();
     let mut aircraft_id = String::new();
@@ -216,7 +222,6 @@ pub async fn create_itinerary(task: &mut Task) -> Result<(), TaskError> {
     //
     // Fast intersection check before collecting all sorts of data
     //
-    let clients = get_clients().await;
     for flight_plan in proposed_flight_plans {
         let path = flight_plan.path.clone().ok_or_else(|| {
             tasks_error!("Flight plan has no path.");
@@ -254,20 +259,34 @@ pub async fn create_itinerary(task: &mut Task) -> Result<(), TaskError> {
     //  in R5 if there's a more complicated way to form (A & B) || (C & D) type queries
     //  to storage we'll replace it.
     // let vertipad_ids = vertipad_ids.into_iter().collect::<Vec<String>>();
-    let existing_flight_plans: Vec<FlightPlanSchedule> = get_sorted_flight_plans(clients)
+    // Sibling vertipads at the landing vertiports may be used if a pad is busy
+    let target_vertiport_ids = proposed_flight_plans
+        .iter()
+        .map(|plan| plan.target_vertiport_id.clone())
+        .collect::<HashSet<String>>();
+
+    // Returns held for other users are treated as booked, unless this
+    //  booking may evict them
+    let held_flight_plans =
+        get_unavailable_held_flight_plans(Some(&user_id.to_string()), eviction_priority).await?;
+    let mut existing_flight_plans: Vec<FlightPlanSchedule> = get_sorted_flight_plans(clients)
         .await
         .map_err(|e| {
             tasks_error!("Could not get existing flight plans: {}", e);
             TaskError::Internal
         })?
         .into_iter()
+        .chain(held_flight_plans)
         .filter(|plan| {
             // Filter out plans that are not in the vertipad list
             vertipad_ids.contains(&plan.origin_vertipad_id)
                 || vertipad_ids.contains(&plan.target_vertipad_id)
+                || target_vertiport_ids.contains(&plan.origin_vertiport_id)
+                || target_vertiport_ids.contains(&plan.target_vertiport_id)
                 || plan.vehicle_id == aircraft_id
         })
         .collect::<Vec<FlightPlanSchedule>>();
+    existing_flight_plans.sort();
 
     //
     // Get all aircraft availabilities
@@ -283,11 +302,19 @@ pub async fn create_itinerary(task: &mut Task) -> Result<(), TaskError> {
     // Get the availability that contains at minimum the requested flight
     // The supplied itinerary (from query_itinerary) should also include the deadhead flights
     //
+    let config = crate::config::get_config().await;
+    let connection_grace = Duration::try_minutes(config.connection_grace_minutes.into())
+        .ok_or_else(|| {
+            tasks_error!("error creating connection grace duration.");
+            TaskError::Internal
+        })?;
+
     let aircraft_gaps = get_aircraft_availabilities(
         &existing_flight_plans,
         &aircraft_time_window.time_start(),
         &aircraft,
         &aircraft_time_window,
+        &connection_grace,
     )
     .map_err(|e| {
         tasks_error!("{}", e);
@@ -321,7 +348,9 @@ pub async fn create_itinerary(task: &mut Task) -> Result<(), TaskError> {
         TaskError::Internal
     })?;
 
-    for flight_plan in proposed_flight_plans {
+    let mut flight_plans = proposed_flight_plans.to_vec();
+    for i in 0..flight_plans.len() {
+        let flight_plan = &flight_plans[i];
         let origin_duration = flight_plan.origin_timeslot_end - flight_plan.origin_timeslot_start;
         let origin_timeslots = crate::router::vertiport::get_available_timeslots(
             &flight_plan.origin_vertiport_id,
@@ -350,13 +379,22 @@ pub async fn create_itinerary(task: &mut Task) -> Result<(), TaskError> {
             return Err(TaskError::ScheduleConflict);
         };
 
-        let target_duration = flight_plan.target_timeslot_end - flight_plan.target_timeslot_start;
-        let target_timeslots = crate::router::vertiport::get_available_timeslots(
+        // Land on the requested vertipad if free, otherwise on a sibling vertipad
+        let target_window = Timeslot::new(
+            flight_plan.target_timeslot_start,
+            flight_plan.target_timeslot_end,
+        )
+        .map_err(|e| {
+            tasks_error!("Invalid target timeslot: {}", e);
+            TaskError::Data
+        })?;
+
+        let choice = crate::router::vertiport::get_vertipad_with_fallback(
             &flight_plan.target_vertiport_id,
-            Some(&flight_plan.target_vertipad_id),
+            &flight_plan.target_vertipad_id,
             &existing_flight_plans,
             &timeslot,
-            &target_duration,
+            &target_window,
             clients,
         )
         .await
@@ -364,29 +402,177 @@ pub async fn create_itinerary(task: &mut Task) -> Result<(), TaskError> {
             tasks_error!("{}", e);
             TaskError::Internal
         })?
-        .remove(&flight_plan.target_vertipad_id)
         .ok_or_else(|| {
-            tasks_error!("No timeslots available for this vertipad.");
+            tasks_error!("This requested timeslot is not available.");
             TaskError::ScheduleConflict
         })?;
 
-        if !target_timeslots.into_iter().any(|gap| {
-            gap.time_start() <= flight_plan.target_timeslot_start
-                && gap.time_end() >= flight_plan.target_timeslot_end
-        }) {
-            tasks_error!("This requested timeslot is not available.");
-            return Err(TaskError::ScheduleConflict);
-        };
+        if choice.is_fallback {
+            tasks_info!(
+                "Vertipad {} is busy, landing on vertipad {} instead.",
+                flight_plan.target_vertipad_id,
+                choice.vertipad_id
+            );
+
+            // The aircraft departs the next leg from where it landed
+            if let Some(next) = flight_plans.get_mut(i + 1) {
+                next.origin_vertipad_id = choice.vertipad_id.clone();
+            }
+
+            flight_plans[i].target_vertipad_id = choice.vertipad_id;
+        }
     }
 
+    Ok(flight_plans)
+}
+
+/// Returns the vertipads landed on instead of the requested vertipads,
+///  in flight plan order
+fn get_fallback_vertipad_ids(
+    proposed_flight_plans: &[FlightPlanSchedule],
+    flight_plans: &[FlightPlanSchedule],
+) -> Vec<String> {
+    proposed_flight_plans
+        .iter()
+        .zip(flight_plans)
+        .filter(|(proposed, booked)| proposed.target_vertipad_id != booked.target_vertipad_id)
+        .map(|(_, booked)| booked.target_vertipad_id.clone())
+        .collect()
+}
+
+/// Returns the audit record of a booked itinerary, linking it to the
+///  soft-cancelled itinerary it restores, if any
+fn get_booked_record(
+    task: &Task,
+    task_id: i64,
+    itinerary_id: String,
+    flight_plan_ids: Vec<String>,
+) -> AuditRecord {
+    AuditRecord {
+        event: match task.restores {
+            Some(_) => AuditEvent::Restored,
+            None => AuditEvent::Booked,
+        },
+        user_id: task.get_actor_id().to_string(),
+        task_id: Some(task_id),
+        itinerary_id: Some(itinerary_id),
+        flight_plan_ids,
+        restored_from: task.restores.map(|id| id.to_string()),
+        timestamp: get_clock().now(),
+    }
+}
+
+/// Removes the soft cancellation of a restored itinerary
+#[cfg(not(tarpaulin_include))]
+// no_coverage: (R5) need redis backend to test this
+async fn forget_soft_cancellation(itinerary_id: &Uuid) {
+    let Some(mut pool) = crate::tasks::pool::get_pool().await else {
+        tasks_warn!("Couldn't get the redis pool, itinerary {itinerary_id} is still restorable.");
+        return;
+    };
+
+    if let Err(e) = pool.remove_soft_cancellation(itinerary_id).await {
+        tasks_warn!("Could not remove soft cancellation of itinerary {itinerary_id}: {e}");
+    }
+}
+
+/// Creates an itinerary given a list of flight plans, if valid
+#[cfg(not(tarpaulin_include))]
+// no_coverage: (R5) needs running backend, integration tests
+pub async fn create_itinerary(task: &mut Task, task_id: i64) -> Result<(), TaskError> {
+    let Some(TaskAction::CreateItinerary) = FromPrimitive::from_i32(task.metadata.action) else {
+        tasks_error!("Invalid task action: {}", task.metadata.action);
+
+        return Err(TaskError::Metadata);
+    };
+
+    let user_id = Uuid::parse_str(&task.metadata.user_id.clone()).map_err(|e| {
+        tasks_error!("Invalid user_id: {}", e);
+        TaskError::UserId
+    })?;
+
+    let TaskBody::CreateItinerary(ref proposed_flight_plans) = task.body else {
+        tasks_error!("Invalid task body: {:?}", task.body);
+        return Err(TaskError::Data);
+    };
+
+    let clients = get_clients().await;
+    let flight_plans = get_bookable_flight_plans(
+        clients,
+        &user_id,
+        proposed_flight_plans,
+        task.eviction_priority,
+    )
+    .await?;
+
     // If we've reached this point, the itinerary is valid
     // Register it with svc-storage
-    let itinerary_id = register_flight_plans(clients, &user_id, proposed_flight_plans).await?;
-    task.metadata.result = Some(itinerary_id);
+    let (itinerary_id, flight_plan_ids) =
+        register_flight_plans(clients, &user_id, &flight_plans).await?;
+    task.metadata.result = Some(itinerary_id.clone());
+    task.metadata.fallback_vertipad_ids =
+        get_fallback_vertipad_ids(proposed_flight_plans, &flight_plans);
+
+    audit(get_booked_record(
+        task,
+        task_id,
+        itinerary_id,
+        flight_plan_ids,
+    ))
+    .await;
+
+    // A restored itinerary can't be restored again
+    if let Some(restored_id) = task.restores {
+        forget_soft_cancellation(&restored_id).await;
+    }
+
+    // A booked return no longer needs to be held
+    if let Err(e) = release_confirmed_holds(&user_id.to_string(), proposed_flight_plans).await {
+        tasks_warn!("Could not release return holds of user {user_id}: {e}");
+    }
+
+    // Lower priority holds contending for the same vertipads or aircraft
+    //  give way to this booking
+    if let Some(priority) = task.eviction_priority {
+        match evict_contending_holds(&flight_plans, priority).await {
+            Ok(evicted) if !evicted.is_empty() => {
+                tasks_info!("Evicted return holds {:?}.", evicted)
+            }
+            Ok(_) => (),
+            Err(e) => tasks_warn!("Could not evict contending return holds: {e}"),
+        }
+    }
+
+    // Hold the return for the user while they book it, if it can
+    //  still be flown after this itinerary
+    if let Some(request) = &task.return_hold {
+        hold_return(clients, &user_id, request).await;
+    }
 
     Ok(())
 }
 
+/// Holds a return itinerary for the user, if its aircraft and vertipads
+///  are still free
+#[cfg(not(tarpaulin_include))]
+// no_coverage: (R5) needs running backend, integration tests
+async fn hold_return(clients: &GrpcClients, user_id: &Uuid, request: &ReturnHoldRequest) {
+    let flight_plans =
+        match get_bookable_flight_plans(clients, user_id, &request.flight_plans, None).await {
+            Ok(flight_plans) => flight_plans,
+            Err(e) => {
+                tasks_warn!("Return of user {user_id} can't be held: {e}");
+                return;
+            }
+        };
+
+    match record_return_hold(&user_id.to_string(), &flight_plans, request.priority).await {
+        Ok(Some(hold_id)) => tasks_info!("Holding return as hold {hold_id}."),
+        Ok(None) => tasks_info!("Return was not held."),
+        Err(e) => tasks_warn!("Could not hold return: {e}"),
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -395,7 +581,7 @@ mod tests {
     cfg_if! {
         if #[cfg(feature = "stub_client")] {
             use crate::router::flight_plan::FlightPlanSchedule;
-            use lib_common::time::{Duration, Utc};
+            use lib_common::time::Utc;
         }
     }
 
@@ -412,9 +598,13 @@ mod tests {
                 ..Default::default()
             },
             body: TaskBody::CancelItinerary(Uuid::new_v4()),
+            eviction_priority: None,
+            return_hold: None,
+            restores: None,
+            actor_id: None,
         };
 
-        let e = create_itinerary(&mut task).await.unwrap_err();
+        let e = create_itinerary(&mut task, 1).await.unwrap_err();
         assert_eq!(e, TaskError::Data);
 
         Ok(())
@@ -429,9 +619,13 @@ mod tests {
                 ..Default::default()
             },
             body: TaskBody::CreateItinerary(vec![]),
+            eviction_priority: None,
+            return_hold: None,
+            restores: None,
+            actor_id: None,
         };
 
-        let e = create_itinerary(&mut task).await.unwrap_err();
+        let e = create_itinerary(&mut task, 1).await.unwrap_err();
         assert_eq!(e, TaskError::Metadata);
 
         let mut task = Task {
@@ -441,9 +635,13 @@ mod tests {
                 ..Default::default()
             },
             body: TaskBody::CreateItinerary(vec![]),
+            eviction_priority: None,
+            return_hold: None,
+            restores: None,
+            actor_id: None,
         };
 
-        let e = create_itinerary(&mut task).await.unwrap_err();
+        let e = create_itinerary(&mut task, 1).await.unwrap_err();
         assert_eq!(e, TaskError::UserId);
 
         Ok(())
@@ -470,11 +668,80 @@ mod tests {
                 vehicle_id: Uuid::new_v4().to_string(),
                 path: Some(vec![]),
             }]),
+            eviction_priority: None,
+            return_hold: None,
+            restores: None,
+            actor_id: None,
         };
 
-        let e = create_itinerary(&mut task).await.unwrap_err();
+        let e = create_itinerary(&mut task, 1).await.unwrap_err();
         assert_eq!(e, TaskError::ScheduleConflict);
 
         Ok(())
     }
+    #[test]
+    fn test_get_booked_record() {
+        let user_id = Uuid::new_v4().to_string();
+        let flight_plan_ids = vec!["a".to_string(), "b".to_string()];
+        let mut task = Task {
+            metadata: TaskMetadata {
+                action: TaskAction::CreateItinerary as i32,
+                user_id: user_id.clone(),
+                ..Default::default()
+            },
+            body: TaskBody::CreateItinerary(vec![]),
+            eviction_priority: None,
+            return_hold: None,
+            restores: None,
+            actor_id: None,
+        };
+
+        let record = get_booked_record(&task, 7, "new".to_string(), flight_plan_ids.clone());
+        assert_eq!(record.event, AuditEvent::Booked);
+        assert_eq!(record.user_id, user_id);
+        assert_eq!(record.task_id, Some(7));
+        assert_eq!(record.itinerary_id, Some("new".to_string()));
+        assert_eq!(record.flight_plan_ids, flight_plan_ids);
+        assert!(record.restored_from.is_none());
+
+        // the authenticated caller is audited, rather than the task's user
+        task.actor_id = Some("operator".to_string());
+        let record = get_booked_record(&task, 7, "new".to_string(), flight_plan_ids.clone());
+        assert_eq!(record.user_id, "operator");
+
+        // a restore links the new itinerary to the soft-cancelled one
+        let restored_id = Uuid::new_v4();
+        task.restores = Some(restored_id);
+        let record = get_booked_record(&task, 7, "new".to_string(), flight_plan_ids);
+        assert_eq!(record.event, AuditEvent::Restored);
+        assert_eq!(record.itinerary_id, Some("new".to_string()));
+        assert_eq!(record.restored_from, Some(restored_id.to_string()));
+    }
+
+    #[test]
+    fn test_get_fallback_vertipad_ids() {
+        let now = lib_common::time::Utc::now();
+        let flight_plan = |target_vertipad_id: &str| FlightPlanSchedule {
+            origin_vertiport_id: "a".to_string(),
+            origin_vertipad_id: "a1".to_string(),
+            origin_timeslot_start: now,
+            origin_timeslot_end: now,
+            target_vertiport_id: "b".to_string(),
+            target_vertipad_id: target_vertipad_id.to_string(),
+            target_timeslot_start: now,
+            target_timeslot_end: now,
+            vehicle_id: "vehicle".to_string(),
+            path: None,
+        };
+
+        let proposed = vec![flight_plan("b1"), flight_plan("b1")];
+        assert!(get_fallback_vertipad_ids(&proposed, &proposed).is_empty());
+
+        // the second flight landed on a sibling of the busy vertipad
+        let booked = vec![flight_plan("b1"), flight_plan("b2")];
+        assert_eq!(
+            get_fallback_vertipad_ids(&proposed, &booked),
+            vec!["b2".to_string()]
+        );
+    }
 }
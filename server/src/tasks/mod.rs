@@ -3,28 +3,41 @@
 
 #[macro_use]
 pub mod macros;
+pub mod audit;
+pub mod consolidation;
 pub mod pool;
+pub mod return_hold;
+pub mod soft_cancel;
+pub mod waitlist;
 
 mod cancel_itinerary;
 mod create_itinerary;
 
 use cancel_itinerary::cancel_itinerary;
+pub(crate) use cancel_itinerary::cancel_itinerary_in_storage;
 use create_itinerary::create_itinerary;
 
+use crate::clock::get_clock;
 use crate::grpc::server::grpc_server::{TaskAction, TaskMetadata, TaskStatus, TaskStatusRationale};
 use crate::router::flight_plan::FlightPlanSchedule;
-use crate::tasks::pool::RedisPool;
+use crate::tasks::pool::{QueuedTask, RedisPool};
+use crate::tasks::return_hold::ReturnHoldRequest;
 use deadpool_redis::redis::{self, FromRedisValue, ToRedisArgs};
-use lib_common::time::{Duration, Utc};
+use lib_common::time::Duration;
 use lib_common::uuid::Uuid;
 use num_traits::FromPrimitive;
+use rand::Rng;
 use serde::{Deserialize, Serialize};
 use std::fmt::{Display, Formatter, Result as FmtResult};
+use svc_storage_client_grpc::prelude::flight_plan::FlightPriority;
 
 /// How long to keep a task in memory after it's been processed
 const TASK_KEEPALIVE_DURATION_MINUTES: i64 = 60;
 /// How long to sleep (in milliseconds) if the queue is empty
 const IDLE_DURATION_MS: u64 = 1000;
+/// How often to sweep expired entries from the waitlist and expired
+///  return holds
+const WAITLIST_SWEEP_INTERVAL_MINUTES: i64 = 5;
 
 /// The required information to complete a task
 #[derive(Serialize, Deserialize, Debug, Clone)]
@@ -32,6 +45,9 @@ pub enum TaskBody {
     /// Cancel an itinerary
     CancelItinerary(Uuid),
 
+    /// Cancel an itinerary, keeping it restorable for a grace period
+    SoftCancelItinerary(Uuid),
+
     /// Create an itinerary
     CreateItinerary(Vec<FlightPlanSchedule>),
 }
@@ -44,6 +60,30 @@ pub struct Task {
 
     /// Details about the task
     pub body: TaskBody,
+
+    /// Priority at which an itinerary may evict lower priority return
+    ///  holds once booked, if any
+    #[serde(default)]
+    pub eviction_priority: Option<i32>,
+
+    /// Return itinerary to hold for the user once the itinerary is booked
+    #[serde(default)]
+    pub return_hold: Option<ReturnHoldRequest>,
+
+    /// Soft-cancelled itinerary that the booked itinerary restores, if any
+    #[serde(default)]
+    pub restores: Option<Uuid>,
+
+    /// Authenticated caller that requested the task, if not the task's user
+    #[serde(default)]
+    pub actor_id: Option<String>,
+}
+
+impl Task {
+    /// Returns who requested the task, for the audit trail
+    pub fn get_actor_id(&self) -> &str {
+        self.actor_id.as_deref().unwrap_or(&self.metadata.user_id)
+    }
 }
 
 impl FromRedisValue for Task {
@@ -144,7 +184,7 @@ pub async fn cancel_task(task_id: i64) -> Result<(), TaskError> {
         TaskError::Internal
     })?;
 
-    let new_expiry = Utc::now() + delta;
+    let new_expiry = get_clock().now() + delta;
     pool.update_task(task_id, &task, new_expiry)
         .await
         .map_err(|e| {
@@ -172,6 +212,137 @@ pub async fn get_task_status(task_id: i64) -> Result<TaskMetadata, TaskError> {
         })
 }
 
+/// Lists the tasks waiting in the queues, in the order they will be processed.
+/// Tasks whose data has already expired are skipped.
+pub async fn list_queued_tasks() -> Result<Vec<(QueuedTask, TaskMetadata)>, TaskError> {
+    let mut pool = crate::tasks::pool::get_pool().await.ok_or_else(|| {
+        tasks_error!("Couldn't get the redis pool.");
+        TaskError::Internal
+    })?;
+
+    let queued = pool.get_queued_tasks().await.map_err(|e| {
+        tasks_error!("error getting queued tasks: {}", e);
+        TaskError::Internal
+    })?;
+
+    let mut tasks = vec![];
+    for queued_task in queued {
+        match pool.get_task_data(queued_task.task_id).await {
+            Ok(task) => tasks.push((queued_task, task.metadata)),
+            Err(e) => tasks_debug!(
+                "skipping queued task #{}, no task data: {}",
+                queued_task.task_id,
+                e
+            ),
+        }
+    }
+
+    Ok(tasks)
+}
+
+/// Returns a random delay of up to `max_seconds`, added to the expiry of
+///  records created in a burst so they don't all expire at once
+pub fn get_expiry_jitter<R: Rng + ?Sized>(max_seconds: u32, rng: &mut R) -> Duration {
+    let max_ms = i64::from(max_seconds) * 1000;
+    Duration::try_milliseconds(rng.gen_range(0..=max_ms)).unwrap_or_else(Duration::zero)
+}
+
+/// Gets the flight plans of the itineraries queued to be created.
+/// These drafts aren't in storage until their task is processed.
+pub fn get_draft_flight_plans(tasks: &[Task]) -> Vec<FlightPlanSchedule> {
+    tasks
+        .iter()
+        .filter(|task| {
+            task.metadata.status == TaskStatus::Queued as i32
+                && task.metadata.action == TaskAction::CreateItinerary as i32
+        })
+        .filter_map(|task| match &task.body {
+            TaskBody::CreateItinerary(flight_plans) => Some(flight_plans.clone()),
+            _ => None,
+        })
+        .flatten()
+        .collect()
+}
+
+/// Gets the flight plans of the itineraries waiting in the queues to
+///  be created, see [`get_draft_flight_plans`]
+#[cfg(not(tarpaulin_include))]
+// no_coverage: (R5) need redis backend to test this
+pub async fn get_queued_draft_flight_plans() -> Result<Vec<FlightPlanSchedule>, TaskError> {
+    let mut pool = crate::tasks::pool::get_pool().await.ok_or_else(|| {
+        tasks_error!("Couldn't get the redis pool.");
+        TaskError::Internal
+    })?;
+
+    let queued = pool.get_queued_tasks().await.map_err(|e| {
+        tasks_error!("error getting queued tasks: {}", e);
+        TaskError::Internal
+    })?;
+
+    let mut tasks = vec![];
+    for queued_task in queued {
+        match pool.get_task_data(queued_task.task_id).await {
+            Ok(task) => tasks.push(task),
+            Err(e) => tasks_debug!(
+                "skipping queued task #{}, no task data: {}",
+                queued_task.task_id,
+                e
+            ),
+        }
+    }
+
+    Ok(get_draft_flight_plans(&tasks))
+}
+
+/// Moves a queued task to another priority queue, or queues a rejected
+///  task to be processed again. Completed tasks can't be requeued.
+pub async fn requeue_task(
+    task_id: i64,
+    priority: FlightPriority,
+) -> Result<TaskMetadata, TaskError> {
+    let mut pool = crate::tasks::pool::get_pool().await.ok_or_else(|| {
+        tasks_error!("Couldn't get the redis pool.");
+        TaskError::Internal
+    })?;
+
+    let mut task = pool.get_task_data(task_id).await.map_err(|e| {
+        tasks_warn!("error getting task: {}", e);
+        TaskError::NotFound
+    })?;
+
+    match FromPrimitive::from_i32(task.metadata.status) {
+        Some(TaskStatus::Queued) => (),
+        Some(TaskStatus::Rejected) => {
+            task.metadata.status = TaskStatus::Queued.into();
+            task.metadata.status_rationale = None;
+            task.metadata.result = None;
+
+            let delta =
+                Duration::try_minutes(TASK_KEEPALIVE_DURATION_MINUTES).ok_or_else(|| {
+                    tasks_error!("error creating time delta.");
+                    TaskError::Internal
+                })?;
+
+            let new_expiry = get_clock().now() + delta;
+            pool.update_task(task_id, &task, new_expiry)
+                .await
+                .map_err(|e| {
+                    tasks_warn!("error updating task: {}", e);
+                    TaskError::Internal
+                })?;
+        }
+        _ => return Err(TaskError::AlreadyProcessed),
+    }
+
+    pool.requeue_task(task_id, priority).await.map_err(|e| {
+        tasks_warn!("error requeueing task: {}", e);
+        TaskError::Internal
+    })?;
+
+    tasks_info!("Requeued task #{task_id} with priority {:?}.", priority);
+    Ok(task.metadata)
+}
+
 /// Iterates through priority queues and implements tasks
 #[cfg(not(tarpaulin_include))]
 // no_coverage: (R5) loops indefinitely
@@ -187,7 +358,30 @@ pub async fn task_loop(_config: crate::config::Config) -> Result<(), ()> {
             tasks_warn!("error creating time delta.");
         })?;
 
+    let sweep_interval =
+        Duration::try_minutes(WAITLIST_SWEEP_INTERVAL_MINUTES).ok_or_else(|| {
+            tasks_warn!("error creating sweep interval.");
+        })?;
+
+    let mut next_sweep = get_clock().now();
     loop {
+        if get_clock().now() >= next_sweep {
+            if let Err(e) = waitlist::sweep_waitlist(&mut pool).await {
+                tasks_warn!("error sweeping waitlist: {}", e);
+            }
+
+            if let Err(e) = return_hold::sweep_return_holds(&mut pool).await {
+                tasks_warn!("error sweeping return holds: {}", e);
+            }
+
+            match pool.queue_depth().await {
+                Ok(depth) => tasks_info!("Task queue depth: {depth}."),
+                Err(e) => tasks_warn!("error getting task queue depth: {}", e),
+            }
+
+            next_sweep = get_clock().now() + sweep_interval;
+        }
+
         let (task_id, mut task) = match pool.next_task().await {
             Ok(t) => t,
             Err(_) => {
@@ -206,8 +400,8 @@ pub async fn task_loop(_config: crate::config::Config) -> Result<(), ()> {
 
         // Results of the action are stored in the task
         let result = match FromPrimitive::from_i32(task.metadata.action) {
-            Some(TaskAction::CreateItinerary) => create_itinerary(&mut task).await,
-            Some(TaskAction::CancelItinerary) => cancel_itinerary(&mut task).await,
+            Some(TaskAction::CreateItinerary) => create_itinerary(&mut task, task_id).await,
+            Some(TaskAction::CancelItinerary) => cancel_itinerary(&mut task, task_id).await,
             None => {
                 tasks_warn!("Invalid task action: {}", task.metadata.action);
                 task.metadata.status = TaskStatus::Rejected.into();
@@ -227,7 +421,7 @@ pub async fn task_loop(_config: crate::config::Config) -> Result<(), ()> {
             }
         }
 
-        let new_expiry = Utc::now() + keepalive_delta;
+        let new_expiry = get_clock().now() + keepalive_delta;
         let _ = pool.update_task(task_id, &task, new_expiry).await;
     }
 
@@ -238,6 +432,112 @@ pub async fn task_loop(_config: crate::config::Config) -> Result<(), ()> {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::router::schedule::Timeslot;
+    use crate::router::vertiport::get_unoccupied_timeslots;
+    use lib_common::time::Utc;
+    use std::collections::HashMap;
+
+    fn get_task(action: TaskAction, status: TaskStatus, body: TaskBody) -> Task {
+        Task {
+            metadata: TaskMetadata {
+                action: action as i32,
+                status: status as i32,
+                ..Default::default()
+            },
+            body,
+            eviction_priority: None,
+            return_hold: None,
+            restores: None,
+            actor_id: None,
+        }
+    }
+
+    #[test]
+    fn test_get_expiry_jitter() {
+        let mut rng = rand::thread_rng();
+        for _ in 0..100 {
+            let jitter = get_expiry_jitter(30, &mut rng);
+            assert!(jitter >= Duration::zero());
+            assert!(jitter <= Duration::try_seconds(30).unwrap());
+        }
+
+        assert_eq!(get_expiry_jitter(0, &mut rng), Duration::zero());
+    }
+
+    #[test]
+    fn test_draft_reduces_availability() {
+        let now = Utc::now();
+        let vertiport_id = Uuid::new_v4().to_string();
+        let vertipad_id = Uuid::new_v4().to_string();
+        let departure = now + Duration::try_hours(1).unwrap();
+        let draft = FlightPlanSchedule {
+            origin_vertiport_id: vertiport_id.clone(),
+            origin_vertipad_id: vertipad_id.clone(),
+            origin_timeslot_start: departure,
+            origin_timeslot_end: departure + Duration::try_minutes(1).unwrap(),
+            target_vertiport_id: Uuid::new_v4().to_string(),
+            target_vertipad_id: Uuid::new_v4().to_string(),
+            target_timeslot_start: departure + Duration::try_minutes(20).unwrap(),
+            target_timeslot_end: departure + Duration::try_minutes(21).unwrap(),
+            vehicle_id: Uuid::new_v4().to_string(),
+            path: None,
+        };
+
+        // only queued itinerary creations are drafts
+        let tasks = vec![
+            get_task(
+                TaskAction::CreateItinerary,
+                TaskStatus::Queued,
+                TaskBody::CreateItinerary(vec![draft.clone()]),
+            ),
+            get_task(
+                TaskAction::CreateItinerary,
+                TaskStatus::Rejected,
+                TaskBody::CreateItinerary(vec![draft.clone()]),
+            ),
+            get_task(
+                TaskAction::CancelItinerary,
+                TaskStatus::Queued,
+                TaskBody::CancelItinerary(Uuid::new_v4()),
+            ),
+        ];
+
+        let drafts = get_draft_flight_plans(&tasks);
+        assert_eq!(drafts.len(), 1);
+
+        // a concurrent query sees nothing in storage yet
+        let window = Timeslot::new(now, now + Duration::try_hours(2).unwrap()).unwrap();
+        let held = Timeslot::new(draft.origin_timeslot_start, draft.origin_timeslot_end).unwrap();
+        let minimum_duration = Duration::try_minutes(1).unwrap();
+        let is_available = |existing_flight_plans: &[FlightPlanSchedule]| {
+            get_unoccupied_timeslots(
+                &vertiport_id,
+                HashMap::from([(vertipad_id.clone(), vec![window])]),
+                existing_flight_plans,
+                &minimum_duration,
+            )
+            .unwrap()[&vertipad_id]
+                .iter()
+                .any(|slot| slot.overlap(&held).is_ok())
+        };
+
+        assert!(is_available(&[]));
+
+        // with drafts as tentative occupancy, the slot is taken
+        assert!(!is_available(&drafts));
+    }
+
+    #[test]
+    fn test_get_draft_flight_plans_empty() {
+        assert!(get_draft_flight_plans(&[]).is_empty());
+
+        let tasks = vec![get_task(
+            TaskAction::CreateItinerary,
+            TaskStatus::Complete,
+            TaskBody::CreateItinerary(vec![]),
+        )];
+        assert!(get_draft_flight_plans(&tasks).is_empty());
+    }
 
     #[test]
     fn test_task_error_display() {
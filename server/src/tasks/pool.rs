@@ -1,17 +1,31 @@
 //! Redis connection pool implementation
 
+use crate::clock::get_clock;
+use crate::tasks::audit::AuditRecord;
+use crate::tasks::return_hold::ReturnHold;
+use crate::tasks::soft_cancel::SoftCancellation;
+use crate::tasks::waitlist::WaitlistEntry;
 use crate::tasks::{Task, TaskStatus};
 use deadpool_redis::{
-    redis::{AsyncCommands, FromRedisValue, Value},
+    redis::{cmd, AsyncCommands, FromRedisValue, Value},
     Pool, Runtime,
 };
 use lib_common::time::{DateTime, Utc};
+use lib_common::uuid::Uuid;
 use std::fmt::{Debug, Display, Formatter, Result as FmtResult};
 use std::sync::Arc;
 use svc_storage_client_grpc::prelude::flight_plan::FlightPriority;
 use tokio::sync::{Mutex, OnceCell};
 use tonic::async_trait;
 
+/// Task queues, in the order they are processed
+const TASK_QUEUES: [(FlightPriority, &str); 4] = [
+    (FlightPriority::Emergency, "scheduler:emergency"),
+    (FlightPriority::High, "scheduler:high"),
+    (FlightPriority::Medium, "scheduler:medium"),
+    (FlightPriority::Low, "scheduler:low"),
+];
+
 /// A global static Redis pool.
 static REDIS_POOL: OnceCell<Arc<Mutex<TaskPool>>> = OnceCell::const_new();
 
@@ -61,6 +75,9 @@ pub enum CacheError {
 
     /// The operation on the Redis cache failed.
     OperationFailed,
+
+    /// The task queues hold the maximum number of tasks
+    QueueFull,
 }
 
 impl Display for CacheError {
@@ -70,6 +87,7 @@ impl Display for CacheError {
             CacheError::CouldNotConnect => write!(f, "Could not connect to cache."),
             CacheError::OperationFailed => write!(f, "Cache operation failed."),
             CacheError::Empty => write!(f, "Cache is empty."),
+            CacheError::QueueFull => write!(f, "Task queue is full."),
         }
     }
 }
@@ -83,6 +101,9 @@ impl Display for CacheError {
 pub struct TaskPool {
     /// The underlying pool of Redis connections.
     pool: Pool,
+
+    /// Maximum number of queued tasks, 0 for no limit
+    max_queue_depth: u32,
 }
 
 impl Debug for TaskPool {
@@ -109,7 +130,10 @@ impl TaskPool {
         match cfg.create_pool(Some(Runtime::Tokio1)) {
             Ok(pool) => {
                 tasks_info!("(TaskPool new) pool created.");
-                Some(TaskPool { pool })
+                Some(TaskPool {
+                    pool,
+                    max_queue_depth: config.max_queue_depth,
+                })
             }
             Err(e) => {
                 tasks_error!("(TaskPool new) could not create pool: {}", e);
@@ -123,6 +147,73 @@ impl RedisPool for TaskPool {
     fn pool(&self) -> &Pool {
         &self.pool
     }
+
+    fn max_queue_depth(&self) -> u32 {
+        self.max_queue_depth
+    }
+}
+
+/// Returns the name of the task queue for a priority tier
+fn get_queue_name(priority: FlightPriority) -> &'static str {
+    match priority {
+        FlightPriority::Emergency => "scheduler:emergency",
+        FlightPriority::High => "scheduler:high",
+        FlightPriority::Medium => "scheduler:medium",
+        FlightPriority::Low => "scheduler:low",
+    }
+}
+
+/// A task waiting in a queue
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct QueuedTask {
+    /// Task ID
+    pub task_id: i64,
+
+    /// Priority tier the task is queued in
+    pub priority: FlightPriority,
+
+    /// Task expiry, tasks in the same queue are processed earliest expiry first
+    pub expiry_ms: i64,
+}
+
+/// Orders queued tasks the way [`RedisPool::next_task`] pops them:
+///  highest priority tier first, then earliest expiry first
+pub fn order_queued_tasks(mut tasks: Vec<QueuedTask>) -> Vec<QueuedTask> {
+    tasks.sort_by_key(|task| {
+        let tier = TASK_QUEUES
+            .iter()
+            .position(|(priority, _)| *priority == task.priority)
+            .unwrap_or(TASK_QUEUES.len());
+
+        (tier, task.expiry_ms)
+    });
+
+    tasks
+}
+
+/// Rejects new tasks once the queues hold `max_depth` tasks, so that
+///  clients back off instead of the queues growing without bound.
+/// A `max_depth` of 0 disables the limit.
+pub fn check_queue_depth(depth: usize, max_depth: u32) -> Result<(), CacheError> {
+    if max_depth == 0 || depth < max_depth as usize {
+        return Ok(());
+    }
+
+    tasks_warn!("Task queue depth {depth} reached the maximum of {max_depth}.");
+    Err(CacheError::QueueFull)
+}
+
+/// Returns the IDs of the oldest waitlist entries that exceed the
+///  maximum waitlist size, 0 for no limit. Entry IDs are expected
+///  oldest first.
+fn get_evicted_waitlist_entries(entry_ids: &[String], max_entries: u32) -> Vec<String> {
+    if max_entries == 0 {
+        return vec![];
+    }
+
+    let max_entries = usize::try_from(max_entries).unwrap_or(usize::MAX);
+    let excess = entry_ids.len().saturating_sub(max_entries);
+    entry_ids[..excess].to_vec()
 }
 
 #[derive(Debug)]
@@ -190,10 +281,13 @@ pub trait RedisPool {
     /// Returns a reference to the underlying pool.
     fn pool(&self) -> &Pool;
 
+    /// Returns the maximum number of queued tasks, 0 for no limit.
+    fn max_queue_depth(&self) -> u32;
+
     /// Validate a new task
     /// Separated for easier unit testing
     fn new_task_validation(task: &Task, expiry: DateTime<Utc>) -> Result<(), CacheError> {
-        if expiry <= Utc::now() {
+        if expiry <= get_clock().now() {
             tasks_error!("(RedisPool new_task) expiry must be in the future.");
             return Err(CacheError::OperationFailed);
         }
@@ -225,12 +319,10 @@ pub trait RedisPool {
     {
         Self::new_task_validation(task, expiry)?;
 
-        let queue_name = match priority {
-            FlightPriority::Emergency => "scheduler:emergency",
-            FlightPriority::High => "scheduler:high",
-            FlightPriority::Medium => "scheduler:medium",
-            FlightPriority::Low => "scheduler:low",
-        };
+        let depth = self.queue_depth().await?;
+        check_queue_depth(depth, self.max_queue_depth())?;
+
+        let queue_name = get_queue_name(priority);
 
         let expiry_ms = TryInto::<usize>::try_into(expiry.timestamp_millis()).map_err(|e| {
             tasks_error!(
@@ -513,12 +605,10 @@ pub trait RedisPool {
         Self: Send + Sync + 'async_trait,
     {
         let counter_key = "scheduler:tasks";
-        let queues = vec![
-            "scheduler:emergency",
-            "scheduler:high",
-            "scheduler:medium",
-            "scheduler:low",
-        ];
+        let queues = TASK_QUEUES
+            .iter()
+            .map(|(_, queue_name)| *queue_name)
+            .collect::<Vec<&str>>();
 
         let mut keys = queues.clone();
         keys.push(counter_key);
@@ -586,6 +676,735 @@ pub trait RedisPool {
 
         Ok((task_id, task))
     }
+
+    /// Gets the number of tasks waiting in every queue
+    #[cfg(not(tarpaulin_include))]
+    // no_coverage: (R5) need redis backend to test this
+    async fn queue_depth(&mut self) -> Result<usize, CacheError>
+    where
+        Self: Send + Sync + 'async_trait,
+    {
+        let mut connection = self.pool().get().await.map_err(|e| {
+            tasks_error!("(RedisPool queue_depth) could not get connection from pool: {e}");
+            CacheError::OperationFailed
+        })?;
+
+        let mut depth = 0;
+        for (_, queue_name) in TASK_QUEUES {
+            let count: usize = connection.zcard(queue_name).await.map_err(|e| {
+                tasks_error!(
+                    "(RedisPool queue_depth) could not count tasks in '{queue_name}' queue: {e}"
+                );
+                CacheError::OperationFailed
+            })?;
+
+            depth += count;
+        }
+
+        Ok(depth)
+    }
+
+    /// Gets the tasks waiting in every queue, in the order they will be processed
+    #[cfg(not(tarpaulin_include))]
+    // no_coverage: (R5) need redis backend to test this
+    async fn get_queued_tasks(&mut self) -> Result<Vec<QueuedTask>, CacheError>
+    where
+        Self: Send + Sync + 'async_trait,
+    {
+        let mut connection = self.pool().get().await.map_err(|e| {
+            tasks_error!("(RedisPool get_queued_tasks) could not get connection from pool: {e}");
+            CacheError::OperationFailed
+        })?;
+
+        let mut tasks = vec![];
+        for (priority, queue_name) in TASK_QUEUES {
+            let entries: Vec<(i64, f64)> = connection
+                .zrange_withscores(queue_name, 0, -1)
+                .await
+                .map_err(|e| {
+                    tasks_error!(
+                        "(RedisPool get_queued_tasks) could not get tasks in '{queue_name}' queue: {e}"
+                    );
+                    CacheError::OperationFailed
+                })?;
+
+            tasks.extend(entries.into_iter().map(|(task_id, expiry_ms)| QueuedTask {
+                task_id,
+                priority,
+                expiry_ms: expiry_ms as i64,
+            }));
+        }
+
+        Ok(order_queued_tasks(tasks))
+    }
+
+    /// Moves a task to the queue of a priority tier, or queues it again if
+    ///  it was already popped. The task is ordered by its current expiry.
+    #[cfg(not(tarpaulin_include))]
+    // no_coverage: (R5) need redis backend to test this
+    async fn requeue_task(
+        &mut self,
+        task_id: i64,
+        priority: FlightPriority,
+    ) -> Result<(), CacheError>
+    where
+        Self: Send + Sync + 'async_trait,
+    {
+        let key = format!("scheduler:tasks:{task_id}");
+        let queue_name = get_queue_name(priority);
+        let mut connection = self.pool().get().await.map_err(|e| {
+            tasks_error!("(RedisPool requeue_task) could not get connection from pool: {e}");
+            CacheError::OperationFailed
+        })?;
+
+        let ttl_ms: i64 = connection.pttl(key).await.map_err(|e| {
+            tasks_error!("(RedisPool requeue_task) could not get task #{task_id} expiry: {e}");
+            CacheError::OperationFailed
+        })?;
+
+        // -2 if the task doesn't exist, -1 if it has no expiry
+        if ttl_ms < 0 {
+            tasks_error!("(RedisPool requeue_task) task #{task_id} has no expiry.");
+            return Err(CacheError::OperationFailed);
+        }
+
+        let expiry_ms = get_clock().now().timestamp_millis() + ttl_ms;
+
+        // TODO(R5): Make this section a transaction if possible
+        for (_, name) in TASK_QUEUES {
+            let _: Value = connection.zrem(name, task_id).await.map_err(|e| {
+                tasks_error!(
+                    "(RedisPool requeue_task) could not remove task #{task_id} from '{name}' queue: {e}"
+                );
+                CacheError::OperationFailed
+            })?;
+        }
+
+        let response = connection
+            .zadd(queue_name, task_id, expiry_ms)
+            .await
+            .map_err(|e| {
+                tasks_error!(
+                    "(RedisPool requeue_task) could not add task #{task_id} to '{queue_name}' queue: {e}"
+                );
+                CacheError::OperationFailed
+            })?;
+
+        let Value::Int(1) = response else {
+            tasks_error!(
+                "(RedisPool requeue_task) unexpected redis response: {:?}",
+                response
+            );
+
+            return Err(CacheError::OperationFailed);
+        };
+
+        tasks_info!("(RedisPool requeue_task) moved task #{task_id} to '{queue_name}' queue.");
+        Ok(())
+    }
+
+    /// Adds a flight query to the waitlist and returns the waitlist entry ID.
+    /// The entry is dropped from the waitlist after the expiry time.
+    /// If the waitlist holds more than `max_entries`, the oldest entries are evicted
+    ///  (0 for no limit).
+    #[cfg(not(tarpaulin_include))]
+    // no_coverage: (R5) need redis backend to test this
+    async fn add_to_waitlist(
+        &mut self,
+        entry: &WaitlistEntry,
+        expiry: DateTime<Utc>,
+        max_entries: u32,
+    ) -> Result<String, CacheError>
+    where
+        Self: Send + Sync + 'async_trait,
+    {
+        let waitlist_key = "scheduler:waitlist";
+        let entry_id = Uuid::new_v4().to_string();
+        let key = format!("{waitlist_key}:{entry_id}");
+
+        let expiry_ms = TryInto::<usize>::try_into(expiry.timestamp_millis()).map_err(|e| {
+            tasks_error!(
+                "(RedisPool add_to_waitlist) Could not convert expiry into redis usize type: {e}"
+            );
+            CacheError::OperationFailed
+        })?;
+
+        let mut connection = self.pool().get().await.map_err(|e| {
+            tasks_error!("(RedisPool add_to_waitlist) could not get connection from pool: {e}");
+            CacheError::OperationFailed
+        })?;
+
+        // TODO(R5): Make this section a transaction if possible
+        let response = connection.set(key.clone(), entry).await.map_err(|e| {
+            tasks_error!("(RedisPool add_to_waitlist) could not set entry {entry_id} data: {e}");
+            CacheError::OperationFailed
+        })?;
+
+        let Value::Okay = response else {
+            tasks_error!(
+                "(RedisPool add_to_waitlist) unexpected redis response: {:?}",
+                response
+            );
+
+            return Err(CacheError::OperationFailed);
+        };
+
+        let response = connection
+            .expire_at(key.clone(), expiry_ms)
+            .await
+            .map_err(|e| {
+                tasks_error!(
+                    "(RedisPool add_to_waitlist) could not set entry {entry_id} expiry: {e}"
+                );
+                CacheError::OperationFailed
+            })?;
+
+        let Value::Int(1) = response else {
+            tasks_error!(
+                "(RedisPool add_to_waitlist) unexpected redis response: {:?}",
+                response
+            );
+
+            return Err(CacheError::OperationFailed);
+        };
+
+        // Waitlist is ordered by insertion time, oldest first
+        let created_ms = get_clock().now().timestamp_millis();
+        let response = connection
+            .zadd(waitlist_key, entry_id.clone(), created_ms)
+            .await
+            .map_err(|e| {
+                tasks_error!(
+                    "(RedisPool add_to_waitlist) could not add entry {entry_id} to waitlist: {e}"
+                );
+                CacheError::OperationFailed
+            })?;
+
+        let Value::Int(1) = response else {
+            tasks_error!(
+                "(RedisPool add_to_waitlist) unexpected redis response: {:?}",
+                response
+            );
+
+            return Err(CacheError::OperationFailed);
+        };
+
+        tasks_info!("(RedisPool add_to_waitlist) added entry {entry_id} to waitlist.");
+        tasks_debug!(
+            "(RedisPool add_to_waitlist) entry {entry_id} data: {:?}",
+            entry
+        );
+
+        // Evict the oldest entries if the waitlist is over capacity
+        let entry_ids: Vec<String> = connection.zrange(waitlist_key, 0, -1).await.map_err(|e| {
+            tasks_error!("(RedisPool add_to_waitlist) could not get waitlist entries: {e}");
+            CacheError::OperationFailed
+        })?;
+
+        for evicted_id in get_evicted_waitlist_entries(&entry_ids, max_entries) {
+            tasks_warn!(
+                "(RedisPool add_to_waitlist) waitlist exceeds {max_entries} entries, evicting oldest entry {evicted_id}."
+            );
+
+            let _: Value = connection
+                .zrem(waitlist_key, evicted_id.clone())
+                .await
+                .map_err(|e| {
+                    tasks_error!(
+                        "(RedisPool add_to_waitlist) could not evict entry {evicted_id}: {e}"
+                    );
+                    CacheError::OperationFailed
+                })?;
+
+            let _: Value = connection
+                .del(format!("{waitlist_key}:{evicted_id}"))
+                .await
+                .map_err(|e| {
+                    tasks_error!(
+                        "(RedisPool add_to_waitlist) could not delete entry {evicted_id}: {e}"
+                    );
+                    CacheError::OperationFailed
+                })?;
+        }
+
+        Ok(entry_id)
+    }
+
+    /// Gets all unexpired waitlist entries and their IDs
+    #[cfg(not(tarpaulin_include))]
+    // no_coverage: (R5) need redis backend to test this
+    async fn get_waitlist(&mut self) -> Result<Vec<(String, WaitlistEntry)>, CacheError>
+    where
+        Self: Send + Sync + 'async_trait,
+    {
+        let waitlist_key = "scheduler:waitlist";
+        let mut connection = self.pool().get().await.map_err(|e| {
+            tasks_error!("(RedisPool get_waitlist) could not get connection from pool: {e}");
+            CacheError::OperationFailed
+        })?;
+
+        let entry_ids: Vec<String> = connection.zrange(waitlist_key, 0, -1).await.map_err(|e| {
+            tasks_error!("(RedisPool get_waitlist) could not get waitlist entries: {e}");
+            CacheError::OperationFailed
+        })?;
+
+        let mut entries = vec![];
+        for entry_id in entry_ids {
+            let key = format!("{waitlist_key}:{entry_id}");
+            match connection.get::<_, Option<WaitlistEntry>>(key).await {
+                Ok(Some(entry)) => entries.push((entry_id, entry)),
+                Ok(None) => {
+                    // data key has expired, drop the entry from the waitlist
+                    tasks_debug!("(RedisPool get_waitlist) entry {entry_id} has expired.");
+                    let _: Result<Value, _> = connection.zrem(waitlist_key, entry_id).await;
+                }
+                Err(e) => {
+                    tasks_warn!("(RedisPool get_waitlist) could not get entry {entry_id}: {e}");
+                }
+            }
+        }
+
+        Ok(entries)
+    }
+
+    /// Removes an entry from the waitlist
+    #[cfg(not(tarpaulin_include))]
+    // no_coverage: (R5) need redis backend to test this
+    async fn remove_from_waitlist(&mut self, entry_id: &str) -> Result<(), CacheError>
+    where
+        Self: Send + Sync + 'async_trait,
+    {
+        let waitlist_key = "scheduler:waitlist";
+        let mut connection = self.pool().get().await.map_err(|e| {
+            tasks_error!(
+                "(RedisPool remove_from_waitlist) could not get connection from pool: {e}"
+            );
+            CacheError::OperationFailed
+        })?;
+
+        let _: Value = connection.zrem(waitlist_key, entry_id).await.map_err(|e| {
+            tasks_error!("(RedisPool remove_from_waitlist) could not remove entry {entry_id}: {e}");
+            CacheError::OperationFailed
+        })?;
+
+        let _: Value = connection
+            .del(format!("{waitlist_key}:{entry_id}"))
+            .await
+            .map_err(|e| {
+                tasks_error!(
+                    "(RedisPool remove_from_waitlist) could not delete entry {entry_id}: {e}"
+                );
+                CacheError::OperationFailed
+            })?;
+
+        tasks_info!("(RedisPool remove_from_waitlist) removed entry {entry_id} from waitlist.");
+
+        Ok(())
+    }
+
+    /// Keeps a soft-cancelled itinerary until the expiry time
+    #[cfg(not(tarpaulin_include))]
+    // no_coverage: (R5) need redis backend to test this
+    async fn add_soft_cancellation(
+        &mut self,
+        itinerary_id: &Uuid,
+        cancellation: &SoftCancellation,
+        expiry: DateTime<Utc>,
+    ) -> Result<(), CacheError>
+    where
+        Self: Send + Sync + 'async_trait,
+    {
+        let cancelled_key = "scheduler:soft_cancelled";
+        let key = format!("{cancelled_key}:{itinerary_id}");
+        let expiry_ms = TryInto::<usize>::try_into(expiry.timestamp_millis()).map_err(|e| {
+            tasks_error!(
+                "(RedisPool add_soft_cancellation) Could not convert expiry into redis usize type: {e}"
+            );
+            CacheError::OperationFailed
+        })?;
+
+        let mut connection = self.pool().get().await.map_err(|e| {
+            tasks_error!(
+                "(RedisPool add_soft_cancellation) could not get connection from pool: {e}"
+            );
+            CacheError::OperationFailed
+        })?;
+
+        // TODO(R5): Make this section a transaction if possible
+        let response = connection
+            .set(key.clone(), cancellation)
+            .await
+            .map_err(|e| {
+                tasks_error!(
+                    "(RedisPool add_soft_cancellation) could not set itinerary {itinerary_id} data: {e}"
+                );
+                CacheError::OperationFailed
+            })?;
+
+        let Value::Okay = response else {
+            tasks_error!(
+                "(RedisPool add_soft_cancellation) unexpected redis response: {:?}",
+                response
+            );
+
+            return Err(CacheError::OperationFailed);
+        };
+
+        let response = connection.expire_at(key, expiry_ms).await.map_err(|e| {
+            tasks_error!(
+                "(RedisPool add_soft_cancellation) could not set itinerary {itinerary_id} expiry: {e}"
+            );
+            CacheError::OperationFailed
+        })?;
+
+        let Value::Int(1) = response else {
+            tasks_error!(
+                "(RedisPool add_soft_cancellation) unexpected redis response: {:?}",
+                response
+            );
+
+            return Err(CacheError::OperationFailed);
+        };
+
+        // index the cancellation so that a user's cancellations can be listed
+        let _: Value = connection
+            .zadd(cancelled_key, itinerary_id.to_string(), expiry_ms)
+            .await
+            .map_err(|e| {
+                tasks_error!(
+                    "(RedisPool add_soft_cancellation) could not index itinerary {itinerary_id}: {e}"
+                );
+                CacheError::OperationFailed
+            })?;
+
+        tasks_info!(
+            "(RedisPool add_soft_cancellation) kept itinerary {itinerary_id} until {expiry}."
+        );
+        Ok(())
+    }
+
+    /// Gets a soft-cancelled itinerary, if its grace period hasn't expired
+    #[cfg(not(tarpaulin_include))]
+    // no_coverage: (R5) need redis backend to test this
+    async fn get_soft_cancellation(
+        &mut self,
+        itinerary_id: &Uuid,
+    ) -> Result<Option<SoftCancellation>, CacheError>
+    where
+        Self: Send + Sync + 'async_trait,
+    {
+        let key = format!("scheduler:soft_cancelled:{itinerary_id}");
+        self.pool()
+            .get()
+            .await
+            .map_err(|e| {
+                tasks_error!(
+                    "(RedisPool get_soft_cancellation) could not get connection from pool: {e}"
+                );
+                CacheError::OperationFailed
+            })?
+            .get(key)
+            .await
+            .map_err(|e| {
+                tasks_error!(
+                    "(RedisPool get_soft_cancellation) could not get itinerary {itinerary_id}: {e}"
+                );
+                CacheError::OperationFailed
+            })
+    }
+
+    /// Removes a soft-cancelled itinerary, so that it can't be restored again
+    #[cfg(not(tarpaulin_include))]
+    // no_coverage: (R5) need redis backend to test this
+    async fn remove_soft_cancellation(&mut self, itinerary_id: &Uuid) -> Result<(), CacheError>
+    where
+        Self: Send + Sync + 'async_trait,
+    {
+        let cancelled_key = "scheduler:soft_cancelled";
+        let mut connection = self.pool().get().await.map_err(|e| {
+            tasks_error!(
+                "(RedisPool remove_soft_cancellation) could not get connection from pool: {e}"
+            );
+            CacheError::OperationFailed
+        })?;
+
+        let _: Value = connection
+            .zrem(cancelled_key, itinerary_id.to_string())
+            .await
+            .map_err(|e| {
+                tasks_error!(
+                    "(RedisPool remove_soft_cancellation) could not unindex itinerary {itinerary_id}: {e}"
+                );
+                CacheError::OperationFailed
+            })?;
+
+        let _: Value = connection
+            .del(format!("{cancelled_key}:{itinerary_id}"))
+            .await
+            .map_err(|e| {
+                tasks_error!(
+                    "(RedisPool remove_soft_cancellation) could not delete itinerary {itinerary_id}: {e}"
+                );
+                CacheError::OperationFailed
+            })?;
+
+        tasks_info!("(RedisPool remove_soft_cancellation) removed itinerary {itinerary_id}.");
+
+        Ok(())
+    }
+
+    /// Gets all soft-cancelled itineraries whose grace period hasn't
+    ///  expired, with their itinerary IDs
+    #[cfg(not(tarpaulin_include))]
+    // no_coverage: (R5) need redis backend to test this
+    async fn get_soft_cancellations(
+        &mut self,
+    ) -> Result<Vec<(String, SoftCancellation)>, CacheError>
+    where
+        Self: Send + Sync + 'async_trait,
+    {
+        let cancelled_key = "scheduler:soft_cancelled";
+        let mut connection = self.pool().get().await.map_err(|e| {
+            tasks_error!(
+                "(RedisPool get_soft_cancellations) could not get connection from pool: {e}"
+            );
+            CacheError::OperationFailed
+        })?;
+
+        let itinerary_ids: Vec<String> =
+            connection.zrange(cancelled_key, 0, -1).await.map_err(|e| {
+                tasks_error!(
+                    "(RedisPool get_soft_cancellations) could not get soft cancellations: {e}"
+                );
+                CacheError::OperationFailed
+            })?;
+
+        let mut cancellations = vec![];
+        for itinerary_id in itinerary_ids {
+            let key = format!("{cancelled_key}:{itinerary_id}");
+            match connection.get::<_, Option<SoftCancellation>>(key).await {
+                Ok(Some(cancellation)) => cancellations.push((itinerary_id, cancellation)),
+                Ok(None) => {
+                    // grace period has expired, drop the itinerary from the index
+                    tasks_debug!(
+                        "(RedisPool get_soft_cancellations) itinerary {itinerary_id} has expired."
+                    );
+                    let _: Result<Value, _> = connection.zrem(cancelled_key, itinerary_id).await;
+                }
+                Err(e) => {
+                    tasks_warn!(
+                        "(RedisPool get_soft_cancellations) could not get itinerary {itinerary_id}: {e}"
+                    );
+                }
+            }
+        }
+
+        Ok(cancellations)
+    }
+
+    /// Holds a return itinerary until the hold expires and returns the hold ID
+    #[cfg(not(tarpaulin_include))]
+    // no_coverage: (R5) need redis backend to test this
+    async fn add_return_hold(&mut self, hold: &ReturnHold) -> Result<String, CacheError>
+    where
+        Self: Send + Sync + 'async_trait,
+    {
+        let holds_key = "scheduler:return_holds";
+        let hold_id = Uuid::new_v4().to_string();
+        let key = format!("{holds_key}:{hold_id}");
+
+        let expiry_ms =
+            TryInto::<usize>::try_into(hold.expiry.timestamp_millis()).map_err(|e| {
+                tasks_error!(
+                    "(RedisPool add_return_hold) Could not convert expiry into redis usize type: {e}"
+                );
+                CacheError::OperationFailed
+            })?;
+
+        let mut connection = self.pool().get().await.map_err(|e| {
+            tasks_error!("(RedisPool add_return_hold) could not get connection from pool: {e}");
+            CacheError::OperationFailed
+        })?;
+
+        // TODO(R5): Make this section a transaction if possible
+        let response = connection.set(key.clone(), hold).await.map_err(|e| {
+            tasks_error!("(RedisPool add_return_hold) could not set hold {hold_id} data: {e}");
+            CacheError::OperationFailed
+        })?;
+
+        let Value::Okay = response else {
+            tasks_error!(
+                "(RedisPool add_return_hold) unexpected redis response: {:?}",
+                response
+            );
+
+            return Err(CacheError::OperationFailed);
+        };
+
+        let response = connection.expire_at(key, expiry_ms).await.map_err(|e| {
+            tasks_error!("(RedisPool add_return_hold) could not set hold {hold_id} expiry: {e}");
+            CacheError::OperationFailed
+        })?;
+
+        let Value::Int(1) = response else {
+            tasks_error!(
+                "(RedisPool add_return_hold) unexpected redis response: {:?}",
+                response
+            );
+
+            return Err(CacheError::OperationFailed);
+        };
+
+        let response = connection
+            .zadd(holds_key, hold_id.clone(), expiry_ms)
+            .await
+            .map_err(|e| {
+                tasks_error!("(RedisPool add_return_hold) could not index hold {hold_id}: {e}");
+                CacheError::OperationFailed
+            })?;
+
+        let Value::Int(1) = response else {
+            tasks_error!(
+                "(RedisPool add_return_hold) unexpected redis response: {:?}",
+                response
+            );
+
+            return Err(CacheError::OperationFailed);
+        };
+
+        tasks_info!(
+            "(RedisPool add_return_hold) held return for user {} until {}.",
+            hold.user_id,
+            hold.expiry
+        );
+
+        Ok(hold_id)
+    }
+
+    /// Gets all unexpired return holds and their IDs
+    #[cfg(not(tarpaulin_include))]
+    // no_coverage: (R5) need redis backend to test this
+    async fn get_return_holds(&mut self) -> Result<Vec<(String, ReturnHold)>, CacheError>
+    where
+        Self: Send + Sync + 'async_trait,
+    {
+        let holds_key = "scheduler:return_holds";
+        let mut connection = self.pool().get().await.map_err(|e| {
+            tasks_error!("(RedisPool get_return_holds) could not get connection from pool: {e}");
+            CacheError::OperationFailed
+        })?;
+
+        let hold_ids: Vec<String> = connection.zrange(holds_key, 0, -1).await.map_err(|e| {
+            tasks_error!("(RedisPool get_return_holds) could not get return holds: {e}");
+            CacheError::OperationFailed
+        })?;
+
+        if hold_ids.is_empty() {
+            return Ok(vec![]);
+        }
+
+        // all holds in one round trip
+        let keys = hold_ids
+            .iter()
+            .map(|hold_id| format!("{holds_key}:{hold_id}"))
+            .collect::<Vec<String>>();
+
+        let values: Vec<Value> = cmd("MGET")
+            .arg(keys)
+            .query_async(&mut connection)
+            .await
+            .map_err(|e| {
+                tasks_error!("(RedisPool get_return_holds) could not get hold data: {e}");
+                CacheError::OperationFailed
+            })?;
+
+        let mut holds = vec![];
+        for (hold_id, value) in hold_ids.into_iter().zip(values) {
+            if matches!(value, Value::Nil) {
+                // data key has expired, drop the hold from the index
+                tasks_debug!("(RedisPool get_return_holds) hold {hold_id} has expired.");
+                let _: Result<Value, _> = connection.zrem(holds_key, hold_id).await;
+                continue;
+            }
+
+            match ReturnHold::from_redis_value(&value) {
+                Ok(hold) => holds.push((hold_id, hold)),
+                Err(e) => {
+                    tasks_warn!("(RedisPool get_return_holds) could not get hold {hold_id}: {e}");
+                }
+            }
+        }
+
+        Ok(holds)
+    }
+
+    /// Releases a return hold
+    #[cfg(not(tarpaulin_include))]
+    // no_coverage: (R5) need redis backend to test this
+    async fn remove_return_hold(&mut self, hold_id: &str) -> Result<(), CacheError>
+    where
+        Self: Send + Sync + 'async_trait,
+    {
+        let holds_key = "scheduler:return_holds";
+        let mut connection = self.pool().get().await.map_err(|e| {
+            tasks_error!("(RedisPool remove_return_hold) could not get connection from pool: {e}");
+            CacheError::OperationFailed
+        })?;
+
+        let _: Value = connection.zrem(holds_key, hold_id).await.map_err(|e| {
+            tasks_error!("(RedisPool remove_return_hold) could not remove hold {hold_id}: {e}");
+            CacheError::OperationFailed
+        })?;
+
+        let _: Value = connection
+            .del(format!("{holds_key}:{hold_id}"))
+            .await
+            .map_err(|e| {
+                tasks_error!("(RedisPool remove_return_hold) could not delete hold {hold_id}: {e}");
+                CacheError::OperationFailed
+            })?;
+
+        tasks_info!("(RedisPool remove_return_hold) released hold {hold_id}.");
+        Ok(())
+    }
+
+    /// Appends a record to the audit trail stream
+    #[cfg(not(tarpaulin_include))]
+    // no_coverage: (R5) need redis backend to test this
+    async fn add_audit_record(&mut self, record: &AuditRecord) -> Result<(), CacheError>
+    where
+        Self: Send + Sync + 'async_trait,
+    {
+        let audit_key = "scheduler:audit";
+        let mut connection = self.pool().get().await.map_err(|e| {
+            tasks_error!("(RedisPool add_audit_record) could not get connection from pool: {e}");
+            CacheError::OperationFailed
+        })?;
+
+        // entries of a stream can't be modified, only appended
+        let response: Value = cmd("XADD")
+            .arg(audit_key)
+            .arg("*")
+            .arg("record")
+            .arg(record)
+            .query_async(&mut connection)
+            .await
+            .map_err(|e| {
+                tasks_error!("(RedisPool add_audit_record) could not add audit record: {e}");
+                CacheError::OperationFailed
+            })?;
+
+        let Value::Data(_) = response else {
+            tasks_error!(
+                "(RedisPool add_audit_record) unexpected redis response: {:?}",
+                response
+            );
+
+            return Err(CacheError::OperationFailed);
+        };
+
+        Ok(())
+    }
 }
 
 #[cfg(test)]
@@ -593,7 +1412,6 @@ mod tests {
     use super::*;
     use crate::tasks::{TaskAction, TaskBody, TaskMetadata, TaskStatus, TaskStatusRationale};
     use lib_common::time::Duration;
-    use lib_common::uuid::Uuid;
 
     #[test]
     fn test_cache_error_display() {
@@ -610,6 +1428,7 @@ mod tests {
             "Cache operation failed."
         );
         assert_eq!(format!("{}", CacheError::Empty), "Cache is empty.");
+        assert_eq!(format!("{}", CacheError::QueueFull), "Task queue is full.");
     }
 
     #[test]
@@ -652,8 +1471,13 @@ mod tests {
                 action: TaskAction::CancelItinerary as i32,
                 user_id: Uuid::new_v4().to_string(),
                 result: None,
+                fallback_vertipad_ids: vec![],
             },
             body: TaskBody::CancelItinerary(Uuid::new_v4()),
+            eviction_priority: None,
+            return_hold: None,
+            restores: None,
+            actor_id: None,
         };
         let error = pool
             .new_task(&task, FlightPriority::Emergency, Utc::now())
@@ -688,6 +1512,93 @@ mod tests {
         assert_eq!(error, CacheError::OperationFailed);
     }
 
+    #[test]
+    fn test_check_queue_depth() {
+        let max_depth = 3;
+
+        // submitting up to the cap is accepted
+        for depth in 0..max_depth as usize {
+            check_queue_depth(depth, max_depth).unwrap();
+        }
+
+        // submitting beyond the cap is rejected
+        let e = check_queue_depth(max_depth as usize, max_depth).unwrap_err();
+        assert_eq!(e, CacheError::QueueFull);
+        let e = check_queue_depth(max_depth as usize + 10, max_depth).unwrap_err();
+        assert_eq!(e, CacheError::QueueFull);
+
+        // no limit
+        check_queue_depth(usize::MAX, 0).unwrap();
+    }
+
+    #[test]
+    fn test_get_queue_name() {
+        for (priority, queue_name) in TASK_QUEUES {
+            assert_eq!(get_queue_name(priority), queue_name);
+        }
+    }
+
+    #[test]
+    fn test_order_queued_tasks_after_reprioritize() {
+        let task = |task_id: i64, priority: FlightPriority, expiry_ms: i64| QueuedTask {
+            task_id,
+            priority,
+            expiry_ms,
+        };
+
+        let mut tasks = vec![
+            task(1, FlightPriority::Low, 100),
+            task(2, FlightPriority::Medium, 300),
+            task(3, FlightPriority::Low, 50),
+        ];
+
+        // higher tiers first, then earliest expiry
+        let order: Vec<i64> = order_queued_tasks(tasks.clone())
+            .iter()
+            .map(|task| task.task_id)
+            .collect();
+        assert_eq!(order, vec![2, 3, 1]);
+
+        // moving task 1 to the emergency queue keeps its expiry
+        tasks[0].priority = FlightPriority::Emergency;
+        let order: Vec<i64> = order_queued_tasks(tasks.clone())
+            .iter()
+            .map(|task| task.task_id)
+            .collect();
+        assert_eq!(order, vec![1, 2, 3]);
+
+        // and moving it back down puts it behind earlier expiries
+        tasks[0].priority = FlightPriority::Low;
+        tasks[2].priority = FlightPriority::High;
+        let order: Vec<i64> = order_queued_tasks(tasks)
+            .iter()
+            .map(|task| task.task_id)
+            .collect();
+        assert_eq!(order, vec![3, 2, 1]);
+    }
+
+    #[test]
+    fn test_get_evicted_waitlist_entries() {
+        let entry_ids: Vec<String> = (0..3).map(|_| Uuid::new_v4().to_string()).collect();
+
+        // under capacity
+        assert!(get_evicted_waitlist_entries(&entry_ids, 3).is_empty());
+        assert!(get_evicted_waitlist_entries(&entry_ids, 10).is_empty());
+
+        // over capacity evicts the oldest entries
+        assert_eq!(
+            get_evicted_waitlist_entries(&entry_ids, 2),
+            vec![entry_ids[0].clone()]
+        );
+        assert_eq!(
+            get_evicted_waitlist_entries(&entry_ids, 1),
+            entry_ids[..2].to_vec()
+        );
+
+        // no limit
+        assert!(get_evicted_waitlist_entries(&entry_ids, 0).is_empty());
+    }
+
     #[test]
     fn test_next_task_from_redis_value() {
         let value = Value::Bulk(vec![
@@ -0,0 +1,994 @@
+//! Return itineraries held while a user books an outbound itinerary.
+//! A held return is treated as booked for every other user until the
+//!  holding user books it or the hold expires.
+
+use crate::clock::get_clock;
+use crate::router::flight_plan::FlightPlanSchedule;
+use crate::tasks::pool::RedisPool;
+use crate::tasks::{get_expiry_jitter, TaskError};
+use deadpool_redis::redis::{self, FromRedisValue, ToRedisArgs};
+use lib_common::time::{DateTime, Duration, Utc};
+use num_traits::FromPrimitive;
+use serde::{Deserialize, Serialize};
+use svc_storage_client_grpc::prelude::flight_plan::FlightPriority;
+
+/// Return flight plans held for a user
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct ReturnHold {
+    /// User the return itinerary is held for
+    pub user_id: String,
+
+    /// Flight plans of the return itinerary
+    pub flight_plans: Vec<FlightPlanSchedule>,
+
+    /// When the hold is released if the user hasn't booked the return
+    pub expiry: DateTime<Utc>,
+
+    /// Priority of the booking the return is held for. Requests of a
+    ///  higher priority may evict the hold
+    #[serde(default = "get_default_hold_priority")]
+    pub priority: i32,
+}
+
+/// Priority of holds recorded before holds had a priority
+fn get_default_hold_priority() -> i32 {
+    FlightPriority::Low as i32
+}
+
+/// A return itinerary to hold for a user once their outbound itinerary
+///  is booked
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct ReturnHoldRequest {
+    /// Flight plans of the return itinerary
+    pub flight_plans: Vec<FlightPlanSchedule>,
+
+    /// Priority of the outbound booking
+    pub priority: i32,
+}
+
+impl FromRedisValue for ReturnHold {
+    fn from_redis_value(v: &redis::Value) -> redis::RedisResult<Self> {
+        let redis::Value::Data(data) = v else {
+            return Err(redis::RedisError::from((
+                redis::ErrorKind::TypeError,
+                "Unexpected Redis value",
+            )));
+        };
+
+        let Ok(hold): Result<ReturnHold, serde_json::Error> = serde_json::from_slice(data) else {
+            return Err(redis::RedisError::from((
+                redis::ErrorKind::TypeError,
+                "Invalid JSON",
+            )));
+        };
+
+        Ok(hold)
+    }
+}
+
+impl ToRedisArgs for ReturnHold {
+    fn write_redis_args<W: ?Sized>(&self, out: &mut W)
+    where
+        W: redis::RedisWrite,
+    {
+        let Ok(result) = serde_json::to_string(&self) else {
+            tasks_warn!("error serializing return hold");
+            return;
+        };
+
+        out.write_arg(result.as_bytes());
+    }
+}
+
+/// Returns the hold to keep for a return itinerary, if holds are enabled.
+/// The hold is extended by `jitter`, and released when the return
+///  departs if that's sooner.
+pub fn get_return_hold(
+    user_id: &str,
+    flight_plans: &[FlightPlanSchedule],
+    priority: i32,
+    hold_minutes: u32,
+    jitter: Duration,
+    now: DateTime<Utc>,
+) -> Option<ReturnHold> {
+    if hold_minutes == 0 {
+        tasks_info!("Return holds are disabled.");
+        return None;
+    }
+
+    let departure = flight_plans.iter().min()?.origin_timeslot_start;
+    let expiry = (now + Duration::try_minutes(hold_minutes.into())? + jitter).min(departure);
+    if expiry <= now {
+        tasks_info!("Return departs too soon to be held.");
+        return None;
+    }
+
+    Some(ReturnHold {
+        user_id: user_id.to_string(),
+        flight_plans: flight_plans.to_vec(),
+        expiry,
+        priority,
+    })
+}
+
+/// Returns the rank of a flight priority, 0 for the most urgent.
+/// Unknown priorities rank lowest.
+fn get_priority_rank(priority: i32) -> usize {
+    match FromPrimitive::from_i32(priority) {
+        Some(FlightPriority::Emergency) => 0,
+        Some(FlightPriority::High) => 1,
+        Some(FlightPriority::Medium) => 2,
+        Some(FlightPriority::Low) | None => 3,
+    }
+}
+
+/// Returns the priority at which a booking may evict return holds, if
+///  any. Only operators may evict holds, for high priority and
+///  emergency bookings.
+pub fn get_eviction_priority(priority: i32, is_operator: bool) -> Option<i32> {
+    match FromPrimitive::from_i32(priority) {
+        Some(FlightPriority::Emergency | FlightPriority::High) if is_operator => Some(priority),
+        _ => None,
+    }
+}
+
+/// Returns true if a request of the given priority may evict the hold
+pub fn can_evict(hold: &ReturnHold, priority: i32) -> bool {
+    get_priority_rank(priority) < get_priority_rank(hold.priority)
+}
+
+/// Returns true if both flight plans need the same vertipad or the
+///  same aircraft at the same time
+fn is_contending(a: &FlightPlanSchedule, b: &FlightPlanSchedule) -> bool {
+    let pad_slots = |fp: &FlightPlanSchedule| {
+        [
+            (
+                fp.origin_vertipad_id.clone(),
+                fp.origin_timeslot_start,
+                fp.origin_timeslot_end,
+            ),
+            (
+                fp.target_vertipad_id.clone(),
+                fp.target_timeslot_start,
+                fp.target_timeslot_end,
+            ),
+        ]
+    };
+
+    let same_pad = pad_slots(a).iter().any(|(pad_a, start_a, end_a)| {
+        pad_slots(b)
+            .iter()
+            .any(|(pad_b, start_b, end_b)| pad_a == pad_b && start_a < end_b && start_b < end_a)
+    });
+
+    let same_aircraft = a.vehicle_id == b.vehicle_id
+        && a.origin_timeslot_start < b.target_timeslot_end
+        && b.origin_timeslot_start < a.target_timeslot_end;
+
+    same_pad || same_aircraft
+}
+
+/// Returns the IDs of the holds of a lower priority than `priority`
+///  that contend with the booked flight plans for a vertipad or an
+///  aircraft
+pub fn get_evictable_holds(
+    holds: &[(String, ReturnHold)],
+    booked: &[FlightPlanSchedule],
+    priority: i32,
+) -> Vec<String> {
+    holds
+        .iter()
+        .filter(|(_, hold)| can_evict(hold, priority))
+        .filter(|(_, hold)| {
+            hold.flight_plans
+                .iter()
+                .any(|held| booked.iter().any(|fp| is_contending(held, fp)))
+        })
+        .map(|(hold_id, _)| hold_id.clone())
+        .collect()
+}
+
+/// Returns true if both flight plans are the same flight
+fn is_same_flight(a: &FlightPlanSchedule, b: &FlightPlanSchedule) -> bool {
+    a.vehicle_id == b.vehicle_id
+        && a.origin_vertipad_id == b.origin_vertipad_id
+        && a.origin_timeslot_start == b.origin_timeslot_start
+        && a.target_vertipad_id == b.target_vertipad_id
+        && a.target_timeslot_start == b.target_timeslot_start
+}
+
+/// Returns the held flight plans that the user can't book.
+/// A user can book the returns held for them, without a user
+///  every unexpired hold is unavailable.
+pub fn get_held_flight_plans(
+    holds: &[ReturnHold],
+    user_id: Option<&str>,
+    now: DateTime<Utc>,
+) -> Vec<FlightPlanSchedule> {
+    holds
+        .iter()
+        .filter(|hold| hold.expiry > now)
+        .filter(|hold| user_id != Some(hold.user_id.as_str()))
+        .flat_map(|hold| hold.flight_plans.clone())
+        .collect()
+}
+
+/// Returns the IDs of the user's holds on any of the booked flight plans
+pub fn get_confirmed_holds(
+    holds: &[(String, ReturnHold)],
+    user_id: &str,
+    booked: &[FlightPlanSchedule],
+) -> Vec<String> {
+    holds
+        .iter()
+        .filter(|(_, hold)| hold.user_id == user_id)
+        .filter(|(_, hold)| {
+            hold.flight_plans
+                .iter()
+                .any(|held| booked.iter().any(|fp| is_same_flight(held, fp)))
+        })
+        .map(|(hold_id, _)| hold_id.clone())
+        .collect()
+}
+
+/// Returns the IDs of holds that have expired
+fn get_expired_holds(holds: &[(String, ReturnHold)], now: DateTime<Utc>) -> Vec<String> {
+    holds
+        .iter()
+        .filter(|(_, hold)| hold.expiry <= now)
+        .map(|(hold_id, _)| hold_id.clone())
+        .collect()
+}
+
+/// Returns the IDs of all holds of the user
+pub fn get_user_holds(holds: &[(String, ReturnHold)], user_id: &str) -> Vec<String> {
+    holds
+        .iter()
+        .filter(|(_, hold)| hold.user_id == user_id)
+        .map(|(hold_id, _)| hold_id.clone())
+        .collect()
+}
+
+/// Returns the IDs of the user's older holds to release so that the
+///  user keeps at most `max_holds` holds, including the new hold.
+/// Holds are expected soonest expiry first, and those expiring soonest
+///  are released first. A `max_holds` of 0 means no limit.
+pub fn get_evicted_user_holds(
+    holds: &[(String, ReturnHold)],
+    user_id: &str,
+    new_hold_id: &str,
+    max_holds: u32,
+) -> Vec<String> {
+    if max_holds == 0 {
+        return vec![];
+    }
+
+    let older = get_user_holds(holds, user_id)
+        .into_iter()
+        .filter(|hold_id| hold_id != new_hold_id)
+        .collect::<Vec<String>>();
+
+    let max_older = usize::try_from(max_holds - 1).unwrap_or(usize::MAX);
+    let excess = older.len().saturating_sub(max_older);
+    older[..excess].to_vec()
+}
+
+/// Returns the IDs of the holds to release so that at most `max_holds`
+///  holds are kept for all users together, including the new hold.
+/// Holds are expected soonest expiry first, and those expiring soonest
+///  are released first. A `max_holds` of 0 means no limit.
+pub fn get_evicted_holds(
+    holds: &[(String, ReturnHold)],
+    new_hold_id: &str,
+    max_holds: u32,
+) -> Vec<String> {
+    if max_holds == 0 {
+        return vec![];
+    }
+
+    let older = holds
+        .iter()
+        .map(|(hold_id, _)| hold_id.clone())
+        .filter(|hold_id| hold_id != new_hold_id)
+        .collect::<Vec<String>>();
+
+    let max_older = usize::try_from(max_holds - 1).unwrap_or(usize::MAX);
+    let excess = older.len().saturating_sub(max_older);
+    older[..excess].to_vec()
+}
+
+/// Holds a return itinerary for the user for the configured hold time.
+/// Past the configured number of holds per user, or of holds for all
+///  users together, the holds expiring soonest are released to make room.
+/// Returns the hold ID, if the return was held.
+#[cfg(not(tarpaulin_include))]
+// no_coverage: (R5) need redis backend to test this
+pub async fn record_return_hold(
+    user_id: &str,
+    flight_plans: &[FlightPlanSchedule],
+    priority: i32,
+) -> Result<Option<String>, TaskError> {
+    let config = crate::config::get_config().await;
+
+    // holds recorded in a burst are released at slightly different times
+    let jitter = get_expiry_jitter(config.expiry_jitter_seconds, &mut rand::thread_rng());
+    let Some(hold) = get_return_hold(
+        user_id,
+        flight_plans,
+        priority,
+        config.return_hold_minutes,
+        jitter,
+        get_clock().now(),
+    ) else {
+        return Ok(None);
+    };
+
+    let mut pool = crate::tasks::pool::get_pool().await.ok_or_else(|| {
+        tasks_error!("Couldn't get the redis pool.");
+        TaskError::Internal
+    })?;
+
+    let hold_id = pool.add_return_hold(&hold).await.map_err(|e| {
+        tasks_error!("Could not hold return for user {user_id}: {e}");
+        TaskError::Internal
+    })?;
+
+    tasks_info!("Return held for user {user_id} until {}.", hold.expiry);
+
+    if config.max_holds_per_user > 0 || config.max_return_holds > 0 {
+        let holds = pool.get_return_holds().await.map_err(|e| {
+            tasks_error!("Could not get return holds: {e}");
+            TaskError::Internal
+        })?;
+
+        let mut evicted =
+            get_evicted_user_holds(&holds, user_id, &hold_id, config.max_holds_per_user);
+        for evicted_id in evicted.iter() {
+            tasks_warn!(
+                "User {user_id} exceeds {} return holds, releasing hold {evicted_id}.",
+                config.max_holds_per_user
+            );
+        }
+
+        // The user's released holds make room for all users
+        let remaining = holds
+            .iter()
+            .filter(|(hold_id, _)| !evicted.contains(hold_id))
+            .cloned()
+            .collect::<Vec<(String, ReturnHold)>>();
+
+        for evicted_id in get_evicted_holds(&remaining, &hold_id, config.max_return_holds) {
+            tasks_warn!(
+                "Return holds exceed {}, releasing hold {evicted_id}.",
+                config.max_return_holds
+            );
+            evicted.push(evicted_id);
+        }
+
+        for evicted_id in evicted.iter() {
+            pool.remove_return_hold(evicted_id).await.map_err(|e| {
+                tasks_error!("Could not release return hold {evicted_id}: {e}");
+                TaskError::Internal
+            })?;
+        }
+
+        // Released flight plans may satisfy waitlisted requests
+        let released = holds
+            .into_iter()
+            .filter(|(hold_id, _)| evicted.contains(hold_id))
+            .flat_map(|(_, hold)| hold.flight_plans)
+            .collect::<Vec<FlightPlanSchedule>>();
+
+        if let Err(e) = crate::tasks::waitlist::rescan_waitlist(&released).await {
+            tasks_warn!("Could not re-evaluate waitlist: {e}");
+        }
+    }
+
+    Ok(Some(hold_id))
+}
+
+/// Gets the held flight plans that the user can't book.
+/// If a `priority` is provided, holds it may evict aren't included.
+#[cfg(not(tarpaulin_include))]
+// no_coverage: (R5) need redis backend to test this
+pub async fn get_unavailable_held_flight_plans(
+    user_id: Option<&str>,
+    priority: Option<i32>,
+) -> Result<Vec<FlightPlanSchedule>, TaskError> {
+    let mut pool = crate::tasks::pool::get_pool().await.ok_or_else(|| {
+        tasks_error!("Couldn't get the redis pool.");
+        TaskError::Internal
+    })?;
+
+    let holds = pool
+        .get_return_holds()
+        .await
+        .map_err(|e| {
+            tasks_error!("Could not get return holds: {e}");
+            TaskError::Internal
+        })?
+        .into_iter()
+        .map(|(_, hold)| hold)
+        .filter(|hold| !priority.is_some_and(|priority| can_evict(hold, priority)))
+        .collect::<Vec<ReturnHold>>();
+
+    Ok(get_held_flight_plans(&holds, user_id, get_clock().now()))
+}
+
+/// Releases the holds of a lower priority contending with the booked
+///  flight plans, so that the booking can take their slots.
+/// Returns the IDs of the released holds.
+#[cfg(not(tarpaulin_include))]
+// no_coverage: (R5) need redis backend to test this
+pub async fn evict_contending_holds(
+    booked: &[FlightPlanSchedule],
+    priority: i32,
+) -> Result<Vec<String>, TaskError> {
+    let mut pool = crate::tasks::pool::get_pool().await.ok_or_else(|| {
+        tasks_error!("Couldn't get the redis pool.");
+        TaskError::Internal
+    })?;
+
+    let holds = pool.get_return_holds().await.map_err(|e| {
+        tasks_error!("Could not get return holds: {e}");
+        TaskError::Internal
+    })?;
+
+    let hold_ids = get_evictable_holds(&holds, booked, priority);
+    for hold_id in hold_ids.iter() {
+        pool.remove_return_hold(hold_id).await.map_err(|e| {
+            tasks_error!("Could not evict return hold {hold_id}: {e}");
+            TaskError::Internal
+        })?;
+
+        tasks_warn!("Return hold {hold_id} evicted by a booking of priority {priority}.");
+    }
+
+    Ok(hold_ids)
+}
+
+/// Releases the user's holds once the held flight plans are booked
+#[cfg(not(tarpaulin_include))]
+// no_coverage: (R5) need redis backend to test this
+pub async fn release_confirmed_holds(
+    user_id: &str,
+    booked: &[FlightPlanSchedule],
+) -> Result<(), TaskError> {
+    let mut pool = crate::tasks::pool::get_pool().await.ok_or_else(|| {
+        tasks_error!("Couldn't get the redis pool.");
+        TaskError::Internal
+    })?;
+
+    let holds = pool.get_return_holds().await.map_err(|e| {
+        tasks_error!("Could not get return holds: {e}");
+        TaskError::Internal
+    })?;
+
+    for hold_id in get_confirmed_holds(&holds, user_id, booked) {
+        pool.remove_return_hold(&hold_id).await.map_err(|e| {
+            tasks_error!("Could not release return hold {hold_id}: {e}");
+            TaskError::Internal
+        })?;
+
+        tasks_info!("Return hold {hold_id} of user {user_id} was booked.");
+    }
+
+    Ok(())
+}
+
+/// Releases every hold of the user, as when the user abandons a booking.
+/// Returns the IDs of the released holds and the flight plans they held.
+#[cfg(not(tarpaulin_include))]
+// no_coverage: (R5) need redis backend to test this
+pub async fn release_user_holds(
+    user_id: &str,
+) -> Result<(Vec<String>, Vec<FlightPlanSchedule>), TaskError> {
+    let mut pool = crate::tasks::pool::get_pool().await.ok_or_else(|| {
+        tasks_error!("Couldn't get the redis pool.");
+        TaskError::Internal
+    })?;
+
+    let holds = pool.get_return_holds().await.map_err(|e| {
+        tasks_error!("Could not get return holds: {e}");
+        TaskError::Internal
+    })?;
+
+    let hold_ids = get_user_holds(&holds, user_id);
+    for hold_id in hold_ids.iter() {
+        pool.remove_return_hold(hold_id).await.map_err(|e| {
+            tasks_error!("Could not release return hold {hold_id}: {e}");
+            TaskError::Internal
+        })?;
+
+        tasks_info!("Return hold {hold_id} of user {user_id} was released.");
+    }
+
+    let flight_plans = holds
+        .into_iter()
+        .filter(|(hold_id, _)| hold_ids.contains(hold_id))
+        .flat_map(|(_, hold)| hold.flight_plans)
+        .collect();
+
+    Ok((hold_ids, flight_plans))
+}
+
+/// Removes expired return holds, in case the Redis expiry of a hold
+///  was not set or was lost across restarts.
+/// Returns the number of holds removed.
+#[cfg(not(tarpaulin_include))]
+// no_coverage: (R5) need redis backend to test this
+pub async fn sweep_return_holds(
+    pool: &mut crate::tasks::pool::TaskPool,
+) -> Result<usize, TaskError> {
+    let holds = pool.get_return_holds().await.map_err(|e| {
+        tasks_error!("Could not get return holds: {e}");
+        TaskError::Internal
+    })?;
+
+    let mut swept = 0;
+    for hold_id in get_expired_holds(&holds, get_clock().now()) {
+        match pool.remove_return_hold(&hold_id).await {
+            Ok(_) => swept += 1,
+            Err(e) => tasks_warn!("Could not remove expired return hold {hold_id}: {e}"),
+        }
+    }
+
+    tasks_info!("return_holds_swept={swept}");
+    Ok(swept)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use lib_common::uuid::Uuid;
+
+    fn get_flight_plans(departure: DateTime<Utc>) -> Vec<FlightPlanSchedule> {
+        vec![FlightPlanSchedule {
+            origin_vertiport_id: Uuid::new_v4().to_string(),
+            origin_vertipad_id: Uuid::new_v4().to_string(),
+            origin_timeslot_start: departure,
+            origin_timeslot_end: departure + Duration::try_minutes(1).unwrap(),
+            target_vertiport_id: Uuid::new_v4().to_string(),
+            target_vertipad_id: Uuid::new_v4().to_string(),
+            target_timeslot_start: departure + Duration::try_minutes(20).unwrap(),
+            target_timeslot_end: departure + Duration::try_minutes(21).unwrap(),
+            vehicle_id: Uuid::new_v4().to_string(),
+            path: None,
+        }]
+    }
+
+    #[test]
+    fn test_get_return_hold() {
+        let now = Utc::now();
+        let user_id = Uuid::new_v4().to_string();
+        let flight_plans = get_flight_plans(now + Duration::try_hours(4).unwrap());
+
+        let hold = get_return_hold(
+            &user_id,
+            &flight_plans,
+            FlightPriority::Low as i32,
+            15,
+            Duration::zero(),
+            now,
+        )
+        .unwrap();
+        assert_eq!(hold.user_id, user_id);
+        assert_eq!(hold.flight_plans, flight_plans);
+        assert_eq!(hold.expiry, now + Duration::try_minutes(15).unwrap());
+
+        // released when the return departs
+        let soon = get_flight_plans(now + Duration::try_minutes(5).unwrap());
+        let hold = get_return_hold(
+            &user_id,
+            &soon,
+            FlightPriority::Low as i32,
+            15,
+            Duration::zero(),
+            now,
+        )
+        .unwrap();
+        assert_eq!(hold.expiry, soon[0].origin_timeslot_start);
+
+        // holds disabled, no return, or return already departed
+        assert!(get_return_hold(
+            &user_id,
+            &flight_plans,
+            FlightPriority::Low as i32,
+            0,
+            Duration::zero(),
+            now
+        )
+        .is_none());
+        assert!(get_return_hold(
+            &user_id,
+            &[],
+            FlightPriority::Low as i32,
+            15,
+            Duration::zero(),
+            now
+        )
+        .is_none());
+        let departed = get_flight_plans(now - Duration::try_minutes(1).unwrap());
+        assert!(get_return_hold(
+            &user_id,
+            &departed,
+            FlightPriority::Low as i32,
+            15,
+            Duration::zero(),
+            now
+        )
+        .is_none());
+    }
+
+    #[test]
+    fn test_return_hold_burst_expiry_spread() {
+        let now = Utc::now();
+        let flight_plans = get_flight_plans(now + Duration::try_hours(4).unwrap());
+        let hold_end = now + Duration::try_minutes(15).unwrap();
+        let mut rng = rand::thread_rng();
+
+        // a burst of holds recorded at the same instant
+        let expiries: Vec<DateTime<Utc>> = (0..50)
+            .map(|_| {
+                let jitter = get_expiry_jitter(30, &mut rng);
+                let user_id = Uuid::new_v4().to_string();
+                get_return_hold(
+                    &user_id,
+                    &flight_plans,
+                    FlightPriority::Low as i32,
+                    15,
+                    jitter,
+                    now,
+                )
+                .unwrap()
+                .expiry
+            })
+            .collect();
+
+        // released over the jitter window instead of all at once
+        let first = *expiries.iter().min().unwrap();
+        let last = *expiries.iter().max().unwrap();
+        assert!(first >= hold_end);
+        assert!(last <= hold_end + Duration::try_seconds(30).unwrap());
+        assert!(last - first > Duration::try_seconds(10).unwrap());
+
+        let distinct: std::collections::HashSet<DateTime<Utc>> = expiries.iter().copied().collect();
+        assert!(distinct.len() > expiries.len() / 2);
+
+        // never held past the departure of the return
+        let soon = get_flight_plans(now + Duration::try_minutes(15).unwrap());
+        let hold = get_return_hold(
+            &Uuid::new_v4().to_string(),
+            &soon,
+            FlightPriority::Low as i32,
+            15,
+            Duration::try_seconds(30).unwrap(),
+            now,
+        )
+        .unwrap();
+        assert_eq!(hold.expiry, soon[0].origin_timeslot_start);
+    }
+
+    #[test]
+    fn test_return_hold_held_then_released() {
+        let now = Utc::now();
+        let user_id = Uuid::new_v4().to_string();
+        let other_id = Uuid::new_v4().to_string();
+        let flight_plans = get_flight_plans(now + Duration::try_hours(4).unwrap());
+        let hold = get_return_hold(
+            &user_id,
+            &flight_plans,
+            FlightPriority::Low as i32,
+            15,
+            Duration::zero(),
+            now,
+        )
+        .unwrap();
+        let holds = vec![hold];
+
+        // held for everyone but the holding user
+        let held = get_held_flight_plans(&holds, None, now);
+        assert_eq!(held.len(), 1);
+        assert!(is_same_flight(&held[0], &flight_plans[0]));
+
+        let held = get_held_flight_plans(&holds, Some(&other_id), now);
+        assert_eq!(held.len(), 1);
+        assert!(get_held_flight_plans(&holds, Some(&user_id), now).is_empty());
+
+        // released once the hold times out
+        let later = now + Duration::try_minutes(15).unwrap();
+        assert!(get_held_flight_plans(&holds, None, later).is_empty());
+        assert!(get_held_flight_plans(&holds, Some(&other_id), later).is_empty());
+    }
+
+    #[test]
+    fn test_get_confirmed_holds() {
+        let now = Utc::now();
+        let user_id = Uuid::new_v4().to_string();
+        let departure = now + Duration::try_hours(4).unwrap();
+        let flight_plans = get_flight_plans(departure);
+        let holds = vec![
+            (
+                "mine".to_string(),
+                get_return_hold(
+                    &user_id,
+                    &flight_plans,
+                    FlightPriority::Low as i32,
+                    15,
+                    Duration::zero(),
+                    now,
+                )
+                .unwrap(),
+            ),
+            (
+                "other".to_string(),
+                get_return_hold(
+                    &Uuid::new_v4().to_string(),
+                    &flight_plans,
+                    FlightPriority::Low as i32,
+                    15,
+                    Duration::zero(),
+                    now,
+                )
+                .unwrap(),
+            ),
+        ];
+
+        // booking the held return confirms only the user's own hold
+        let confirmed = get_confirmed_holds(&holds, &user_id, &flight_plans);
+        assert_eq!(confirmed, vec!["mine".to_string()]);
+
+        // another flight at the same time doesn't
+        let unrelated = get_flight_plans(departure);
+        assert!(get_confirmed_holds(&holds, &user_id, &unrelated).is_empty());
+    }
+
+    #[test]
+    fn test_get_user_holds() {
+        let now = Utc::now();
+        let user_id = Uuid::new_v4().to_string();
+        let departure = now + Duration::try_hours(4).unwrap();
+        let hold = |user_id: &str| {
+            get_return_hold(
+                user_id,
+                &get_flight_plans(departure),
+                FlightPriority::Low as i32,
+                15,
+                Duration::zero(),
+                now,
+            )
+            .unwrap()
+        };
+
+        let holds = vec![
+            ("first".to_string(), hold(&user_id)),
+            ("other".to_string(), hold(&Uuid::new_v4().to_string())),
+            ("second".to_string(), hold(&user_id)),
+        ];
+
+        // both of the user's holds are released at once, no one else's
+        assert_eq!(
+            get_user_holds(&holds, &user_id),
+            vec!["first".to_string(), "second".to_string()]
+        );
+        assert!(get_user_holds(&holds, &Uuid::new_v4().to_string()).is_empty());
+    }
+
+    #[test]
+    fn test_high_priority_evicts_low_priority_hold() {
+        let now = Utc::now();
+        let departure = now + Duration::try_hours(4).unwrap();
+
+        // a low priority draft holds the only slot of the vertipad
+        let held = get_flight_plans(departure);
+        let hold = get_return_hold(
+            &Uuid::new_v4().to_string(),
+            &held,
+            FlightPriority::Low as i32,
+            15,
+            Duration::zero(),
+            now,
+        )
+        .unwrap();
+        let holds = vec![("draft".to_string(), hold.clone())];
+
+        // another aircraft needs the same vertipad at the same time
+        let mut booked = get_flight_plans(departure);
+        booked[0].origin_vertipad_id = held[0].origin_vertipad_id.clone();
+
+        // a high priority request may evict the draft, and doesn't see
+        //  its slot as taken
+        assert!(can_evict(&hold, FlightPriority::High as i32));
+        assert_eq!(
+            get_evictable_holds(&holds, &booked, FlightPriority::High as i32),
+            vec!["draft".to_string()]
+        );
+        assert_eq!(
+            get_evictable_holds(&holds, &booked, FlightPriority::Emergency as i32),
+            vec!["draft".to_string()]
+        );
+
+        // a request of the same priority can't
+        assert!(!can_evict(&hold, FlightPriority::Low as i32));
+        assert!(get_evictable_holds(&holds, &booked, FlightPriority::Low as i32).is_empty());
+
+        // nor can a request that doesn't contend for the slot
+        let elsewhere = get_flight_plans(departure);
+        assert!(get_evictable_holds(&holds, &elsewhere, FlightPriority::High as i32).is_empty());
+    }
+
+    #[test]
+    fn test_get_eviction_priority() {
+        // only operators evict, for high priority and emergency bookings
+        for priority in [FlightPriority::High, FlightPriority::Emergency] {
+            assert_eq!(
+                get_eviction_priority(priority as i32, true),
+                Some(priority as i32)
+            );
+            assert_eq!(get_eviction_priority(priority as i32, false), None);
+        }
+
+        for priority in [FlightPriority::Low, FlightPriority::Medium] {
+            assert_eq!(get_eviction_priority(priority as i32, true), None);
+            assert_eq!(get_eviction_priority(priority as i32, false), None);
+        }
+
+        assert_eq!(get_eviction_priority(-1, true), None);
+        let later = vec![FlightPlanSchedule {
+            origin_vertipad_id: held[0].origin_vertipad_id.clone(),
+            ..get_flight_plans(departure + Duration::try_hours(1).unwrap())[0].clone()
+        }];
+        assert!(get_evictable_holds(&holds, &later, FlightPriority::High as i32).is_empty());
+
+        // the same aircraft at the same time contends as well
+        let mut same_aircraft = get_flight_plans(departure);
+        same_aircraft[0].vehicle_id = held[0].vehicle_id.clone();
+        assert_eq!(
+            get_evictable_holds(&holds, &same_aircraft, FlightPriority::High as i32),
+            vec!["draft".to_string()]
+        );
+    }
+
+    #[test]
+    fn test_return_hold_default_priority() {
+        let hold: ReturnHold = serde_json::from_str(&format!(
+            r#"{{"user_id":"{}","flight_plans":[],"expiry":"2030-01-01T00:00:00Z"}}"#,
+            Uuid::new_v4()
+        ))
+        .unwrap();
+        assert_eq!(hold.priority, FlightPriority::Low as i32);
+    }
+
+    #[test]
+    fn test_user_over_hold_limit() {
+        let now = Utc::now();
+        let user_id = Uuid::new_v4().to_string();
+        let other_id = Uuid::new_v4().to_string();
+        let hold = |user_id: &str, departure_hours: i64| {
+            let departure = now + Duration::try_hours(departure_hours).unwrap();
+            get_return_hold(
+                user_id,
+                &get_flight_plans(departure),
+                FlightPriority::Low as i32,
+                15,
+                Duration::zero(),
+                now,
+            )
+            .unwrap()
+        };
+
+        // the user already holds two returns, the limit is two
+        let mut holds = vec![
+            ("first".to_string(), hold(&user_id, 2)),
+            ("other".to_string(), hold(&other_id, 3)),
+            ("second".to_string(), hold(&user_id, 4)),
+            ("third".to_string(), hold(&user_id, 5)),
+        ];
+
+        // the new hold makes room by releasing the oldest one
+        let evicted = get_evicted_user_holds(&holds, &user_id, "third", 2);
+        assert_eq!(evicted, vec!["first".to_string()]);
+        holds.retain(|(hold_id, _)| !evicted.contains(hold_id));
+
+        // the user holds no more than two returns' worth of slots
+        let holds = holds
+            .into_iter()
+            .map(|(_, hold)| hold)
+            .collect::<Vec<ReturnHold>>();
+        let user_held = get_held_flight_plans(&holds, Some(&other_id), now);
+        assert_eq!(user_held.len(), 2);
+
+        // no limit, or within the limit
+        let holds = vec![
+            ("first".to_string(), hold(&user_id, 2)),
+            ("second".to_string(), hold(&user_id, 4)),
+        ];
+        assert!(get_evicted_user_holds(&holds, &user_id, "second", 0).is_empty());
+        assert!(get_evicted_user_holds(&holds, &user_id, "second", 2).is_empty());
+
+        // a limit of one keeps only the new hold
+        assert_eq!(
+            get_evicted_user_holds(&holds, &user_id, "second", 1),
+            vec!["first".to_string()]
+        );
+        assert!(get_evicted_user_holds(&holds, &other_id, "second", 1).is_empty());
+    }
+
+    #[test]
+    fn test_get_expired_holds() {
+        let now = Utc::now();
+        let hold = |now: DateTime<Utc>| {
+            get_return_hold(
+                &Uuid::new_v4().to_string(),
+                &get_flight_plans(now + Duration::try_hours(2).unwrap()),
+                FlightPriority::Low as i32,
+                15,
+                Duration::zero(),
+                now,
+            )
+            .unwrap()
+        };
+
+        let held = (Uuid::new_v4().to_string(), hold(now));
+        let expired = (
+            Uuid::new_v4().to_string(),
+            hold(now - Duration::try_hours(1).unwrap()),
+        );
+
+        let holds = vec![held.clone(), expired.clone()];
+        assert_eq!(get_expired_holds(&holds, now), vec![expired.0]);
+
+        // nothing to sweep
+        assert!(get_expired_holds(&[held], now).is_empty());
+    }
+
+    #[test]
+    fn test_over_return_hold_limit() {
+        let now = Utc::now();
+        let hold = |departure_hours: i64| {
+            let departure = now + Duration::try_hours(departure_hours).unwrap();
+            get_return_hold(
+                &Uuid::new_v4().to_string(),
+                &get_flight_plans(departure),
+                FlightPriority::Low as i32,
+                15,
+                Duration::zero(),
+                now,
+            )
+            .unwrap()
+        };
+
+        // three users hold returns, the new hold is the fourth
+        let holds = vec![
+            ("first".to_string(), hold(2)),
+            ("second".to_string(), hold(3)),
+            ("new".to_string(), hold(4)),
+            ("third".to_string(), hold(5)),
+        ];
+
+        // exceeding the limit releases the holds expiring soonest,
+        //  whichever user they're held for
+        assert_eq!(
+            get_evicted_holds(&holds, "new", 3),
+            vec!["first".to_string()]
+        );
+        assert_eq!(
+            get_evicted_holds(&holds, "new", 2),
+            vec!["first".to_string(), "second".to_string()]
+        );
+
+        // a limit of one keeps only the new hold
+        assert_eq!(
+            get_evicted_holds(&holds, "new", 1),
+            vec![
+                "first".to_string(),
+                "second".to_string(),
+                "third".to_string()
+            ]
+        );
+
+        // no limit, or within the limit
+        assert!(get_evicted_holds(&holds, "new", 0).is_empty());
+        assert!(get_evicted_holds(&holds, "new", 4).is_empty());
+    }
+}
@@ -0,0 +1,188 @@
+//! Soft-cancelled itineraries.
+//! A soft cancellation frees capacity like a hard cancellation, but the
+//!  cancelled flight plans are kept for a grace period during which the
+//!  same user can restore the itinerary if its flight plans are still free.
+
+use crate::clock::get_clock;
+use crate::grpc::server::grpc_server::CancellationKind;
+use crate::router::flight_plan::FlightPlanSchedule;
+use crate::tasks::pool::RedisPool;
+use crate::tasks::{get_expiry_jitter, TaskError};
+use deadpool_redis::redis::{self, FromRedisValue, ToRedisArgs};
+use lib_common::time::Duration;
+use lib_common::uuid::Uuid;
+use serde::{Deserialize, Serialize};
+
+/// A soft-cancelled itinerary that can still be restored
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct SoftCancellation {
+    /// User that cancelled the itinerary
+    pub user_id: String,
+
+    /// Flight plans of the cancelled itinerary
+    pub flight_plans: Vec<FlightPlanSchedule>,
+}
+
+impl FromRedisValue for SoftCancellation {
+    fn from_redis_value(v: &redis::Value) -> redis::RedisResult<Self> {
+        let redis::Value::Data(data) = v else {
+            return Err(redis::RedisError::from((
+                redis::ErrorKind::TypeError,
+                "Unexpected Redis value",
+            )));
+        };
+
+        let Ok(cancellation): Result<SoftCancellation, serde_json::Error> =
+            serde_json::from_slice(data)
+        else {
+            return Err(redis::RedisError::from((
+                redis::ErrorKind::TypeError,
+                "Invalid JSON",
+            )));
+        };
+
+        Ok(cancellation)
+    }
+}
+
+impl ToRedisArgs for SoftCancellation {
+    fn write_redis_args<W: ?Sized>(&self, out: &mut W)
+    where
+        W: redis::RedisWrite,
+    {
+        let Ok(result) = serde_json::to_string(&self) else {
+            tasks_warn!("error serializing soft cancellation");
+            return;
+        };
+
+        out.write_arg(result.as_bytes());
+    }
+}
+
+/// Returns the record to keep for a cancellation, if it can be reversed
+pub fn get_soft_cancellation(
+    kind: CancellationKind,
+    user_id: &str,
+    cancelled: &[FlightPlanSchedule],
+) -> Option<SoftCancellation> {
+    match kind {
+        CancellationKind::Hard => None,
+        CancellationKind::Soft => Some(SoftCancellation {
+            user_id: user_id.to_string(),
+            flight_plans: cancelled.to_vec(),
+        }),
+    }
+}
+
+/// Returns the flight plans to rebook if the user can restore the itinerary.
+/// There is no record once the grace period expires, or if the
+///  itinerary was hard-cancelled.
+pub fn get_restorable_flight_plans(
+    cancellation: Option<SoftCancellation>,
+    user_id: &str,
+) -> Result<Vec<FlightPlanSchedule>, TaskError> {
+    let cancellation = cancellation.ok_or(TaskError::NotFound)?;
+    if cancellation.user_id != user_id {
+        tasks_warn!("Itinerary was cancelled by another user.");
+        return Err(TaskError::UserId);
+    }
+
+    if cancellation.flight_plans.is_empty() {
+        tasks_warn!("Soft cancellation has no flight plans.");
+        return Err(TaskError::Data);
+    }
+
+    Ok(cancellation.flight_plans)
+}
+
+/// Keeps a soft cancellation until the configured grace period expires
+#[cfg(not(tarpaulin_include))]
+// no_coverage: (R5) need redis backend to test this
+pub async fn record_soft_cancellation(
+    itinerary_id: &Uuid,
+    cancellation: &SoftCancellation,
+) -> Result<(), TaskError> {
+    let config = crate::config::get_config().await;
+    let grace_minutes = config.soft_cancel_grace_minutes;
+    let grace = Duration::try_minutes(grace_minutes.into()).ok_or_else(|| {
+        tasks_error!("error creating soft cancel grace period.");
+        TaskError::Internal
+    })?;
+
+    let mut pool = crate::tasks::pool::get_pool().await.ok_or_else(|| {
+        tasks_error!("Couldn't get the redis pool.");
+        TaskError::Internal
+    })?;
+
+    // cancellations recorded in a burst expire at slightly different times
+    let jitter = get_expiry_jitter(config.expiry_jitter_seconds, &mut rand::thread_rng());
+    pool.add_soft_cancellation(
+        itinerary_id,
+        cancellation,
+        get_clock().now() + grace + jitter,
+    )
+    .await
+    .map_err(|e| {
+        tasks_error!("Could not record soft cancellation of itinerary {itinerary_id}: {e}");
+        TaskError::Internal
+    })?;
+
+    tasks_info!("Itinerary {itinerary_id} can be restored for {grace_minutes} minutes.");
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use lib_common::time::Utc;
+
+    fn get_flight_plans() -> Vec<FlightPlanSchedule> {
+        let now = Utc::now();
+        vec![FlightPlanSchedule {
+            origin_vertiport_id: Uuid::new_v4().to_string(),
+            origin_vertipad_id: Uuid::new_v4().to_string(),
+            origin_timeslot_start: now,
+            origin_timeslot_end: now + Duration::try_minutes(1).unwrap(),
+            target_vertiport_id: Uuid::new_v4().to_string(),
+            target_vertipad_id: Uuid::new_v4().to_string(),
+            target_timeslot_start: now + Duration::try_minutes(20).unwrap(),
+            target_timeslot_end: now + Duration::try_minutes(21).unwrap(),
+            vehicle_id: Uuid::new_v4().to_string(),
+            path: Some(vec![]),
+        }]
+    }
+
+    #[test]
+    fn test_soft_cancel_can_be_restored() {
+        let user_id = Uuid::new_v4().to_string();
+        let cancelled = get_flight_plans();
+
+        let cancellation = get_soft_cancellation(CancellationKind::Soft, &user_id, &cancelled);
+        let flight_plans = get_restorable_flight_plans(cancellation.clone(), &user_id).unwrap();
+        assert_eq!(flight_plans, cancelled);
+
+        // only by the user that cancelled it
+        let e = get_restorable_flight_plans(cancellation, &Uuid::new_v4().to_string()).unwrap_err();
+        assert_eq!(e, TaskError::UserId);
+    }
+
+    #[test]
+    fn test_hard_cancel_cannot_be_restored() {
+        let user_id = Uuid::new_v4().to_string();
+        let cancelled = get_flight_plans();
+
+        let cancellation = get_soft_cancellation(CancellationKind::Hard, &user_id, &cancelled);
+        assert!(cancellation.is_none());
+
+        let e = get_restorable_flight_plans(cancellation, &user_id).unwrap_err();
+        assert_eq!(e, TaskError::NotFound);
+    }
+
+    #[test]
+    fn test_soft_cancel_without_flight_plans() {
+        let user_id = Uuid::new_v4().to_string();
+        let cancellation = get_soft_cancellation(CancellationKind::Soft, &user_id, &[]);
+        let e = get_restorable_flight_plans(cancellation, &user_id).unwrap_err();
+        assert_eq!(e, TaskError::Data);
+    }
+}
@@ -0,0 +1,765 @@
+//! Waitlist for flight queries that found no available flights.
+//! Waitlisted queries are re-evaluated when a cancellation frees up capacity
+//!  on the same route, and are booked automatically if a flight is found.
+
+use crate::clock::get_clock;
+use crate::grpc::api::query_flight::{query_flight, QueryCaller, ADVANCE_NOTICE_MINUTES};
+use crate::grpc::client::{get_clients, GrpcClients};
+use crate::grpc::server::grpc_server::Itinerary;
+use crate::grpc::server::grpc_server::{QueryFlightRequest, TaskAction, TaskMetadata, TaskStatus};
+use crate::router::flight_plan::{FlightPlanError, FlightPlanSchedule};
+use crate::router::vehicle_model::{get_vehicle_models, VehicleModels};
+use crate::tasks::consolidation::{consolidate_entries, Consolidation};
+use crate::tasks::pool::RedisPool;
+use crate::tasks::{Task, TaskBody, TaskError};
+use deadpool_redis::redis::{self, FromRedisValue, ToRedisArgs};
+use lib_common::time::{DateTime, Duration, Utc};
+use lib_common::uuid::Uuid;
+use num_traits::FromPrimitive;
+use serde::{Deserialize, Serialize};
+use svc_storage_client_grpc::prelude::{flight_plan, Id};
+
+/// A flight query waiting for capacity to free up
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct WaitlistEntry {
+    /// User to book the itinerary for
+    pub user_id: String,
+
+    /// Flight priority
+    pub priority: i32,
+
+    /// True if cargo mission, false if people transport
+    pub is_cargo: bool,
+
+    /// Number of people for transport
+    pub persons: Option<u32>,
+
+    /// Weight in grams
+    pub weight_grams: Option<u32>,
+
+    /// Departure vertiport ID
+    pub origin_vertiport_id: String,
+
+    /// Arrival vertiport ID
+    pub target_vertiport_id: String,
+
+    /// Beginning of the requested time window
+    pub earliest_departure_time: DateTime<Utc>,
+
+    /// End of the requested time window
+    pub latest_arrival_time: DateTime<Utc>,
+
+    /// Vertiports to route around
+    #[serde(default)]
+    pub excluded_vertiport_ids: Vec<String>,
+
+    /// Requested aircraft type, any type if not provided
+    #[serde(default)]
+    pub aircraft_type: Option<i32>,
+
+    /// Emergency medical flight
+    #[serde(default)]
+    pub emergency: bool,
+
+    /// Required arrival precision, anywhere in the window if not provided
+    #[serde(default)]
+    pub required_arrival_precision_seconds: Option<u32>,
+
+    /// Itinerary ranking, earliest departure first if not provided
+    #[serde(default)]
+    pub ranking: Option<i32>,
+
+    /// Ideal departure time within the window, if any
+    #[serde(default)]
+    pub preferred_departure_time: Option<DateTime<Utc>>,
+
+    /// Type of the departure node, a vertiport if not provided
+    #[serde(default)]
+    pub origin_type: Option<i32>,
+
+    /// Type of the arrival node, a vertiport if not provided
+    #[serde(default)]
+    pub target_type: Option<i32>,
+
+    /// Longest time the requested flight may take, any if not provided
+    #[serde(default)]
+    pub max_total_travel_seconds: Option<u32>,
+
+    /// Hazard class of the cargo, if any
+    #[serde(default)]
+    pub cargo_hazard_class: Option<String>,
+}
+
+impl WaitlistEntry {
+    /// Creates a waitlist entry from a flight query
+    pub fn try_from_request(
+        request: &QueryFlightRequest,
+        user_id: &str,
+    ) -> Result<WaitlistEntry, TaskError> {
+        // TODO(R5): waitlist loop flights
+        if !request.loop_waypoints.is_empty() {
+            tasks_warn!("Loop flights can't be waitlisted.");
+            return Err(TaskError::Data);
+        }
+
+        let user_id = Uuid::parse_str(user_id).map_err(|e| {
+            tasks_warn!("Invalid user ID for waitlist: {e}");
+            TaskError::UserId
+        })?;
+
+        let earliest_departure_time: DateTime<Utc> = request
+            .earliest_departure_time
+            .clone()
+            .ok_or_else(|| {
+                tasks_warn!("Waitlist request has no earliest departure time.");
+                TaskError::Data
+            })?
+            .into();
+
+        let latest_arrival_time: DateTime<Utc> = request
+            .latest_arrival_time
+            .clone()
+            .ok_or_else(|| {
+                tasks_warn!("Waitlist request has no latest arrival time.");
+                TaskError::Data
+            })?
+            .into();
+
+        // Emergency flights are booked at the top priority
+        let priority = match request.emergency {
+            true => flight_plan::FlightPriority::Emergency as i32,
+            false => request.priority,
+        };
+
+        Ok(WaitlistEntry {
+            user_id: user_id.to_string(),
+            priority,
+            is_cargo: request.is_cargo,
+            persons: request.persons,
+            weight_grams: request.weight_grams,
+            origin_vertiport_id: request.origin_vertiport_id.clone(),
+            target_vertiport_id: request.target_vertiport_id.clone(),
+            earliest_departure_time,
+            latest_arrival_time,
+            excluded_vertiport_ids: request.excluded_vertiport_ids.clone(),
+            aircraft_type: request.aircraft_type,
+            emergency: request.emergency,
+            required_arrival_precision_seconds: request.required_arrival_precision_seconds,
+            ranking: request.ranking,
+            preferred_departure_time: request
+                .preferred_departure_time
+                .clone()
+                .map(DateTime::<Utc>::from),
+            origin_type: request.origin_type,
+            target_type: request.target_type,
+            max_total_travel_seconds: request.max_total_travel_seconds,
+            cargo_hazard_class: request.cargo_hazard_class.clone(),
+        })
+    }
+
+    /// Returns true if both entries are the same user asking for the same
+    ///  flight: route, window and payload
+    pub fn is_same_request(&self, other: &WaitlistEntry) -> bool {
+        self.user_id == other.user_id
+            && self.origin_vertiport_id == other.origin_vertiport_id
+            && self.target_vertiport_id == other.target_vertiport_id
+            && self.earliest_departure_time == other.earliest_departure_time
+            && self.latest_arrival_time == other.latest_arrival_time
+            && self.is_cargo == other.is_cargo
+            && self.persons == other.persons
+            && self.weight_grams == other.weight_grams
+    }
+
+    /// Returns true if a cancelled flight plan frees capacity for this entry:
+    ///  same origin and target vertiports, overlapping the requested window
+    pub fn matches(&self, cancelled: &FlightPlanSchedule) -> bool {
+        self.origin_vertiport_id == cancelled.origin_vertiport_id
+            && self.target_vertiport_id == cancelled.target_vertiport_id
+            && cancelled.origin_timeslot_start < self.latest_arrival_time
+            && cancelled.target_timeslot_end > self.earliest_departure_time
+    }
+
+    /// Builds a flight query for this entry, moving the departure time
+    ///  forward if it is now within the advance notice period
+    pub fn to_query(&self, now: DateTime<Utc>) -> Option<QueryFlightRequest> {
+        let advance_notice = Duration::try_minutes(ADVANCE_NOTICE_MINUTES + 1)?;
+        let earliest_departure_time = self.earliest_departure_time.max(now + advance_notice);
+        if earliest_departure_time >= self.latest_arrival_time {
+            return None;
+        }
+
+        Some(QueryFlightRequest {
+            is_cargo: self.is_cargo,
+            persons: self.persons,
+            weight_grams: self.weight_grams,
+            earliest_departure_time: Some(earliest_departure_time.into()),
+            latest_arrival_time: Some(self.latest_arrival_time.into()),
+            origin_vertiport_id: self.origin_vertiport_id.clone(),
+            target_vertiport_id: self.target_vertiport_id.clone(),
+            priority: self.priority,
+            // don't waitlist again if the re-evaluation fails
+            waitlist_user_id: None,
+            excluded_vertiport_ids: self.excluded_vertiport_ids.clone(),
+            loop_waypoints: vec![],
+            aircraft_type: self.aircraft_type,
+            emergency: self.emergency,
+            required_arrival_precision_seconds: self.required_arrival_precision_seconds,
+            auto_widen: false,
+            ranking: self.ranking,
+            include_local_times: false,
+            preferred_departure_time: self
+                .preferred_departure_time
+                .map(|time| time.max(earliest_departure_time).into()),
+            origin_type: self.origin_type,
+            target_type: self.target_type,
+            max_total_travel_seconds: self.max_total_travel_seconds,
+            min_results: None,
+            cargo_hazard_class: self.cargo_hazard_class.clone(),
+        })
+    }
+}
+
+impl FromRedisValue for WaitlistEntry {
+    fn from_redis_value(v: &redis::Value) -> redis::RedisResult<Self> {
+        let redis::Value::Data(data) = v else {
+            return Err(redis::RedisError::from((
+                redis::ErrorKind::TypeError,
+                "Unexpected Redis value",
+            )));
+        };
+
+        let Ok(entry): Result<WaitlistEntry, serde_json::Error> = serde_json::from_slice(data)
+        else {
+            return Err(redis::RedisError::from((
+                redis::ErrorKind::TypeError,
+                "Invalid JSON",
+            )));
+        };
+
+        Ok(entry)
+    }
+}
+
+impl ToRedisArgs for WaitlistEntry {
+    fn write_redis_args<W: ?Sized>(&self, out: &mut W)
+    where
+        W: redis::RedisWrite,
+    {
+        let Ok(result) = serde_json::to_string(&self) else {
+            tasks_warn!("error serializing waitlist entry");
+            return;
+        };
+
+        out.write_arg(result.as_bytes());
+    }
+}
+
+/// Returns the waitlist entries that could use the capacity freed
+///  by the cancelled flight plans
+fn get_matching_entries(
+    entries: Vec<(String, WaitlistEntry)>,
+    cancelled: &[FlightPlanSchedule],
+) -> Vec<(String, WaitlistEntry)> {
+    entries
+        .into_iter()
+        .filter(|(_, entry)| cancelled.iter().any(|fp| entry.matches(fp)))
+        .collect()
+}
+
+/// Returns the ID of a waitlist entry for the same request, if the user
+///  already waitlisted it
+fn get_duplicate_entry(
+    entries: &[(String, WaitlistEntry)],
+    entry: &WaitlistEntry,
+) -> Option<String> {
+    entries
+        .iter()
+        .find(|(_, existing)| existing.is_same_request(entry))
+        .map(|(entry_id, _)| entry_id.clone())
+}
+
+/// Returns the IDs of waitlist entries whose window can no longer be booked
+fn get_expired_entries(entries: &[(String, WaitlistEntry)], now: DateTime<Utc>) -> Vec<String> {
+    entries
+        .iter()
+        .filter(|(_, entry)| entry.to_query(now).is_none())
+        .map(|(entry_id, _)| entry_id.clone())
+        .collect()
+}
+
+/// Removes waitlist entries that can no longer be booked, in case the
+///  Redis expiry of an entry was not set or was lost across restarts.
+/// Returns the number of entries removed.
+#[cfg(not(tarpaulin_include))]
+// no_coverage: (R5) need redis backend to test this
+pub async fn sweep_waitlist(pool: &mut crate::tasks::pool::TaskPool) -> Result<usize, TaskError> {
+    let entries = pool.get_waitlist().await.map_err(|e| {
+        tasks_error!("Could not get waitlist: {e}");
+        TaskError::Internal
+    })?;
+
+    let mut swept = 0;
+    for entry_id in get_expired_entries(&entries, get_clock().now()) {
+        match pool.remove_from_waitlist(&entry_id).await {
+            Ok(_) => swept += 1,
+            Err(e) => tasks_warn!("Could not remove expired waitlist entry {entry_id}: {e}"),
+        }
+    }
+
+    tasks_info!("waitlist_entries_swept={swept}");
+    Ok(swept)
+}
+
+/// Adds a flight query to the waitlist on behalf of a user.
+/// Returns the ID of the existing entry if the user already waitlisted
+///  the same request.
+#[cfg(not(tarpaulin_include))]
+// no_coverage: (R5) need redis backend to test this
+pub async fn add_to_waitlist(
+    request: &QueryFlightRequest,
+    user_id: &str,
+) -> Result<String, TaskError> {
+    let entry = WaitlistEntry::try_from_request(request, user_id)?;
+
+    let mut pool = crate::tasks::pool::get_pool().await.ok_or_else(|| {
+        tasks_error!("Couldn't get the redis pool.");
+        TaskError::Internal
+    })?;
+
+    let entries = pool.get_waitlist().await.map_err(|e| {
+        tasks_error!("Could not get waitlist: {e}");
+        TaskError::Internal
+    })?;
+
+    if let Some(entry_id) = get_duplicate_entry(&entries, &entry) {
+        tasks_info!("Request already waitlisted as entry {entry_id}.");
+        return Ok(entry_id);
+    }
+
+    let max_entries = crate::config::get_config().await.max_waitlist_entries;
+    pool.add_to_waitlist(&entry, entry.latest_arrival_time, max_entries)
+        .await
+        .map_err(|e| {
+            tasks_error!("Could not add request to waitlist: {e}");
+            TaskError::Internal
+        })
+}
+
+/// Creates an itinerary task for a waitlisted user
+#[cfg(not(tarpaulin_include))]
+// no_coverage: (R5) need redis backend to test this
+async fn book_waitlist_entry(
+    pool: &mut crate::tasks::pool::TaskPool,
+    entry: &WaitlistEntry,
+    flight_plans: Vec<flight_plan::Data>,
+) -> Result<i64, TaskError> {
+    let priority = FromPrimitive::from_i32(entry.priority).ok_or_else(|| {
+        tasks_error!("Invalid priority for waitlist entry: {}", entry.priority);
+        TaskError::Data
+    })?;
+
+    let schedules = flight_plans
+        .into_iter()
+        .map(FlightPlanSchedule::try_from)
+        .collect::<Result<Vec<FlightPlanSchedule>, FlightPlanError>>()
+        .map_err(|e| {
+            tasks_error!("Invalid flight plans for waitlist entry: {e}");
+            TaskError::Data
+        })?;
+
+    // Set to expire if it hasn't been acted on by the start of the first flight plan
+    let expiry = schedules
+        .iter()
+        .min()
+        .ok_or_else(|| {
+            tasks_error!("No flight plans for waitlist entry.");
+            TaskError::Data
+        })?
+        .origin_timeslot_start;
+
+    let task = Task {
+        metadata: TaskMetadata {
+            status: TaskStatus::Queued as i32,
+            status_rationale: None,
+            action: TaskAction::CreateItinerary as i32,
+            user_id: entry.user_id.clone(),
+            result: None,
+            fallback_vertipad_ids: vec![],
+        },
+        body: TaskBody::CreateItinerary(schedules),
+        eviction_priority: None,
+        return_hold: None,
+        restores: None,
+        actor_id: None,
+    };
+
+    pool.new_task(&task, priority, expiry).await.map_err(|e| {
+        tasks_error!("Could not create new task: {e}");
+        TaskError::Internal
+    })
+}
+
+/// Returns the heaviest payload the aircraft flying an itinerary can
+///  carry, 0 if its vehicle model isn't known
+#[cfg(not(tarpaulin_include))]
+// no_coverage: (R5) needs running backend, integration tests
+async fn get_itinerary_payload_grams(
+    clients: &GrpcClients,
+    itinerary: &Itinerary,
+    models: &VehicleModels,
+) -> u32 {
+    let Some(vehicle) = itinerary.vehicle.as_ref() else {
+        return 0;
+    };
+
+    match clients
+        .storage()
+        .await
+        .vehicle
+        .get_by_id(Id {
+            id: vehicle.vehicle_id.clone(),
+        })
+        .await
+    {
+        Ok(response) => response
+            .into_inner()
+            .data
+            .and_then(|data| models.get(&data.vehicle_model_id).cloned())
+            .map(|model| model.max_payload_grams)
+            .unwrap_or(0),
+        Err(e) => {
+            tasks_warn!("Could not get vehicle {}: {e}", vehicle.vehicle_id);
+            0
+        }
+    }
+}
+
+/// Re-evaluates waitlisted queries matching the cancelled flight plans,
+///  booking any that can now be satisfied.
+/// Cargo shipments that fit together are planned onto one shared flight
+///  with an aircraft that can carry them all.
+#[cfg(not(tarpaulin_include))]
+// no_coverage: (R5) need redis backend to test this
+pub async fn rescan_waitlist(cancelled: &[FlightPlanSchedule]) -> Result<(), TaskError> {
+    if cancelled.is_empty() {
+        return Ok(());
+    }
+
+    let mut pool = crate::tasks::pool::get_pool().await.ok_or_else(|| {
+        tasks_error!("Couldn't get the redis pool.");
+        TaskError::Internal
+    })?;
+
+    let entries = pool.get_waitlist().await.map_err(|e| {
+        tasks_error!("Could not get waitlist: {e}");
+        TaskError::Internal
+    })?;
+
+    // Shipments are only grouped up to the heaviest payload in the fleet
+    let models = get_vehicle_models().await.map_err(|e| {
+        tasks_error!("Could not get vehicle models: {e}");
+        TaskError::Internal
+    })?;
+    let max_weight_grams = models.max_payload_grams();
+    let clients = get_clients().await;
+
+    let now = get_clock().now();
+    let mut matching = get_matching_entries(entries, cancelled);
+    let expired = get_expired_entries(&matching, now);
+    for entry_id in expired.iter() {
+        tasks_info!("Waitlist entry {entry_id} window has passed.");
+        let _ = pool.remove_from_waitlist(entry_id).await;
+    }
+
+    matching.retain(|(entry_id, _)| !expired.contains(entry_id));
+    for consolidation in consolidate_entries(matching, max_weight_grams) {
+        let Consolidation { entry_ids, entry } = &consolidation;
+        let Some(query) = entry.to_query(now) else {
+            tasks_debug!("Waitlist entries {entry_ids:?} no longer share a window.");
+            continue;
+        };
+
+        let itineraries = match query_flight(query, &QueryCaller::default()).await {
+            Ok(response) => response.into_inner().itineraries,
+            Err(e) => {
+                tasks_debug!("Waitlist entries {entry_ids:?} still have no flights: {e}");
+                continue;
+            }
+        };
+
+        // The aircraft flying a shared flight must carry every shipment
+        let mut booked = None;
+        for itinerary in itineraries {
+            let payload_grams = match entry_ids.len() {
+                1 => 0,
+                _ => get_itinerary_payload_grams(clients, &itinerary, &models).await,
+            };
+
+            if consolidation.fits_payload(payload_grams) {
+                booked = Some(itinerary);
+                break;
+            }
+        }
+
+        let Some(itinerary) = booked else {
+            tasks_debug!("Waitlist entries {entry_ids:?} still have no flights.");
+            continue;
+        };
+
+        match book_waitlist_entry(&mut pool, entry, itinerary.flight_plans).await {
+            Ok(task_id) => {
+                tasks_info!("Waitlist entries {entry_ids:?} booked with task #{task_id}.");
+
+                // Only the entry of the booked user is served, the
+                //  others stay waitlisted until they are booked
+                if let Some(entry_id) = consolidation.booked_entry_id() {
+                    let _ = pool.remove_from_waitlist(entry_id).await;
+                }
+            }
+            Err(e) => {
+                tasks_warn!("Could not book waitlist entries {entry_ids:?}: {e}");
+            }
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::grpc::server::grpc_server::Waypoint;
+
+    fn get_entry(now: DateTime<Utc>) -> WaitlistEntry {
+        WaitlistEntry {
+            user_id: Uuid::new_v4().to_string(),
+            priority: flight_plan::FlightPriority::Low as i32,
+            is_cargo: true,
+            persons: None,
+            weight_grams: Some(100),
+            origin_vertiport_id: Uuid::new_v4().to_string(),
+            target_vertiport_id: Uuid::new_v4().to_string(),
+            earliest_departure_time: now + Duration::try_hours(1).unwrap(),
+            latest_arrival_time: now + Duration::try_hours(3).unwrap(),
+            excluded_vertiport_ids: vec![],
+            aircraft_type: None,
+            emergency: false,
+            required_arrival_precision_seconds: None,
+            ranking: None,
+            preferred_departure_time: None,
+            origin_type: None,
+            target_type: None,
+            max_total_travel_seconds: None,
+            cargo_hazard_class: None,
+        }
+    }
+
+    fn get_schedule(
+        entry: &WaitlistEntry,
+        origin_timeslot_start: DateTime<Utc>,
+    ) -> FlightPlanSchedule {
+        FlightPlanSchedule {
+            origin_vertiport_id: entry.origin_vertiport_id.clone(),
+            origin_vertipad_id: Uuid::new_v4().to_string(),
+            origin_timeslot_start,
+            origin_timeslot_end: origin_timeslot_start + Duration::try_minutes(1).unwrap(),
+            target_vertiport_id: entry.target_vertiport_id.clone(),
+            target_vertipad_id: Uuid::new_v4().to_string(),
+            target_timeslot_start: origin_timeslot_start + Duration::try_minutes(20).unwrap(),
+            target_timeslot_end: origin_timeslot_start + Duration::try_minutes(21).unwrap(),
+            vehicle_id: Uuid::new_v4().to_string(),
+            path: None,
+        }
+    }
+
+    #[test]
+    fn test_waitlist_entry_try_from_request() {
+        let now = Utc::now();
+        let user_id = Uuid::new_v4().to_string();
+        let mut request = QueryFlightRequest {
+            is_cargo: true,
+            persons: None,
+            weight_grams: Some(100),
+            earliest_departure_time: Some(now.into()),
+            latest_arrival_time: Some((now + Duration::try_hours(1).unwrap()).into()),
+            origin_vertiport_id: Uuid::new_v4().to_string(),
+            target_vertiport_id: Uuid::new_v4().to_string(),
+            priority: flight_plan::FlightPriority::High as i32,
+            waitlist_user_id: Some(user_id.clone()),
+            excluded_vertiport_ids: vec![Uuid::new_v4().to_string()],
+            loop_waypoints: vec![],
+            aircraft_type: None,
+            emergency: false,
+            required_arrival_precision_seconds: None,
+            auto_widen: false,
+            ranking: None,
+            include_local_times: false,
+            preferred_departure_time: None,
+            origin_type: None,
+            target_type: None,
+            max_total_travel_seconds: None,
+            min_results: None,
+            cargo_hazard_class: Some("3".to_string()),
+        };
+
+        let entry = WaitlistEntry::try_from_request(&request, &user_id).unwrap();
+        assert_eq!(entry.user_id, user_id);
+        assert_eq!(entry.earliest_departure_time, now);
+        assert_eq!(entry.origin_vertiport_id, request.origin_vertiport_id);
+        assert_eq!(entry.target_vertiport_id, request.target_vertiport_id);
+        assert_eq!(entry.excluded_vertiport_ids, request.excluded_vertiport_ids);
+        assert_eq!(entry.priority, request.priority);
+        assert_eq!(
+            entry.to_query(now).unwrap().cargo_hazard_class,
+            request.cargo_hazard_class
+        );
+
+        // Emergency flights are waitlisted at the top priority
+        let mut tmp = request.clone();
+        tmp.emergency = true;
+        let entry = WaitlistEntry::try_from_request(&tmp, &user_id).unwrap();
+        assert_eq!(
+            entry.priority,
+            flight_plan::FlightPriority::Emergency as i32
+        );
+        assert!(entry.to_query(now).unwrap().emergency);
+
+        let e = WaitlistEntry::try_from_request(&request, "invalid").unwrap_err();
+        assert_eq!(e, TaskError::UserId);
+
+        let mut tmp = request.clone();
+        tmp.loop_waypoints = vec![Waypoint::default()];
+        let e = WaitlistEntry::try_from_request(&tmp, &user_id).unwrap_err();
+        assert_eq!(e, TaskError::Data);
+
+        request.latest_arrival_time = None;
+        let e = WaitlistEntry::try_from_request(&request, &user_id).unwrap_err();
+        assert_eq!(e, TaskError::Data);
+    }
+
+    #[test]
+    fn test_waitlist_entry_matches() {
+        let now = Utc::now();
+        let entry = get_entry(now);
+
+        // same route, within the window
+        let schedule = get_schedule(&entry, entry.earliest_departure_time);
+        assert!(entry.matches(&schedule));
+
+        // different origin
+        let mut tmp = schedule.clone();
+        tmp.origin_vertiport_id = Uuid::new_v4().to_string();
+        assert!(!entry.matches(&tmp));
+
+        // different target
+        let mut tmp = schedule.clone();
+        tmp.target_vertiport_id = Uuid::new_v4().to_string();
+        assert!(!entry.matches(&tmp));
+
+        // after the window
+        let tmp = get_schedule(&entry, entry.latest_arrival_time);
+        assert!(!entry.matches(&tmp));
+
+        // before the window
+        let tmp = get_schedule(
+            &entry,
+            entry.earliest_departure_time - Duration::try_hours(1).unwrap(),
+        );
+        assert!(!entry.matches(&tmp));
+    }
+
+    #[test]
+    fn test_cancellation_makes_waitlist_entry_bookable() {
+        let now = Utc::now();
+        let waiting = get_entry(now);
+        let other_route = get_entry(now);
+        let entries = vec![
+            ("a".to_string(), waiting.clone()),
+            ("b".to_string(), other_route.clone()),
+        ];
+
+        // nothing cancelled, nothing to re-evaluate
+        assert!(get_matching_entries(entries.clone(), &[]).is_empty());
+
+        // cancelling a flight on the waitlisted route selects that entry
+        let cancelled = get_schedule(&waiting, waiting.earliest_departure_time);
+        let matches = get_matching_entries(entries, &[cancelled]);
+        assert_eq!(matches, vec![("a".to_string(), waiting.clone())]);
+
+        let query = waiting.to_query(now).unwrap();
+        assert_eq!(query.origin_vertiport_id, waiting.origin_vertiport_id);
+        assert_eq!(query.target_vertiport_id, waiting.target_vertiport_id);
+        assert_eq!(query.waitlist_user_id, None);
+    }
+
+    #[test]
+    fn test_waitlist_entry_to_query() {
+        let now = Utc::now();
+        let mut entry = get_entry(now);
+
+        // departure time within advance notice is moved forward
+        entry.earliest_departure_time = now;
+        let query = entry.to_query(now).unwrap();
+        let departure: DateTime<Utc> = query.earliest_departure_time.unwrap().into();
+        assert_eq!(
+            departure,
+            now + Duration::try_minutes(ADVANCE_NOTICE_MINUTES + 1).unwrap()
+        );
+
+        // preferred departure time moves forward with the window
+        entry.preferred_departure_time = Some(now);
+        let query = entry.to_query(now).unwrap();
+        assert_eq!(
+            query.preferred_departure_time,
+            query.earliest_departure_time
+        );
+
+        // window has passed
+        entry.latest_arrival_time = now;
+        assert!(entry.to_query(now).is_none());
+    }
+
+    #[test]
+    fn test_get_duplicate_entry() {
+        let now = Utc::now();
+        let waiting = get_entry(now);
+        let entries = vec![("a".to_string(), waiting.clone())];
+
+        // same user and request
+        assert_eq!(
+            get_duplicate_entry(&entries, &waiting),
+            Some("a".to_string())
+        );
+
+        // same request from another user
+        let mut tmp = waiting.clone();
+        tmp.user_id = Uuid::new_v4().to_string();
+        assert!(get_duplicate_entry(&entries, &tmp).is_none());
+
+        // same user, another window
+        let mut tmp = waiting.clone();
+        tmp.latest_arrival_time += Duration::try_hours(1).unwrap();
+        assert!(get_duplicate_entry(&entries, &tmp).is_none());
+
+        // same user, another payload
+        let mut tmp = waiting.clone();
+        tmp.weight_grams = Some(200);
+        assert!(get_duplicate_entry(&entries, &tmp).is_none());
+    }
+
+    #[test]
+    fn test_get_expired_entries() {
+        let now = Utc::now();
+        let waiting = (Uuid::new_v4().to_string(), get_entry(now));
+        let expired = (
+            Uuid::new_v4().to_string(),
+            get_entry(now - Duration::try_hours(5).unwrap()),
+        );
+
+        let entries = vec![waiting.clone(), expired.clone()];
+        assert_eq!(get_expired_entries(&entries, now), vec![expired.0]);
+
+        // nothing to sweep
+        assert!(get_expired_entries(&[waiting], now).is_empty());
+    }
+}
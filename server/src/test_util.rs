@@ -5,25 +5,31 @@ use lib_common::time::{DateTime, Utc};
 use lib_common::uuid::Uuid;
 use svc_storage_client_grpc::prelude::*;
 use tokio::sync::OnceCell;
+use tonic::async_trait;
+
+use crate::clock::Clock;
+use crate::router::vehicle::{AircraftType, GroundTime};
+use crate::router::vehicle_model::{VehicleModel, VehicleModels};
+use crate::tasks::audit::{AuditError, AuditRecord, AuditSink};
 
 log_macros!("ut", "test");
 
 static INIT_MOCK_DATA: OnceCell<bool> = tokio::sync::OnceCell::const_new();
 async fn init_mock_data() -> bool {
     let clients = get_clients().await;
+    let storage = clients.storage().await;
 
-    let vertiports = generate_vertiports(&clients.storage.vertiport).await;
+    let vertiports = generate_vertiports(&storage.vertiport).await;
     ut_debug!("Generated vertiports: {:#?}", vertiports);
-    let vertipads = generate_vertipads(&clients.storage.vertipad, &vertiports).await;
+    let vertipads = generate_vertipads(&storage.vertipad, &vertiports).await;
     ut_debug!("Generated vertipads: {:#?}", vertipads);
-    let vehicles = generate_vehicles(&clients.storage.vehicle, &vertiports).await;
+    let vehicles = generate_vehicles(&storage.vehicle, &vertiports).await;
     ut_debug!("Generated vehicles: {:#?}", vehicles);
-    let flight_plans =
-        generate_flight_plans(&clients.storage.flight_plan, &vertipads, &vehicles).await;
+    let flight_plans = generate_flight_plans(&storage.flight_plan, &vertipads, &vehicles).await;
     ut_debug!("Generated flight_plans: {:#?}", flight_plans);
     let itinerary = generate_itinerary(
-        &clients.storage.itinerary,
-        &clients.storage.itinerary_flight_plan_link,
+        &storage.itinerary,
+        &storage.itinerary_flight_plan_link,
         &flight_plans,
     )
     .await;
@@ -40,7 +46,8 @@ pub async fn get_vertiports_from_storage() -> Result<Vec<vertiport::Object>, ()>
 
     get_clients()
         .await
-        .storage
+        .storage()
+        .await
         .vertiport
         .search(AdvancedSearchFilter {
             filters: vec![],
@@ -449,3 +456,67 @@ async fn generate_itinerary(
 
     itineraries
 }
+
+/// Audit sink keeping the emitted records in memory
+#[derive(Debug, Default)]
+pub struct MemoryAuditSink {
+    pub records: Vec<AuditRecord>,
+}
+
+#[async_trait]
+impl AuditSink for MemoryAuditSink {
+    async fn emit(&mut self, record: &AuditRecord) -> Result<(), AuditError> {
+        self.records.push(record.clone());
+        Ok(())
+    }
+}
+
+/// Clock stopped at a fixed time, moved only by the test
+#[derive(Debug)]
+pub struct MockClock {
+    now: std::sync::Mutex<DateTime<Utc>>,
+}
+
+impl MockClock {
+    /// Creates a clock stopped at `now`
+    pub fn new(now: DateTime<Utc>) -> Self {
+        MockClock {
+            now: std::sync::Mutex::new(now),
+        }
+    }
+
+    /// Moves the clock forward
+    pub fn advance(&self, duration: lib_common::time::Duration) {
+        *self.now.lock().unwrap() += duration;
+    }
+}
+
+impl Clock for MockClock {
+    fn now(&self) -> DateTime<Utc> {
+        *self.now.lock().unwrap()
+    }
+}
+
+/// Stops the service clock of the current thread at `now`, returning
+///  the clock so that the test can move it
+pub fn set_mock_clock(now: DateTime<Utc>) -> &'static MockClock {
+    let clock: &'static MockClock = Box::leak(Box::new(MockClock::new(now)));
+    crate::clock::set_test_clock(Some(clock));
+    clock
+}
+
+/// Vehicle models describing every vehicle as a cargo aircraft
+pub fn get_test_vehicle_models() -> VehicleModels {
+    VehicleModels {
+        models: Default::default(),
+        other_models: Some(VehicleModel {
+            name: "Cargo aircraft".to_string(),
+            aircraft_type: AircraftType::Cargo,
+            ground_time: GroundTime {
+                loading: lib_common::time::Duration::try_minutes(1).unwrap(),
+                unloading: lib_common::time::Duration::try_minutes(1).unwrap(),
+            },
+            max_payload_grams: 20000,
+        }),
+    }
+}